@@ -0,0 +1,288 @@
+//! Imágenes de Disco para ReactOS Rust Kernel
+//!
+//! Dispositivos de bloque respaldados por memoria o por un archivo de
+//! imagen. Se registran en el gestor de almacenamiento como cualquier otro
+//! dispositivo, de forma que los drivers de sistemas de archivos pueden
+//! compilarse para el host y probarse contra imágenes reales (por ejemplo
+//! las generadas con mkfs.vfat o mkntfs) sin arrancar el kernel.
+//!
+//! El respaldo por archivo solo está disponible con la feature `std`.
+
+use core::fmt;
+
+use super::storage::{self, BlockBackend, StorageDevice};
+
+/// Disco en memoria sobre un buffer de bytes
+pub struct MemoryDisk {
+    /// Contenido del disco
+    data: &'static mut [u8],
+}
+
+impl MemoryDisk {
+    /// Crear un disco sobre un buffer; el tamaño del disco es el del buffer
+    pub fn new(data: &'static mut [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Contenido actual del disco
+    pub fn data(&self) -> &[u8] {
+        self.data
+    }
+
+    /// Rango de bytes de una petición, si cabe en el disco
+    fn range(&self, sector: u64, count: u32, sector_size: u32) -> Option<(usize, usize)> {
+        let start = usize::try_from(sector.checked_mul(sector_size as u64)?).ok()?;
+        let len = count as usize * sector_size as usize;
+        if start.checked_add(len)? > self.data.len() {
+            return None;
+        }
+        Some((start, len))
+    }
+}
+
+impl BlockBackend for MemoryDisk {
+    fn read_sectors(&mut self, sector: u64, count: u32, sector_size: u32, buffer: &mut [u8]) -> bool {
+        match self.range(sector, count, sector_size) {
+            Some((start, len)) => {
+                buffer[..len].copy_from_slice(&self.data[start..start + len]);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn write_sectors(&mut self, sector: u64, count: u32, sector_size: u32, buffer: &[u8]) -> bool {
+        match self.range(sector, count, sector_size) {
+            Some((start, len)) => {
+                self.data[start..start + len].copy_from_slice(&buffer[..len]);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn size(&self) -> u64 {
+        self.data.len() as u64
+    }
+}
+
+impl fmt::Debug for MemoryDisk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryDisk").field("size", &self.data.len()).finish()
+    }
+}
+
+/// Disco respaldado por un archivo de imagen del host
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct FileDisk {
+    /// Archivo de imagen abierto
+    file: std::fs::File,
+    /// Tamaño de la imagen en bytes
+    size: u64,
+    /// Imagen abierta en modo de solo lectura
+    read_only: bool,
+}
+
+#[cfg(feature = "std")]
+impl FileDisk {
+    /// Abrir una imagen de disco, en lectura y escritura salvo que `read_only` sea `true`
+    pub fn open<P: AsRef<std::path::Path>>(path: P, read_only: bool) -> std::io::Result<Self> {
+        let file = std::fs::OpenOptions::new().read(true).write(!read_only).open(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { file, size, read_only })
+    }
+
+    /// Rango de bytes de una petición, si cabe en la imagen
+    fn range(&self, sector: u64, count: u32, sector_size: u32) -> Option<(u64, usize)> {
+        let start = sector.checked_mul(sector_size as u64)?;
+        let len = count as usize * sector_size as usize;
+        if start.checked_add(len as u64)? > self.size {
+            return None;
+        }
+        Some((start, len))
+    }
+}
+
+#[cfg(feature = "std")]
+impl BlockBackend for FileDisk {
+    fn read_sectors(&mut self, sector: u64, count: u32, sector_size: u32, buffer: &mut [u8]) -> bool {
+        use std::io::{Read, Seek, SeekFrom};
+
+        match self.range(sector, count, sector_size) {
+            Some((start, len)) => {
+                self.file.seek(SeekFrom::Start(start)).is_ok() && self.file.read_exact(&mut buffer[..len]).is_ok()
+            }
+            None => false,
+        }
+    }
+
+    fn write_sectors(&mut self, sector: u64, count: u32, sector_size: u32, buffer: &[u8]) -> bool {
+        use std::io::{Seek, SeekFrom, Write};
+
+        if self.read_only {
+            return false;
+        }
+        match self.range(sector, count, sector_size) {
+            Some((start, len)) => {
+                self.file.seek(SeekFrom::Start(start)).is_ok() && self.file.write_all(&buffer[..len]).is_ok()
+            }
+            None => false,
+        }
+    }
+
+    fn flush(&mut self) -> bool {
+        self.read_only || self.file.sync_data().is_ok()
+    }
+
+    fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+/// Registrar en el gestor global un dispositivo respaldado por `backend`
+///
+/// Si el gestor no está inicializado se crea uno vacío, sin el dispositivo
+/// de ejemplo de `init_storage_manager`. Las particiones del disco se
+/// registran también. Devuelve el ID asignado o 0 si no hay slots libres.
+pub fn register_image(name: &str, sector_size: u32, backend: &'static mut dyn BlockBackend) -> u32 {
+    if storage::get_storage_manager().is_none() {
+        storage::set_storage_manager(storage::StorageManager::new());
+    }
+    let manager = match storage::get_storage_manager() {
+        Some(manager) => manager,
+        None => return 0,
+    };
+
+    let device_id = manager.register_device(StorageDevice::with_backend(name, sector_size, backend));
+    if device_id != 0 {
+        manager.scan_partitions(device_id);
+    }
+    device_id
+}
+
+/// Abrir una imagen de disco y registrarla en el gestor global
+///
+/// El archivo queda abierto mientras dure el proceso.
+#[cfg(feature = "std")]
+pub fn register_image_file<P: AsRef<std::path::Path>>(path: P, sector_size: u32, read_only: bool) -> std::io::Result<u32> {
+    let disk = FileDisk::open(&path, read_only)?;
+    let name = path.as_ref().file_name().and_then(|name| name.to_str()).unwrap_or("image");
+    match register_image(name, sector_size, Box::leak(Box::new(disk))) {
+        0 => Err(std::io::Error::other("no hay slots libres en el gestor de almacenamiento")),
+        device_id => Ok(device_id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::{boxed::Box, vec, vec::Vec};
+
+    /// Buffer de disco que vive hasta el final de las pruebas
+    fn leaked(size: usize) -> &'static mut [u8] {
+        Box::leak(vec![0u8; size].into_boxed_slice())
+    }
+
+    /// Sectores de prueba: cada byte codifica su posición y el sector
+    fn pattern(sector: u64, count: u32, sector_size: u32) -> Vec<u8> {
+        (0..count as usize * sector_size as usize).map(|i| (i as u64 / 7 + sector * 31) as u8).collect()
+    }
+
+    #[test]
+    fn test_memory_disk_sector_round_trip() {
+        for sector_size in [512u32, 4096] {
+            let mut disk = MemoryDisk::new(leaked(16 * sector_size as usize));
+            assert_eq!(disk.size(), 16 * sector_size as u64);
+
+            let data = pattern(3, 4, sector_size);
+            assert!(disk.write_sectors(3, 4, sector_size, &data));
+            let mut buffer = vec![0u8; data.len()];
+            assert!(disk.read_sectors(3, 4, sector_size, &mut buffer));
+            assert_eq!(buffer, data);
+            assert_eq!(&disk.data()[3 * sector_size as usize..7 * sector_size as usize], &data[..]);
+            assert!(disk.data()[..3 * sector_size as usize].iter().all(|&byte| byte == 0));
+
+            // Las peticiones que se salen del disco fallan sin tocar el buffer
+            let mut buffer = vec![0xAAu8; 2 * sector_size as usize];
+            assert!(!disk.read_sectors(15, 2, sector_size, &mut buffer));
+            assert!(buffer.iter().all(|&byte| byte == 0xAA));
+            assert!(!disk.write_sectors(16, 1, sector_size, &buffer));
+            assert!(!disk.read_sectors(u64::MAX, 1, sector_size, &mut buffer));
+            assert!(disk.read_sectors(15, 1, sector_size, &mut buffer));
+        }
+    }
+
+    #[test]
+    fn test_register_image_with_partitions() {
+        // MBR con una partición en los sectores 8..40
+        let data = leaked(64 * 512);
+        data[446 + 4] = 0x0C;
+        data[446 + 8..446 + 12].copy_from_slice(&8u32.to_le_bytes());
+        data[446 + 12..446 + 16].copy_from_slice(&32u32.to_le_bytes());
+        data[510] = 0x55;
+        data[511] = 0xAA;
+        let disk: &'static mut MemoryDisk = Box::leak(Box::new(MemoryDisk::new(data)));
+        let disk_ptr: *const MemoryDisk = disk;
+
+        let device_id = register_image("memoria", 512, disk);
+        assert_ne!(device_id, 0);
+        let manager = storage::get_storage_manager().unwrap();
+        let device = manager.get_device_info(device_id).unwrap();
+        assert_eq!((device.sector_size, device.sector_count), (512, 64));
+
+        let partition_id = manager
+            .devices
+            .iter()
+            .flatten()
+            .find(|device| device.parent_device == Some(device_id))
+            .map(|device| device.device_id)
+            .unwrap();
+        assert_eq!(manager.get_device_info(partition_id).unwrap().sector_count, 32);
+
+        // Lo escrito en la partición aparece desplazado en el disco al sincronizar
+        let written = pattern(2, 3, 512);
+        assert!(storage::write_sectors(partition_id, 2, 3, &written));
+        let mut buffer = vec![0u8; written.len()];
+        assert!(storage::read_sectors(device_id, 10, 3, &mut buffer));
+        assert_eq!(buffer, written);
+        assert!(storage::sync_storage(None));
+        let contents = unsafe { (*disk_ptr).data() };
+        assert_eq!(&contents[10 * 512..13 * 512], &written[..]);
+
+        assert!(!storage::read_sectors(partition_id, 31, 2, &mut buffer));
+        assert!(manager.unregister_device(device_id));
+        assert!(manager.get_device_info(partition_id).is_none());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_file_disk_round_trip_and_read_only() {
+        let path = std::env::temp_dir().join(std::format!("reactos-image-test-{}.img", std::process::id()));
+        std::fs::write(&path, vec![0u8; 8 * 512]).unwrap();
+
+        let mut disk = FileDisk::open(&path, false).unwrap();
+        assert_eq!(disk.size(), 8 * 512);
+        let data = pattern(5, 2, 512);
+        assert!(disk.write_sectors(5, 2, 512, &data));
+        assert!(disk.flush());
+        assert!(!disk.write_sectors(7, 2, 512, &data));
+        let mut buffer = vec![0u8; data.len()];
+        assert!(disk.read_sectors(5, 2, 512, &mut buffer));
+        assert_eq!(buffer, data);
+        assert_eq!(&std::fs::read(&path).unwrap()[5 * 512..7 * 512], &data[..]);
+
+        // En solo lectura se lee lo mismo y se rechaza la escritura
+        let mut read_only = FileDisk::open(&path, true).unwrap();
+        let mut buffer = vec![0u8; data.len()];
+        assert!(read_only.read_sectors(5, 2, 512, &mut buffer));
+        assert_eq!(buffer, data);
+        assert!(!read_only.write_sectors(0, 1, 512, &data));
+        assert!(read_only.flush());
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(FileDisk::open(&path, true).is_err());
+    }
+}
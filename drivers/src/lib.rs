@@ -3,13 +3,15 @@
 //! Drivers del sistema para ReactOS Rust OS.
 //! Proporciona controladores para dispositivos de hardware.
 
-#![no_std]
+#![cfg_attr(not(feature = "std"), no_std)]
 
 use core::arch::asm;
 
 // Módulos de drivers
 pub mod system;
 pub mod storage;
+pub mod partition;
+pub mod image;
 pub mod network;
 
 /// Inicializar drivers del sistema
@@ -29,6 +31,7 @@ pub fn process_driver_events() {
     keyboard::process_events();
     mouse::process_events();
     disk::process_events();
+    storage::process_events();
     network::process_events();
     audio::process_events();
     video::process_events();
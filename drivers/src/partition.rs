@@ -0,0 +1,769 @@
+//! Tablas de Particiones para ReactOS Rust Kernel
+//!
+//! Interpreta las tablas de particiones MBR (incluidas las particiones
+//! extendidas y lógicas) y GPT, validando el CRC32 de la cabecera y del
+//! array de entradas y recurriendo a la copia de respaldo al final del
+//! disco si la principal está dañada. El gestor de almacenamiento registra
+//! cada partición encontrada como un dispositivo de bloque propio.
+
+/// Número máximo de particiones que se conservan de una tabla
+pub const PARTITION_MAX_ENTRIES: usize = 16;
+/// Tamaño máximo de sector soportado al leer una tabla
+pub const PARTITION_MAX_SECTOR_SIZE: usize = 4096;
+/// Tamaño en bytes de la etiqueta UTF-8 (36 caracteres UTF-16 de GPT)
+pub const PARTITION_LABEL_SIZE: usize = 108;
+/// Número máximo de particiones lógicas recorridas en una partición extendida
+pub const MBR_MAX_LOGICAL_PARTITIONS: usize = 64;
+
+/// Tipo MBR de la partición protectora de un disco GPT
+pub const MBR_TYPE_GPT_PROTECTIVE: u8 = 0xEE;
+/// Firma de la cabecera GPT
+pub const GPT_SIGNATURE: &[u8; 8] = b"EFI PART";
+
+/// GUID de tipo de la partición de sistema EFI
+pub const GPT_TYPE_EFI_SYSTEM: [u8; 16] = [
+    0x28, 0x73, 0x2A, 0xC1, 0x1F, 0xF8, 0xD2, 0x11, 0xBA, 0x4B, 0x00, 0xA0, 0xC9, 0x3E, 0xC9, 0x3B,
+];
+/// GUID de tipo de datos básicos de Microsoft (FAT, exFAT, NTFS)
+pub const GPT_TYPE_BASIC_DATA: [u8; 16] = [
+    0xA2, 0xA0, 0xD0, 0xEB, 0xE5, 0xB9, 0x33, 0x44, 0x87, 0xC0, 0x68, 0xB6, 0xB7, 0x26, 0x99, 0xC7,
+];
+/// GUID de tipo de sistema de archivos Linux
+pub const GPT_TYPE_LINUX_FILESYSTEM: [u8; 16] = [
+    0xAF, 0x3D, 0xC6, 0x0F, 0x83, 0x84, 0x72, 0x47, 0x8E, 0x79, 0x3D, 0x69, 0xD8, 0x47, 0x7D, 0xE4,
+];
+/// GUID de tipo de área de intercambio Linux
+pub const GPT_TYPE_LINUX_SWAP: [u8; 16] = [
+    0x6D, 0xFD, 0x57, 0x06, 0xAB, 0xA4, 0xC4, 0x43, 0x84, 0xE5, 0x09, 0x33, 0xC8, 0x4B, 0x4F, 0x4F,
+];
+
+/// Esquema de particionado de un disco
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartitionScheme {
+    /// Master Boot Record
+    Mbr,
+    /// GUID Partition Table
+    Gpt,
+}
+
+/// Partición encontrada en una tabla
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartitionEntry {
+    /// Número de partición (MBR: 1-4 primarias y 5+ lógicas; GPT: índice + 1)
+    pub number: u32,
+    /// Primer sector de la partición en el disco
+    pub first_sector: u64,
+    /// Tamaño de la partición en sectores
+    pub sector_count: u64,
+    /// Tipo de partición MBR (0 en GPT)
+    pub mbr_type: u8,
+    /// GUID de tipo de partición (ceros en MBR)
+    pub type_guid: [u8; 16],
+    /// GUID único de la partición (ceros en MBR)
+    pub unique_guid: [u8; 16],
+    /// Atributos GPT
+    pub attributes: u64,
+    /// Partición marcada como activa (MBR)
+    pub bootable: bool,
+    /// Nombre de la partición en UTF-8 terminado en NUL (vacío en MBR)
+    pub label: [u8; PARTITION_LABEL_SIZE],
+}
+
+impl PartitionEntry {
+    /// Crear una partición sin tipo ni etiqueta
+    fn new(number: u32, first_sector: u64, sector_count: u64) -> Self {
+        Self {
+            number,
+            first_sector,
+            sector_count,
+            mbr_type: 0,
+            type_guid: [0; 16],
+            unique_guid: [0; 16],
+            attributes: 0,
+            bootable: false,
+            label: [0; PARTITION_LABEL_SIZE],
+        }
+    }
+
+    /// Nombre de la partición
+    pub fn label(&self) -> &str {
+        let len = self.label.iter().position(|&byte| byte == 0).unwrap_or(PARTITION_LABEL_SIZE);
+        core::str::from_utf8(&self.label[..len]).unwrap_or("")
+    }
+
+    /// Último sector de la partición (inclusive)
+    pub fn last_sector(&self) -> u64 {
+        self.first_sector + self.sector_count - 1
+    }
+}
+
+/// Tabla de particiones de un disco
+#[derive(Debug, Clone, Copy)]
+pub struct PartitionTable {
+    /// Esquema de la tabla
+    pub scheme: PartitionScheme,
+    /// GUID del disco (GPT)
+    pub disk_guid: [u8; 16],
+    /// Firma del disco (MBR)
+    pub disk_signature: u32,
+    /// La tabla se leyó de la copia GPT de respaldo
+    pub backup_gpt: bool,
+    /// Particiones encontradas, en orden de número
+    pub entries: [Option<PartitionEntry>; PARTITION_MAX_ENTRIES],
+    /// Número de particiones encontradas
+    pub count: usize,
+}
+
+impl PartitionTable {
+    /// Crear una tabla vacía
+    fn new(scheme: PartitionScheme) -> Self {
+        Self {
+            scheme,
+            disk_guid: [0; 16],
+            disk_signature: 0,
+            backup_gpt: false,
+            entries: [None; PARTITION_MAX_ENTRIES],
+            count: 0,
+        }
+    }
+
+    /// Añadir una partición; las que no caben se descartan
+    fn push(&mut self, entry: PartitionEntry) {
+        if self.count < PARTITION_MAX_ENTRIES {
+            self.entries[self.count] = Some(entry);
+            self.count += 1;
+        }
+    }
+
+    /// Recorrer las particiones
+    pub fn iter(&self) -> impl Iterator<Item = &PartitionEntry> {
+        self.entries.iter().flatten()
+    }
+}
+
+/// Cabecera GPT validada
+#[derive(Debug, Clone, Copy)]
+struct GptHeader {
+    alternate_lba: u64,
+    first_usable_lba: u64,
+    last_usable_lba: u64,
+    disk_guid: [u8; 16],
+    entries_lba: u64,
+    entry_count: u32,
+    entry_size: u32,
+    entries_crc32: u32,
+}
+
+/// Leer la tabla de particiones de un disco
+///
+/// `read` lee un sector del disco en el buffer recibido, que tiene
+/// exactamente `sector_size` bytes. Un MBR protector (tipo 0xEE) indica un
+/// disco GPT; si no, se interpreta el MBR. Devuelve `None` si el disco no
+/// tiene una tabla válida.
+pub fn read_partition_table<F>(sector_size: u32, sector_count: u64, mut read: F) -> Option<PartitionTable>
+where
+    F: FnMut(u64, &mut [u8]) -> bool,
+{
+    let sector_size = sector_size as usize;
+    if !(512..=PARTITION_MAX_SECTOR_SIZE).contains(&sector_size) || sector_count < 2 {
+        return None;
+    }
+
+    let mut mbr = [0u8; PARTITION_MAX_SECTOR_SIZE];
+    if !read(0, &mut mbr[..sector_size]) || mbr[510] != 0x55 || mbr[511] != 0xAA {
+        return None;
+    }
+
+    let protective = (0..4).any(|slot| mbr[446 + slot * 16 + 4] == MBR_TYPE_GPT_PROTECTIVE);
+    if protective {
+        read_gpt(sector_size, sector_count, &mut read)
+    } else {
+        read_mbr(&mbr, sector_size, sector_count, &mut read)
+    }
+}
+
+/// Interpretar un MBR y, si la hay, la cadena de EBR de su partición extendida
+fn read_mbr<F>(mbr: &[u8], sector_size: usize, sector_count: u64, read: &mut F) -> Option<PartitionTable>
+where
+    F: FnMut(u64, &mut [u8]) -> bool,
+{
+    // Un sector de arranque de volumen (FAT sin particionar) no tiene indicadores de estado válidos
+    if (0..4).any(|slot| !matches!(mbr[446 + slot * 16], 0x00 | 0x80)) {
+        return None;
+    }
+
+    let mut table = PartitionTable::new(PartitionScheme::Mbr);
+    table.disk_signature = read_u32(mbr, 440);
+
+    let mut extended = None;
+    for slot in 0..4 {
+        let Some(entry) = parse_mbr_entry(mbr, slot, slot as u32 + 1, 0) else { continue };
+        if entry.last_sector() >= sector_count {
+            continue;
+        }
+        if is_extended(entry.mbr_type) {
+            extended = extended.or(Some(entry));
+            continue;
+        }
+        table.push(entry);
+    }
+
+    if let Some(extended) = extended {
+        read_logical_partitions(&mut table, &extended, sector_size, read);
+    }
+    Some(table)
+}
+
+/// Recorrer la cadena de EBR de una partición extendida
+///
+/// Cada EBR describe una partición lógica (relativa al propio EBR) y el
+/// siguiente EBR (relativo al inicio de la partición extendida).
+fn read_logical_partitions<F>(table: &mut PartitionTable, extended: &PartitionEntry, sector_size: usize, read: &mut F)
+where
+    F: FnMut(u64, &mut [u8]) -> bool,
+{
+    let mut ebr = [0u8; PARTITION_MAX_SECTOR_SIZE];
+    let mut ebr_sector = extended.first_sector;
+    let mut number = 5;
+
+    for _ in 0..MBR_MAX_LOGICAL_PARTITIONS {
+        if !read(ebr_sector, &mut ebr[..sector_size]) || ebr[510] != 0x55 || ebr[511] != 0xAA {
+            break;
+        }
+
+        if let Some(logical) = parse_mbr_entry(&ebr, 0, number, ebr_sector) {
+            let inside = logical.first_sector > ebr_sector && logical.last_sector() <= extended.last_sector();
+            if inside && !is_extended(logical.mbr_type) {
+                table.push(logical);
+                number += 1;
+            }
+        }
+
+        match parse_mbr_entry(&ebr, 1, 0, extended.first_sector) {
+            // El siguiente EBR debe avanzar dentro de la partición extendida
+            Some(next) if is_extended(next.mbr_type) && next.first_sector > ebr_sector && next.first_sector <= extended.last_sector() => {
+                ebr_sector = next.first_sector;
+            }
+            _ => break,
+        }
+    }
+}
+
+/// Interpretar una de las cuatro entradas de un MBR o EBR
+///
+/// `base` se suma al LBA de inicio de la entrada. Las entradas vacías
+/// devuelven `None`.
+fn parse_mbr_entry(sector: &[u8], slot: usize, number: u32, base: u64) -> Option<PartitionEntry> {
+    let entry = &sector[446 + slot * 16..446 + slot * 16 + 16];
+    let mbr_type = entry[4];
+    let start = read_u32(entry, 8) as u64;
+    let count = read_u32(entry, 12) as u64;
+    if mbr_type == 0 || count == 0 {
+        return None;
+    }
+
+    let mut partition = PartitionEntry::new(number, base + start, count);
+    partition.mbr_type = mbr_type;
+    partition.bootable = entry[0] == 0x80;
+    Some(partition)
+}
+
+/// Verificar si un tipo MBR corresponde a una partición extendida
+fn is_extended(mbr_type: u8) -> bool {
+    matches!(mbr_type, 0x05 | 0x0F | 0x85)
+}
+
+/// Leer la GPT principal o, si está dañada, la de respaldo
+fn read_gpt<F>(sector_size: usize, sector_count: u64, read: &mut F) -> Option<PartitionTable>
+where
+    F: FnMut(u64, &mut [u8]) -> bool,
+{
+    let primary = read_gpt_header(1, sector_size, sector_count, read);
+    if let Some(table) = primary.and_then(|header| read_gpt_entries(&header, sector_size, read)) {
+        return Some(table);
+    }
+
+    // La cabecera principal indica dónde está la de respaldo; si no es
+    // válida, la copia ocupa el último sector del disco
+    let last_sector = sector_count - 1;
+    let mut candidates = [last_sector, last_sector];
+    if let Some(header) = primary {
+        candidates[0] = header.alternate_lba;
+    }
+
+    for (index, &lba) in candidates.iter().enumerate() {
+        if index == 1 && lba == candidates[0] {
+            break;
+        }
+        let header = read_gpt_header(lba, sector_size, sector_count, read);
+        if let Some(mut table) = header.and_then(|header| read_gpt_entries(&header, sector_size, read)) {
+            table.backup_gpt = true;
+            return Some(table);
+        }
+    }
+    None
+}
+
+/// Leer y validar la cabecera GPT almacenada en `lba`
+fn read_gpt_header<F>(lba: u64, sector_size: usize, sector_count: u64, read: &mut F) -> Option<GptHeader>
+where
+    F: FnMut(u64, &mut [u8]) -> bool,
+{
+    if lba == 0 || lba >= sector_count {
+        return None;
+    }
+
+    let mut sector = [0u8; PARTITION_MAX_SECTOR_SIZE];
+    if !read(lba, &mut sector[..sector_size]) || &sector[0..8] != GPT_SIGNATURE {
+        return None;
+    }
+
+    let header_size = read_u32(&sector, 12) as usize;
+    if header_size < 92 || header_size > sector_size {
+        return None;
+    }
+
+    // El CRC de la cabecera se calcula con su propio campo a cero
+    let header_crc32 = read_u32(&sector, 16);
+    sector[16..20].fill(0);
+    if crc32(&sector[..header_size]) != header_crc32 || read_u64(&sector, 24) != lba {
+        return None;
+    }
+
+    let header = GptHeader {
+        alternate_lba: read_u64(&sector, 32),
+        first_usable_lba: read_u64(&sector, 40),
+        last_usable_lba: read_u64(&sector, 48),
+        disk_guid: read_guid(&sector, 56),
+        entries_lba: read_u64(&sector, 72),
+        entry_count: read_u32(&sector, 80),
+        entry_size: read_u32(&sector, 84),
+        entries_crc32: read_u32(&sector, 88),
+    };
+
+    let entry_size = header.entry_size as usize;
+    if entry_size < 128 || !entry_size.is_power_of_two() || entry_size > sector_size {
+        return None;
+    }
+    let entry_sectors = (header.entry_count as u64 * entry_size as u64).div_ceil(sector_size as u64);
+    if header.entry_count == 0
+        || header.first_usable_lba > header.last_usable_lba
+        || header.last_usable_lba >= sector_count
+        || header.entries_lba == 0
+        || header.entries_lba + entry_sectors > sector_count
+    {
+        return None;
+    }
+    Some(header)
+}
+
+/// Leer el array de entradas de una cabecera GPT validando su CRC32
+fn read_gpt_entries<F>(header: &GptHeader, sector_size: usize, read: &mut F) -> Option<PartitionTable>
+where
+    F: FnMut(u64, &mut [u8]) -> bool,
+{
+    let mut table = PartitionTable::new(PartitionScheme::Gpt);
+    table.disk_guid = header.disk_guid;
+
+    let entry_size = header.entry_size as usize;
+    let mut remaining = header.entry_count as usize * entry_size;
+    let mut sector = [0u8; PARTITION_MAX_SECTOR_SIZE];
+    let mut lba = header.entries_lba;
+    let mut index = 0u32;
+    let mut crc = !0u32;
+
+    while remaining > 0 {
+        if !read(lba, &mut sector[..sector_size]) {
+            return None;
+        }
+        let len = remaining.min(sector_size);
+        crc = crc32_update(crc, &sector[..len]);
+
+        for entry in sector[..len].chunks_exact(entry_size) {
+            index += 1;
+            if let Some(partition) = parse_gpt_entry(entry, index, header) {
+                table.push(partition);
+            }
+        }
+
+        remaining -= len;
+        lba += 1;
+    }
+
+    if !crc != header.entries_crc32 {
+        return None;
+    }
+    Some(table)
+}
+
+/// Interpretar una entrada GPT; las vacías o fuera del área utilizable devuelven `None`
+fn parse_gpt_entry(entry: &[u8], number: u32, header: &GptHeader) -> Option<PartitionEntry> {
+    let type_guid = read_guid(entry, 0);
+    let first_lba = read_u64(entry, 32);
+    let last_lba = read_u64(entry, 40);
+    if type_guid == [0; 16]
+        || first_lba > last_lba
+        || first_lba < header.first_usable_lba
+        || last_lba > header.last_usable_lba
+    {
+        return None;
+    }
+
+    let mut partition = PartitionEntry::new(number, first_lba, last_lba - first_lba + 1);
+    partition.type_guid = type_guid;
+    partition.unique_guid = read_guid(entry, 16);
+    partition.attributes = read_u64(entry, 48);
+
+    // Nombre en UTF-16LE terminado en NUL, convertido a UTF-8
+    let units = entry[56..128].chunks_exact(2).map(|unit| u16::from_le_bytes([unit[0], unit[1]])).take_while(|&unit| unit != 0);
+    let mut len = 0;
+    for ch in char::decode_utf16(units).map(|ch| ch.unwrap_or(char::REPLACEMENT_CHARACTER)) {
+        if len + ch.len_utf8() >= PARTITION_LABEL_SIZE {
+            break;
+        }
+        len += ch.encode_utf8(&mut partition.label[len..]).len();
+    }
+    Some(partition)
+}
+
+/// Tabla del CRC32 (polinomio IEEE 802.3 reflejado)
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut value = index as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            value = if value & 1 != 0 { (value >> 1) ^ 0xEDB8_8320 } else { value >> 1 };
+            bit += 1;
+        }
+        table[index] = value;
+        index += 1;
+    }
+    table
+};
+
+/// Calcular el CRC32 usado por GPT
+pub fn crc32(data: &[u8]) -> u32 {
+    !crc32_update(!0, data)
+}
+
+/// Acumular bytes en un CRC32 sin invertir (estado inicial `!0`)
+fn crc32_update(mut crc: u32, data: &[u8]) -> u32 {
+    for &byte in data {
+        crc = CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    crc
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+fn read_guid(data: &[u8], offset: usize) -> [u8; 16] {
+    let mut guid = [0u8; 16];
+    guid.copy_from_slice(&data[offset..offset + 16]);
+    guid
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::{vec, vec::Vec};
+
+    /// Particiones esperadas: (número, primer sector, sectores)
+    type Expected = &'static [(u32, u64, u64)];
+
+    // Geometría GPT de las pruebas
+    const GPT_ENTRY_COUNT: u32 = 128;
+    const GPT_ENTRY_SIZE: usize = 128;
+    const DISK_GUID: [u8; 16] = [0x11; 16];
+
+    fn read_table(disk: &[u8], sector_size: usize) -> Option<PartitionTable> {
+        let sector_count = (disk.len() / sector_size) as u64;
+        read_partition_table(sector_size as u32, sector_count, |lba, buffer| {
+            let start = lba as usize * sector_size;
+            buffer.copy_from_slice(&disk[start..start + sector_size]);
+            true
+        })
+    }
+
+    fn partitions(table: &PartitionTable) -> Vec<(u32, u64, u64)> {
+        table.iter().map(|entry| (entry.number, entry.first_sector, entry.sector_count)).collect()
+    }
+
+    /// Escribir una entrada de MBR o EBR y la firma del sector
+    fn set_mbr_entry(disk: &mut [u8], sector: u64, slot: usize, mbr_type: u8, start: u32, count: u32) {
+        let base = sector as usize * 512;
+        let entry = &mut disk[base + 446 + slot * 16..base + 462 + slot * 16];
+        entry[4] = mbr_type;
+        entry[8..12].copy_from_slice(&start.to_le_bytes());
+        entry[12..16].copy_from_slice(&count.to_le_bytes());
+        disk[base + 510] = 0x55;
+        disk[base + 511] = 0xAA;
+    }
+
+    /// Sectores que ocupa el array de entradas GPT
+    fn entry_sectors(sector_size: usize) -> u64 {
+        (GPT_ENTRY_COUNT as usize * GPT_ENTRY_SIZE / sector_size) as u64
+    }
+
+    /// Escribir una cabecera GPT con sus CRC calculados sobre el array de entradas
+    fn write_gpt_header(disk: &mut [u8], sector_size: usize, lba: u64, alternate: u64, entries_lba: u64) {
+        let sector_count = (disk.len() / sector_size) as u64;
+        let entries_start = entries_lba as usize * sector_size;
+        let entries_crc32 = crc32(&disk[entries_start..entries_start + GPT_ENTRY_COUNT as usize * GPT_ENTRY_SIZE]);
+
+        let header = &mut disk[lba as usize * sector_size..(lba as usize + 1) * sector_size];
+        header.fill(0);
+        header[0..8].copy_from_slice(GPT_SIGNATURE);
+        header[8..12].copy_from_slice(&0x0001_0000u32.to_le_bytes());
+        header[12..16].copy_from_slice(&92u32.to_le_bytes());
+        header[24..32].copy_from_slice(&lba.to_le_bytes());
+        header[32..40].copy_from_slice(&alternate.to_le_bytes());
+        header[40..48].copy_from_slice(&(2 + entry_sectors(sector_size)).to_le_bytes());
+        header[48..56].copy_from_slice(&(sector_count - 2 - entry_sectors(sector_size)).to_le_bytes());
+        header[56..72].copy_from_slice(&DISK_GUID);
+        header[72..80].copy_from_slice(&entries_lba.to_le_bytes());
+        header[80..84].copy_from_slice(&GPT_ENTRY_COUNT.to_le_bytes());
+        header[84..88].copy_from_slice(&(GPT_ENTRY_SIZE as u32).to_le_bytes());
+        header[88..92].copy_from_slice(&entries_crc32.to_le_bytes());
+        rehash_gpt_header(disk, sector_size, lba);
+    }
+
+    /// Recalcular el CRC de una cabecera GPT tras modificarla
+    fn rehash_gpt_header(disk: &mut [u8], sector_size: usize, lba: u64) {
+        let header = &mut disk[lba as usize * sector_size..lba as usize * sector_size + 92];
+        header[16..20].fill(0);
+        let crc = crc32(header);
+        header[16..20].copy_from_slice(&crc.to_le_bytes());
+    }
+
+    /// Escribir una entrada GPT en los dos arrays de entradas
+    fn set_gpt_entry(disk: &mut [u8], sector_size: usize, index: usize, type_guid: [u8; 16], first: u64, last: u64, label: &str) {
+        let sector_count = (disk.len() / sector_size) as u64;
+        for entries_lba in [2, sector_count - 1 - entry_sectors(sector_size)] {
+            let start = entries_lba as usize * sector_size + index * GPT_ENTRY_SIZE;
+            let entry = &mut disk[start..start + GPT_ENTRY_SIZE];
+            entry[0..16].copy_from_slice(&type_guid);
+            entry[16..32].copy_from_slice(&[index as u8 + 0x40; 16]);
+            entry[32..40].copy_from_slice(&first.to_le_bytes());
+            entry[40..48].copy_from_slice(&last.to_le_bytes());
+            for (unit, chunk) in label.encode_utf16().zip(entry[56..128].chunks_exact_mut(2)) {
+                chunk.copy_from_slice(&unit.to_le_bytes());
+            }
+        }
+    }
+
+    /// Disco GPT de `sectors` sectores con cabecera y entradas principales y de respaldo
+    fn gpt_disk(sector_size: usize, sectors: u64) -> Vec<u8> {
+        let mut disk = vec![0u8; sectors as usize * sector_size];
+        set_mbr_entry(&mut disk, 0, 0, MBR_TYPE_GPT_PROTECTIVE, 1, (sectors - 1) as u32);
+        set_gpt_entry(&mut disk, sector_size, 0, GPT_TYPE_EFI_SYSTEM, 64, 127, "EFI");
+        set_gpt_entry(&mut disk, sector_size, 1, GPT_TYPE_BASIC_DATA, 128, 255, "Datos ñ");
+        let last = sectors - 1;
+        write_gpt_header(&mut disk, sector_size, 1, last, 2);
+        write_gpt_header(&mut disk, sector_size, last, 1, last - entry_sectors(sector_size));
+        disk
+    }
+
+    #[test]
+    fn test_crc32_reference_values() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b"The quick brown fox jumps over the lazy dog"), 0x414F_A339);
+        // Acumular por trozos da el mismo resultado que de una vez
+        assert_eq!(!crc32_update(crc32_update(!0, b"1234"), b"56789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_gpt_primary_and_backup() {
+        /// Modificación del disco y resultado: `None` si no hay tabla, o si
+        /// la tabla sale de la copia de respaldo
+        type Case = (&'static str, fn(&mut [u8], usize), Option<bool>);
+        let cases: &[Case] = &[
+            ("intacto", |_, _| {}, Some(false)),
+            ("CRC de la cabecera principal", |disk, size| disk[size + 60] ^= 1, Some(true)),
+            ("CRC de las entradas principales", |disk, size| disk[2 * size + 60] ^= 1, Some(true)),
+            ("firma principal", |disk, size| disk[size] = 0, Some(true)),
+            (
+                "LBA propio de la cabecera principal",
+                |disk, size| {
+                    disk[size + 24] = 5;
+                    rehash_gpt_header(disk, size, 1);
+                },
+                Some(true),
+            ),
+            (
+                "tamaño de entrada inválido",
+                |disk, size| {
+                    disk[size + 84] = 100;
+                    rehash_gpt_header(disk, size, 1);
+                },
+                Some(true),
+            ),
+            (
+                // La cabecera principal es válida pero apunta mal a la de
+                // respaldo: se prueba también el último sector
+                "entradas principales dañadas y alternate erróneo",
+                |disk, size| {
+                    disk[2 * size + 60] ^= 1;
+                    disk[size + 32] = 3;
+                    rehash_gpt_header(disk, size, 1);
+                },
+                Some(true),
+            ),
+            (
+                "respaldo dañado",
+                |disk, size| {
+                    let last = disk.len() - size;
+                    disk[last + 60] ^= 1;
+                },
+                Some(false),
+            ),
+            (
+                "ambas cabeceras dañadas",
+                |disk, size| {
+                    let last = disk.len() - size;
+                    disk[size + 60] ^= 1;
+                    disk[last + 60] ^= 1;
+                },
+                None,
+            ),
+            (
+                "ambos arrays de entradas dañados",
+                |disk, size| {
+                    let backup_entries = disk.len() - size * (1 + entry_sectors(size) as usize);
+                    disk[2 * size + 60] ^= 1;
+                    disk[backup_entries + 60] ^= 1;
+                },
+                None,
+            ),
+        ];
+
+        for sector_size in [512, 4096] {
+            for (name, corrupt, expected) in cases {
+                let mut disk = gpt_disk(sector_size, 1024);
+                corrupt(&mut disk, sector_size);
+                let table = read_table(&disk, sector_size);
+                assert_eq!(table.map(|table| table.backup_gpt), *expected, "{} ({} bytes)", name, sector_size);
+
+                if let Some(table) = table {
+                    assert_eq!(table.scheme, PartitionScheme::Gpt, "{}", name);
+                    assert_eq!(table.disk_guid, DISK_GUID, "{}", name);
+                    assert_eq!(partitions(&table), [(1, 64, 64), (2, 128, 128)], "{}", name);
+                    let entries: Vec<&PartitionEntry> = table.iter().collect();
+                    assert_eq!((entries[0].label(), entries[0].type_guid), ("EFI", GPT_TYPE_EFI_SYSTEM), "{}", name);
+                    assert_eq!((entries[1].label(), entries[1].type_guid), ("Datos ñ", GPT_TYPE_BASIC_DATA), "{}", name);
+                    assert_eq!(entries[1].unique_guid, [0x41; 16], "{}", name);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_gpt_entries_outside_usable_area() {
+        let mut disk = gpt_disk(512, 1024);
+        let last_usable = 1024 - 2 - entry_sectors(512);
+        set_gpt_entry(&mut disk, 512, 2, GPT_TYPE_LINUX_SWAP, 300, last_usable + 1, "fuera");
+        set_gpt_entry(&mut disk, 512, 3, GPT_TYPE_LINUX_FILESYSTEM, 400, 399, "invertida");
+        set_gpt_entry(&mut disk, 512, 4, GPT_TYPE_LINUX_FILESYSTEM, 500, last_usable, "raíz");
+        write_gpt_header(&mut disk, 512, 1, 1023, 2);
+
+        // Las entradas descartadas conservan su número en las siguientes
+        let table = read_table(&disk, 512).unwrap();
+        assert!(!table.backup_gpt);
+        assert_eq!(partitions(&table), [(1, 64, 64), (2, 128, 128), (5, 500, last_usable - 499)]);
+        assert_eq!(table.iter().last().unwrap().label(), "raíz");
+    }
+
+    /// Disco MBR de 4096 sectores: una primaria y una extendida (1000..2999)
+    /// con tres EBR en 1000, 1300 y 1600
+    fn mbr_disk() -> Vec<u8> {
+        let mut disk = vec![0u8; 4096 * 512];
+        set_mbr_entry(&mut disk, 0, 0, 0x83, 63, 100);
+        set_mbr_entry(&mut disk, 0, 1, 0x0F, 1000, 2000);
+        for (ebr, next) in [(1000, 300), (1300, 600), (1600, 0)] {
+            set_mbr_entry(&mut disk, ebr, 0, 0x07, 63, 100);
+            if next != 0 {
+                set_mbr_entry(&mut disk, ebr, 1, 0x05, next, 100);
+            }
+        }
+        disk
+    }
+
+    #[test]
+    fn test_mbr_logical_partition_chain() {
+        type Case = (&'static str, fn(&mut [u8]), Expected);
+        let cases: &[Case] = &[
+            ("cadena completa", |_| {}, &[(1, 63, 100), (5, 1063, 100), (6, 1363, 100), (7, 1663, 100)]),
+            // Un EBR que apunta hacia atrás cortaría el recorrido en un bucle
+            ("bucle", |disk| set_mbr_entry(disk, 1300, 1, 0x05, 0, 100), &[(1, 63, 100), (5, 1063, 100), (6, 1363, 100)]),
+            (
+                "siguiente EBR fuera de la extendida",
+                |disk| set_mbr_entry(disk, 1300, 1, 0x05, 2500, 100),
+                &[(1, 63, 100), (5, 1063, 100), (6, 1363, 100)],
+            ),
+            (
+                "lógica fuera de la extendida",
+                |disk| set_mbr_entry(disk, 1300, 0, 0x07, 63, 5000),
+                &[(1, 63, 100), (5, 1063, 100), (6, 1663, 100)],
+            ),
+            (
+                "lógica de tipo extendida",
+                |disk| set_mbr_entry(disk, 1000, 0, 0x05, 63, 100),
+                &[(1, 63, 100), (5, 1363, 100), (6, 1663, 100)],
+            ),
+            ("EBR sin firma", |disk| disk[1300 * 512 + 511] = 0, &[(1, 63, 100), (5, 1063, 100)]),
+            (
+                "primaria más allá del disco",
+                |disk| set_mbr_entry(disk, 0, 2, 0x83, 4000, 200),
+                &[(1, 63, 100), (5, 1063, 100), (6, 1363, 100), (7, 1663, 100)],
+            ),
+            (
+                "primaria tras la extendida",
+                |disk| set_mbr_entry(disk, 0, 3, 0x0C, 3000, 1000),
+                &[(1, 63, 100), (4, 3000, 1000), (5, 1063, 100), (6, 1363, 100), (7, 1663, 100)],
+            ),
+        ];
+
+        for (name, modify, expected) in cases {
+            let mut disk = mbr_disk();
+            modify(&mut disk);
+            let table = read_table(&disk, 512).unwrap_or_else(|| panic!("{}: sin tabla", name));
+            assert_eq!(table.scheme, PartitionScheme::Mbr, "{}", name);
+            assert_eq!(partitions(&table), *expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_mbr_rejections() {
+        type Case = (&'static str, fn(&mut [u8]));
+        let cases: &[Case] = &[
+            ("sin firma", |disk| disk[510] = 0),
+            // Un sector de arranque FAT tiene código donde irían los indicadores de estado
+            ("indicador de estado inválido", |disk| disk[446 + 16] = 0x12),
+        ];
+        for (name, modify) in cases {
+            let mut disk = mbr_disk();
+            modify(&mut disk);
+            assert!(read_table(&disk, 512).is_none(), "{}", name);
+        }
+
+        let mut disk = mbr_disk();
+        disk[446] = 0x80;
+        disk[440..444].copy_from_slice(&0xCAFE_F00Du32.to_le_bytes());
+        let table = read_table(&disk, 512).unwrap();
+        assert_eq!(table.disk_signature, 0xCAFE_F00D);
+        assert!(table.iter().next().unwrap().bootable);
+        assert!(read_table(&disk[..512], 512).is_none());
+    }
+}
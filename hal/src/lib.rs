@@ -186,6 +186,132 @@ pub mod timer {
     }
 }
 
+/// Módulo de reloj de tiempo real (RTC del CMOS)
+pub mod rtc {
+    use core::arch::asm;
+
+    const CMOS_ADDRESS: u16 = 0x70;
+    const CMOS_DATA: u16 = 0x71;
+
+    /// Registros de estado A (actualización en curso) y B (formato)
+    const RTC_STATUS_A: u8 = 0x0A;
+    const RTC_STATUS_B: u8 = 0x0B;
+    const RTC_UPDATE_IN_PROGRESS: u8 = 0x80;
+    const RTC_FORMAT_BINARY: u8 = 0x04;
+    const RTC_FORMAT_24_HOUR: u8 = 0x02;
+    const RTC_HOUR_PM: u8 = 0x80;
+
+    /// Leer un registro del CMOS
+    fn read_register(register: u8) -> u8 {
+        let value: u8;
+        unsafe {
+            asm!("out dx, al", in("dx") CMOS_ADDRESS, in("al") register, options(nomem, nostack));
+            asm!("in al, dx", in("dx") CMOS_DATA, out("al") value, options(nomem, nostack));
+        }
+        value
+    }
+
+    /// Leer segundos, minutos, hora, día, mes, año y siglo tal como los guarda el RTC
+    fn read_raw() -> [u8; 7] {
+        while read_register(RTC_STATUS_A) & RTC_UPDATE_IN_PROGRESS != 0 {}
+        [
+            read_register(0x00),
+            read_register(0x02),
+            read_register(0x04),
+            read_register(0x07),
+            read_register(0x08),
+            read_register(0x09),
+            read_register(0x32),
+        ]
+    }
+
+    fn bcd_to_binary(value: u8) -> u8 {
+        (value & 0x0F) + (value >> 4) * 10
+    }
+
+    /// Días desde 1970-01-01 hasta la fecha dada (calendario gregoriano, años positivos)
+    fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+        let year = if month <= 2 { year - 1 } else { year };
+        let era = year / 400;
+        let year_of_era = year - era * 400;
+        let month_index = (month as i64 + 9) % 12;
+        let day_of_year = (153 * month_index + 2) / 5 + day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146097 + day_of_era - 719468
+    }
+
+    /// Obtener la hora del RTC en segundos desde la época Unix (UTC)
+    pub fn unix_time() -> u64 {
+        // Repetir la lectura hasta obtener dos iguales: evita leer durante una actualización
+        let mut raw = read_raw();
+        loop {
+            let again = read_raw();
+            if again == raw {
+                break;
+            }
+            raw = again;
+        }
+
+        decode(raw, read_register(RTC_STATUS_B))
+    }
+
+    /// Convertir una lectura del RTC a segundos Unix según el formato del registro B
+    fn decode(raw: [u8; 7], status_b: u8) -> u64 {
+        let [mut second, mut minute, mut hour, mut day, mut month, mut year, mut century] = raw;
+        if status_b & RTC_FORMAT_BINARY == 0 {
+            second = bcd_to_binary(second);
+            minute = bcd_to_binary(minute);
+            hour = bcd_to_binary(hour & !RTC_HOUR_PM) | (hour & RTC_HOUR_PM);
+            day = bcd_to_binary(day);
+            month = bcd_to_binary(month);
+            year = bcd_to_binary(year);
+            century = bcd_to_binary(century);
+        }
+        if status_b & RTC_FORMAT_24_HOUR == 0 {
+            // 12 AM es la hora 0 y 12 PM la 12
+            let pm = hour & RTC_HOUR_PM != 0;
+            hour = (hour & !RTC_HOUR_PM) % 12 + if pm { 12 } else { 0 };
+        }
+
+        // Sin registro de siglo válido se asume el siglo XXI
+        let century = if (19..=21).contains(&century) { century } else { 20 };
+        let full_year = century as i64 * 100 + year as i64;
+        let days = days_from_civil(full_year, month.clamp(1, 12) as u32, day.max(1) as u32);
+        let seconds = days * 86400 + hour as i64 * 3600 + minute as i64 * 60 + second as i64;
+        seconds.max(0) as u64
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// 2024-01-01 00:00:00 UTC
+        const NEW_YEAR_2024: u64 = 1_704_067_200;
+
+        #[test]
+        fn test_12_hour_format() {
+            // (hora del RTC, hora del día)
+            for (hour, expected) in [(12, 0), (1, 1), (11, 11), (12 | RTC_HOUR_PM, 12), (1 | RTC_HOUR_PM, 13), (11 | RTC_HOUR_PM, 23)] {
+                let binary = decode([0, 0, hour, 1, 1, 24, 20], RTC_FORMAT_BINARY);
+                assert_eq!(binary, NEW_YEAR_2024 + expected * 3600, "hora {:#04x}", hour);
+                let bcd_hour = ((hour & !RTC_HOUR_PM) / 10 * 16 + (hour & !RTC_HOUR_PM) % 10) | (hour & RTC_HOUR_PM);
+                let bcd = decode([0, 0, bcd_hour, 0x01, 0x01, 0x24, 0x20], 0);
+                assert_eq!(bcd, binary, "hora BCD {:#04x}", bcd_hour);
+            }
+        }
+
+        #[test]
+        fn test_24_hour_format_and_century() {
+            let status_b = RTC_FORMAT_BINARY | RTC_FORMAT_24_HOUR;
+            assert_eq!(decode([0, 0, 0, 1, 1, 24, 20], status_b), NEW_YEAR_2024);
+            assert_eq!(decode([5, 4, 13, 1, 1, 24, 20], status_b), NEW_YEAR_2024 + 13 * 3600 + 4 * 60 + 5);
+            assert_eq!(decode([0x59, 0x59, 0x23, 0x31, 0x12, 0x99, 0x19], RTC_FORMAT_24_HOUR), 946_684_799);
+            // Sin registro de siglo se asume el siglo XXI
+            assert_eq!(decode([0, 0, 0, 1, 1, 24, 0], status_b), NEW_YEAR_2024);
+        }
+    }
+}
+
 /// Módulo de puerto serie
 pub mod serial {
     use core::arch::asm;
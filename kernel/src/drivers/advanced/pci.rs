@@ -3,6 +3,7 @@
 //! Implementa soporte completo para dispositivos PCI
 //! incluyendo detección, configuración y gestión de recursos.
 
+use alloc::vec::Vec;
 use core::sync::atomic::{AtomicU32, AtomicU64, AtomicBool, Ordering};
 
 /// Clase de dispositivo PCI
@@ -137,7 +138,7 @@ pub struct PciStats {
 impl PciManager {
     pub fn new() -> Self {
         Self {
-            devices: [None; 256],
+            devices: [const { None }; 256],
             device_count: AtomicU32::new(0),
             is_initialized: AtomicBool::new(false),
             stats: PciStats {
@@ -505,7 +506,7 @@ impl PciManager {
         // Procesar eventos de dispositivos
         for i in 0..self.device_count.load(Ordering::Relaxed) as usize {
             if let Some(device) = &mut self.devices[i] {
-                if let Err(e) = self.process_device_events(device) {
+                if let Err(e) = Self::process_device_events(&mut self.stats, device) {
                     self.stats.last_error_code = e;
                     return Err(e);
                 }
@@ -516,14 +517,14 @@ impl PciManager {
     }
     
     /// Procesar eventos de dispositivo
-    fn process_device_events(&mut self, device: &mut PciDevice) -> Result<(), u32> {
+    fn process_device_events(stats: &mut PciStats, device: &mut PciDevice) -> Result<(), u32> {
         // TODO: Implementar procesamiento de eventos del dispositivo
         // Por ahora, simulamos el procesamiento
         
         if device.is_enabled {
             // Simular interrupciones ocasionales
-            if self.stats.total_config_reads % 1000 == 0 {
-                self.stats.total_interrupts += 1;
+            if stats.total_config_reads % 1000 == 0 {
+                stats.total_interrupts += 1;
             }
         }
         
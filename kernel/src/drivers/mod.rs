@@ -9,3 +9,6 @@ pub mod system;
 pub mod storage;
 pub mod network;
 pub mod advanced;
+
+// Las tablas de particiones y las imágenes de disco viven en el crate de drivers
+pub use reactos_rust_drivers::{image, partition};
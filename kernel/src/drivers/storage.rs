@@ -3,10 +3,13 @@
 //! Implementa un driver básico para dispositivos de almacenamiento
 //! con soporte para ATA, SATA y dispositivos de bloque.
 
+use core::fmt;
 use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
 use core::ptr::NonNull;
 use core::mem;
 
+use super::partition::{self, PartitionEntry};
+
 /// Tipos de dispositivos de almacenamiento
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StorageType {
@@ -20,6 +23,8 @@ pub enum StorageType {
     USB,
     /// Dispositivo NVMe
     NVMe,
+    /// Imagen de disco en memoria o en un archivo (`BlockBackend`)
+    Image,
     /// Dispositivo desconocido
     Unknown,
 }
@@ -58,6 +63,26 @@ pub enum StorageOperation {
     Sync,
 }
 
+/// Almacenamiento que respalda los sectores de un dispositivo
+///
+/// Las peticiones llegan ya validadas contra el tamaño del dispositivo y
+/// `buffer` tiene al menos `count * sector_size` bytes.
+pub trait BlockBackend: fmt::Debug {
+    /// Leer `count` sectores a partir de `sector`
+    fn read_sectors(&mut self, sector: u64, count: u32, sector_size: u32, buffer: &mut [u8]) -> bool;
+
+    /// Escribir `count` sectores a partir de `sector`
+    fn write_sectors(&mut self, sector: u64, count: u32, sector_size: u32, buffer: &[u8]) -> bool;
+
+    /// Llevar las escrituras pendientes al almacenamiento persistente
+    fn flush(&mut self) -> bool {
+        true
+    }
+
+    /// Tamaño del almacenamiento en bytes
+    fn size(&self) -> u64;
+}
+
 /// Estructura de información de dispositivo de almacenamiento
 #[derive(Debug)]
 pub struct StorageDevice {
@@ -99,6 +124,12 @@ pub struct StorageDevice {
     pub transfer_mode: u8,
     /// Velocidad de transferencia
     pub transfer_speed: u32,
+    /// Disco que contiene la partición (`None` para discos completos)
+    pub parent_device: Option<u32>,
+    /// Entrada de la tabla de particiones si el dispositivo es una partición
+    pub partition: Option<PartitionEntry>,
+    /// Almacenamiento que respalda los sectores (`None` en dispositivos simulados)
+    pub backend: Option<&'static mut dyn BlockBackend>,
 }
 
 impl StorageDevice {
@@ -129,9 +160,28 @@ impl StorageDevice {
             cache_enabled: false,
             transfer_mode: 0,
             transfer_speed: 0,
+            parent_device: None,
+            partition: None,
+            backend: None,
         }
     }
 
+    /// Crear un dispositivo listo para usar respaldado por `backend`
+    ///
+    /// El número de sectores se calcula a partir del tamaño del respaldo;
+    /// los bytes que no completan un sector se ignoran.
+    pub fn with_backend(name: &str, sector_size: u32, backend: &'static mut dyn BlockBackend) -> Self {
+        let mut device = Self::new(0, name, StorageType::Image);
+        device.sector_size = sector_size;
+        device.sector_count = if sector_size == 0 { 0 } else { backend.size() / sector_size as u64 };
+        device.total_size = device.sector_count * sector_size as u64;
+        device.state = StorageState::Ready;
+        device.enabled = true;
+        device.cache_enabled = true;
+        device.backend = Some(backend);
+        device
+    }
+
     /// Inicializar el dispositivo
     pub fn initialize(&mut self) -> bool {
         match self.storage_type {
@@ -140,6 +190,7 @@ impl StorageDevice {
             StorageType::SCSI => self.initialize_scsi(),
             StorageType::USB => self.initialize_usb(),
             StorageType::NVMe => self.initialize_nvme(),
+            StorageType::Image => self.initialize_image(),
             StorageType::Unknown => false,
         }
     }
@@ -184,8 +235,18 @@ impl StorageDevice {
         true
     }
 
-    /// Leer sectores del dispositivo
-    pub fn read_sectors(&mut self, sector: u64, count: u32, buffer: &mut [u8]) -> bool {
+    /// Inicializar una imagen de disco: está lista en cuanto tiene respaldo
+    fn initialize_image(&mut self) -> bool {
+        if self.backend.is_none() {
+            return false;
+        }
+        self.state = StorageState::Ready;
+        self.enabled = true;
+        true
+    }
+
+    /// Verificar que una petición de E/S es válida para el dispositivo
+    fn accepts_request(&self, sector: u64, count: u32, buffer_len: usize) -> bool {
         if self.state != StorageState::Ready {
             return false;
         }
@@ -194,11 +255,21 @@ impl StorageDevice {
             return false;
         }
 
-        let bytes_to_read = count as usize * self.sector_size as usize;
-        if buffer.len() < bytes_to_read {
+        buffer_len >= count as usize * self.sector_size as usize
+    }
+
+    /// Leer sectores del dispositivo
+    pub fn read_sectors(&mut self, sector: u64, count: u32, buffer: &mut [u8]) -> bool {
+        if !self.accepts_request(sector, count, buffer.len()) {
             return false;
         }
 
+        if let Some(backend) = self.backend.as_mut() {
+            return backend.read_sectors(sector, count, self.sector_size, buffer);
+        }
+
+        let bytes_to_read = count as usize * self.sector_size as usize;
+
         self.state = StorageState::Busy;
         
         // TODO: Implementar lectura real de sectores
@@ -213,17 +284,12 @@ impl StorageDevice {
 
     /// Escribir sectores al dispositivo
     pub fn write_sectors(&mut self, sector: u64, count: u32, buffer: &[u8]) -> bool {
-        if self.state != StorageState::Ready {
-            return false;
-        }
-
-        if sector + count as u64 > self.sector_count {
+        if !self.accepts_request(sector, count, buffer.len()) {
             return false;
         }
 
-        let bytes_to_write = count as usize * self.sector_size as usize;
-        if buffer.len() < bytes_to_write {
-            return false;
+        if let Some(backend) = self.backend.as_mut() {
+            return backend.write_sectors(sector, count, self.sector_size, buffer);
         }
 
         self.state = StorageState::Busy;
@@ -235,6 +301,14 @@ impl StorageDevice {
         true
     }
 
+    /// Llevar al almacenamiento de respaldo las escrituras pendientes
+    pub fn flush(&mut self) -> bool {
+        match self.backend.as_mut() {
+            Some(backend) => backend.flush(),
+            None => true,
+        }
+    }
+
     /// Obtener información del dispositivo
     pub fn get_info(&self) -> (u64, u32, u64) {
         (self.sector_count, self.sector_size, self.total_size)
@@ -256,6 +330,328 @@ impl StorageDevice {
     }
 }
 
+/// Tamaño de un bloque de la caché en bytes
+pub const BLOCK_CACHE_BLOCK_SIZE: usize = 512;
+/// Número máximo de bloques que puede contener la caché
+pub const BLOCK_CACHE_MAX_BLOCKS: usize = 256;
+/// Capacidad por defecto de la caché en bloques
+pub const BLOCK_CACHE_DEFAULT_CAPACITY: usize = 128;
+/// Bloques leídos por adelantado en accesos secuenciales
+pub const BLOCK_CACHE_DEFAULT_READ_AHEAD: u32 = 8;
+/// Ticks entre dos escrituras periódicas de los bloques sucios
+pub const BLOCK_CACHE_DEFAULT_FLUSH_INTERVAL: u64 = 100;
+
+/// Bloque almacenado en la caché
+#[derive(Debug, Clone, Copy)]
+pub struct CachedBlock {
+    /// Dispositivo al que pertenece el bloque
+    pub device_id: u32,
+    /// Sector del dispositivo
+    pub sector: u64,
+    /// Contenido del sector
+    pub data: [u8; BLOCK_CACHE_BLOCK_SIZE],
+    /// Bloque modificado pendiente de escribir
+    pub dirty: bool,
+    /// Marca del último acceso (LRU)
+    pub last_used: u64,
+}
+
+/// Estadísticas de la caché de bloques
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BlockCacheStats {
+    /// Lecturas servidas desde la caché
+    pub hits: u64,
+    /// Lecturas que tuvieron que ir al dispositivo
+    pub misses: u64,
+    /// Bloques cargados por lectura anticipada
+    pub read_ahead_blocks: u64,
+    /// Bloques expulsados por LRU
+    pub evictions: u64,
+    /// Bloques sucios escritos al dispositivo
+    pub writebacks: u64,
+    /// Bloques presentes en la caché
+    pub cached_blocks: usize,
+    /// Bloques sucios pendientes de escribir
+    pub dirty_blocks: usize,
+    /// Capacidad actual en bloques
+    pub capacity: usize,
+}
+
+/// Caché de bloques compartida con escritura diferida y expulsión LRU
+pub struct BlockCache {
+    /// Bloques en caché
+    pub blocks: [Option<CachedBlock>; BLOCK_CACHE_MAX_BLOCKS],
+    /// Capacidad en bloques
+    pub capacity: usize,
+    /// Bloques a leer por adelantado (0 la desactiva)
+    pub read_ahead: u32,
+    /// Ticks entre escrituras periódicas (0 las desactiva)
+    pub flush_interval: u64,
+    /// Reloj lógico para LRU
+    clock: u64,
+    /// Ticks transcurridos
+    ticks: u64,
+    /// Tick de la última escritura periódica
+    last_flush: u64,
+    /// Siguiente sector esperado en una lectura secuencial
+    next_sequential: Option<(u32, u64)>,
+    /// Estadísticas acumuladas
+    stats: BlockCacheStats,
+}
+
+impl BlockCache {
+    /// Crear una caché con la capacidad indicada
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            blocks: [None; BLOCK_CACHE_MAX_BLOCKS],
+            capacity: capacity.clamp(1, BLOCK_CACHE_MAX_BLOCKS),
+            read_ahead: BLOCK_CACHE_DEFAULT_READ_AHEAD,
+            flush_interval: BLOCK_CACHE_DEFAULT_FLUSH_INTERVAL,
+            clock: 0,
+            ticks: 0,
+            last_flush: 0,
+            next_sequential: None,
+            stats: BlockCacheStats::default(),
+        }
+    }
+
+    /// Cambiar la capacidad, expulsando bloques si hace falta
+    pub fn set_capacity(&mut self, devices: &mut [Option<StorageDevice>], capacity: usize) -> bool {
+        self.capacity = capacity.clamp(1, BLOCK_CACHE_MAX_BLOCKS);
+        while self.cached_count() > self.capacity {
+            if self.evict(devices).is_none() {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Leer sectores a través de la caché
+    pub fn read(&mut self, devices: &mut [Option<StorageDevice>], device_id: u32, sector: u64, count: u32, buffer: &mut [u8]) -> bool {
+        for i in 0..count as usize {
+            let target = sector + i as u64;
+            let slot = match self.find(device_id, target) {
+                Some(slot) => {
+                    self.stats.hits += 1;
+                    slot
+                }
+                None => {
+                    self.stats.misses += 1;
+                    match self.load(devices, device_id, target) {
+                        Some(slot) => slot,
+                        None => return false,
+                    }
+                }
+            };
+
+            let stamp = self.tick_clock();
+            if let Some(ref mut block) = self.blocks[slot] {
+                block.last_used = stamp;
+                buffer[i * BLOCK_CACHE_BLOCK_SIZE..(i + 1) * BLOCK_CACHE_BLOCK_SIZE].copy_from_slice(&block.data);
+            }
+        }
+
+        let next = sector + count as u64;
+        if self.next_sequential == Some((device_id, sector)) {
+            self.prefetch(devices, device_id, next);
+        }
+        self.next_sequential = Some((device_id, next));
+        true
+    }
+
+    /// Escribir sectores en la caché; se llevan al dispositivo al sincronizar
+    pub fn write(&mut self, devices: &mut [Option<StorageDevice>], device_id: u32, sector: u64, count: u32, buffer: &[u8]) -> bool {
+        for i in 0..count as usize {
+            let target = sector + i as u64;
+            let slot = match self.find(device_id, target) {
+                Some(slot) => slot,
+                None => match self.allocate(devices) {
+                    Some(slot) => slot,
+                    None => return false,
+                },
+            };
+
+            let mut data = [0u8; BLOCK_CACHE_BLOCK_SIZE];
+            data.copy_from_slice(&buffer[i * BLOCK_CACHE_BLOCK_SIZE..(i + 1) * BLOCK_CACHE_BLOCK_SIZE]);
+            let last_used = self.tick_clock();
+            self.blocks[slot] = Some(CachedBlock { device_id, sector: target, data, dirty: true, last_used });
+        }
+        true
+    }
+
+    /// Escribir los bloques sucios de un dispositivo (o de todos)
+    pub fn sync(&mut self, devices: &mut [Option<StorageDevice>], device_id: Option<u32>) -> bool {
+        let mut success = true;
+        for slot in 0..BLOCK_CACHE_MAX_BLOCKS {
+            let pending = match self.blocks[slot] {
+                Some(ref block) => block.dirty && (device_id.is_none() || device_id == Some(block.device_id)),
+                None => false,
+            };
+            if pending && !self.write_back(devices, slot) {
+                success = false;
+            }
+        }
+        success
+    }
+
+    /// Descartar los bloques de un dispositivo sin escribirlos
+    pub fn invalidate(&mut self, device_id: u32) {
+        for slot in self.blocks.iter_mut() {
+            if matches!(slot, Some(block) if block.device_id == device_id) {
+                *slot = None;
+            }
+        }
+        if matches!(self.next_sequential, Some((id, _)) if id == device_id) {
+            self.next_sequential = None;
+        }
+    }
+
+    /// Avanzar un tick y escribir los bloques sucios si vence el intervalo
+    pub fn tick(&mut self, devices: &mut [Option<StorageDevice>]) -> bool {
+        self.ticks += 1;
+        if self.flush_interval == 0 || self.ticks - self.last_flush < self.flush_interval {
+            return true;
+        }
+        self.last_flush = self.ticks;
+        self.sync(devices, None)
+    }
+
+    /// Obtener las estadísticas de la caché
+    pub fn stats(&self) -> BlockCacheStats {
+        let mut stats = self.stats;
+        stats.cached_blocks = self.cached_count();
+        stats.dirty_blocks = self.blocks.iter().flatten().filter(|block| block.dirty).count();
+        stats.capacity = self.capacity;
+        stats
+    }
+
+    /// Número de bloques en la caché
+    fn cached_count(&self) -> usize {
+        self.blocks.iter().flatten().count()
+    }
+
+    /// Avanzar el reloj lógico
+    fn tick_clock(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    /// Buscar un bloque en la caché
+    fn find(&self, device_id: u32, sector: u64) -> Option<usize> {
+        self.blocks.iter().position(|slot| {
+            matches!(slot, Some(block) if block.device_id == device_id && block.sector == sector)
+        })
+    }
+
+    /// Cargar un sector del dispositivo en la caché
+    fn load(&mut self, devices: &mut [Option<StorageDevice>], device_id: u32, sector: u64) -> Option<usize> {
+        let mut data = [0u8; BLOCK_CACHE_BLOCK_SIZE];
+        if !find_device(devices, device_id)?.read_sectors(sector, 1, &mut data) {
+            return None;
+        }
+
+        let slot = self.allocate(devices)?;
+        let last_used = self.tick_clock();
+        self.blocks[slot] = Some(CachedBlock { device_id, sector, data, dirty: false, last_used });
+        Some(slot)
+    }
+
+    /// Leer por adelantado los sectores siguientes a un acceso secuencial
+    fn prefetch(&mut self, devices: &mut [Option<StorageDevice>], device_id: u32, start: u64) {
+        let sector_count = match find_device(devices, device_id) {
+            Some(device) => device.sector_count,
+            None => return,
+        };
+
+        for sector in start..start + self.read_ahead as u64 {
+            if sector >= sector_count {
+                break;
+            }
+            if self.find(device_id, sector).is_some() {
+                continue;
+            }
+            if self.load(devices, device_id, sector).is_none() {
+                break;
+            }
+            self.stats.read_ahead_blocks += 1;
+        }
+    }
+
+    /// Obtener un slot libre, expulsando el bloque LRU si la caché está llena
+    fn allocate(&mut self, devices: &mut [Option<StorageDevice>]) -> Option<usize> {
+        if self.cached_count() >= self.capacity {
+            return self.evict(devices);
+        }
+        self.blocks.iter().position(|slot| slot.is_none())
+    }
+
+    /// Expulsar el bloque usado hace más tiempo, escribiéndolo si está sucio
+    fn evict(&mut self, devices: &mut [Option<StorageDevice>]) -> Option<usize> {
+        let slot = (0..BLOCK_CACHE_MAX_BLOCKS)
+            .filter_map(|slot| self.blocks[slot].map(|block| (slot, block.last_used)))
+            .min_by_key(|&(_, last_used)| last_used)
+            .map(|(slot, _)| slot)?;
+
+        if matches!(self.blocks[slot], Some(block) if block.dirty) && !self.write_back(devices, slot) {
+            return None;
+        }
+
+        self.blocks[slot] = None;
+        self.stats.evictions += 1;
+        Some(slot)
+    }
+
+    /// Escribir un bloque sucio al dispositivo
+    fn write_back(&mut self, devices: &mut [Option<StorageDevice>], slot: usize) -> bool {
+        let block = match self.blocks[slot] {
+            Some(ref mut block) => block,
+            None => return true,
+        };
+
+        let written = match find_device(devices, block.device_id) {
+            Some(device) => device.write_sectors(block.sector, 1, &block.data),
+            None => false,
+        };
+        if written {
+            block.dirty = false;
+            self.stats.writebacks += 1;
+        }
+        written
+    }
+}
+
+/// Nombre de una partición: el del disco seguido de `p` y el número de partición
+fn partition_device_name(disk_name: &[u8; 64], number: u32) -> [u8; 64] {
+    let mut name = [0u8; 64];
+    let disk_len = disk_name.iter().position(|&byte| byte == 0).unwrap_or(64);
+
+    let mut digits = [0u8; 10];
+    let mut digit_count = 0;
+    let mut value = number;
+    loop {
+        digits[digit_count] = b'0' + (value % 10) as u8;
+        digit_count += 1;
+        value /= 10;
+        if value == 0 {
+            break;
+        }
+    }
+
+    // Se recorta el nombre del disco para que el sufijo quepa siempre
+    let copy_len = disk_len.min(63 - 1 - digit_count);
+    name[..copy_len].copy_from_slice(&disk_name[..copy_len]);
+    name[copy_len] = b'p';
+    for i in 0..digit_count {
+        name[copy_len + 1 + i] = digits[digit_count - 1 - i];
+    }
+    name
+}
+
+/// Buscar un dispositivo registrado por su ID
+fn find_device(devices: &mut [Option<StorageDevice>], device_id: u32) -> Option<&mut StorageDevice> {
+    devices.iter_mut().flatten().find(|device| device.device_id == device_id)
+}
+
 /// Estructura del gestor de almacenamiento
 pub struct StorageManager {
     /// Contador de dispositivos
@@ -266,6 +662,8 @@ pub struct StorageManager {
     pub device_count: AtomicUsize,
     /// Dispositivo actual
     pub current_device: AtomicUsize,
+    /// Caché de bloques compartida por todos los dispositivos
+    pub cache: BlockCache,
 }
 
 impl StorageManager {
@@ -276,6 +674,7 @@ impl StorageManager {
             devices: [(); 16].map(|_| None),
             device_count: AtomicUsize::new(0),
             current_device: AtomicUsize::new(0),
+            cache: BlockCache::new(BLOCK_CACHE_DEFAULT_CAPACITY),
         }
     }
 
@@ -297,7 +696,13 @@ impl StorageManager {
     }
 
     /// Desregistrar un dispositivo de almacenamiento
+    ///
+    /// Al desregistrar un disco también se eliminan sus particiones.
     pub fn unregister_device(&mut self, device_id: u32) -> bool {
+        self.remove_partitions(device_id);
+        self.cache.sync(&mut self.devices, Some(device_id));
+        self.cache.invalidate(device_id);
+
         for i in 0..16 {
             if let Some(ref device) = self.devices[i] {
                 if device.device_id == device_id {
@@ -324,6 +729,15 @@ impl StorageManager {
 
     /// Leer sectores de un dispositivo
     pub fn read_sectors(&mut self, device_id: u32, sector: u64, count: u32, buffer: &mut [u8]) -> bool {
+        let (device_id, sector) = match self.map_partition(device_id, sector, count) {
+            Some(target) => target,
+            None => return false,
+        };
+
+        if self.uses_cache(device_id, sector, count, buffer.len()) {
+            return self.cache.read(&mut self.devices, device_id, sector, count, buffer);
+        }
+
         for i in 0..16 {
             if let Some(ref mut device) = self.devices[i] {
                 if device.device_id == device_id {
@@ -336,6 +750,15 @@ impl StorageManager {
 
     /// Escribir sectores a un dispositivo
     pub fn write_sectors(&mut self, device_id: u32, sector: u64, count: u32, buffer: &[u8]) -> bool {
+        let (device_id, sector) = match self.map_partition(device_id, sector, count) {
+            Some(target) => target,
+            None => return false,
+        };
+
+        if self.uses_cache(device_id, sector, count, buffer.len()) {
+            return self.cache.write(&mut self.devices, device_id, sector, count, buffer);
+        }
+
         for i in 0..16 {
             if let Some(ref mut device) = self.devices[i] {
                 if device.device_id == device_id {
@@ -346,6 +769,124 @@ impl StorageManager {
         false
     }
 
+    /// Traducir una petición sobre una partición al disco que la contiene
+    ///
+    /// Los discos completos se devuelven sin cambios. Las peticiones que se
+    /// salen de la partición devuelven `None`.
+    fn map_partition(&self, device_id: u32, sector: u64, count: u32) -> Option<(u32, u64)> {
+        let device = match self.get_device_info(device_id) {
+            Some(device) => device,
+            None => return Some((device_id, sector)),
+        };
+        match (device.parent_device, device.partition) {
+            (Some(disk_id), Some(partition)) => {
+                if sector + count as u64 > partition.sector_count {
+                    return None;
+                }
+                Some((disk_id, partition.first_sector + sector))
+            }
+            _ => Some((device_id, sector)),
+        }
+    }
+
+    /// Leer la tabla de particiones de un disco y registrar cada partición como dispositivo
+    ///
+    /// Las particiones se llaman como el disco seguido de `p` y su número, y
+    /// heredan su tipo, estado y tamaño de sector. Las registradas en un
+    /// escaneo anterior se eliminan antes. Devuelve el número de particiones
+    /// registradas.
+    pub fn scan_partitions(&mut self, disk_id: u32) -> usize {
+        let (storage_type, state, sector_size, sector_count, name) = match self.get_device_info(disk_id) {
+            Some(disk) if disk.parent_device.is_none() => {
+                (disk.storage_type, disk.state, disk.sector_size, disk.sector_count, disk.name)
+            }
+            _ => return 0,
+        };
+        self.remove_partitions(disk_id);
+
+        let table = match partition::read_partition_table(sector_size, sector_count, |sector, buffer| {
+            self.read_sectors(disk_id, sector, 1, buffer)
+        }) {
+            Some(table) => table,
+            None => return 0,
+        };
+
+        let mut registered = 0;
+        for entry in table.iter() {
+            let mut device = StorageDevice::new(0, "", storage_type);
+            device.name = partition_device_name(&name, entry.number);
+            device.state = state;
+            device.sector_size = sector_size;
+            device.sector_count = entry.sector_count;
+            device.total_size = entry.sector_count * sector_size as u64;
+            device.enabled = true;
+            device.parent_device = Some(disk_id);
+            device.partition = Some(*entry);
+
+            if self.register_device(device) == 0 {
+                break;
+            }
+            registered += 1;
+        }
+        registered
+    }
+
+    /// Desregistrar las particiones de un disco
+    fn remove_partitions(&mut self, disk_id: u32) {
+        for i in 0..16 {
+            if matches!(self.devices[i], Some(ref device) if device.parent_device == Some(disk_id)) {
+                self.devices[i] = None;
+                self.device_count.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    /// Verificar si una petición válida puede servirse desde la caché
+    fn uses_cache(&self, device_id: u32, sector: u64, count: u32, buffer_len: usize) -> bool {
+        match self.get_device_info(device_id) {
+            Some(device) => {
+                device.cache_enabled
+                    && device.sector_size as usize == BLOCK_CACHE_BLOCK_SIZE
+                    && device.accepts_request(sector, count, buffer_len)
+            }
+            None => false,
+        }
+    }
+
+    /// Escribir los bloques sucios de un dispositivo (o de todos si es `None`)
+    ///
+    /// Las particiones se sincronizan a través de su disco, ya que la caché
+    /// guarda los bloques con el ID del disco. Tras vaciar la caché se
+    /// sincroniza también el almacenamiento de respaldo.
+    pub fn sync(&mut self, device_id: Option<u32>) -> bool {
+        let device_id = device_id.map(|id| {
+            self.get_device_info(id).and_then(|device| device.parent_device).unwrap_or(id)
+        });
+        let mut success = self.cache.sync(&mut self.devices, device_id);
+
+        for device in self.devices.iter_mut().flatten() {
+            if (device_id.is_none() || device_id == Some(device.device_id)) && !device.flush() {
+                success = false;
+            }
+        }
+        success
+    }
+
+    /// Procesar un tick: escritura periódica de la caché
+    pub fn tick(&mut self) -> bool {
+        self.cache.tick(&mut self.devices)
+    }
+
+    /// Cambiar la capacidad de la caché en bloques
+    pub fn set_cache_capacity(&mut self, capacity: usize) -> bool {
+        self.cache.set_capacity(&mut self.devices, capacity)
+    }
+
+    /// Obtener estadísticas de la caché de bloques
+    pub fn get_cache_stats(&self) -> BlockCacheStats {
+        self.cache.stats()
+    }
+
     /// Obtener información de un dispositivo
     pub fn get_device_info(&self, device_id: u32) -> Option<&StorageDevice> {
         for i in 0..16 {
@@ -394,8 +935,11 @@ impl StorageManager {
     }
 }
 
+/// Gestor de almacenamiento global
+static mut STORAGE_MANAGER: Option<StorageManager> = None;
+
 /// Función para inicializar el gestor de almacenamiento
-pub fn init_storage_manager() -> StorageManager {
+pub fn init_storage_manager() {
     let mut manager = StorageManager::new();
     
     // Registrar un dispositivo de ejemplo
@@ -415,13 +959,68 @@ pub fn init_storage_manager() -> StorageManager {
     device.transfer_mode = 2; // PIO Mode 2
     device.transfer_speed = 16; // 16 MB/s
     
-    manager.register_device(device);
+    let device_id = manager.register_device(device);
+    manager.scan_partitions(device_id);
     
-    manager
+    unsafe {
+        STORAGE_MANAGER = Some(manager);
+    }
+}
+
+/// Instalar un gestor de almacenamiento global ya configurado
+pub fn set_storage_manager(manager: StorageManager) {
+    unsafe {
+        STORAGE_MANAGER = Some(manager);
+    }
+}
+
+/// Obtener el gestor de almacenamiento global
+pub fn get_storage_manager() -> Option<&'static mut StorageManager> {
+    unsafe { (*core::ptr::addr_of_mut!(STORAGE_MANAGER)).as_mut() }
+}
+
+/// Leer sectores de un dispositivo a través de la caché de bloques
+pub fn read_sectors(device_id: u32, sector: u64, count: u32, buffer: &mut [u8]) -> bool {
+    if let Some(manager) = get_storage_manager() {
+        manager.read_sectors(device_id, sector, count, buffer)
+    } else {
+        false
+    }
+}
+
+/// Escribir sectores en un dispositivo a través de la caché de bloques
+pub fn write_sectors(device_id: u32, sector: u64, count: u32, buffer: &[u8]) -> bool {
+    if let Some(manager) = get_storage_manager() {
+        manager.write_sectors(device_id, sector, count, buffer)
+    } else {
+        false
+    }
+}
+
+/// Escribir los bloques sucios de un dispositivo (o de todos si es `None`)
+///
+/// Sin gestor inicializado no hay nada pendiente y la sincronización tiene éxito.
+pub fn sync_storage(device_id: Option<u32>) -> bool {
+    if let Some(manager) = get_storage_manager() {
+        manager.sync(device_id)
+    } else {
+        true
+    }
+}
+
+/// Procesar eventos de almacenamiento (escritura periódica de la caché)
+pub fn process_events() {
+    if let Some(manager) = get_storage_manager() {
+        manager.tick();
+    }
+}
+
+/// Obtener estadísticas de la caché de bloques
+pub fn get_block_cache_stats() -> BlockCacheStats {
+    get_storage_manager().map_or(BlockCacheStats::default(), |manager| manager.get_cache_stats())
 }
 
 /// Función para obtener estadísticas de almacenamiento
 pub fn get_storage_statistics() -> (usize, usize, usize) {
-    // TODO: Implementar acceso a las estadísticas del gestor de almacenamiento
-    (1, 1, 0) // (total, ready, errors)
+    get_storage_manager().map_or((0, 0, 0), |manager| manager.get_stats()) // (total, ready, errors)
 }
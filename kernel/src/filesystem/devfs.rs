@@ -0,0 +1,460 @@
+//! Sistema de archivos de dispositivos (devfs) para ReactOS Rust Kernel
+//!
+//! Expone como nodos de `/dev` los dispositivos de almacenamiento
+//! registrados (`sda`, `sdb`, ... y sus particiones `sda1`, ...), el puerto
+//! serie (`ttyS0`) y el framebuffer (`fb0`). Los nodos se generan en cada
+//! consulta a partir del estado de los drivers, por lo que reflejan los
+//! dispositivos conectados en cada momento.
+
+use alloc::{string::String, vec, vec::Vec};
+
+use super::path::path_name;
+use super::vfs::{FileInfo, FilePermissions, FileSystemDriver, NodeType};
+
+/// Nombre del nodo del puerto serie
+pub const DEVFS_SERIAL_NAME: &str = "ttyS0";
+
+/// Nombre del nodo del framebuffer
+pub const DEVFS_FRAMEBUFFER_NAME: &str = "fb0";
+
+/// Sectores transferidos como máximo por operación de bloque
+pub const DEVFS_MAX_TRANSFER_SECTORS: usize = 128;
+
+/// Número máximo de discos con nombre (`sda`..`sdz`)
+pub const DEVFS_MAX_BLOCK_DEVICES: usize = 26;
+
+// Inodos de los nodos fijos; los dispositivos de bloque usan
+// DEVFS_BLOCK_INODE_BASE + ID del dispositivo
+const DEVFS_ROOT_INODE: u64 = 1;
+const DEVFS_SERIAL_INODE: u64 = 2;
+const DEVFS_FRAMEBUFFER_INODE: u64 = 3;
+const DEVFS_BLOCK_INODE_BASE: u64 = 0x100;
+
+/// Nodo de /dev identificado a partir de una ruta
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DevNode {
+    /// Directorio raíz
+    Root,
+    /// Dispositivo de almacenamiento (ID en el gestor de almacenamiento)
+    Block(u32),
+    /// Puerto serie COM1
+    Serial,
+    /// Framebuffer del sistema
+    Framebuffer,
+}
+
+/// Driver del sistema de archivos de dispositivos
+pub struct DevFsDriver {
+    /// Dispositivo de bloque del montaje (no se usa)
+    pub device_id: u32,
+}
+
+impl DevFsDriver {
+    /// Crear el driver
+    pub fn new(device_id: u32) -> Self {
+        Self { device_id }
+    }
+
+    /// Identificar el nodo de una ruta
+    pub fn lookup(&self, path: &str) -> Result<DevNode, &'static str> {
+        let mut components = path.split('/').filter(|component| !component.is_empty() && *component != ".");
+        let name = match components.next() {
+            Some(name) => name,
+            None => return Ok(DevNode::Root),
+        };
+        if components.next().is_some() {
+            return Err("Ruta no encontrada");
+        }
+
+        match name {
+            DEVFS_SERIAL_NAME => Ok(DevNode::Serial),
+            DEVFS_FRAMEBUFFER_NAME if crate::gui::framebuffer::get_framebuffer().is_some() => Ok(DevNode::Framebuffer),
+            _ => block_devices()
+                .into_iter()
+                .find(|(_, device_name)| device_name == name)
+                .map(|(device_id, _)| DevNode::Block(device_id))
+                .ok_or("Ruta no encontrada"),
+        }
+    }
+
+    /// Nodos presentes en /dev
+    pub fn nodes(&self) -> Vec<(String, DevNode)> {
+        let mut nodes: Vec<(String, DevNode)> = block_devices()
+            .into_iter()
+            .map(|(device_id, name)| (name, DevNode::Block(device_id)))
+            .collect();
+        nodes.push((String::from(DEVFS_SERIAL_NAME), DevNode::Serial));
+        if crate::gui::framebuffer::get_framebuffer().is_some() {
+            nodes.push((String::from(DEVFS_FRAMEBUFFER_NAME), DevNode::Framebuffer));
+        }
+        nodes
+    }
+
+    /// Información de un nodo para el VFS
+    fn vfs_file_info(&self, name: &str, node: DevNode) -> FileInfo {
+        let (node_type, inode) = match node {
+            DevNode::Root => (NodeType::Directory, DEVFS_ROOT_INODE),
+            DevNode::Block(device_id) => (NodeType::BlockDevice, DEVFS_BLOCK_INODE_BASE + device_id as u64),
+            DevNode::Serial => (NodeType::CharDevice, DEVFS_SERIAL_INODE),
+            DevNode::Framebuffer => (NodeType::CharDevice, DEVFS_FRAMEBUFFER_INODE),
+        };
+        let mut file_info = FileInfo::new(0, name, node_type);
+        file_info.inode = inode;
+        file_info.link_count = 1;
+        file_info.permissions = if node == DevNode::Root {
+            FilePermissions::new(7, 5, 5)
+        } else {
+            FilePermissions::new(6, 6, 0)
+        };
+        match node {
+            DevNode::Block(device_id) => {
+                if let Some((sector_size, size)) = block_geometry(device_id) {
+                    file_info.size = size;
+                    file_info.block_size = sector_size;
+                    file_info.block_count = size / sector_size as u64;
+                }
+                file_info.device = device_id;
+            }
+            DevNode::Framebuffer => {
+                file_info.size = crate::gui::framebuffer::get_framebuffer().map_or(0, |framebuffer| framebuffer.size as u64);
+            }
+            _ => {}
+        }
+        file_info
+    }
+}
+
+impl FileSystemDriver for DevFsDriver {
+    fn open(&mut self, file_path: &str, create: bool) -> Result<FileInfo, &'static str> {
+        match self.lookup(file_path) {
+            Ok(node) => Ok(self.vfs_file_info(path_name(file_path), node)),
+            Err(_) if create => Err("Operación no soportada"),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn read_file(&mut self, file_path: &str, buffer: &mut [u8], offset: u64) -> Result<usize, &'static str> {
+        match self.lookup(file_path)? {
+            DevNode::Root => Err("No se puede leer un directorio como archivo"),
+            DevNode::Block(device_id) => read_block_device(device_id, offset, buffer),
+            // El driver serie solo transmite
+            DevNode::Serial => Ok(0),
+            DevNode::Framebuffer => {
+                let framebuffer = crate::gui::framebuffer::get_framebuffer().ok_or("Framebuffer no disponible")?;
+                if framebuffer.buffer.is_null() || offset >= framebuffer.size as u64 {
+                    return Ok(0);
+                }
+                let length = core::cmp::min(buffer.len(), framebuffer.size - offset as usize);
+                let memory = unsafe { core::slice::from_raw_parts(framebuffer.buffer, framebuffer.size) };
+                buffer[..length].copy_from_slice(&memory[offset as usize..offset as usize + length]);
+                Ok(length)
+            }
+        }
+    }
+
+    fn write_file(&mut self, file_path: &str, buffer: &[u8], offset: u64) -> Result<usize, &'static str> {
+        match self.lookup(file_path)? {
+            DevNode::Root => Err("No se puede escribir en un directorio"),
+            DevNode::Block(device_id) => write_block_device(device_id, offset, buffer),
+            DevNode::Serial => {
+                for &byte in buffer {
+                    crate::hal::serial::send_byte(byte);
+                }
+                Ok(buffer.len())
+            }
+            DevNode::Framebuffer => {
+                let framebuffer = crate::gui::framebuffer::get_framebuffer().ok_or("Framebuffer no disponible")?;
+                if framebuffer.buffer.is_null() || offset >= framebuffer.size as u64 {
+                    return Err("No hay espacio en el dispositivo");
+                }
+                let length = core::cmp::min(buffer.len(), framebuffer.size - offset as usize);
+                let memory = unsafe { core::slice::from_raw_parts_mut(framebuffer.buffer, framebuffer.size) };
+                memory[offset as usize..offset as usize + length].copy_from_slice(&buffer[..length]);
+                Ok(length)
+            }
+        }
+    }
+
+    fn read_dir(&mut self, dir_path: &str) -> Result<Vec<FileInfo>, &'static str> {
+        if self.lookup(dir_path)? != DevNode::Root {
+            return Err("La ruta no es un directorio");
+        }
+        Ok(self.nodes().iter().map(|(name, node)| self.vfs_file_info(name, *node)).collect())
+    }
+
+    fn stat(&mut self, file_path: &str) -> Result<FileInfo, &'static str> {
+        let node = self.lookup(file_path)?;
+        Ok(self.vfs_file_info(path_name(file_path), node))
+    }
+
+    fn unlink(&mut self, _file_path: &str) -> Result<(), &'static str> {
+        Err("Operación no soportada")
+    }
+
+    fn truncate(&mut self, _file_path: &str, _size: u64) -> Result<(), &'static str> {
+        Err("Operación no soportada")
+    }
+
+    fn rename(&mut self, _old_path: &str, _new_path: &str) -> Result<(), &'static str> {
+        Err("Operación no soportada")
+    }
+
+    fn sync(&mut self) -> Result<(), &'static str> {
+        // Las escrituras en los nodos de bloque pasan por la caché de bloques
+        if crate::drivers::storage::sync_storage(None) {
+            Ok(())
+        } else {
+            Err("Error al sincronizar el dispositivo")
+        }
+    }
+}
+
+/// Dispositivos de almacenamiento registrados con su nombre en /dev, por ID
+///
+/// Los discos se llaman `sda`, `sdb`, ... por orden de ID y sus particiones
+/// añaden el número de partición al nombre del disco (`sda1`, `sda5`, ...).
+pub fn block_devices() -> Vec<(u32, String)> {
+    let manager = match crate::drivers::storage::get_storage_manager() {
+        Some(manager) => manager,
+        None => return Vec::new(),
+    };
+
+    let mut disk_ids: Vec<u32> = manager
+        .devices
+        .iter()
+        .flatten()
+        .filter(|device| device.parent_device.is_none())
+        .map(|device| device.device_id)
+        .collect();
+    disk_ids.sort_unstable();
+
+    let mut names = Vec::new();
+    for (index, &disk_id) in disk_ids.iter().take(DEVFS_MAX_BLOCK_DEVICES).enumerate() {
+        let mut disk_name = String::from("sd");
+        disk_name.push((b'a' + index as u8) as char);
+
+        let mut partitions: Vec<(u32, u32)> = manager
+            .devices
+            .iter()
+            .flatten()
+            .filter(|device| device.parent_device == Some(disk_id))
+            .filter_map(|device| device.partition.map(|partition| (partition.number, device.device_id)))
+            .collect();
+        partitions.sort_unstable();
+
+        for (number, device_id) in partitions {
+            let mut name = disk_name.clone();
+            name.push_str(&super::procfs::decimal(number as u64));
+            names.push((device_id, name));
+        }
+        names.push((disk_id, disk_name));
+    }
+    names.sort_unstable_by_key(|&(device_id, _)| device_id);
+    names
+}
+
+/// Nombre en /dev de un dispositivo de almacenamiento
+pub fn block_device_name(device_id: u32) -> Option<String> {
+    block_devices().into_iter().find(|&(id, _)| id == device_id).map(|(_, name)| name)
+}
+
+/// Tamaño de sector y tamaño total en bytes de un dispositivo de almacenamiento
+fn block_geometry(device_id: u32) -> Option<(u32, u64)> {
+    let device = crate::drivers::storage::get_storage_manager()?.get_device_info(device_id)?;
+    if device.sector_size == 0 {
+        return None;
+    }
+    Some((device.sector_size, device.sector_count * device.sector_size as u64))
+}
+
+/// Leer bytes de un dispositivo de almacenamiento desde cualquier desplazamiento
+fn read_block_device(device_id: u32, offset: u64, buffer: &mut [u8]) -> Result<usize, &'static str> {
+    let (sector_size, size) = block_geometry(device_id).ok_or("Dispositivo no encontrado")?;
+    if offset >= size {
+        return Ok(0);
+    }
+    let length = core::cmp::min(buffer.len() as u64, size - offset) as usize;
+    let sector_size = sector_size as usize;
+    let mut sector = vec![0u8; sector_size];
+    let mut done = 0;
+
+    while done < length {
+        let position = offset + done as u64;
+        let within = (position % sector_size as u64) as usize;
+        if within == 0 && length - done >= sector_size {
+            // Sectores completos directamente sobre el buffer
+            let count = core::cmp::min((length - done) / sector_size, DEVFS_MAX_TRANSFER_SECTORS);
+            let chunk = &mut buffer[done..done + count * sector_size];
+            if !crate::drivers::storage::read_sectors(device_id, position / sector_size as u64, count as u32, chunk) {
+                return Err("Error de E/S en el dispositivo");
+            }
+            done += count * sector_size;
+        } else {
+            let chunk = core::cmp::min(sector_size - within, length - done);
+            if !crate::drivers::storage::read_sectors(device_id, position / sector_size as u64, 1, &mut sector) {
+                return Err("Error de E/S en el dispositivo");
+            }
+            buffer[done..done + chunk].copy_from_slice(&sector[within..within + chunk]);
+            done += chunk;
+        }
+    }
+    Ok(length)
+}
+
+/// Escribir bytes en un dispositivo de almacenamiento desde cualquier desplazamiento
+///
+/// Los sectores escritos de forma parcial se leen y se completan antes de escribirlos.
+fn write_block_device(device_id: u32, offset: u64, buffer: &[u8]) -> Result<usize, &'static str> {
+    let (sector_size, size) = block_geometry(device_id).ok_or("Dispositivo no encontrado")?;
+    if offset >= size {
+        return Err("No hay espacio en el dispositivo");
+    }
+    let length = core::cmp::min(buffer.len() as u64, size - offset) as usize;
+    let sector_size = sector_size as usize;
+    let mut sector = vec![0u8; sector_size];
+    let mut done = 0;
+
+    while done < length {
+        let position = offset + done as u64;
+        let within = (position % sector_size as u64) as usize;
+        if within == 0 && length - done >= sector_size {
+            let count = core::cmp::min((length - done) / sector_size, DEVFS_MAX_TRANSFER_SECTORS);
+            let chunk = &buffer[done..done + count * sector_size];
+            if !crate::drivers::storage::write_sectors(device_id, position / sector_size as u64, count as u32, chunk) {
+                return Err("Error de E/S en el dispositivo");
+            }
+            done += count * sector_size;
+        } else {
+            let chunk = core::cmp::min(sector_size - within, length - done);
+            let sector_number = position / sector_size as u64;
+            if !crate::drivers::storage::read_sectors(device_id, sector_number, 1, &mut sector) {
+                return Err("Error de E/S en el dispositivo");
+            }
+            sector[within..within + chunk].copy_from_slice(&buffer[done..done + chunk]);
+            if !crate::drivers::storage::write_sectors(device_id, sector_number, 1, &sector) {
+                return Err("Error de E/S en el dispositivo");
+            }
+            done += chunk;
+        }
+    }
+    Ok(length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::test_util::TestDisk;
+
+    /// Disco MBR de 1 MiB con una partición primaria (sectores 64..192) y
+    /// una lógica (sectores 320..448) dentro de una extendida (256..1024)
+    fn partitioned_disk() -> TestDisk {
+        fn entry(sector: &mut [u8], slot: usize, mbr_type: u8, start: u32, count: u32) {
+            let entry = &mut sector[446 + slot * 16..462 + slot * 16];
+            entry[4] = mbr_type;
+            entry[8..12].copy_from_slice(&start.to_le_bytes());
+            entry[12..16].copy_from_slice(&count.to_le_bytes());
+        }
+
+        let mut image = vec![0u8; 1024 * 1024];
+        entry(&mut image[..512], 0, 0x83, 64, 128);
+        entry(&mut image[..512], 1, 0x05, 256, 768);
+        entry(&mut image[256 * 512..257 * 512], 0, 0x83, 64, 128);
+        for sector in [0, 256] {
+            image[sector * 512 + 510] = 0x55;
+            image[sector * 512 + 511] = 0xAA;
+        }
+        for (i, byte) in image[64 * 512..192 * 512].iter_mut().enumerate() {
+            *byte = (i % 251) as u8;
+        }
+        TestDisk::with_data(image, 512)
+    }
+
+    #[test]
+    fn test_block_devices_named_by_disk_and_partition() {
+        let disk = partitioned_disk();
+        let partitions = disk.partitions();
+        assert_eq!(partitions.len(), 2);
+        assert_eq!(
+            block_devices(),
+            [
+                (disk.device_id, String::from("sda")),
+                (partitions[0], String::from("sda1")),
+                (partitions[1], String::from("sda5")),
+            ]
+        );
+        assert_eq!(block_device_name(partitions[1]).as_deref(), Some("sda5"));
+        assert_eq!(block_device_name(0), None);
+
+        let mut driver = DevFsDriver::new(0);
+        assert_eq!(driver.lookup("/sda5"), Ok(DevNode::Block(partitions[1])));
+        assert_eq!(driver.lookup("/sda2"), Err("Ruta no encontrada"));
+        assert_eq!(driver.lookup("/sda/1"), Err("Ruta no encontrada"));
+
+        let info = FileSystemDriver::stat(&mut driver, "/sda1").unwrap();
+        assert_eq!(info.node_type, NodeType::BlockDevice);
+        assert_eq!(info.size, 128 * 512);
+        assert_eq!(info.block_size, 512);
+        assert_eq!(info.block_count, 128);
+        assert_eq!(info.device, partitions[0]);
+
+        let listing = FileSystemDriver::read_dir(&mut driver, "/").unwrap();
+        let names: Vec<&str> = listing.iter().map(|entry| entry.name()).collect();
+        assert_eq!(names, ["sda", "sda1", "sda5", DEVFS_SERIAL_NAME]);
+    }
+
+    #[test]
+    fn test_block_node_unaligned_reads_and_writes() {
+        let disk = partitioned_disk();
+        let mut driver = DevFsDriver::new(0);
+
+        // Lectura que cruza sectores: el nodo de la partición empieza en su primer sector
+        let mut buffer = vec![0u8; 1500];
+        assert_eq!(FileSystemDriver::read_file(&mut driver, "/sda1", &mut buffer, 300), Ok(1500));
+        assert!(buffer.iter().enumerate().all(|(i, &byte)| byte == ((300 + i) % 251) as u8));
+
+        // Escritura parcial de dos sectores y un sector completo en medio
+        let data: Vec<u8> = (0..1100).map(|i| (i % 7) as u8 + 0xF0).collect();
+        assert_eq!(FileSystemDriver::write_file(&mut driver, "/sda1", &data, 500), Ok(1100));
+        let mut sectors = vec![0u8; 4 * 512];
+        disk.read(64, &mut sectors);
+        assert!(sectors[..500].iter().enumerate().all(|(i, &byte)| byte == (i % 251) as u8));
+        assert_eq!(&sectors[500..1600], &data[..]);
+        assert!(sectors[1600..].iter().enumerate().all(|(i, &byte)| byte == ((1600 + i) % 251) as u8));
+
+        // Transferencias mayores que DEVFS_MAX_TRANSFER_SECTORS sobre el disco entero
+        let mut whole = vec![0u8; (DEVFS_MAX_TRANSFER_SECTORS + 3) * 512];
+        assert_eq!(FileSystemDriver::read_file(&mut driver, "/sda", &mut whole, 64 * 512), Ok(whole.len()));
+        assert_eq!(&whole[500..1600], &data[..]);
+
+        // El final del dispositivo recorta las lecturas y rechaza las escrituras
+        assert_eq!(FileSystemDriver::read_file(&mut driver, "/sda1", &mut buffer, 128 * 512 - 10), Ok(10));
+        assert_eq!(FileSystemDriver::read_file(&mut driver, "/sda1", &mut buffer, 128 * 512), Ok(0));
+        assert_eq!(FileSystemDriver::write_file(&mut driver, "/sda1", &data, 128 * 512), Err("No hay espacio en el dispositivo"));
+        assert_eq!(FileSystemDriver::write_file(&mut driver, "/sda1", &data, 128 * 512 - 4), Ok(4));
+        assert_eq!(FileSystemDriver::sync(&mut driver), Ok(()));
+    }
+
+    #[test]
+    fn test_fixed_nodes() {
+        let mut driver = DevFsDriver::new(0);
+        assert_eq!(driver.lookup("/"), Ok(DevNode::Root));
+        assert_eq!(driver.lookup("/./ttyS0"), Ok(DevNode::Serial));
+        // Sin framebuffer no hay nodo fb0
+        assert_eq!(driver.lookup("/fb0"), Err("Ruta no encontrada"));
+
+        let mut buffer = [0u8; 4];
+        assert_eq!(FileSystemDriver::write_file(&mut driver, "/ttyS0", b"hola", 0), Ok(4));
+        assert_eq!(FileSystemDriver::read_file(&mut driver, "/ttyS0", &mut buffer, 0), Ok(0));
+        assert!(FileSystemDriver::read_file(&mut driver, "/", &mut buffer, 0).is_err());
+        assert!(FileSystemDriver::read_dir(&mut driver, "/ttyS0").is_err());
+
+        let serial = FileSystemDriver::stat(&mut driver, "/ttyS0").unwrap();
+        assert_eq!(serial.node_type, NodeType::CharDevice);
+        assert_eq!((serial.permissions.owner, serial.permissions.group, serial.permissions.others), (6, 6, 0));
+        assert_eq!(FileSystemDriver::stat(&mut driver, "/").unwrap().node_type, NodeType::Directory);
+
+        assert_eq!(FileSystemDriver::open(&mut driver, "/null", true).unwrap_err(), "Operación no soportada");
+        assert_eq!(FileSystemDriver::unlink(&mut driver, "/ttyS0"), Err("Operación no soportada"));
+        assert_eq!(FileSystemDriver::rename(&mut driver, "/ttyS0", "/ttyS1"), Err("Operación no soportada"));
+    }
+}
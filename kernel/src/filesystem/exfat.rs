@@ -0,0 +1,1626 @@
+//! Driver exFAT para ReactOS Rust Kernel
+//!
+//! Implementa un driver de lectura y escritura para exFAT, con soporte
+//! para el bitmap de asignación, la tabla de mayúsculas (up-case) y los
+//! checksums de los conjuntos de entradas de directorio.
+
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use alloc::{string::String, vec, vec::Vec};
+
+use super::fat32::{fat_datetime_to_unix, unix_to_fat_datetime, FAT32_ATTR_ARCHIVE, FAT32_ATTR_DIRECTORY, FAT32_ATTR_READ_ONLY};
+use super::path::split_parent_at;
+use super::vfs::{FileInfo, FilePermissions, FileSystemDriver, NodeType};
+
+/// Estructura del sector de arranque exFAT
+#[derive(Debug, Clone, Copy)]
+#[repr(C, packed)]
+pub struct ExFatBootSector {
+    /// Código de salto
+    pub jump_instruction: [u8; 3],
+    /// Nombre del sistema de archivos ("EXFAT   ")
+    pub fs_name: [u8; 8],
+    /// Debe ser cero (ocupa el lugar del BPB de FAT)
+    pub must_be_zero: [u8; 53],
+    /// Sector de inicio de la partición
+    pub partition_offset: u64,
+    /// Tamaño del volumen en sectores
+    pub volume_length: u64,
+    /// Primer sector de la FAT
+    pub fat_offset: u32,
+    /// Sectores por FAT
+    pub fat_length: u32,
+    /// Primer sector del área de clústeres
+    pub cluster_heap_offset: u32,
+    /// Número de clústeres
+    pub cluster_count: u32,
+    /// Primer clúster del directorio raíz
+    pub first_cluster_of_root: u32,
+    /// Número de serie del volumen
+    pub volume_serial: u32,
+    /// Revisión del sistema de archivos
+    pub fs_revision: u16,
+    /// Flags del volumen
+    pub volume_flags: u16,
+    /// log2 de bytes por sector
+    pub bytes_per_sector_shift: u8,
+    /// log2 de sectores por clúster
+    pub sectors_per_cluster_shift: u8,
+    /// Número de FATs
+    pub number_of_fats: u8,
+    /// Unidad BIOS
+    pub drive_select: u8,
+    /// Porcentaje de uso
+    pub percent_in_use: u8,
+    /// Reservado
+    pub reserved: [u8; 7],
+    /// Código de arranque
+    pub boot_code: [u8; 390],
+    /// Firma del sector de arranque
+    pub boot_signature: u16,
+}
+
+/// Tipos de entradas de directorio exFAT
+pub const EXFAT_ENTRY_END: u8 = 0x00;
+pub const EXFAT_ENTRY_ALLOCATION_BITMAP: u8 = 0x81;
+pub const EXFAT_ENTRY_UPCASE_TABLE: u8 = 0x82;
+pub const EXFAT_ENTRY_VOLUME_LABEL: u8 = 0x83;
+pub const EXFAT_ENTRY_FILE: u8 = 0x85;
+pub const EXFAT_ENTRY_STREAM_EXTENSION: u8 = 0xC0;
+pub const EXFAT_ENTRY_FILE_NAME: u8 = 0xC1;
+/// Bit "en uso" del tipo de entrada
+pub const EXFAT_ENTRY_IN_USE: u8 = 0x80;
+
+/// Flags de la extensión de flujo
+pub const EXFAT_FLAG_ALLOCATION_POSSIBLE: u8 = 0x01;
+pub const EXFAT_FLAG_NO_FAT_CHAIN: u8 = 0x02;
+
+/// Flags del volumen
+pub const EXFAT_VOLUME_ACTIVE_FAT: u16 = 0x0001;
+pub const EXFAT_VOLUME_DIRTY: u16 = 0x0002;
+
+/// Valores especiales de la FAT exFAT
+pub const EXFAT_FREE_CLUSTER: u32 = 0x00000000;
+pub const EXFAT_BAD_CLUSTER: u32 = 0xFFFFFFF7;
+pub const EXFAT_END_OF_CHAIN: u32 = 0xFFFFFFFF;
+
+/// Tamaño de una entrada de directorio
+pub const EXFAT_DIRECTORY_ENTRY_SIZE: usize = 32;
+/// Caracteres UTF-16 por entrada de nombre
+pub const EXFAT_NAME_CHARS_PER_ENTRY: usize = 15;
+/// Longitud máxima de un nombre en unidades UTF-16
+pub const EXFAT_MAX_NAME_LENGTH: usize = 255;
+
+/// Estados del driver exFAT
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExFatState {
+    /// No inicializado
+    Uninitialized,
+    /// Inicializado
+    Initialized,
+    /// Montado
+    Mounted,
+    /// Con error
+    Error,
+}
+
+/// Posición de una entrada dentro de un directorio
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExFatEntryLocation {
+    /// Clúster del directorio que contiene la entrada
+    pub cluster: u32,
+    /// Índice de la entrada dentro del clúster
+    pub index: u32,
+}
+
+/// Flujo de datos (contenido de un archivo o directorio)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExFatStream {
+    /// Primer clúster (0 si no tiene clústeres)
+    pub first_cluster: u32,
+    /// Tamaño de los datos en bytes
+    pub data_length: u64,
+    /// Bytes con datos válidos; más allá se leen ceros
+    pub valid_data_length: u64,
+    /// Los clústeres son contiguos y no se usa la FAT
+    pub no_fat_chain: bool,
+}
+
+/// Conjunto de entradas de un archivo o directorio
+#[derive(Debug, Clone)]
+pub struct ExFatDirEntry {
+    /// Posición de la entrada primaria (tipo 0x85)
+    pub location: ExFatEntryLocation,
+    /// Posición de cada entrada del conjunto (puede cruzar clústeres)
+    pub slots: Vec<ExFatEntryLocation>,
+    /// Entradas en bruto del conjunto (primaria + secundarias)
+    pub entries: Vec<[u8; 32]>,
+    /// Nombre del archivo
+    pub name: String,
+    /// Atributos (mismos valores que FAT)
+    pub attributes: u16,
+    /// Flujo de datos
+    pub stream: ExFatStream,
+    /// Marca de tiempo de creación
+    pub create_timestamp: u32,
+    /// Marca de tiempo de modificación
+    pub modify_timestamp: u32,
+    /// Marca de tiempo de acceso
+    pub access_timestamp: u32,
+}
+
+impl ExFatDirEntry {
+    /// Verificar si la entrada es un directorio
+    pub fn is_directory(&self) -> bool {
+        self.attributes & FAT32_ATTR_DIRECTORY as u16 != 0
+    }
+
+    /// Tamaño del archivo en bytes
+    pub fn size(&self) -> u64 {
+        self.stream.data_length
+    }
+
+    /// Volcar los campos decodificados en las entradas en bruto y recalcular el checksum
+    fn encode(&mut self) {
+        let primary = &mut self.entries[0];
+        primary[4..6].copy_from_slice(&self.attributes.to_le_bytes());
+        primary[8..12].copy_from_slice(&self.create_timestamp.to_le_bytes());
+        primary[12..16].copy_from_slice(&self.modify_timestamp.to_le_bytes());
+        primary[16..20].copy_from_slice(&self.access_timestamp.to_le_bytes());
+
+        let stream = &mut self.entries[1];
+        let mut flags = stream[1] & !(EXFAT_FLAG_NO_FAT_CHAIN | EXFAT_FLAG_ALLOCATION_POSSIBLE);
+        flags |= EXFAT_FLAG_ALLOCATION_POSSIBLE;
+        if self.stream.no_fat_chain && self.stream.first_cluster != 0 {
+            flags |= EXFAT_FLAG_NO_FAT_CHAIN;
+        }
+        stream[1] = flags;
+        stream[8..16].copy_from_slice(&self.stream.valid_data_length.to_le_bytes());
+        stream[20..24].copy_from_slice(&self.stream.first_cluster.to_le_bytes());
+        stream[24..32].copy_from_slice(&self.stream.data_length.to_le_bytes());
+
+        let checksum = entry_set_checksum(&self.entries);
+        self.entries[0][2..4].copy_from_slice(&checksum.to_le_bytes());
+    }
+}
+
+/// Calcular el checksum de un conjunto de entradas (se omite el propio campo de checksum)
+pub fn entry_set_checksum(entries: &[[u8; 32]]) -> u16 {
+    let mut checksum: u16 = 0;
+    for (i, entry) in entries.iter().enumerate() {
+        for (j, &byte) in entry.iter().enumerate() {
+            if i == 0 && (j == 2 || j == 3) {
+                continue;
+            }
+            checksum = checksum.rotate_right(1).wrapping_add(byte as u16);
+        }
+    }
+    checksum
+}
+
+/// Calcular el hash de un nombre ya convertido a mayúsculas
+pub fn name_hash(upcased_name: &[u16]) -> u16 {
+    let mut hash: u16 = 0;
+    for &unit in upcased_name {
+        hash = hash.rotate_right(1).wrapping_add(unit & 0xFF);
+        hash = hash.rotate_right(1).wrapping_add(unit >> 8);
+    }
+    hash
+}
+
+/// Calcular el checksum de la tabla up-case o del sector de arranque
+pub fn table_checksum(data: &[u8]) -> u32 {
+    let mut checksum: u32 = 0;
+    for &byte in data {
+        checksum = checksum.rotate_right(1).wrapping_add(byte as u32);
+    }
+    checksum
+}
+
+/// Convertir segundos desde la época Unix a una marca de tiempo exFAT
+pub fn unix_to_exfat_timestamp(unix_seconds: u64) -> u32 {
+    let (date, time) = unix_to_fat_datetime(unix_seconds);
+    ((date as u32) << 16) | time as u32
+}
+
+/// Convertir una marca de tiempo exFAT a segundos desde la época Unix
+pub fn exfat_timestamp_to_unix(timestamp: u32) -> u64 {
+    fat_datetime_to_unix((timestamp >> 16) as u16, timestamp as u16)
+}
+
+/// Estructura del driver exFAT
+pub struct ExFatDriver {
+    /// ID del driver
+    pub driver_id: u32,
+    /// Estado del driver
+    pub state: ExFatState,
+    /// Sector de arranque
+    pub boot_sector: ExFatBootSector,
+    /// Bytes por sector
+    pub bytes_per_sector: u32,
+    /// Sectores por clúster
+    pub sectors_per_cluster: u32,
+    /// Tamaño del clúster en bytes
+    pub cluster_size: u32,
+    /// Número total de clústeres
+    pub cluster_count: u32,
+    /// Primer clúster del directorio raíz
+    pub root_cluster: u32,
+    /// Flujo del bitmap de asignación
+    pub bitmap_stream: ExFatStream,
+    /// Bitmap de asignación en memoria (un bit por clúster)
+    pub allocation_bitmap: Vec<u8>,
+    /// Tabla de conversión a mayúsculas
+    pub upcase_table: Vec<u16>,
+    /// Número de clústeres libres
+    pub free_clusters: AtomicU32,
+    /// Pista del siguiente clúster libre
+    pub next_free_hint: u32,
+    /// Dispositivo de bloque asociado
+    pub block_device: u32,
+    /// Contador de operaciones de E/S
+    pub io_counter: AtomicUsize,
+}
+
+impl ExFatDriver {
+    /// Crear un nuevo driver exFAT
+    pub fn new(driver_id: u32, block_device: u32) -> Self {
+        Self {
+            driver_id,
+            state: ExFatState::Uninitialized,
+            boot_sector: ExFatBootSector {
+                jump_instruction: [0xEB, 0x76, 0x90],
+                fs_name: *b"EXFAT   ",
+                must_be_zero: [0; 53],
+                partition_offset: 0,
+                volume_length: 0,
+                fat_offset: 0,
+                fat_length: 0,
+                cluster_heap_offset: 0,
+                cluster_count: 0,
+                first_cluster_of_root: 0,
+                volume_serial: 0,
+                fs_revision: 0x0100,
+                volume_flags: 0,
+                bytes_per_sector_shift: 9,
+                sectors_per_cluster_shift: 0,
+                number_of_fats: 1,
+                drive_select: 0x80,
+                percent_in_use: 0xFF,
+                reserved: [0; 7],
+                boot_code: [0; 390],
+                boot_signature: 0xAA55,
+            },
+            bytes_per_sector: 512,
+            sectors_per_cluster: 1,
+            cluster_size: 512,
+            cluster_count: 0,
+            root_cluster: 0,
+            bitmap_stream: ExFatStream {
+                first_cluster: 0,
+                data_length: 0,
+                valid_data_length: 0,
+                no_fat_chain: false,
+            },
+            allocation_bitmap: Vec::new(),
+            upcase_table: Vec::new(),
+            free_clusters: AtomicU32::new(0),
+            next_free_hint: 2,
+            block_device,
+            io_counter: AtomicUsize::new(0),
+        }
+    }
+
+    /// Inicializar el driver exFAT
+    pub fn initialize(&mut self) -> Result<(), &'static str> {
+        // Leer el sector de arranque
+        let mut boot_sector_data = [0u8; 512];
+        if !self.read_sector(0, &mut boot_sector_data) {
+            return Err("Error leyendo sector de arranque");
+        }
+
+        // Copiar datos del sector de arranque
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                boot_sector_data.as_ptr(),
+                &mut self.boot_sector as *mut ExFatBootSector as *mut u8,
+                core::mem::size_of::<ExFatBootSector>(),
+            );
+        }
+
+        // Verificar la firma y el nombre del sistema de archivos
+        if self.boot_sector.boot_signature != 0xAA55 {
+            return Err("Firma de sector de arranque inválida");
+        }
+        if &self.boot_sector.fs_name != b"EXFAT   " {
+            return Err("No es un sistema de archivos exFAT");
+        }
+        if self.boot_sector.must_be_zero.iter().any(|&b| b != 0) {
+            return Err("Sector de arranque exFAT inválido");
+        }
+
+        let bytes_per_sector_shift = self.boot_sector.bytes_per_sector_shift as u32;
+        let sectors_per_cluster_shift = self.boot_sector.sectors_per_cluster_shift as u32;
+        if !(9..=12).contains(&bytes_per_sector_shift) || bytes_per_sector_shift + sectors_per_cluster_shift > 25 {
+            return Err("Geometría exFAT inválida");
+        }
+
+        // Calcular parámetros del sistema de archivos
+        self.bytes_per_sector = 1 << bytes_per_sector_shift;
+        self.sectors_per_cluster = 1 << sectors_per_cluster_shift;
+        self.cluster_size = self.bytes_per_sector * self.sectors_per_cluster;
+        self.cluster_count = self.boot_sector.cluster_count;
+        self.root_cluster = self.boot_sector.first_cluster_of_root;
+
+        if !self.is_valid_cluster(self.root_cluster) {
+            return Err("Clúster raíz exFAT inválido");
+        }
+
+        self.verify_boot_checksum()?;
+        self.load_metadata()?;
+
+        self.state = ExFatState::Initialized;
+        Ok(())
+    }
+
+    /// Verificar el checksum de la región de arranque (sectores 0 a 10)
+    fn verify_boot_checksum(&mut self) -> Result<(), &'static str> {
+        let bytes_per_sector = self.bytes_per_sector as usize;
+        let mut region = vec![0u8; bytes_per_sector * 11];
+        for i in 0..11 {
+            if !self.read_sector(i as u32, &mut region[i * bytes_per_sector..(i + 1) * bytes_per_sector]) {
+                return Err("Error leyendo región de arranque");
+            }
+        }
+
+        // VolumeFlags y PercentInUse no forman parte del checksum
+        let mut checksum: u32 = 0;
+        for (i, &byte) in region.iter().enumerate() {
+            if i == 106 || i == 107 || i == 112 {
+                continue;
+            }
+            checksum = checksum.rotate_right(1).wrapping_add(byte as u32);
+        }
+
+        let mut checksum_sector = vec![0u8; bytes_per_sector];
+        if !self.read_sector(11, &mut checksum_sector) {
+            return Err("Error leyendo sector de checksum");
+        }
+        let stored = u32::from_le_bytes([checksum_sector[0], checksum_sector[1], checksum_sector[2], checksum_sector[3]]);
+        if stored != checksum {
+            return Err("Checksum de la región de arranque exFAT inválido");
+        }
+        Ok(())
+    }
+
+    /// Cargar el bitmap de asignación y la tabla up-case desde el directorio raíz
+    fn load_metadata(&mut self) -> Result<(), &'static str> {
+        let root = self.root_stream();
+        let clusters = self.stream_clusters(&root)?;
+        let mut cluster_buffer = vec![0u8; self.cluster_size as usize];
+        let mut bitmap = None;
+        let mut upcase = None;
+
+        'scan: for &cluster in &clusters {
+            if !self.read_cluster(cluster, &mut cluster_buffer) {
+                return Err("Error leyendo directorio raíz");
+            }
+            for entry in cluster_buffer.chunks(EXFAT_DIRECTORY_ENTRY_SIZE) {
+                let stream = ExFatStream {
+                    first_cluster: u32::from_le_bytes([entry[20], entry[21], entry[22], entry[23]]),
+                    data_length: read_u64(entry, 24),
+                    valid_data_length: read_u64(entry, 24),
+                    no_fat_chain: false,
+                };
+                match entry[0] {
+                    EXFAT_ENTRY_END => break 'scan,
+                    // Con dos FATs (TexFAT) se usa el bitmap de la FAT activa
+                    EXFAT_ENTRY_ALLOCATION_BITMAP
+                        if (entry[1] & 1) as u16 == self.boot_sector.volume_flags & EXFAT_VOLUME_ACTIVE_FAT
+                            || self.boot_sector.number_of_fats == 1 =>
+                    {
+                        bitmap = Some(stream)
+                    }
+                    EXFAT_ENTRY_UPCASE_TABLE => {
+                        let checksum = u32::from_le_bytes([entry[4], entry[5], entry[6], entry[7]]);
+                        upcase = Some((stream, checksum));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        // Bitmap de asignación
+        let bitmap_stream = bitmap.ok_or("Bitmap de asignación no encontrado")?;
+        if bitmap_stream.data_length < (self.cluster_count as u64 + 7) / 8 {
+            return Err("Bitmap de asignación demasiado pequeño");
+        }
+        let mut bitmap_data = vec![0u8; bitmap_stream.data_length as usize];
+        self.read_stream(&bitmap_stream, 0, &mut bitmap_data)?;
+        self.bitmap_stream = bitmap_stream;
+        self.allocation_bitmap = bitmap_data;
+
+        let mut free = 0;
+        for cluster in 2..self.cluster_count + 2 {
+            if !self.is_cluster_allocated(cluster) {
+                free += 1;
+            }
+        }
+        self.free_clusters.store(free, Ordering::SeqCst);
+
+        // Tabla up-case (comprimida: 0xFFFF seguido de un número de caracteres idénticos)
+        let (upcase_stream, expected_checksum) = upcase.ok_or("Tabla up-case no encontrada")?;
+        let mut upcase_data = vec![0u8; upcase_stream.data_length as usize];
+        self.read_stream(&upcase_stream, 0, &mut upcase_data)?;
+        if table_checksum(&upcase_data) != expected_checksum {
+            return Err("Checksum de la tabla up-case inválido");
+        }
+
+        let mut table: Vec<u16> = (0..=0xFFFFu32).map(|c| c as u16).collect();
+        let mut position = 0usize;
+        let mut units = upcase_data.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]]));
+        while let Some(unit) = units.next() {
+            if position > 0xFFFF {
+                break;
+            }
+            if unit == 0xFFFF {
+                position += units.next().unwrap_or(0) as usize;
+            } else {
+                table[position] = unit;
+                position += 1;
+            }
+        }
+        self.upcase_table = table;
+
+        Ok(())
+    }
+
+    /// Montar el sistema de archivos
+    pub fn mount(&mut self) -> Result<(), &'static str> {
+        if self.state != ExFatState::Initialized {
+            return Err("Driver no inicializado");
+        }
+
+        self.state = ExFatState::Mounted;
+        Ok(())
+    }
+
+    /// Desmontar el sistema de archivos
+    pub fn unmount(&mut self) -> Result<(), &'static str> {
+        if self.state != ExFatState::Mounted {
+            return Err("Sistema de archivos no montado");
+        }
+
+        self.state = ExFatState::Initialized;
+        Ok(())
+    }
+
+    /// Leer un sector del dispositivo
+    pub fn read_sector(&mut self, sector: u32, buffer: &mut [u8]) -> bool {
+        self.io_counter.fetch_add(1, Ordering::SeqCst);
+        crate::drivers::storage::read_sectors(self.block_device, sector as u64, 1, buffer)
+    }
+
+    /// Escribir un sector al dispositivo
+    pub fn write_sector(&mut self, sector: u32, buffer: &[u8]) -> bool {
+        self.io_counter.fetch_add(1, Ordering::SeqCst);
+        crate::drivers::storage::write_sectors(self.block_device, sector as u64, 1, buffer)
+    }
+
+    /// Verificar si un número de clúster está dentro del área de datos
+    pub fn is_valid_cluster(&self, cluster: u32) -> bool {
+        cluster >= 2 && cluster < self.cluster_count + 2
+    }
+
+    /// Primer sector de un clúster
+    fn cluster_to_sector(&self, cluster: u32) -> u32 {
+        self.boot_sector.cluster_heap_offset + (cluster - 2) * self.sectors_per_cluster
+    }
+
+    /// Leer un clúster del sistema de archivos
+    pub fn read_cluster(&mut self, cluster: u32, buffer: &mut [u8]) -> bool {
+        if !self.is_valid_cluster(cluster) || buffer.len() < self.cluster_size as usize {
+            return false;
+        }
+
+        let sector = self.cluster_to_sector(cluster);
+        let bytes_per_sector = self.bytes_per_sector as usize;
+        for i in 0..self.sectors_per_cluster {
+            let start = i as usize * bytes_per_sector;
+            if !self.read_sector(sector + i, &mut buffer[start..start + bytes_per_sector]) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Escribir un clúster al sistema de archivos
+    pub fn write_cluster(&mut self, cluster: u32, buffer: &[u8]) -> bool {
+        if !self.is_valid_cluster(cluster) || buffer.len() < self.cluster_size as usize {
+            return false;
+        }
+
+        let sector = self.cluster_to_sector(cluster);
+        let bytes_per_sector = self.bytes_per_sector as usize;
+        for i in 0..self.sectors_per_cluster {
+            let start = i as usize * bytes_per_sector;
+            if !self.write_sector(sector + i, &buffer[start..start + bytes_per_sector]) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Primer sector de la FAT activa
+    fn active_fat_start(&self) -> u32 {
+        let active_fat = if self.boot_sector.number_of_fats > 1 {
+            (self.boot_sector.volume_flags & EXFAT_VOLUME_ACTIVE_FAT) as u32
+        } else {
+            0
+        };
+        self.boot_sector.fat_offset + active_fat * self.boot_sector.fat_length
+    }
+
+    /// Leer una entrada de la FAT
+    pub fn read_fat_entry(&mut self, cluster: u32) -> Option<u32> {
+        if cluster >= self.cluster_count + 2 {
+            return None;
+        }
+
+        let bytes_per_sector = self.bytes_per_sector;
+        let fat_sector = self.active_fat_start() + (cluster * 4) / bytes_per_sector;
+        let offset = ((cluster * 4) % bytes_per_sector) as usize;
+
+        let mut sector_buffer = vec![0u8; bytes_per_sector as usize];
+        if !self.read_sector(fat_sector, &mut sector_buffer) {
+            return None;
+        }
+        Some(u32::from_le_bytes([
+            sector_buffer[offset],
+            sector_buffer[offset + 1],
+            sector_buffer[offset + 2],
+            sector_buffer[offset + 3],
+        ]))
+    }
+
+    /// Escribir una entrada en la FAT activa
+    pub fn write_fat_entry(&mut self, cluster: u32, value: u32) -> bool {
+        if cluster >= self.cluster_count + 2 {
+            return false;
+        }
+
+        let bytes_per_sector = self.bytes_per_sector;
+        let fat_sector = self.active_fat_start() + (cluster * 4) / bytes_per_sector;
+        let offset = ((cluster * 4) % bytes_per_sector) as usize;
+
+        let mut sector_buffer = vec![0u8; bytes_per_sector as usize];
+        if !self.read_sector(fat_sector, &mut sector_buffer) {
+            return false;
+        }
+        sector_buffer[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        self.write_sector(fat_sector, &sector_buffer)
+    }
+
+    /// Verificar en el bitmap si un clúster está asignado
+    pub fn is_cluster_allocated(&self, cluster: u32) -> bool {
+        let bit = (cluster - 2) as usize;
+        self.allocation_bitmap
+            .get(bit / 8)
+            .map(|byte| byte & (1 << (bit % 8)) != 0)
+            .unwrap_or(true)
+    }
+
+    /// Marcar un clúster en el bitmap y escribir el sector afectado
+    fn set_cluster_allocated(&mut self, cluster: u32, allocated: bool) -> Result<(), &'static str> {
+        let bit = (cluster - 2) as usize;
+        let byte_index = bit / 8;
+        if byte_index >= self.allocation_bitmap.len() {
+            return Err("Clúster fuera del bitmap");
+        }
+
+        let was_allocated = self.allocation_bitmap[byte_index] & (1 << (bit % 8)) != 0;
+        if was_allocated == allocated {
+            return Ok(());
+        }
+        if allocated {
+            self.allocation_bitmap[byte_index] |= 1 << (bit % 8);
+            self.free_clusters.fetch_sub(1, Ordering::SeqCst);
+        } else {
+            self.allocation_bitmap[byte_index] &= !(1 << (bit % 8));
+            self.free_clusters.fetch_add(1, Ordering::SeqCst);
+        }
+
+        // Escribir solo el sector del bitmap que contiene el bit
+        let bytes_per_sector = self.bytes_per_sector as usize;
+        let sector_start = byte_index / bytes_per_sector * bytes_per_sector;
+        let sector_end = core::cmp::min(sector_start + bytes_per_sector, self.allocation_bitmap.len());
+        let data = self.allocation_bitmap[sector_start..sector_end].to_vec();
+        let stream = self.bitmap_stream;
+        self.write_stream(&stream, sector_start as u64, &data)
+    }
+
+    /// Flujo del directorio raíz (siempre encadenado por la FAT)
+    fn root_stream(&self) -> ExFatStream {
+        ExFatStream {
+            first_cluster: self.root_cluster,
+            data_length: 0,
+            valid_data_length: 0,
+            no_fat_chain: false,
+        }
+    }
+
+    /// Obtener los clústeres de un flujo
+    ///
+    /// Con NoFatChain los clústeres son contiguos; si no, se sigue la FAT.
+    /// Un `data_length` de 0 en un flujo encadenado indica "hasta el fin de cadena".
+    pub fn stream_clusters(&mut self, stream: &ExFatStream) -> Result<Vec<u32>, &'static str> {
+        let mut clusters = Vec::new();
+        if stream.first_cluster == 0 {
+            return Ok(clusters);
+        }
+        if !self.is_valid_cluster(stream.first_cluster) {
+            return Err("Clúster inicial inválido");
+        }
+
+        let cluster_size = self.cluster_size as u64;
+        let count = ((stream.data_length + cluster_size - 1) / cluster_size) as u32;
+
+        if stream.no_fat_chain {
+            if !self.is_valid_cluster(stream.first_cluster + count.saturating_sub(1)) {
+                return Err("Flujo contiguo fuera del volumen");
+            }
+            clusters.extend(stream.first_cluster..stream.first_cluster + count);
+            return Ok(clusters);
+        }
+
+        let mut current = stream.first_cluster;
+        loop {
+            clusters.push(current);
+            if clusters.len() > self.cluster_count as usize {
+                return Err("Cadena de clústeres cíclica");
+            }
+            if stream.data_length != 0 && clusters.len() as u32 >= count {
+                break;
+            }
+            match self.read_fat_entry(current) {
+                Some(EXFAT_END_OF_CHAIN) => break,
+                Some(next) if self.is_valid_cluster(next) => current = next,
+                _ => return Err("Cadena de clústeres corrupta"),
+            }
+        }
+
+        Ok(clusters)
+    }
+
+    /// Leer datos de un flujo a partir de un desplazamiento
+    ///
+    /// Los bytes más allá de `valid_data_length` se devuelven como ceros.
+    pub fn read_stream(&mut self, stream: &ExFatStream, offset: u64, buffer: &mut [u8]) -> Result<usize, &'static str> {
+        if offset >= stream.data_length {
+            return Ok(0);
+        }
+
+        let end = core::cmp::min(offset + buffer.len() as u64, stream.data_length);
+        let clusters = self.stream_clusters(stream)?;
+        let cluster_size = self.cluster_size as u64;
+        let mut cluster_buffer = vec![0u8; cluster_size as usize];
+        let mut position = offset;
+
+        while position < end {
+            let cluster_index = (position / cluster_size) as usize;
+            let cluster_offset = (position % cluster_size) as usize;
+            let chunk = core::cmp::min(cluster_size - cluster_offset as u64, end - position) as usize;
+            let out = (position - offset) as usize;
+
+            if position >= stream.valid_data_length {
+                buffer[out..out + chunk].fill(0);
+            } else {
+                let cluster = *clusters.get(cluster_index).ok_or("Flujo más corto que su tamaño")?;
+                if !self.read_cluster(cluster, &mut cluster_buffer) {
+                    return Err("Error leyendo clúster");
+                }
+                buffer[out..out + chunk].copy_from_slice(&cluster_buffer[cluster_offset..cluster_offset + chunk]);
+
+                // Poner a cero la parte posterior a los datos válidos
+                let valid_end = stream.valid_data_length.saturating_sub(position) as usize;
+                if valid_end < chunk {
+                    buffer[out + valid_end..out + chunk].fill(0);
+                }
+            }
+            position += chunk as u64;
+        }
+
+        Ok((end - offset) as usize)
+    }
+
+    /// Escribir datos dentro de los clústeres ya asignados a un flujo
+    fn write_stream(&mut self, stream: &ExFatStream, offset: u64, data: &[u8]) -> Result<(), &'static str> {
+        let clusters = self.stream_clusters(stream)?;
+        let cluster_size = self.cluster_size as u64;
+        let mut cluster_buffer = vec![0u8; cluster_size as usize];
+        let end = offset + data.len() as u64;
+        let mut position = offset;
+
+        while position < end {
+            let cluster_index = (position / cluster_size) as usize;
+            let cluster_offset = (position % cluster_size) as usize;
+            let chunk = core::cmp::min(cluster_size - cluster_offset as u64, end - position) as usize;
+            let cluster = *clusters.get(cluster_index).ok_or("Escritura fuera del flujo")?;
+
+            if chunk != cluster_size as usize && !self.read_cluster(cluster, &mut cluster_buffer) {
+                return Err("Error leyendo clúster");
+            }
+            let input = (position - offset) as usize;
+            cluster_buffer[cluster_offset..cluster_offset + chunk].copy_from_slice(&data[input..input + chunk]);
+            if !self.write_cluster(cluster, &cluster_buffer) {
+                return Err("Error escribiendo clúster");
+            }
+            position += chunk as u64;
+        }
+
+        Ok(())
+    }
+
+    /// Buscar un clúster libre en el bitmap empezando por `start`
+    fn find_free_cluster(&self, start: u32) -> Option<u32> {
+        let start = if self.is_valid_cluster(start) { start } else { 2 };
+        (start..self.cluster_count + 2)
+            .chain(2..start)
+            .find(|&cluster| !self.is_cluster_allocated(cluster))
+    }
+
+    /// Convertir un flujo contiguo en una cadena de FAT explícita
+    fn materialize_fat_chain(&mut self, stream: &mut ExFatStream) -> Result<(), &'static str> {
+        if !stream.no_fat_chain {
+            return Ok(());
+        }
+
+        let clusters = self.stream_clusters(stream)?;
+        for pair in clusters.windows(2) {
+            if !self.write_fat_entry(pair[0], pair[1]) {
+                return Err("Error escribiendo la FAT");
+            }
+        }
+        if let Some(&last) = clusters.last() {
+            if !self.write_fat_entry(last, EXFAT_END_OF_CHAIN) {
+                return Err("Error escribiendo la FAT");
+            }
+        }
+        stream.no_fat_chain = false;
+        Ok(())
+    }
+
+    /// Asignar clústeres hasta que el flujo tenga al menos `cluster_count` clústeres
+    ///
+    /// Se intenta mantener el flujo contiguo; si no es posible se pasa a cadena FAT.
+    /// Los clústeres nuevos se rellenan con ceros.
+    fn grow_stream(&mut self, stream: &mut ExFatStream, cluster_count: usize) -> Result<(), &'static str> {
+        let mut clusters = self.stream_clusters(stream)?;
+        if clusters.is_empty() && cluster_count > 0 {
+            // Flujo vacío: empezar una cadena contigua
+            stream.no_fat_chain = true;
+        }
+
+        while clusters.len() < cluster_count {
+            let preferred = clusters.last().map(|&c| c + 1).unwrap_or(self.next_free_hint);
+            let cluster = self.find_free_cluster(preferred).ok_or("No hay clústeres libres")?;
+
+            let contiguous = clusters.last().map(|&c| c + 1 == cluster).unwrap_or(true);
+            if !contiguous && stream.no_fat_chain {
+                // El hueco contiguo se acabó: encadenar explícitamente por la FAT
+                let mut current = *stream;
+                current.data_length = clusters.len() as u64 * self.cluster_size as u64;
+                self.materialize_fat_chain(&mut current)?;
+                stream.no_fat_chain = false;
+            }
+
+            self.set_cluster_allocated(cluster, true)?;
+            let zeros = vec![0u8; self.cluster_size as usize];
+            if !self.write_cluster(cluster, &zeros) {
+                return Err("Error inicializando clúster");
+            }
+
+            if !stream.no_fat_chain {
+                if !self.write_fat_entry(cluster, EXFAT_END_OF_CHAIN) {
+                    return Err("Error escribiendo la FAT");
+                }
+                if let Some(&last) = clusters.last() {
+                    if !self.write_fat_entry(last, cluster) {
+                        return Err("Error escribiendo la FAT");
+                    }
+                }
+            }
+
+            if clusters.is_empty() {
+                stream.first_cluster = cluster;
+            }
+            clusters.push(cluster);
+            self.next_free_hint = cluster + 1;
+        }
+
+        Ok(())
+    }
+
+    /// Liberar los clústeres de un flujo a partir del índice `keep`
+    fn shrink_stream(&mut self, stream: &mut ExFatStream, keep: usize) -> Result<(), &'static str> {
+        let clusters = self.stream_clusters(stream)?;
+        if clusters.len() <= keep {
+            return Ok(());
+        }
+
+        for &cluster in &clusters[keep..] {
+            self.set_cluster_allocated(cluster, false)?;
+            if !stream.no_fat_chain && !self.write_fat_entry(cluster, EXFAT_FREE_CLUSTER) {
+                return Err("Error escribiendo la FAT");
+            }
+        }
+
+        if keep == 0 {
+            stream.first_cluster = 0;
+            stream.no_fat_chain = false;
+        } else if !stream.no_fat_chain && !self.write_fat_entry(clusters[keep - 1], EXFAT_END_OF_CHAIN) {
+            return Err("Error escribiendo la FAT");
+        }
+        Ok(())
+    }
+
+    /// Convertir un nombre a mayúsculas con la tabla up-case del volumen
+    pub fn upcase_name(&self, name: &[u16]) -> Vec<u16> {
+        name.iter()
+            .map(|&unit| self.upcase_table.get(unit as usize).copied().unwrap_or(unit))
+            .collect()
+    }
+
+    /// Leer las entradas de un directorio como conjuntos de entradas válidos
+    ///
+    /// Se omiten las entradas libres, las de metadatos (bitmap, up-case,
+    /// etiqueta) y los conjuntos con checksum incorrecto.
+    pub fn read_dir_stream(&mut self, directory: &ExFatStream) -> Result<Vec<ExFatDirEntry>, &'static str> {
+        let clusters = self.stream_clusters(directory)?;
+        let entries_per_cluster = self.cluster_size as usize / EXFAT_DIRECTORY_ENTRY_SIZE;
+
+        // Cargar todas las entradas en bruto con su posición
+        let mut raw: Vec<([u8; 32], ExFatEntryLocation)> = Vec::new();
+        let mut cluster_buffer = vec![0u8; self.cluster_size as usize];
+        'load: for &cluster in &clusters {
+            if !self.read_cluster(cluster, &mut cluster_buffer) {
+                return Err("Error leyendo clúster de directorio");
+            }
+            for i in 0..entries_per_cluster {
+                let mut entry = [0u8; 32];
+                entry.copy_from_slice(&cluster_buffer[i * 32..i * 32 + 32]);
+                if entry[0] == EXFAT_ENTRY_END {
+                    break 'load;
+                }
+                raw.push((entry, ExFatEntryLocation { cluster, index: i as u32 }));
+            }
+        }
+
+        let mut result = Vec::new();
+        let mut i = 0;
+        while i < raw.len() {
+            let (primary, location) = raw[i];
+            if primary[0] != EXFAT_ENTRY_FILE {
+                i += 1;
+                continue;
+            }
+
+            let secondary_count = primary[1] as usize;
+            if secondary_count < 2 || i + secondary_count >= raw.len() {
+                i += 1;
+                continue;
+            }
+
+            let entries: Vec<[u8; 32]> = raw[i..=i + secondary_count].iter().map(|(e, _)| *e).collect();
+            let slots: Vec<ExFatEntryLocation> = raw[i..=i + secondary_count].iter().map(|(_, l)| *l).collect();
+            i += 1;
+
+            let stored_checksum = u16::from_le_bytes([primary[2], primary[3]]);
+            if entries[1][0] != EXFAT_ENTRY_STREAM_EXTENSION || entry_set_checksum(&entries) != stored_checksum {
+                continue;
+            }
+
+            let stream_entry = &entries[1];
+            let name_length = stream_entry[3] as usize;
+            let mut name_units = Vec::with_capacity(name_length);
+            for name_entry in entries[2..].iter().filter(|e| e[0] == EXFAT_ENTRY_FILE_NAME) {
+                for c in 0..EXFAT_NAME_CHARS_PER_ENTRY {
+                    if name_units.len() == name_length {
+                        break;
+                    }
+                    name_units.push(u16::from_le_bytes([name_entry[2 + c * 2], name_entry[3 + c * 2]]));
+                }
+            }
+            if name_units.len() != name_length || name_length == 0 {
+                continue;
+            }
+
+            let name: String = core::char::decode_utf16(name_units.iter().copied())
+                .map(|c| c.unwrap_or(core::char::REPLACEMENT_CHARACTER))
+                .collect();
+
+            result.push(ExFatDirEntry {
+                location,
+                slots,
+                name,
+                attributes: u16::from_le_bytes([primary[4], primary[5]]),
+                stream: ExFatStream {
+                    first_cluster: u32::from_le_bytes([stream_entry[20], stream_entry[21], stream_entry[22], stream_entry[23]]),
+                    data_length: read_u64(stream_entry, 24),
+                    valid_data_length: read_u64(stream_entry, 8),
+                    no_fat_chain: stream_entry[1] & EXFAT_FLAG_NO_FAT_CHAIN != 0,
+                },
+                create_timestamp: u32::from_le_bytes([primary[8], primary[9], primary[10], primary[11]]),
+                modify_timestamp: u32::from_le_bytes([primary[12], primary[13], primary[14], primary[15]]),
+                access_timestamp: u32::from_le_bytes([primary[16], primary[17], primary[18], primary[19]]),
+                entries,
+            });
+            i += secondary_count;
+        }
+
+        Ok(result)
+    }
+
+    /// Buscar un nombre en un directorio (sin distinguir mayúsculas, usando la tabla up-case)
+    pub fn lookup(&mut self, directory: &ExFatStream, filename: &str) -> Result<Option<ExFatDirEntry>, &'static str> {
+        let target: Vec<u16> = filename.encode_utf16().collect();
+        let target_upcase = self.upcase_name(&target);
+        let target_hash = name_hash(&target_upcase);
+
+        for entry in self.read_dir_stream(directory)? {
+            let stored_hash = u16::from_le_bytes([entry.entries[1][4], entry.entries[1][5]]);
+            if stored_hash != target_hash {
+                continue;
+            }
+            let units: Vec<u16> = entry.name.encode_utf16().collect();
+            if self.upcase_name(&units) == target_upcase {
+                return Ok(Some(entry));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Resolver una ruta y devolver la pila de directorios recorridos y la entrada final
+    ///
+    /// La entrada final es `None` para el directorio raíz.
+    fn resolve(&mut self, path: &str) -> Result<Vec<ExFatDirEntry>, &'static str> {
+        let mut stack: Vec<ExFatDirEntry> = Vec::new();
+
+        for component in path.split(|c| c == '\\' || c == '/') {
+            match component {
+                "" | "." => continue,
+                ".." => {
+                    stack.pop();
+                    continue;
+                }
+                _ => {}
+            }
+
+            let directory = match stack.last() {
+                Some(parent) if !parent.is_directory() => return Err("Componente de ruta no es un directorio"),
+                Some(parent) => parent.stream,
+                None => self.root_stream(),
+            };
+            let entry = self.lookup(&directory, component)?.ok_or("Ruta no encontrada")?;
+            stack.push(entry);
+        }
+
+        Ok(stack)
+    }
+
+    /// Resolver una ruta a su conjunto de entradas
+    pub fn resolve_path(&mut self, path: &str) -> Result<Option<ExFatDirEntry>, &'static str> {
+        Ok(self.resolve(path)?.pop())
+    }
+
+    /// Listar un directorio a partir de su ruta
+    pub fn read_dir(&mut self, path: &str) -> Result<Vec<ExFatDirEntry>, &'static str> {
+        let directory = match self.resolve_path(path)? {
+            Some(entry) if !entry.is_directory() => return Err("La ruta no es un directorio"),
+            Some(entry) => entry.stream,
+            None => self.root_stream(),
+        };
+        self.read_dir_stream(&directory)
+    }
+
+    /// Leer datos de un archivo a partir de un desplazamiento
+    pub fn read_file(&mut self, path: &str, offset: u64, buffer: &mut [u8]) -> Result<usize, &'static str> {
+        let entry = self.resolve_path(path)?.ok_or("No se puede leer un directorio como archivo")?;
+        if entry.is_directory() {
+            return Err("No se puede leer un directorio como archivo");
+        }
+        self.read_stream(&entry.stream, offset, buffer)
+    }
+
+    /// Escribir un conjunto de entradas en su posición
+    fn write_entry_set(&mut self, entry: &mut ExFatDirEntry) -> Result<(), &'static str> {
+        entry.encode();
+        for i in 0..entry.entries.len() {
+            self.write_directory_slot(entry.slots[i], &entry.entries[i])?;
+        }
+        Ok(())
+    }
+
+    /// Escribir 32 bytes en bruto en una posición de directorio
+    fn write_directory_slot(&mut self, location: ExFatEntryLocation, slot: &[u8; 32]) -> Result<(), &'static str> {
+        let mut cluster_buffer = vec![0u8; self.cluster_size as usize];
+        if !self.read_cluster(location.cluster, &mut cluster_buffer) {
+            return Err("Error leyendo clúster de directorio");
+        }
+        let offset = location.index as usize * EXFAT_DIRECTORY_ENTRY_SIZE;
+        cluster_buffer[offset..offset + EXFAT_DIRECTORY_ENTRY_SIZE].copy_from_slice(slot);
+        if !self.write_cluster(location.cluster, &cluster_buffer) {
+            return Err("Error escribiendo clúster de directorio");
+        }
+        Ok(())
+    }
+
+    /// Marca de tiempo actual en formato exFAT
+    fn current_timestamp(&self) -> u32 {
+        unix_to_exfat_timestamp(self.get_system_time())
+    }
+
+    /// Crear un archivo o directorio vacío a partir de su ruta
+    pub fn create_file(&mut self, path: &str, attributes: u16) -> Result<ExFatDirEntry, &'static str> {
+        if self.state != ExFatState::Mounted {
+            return Err("Sistema de archivos no montado");
+        }
+
+        let (parent_path, name) = split_entry_path(path)?;
+        let (parent, directory) = self.resolve_directory(parent_path)?;
+        if self.lookup(&directory, name)?.is_some() {
+            return Err("El archivo ya existe");
+        }
+        let entries = self.build_entry_set(name)?;
+
+        let directory_flag = attributes & FAT32_ATTR_DIRECTORY as u16;
+        let now = self.current_timestamp();
+        let mut entry = ExFatDirEntry {
+            location: ExFatEntryLocation { cluster: 0, index: 0 },
+            slots: Vec::new(),
+            entries,
+            name: String::from(name),
+            attributes: if directory_flag != 0 { attributes } else { attributes | FAT32_ATTR_ARCHIVE as u16 },
+            stream: ExFatStream {
+                first_cluster: 0,
+                data_length: 0,
+                valid_data_length: 0,
+                no_fat_chain: false,
+            },
+            create_timestamp: now,
+            modify_timestamp: now,
+            access_timestamp: now,
+        };
+
+        // Los directorios empiezan con un clúster vacío
+        if entry.is_directory() {
+            self.grow_stream(&mut entry.stream, 1)?;
+            entry.stream.data_length = self.cluster_size as u64;
+            entry.stream.valid_data_length = self.cluster_size as u64;
+        }
+
+        self.place_entry_set(parent, directory, &mut entry)?;
+        Ok(entry)
+    }
+
+    /// Resolver el directorio padre de una entrada (`None` para el raíz) y su flujo
+    fn resolve_directory(&mut self, path: &str) -> Result<(Option<ExFatDirEntry>, ExFatStream), &'static str> {
+        let parent = self.resolve(path)?.pop();
+        let directory = match &parent {
+            Some(entry) if !entry.is_directory() => return Err("Componente de ruta no es un directorio"),
+            Some(entry) => entry.stream,
+            None => self.root_stream(),
+        };
+        Ok((parent, directory))
+    }
+
+    /// Construir un conjunto de entradas vacío (archivo + extensión de flujo + nombres)
+    fn build_entry_set(&self, name: &str) -> Result<Vec<[u8; 32]>, &'static str> {
+        let name_units: Vec<u16> = name.encode_utf16().collect();
+        if name_units.len() > EXFAT_MAX_NAME_LENGTH {
+            return Err("Nombre de archivo demasiado largo");
+        }
+
+        let name_entries = (name_units.len() + EXFAT_NAME_CHARS_PER_ENTRY - 1) / EXFAT_NAME_CHARS_PER_ENTRY;
+        let mut entries = vec![[0u8; 32]; 2 + name_entries];
+        entries[0][0] = EXFAT_ENTRY_FILE;
+        entries[0][1] = (1 + name_entries) as u8;
+        entries[1][0] = EXFAT_ENTRY_STREAM_EXTENSION;
+        entries[1][3] = name_units.len() as u8;
+        let hash = name_hash(&self.upcase_name(&name_units));
+        entries[1][4..6].copy_from_slice(&hash.to_le_bytes());
+        for (i, chunk) in name_units.chunks(EXFAT_NAME_CHARS_PER_ENTRY).enumerate() {
+            let name_entry = &mut entries[2 + i];
+            name_entry[0] = EXFAT_ENTRY_FILE_NAME;
+            for (c, &unit) in chunk.iter().enumerate() {
+                name_entry[2 + c * 2..4 + c * 2].copy_from_slice(&unit.to_le_bytes());
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Escribir un conjunto de entradas en un hueco del directorio (ampliándolo si hace falta)
+    fn place_entry_set(&mut self, mut parent: Option<ExFatDirEntry>, mut directory: ExFatStream, entry: &mut ExFatDirEntry) -> Result<(), &'static str> {
+        let count = entry.entries.len();
+        entry.slots = match self.find_free_entries(&directory, count)? {
+            Some(slots) => slots,
+            None => {
+                let cluster_size = self.cluster_size as u64;
+                let clusters = self.stream_clusters(&directory)?.len();
+                self.grow_stream(&mut directory, clusters + 1)?;
+                if let Some(parent_entry) = parent.as_mut() {
+                    parent_entry.stream = directory;
+                    parent_entry.stream.data_length = (clusters as u64 + 1) * cluster_size;
+                    parent_entry.stream.valid_data_length = parent_entry.stream.data_length;
+                    directory = parent_entry.stream;
+                    self.write_entry_set(parent_entry)?;
+                }
+                self.find_free_entries(&directory, count)?
+                    .ok_or("No hay entradas libres en el directorio")?
+            }
+        };
+
+        entry.location = entry.slots[0];
+        self.write_entry_set(entry)
+    }
+
+    /// Marcar un conjunto de entradas como no usado
+    fn mark_entry_set_unused(&mut self, entry: &ExFatDirEntry) -> Result<(), &'static str> {
+        for (slot, &location) in entry.entries.iter().zip(entry.slots.iter()) {
+            let mut slot = *slot;
+            slot[0] &= !EXFAT_ENTRY_IN_USE;
+            self.write_directory_slot(location, &slot)?;
+        }
+        Ok(())
+    }
+
+    /// Renombrar o mover un archivo o directorio
+    ///
+    /// Se escribe un conjunto de entradas nuevo en el directorio de destino
+    /// con el mismo flujo, atributos y marcas de tiempo, y se libera el antiguo.
+    pub fn rename_file(&mut self, path: &str, new_path: &str) -> Result<(), &'static str> {
+        if self.state != ExFatState::Mounted {
+            return Err("Sistema de archivos no montado");
+        }
+
+        let old = self.resolve_path(path)?.ok_or("No se puede renombrar el directorio raíz")?;
+        if old.attributes & FAT32_ATTR_READ_ONLY as u16 != 0 {
+            return Err("Archivo de solo lectura");
+        }
+        let (parent_path, name) = split_entry_path(new_path)?;
+        let (parent, directory) = self.resolve_directory(parent_path)?;
+        // Un cambio de mayúsculas encuentra la propia entrada
+        if let Some(existing) = self.lookup(&directory, name)? {
+            if existing.location != old.location {
+                return Err("El archivo ya existe");
+            }
+        }
+
+        let mut entry = ExFatDirEntry {
+            location: ExFatEntryLocation { cluster: 0, index: 0 },
+            slots: Vec::new(),
+            entries: self.build_entry_set(name)?,
+            name: String::from(name),
+            ..old.clone()
+        };
+        // Primero el conjunto nuevo, para no perder el archivo si algo falla
+        self.place_entry_set(parent, directory, &mut entry)?;
+        self.mark_entry_set_unused(&old)
+    }
+
+    /// Buscar `count` entradas libres consecutivas (el hueco puede cruzar clústeres)
+    fn find_free_entries(&mut self, directory: &ExFatStream, count: usize) -> Result<Option<Vec<ExFatEntryLocation>>, &'static str> {
+        let clusters = self.stream_clusters(directory)?;
+        let entries_per_cluster = self.cluster_size as usize / EXFAT_DIRECTORY_ENTRY_SIZE;
+        let mut cluster_buffer = vec![0u8; self.cluster_size as usize];
+        let mut run: Vec<ExFatEntryLocation> = Vec::with_capacity(count);
+
+        for &cluster in &clusters {
+            if !self.read_cluster(cluster, &mut cluster_buffer) {
+                return Err("Error leyendo clúster de directorio");
+            }
+
+            for i in 0..entries_per_cluster {
+                if cluster_buffer[i * EXFAT_DIRECTORY_ENTRY_SIZE] & EXFAT_ENTRY_IN_USE == 0 {
+                    run.push(ExFatEntryLocation { cluster, index: i as u32 });
+                    if run.len() == count {
+                        return Ok(Some(run));
+                    }
+                } else {
+                    run.clear();
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Escribir datos en un archivo a partir de un desplazamiento
+    pub fn write_file(&mut self, path: &str, offset: u64, data: &[u8]) -> Result<usize, &'static str> {
+        if self.state != ExFatState::Mounted {
+            return Err("Sistema de archivos no montado");
+        }
+
+        let mut entry = self.resolve_path(path)?.ok_or("No se puede escribir en un directorio")?;
+        if entry.is_directory() {
+            return Err("No se puede escribir en un directorio");
+        }
+        if entry.attributes & FAT32_ATTR_READ_ONLY as u16 != 0 {
+            return Err("Archivo de solo lectura");
+        }
+
+        let end = offset + data.len() as u64;
+        let cluster_size = self.cluster_size as u64;
+        let needed = ((end + cluster_size - 1) / cluster_size) as usize;
+        let mut stream = entry.stream;
+        stream.data_length = core::cmp::max(stream.data_length, end);
+        let current_clusters = ((entry.stream.data_length + cluster_size - 1) / cluster_size) as usize;
+        if needed > current_clusters {
+            let mut allocated = entry.stream;
+            self.grow_stream(&mut allocated, needed)?;
+            stream.first_cluster = allocated.first_cluster;
+            stream.no_fat_chain = allocated.no_fat_chain;
+        }
+
+        // Rellenar con ceros, clúster a clúster, el hueco entre los datos válidos y el desplazamiento
+        if offset > stream.valid_data_length {
+            let zeros = vec![0u8; self.cluster_size as usize];
+            let mut position = stream.valid_data_length;
+            while position < offset {
+                let chunk = core::cmp::min(cluster_size - position % cluster_size, offset - position);
+                self.write_stream(&stream, position, &zeros[..chunk as usize])?;
+                position += chunk;
+            }
+        }
+        self.write_stream(&stream, offset, data)?;
+
+        stream.valid_data_length = core::cmp::max(stream.valid_data_length, end);
+        entry.stream = stream;
+        entry.attributes |= FAT32_ATTR_ARCHIVE as u16;
+        entry.modify_timestamp = self.current_timestamp();
+        entry.access_timestamp = entry.modify_timestamp;
+        self.write_entry_set(&mut entry)?;
+
+        Ok(data.len())
+    }
+
+    /// Cambiar el tamaño de un archivo
+    ///
+    /// Al ampliar solo se reservan clústeres: la zona nueva queda fuera de
+    /// `valid_data_length` y se lee como ceros.
+    pub fn truncate_file(&mut self, path: &str, new_size: u64) -> Result<(), &'static str> {
+        if self.state != ExFatState::Mounted {
+            return Err("Sistema de archivos no montado");
+        }
+
+        let mut entry = self.resolve_path(path)?.ok_or("No se puede truncar un directorio")?;
+        if entry.is_directory() {
+            return Err("No se puede truncar un directorio");
+        }
+
+        let cluster_size = self.cluster_size as u64;
+        let needed = ((new_size + cluster_size - 1) / cluster_size) as usize;
+        let mut stream = entry.stream;
+        if new_size < stream.data_length {
+            self.shrink_stream(&mut stream, needed)?;
+        } else {
+            self.grow_stream(&mut stream, needed)?;
+        }
+
+        stream.data_length = new_size;
+        stream.valid_data_length = core::cmp::min(stream.valid_data_length, new_size);
+        entry.stream = stream;
+        entry.modify_timestamp = self.current_timestamp();
+        self.write_entry_set(&mut entry)
+    }
+
+    /// Eliminar un archivo o directorio vacío
+    pub fn delete_file(&mut self, path: &str) -> Result<(), &'static str> {
+        if self.state != ExFatState::Mounted {
+            return Err("Sistema de archivos no montado");
+        }
+
+        let entry = self.resolve_path(path)?.ok_or("No se puede eliminar el directorio raíz")?;
+        if entry.attributes & FAT32_ATTR_READ_ONLY as u16 != 0 {
+            return Err("Archivo de solo lectura");
+        }
+        if entry.is_directory() && !self.read_dir_stream(&entry.stream)?.is_empty() {
+            return Err("El directorio no está vacío");
+        }
+
+        // Marcar el conjunto como no usado antes de liberar los clústeres
+        self.mark_entry_set_unused(&entry)?;
+
+        let mut stream = entry.stream;
+        self.shrink_stream(&mut stream, 0)
+    }
+
+    /// Obtener información del sistema de archivos
+    pub fn get_filesystem_info(&self) -> (u32, u32, u32, u32) {
+        (
+            self.cluster_count,
+            self.cluster_size,
+            self.free_clusters.load(Ordering::SeqCst),
+            self.root_cluster,
+        )
+    }
+
+    /// Verificar si el sistema de archivos está montado
+    pub fn is_mounted(&self) -> bool {
+        self.state == ExFatState::Mounted
+    }
+
+    /// Obtener tiempo del sistema (segundos desde la época Unix)
+    fn get_system_time(&self) -> u64 {
+        crate::hal::rtc::unix_time()
+    }
+
+    /// Información de una entrada para el VFS (`None` es el directorio raíz)
+    fn vfs_file_info(&self, entry: Option<&ExFatDirEntry>) -> FileInfo {
+        let entry = match entry {
+            Some(entry) => entry,
+            None => {
+                let mut root = FileInfo::new(0, "/", NodeType::Directory);
+                root.block_size = self.cluster_size;
+                root.device = self.block_device;
+                root.inode = self.root_cluster as u64;
+                return root;
+            }
+        };
+
+        let node_type = if entry.is_directory() { NodeType::Directory } else { NodeType::File };
+        let mut file_info = FileInfo::new(0, &entry.name, node_type);
+        file_info.size = entry.size();
+        file_info.block_size = self.cluster_size;
+        file_info.block_count = (entry.size() + self.cluster_size as u64 - 1) / self.cluster_size as u64;
+        if entry.attributes & FAT32_ATTR_READ_ONLY as u16 != 0 {
+            file_info.permissions = FilePermissions::read_only();
+        }
+        file_info.created_time = exfat_timestamp_to_unix(entry.create_timestamp);
+        file_info.modified_time = exfat_timestamp_to_unix(entry.modify_timestamp);
+        file_info.accessed_time = exfat_timestamp_to_unix(entry.access_timestamp);
+        file_info.device = self.block_device;
+        file_info.inode = entry.stream.first_cluster as u64;
+        file_info
+    }
+}
+
+impl FileSystemDriver for ExFatDriver {
+    fn open(&mut self, file_path: &str, create: bool) -> Result<FileInfo, &'static str> {
+        match self.resolve_path(file_path) {
+            Ok(entry) => Ok(self.vfs_file_info(entry.as_ref())),
+            Err("Ruta no encontrada") if create => {
+                let entry = self.create_file(file_path, 0)?;
+                Ok(self.vfs_file_info(Some(&entry)))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn read_file(&mut self, file_path: &str, buffer: &mut [u8], offset: u64) -> Result<usize, &'static str> {
+        ExFatDriver::read_file(self, file_path, offset, buffer)
+    }
+
+    fn write_file(&mut self, file_path: &str, buffer: &[u8], offset: u64) -> Result<usize, &'static str> {
+        ExFatDriver::write_file(self, file_path, offset, buffer)
+    }
+
+    fn read_dir(&mut self, dir_path: &str) -> Result<Vec<FileInfo>, &'static str> {
+        Ok(ExFatDriver::read_dir(self, dir_path)?
+            .iter()
+            .map(|entry| self.vfs_file_info(Some(entry)))
+            .collect())
+    }
+
+    fn stat(&mut self, file_path: &str) -> Result<FileInfo, &'static str> {
+        let entry = self.resolve_path(file_path)?;
+        Ok(self.vfs_file_info(entry.as_ref()))
+    }
+
+    fn unlink(&mut self, file_path: &str) -> Result<(), &'static str> {
+        self.delete_file(file_path)
+    }
+
+    fn truncate(&mut self, file_path: &str, size: u64) -> Result<(), &'static str> {
+        self.truncate_file(file_path, size)
+    }
+
+    fn rename(&mut self, old_path: &str, new_path: &str) -> Result<(), &'static str> {
+        self.rename_file(old_path, new_path)
+    }
+}
+
+/// Separar una ruta en directorio padre y nombre, validando el nombre
+fn split_entry_path(path: &str) -> Result<(&str, &str), &'static str> {
+    let (parent_path, name) = split_parent_at(path, &['/', '\\'])?;
+    let name = name.trim_end_matches(|c| c == ' ' || c == '.');
+    if name.is_empty()
+        || name.chars().any(|c| c < ' ' || matches!(c, '"' | '*' | '/' | ':' | '<' | '>' | '?' | '\\' | '|'))
+    {
+        return Err("Nombre de archivo inválido");
+    }
+    Ok((parent_path, name))
+}
+
+/// Leer un u64 little-endian de un buffer
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+/// Función para inicializar el driver exFAT
+pub fn init_exfat(block_device: u32) -> Result<ExFatDriver, &'static str> {
+    let mut driver = ExFatDriver::new(1, block_device);
+    driver.initialize()?;
+    driver.mount()?;
+    Ok(driver)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::test_util::TestDisk;
+
+    const SECTOR_SIZE: usize = 512;
+    /// Volumen de 2 MiB con clústeres de 4 KiB
+    const VOLUME_SECTORS: usize = 4096;
+    const SECTORS_PER_CLUSTER_SHIFT: u8 = 3;
+    const FAT_OFFSET: usize = 24;
+    const FAT_LENGTH: usize = 8;
+    const CLUSTER_HEAP_OFFSET: usize = 64;
+    const CLUSTER_COUNT: usize = (VOLUME_SECTORS - CLUSTER_HEAP_OFFSET) >> SECTORS_PER_CLUSTER_SHIFT;
+    const CLUSTER_SIZE: usize = SECTOR_SIZE << SECTORS_PER_CLUSTER_SHIFT;
+    // Bitmap, tabla up-case y directorio raíz en los tres primeros clústeres
+    const BITMAP_CLUSTER: usize = 2;
+    const UPCASE_CLUSTER: usize = 3;
+    const ROOT_CLUSTER: usize = 4;
+
+    fn cluster_offset(cluster: usize) -> usize {
+        (CLUSTER_HEAP_OFFSET + ((cluster - 2) << SECTORS_PER_CLUSTER_SHIFT)) * SECTOR_SIZE
+    }
+
+    /// Imagen exFAT vacía equivalente a la que genera mkfs.exfat
+    fn exfat_image() -> Vec<u8> {
+        let mut image = vec![0u8; VOLUME_SECTORS * SECTOR_SIZE];
+
+        let boot = &mut image[..SECTOR_SIZE];
+        boot[0..3].copy_from_slice(&[0xEB, 0x76, 0x90]);
+        boot[3..11].copy_from_slice(b"EXFAT   ");
+        boot[72..80].copy_from_slice(&(VOLUME_SECTORS as u64).to_le_bytes());
+        boot[80..84].copy_from_slice(&(FAT_OFFSET as u32).to_le_bytes());
+        boot[84..88].copy_from_slice(&(FAT_LENGTH as u32).to_le_bytes());
+        boot[88..92].copy_from_slice(&(CLUSTER_HEAP_OFFSET as u32).to_le_bytes());
+        boot[92..96].copy_from_slice(&(CLUSTER_COUNT as u32).to_le_bytes());
+        boot[96..100].copy_from_slice(&(ROOT_CLUSTER as u32).to_le_bytes());
+        boot[100..104].copy_from_slice(&0x1234_5678u32.to_le_bytes());
+        boot[104..106].copy_from_slice(&0x0100u16.to_le_bytes());
+        boot[108] = 9;
+        boot[109] = SECTORS_PER_CLUSTER_SHIFT;
+        boot[110] = 1;
+        boot[111] = 0x80;
+        boot[510] = 0x55;
+        boot[511] = 0xAA;
+
+        // Checksum de la región de arranque repetido en el sector 11
+        let mut checksum: u32 = 0;
+        for (i, &byte) in image[..11 * SECTOR_SIZE].iter().enumerate() {
+            if i != 106 && i != 107 && i != 112 {
+                checksum = checksum.rotate_right(1).wrapping_add(byte as u32);
+            }
+        }
+        for chunk in image[11 * SECTOR_SIZE..12 * SECTOR_SIZE].chunks_mut(4) {
+            chunk.copy_from_slice(&checksum.to_le_bytes());
+        }
+
+        // FAT: entradas reservadas y cadenas de un clúster para los metadatos
+        let fat = FAT_OFFSET * SECTOR_SIZE;
+        image[fat..fat + 4].copy_from_slice(&0xFFFF_FFF8u32.to_le_bytes());
+        for cluster in [1, BITMAP_CLUSTER, UPCASE_CLUSTER, ROOT_CLUSTER] {
+            image[fat + cluster * 4..fat + cluster * 4 + 4].copy_from_slice(&EXFAT_END_OF_CHAIN.to_le_bytes());
+        }
+
+        // Bitmap de asignación con los clústeres 2, 3 y 4 en uso
+        image[cluster_offset(BITMAP_CLUSTER)] = 0b111;
+
+        // Tabla up-case ASCII: solo cambian las minúsculas
+        let upcase: Vec<u8> = (0..128u16)
+            .map(|c| if (b'a' as u16..=b'z' as u16).contains(&c) { c - 32 } else { c })
+            .flat_map(|c| c.to_le_bytes())
+            .collect();
+        let upcase_at = cluster_offset(UPCASE_CLUSTER);
+        image[upcase_at..upcase_at + upcase.len()].copy_from_slice(&upcase);
+
+        // Directorio raíz: etiqueta, bitmap y tabla up-case
+        let root = cluster_offset(ROOT_CLUSTER);
+        let label: Vec<u8> = "TEST".encode_utf16().flat_map(|c| c.to_le_bytes()).collect();
+        image[root] = EXFAT_ENTRY_VOLUME_LABEL;
+        image[root + 1] = 4;
+        image[root + 2..root + 2 + label.len()].copy_from_slice(&label);
+
+        let bitmap_entry = root + 32;
+        image[bitmap_entry] = EXFAT_ENTRY_ALLOCATION_BITMAP;
+        image[bitmap_entry + 20..bitmap_entry + 24].copy_from_slice(&(BITMAP_CLUSTER as u32).to_le_bytes());
+        image[bitmap_entry + 24..bitmap_entry + 32].copy_from_slice(&(CLUSTER_COUNT.div_ceil(8) as u64).to_le_bytes());
+
+        let upcase_entry = root + 64;
+        image[upcase_entry] = EXFAT_ENTRY_UPCASE_TABLE;
+        image[upcase_entry + 4..upcase_entry + 8].copy_from_slice(&table_checksum(&upcase).to_le_bytes());
+        image[upcase_entry + 20..upcase_entry + 24].copy_from_slice(&(UPCASE_CLUSTER as u32).to_le_bytes());
+        image[upcase_entry + 24..upcase_entry + 32].copy_from_slice(&(upcase.len() as u64).to_le_bytes());
+
+        image
+    }
+
+    fn free_clusters(driver: &ExFatDriver) -> u32 {
+        driver.get_filesystem_info().2
+    }
+
+    #[test]
+    fn test_mount_checks_boot_region_checksum() {
+        let disk = TestDisk::with_data(exfat_image(), 512);
+        let driver = init_exfat(disk.device_id).unwrap();
+        assert_eq!(free_clusters(&driver), CLUSTER_COUNT as u32 - 3);
+
+        // Cambiar el número de serie invalida el checksum de la región de arranque
+        let mut boot = vec![0u8; SECTOR_SIZE];
+        disk.read(0, &mut boot);
+        boot[100] ^= 0xFF;
+        disk.write(0, &boot);
+        assert_eq!(init_exfat(disk.device_id).err(), Some("Checksum de la región de arranque exFAT inválido"));
+    }
+
+    #[test]
+    fn test_create_write_truncate_delete() {
+        let disk = TestDisk::with_data(exfat_image(), 512);
+        let mut driver = init_exfat(disk.device_id).unwrap();
+        let free_at_start = free_clusters(&driver);
+
+        driver.create_file("/Informe anual.txt", 0).unwrap();
+        driver.write_file("/Informe anual.txt", 0, b"cabecera").unwrap();
+
+        // El hueco hasta el desplazamiento abarca varios clústeres y se lee como ceros
+        let offset = 3 * CLUSTER_SIZE as u64 + 100;
+        driver.write_file("/Informe anual.txt", offset, b"cola").unwrap();
+        let mut content = vec![0xAAu8; offset as usize + 4];
+        assert_eq!(driver.read_file("/informe ANUAL.txt", 0, &mut content).unwrap(), content.len());
+        assert_eq!(&content[..8], b"cabecera");
+        assert!(content[8..offset as usize].iter().all(|&byte| byte == 0));
+        assert_eq!(&content[offset as usize..], b"cola");
+        assert_eq!(free_clusters(&driver), free_at_start - 4);
+
+        // Las entradas sobreviven a un nuevo montaje (checksums del conjunto válidos)
+        let mut driver = init_exfat(disk.device_id).unwrap();
+        let entry = driver.resolve_path("/Informe anual.txt").unwrap().unwrap();
+        assert_eq!(entry.size(), offset + 4);
+
+        driver.truncate_file("/Informe anual.txt", 5).unwrap();
+        let mut content = [0u8; 16];
+        assert_eq!(driver.read_file("/Informe anual.txt", 0, &mut content).unwrap(), 5);
+        assert_eq!(&content[..5], b"cabec");
+        assert_eq!(free_clusters(&driver), free_at_start - 1);
+
+        driver.delete_file("/Informe anual.txt").unwrap();
+        assert!(driver.resolve_path("/Informe anual.txt").is_err());
+        assert_eq!(free_clusters(&driver), free_at_start);
+    }
+
+    #[test]
+    fn test_directories_and_rename() {
+        let disk = TestDisk::with_data(exfat_image(), 512);
+        let mut driver = init_exfat(disk.device_id).unwrap();
+
+        driver.create_file("/docs", FAT32_ATTR_DIRECTORY as u16).unwrap();
+        driver.create_file("/docs/nota.txt", 0).unwrap();
+        driver.write_file("/docs/nota.txt", 0, b"hola").unwrap();
+        assert_eq!(driver.delete_file("/docs"), Err("El directorio no está vacío"));
+
+        driver.rename_file("/docs/nota.txt", "/Nota movida.txt").unwrap();
+        let names: Vec<String> = driver.read_dir("/").unwrap().into_iter().map(|entry| entry.name).collect();
+        assert_eq!(names, ["docs", "Nota movida.txt"]);
+        let mut content = [0u8; 4];
+        driver.read_file("/NOTA MOVIDA.TXT", 0, &mut content).unwrap();
+        assert_eq!(&content, b"hola");
+
+        driver.delete_file("/docs").unwrap();
+    }
+}
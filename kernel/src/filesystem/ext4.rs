@@ -0,0 +1,1513 @@
+//! Driver ext2/ext3/ext4 para ReactOS Rust Kernel
+//!
+//! Implementa un driver de solo lectura para la familia ext: superbloque y
+//! descriptores de grupo (incluidos los de 64 bits y `meta_bg`), tablas de
+//! inodos, árboles de extents y bloques indirectos, búsqueda en directorios
+//! indexados (htree) y enlaces simbólicos. El diario de ext3/ext4 no se
+//! reproduce.
+
+use alloc::{borrow::ToOwned, string::String, vec, vec::Vec};
+
+use super::path::path_name;
+use super::vfs::{FileInfo, FilePermissions, FileSystemDriver, NodeType, VFS_MAX_SYMLINK_DEPTH};
+
+/// Desplazamiento del superbloque en el dispositivo
+pub const EXT4_SUPERBLOCK_OFFSET: u64 = 1024;
+
+/// Tamaño del superbloque
+pub const EXT4_SUPERBLOCK_SIZE: usize = 1024;
+
+/// Firma del superbloque
+pub const EXT4_SUPER_MAGIC: u16 = 0xEF53;
+
+/// Inodo del directorio raíz
+pub const EXT4_ROOT_INODE: u32 = 2;
+
+/// Tamaño de un inodo en la revisión 0
+pub const EXT4_GOOD_OLD_INODE_SIZE: u16 = 128;
+
+/// Tamaño de un descriptor de grupo sin la característica 64bit
+pub const EXT4_MIN_DESC_SIZE: u16 = 32;
+
+// Características compatibles
+pub const EXT4_FEATURE_COMPAT_HAS_JOURNAL: u32 = 0x0004;
+pub const EXT4_FEATURE_COMPAT_DIR_INDEX: u32 = 0x0020;
+
+// Características de solo lectura compatibles
+pub const EXT4_FEATURE_RO_COMPAT_SPARSE_SUPER: u32 = 0x0001;
+
+// Características incompatibles
+pub const EXT4_FEATURE_INCOMPAT_COMPRESSION: u32 = 0x0001;
+pub const EXT4_FEATURE_INCOMPAT_FILETYPE: u32 = 0x0002;
+pub const EXT4_FEATURE_INCOMPAT_RECOVER: u32 = 0x0004;
+pub const EXT4_FEATURE_INCOMPAT_JOURNAL_DEV: u32 = 0x0008;
+pub const EXT4_FEATURE_INCOMPAT_META_BG: u32 = 0x0010;
+pub const EXT4_FEATURE_INCOMPAT_EXTENTS: u32 = 0x0040;
+pub const EXT4_FEATURE_INCOMPAT_64BIT: u32 = 0x0080;
+pub const EXT4_FEATURE_INCOMPAT_DIRDATA: u32 = 0x1000;
+pub const EXT4_FEATURE_INCOMPAT_INLINE_DATA: u32 = 0x8000;
+pub const EXT4_FEATURE_INCOMPAT_ENCRYPT: u32 = 0x10000;
+
+/// Características incompatibles que impiden leer el volumen
+pub const EXT4_FEATURE_INCOMPAT_UNSUPPORTED: u32 = EXT4_FEATURE_INCOMPAT_COMPRESSION
+    | EXT4_FEATURE_INCOMPAT_JOURNAL_DEV
+    | EXT4_FEATURE_INCOMPAT_DIRDATA
+    | EXT4_FEATURE_INCOMPAT_INLINE_DATA
+    | EXT4_FEATURE_INCOMPAT_ENCRYPT;
+
+/// Flag del superbloque: los hashes de directorio usan caracteres sin signo
+pub const EXT4_FLAGS_UNSIGNED_HASH: u32 = 0x0002;
+
+// Flags de inodo
+pub const EXT4_INDEX_FL: u32 = 0x0000_1000;
+pub const EXT4_EXTENTS_FL: u32 = 0x0008_0000;
+pub const EXT4_INLINE_DATA_FL: u32 = 0x1000_0000;
+
+// Tipos de archivo del campo de modo
+pub const EXT4_S_IFMT: u16 = 0xF000;
+pub const EXT4_S_IFIFO: u16 = 0x1000;
+pub const EXT4_S_IFCHR: u16 = 0x2000;
+pub const EXT4_S_IFDIR: u16 = 0x4000;
+pub const EXT4_S_IFBLK: u16 = 0x6000;
+pub const EXT4_S_IFREG: u16 = 0x8000;
+pub const EXT4_S_IFLNK: u16 = 0xA000;
+pub const EXT4_S_IFSOCK: u16 = 0xC000;
+
+/// Firma de la cabecera de un nodo del árbol de extents
+pub const EXT4_EXTENT_MAGIC: u16 = 0xF30A;
+
+/// Longitud máxima de un extent inicializado
+pub const EXT4_EXTENT_MAX_INIT_LENGTH: u16 = 32768;
+
+/// Profundidad máxima del árbol de extents
+pub const EXT4_EXTENT_MAX_DEPTH: u16 = 5;
+
+/// Bloques directos en `i_block`
+pub const EXT4_DIRECT_BLOCKS: usize = 12;
+
+// Versiones de hash de los directorios indexados
+pub const EXT4_DX_HASH_LEGACY: u8 = 0;
+pub const EXT4_DX_HASH_HALF_MD4: u8 = 1;
+pub const EXT4_DX_HASH_TEA: u8 = 2;
+pub const EXT4_DX_HASH_LEGACY_UNSIGNED: u8 = 3;
+pub const EXT4_DX_HASH_HALF_MD4_UNSIGNED: u8 = 4;
+pub const EXT4_DX_HASH_TEA_UNSIGNED: u8 = 5;
+
+/// Superbloque ext2/ext3/ext4 (campos usados por el driver)
+#[derive(Debug, Clone)]
+pub struct Ext4Superblock {
+    /// Número total de inodos
+    pub inodes_count: u32,
+    /// Número total de bloques
+    pub blocks_count: u64,
+    /// Primer bloque de datos (1 con bloques de 1 KiB, 0 en otro caso)
+    pub first_data_block: u32,
+    /// Tamaño de bloque como 1024 << `log_block_size`
+    pub log_block_size: u32,
+    /// Bloques por grupo
+    pub blocks_per_group: u32,
+    /// Inodos por grupo
+    pub inodes_per_group: u32,
+    /// Estado del sistema de archivos
+    pub state: u16,
+    /// Revisión
+    pub rev_level: u32,
+    /// Tamaño de un inodo
+    pub inode_size: u16,
+    /// Características compatibles
+    pub feature_compat: u32,
+    /// Características incompatibles
+    pub feature_incompat: u32,
+    /// Características de solo lectura compatibles
+    pub feature_ro_compat: u32,
+    /// UUID del volumen
+    pub uuid: [u8; 16],
+    /// Nombre del volumen
+    pub volume_name: String,
+    /// Semilla de los hashes de directorio
+    pub hash_seed: [u32; 4],
+    /// Versión de hash por defecto
+    pub default_hash_version: u8,
+    /// Tamaño de un descriptor de grupo
+    pub desc_size: u16,
+    /// Primer grupo con descriptores `meta_bg`
+    pub first_meta_bg: u32,
+    /// Flags
+    pub flags: u32,
+}
+
+impl Ext4Superblock {
+    /// Interpretar el superbloque
+    pub fn parse(data: &[u8]) -> Result<Self, &'static str> {
+        if data.len() < EXT4_SUPERBLOCK_SIZE || read_u16(data, 56) != EXT4_SUPER_MAGIC {
+            return Err("No es un sistema de archivos ext2/ext3/ext4");
+        }
+
+        let rev_level = read_u32(data, 76);
+        let feature_incompat = if rev_level >= 1 { read_u32(data, 96) } else { 0 };
+        let blocks_hi = if feature_incompat & EXT4_FEATURE_INCOMPAT_64BIT != 0 { read_u32(data, 0x150) as u64 } else { 0 };
+        let mut uuid = [0u8; 16];
+        uuid.copy_from_slice(&data[104..120]);
+        let name_length = data[120..136].iter().position(|&byte| byte == 0).unwrap_or(16);
+        let mut hash_seed = [0u32; 4];
+        for (i, word) in hash_seed.iter_mut().enumerate() {
+            *word = read_u32(data, 0xEC + i * 4);
+        }
+
+        let superblock = Self {
+            inodes_count: read_u32(data, 0),
+            blocks_count: read_u32(data, 4) as u64 | (blocks_hi << 32),
+            first_data_block: read_u32(data, 20),
+            log_block_size: read_u32(data, 24),
+            blocks_per_group: read_u32(data, 32),
+            inodes_per_group: read_u32(data, 40),
+            state: read_u16(data, 58),
+            rev_level,
+            inode_size: if rev_level >= 1 { read_u16(data, 88) } else { EXT4_GOOD_OLD_INODE_SIZE },
+            feature_compat: if rev_level >= 1 { read_u32(data, 92) } else { 0 },
+            feature_incompat,
+            feature_ro_compat: if rev_level >= 1 { read_u32(data, 100) } else { 0 },
+            uuid,
+            volume_name: String::from(core::str::from_utf8(&data[120..120 + name_length]).unwrap_or("")),
+            hash_seed,
+            default_hash_version: data[0xFC],
+            desc_size: if feature_incompat & EXT4_FEATURE_INCOMPAT_64BIT != 0 { read_u16(data, 0xFE) } else { EXT4_MIN_DESC_SIZE },
+            first_meta_bg: read_u32(data, 0x104),
+            flags: read_u32(data, 0x160),
+        };
+
+        if superblock.log_block_size > 6
+            || superblock.blocks_per_group == 0
+            || superblock.inodes_per_group == 0
+            || superblock.inode_size < EXT4_GOOD_OLD_INODE_SIZE
+            || !superblock.inode_size.is_power_of_two()
+            || superblock.desc_size < EXT4_MIN_DESC_SIZE
+            || !superblock.desc_size.is_power_of_two()
+        {
+            return Err("Geometría ext inválida");
+        }
+        Ok(superblock)
+    }
+
+    /// Tamaño de bloque en bytes
+    pub fn block_size(&self) -> u32 {
+        1024 << self.log_block_size
+    }
+
+    /// Número de grupos de bloques
+    pub fn group_count(&self) -> u32 {
+        let data_blocks = self.blocks_count - self.first_data_block as u64;
+        ((data_blocks + self.blocks_per_group as u64 - 1) / self.blocks_per_group as u64) as u32
+    }
+
+    /// Verificar si un grupo guarda una copia del superbloque
+    pub fn group_has_superblock(&self, group: u32) -> bool {
+        if self.feature_ro_compat & EXT4_FEATURE_RO_COMPAT_SPARSE_SUPER == 0 || group <= 1 {
+            return true;
+        }
+        [3, 5, 7].iter().any(|&base| {
+            let mut power = base;
+            while power < group {
+                power *= base;
+            }
+            power == group
+        })
+    }
+}
+
+/// Inodo ext (campos usados por el driver)
+#[derive(Debug, Clone)]
+pub struct Ext4Inode {
+    /// Número de inodo
+    pub number: u32,
+    /// Modo (tipo y permisos)
+    pub mode: u16,
+    /// ID del propietario
+    pub uid: u32,
+    /// ID del grupo
+    pub gid: u32,
+    /// Tamaño en bytes
+    pub size: u64,
+    /// Número de enlaces
+    pub links_count: u16,
+    /// Sectores de 512 bytes asignados (incluido el bloque de atributos)
+    pub blocks: u64,
+    /// Flags
+    pub flags: u32,
+    /// Bloque de atributos extendidos
+    pub file_acl: u64,
+    /// Mapa de bloques, árbol de extents o destino de un enlace rápido
+    pub block: [u8; 60],
+    /// Tiempo de acceso
+    pub accessed_time: u64,
+    /// Tiempo de cambio de metadatos
+    pub changed_time: u64,
+    /// Tiempo de modificación
+    pub modified_time: u64,
+    /// Tiempo de creación (0 si el inodo no lo guarda)
+    pub created_time: u64,
+}
+
+impl Ext4Inode {
+    /// Interpretar un inodo de la tabla
+    pub fn parse(number: u32, data: &[u8]) -> Self {
+        let mut block = [0u8; 60];
+        block.copy_from_slice(&data[40..100]);
+        let extra_size = if data.len() > EXT4_GOOD_OLD_INODE_SIZE as usize { read_u16(data, 128) as usize } else { 0 };
+        let created_time = if EXT4_GOOD_OLD_INODE_SIZE as usize + extra_size >= 0x94 && data.len() >= 0x94 {
+            read_u32(data, 0x90) as u64
+        } else {
+            0
+        };
+
+        Self {
+            number,
+            mode: read_u16(data, 0),
+            uid: read_u16(data, 2) as u32 | ((read_u16(data, 120) as u32) << 16),
+            gid: read_u16(data, 24) as u32 | ((read_u16(data, 122) as u32) << 16),
+            size: read_u32(data, 4) as u64 | ((read_u32(data, 108) as u64) << 32),
+            links_count: read_u16(data, 26),
+            blocks: read_u32(data, 28) as u64 | ((read_u16(data, 116) as u64) << 32),
+            flags: read_u32(data, 32),
+            file_acl: read_u32(data, 104) as u64 | ((read_u16(data, 118) as u64) << 32),
+            block,
+            accessed_time: read_u32(data, 8) as u64,
+            changed_time: read_u32(data, 12) as u64,
+            modified_time: read_u32(data, 16) as u64,
+            created_time,
+        }
+    }
+
+    /// Tipo de nodo según el modo
+    pub fn node_type(&self) -> NodeType {
+        match self.mode & EXT4_S_IFMT {
+            EXT4_S_IFREG => NodeType::File,
+            EXT4_S_IFDIR => NodeType::Directory,
+            EXT4_S_IFLNK => NodeType::Symlink,
+            EXT4_S_IFCHR => NodeType::CharDevice,
+            EXT4_S_IFBLK => NodeType::BlockDevice,
+            EXT4_S_IFIFO => NodeType::NamedPipe,
+            EXT4_S_IFSOCK => NodeType::Socket,
+            _ => NodeType::Unknown,
+        }
+    }
+
+    /// Verificar si es un directorio
+    pub fn is_directory(&self) -> bool {
+        self.mode & EXT4_S_IFMT == EXT4_S_IFDIR
+    }
+
+    /// Verificar si es un enlace simbólico
+    pub fn is_symlink(&self) -> bool {
+        self.mode & EXT4_S_IFMT == EXT4_S_IFLNK
+    }
+
+    /// Verificar si los datos se describen con un árbol de extents
+    pub fn uses_extents(&self) -> bool {
+        self.flags & EXT4_EXTENTS_FL != 0
+    }
+}
+
+/// Entrada de directorio ext
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ext4DirEntry {
+    /// Inodo al que apunta
+    pub inode: u32,
+    /// Nombre
+    pub name: String,
+    /// Tipo de archivo de la entrada (0 sin la característica filetype)
+    pub file_type: u8,
+}
+
+/// Driver ext2/ext3/ext4 de solo lectura
+pub struct Ext4Driver {
+    /// Dispositivo de bloque
+    pub device_id: u32,
+    /// Tamaño de sector del dispositivo
+    pub sector_size: u32,
+    /// Volumen montado
+    pub mounted: bool,
+    /// Superbloque
+    pub superblock: Option<Ext4Superblock>,
+    /// Tamaño de bloque en bytes
+    pub block_size: u32,
+    /// Número de grupos de bloques
+    pub group_count: u32,
+    /// El diario tiene transacciones sin aplicar (los datos pueden estar desfasados)
+    pub needs_recovery: bool,
+}
+
+impl Ext4Driver {
+    /// Crear un driver sin montar
+    pub fn new(device_id: u32) -> Self {
+        Self {
+            device_id,
+            sector_size: 512,
+            mounted: false,
+            superblock: None,
+            block_size: 1024,
+            group_count: 0,
+            needs_recovery: false,
+        }
+    }
+
+    /// Leer el superbloque y montar
+    pub fn initialize(&mut self) -> Result<(), &'static str> {
+        self.sector_size = crate::drivers::storage::get_storage_manager()
+            .and_then(|manager| manager.get_device_info(self.device_id))
+            .map_or(512, |device| device.sector_size);
+        if self.sector_size == 0 || !self.sector_size.is_power_of_two() {
+            return Err("Tamaño de sector no soportado");
+        }
+
+        let mut data = vec![0u8; EXT4_SUPERBLOCK_SIZE];
+        if !self.read_bytes(EXT4_SUPERBLOCK_OFFSET, &mut data) {
+            return Err("Error leyendo superbloque");
+        }
+        let superblock = Ext4Superblock::parse(&data)?;
+        if superblock.feature_incompat & EXT4_FEATURE_INCOMPAT_UNSUPPORTED != 0 {
+            return Err("Característica ext no soportada");
+        }
+        if superblock.block_size() < self.sector_size {
+            return Err("Tamaño de bloque menor que el sector");
+        }
+
+        self.block_size = superblock.block_size();
+        self.group_count = superblock.group_count();
+        self.needs_recovery = superblock.feature_incompat & EXT4_FEATURE_INCOMPAT_RECOVER != 0;
+        self.superblock = Some(superblock);
+        self.mounted = true;
+        Ok(())
+    }
+
+    /// Superbloque del volumen montado
+    fn superblock(&self) -> Result<&Ext4Superblock, &'static str> {
+        self.superblock.as_ref().filter(|_| self.mounted).ok_or("Sistema de archivos no montado")
+    }
+
+    /// Leer bytes del dispositivo desde cualquier desplazamiento
+    fn read_bytes(&self, offset: u64, buffer: &mut [u8]) -> bool {
+        let sector_size = self.sector_size as u64;
+        let first = offset / sector_size;
+        let last = (offset + buffer.len() as u64 + sector_size - 1) / sector_size;
+        let mut sectors = vec![0u8; ((last - first) * sector_size) as usize];
+        if !crate::drivers::storage::read_sectors(self.device_id, first, (last - first) as u32, &mut sectors) {
+            return false;
+        }
+        let start = (offset - first * sector_size) as usize;
+        buffer.copy_from_slice(&sectors[start..start + buffer.len()]);
+        true
+    }
+
+    /// Leer un bloque
+    pub fn read_block(&self, block: u64, buffer: &mut [u8]) -> bool {
+        let sectors_per_block = self.block_size / self.sector_size;
+        crate::drivers::storage::read_sectors(
+            self.device_id,
+            block * sectors_per_block as u64,
+            sectors_per_block,
+            &mut buffer[..self.block_size as usize],
+        )
+    }
+
+    /// Primer bloque de la tabla de inodos de un grupo
+    fn inode_table(&self, group: u32) -> Result<u64, &'static str> {
+        let superblock = self.superblock()?;
+        if group >= self.group_count {
+            return Err("Grupo de bloques inválido");
+        }
+
+        // Con meta_bg cada metagrupo guarda sus descriptores en su primer grupo
+        let desc_size = superblock.desc_size as u32;
+        let per_block = self.block_size / desc_size;
+        let meta_group = group / per_block;
+        let block = if superblock.feature_incompat & EXT4_FEATURE_INCOMPAT_META_BG != 0 && meta_group >= superblock.first_meta_bg {
+            let first_group = meta_group * per_block;
+            let group_start = superblock.first_data_block as u64 + first_group as u64 * superblock.blocks_per_group as u64;
+            group_start + if superblock.group_has_superblock(first_group) { 1 } else { 0 }
+        } else {
+            superblock.first_data_block as u64 + 1 + meta_group as u64
+        };
+
+        let mut buffer = vec![0u8; self.block_size as usize];
+        if !self.read_block(block, &mut buffer) {
+            return Err("Error leyendo descriptor de grupo");
+        }
+        let descriptor = &buffer[((group % per_block) * desc_size) as usize..];
+        let high = if desc_size >= 64 { read_u32(descriptor, 0x28) as u64 } else { 0 };
+        Ok(read_u32(descriptor, 8) as u64 | (high << 32))
+    }
+
+    /// Leer un inodo
+    pub fn read_inode(&self, number: u32) -> Result<Ext4Inode, &'static str> {
+        let superblock = self.superblock()?;
+        if number == 0 || number > superblock.inodes_count {
+            return Err("Número de inodo inválido");
+        }
+
+        let index = number - 1;
+        let inode_size = superblock.inode_size as u64;
+        let table = self.inode_table(index / superblock.inodes_per_group)?;
+        let byte_offset = (index % superblock.inodes_per_group) as u64 * inode_size;
+        let mut buffer = vec![0u8; self.block_size as usize];
+        if !self.read_block(table + byte_offset / self.block_size as u64, &mut buffer) {
+            return Err("Error leyendo tabla de inodos");
+        }
+        let start = (byte_offset % self.block_size as u64) as usize;
+        Ok(Ext4Inode::parse(number, &buffer[start..start + inode_size as usize]))
+    }
+
+    /// Bloque físico de un bloque lógico del inodo (`None` si es un hueco)
+    pub fn map_block(&self, inode: &Ext4Inode, logical: u32) -> Result<Option<u64>, &'static str> {
+        if inode.uses_extents() {
+            self.map_extent_block(inode, logical)
+        } else {
+            self.map_indirect_block(inode, logical)
+        }
+    }
+
+    /// Recorrer el árbol de extents hasta la hoja que contiene el bloque
+    fn map_extent_block(&self, inode: &Ext4Inode, logical: u32) -> Result<Option<u64>, &'static str> {
+        let mut node: Vec<u8> = Vec::from(&inode.block[..]);
+        for _ in 0..=EXT4_EXTENT_MAX_DEPTH {
+            if read_u16(&node, 0) != EXT4_EXTENT_MAGIC {
+                return Err("Árbol de extents dañado");
+            }
+            let entries = read_u16(&node, 2) as usize;
+            let depth = read_u16(&node, 6);
+            if 12 + entries * 12 > node.len() {
+                return Err("Árbol de extents dañado");
+            }
+
+            if depth == 0 {
+                for i in 0..entries {
+                    let entry = &node[12 + i * 12..24 + i * 12];
+                    let first = read_u32(entry, 0);
+                    let raw_length = read_u16(entry, 4);
+                    // Los extents sin inicializar (preasignados) se leen como ceros
+                    let (length, initialized) = if raw_length > EXT4_EXTENT_MAX_INIT_LENGTH {
+                        (raw_length - EXT4_EXTENT_MAX_INIT_LENGTH, false)
+                    } else {
+                        (raw_length, true)
+                    };
+                    if logical >= first && logical - first < length as u32 {
+                        if !initialized {
+                            return Ok(None);
+                        }
+                        let start = read_u32(entry, 8) as u64 | ((read_u16(entry, 6) as u64) << 32);
+                        return Ok(Some(start + (logical - first) as u64));
+                    }
+                }
+                return Ok(None);
+            }
+
+            // Índice: el último cuyo primer bloque no supera el buscado
+            let child = (0..entries)
+                .map(|i| &node[12 + i * 12..24 + i * 12])
+                .take_while(|index| read_u32(index, 0) <= logical)
+                .last()
+                .map(|index| read_u32(index, 4) as u64 | ((read_u16(index, 8) as u64) << 32));
+            let child = match child {
+                Some(child) => child,
+                None => return Ok(None),
+            };
+            node = vec![0u8; self.block_size as usize];
+            if !self.read_block(child, &mut node) {
+                return Err("Error leyendo nodo de extents");
+            }
+        }
+        Err("Árbol de extents demasiado profundo")
+    }
+
+    /// Seguir el mapa de bloques directos e indirectos (ext2/ext3)
+    fn map_indirect_block(&self, inode: &Ext4Inode, logical: u32) -> Result<Option<u64>, &'static str> {
+        let per_block = (self.block_size / 4) as u64;
+        let mut logical = logical as u64;
+        if logical < EXT4_DIRECT_BLOCKS as u64 {
+            return Ok(non_zero(read_u32(&inode.block, logical as usize * 4)));
+        }
+        logical -= EXT4_DIRECT_BLOCKS as u64;
+
+        // Nivel de indirección (1, 2 o 3) y entrada de i_block que lo inicia
+        let mut span = per_block;
+        let mut level = 1;
+        while logical >= span {
+            logical -= span;
+            span *= per_block;
+            level += 1;
+            if level > 3 {
+                return Err("Bloque fuera del mapa de bloques");
+            }
+        }
+
+        let mut block = match non_zero(read_u32(&inode.block, (EXT4_DIRECT_BLOCKS + level - 1) * 4)) {
+            Some(block) => block,
+            None => return Ok(None),
+        };
+        let mut buffer = vec![0u8; self.block_size as usize];
+        for _ in 0..level {
+            span /= per_block;
+            if !self.read_block(block, &mut buffer) {
+                return Err("Error leyendo bloque indirecto");
+            }
+            block = match non_zero(read_u32(&buffer, ((logical / span) * 4) as usize)) {
+                Some(next) => next,
+                None => return Ok(None),
+            };
+            logical %= span;
+        }
+        Ok(Some(block))
+    }
+
+    /// Leer datos de un inodo a partir de un desplazamiento (los huecos se leen como ceros)
+    pub fn read_inode_data(&self, inode: &Ext4Inode, offset: u64, buffer: &mut [u8]) -> Result<usize, &'static str> {
+        if offset >= inode.size {
+            return Ok(0);
+        }
+        let length = core::cmp::min(buffer.len() as u64, inode.size - offset) as usize;
+        let block_size = self.block_size as u64;
+        let mut block = vec![0u8; self.block_size as usize];
+        let mut done = 0;
+
+        while done < length {
+            let position = offset + done as u64;
+            let block_offset = (position % block_size) as usize;
+            let chunk = core::cmp::min(self.block_size as usize - block_offset, length - done);
+            match self.map_block(inode, (position / block_size) as u32)? {
+                Some(physical) => {
+                    if !self.read_block(physical, &mut block) {
+                        return Err("Error leyendo bloque de datos");
+                    }
+                    buffer[done..done + chunk].copy_from_slice(&block[block_offset..block_offset + chunk]);
+                }
+                None => buffer[done..done + chunk].fill(0),
+            }
+            done += chunk;
+        }
+        Ok(length)
+    }
+
+    /// Leer un bloque lógico completo de un inodo
+    fn read_logical_block(&self, inode: &Ext4Inode, logical: u32, buffer: &mut [u8]) -> Result<(), &'static str> {
+        match self.map_block(inode, logical)? {
+            Some(physical) if self.read_block(physical, buffer) => Ok(()),
+            Some(_) => Err("Error leyendo bloque de directorio"),
+            None => {
+                buffer.fill(0);
+                Ok(())
+            }
+        }
+    }
+
+    /// Entradas de un bloque de directorio
+    ///
+    /// Se omiten las entradas libres (inodo 0), que incluyen los nodos
+    /// internos del htree y la cola de checksum.
+    fn parse_directory_block(&self, block: &[u8], entries: &mut Vec<Ext4DirEntry>) -> Result<(), &'static str> {
+        let file_type = self.superblock()?.feature_incompat & EXT4_FEATURE_INCOMPAT_FILETYPE != 0;
+        let mut offset = 0;
+        while offset + 8 <= block.len() {
+            let inode = read_u32(block, offset);
+            let record_length = read_u16(block, offset + 4) as usize;
+            if record_length < 8 || record_length % 4 != 0 || offset + record_length > block.len() {
+                return Err("Entrada de directorio inválida");
+            }
+            let (name_length, entry_type) = if file_type {
+                (block[offset + 6] as usize, block[offset + 7])
+            } else {
+                (read_u16(block, offset + 6) as usize, 0)
+            };
+            if inode != 0 && 8 + name_length <= record_length {
+                let name = &block[offset + 8..offset + 8 + name_length];
+                entries.push(Ext4DirEntry {
+                    inode,
+                    name: String::from_utf8_lossy(name).into_owned(),
+                    file_type: entry_type,
+                });
+            }
+            offset += record_length;
+        }
+        Ok(())
+    }
+
+    /// Leer todas las entradas de un directorio (sin "." ni "..")
+    pub fn read_directory(&self, directory: &Ext4Inode) -> Result<Vec<Ext4DirEntry>, &'static str> {
+        if !directory.is_directory() {
+            return Err("La ruta no es un directorio");
+        }
+        let mut entries = Vec::new();
+        let mut block = vec![0u8; self.block_size as usize];
+        let blocks = (directory.size + self.block_size as u64 - 1) / self.block_size as u64;
+        for logical in 0..blocks {
+            self.read_logical_block(directory, logical as u32, &mut block)?;
+            self.parse_directory_block(&block, &mut entries)?;
+        }
+        entries.retain(|entry| entry.name != "." && entry.name != "..");
+        Ok(entries)
+    }
+
+    /// Buscar un nombre en un directorio
+    ///
+    /// En los directorios indexados se desciende por el htree hasta la hoja
+    /// cuyo rango de hashes contiene el nombre; si el índice no se puede
+    /// usar se recorre el directorio entero.
+    pub fn lookup(&self, directory: &Ext4Inode, name: &str) -> Result<u32, &'static str> {
+        if !directory.is_directory() {
+            return Err("Componente de ruta no es un directorio");
+        }
+        let indexed = directory.flags & EXT4_INDEX_FL != 0
+            && self.superblock()?.feature_compat & EXT4_FEATURE_COMPAT_DIR_INDEX != 0;
+        if indexed {
+            if let Some(result) = self.htree_lookup(directory, name)? {
+                return result.ok_or("Ruta no encontrada");
+            }
+        }
+        self.read_directory(directory)?
+            .into_iter()
+            .find(|entry| entry.name == name)
+            .map(|entry| entry.inode)
+            .ok_or("Ruta no encontrada")
+    }
+
+    /// Búsqueda por hash en un directorio indexado
+    ///
+    /// Devuelve `None` si la raíz del índice no es válida.
+    fn htree_lookup(&self, directory: &Ext4Inode, name: &str) -> Result<Option<Option<u32>>, &'static str> {
+        let superblock = self.superblock()?;
+        let mut block = vec![0u8; self.block_size as usize];
+        self.read_logical_block(directory, 0, &mut block)?;
+
+        // dx_root: "." (12 bytes), ".." hasta el final del bloque y dx_root_info en 24
+        let mut hash_version = block[0x1C];
+        let info_length = block[0x1D] as usize;
+        let levels = block[0x1E];
+        if read_u32(&block, 0x18) != 0 || info_length != 8 || levels > 2 || hash_version > EXT4_DX_HASH_TEA {
+            return Ok(None);
+        }
+        if superblock.flags & EXT4_FLAGS_UNSIGNED_HASH != 0 {
+            hash_version += 3;
+        }
+        let (hash, _) = ext4_dirhash(name.as_bytes(), hash_version, &superblock.hash_seed);
+
+        // Descender por los niveles de índice
+        let mut offset = 0x18 + info_length;
+        let mut leaf = 0;
+        let mut next_hash = None;
+        for level in 0..=levels {
+            let limit = read_u16(&block, offset) as usize;
+            let count = read_u16(&block, offset + 2) as usize;
+            if count == 0 || count > limit || offset + count * 8 > block.len() {
+                return Ok(None);
+            }
+            // La primera entrada no tiene hash (cubre desde 0). Una entrada con
+            // el bit de colisión no se elige: el nombre puede estar en la hoja
+            // anterior y se continúa en la siguiente si hace falta.
+            let mut chosen = 0;
+            for i in 1..count {
+                if read_u32(&block, offset + i * 8) > hash {
+                    break;
+                }
+                chosen = i;
+            }
+            if chosen + 1 < count {
+                next_hash = Some(read_u32(&block, offset + (chosen + 1) * 8));
+            }
+            leaf = read_u32(&block, offset + chosen * 8 + 4);
+            if level < levels {
+                self.read_logical_block(directory, leaf, &mut block)?;
+                // Nodo interno: una entrada vacía que ocupa el bloque y después el índice
+                offset = 8;
+            }
+        }
+
+        // Hoja; si hay colisiones el nombre puede seguir en el bloque siguiente
+        loop {
+            let mut entries = Vec::new();
+            self.read_logical_block(directory, leaf, &mut block)?;
+            self.parse_directory_block(&block, &mut entries)?;
+            if let Some(entry) = entries.iter().find(|entry| entry.name == name) {
+                return Ok(Some(Some(entry.inode)));
+            }
+            match next_hash {
+                Some(next) if next & 1 != 0 && next & !1 == hash => {
+                    leaf += 1;
+                    next_hash = None;
+                }
+                _ => return Ok(Some(None)),
+            }
+        }
+    }
+
+    /// Leer el destino de un enlace simbólico
+    pub fn read_symlink(&self, inode: &Ext4Inode) -> Result<String, &'static str> {
+        if !inode.is_symlink() {
+            return Err("No es un enlace simbólico");
+        }
+        // Enlace rápido: el destino cabe en i_block y no hay bloques de datos
+        let acl_sectors = if inode.file_acl != 0 { (self.block_size / 512) as u64 } else { 0 };
+        let fast = inode.size < 60 && !inode.uses_extents() && inode.blocks.saturating_sub(acl_sectors) == 0;
+        if fast {
+            return Ok(String::from_utf8_lossy(&inode.block[..inode.size as usize]).into_owned());
+        }
+        let mut target = vec![0u8; core::cmp::min(inode.size, 4096) as usize];
+        let length = self.read_inode_data(inode, 0, &mut target)?;
+        Ok(String::from_utf8_lossy(&target[..length]).into_owned())
+    }
+
+    /// Resolver una ruta del volumen
+    ///
+    /// Se siguen los enlaces simbólicos intermedios (y el final si
+    /// `follow_final`); los destinos absolutos parten de la raíz del volumen.
+    pub fn resolve_path(&self, path: &str, follow_final: bool) -> Result<Ext4Inode, &'static str> {
+        let mut pending: Vec<String> = path
+            .split('/')
+            .filter(|component| !component.is_empty())
+            .rev()
+            .map(String::from)
+            .collect();
+        let mut stack = vec![self.read_inode(EXT4_ROOT_INODE)?];
+        let mut links = 0;
+
+        while let Some(component) = pending.pop() {
+            if component == "." {
+                continue;
+            }
+            if component == ".." {
+                if stack.len() > 1 {
+                    stack.pop();
+                }
+                continue;
+            }
+            let current = stack.last().ok_or("Ruta no encontrada")?;
+            let inode = self.read_inode(self.lookup(current, &component)?)?;
+            if inode.is_symlink() && (follow_final || !pending.is_empty()) {
+                links += 1;
+                if links > VFS_MAX_SYMLINK_DEPTH {
+                    return Err("Demasiados niveles de enlaces simbólicos");
+                }
+                let target = self.read_symlink(&inode)?;
+                if target.starts_with('/') {
+                    stack.truncate(1);
+                }
+                pending.extend(target.split('/').filter(|component| !component.is_empty()).rev().map(String::from));
+            } else {
+                stack.push(inode);
+            }
+        }
+
+        stack.pop().ok_or("Ruta no encontrada")
+    }
+
+    /// Leer el destino de un enlace simbólico por ruta
+    pub fn read_link(&self, path: &str) -> Result<String, &'static str> {
+        self.read_symlink(&self.resolve_path(path, false)?)
+    }
+
+    /// Leer datos de un archivo a partir de un desplazamiento
+    pub fn read_file(&self, path: &str, offset: u64, buffer: &mut [u8]) -> Result<usize, &'static str> {
+        let inode = self.resolve_path(path, true)?;
+        if inode.is_directory() {
+            return Err("No se puede leer un directorio como archivo");
+        }
+        self.read_inode_data(&inode, offset, buffer)
+    }
+
+    /// Información de un inodo para el VFS
+    fn vfs_file_info(&self, name: &str, inode: &Ext4Inode) -> FileInfo {
+        let mut file_info = FileInfo::new(0, name, inode.node_type());
+        file_info.size = inode.size;
+        file_info.block_size = self.block_size;
+        file_info.block_count = inode.blocks * 512 / self.block_size as u64;
+        file_info.permissions = FilePermissions::new(
+            ((inode.mode >> 6) & 7) as u8,
+            ((inode.mode >> 3) & 7) as u8,
+            (inode.mode & 7) as u8,
+        );
+        file_info.owner_id = inode.uid;
+        file_info.group_id = inode.gid;
+        file_info.created_time = inode.created_time;
+        file_info.modified_time = inode.modified_time;
+        file_info.accessed_time = inode.accessed_time;
+        file_info.link_count = inode.links_count as u32;
+        file_info.device = self.device_id;
+        file_info.inode = inode.number as u64;
+        file_info
+    }
+}
+
+impl FileSystemDriver for Ext4Driver {
+    fn open(&mut self, file_path: &str, create: bool) -> Result<FileInfo, &'static str> {
+        match self.resolve_path(file_path, true) {
+            Ok(inode) => Ok(self.vfs_file_info(path_name(file_path), &inode)),
+            Err("Ruta no encontrada") if create => Err("Sistema de archivos de solo lectura"),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn read_file(&mut self, file_path: &str, buffer: &mut [u8], offset: u64) -> Result<usize, &'static str> {
+        Ext4Driver::read_file(self, file_path, offset, buffer)
+    }
+
+    fn write_file(&mut self, _file_path: &str, _buffer: &[u8], _offset: u64) -> Result<usize, &'static str> {
+        Err("Sistema de archivos de solo lectura")
+    }
+
+    fn read_dir(&mut self, dir_path: &str) -> Result<Vec<FileInfo>, &'static str> {
+        let directory = self.resolve_path(dir_path, true)?;
+        let mut infos = Vec::new();
+        for entry in self.read_directory(&directory)? {
+            let inode = self.read_inode(entry.inode)?;
+            infos.push(self.vfs_file_info(&entry.name, &inode));
+        }
+        Ok(infos)
+    }
+
+    fn stat(&mut self, file_path: &str) -> Result<FileInfo, &'static str> {
+        let inode = self.resolve_path(file_path, true)?;
+        Ok(self.vfs_file_info(path_name(file_path), &inode))
+    }
+
+    fn unlink(&mut self, _file_path: &str) -> Result<(), &'static str> {
+        Err("Sistema de archivos de solo lectura")
+    }
+
+    fn truncate(&mut self, _file_path: &str, _size: u64) -> Result<(), &'static str> {
+        Err("Sistema de archivos de solo lectura")
+    }
+
+    fn rename(&mut self, _old_path: &str, _new_path: &str) -> Result<(), &'static str> {
+        Err("Sistema de archivos de solo lectura")
+    }
+}
+
+/// Calcular el hash de un nombre para un directorio indexado
+///
+/// Devuelve el hash principal (con el bit de colisión a cero) y el secundario,
+/// con los mismos algoritmos que Linux (`ext4fs_dirhash`).
+pub fn ext4_dirhash(name: &[u8], hash_version: u8, seed: &[u32; 4]) -> (u32, u32) {
+    let mut buffer = [0x6745_2301u32, 0xEFCD_AB89, 0x98BA_DCFE, 0x1032_5476];
+    if seed.iter().any(|&word| word != 0) {
+        buffer = *seed;
+    }
+
+    let unsigned = matches!(hash_version, EXT4_DX_HASH_LEGACY_UNSIGNED | EXT4_DX_HASH_HALF_MD4_UNSIGNED | EXT4_DX_HASH_TEA_UNSIGNED);
+    let (hash, minor_hash) = match hash_version {
+        EXT4_DX_HASH_LEGACY | EXT4_DX_HASH_LEGACY_UNSIGNED => (dx_hack_hash(name, unsigned), 0),
+        EXT4_DX_HASH_HALF_MD4 | EXT4_DX_HASH_HALF_MD4_UNSIGNED => {
+            let mut input = [0u32; 8];
+            let mut rest = name;
+            loop {
+                str_to_hash_buffer(rest, &mut input, unsigned);
+                half_md4_transform(&mut buffer, &input);
+                if rest.len() <= 32 {
+                    break;
+                }
+                rest = &rest[32..];
+            }
+            (buffer[1], buffer[2])
+        }
+        _ => {
+            let mut input = [0u32; 4];
+            let mut rest = name;
+            loop {
+                str_to_hash_buffer(rest, &mut input, unsigned);
+                tea_transform(&mut buffer, &input);
+                if rest.len() <= 16 {
+                    break;
+                }
+                rest = &rest[16..];
+            }
+            (buffer[0], buffer[1])
+        }
+    };
+
+    // El bit bajo marca las colisiones y 0xFFFFFFFE está reservado como fin de índice
+    let mut hash = hash & !1;
+    if hash == 0x7FFF_FFFF << 1 {
+        hash = (0x7FFF_FFFF - 1) << 1;
+    }
+    (hash, minor_hash)
+}
+
+/// Hash heredado de ext3 ("dx_hack_hash")
+fn dx_hack_hash(name: &[u8], unsigned: bool) -> u32 {
+    let (mut hash0, mut hash1) = (0x12A3_FE2Du32, 0x37AB_E8F9u32);
+    for &byte in name {
+        let value = if unsigned { byte as i32 } else { byte as i8 as i32 };
+        let mut hash = hash1.wrapping_add(hash0 ^ (value.wrapping_mul(7_152_373) as u32));
+        if hash & 0x8000_0000 != 0 {
+            hash = hash.wrapping_sub(0x7FFF_FFFF);
+        }
+        hash1 = hash0;
+        hash0 = hash;
+    }
+    hash0 << 1
+}
+
+/// Empaquetar un nombre en palabras de 32 bits rellenas con su longitud
+fn str_to_hash_buffer(name: &[u8], output: &mut [u32], unsigned: bool) {
+    let length = name.len() as u32;
+    let mut pad = length | (length << 8);
+    pad |= pad << 16;
+
+    let mut value = pad;
+    let mut words = 0;
+    for (i, &byte) in name.iter().take(output.len() * 4).enumerate() {
+        let byte = if unsigned { byte as i32 } else { byte as i8 as i32 };
+        value = (byte as u32).wrapping_add(value << 8);
+        if i % 4 == 3 {
+            output[words] = value;
+            words += 1;
+            value = pad;
+        }
+    }
+    if words < output.len() {
+        output[words] = value;
+        words += 1;
+    }
+    for word in output.iter_mut().skip(words) {
+        *word = pad;
+    }
+}
+
+/// Transformación MD4 reducida de los hashes de directorio
+fn half_md4_transform(buffer: &mut [u32; 4], input: &[u32; 8]) {
+    const K2: u32 = 0o13240474631;
+    const K3: u32 = 0o15666365641;
+    let f = |x: u32, y: u32, z: u32| z ^ (x & (y ^ z));
+    let g = |x: u32, y: u32, z: u32| (x & y).wrapping_add((x ^ y) & z);
+    let h = |x: u32, y: u32, z: u32| x ^ y ^ z;
+    let round = |a: u32, value: u32, x: u32, shift: u32| a.wrapping_add(value).wrapping_add(x).rotate_left(shift);
+
+    let [mut a, mut b, mut c, mut d] = *buffer;
+
+    a = round(a, f(b, c, d), input[0], 3);
+    d = round(d, f(a, b, c), input[1], 7);
+    c = round(c, f(d, a, b), input[2], 11);
+    b = round(b, f(c, d, a), input[3], 19);
+    a = round(a, f(b, c, d), input[4], 3);
+    d = round(d, f(a, b, c), input[5], 7);
+    c = round(c, f(d, a, b), input[6], 11);
+    b = round(b, f(c, d, a), input[7], 19);
+
+    a = round(a, g(b, c, d), input[1].wrapping_add(K2), 3);
+    d = round(d, g(a, b, c), input[3].wrapping_add(K2), 5);
+    c = round(c, g(d, a, b), input[5].wrapping_add(K2), 9);
+    b = round(b, g(c, d, a), input[7].wrapping_add(K2), 13);
+    a = round(a, g(b, c, d), input[0].wrapping_add(K2), 3);
+    d = round(d, g(a, b, c), input[2].wrapping_add(K2), 5);
+    c = round(c, g(d, a, b), input[4].wrapping_add(K2), 9);
+    b = round(b, g(c, d, a), input[6].wrapping_add(K2), 13);
+
+    a = round(a, h(b, c, d), input[3].wrapping_add(K3), 3);
+    d = round(d, h(a, b, c), input[7].wrapping_add(K3), 9);
+    c = round(c, h(d, a, b), input[2].wrapping_add(K3), 11);
+    b = round(b, h(c, d, a), input[6].wrapping_add(K3), 15);
+    a = round(a, h(b, c, d), input[1].wrapping_add(K3), 3);
+    d = round(d, h(a, b, c), input[5].wrapping_add(K3), 9);
+    c = round(c, h(d, a, b), input[0].wrapping_add(K3), 11);
+    b = round(b, h(c, d, a), input[4].wrapping_add(K3), 15);
+
+    buffer[0] = buffer[0].wrapping_add(a);
+    buffer[1] = buffer[1].wrapping_add(b);
+    buffer[2] = buffer[2].wrapping_add(c);
+    buffer[3] = buffer[3].wrapping_add(d);
+}
+
+/// Transformación TEA de los hashes de directorio
+fn tea_transform(buffer: &mut [u32; 4], input: &[u32; 4]) {
+    const DELTA: u32 = 0x9E37_79B9;
+    let (mut b0, mut b1) = (buffer[0], buffer[1]);
+    let [a, b, c, d] = *input;
+    let mut sum = 0u32;
+    for _ in 0..16 {
+        sum = sum.wrapping_add(DELTA);
+        b0 = b0.wrapping_add((b1 << 4).wrapping_add(a) ^ b1.wrapping_add(sum) ^ (b1 >> 5).wrapping_add(b));
+        b1 = b1.wrapping_add((b0 << 4).wrapping_add(c) ^ b0.wrapping_add(sum) ^ (b0 >> 5).wrapping_add(d));
+    }
+    buffer[0] = buffer[0].wrapping_add(b0);
+    buffer[1] = buffer[1].wrapping_add(b1);
+}
+
+/// Número de bloque de un mapa (`None` si es 0, un hueco)
+fn non_zero(block: u32) -> Option<u64> {
+    if block == 0 {
+        None
+    } else {
+        Some(block as u64)
+    }
+}
+
+/// Leer un u16 little-endian de un buffer
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+/// Leer un u32 little-endian de un buffer
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+/// Función para inicializar el driver ext2/ext3/ext4
+pub fn init_ext4(device_id: u32) -> Result<Ext4Driver, &'static str> {
+    let mut driver = Ext4Driver::new(device_id);
+    driver.initialize()?;
+    Ok(driver)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::test_util::TestDisk;
+
+    const BLOCK_SIZE: usize = 1024;
+    const IMAGE_BLOCKS: usize = 256;
+    const BLOCKS_PER_GROUP: u32 = 128;
+    const INODES_PER_GROUP: u32 = 32;
+    const INODE_SIZE: usize = 256;
+
+    // Bloques de la imagen sintética
+    const GROUP_DESCRIPTOR_BLOCK: u32 = 2;
+    const INODE_TABLE_0: u32 = 4;
+    const INODE_TABLE_1: u32 = 130;
+    const ROOT_BLOCK: u32 = 30;
+    const EXTENT_LEAF_BLOCK: u32 = 40;
+    const EXTENT_DATA_BLOCK: u32 = 50;
+    const EXTENT_TAIL_BLOCK: u32 = 52;
+    const UNINITIALIZED_BLOCK: u32 = 60;
+    const LEGACY_FIRST_BLOCK: u32 = 70;
+    const INDIRECT_BLOCK: u32 = 90;
+    const HTREE_BLOCK: u32 = 100;
+    const SLOW_LINK_BLOCK: u32 = 110;
+    const FAR_BLOCK: u32 = 140;
+
+    // Inodos de la imagen sintética
+    const EXTENTS_INODE: u32 = 12;
+    const LEGACY_INODE: u32 = 13;
+    const BIG_INODE: u32 = 14;
+    const FAST_INODE: u32 = 15;
+    const SLOW_INODE: u32 = 16;
+    const FAR_INODE: u32 = 33;
+    const BIG_FIRST_CHILD: u32 = 40;
+
+    /// Semilla del hash de directorios (UUID 00112233-4455-6677-8899-aabbccddeeff)
+    const HASH_SEED: [u32; 4] = [0x3322_1100, 0x7766_5544, 0xBBAA_9988, 0xFFEE_DDCC];
+
+    const SLOW_TARGET: &str = "./././././././././././././././././././././././././././legacy.bin";
+    const FAR_CONTENT: &[u8] = b"segundo grupo\n";
+
+    fn put16(data: &mut [u8], offset: usize, value: u16) {
+        data[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn put32(data: &mut [u8], offset: usize, value: u32) {
+        data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn block(image: &mut [u8], number: u32) -> &mut [u8] {
+        &mut image[number as usize * BLOCK_SIZE..(number as usize + 1) * BLOCK_SIZE]
+    }
+
+    /// Cabecera de un nodo del árbol de extents
+    fn extent_header(node: &mut [u8], entries: u16, max: u16, depth: u16) {
+        put16(node, 0, EXT4_EXTENT_MAGIC);
+        put16(node, 2, entries);
+        put16(node, 4, max);
+        put16(node, 6, depth);
+    }
+
+    /// Hoja de extents: (primer bloque lógico, longitud en bruto, bloque físico)
+    fn extent_leaf(node: &mut [u8], max: u16, extents: &[(u32, u16, u32)]) {
+        extent_header(node, extents.len() as u16, max, 0);
+        for (i, &(first, length, start)) in extents.iter().enumerate() {
+            let entry = 12 + i * 12;
+            put32(node, entry, first);
+            put16(node, entry + 4, length);
+            put32(node, entry + 8, start);
+        }
+    }
+
+    /// Escribir un inodo en la tabla de su grupo
+    fn write_inode(image: &mut [u8], number: u32, mode: u16, size: u64, flags: u32, blocks: u32, i_block: &[u8]) {
+        let index = number - 1;
+        let table = if index / INODES_PER_GROUP == 0 { INODE_TABLE_0 } else { INODE_TABLE_1 };
+        let offset = table as usize * BLOCK_SIZE + (index % INODES_PER_GROUP) as usize * INODE_SIZE;
+        let inode = &mut image[offset..offset + INODE_SIZE];
+        put16(inode, 0, mode);
+        put32(inode, 4, size as u32);
+        put16(inode, 26, 1);
+        put32(inode, 28, blocks);
+        put32(inode, 32, flags);
+        inode[40..40 + i_block.len()].copy_from_slice(i_block);
+        put32(inode, 108, (size >> 32) as u32);
+    }
+
+    /// Inodo cuyos datos son un único extent
+    fn write_extent_inode(image: &mut [u8], number: u32, mode: u16, size: u64, start: u32, length: u16) {
+        let mut i_block = [0u8; 60];
+        extent_leaf(&mut i_block, 4, &[(0, length, start)]);
+        write_inode(image, number, mode, size, EXT4_EXTENTS_FL, length as u32 * 2, &i_block);
+    }
+
+    /// Bloque de directorio lineal; la última entrada ocupa el resto del bloque
+    fn directory_block(data: &mut [u8], entries: &[(u32, &str, u8)]) {
+        let mut offset = 0;
+        for (i, &(inode, name, file_type)) in entries.iter().enumerate() {
+            let length = if i + 1 == entries.len() { data.len() - offset } else { (8 + name.len() + 3) & !3 };
+            put32(data, offset, inode);
+            put16(data, offset + 4, length as u16);
+            data[offset + 6] = name.len() as u8;
+            data[offset + 7] = file_type;
+            data[offset + 8..offset + 8 + name.len()].copy_from_slice(name.as_bytes());
+            offset += length;
+        }
+    }
+
+    fn name_hash(name: &str) -> u32 {
+        ext4_dirhash(name.as_bytes(), EXT4_DX_HASH_HALF_MD4, &HASH_SEED).0
+    }
+
+    /// Nombres del directorio indexado y hash que separa sus dos hojas
+    ///
+    /// La entrada del índice para la segunda hoja lleva el bit de colisión:
+    /// los nombres con el hash de corte pueden estar en cualquiera de las dos.
+    /// "stray" cae por hash en la primera hoja y se guarda en la segunda, donde
+    /// solo lo encuentra un recorrido lineal.
+    fn htree_layout() -> (Vec<(String, u32)>, u32, String) {
+        let mut names: Vec<(String, u32)> =
+            (0..20).map(|i| (alloc::format!("file-{:02}", i), BIG_FIRST_CHILD + i)).collect();
+        names.sort_by_key(|(name, _)| name_hash(name));
+        let split = name_hash(&names[10].0);
+        let stray = (0..)
+            .map(|i| alloc::format!("stray-{}", i))
+            .find(|name| name_hash(name) < split)
+            .unwrap();
+        (names, split, stray)
+    }
+
+    /// Hojas del directorio indexado; el nombre con el hash de corte va a la
+    /// primera o a la segunda según `collision_first`
+    fn write_htree_leaves(image: &mut [u8], collision_first: bool) {
+        let (names, split, stray) = htree_layout();
+        let mut first: Vec<(u32, &str, u8)> = Vec::new();
+        let mut second: Vec<(u32, &str, u8)> = vec![(BIG_FIRST_CHILD + 20, stray.as_str(), 1)];
+        for (name, inode) in &names {
+            let hash = name_hash(name);
+            if hash < split || (hash == split && collision_first) {
+                first.push((*inode, name.as_str(), 1));
+            } else {
+                second.push((*inode, name.as_str(), 1));
+            }
+        }
+        directory_block(block(image, HTREE_BLOCK + 1), &first);
+        directory_block(block(image, HTREE_BLOCK + 2), &second);
+    }
+
+    /// Volumen ext4 de 256 KiB con dos grupos de bloques
+    fn ext4_image(desc_size: u16) -> Vec<u8> {
+        let mut image = vec![0u8; IMAGE_BLOCKS * BLOCK_SIZE];
+
+        // Superbloque
+        let superblock = &mut image[1024..2048];
+        put32(superblock, 0, INODES_PER_GROUP * 2);
+        put32(superblock, 4, IMAGE_BLOCKS as u32);
+        put32(superblock, 20, 1);
+        put32(superblock, 24, 0);
+        put32(superblock, 32, BLOCKS_PER_GROUP);
+        put32(superblock, 40, INODES_PER_GROUP);
+        put16(superblock, 56, EXT4_SUPER_MAGIC);
+        put16(superblock, 58, 1);
+        put32(superblock, 76, 1);
+        put16(superblock, 88, INODE_SIZE as u16);
+        put32(superblock, 92, EXT4_FEATURE_COMPAT_DIR_INDEX);
+        let mut incompat = EXT4_FEATURE_INCOMPAT_FILETYPE | EXT4_FEATURE_INCOMPAT_EXTENTS;
+        if desc_size > EXT4_MIN_DESC_SIZE {
+            incompat |= EXT4_FEATURE_INCOMPAT_64BIT;
+            put16(superblock, 0xFE, desc_size);
+        }
+        put32(superblock, 96, incompat);
+        superblock[120..124].copy_from_slice(b"test");
+        for (i, word) in HASH_SEED.iter().enumerate() {
+            put32(superblock, 0xEC + i * 4, *word);
+        }
+        superblock[0xFC] = EXT4_DX_HASH_HALF_MD4;
+
+        // Descriptores de grupo
+        let descriptors = block(&mut image, GROUP_DESCRIPTOR_BLOCK);
+        put32(descriptors, 8, INODE_TABLE_0);
+        put32(descriptors, desc_size as usize + 8, INODE_TABLE_1);
+
+        // Raíz
+        write_extent_inode(&mut image, EXT4_ROOT_INODE, EXT4_S_IFDIR | 0o755, BLOCK_SIZE as u64, ROOT_BLOCK, 1);
+        directory_block(
+            block(&mut image, ROOT_BLOCK),
+            &[
+                (EXT4_ROOT_INODE, ".", 2),
+                (EXT4_ROOT_INODE, "..", 2),
+                (EXTENTS_INODE, "extents.bin", 1),
+                (LEGACY_INODE, "legacy.bin", 1),
+                (BIG_INODE, "big", 2),
+                (FAST_INODE, "fast", 7),
+                (SLOW_INODE, "slow", 7),
+                (FAR_INODE, "far.txt", 1),
+            ],
+        );
+
+        // Árbol de extents de profundidad 1: [0, 2) datos, [2, 4) hueco,
+        // 4 sin inicializar y 5 datos
+        let mut i_block = [0u8; 60];
+        extent_header(&mut i_block, 1, 4, 1);
+        put32(&mut i_block, 12, 0);
+        put32(&mut i_block, 16, EXTENT_LEAF_BLOCK);
+        write_inode(&mut image, EXTENTS_INODE, EXT4_S_IFREG | 0o644, 6 * BLOCK_SIZE as u64, EXT4_EXTENTS_FL, 10, &i_block);
+        extent_leaf(
+            block(&mut image, EXTENT_LEAF_BLOCK),
+            84,
+            &[
+                (0, 2, EXTENT_DATA_BLOCK),
+                (4, EXT4_EXTENT_MAX_INIT_LENGTH + 1, UNINITIALIZED_BLOCK),
+                (5, 1, EXTENT_TAIL_BLOCK),
+            ],
+        );
+        block(&mut image, EXTENT_DATA_BLOCK).fill(b'A');
+        block(&mut image, EXTENT_DATA_BLOCK + 1).fill(b'B');
+        block(&mut image, EXTENT_TAIL_BLOCK).fill(b'C');
+        block(&mut image, UNINITIALIZED_BLOCK).fill(b'X');
+
+        // Mapa ext2: 12 directos con un hueco en el 3 y un indirecto simple
+        // con los bloques lógicos 12 y 14
+        let mut i_block = [0u8; 60];
+        for logical in 0..EXT4_DIRECT_BLOCKS as u32 {
+            if logical != 3 {
+                put32(&mut i_block, logical as usize * 4, LEGACY_FIRST_BLOCK + logical);
+                block(&mut image, LEGACY_FIRST_BLOCK + logical).fill(logical as u8 + 1);
+            }
+        }
+        put32(&mut i_block, EXT4_DIRECT_BLOCKS * 4, INDIRECT_BLOCK);
+        write_inode(&mut image, LEGACY_INODE, EXT4_S_IFREG | 0o644, 15 * BLOCK_SIZE as u64, 0, 28, &i_block);
+        let indirect = block(&mut image, INDIRECT_BLOCK);
+        put32(indirect, 0, INDIRECT_BLOCK + 1);
+        put32(indirect, 8, INDIRECT_BLOCK + 2);
+        block(&mut image, INDIRECT_BLOCK + 1).fill(13);
+        block(&mut image, INDIRECT_BLOCK + 2).fill(15);
+
+        // Directorio indexado: raíz dx y dos hojas
+        let mut i_block = [0u8; 60];
+        extent_leaf(&mut i_block, 4, &[(0, 3, HTREE_BLOCK)]);
+        write_inode(&mut image, BIG_INODE, EXT4_S_IFDIR | 0o755, 3 * BLOCK_SIZE as u64, EXT4_EXTENTS_FL | EXT4_INDEX_FL, 6, &i_block);
+        let (_, split, _) = htree_layout();
+        let root = block(&mut image, HTREE_BLOCK);
+        directory_block(&mut root[..12], &[(BIG_INODE, ".", 2)]);
+        directory_block(&mut root[12..], &[(EXT4_ROOT_INODE, "..", 2)]);
+        root[0x1C] = EXT4_DX_HASH_HALF_MD4;
+        root[0x1D] = 8;
+        put16(root, 0x20, ((BLOCK_SIZE - 0x20) / 8) as u16);
+        put16(root, 0x22, 2);
+        put32(root, 0x24, 1);
+        put32(root, 0x28, split | 1);
+        put32(root, 0x2C, 2);
+        write_htree_leaves(&mut image, true);
+
+        // Enlaces: el rápido en i_block, el lento en un bloque de datos
+        write_inode(&mut image, FAST_INODE, EXT4_S_IFLNK | 0o777, 11, 0, 0, b"extents.bin");
+        write_extent_inode(&mut image, SLOW_INODE, EXT4_S_IFLNK | 0o777, SLOW_TARGET.len() as u64, SLOW_LINK_BLOCK, 1);
+        block(&mut image, SLOW_LINK_BLOCK)[..SLOW_TARGET.len()].copy_from_slice(SLOW_TARGET.as_bytes());
+
+        // Archivo cuyo inodo está en el segundo grupo
+        write_extent_inode(&mut image, FAR_INODE, EXT4_S_IFREG | 0o600, FAR_CONTENT.len() as u64, FAR_BLOCK, 1);
+        block(&mut image, FAR_BLOCK)[..FAR_CONTENT.len()].copy_from_slice(FAR_CONTENT);
+
+        image
+    }
+
+    fn mount(image: Vec<u8>) -> (TestDisk, Ext4Driver) {
+        let disk = TestDisk::with_data(image, 512);
+        let driver = init_ext4(disk.device_id).unwrap();
+        (disk, driver)
+    }
+
+    fn read_all(driver: &Ext4Driver, path: &str) -> Vec<u8> {
+        let mut data = vec![0u8; 32 * BLOCK_SIZE];
+        let length = driver.read_file(path, 0, &mut data).unwrap();
+        data.truncate(length);
+        data
+    }
+
+    #[test]
+    fn test_dirhash_matches_e2fsprogs() {
+        // Valores de `debugfs -R "dx_hash -h <versión> [-s <semilla>] <nombre>"`
+        let long = b"a-much-longer-file-name-that-spans-more-than-32-bytes.dat";
+        let none = [0u32; 4];
+        assert_eq!(ext4_dirhash(b"readme.txt", EXT4_DX_HASH_LEGACY, &none), (0xEC3B_2F76, 0));
+        assert_eq!(ext4_dirhash(long, EXT4_DX_HASH_LEGACY, &none), (0x6464_94A6, 0));
+        assert_eq!(ext4_dirhash(b"readme.txt", EXT4_DX_HASH_HALF_MD4, &none), (0x0447_1772, 0xDAB4_4F73));
+        assert_eq!(ext4_dirhash(long, EXT4_DX_HASH_HALF_MD4, &none), (0xA0C0_7974, 0x47C3_4D66));
+        assert_eq!(ext4_dirhash(b"readme.txt", EXT4_DX_HASH_HALF_MD4, &HASH_SEED), (0x4544_530A, 0x2A55_C647));
+        assert_eq!(ext4_dirhash(long, EXT4_DX_HASH_HALF_MD4, &HASH_SEED), (0x232D_8A44, 0x3691_E259));
+        assert_eq!(ext4_dirhash("ñame".as_bytes(), EXT4_DX_HASH_HALF_MD4, &none), (0xD22E_AD4C, 0x2CCA_A15F));
+        assert_eq!(ext4_dirhash(b"readme.txt", EXT4_DX_HASH_TEA, &none), (0xBF91_66DE, 0xB792_A5F7));
+        assert_eq!(ext4_dirhash(long, EXT4_DX_HASH_TEA, &none), (0x2D10_9152, 0x7B45_C29F));
+        assert_eq!(ext4_dirhash(b"readme.txt", EXT4_DX_HASH_TEA, &HASH_SEED), (0xC1F5_A83C, 0xFB13_6985));
+        assert_eq!(ext4_dirhash(long, EXT4_DX_HASH_TEA, &HASH_SEED), (0xFA78_FCBE, 0xD995_3709));
+    }
+
+    #[test]
+    fn test_superblock_validation() {
+        let image = ext4_image(EXT4_MIN_DESC_SIZE);
+        let superblock = Ext4Superblock::parse(&image[1024..2048]).unwrap();
+        assert_eq!(superblock.block_size(), 1024);
+        assert_eq!(superblock.group_count(), 2);
+        assert_eq!(superblock.volume_name, "test");
+        assert_eq!(superblock.hash_seed, HASH_SEED);
+
+        let mut bad_magic = image[1024..2048].to_vec();
+        put16(&mut bad_magic, 56, 0x1234);
+        assert!(Ext4Superblock::parse(&bad_magic).is_err());
+        let mut bad_inode_size = image[1024..2048].to_vec();
+        put16(&mut bad_inode_size, 88, 200);
+        assert_eq!(Ext4Superblock::parse(&bad_inode_size).unwrap_err(), "Geometría ext inválida");
+        let mut bad_desc_size = image[1024..2048].to_vec();
+        bad_desc_size[96] |= EXT4_FEATURE_INCOMPAT_64BIT as u8;
+        put16(&mut bad_desc_size, 0xFE, 48);
+        assert_eq!(Ext4Superblock::parse(&bad_desc_size).unwrap_err(), "Geometría ext inválida");
+
+        let mut compressed = image;
+        compressed[1024 + 96] |= EXT4_FEATURE_INCOMPAT_COMPRESSION as u8;
+        let disk = TestDisk::with_data(compressed, 512);
+        assert_eq!(init_ext4(disk.device_id).err(), Some("Característica ext no soportada"));
+    }
+
+    #[test]
+    fn test_extent_tree_holes_and_uninitialized_extents() {
+        let (_disk, driver) = mount(ext4_image(EXT4_MIN_DESC_SIZE));
+        let inode = driver.read_inode(EXTENTS_INODE).unwrap();
+        assert!(inode.uses_extents());
+        let mapped: Vec<Option<u64>> = (0..7).map(|logical| driver.map_block(&inode, logical).unwrap()).collect();
+        assert_eq!(
+            mapped,
+            [
+                Some(EXTENT_DATA_BLOCK as u64),
+                Some(EXTENT_DATA_BLOCK as u64 + 1),
+                None,
+                None,
+                None,
+                Some(EXTENT_TAIL_BLOCK as u64),
+                None,
+            ]
+        );
+
+        let data = read_all(&driver, "/extents.bin");
+        assert_eq!(data.len(), 6 * BLOCK_SIZE);
+        let expected = [b'A', b'B', 0, 0, 0, b'C'];
+        for (logical, chunk) in data.chunks(BLOCK_SIZE).enumerate() {
+            assert!(chunk.iter().all(|&byte| byte == expected[logical]), "bloque lógico {}", logical);
+        }
+
+        // Lectura que cruza del segundo bloque de datos al hueco
+        let mut buffer = [0xFFu8; 8];
+        assert_eq!(driver.read_file("/extents.bin", 2 * BLOCK_SIZE as u64 - 4, &mut buffer).unwrap(), 8);
+        assert_eq!(buffer, [b'B', b'B', b'B', b'B', 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_damaged_extent_tree_is_reported() {
+        let mut image = ext4_image(EXT4_MIN_DESC_SIZE);
+        put16(block(&mut image, EXTENT_LEAF_BLOCK), 0, 0);
+        let (_disk, driver) = mount(image);
+        let inode = driver.read_inode(EXTENTS_INODE).unwrap();
+        assert_eq!(driver.map_block(&inode, 0).unwrap_err(), "Árbol de extents dañado");
+    }
+
+    #[test]
+    fn test_indirect_block_map() {
+        let (_disk, driver) = mount(ext4_image(EXT4_MIN_DESC_SIZE));
+        let inode = driver.read_inode(LEGACY_INODE).unwrap();
+        assert!(!inode.uses_extents());
+        assert_eq!(driver.map_block(&inode, 0).unwrap(), Some(LEGACY_FIRST_BLOCK as u64));
+        assert_eq!(driver.map_block(&inode, 3).unwrap(), None);
+        assert_eq!(driver.map_block(&inode, 11).unwrap(), Some(LEGACY_FIRST_BLOCK as u64 + 11));
+        assert_eq!(driver.map_block(&inode, 12).unwrap(), Some(INDIRECT_BLOCK as u64 + 1));
+        assert_eq!(driver.map_block(&inode, 13).unwrap(), None);
+        assert_eq!(driver.map_block(&inode, 14).unwrap(), Some(INDIRECT_BLOCK as u64 + 2));
+        // Sin bloque doble indirecto
+        assert_eq!(driver.map_block(&inode, 12 + 256).unwrap(), None);
+
+        let data = read_all(&driver, "/legacy.bin");
+        assert_eq!(data.len(), 15 * BLOCK_SIZE);
+        for (logical, chunk) in data.chunks(BLOCK_SIZE).enumerate() {
+            let expected = if logical == 3 || logical == 13 { 0 } else { logical as u8 + 1 };
+            assert!(chunk.iter().all(|&byte| byte == expected), "bloque lógico {}", logical);
+        }
+    }
+
+    #[test]
+    fn test_group_descriptor_sizes() {
+        for desc_size in [EXT4_MIN_DESC_SIZE, 64] {
+            let (_disk, mut driver) = mount(ext4_image(desc_size));
+            assert_eq!(driver.superblock().unwrap().desc_size, desc_size);
+            assert_eq!(read_all(&driver, "/far.txt"), FAR_CONTENT);
+            let info = FileSystemDriver::stat(&mut driver, "/far.txt").unwrap();
+            assert_eq!(info.inode, FAR_INODE as u64);
+            assert_eq!(info.size, FAR_CONTENT.len() as u64);
+        }
+    }
+
+    #[test]
+    fn test_htree_lookup() {
+        let (names, _, stray) = htree_layout();
+        let (_disk, mut driver) = mount(ext4_image(EXT4_MIN_DESC_SIZE));
+        let big = driver.resolve_path("/big", true).unwrap();
+        assert_eq!(big.flags & EXT4_INDEX_FL, EXT4_INDEX_FL);
+
+        // Cada nombre se encuentra en la hoja que indica su hash; el del hash
+        // de corte está en la primera aunque la segunda empiece por ese hash
+        for (name, inode) in &names {
+            assert_eq!(driver.lookup(&big, name), Ok(*inode), "{}", name);
+            assert_eq!(driver.htree_lookup(&big, name).unwrap(), Some(Some(*inode)));
+        }
+        assert_eq!(driver.lookup(&big, "missing"), Err("Ruta no encontrada"));
+
+        // El índice no lleva a la hoja donde está "stray"
+        assert_eq!(driver.htree_lookup(&big, &stray).unwrap(), Some(None));
+        assert_eq!(driver.lookup(&big, &stray), Err("Ruta no encontrada"));
+
+        // El listado recorre todas las hojas y omite la raíz del índice
+        let listing = FileSystemDriver::read_dir(&mut driver, "/big").unwrap();
+        assert_eq!(listing.len(), names.len() + 1);
+    }
+
+    #[test]
+    fn test_htree_lookup_follows_hash_collisions() {
+        let (names, split, _) = htree_layout();
+        let mut image = ext4_image(EXT4_MIN_DESC_SIZE);
+        write_htree_leaves(&mut image, false);
+        let (_disk, driver) = mount(image);
+        let big = driver.resolve_path("/big", true).unwrap();
+        let (name, inode) = names.iter().find(|(name, _)| name_hash(name) == split).unwrap();
+        assert_eq!(driver.lookup(&big, name), Ok(*inode));
+    }
+
+    #[test]
+    fn test_htree_invalid_root_falls_back_to_linear_scan() {
+        let (names, _, stray) = htree_layout();
+        let mut image = ext4_image(EXT4_MIN_DESC_SIZE);
+        block(&mut image, HTREE_BLOCK)[0x1D] = 0;
+        let (_disk, driver) = mount(image);
+        let big = driver.resolve_path("/big", true).unwrap();
+        assert_eq!(driver.htree_lookup(&big, &stray).unwrap(), None);
+        assert_eq!(driver.lookup(&big, &stray), Ok(BIG_FIRST_CHILD + 20));
+        assert_eq!(driver.lookup(&big, &names[0].0), Ok(names[0].1));
+    }
+
+    #[test]
+    fn test_fast_and_slow_symlinks() {
+        let (_disk, mut driver) = mount(ext4_image(EXT4_MIN_DESC_SIZE));
+        assert_eq!(driver.read_link("/fast").unwrap(), "extents.bin");
+        assert_eq!(driver.read_link("/slow").unwrap(), SLOW_TARGET);
+        assert_eq!(driver.resolve_path("/fast", true).unwrap().number, EXTENTS_INODE);
+        assert_eq!(driver.resolve_path("/slow", true).unwrap().number, LEGACY_INODE);
+        assert_eq!(driver.resolve_path("/fast", false).unwrap().number, FAST_INODE);
+        assert_eq!(FileSystemDriver::stat(&mut driver, "/slow").unwrap().size, 15 * BLOCK_SIZE as u64);
+        assert_eq!(FileSystemDriver::write_file(&mut driver, "/fast", b"x", 0).unwrap_err(), "Sistema de archivos de solo lectura");
+    }
+}
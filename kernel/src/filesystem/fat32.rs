@@ -2,10 +2,15 @@
 //! 
 //! Implementa un driver completo para el sistema de archivos FAT32
 //! con soporte para lectura, escritura y gestión de directorios.
+//! El mismo driver maneja volúmenes FAT12 y FAT16, que solo se diferencian
+//! en el tamaño de las entradas de la FAT y en el directorio raíz fijo.
 
 use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
 use core::ptr::NonNull;
 use core::mem;
+use alloc::{string::String, vec, vec::Vec};
+
+use super::vfs::{FileInfo, FilePermissions, FileSystemDriver, NodeType};
 
 /// Estructura del sector de arranque FAT32
 #[derive(Debug, Clone, Copy)]
@@ -112,6 +117,587 @@ pub const FAT32_ATTR_DIRECTORY: u8 = 0x10;
 pub const FAT32_ATTR_ARCHIVE: u8 = 0x20;
 pub const FAT32_ATTR_LONG_NAME: u8 = 0x0F;
 
+/// Valores especiales de la tabla FAT32
+pub const FAT32_FREE_CLUSTER: u32 = 0x00000000;
+pub const FAT32_BAD_CLUSTER: u32 = 0x0FFFFFF7;
+pub const FAT32_END_OF_CHAIN_MIN: u32 = 0x0FFFFFF8;
+pub const FAT32_END_OF_CHAIN: u32 = 0x0FFFFFFF;
+pub const FAT32_CLUSTER_MASK: u32 = 0x0FFFFFFF;
+
+/// Marcadores de entradas de directorio
+pub const FAT32_ENTRY_END: u8 = 0x00;
+pub const FAT32_ENTRY_DELETED: u8 = 0xE5;
+pub const FAT32_DIRECTORY_ENTRY_SIZE: usize = 32;
+
+/// Firmas del sector FSInfo
+pub const FAT32_FSINFO_LEAD_SIGNATURE: u32 = 0x41615252;
+pub const FAT32_FSINFO_STRUCT_SIGNATURE: u32 = 0x61417272;
+pub const FAT32_FSINFO_TRAIL_SIGNATURE: u32 = 0xAA550000;
+/// Valor de FSInfo que indica "desconocido"
+pub const FAT32_FSINFO_UNKNOWN: u32 = 0xFFFFFFFF;
+
+/// Información del sector FSInfo de FAT32
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fat32FsInfo {
+    /// Número de clústeres libres (0xFFFFFFFF si es desconocido)
+    pub free_count: u32,
+    /// Pista del siguiente clúster libre (0xFFFFFFFF si es desconocida)
+    pub next_free: u32,
+    /// El sector FSInfo en disco tenía firmas válidas
+    pub valid: bool,
+}
+
+impl Fat32FsInfo {
+    /// FSInfo sin información
+    pub fn unknown() -> Self {
+        Self {
+            free_count: FAT32_FSINFO_UNKNOWN,
+            next_free: FAT32_FSINFO_UNKNOWN,
+            valid: false,
+        }
+    }
+}
+
+/// Posición de una entrada dentro de un directorio
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fat32EntryLocation {
+    /// Clúster del directorio que contiene la entrada
+    pub cluster: u32,
+    /// Índice de la entrada dentro del clúster
+    pub index: u32,
+}
+
+/// Entrada de directorio decodificada, con su nombre largo si lo tiene
+#[derive(Debug, Clone)]
+pub struct Fat32DirEntryInfo {
+    /// Entrada corta (8.3) con los metadatos
+    pub entry: Fat32DirectoryEntry,
+    /// Posición de la entrada corta
+    pub location: Fat32EntryLocation,
+    /// Posición de la primera entrada de la secuencia (LFN o la propia entrada corta)
+    pub first_slot: Fat32EntryLocation,
+    /// Número de entradas que ocupa (LFN + corta)
+    pub slot_count: u32,
+    /// Nombre visible: el nombre largo si existe, si no el nombre corto
+    pub name: String,
+}
+
+impl Fat32DirEntryInfo {
+    /// Verificar si la entrada es un directorio
+    pub fn is_directory(&self) -> bool {
+        self.entry.is_directory()
+    }
+
+    /// Atributos FAT de la entrada
+    pub fn attributes(&self) -> u8 {
+        self.entry.attributes
+    }
+
+    /// Tamaño del archivo en bytes
+    pub fn size(&self) -> u32 {
+        self.entry.file_size
+    }
+
+    /// Verificar si el nombre coincide (sin distinguir mayúsculas) con el largo o el corto
+    pub fn matches(&self, filename: &str) -> bool {
+        if names_equal_ignore_case(&self.name, filename) {
+            return true;
+        }
+        let (short_name, len) = Fat32Driver::format_short_name(&self.entry);
+        short_name[..len].eq_ignore_ascii_case(filename.as_bytes())
+    }
+}
+
+impl Fat32DirectoryEntry {
+    /// Entrada de directorio vacía
+    pub fn empty() -> Self {
+        Self {
+            name: [0x20; 8],
+            extension: [0x20; 3],
+            attributes: 0,
+            reserved: 0,
+            creation_time_ms: 0,
+            creation_time: 0,
+            creation_date: 0,
+            access_date: 0,
+            cluster_high: 0,
+            modification_time: 0,
+            modification_date: 0,
+            cluster_low: 0,
+            file_size: 0,
+        }
+    }
+
+    /// Decodificar una entrada desde 32 bytes de directorio
+    pub fn from_bytes(data: &[u8]) -> Self {
+        let mut name = [0u8; 8];
+        let mut extension = [0u8; 3];
+        name.copy_from_slice(&data[0..8]);
+        extension.copy_from_slice(&data[8..11]);
+
+        Self {
+            name,
+            extension,
+            attributes: data[11],
+            reserved: data[12],
+            creation_time_ms: data[13],
+            creation_time: u16::from_le_bytes([data[14], data[15]]),
+            creation_date: u16::from_le_bytes([data[16], data[17]]),
+            access_date: u16::from_le_bytes([data[18], data[19]]),
+            cluster_high: u16::from_le_bytes([data[20], data[21]]),
+            modification_time: u16::from_le_bytes([data[22], data[23]]),
+            modification_date: u16::from_le_bytes([data[24], data[25]]),
+            cluster_low: u16::from_le_bytes([data[26], data[27]]),
+            file_size: u32::from_le_bytes([data[28], data[29], data[30], data[31]]),
+        }
+    }
+
+    /// Codificar la entrada en 32 bytes de directorio
+    pub fn to_bytes(&self) -> [u8; 32] {
+        let mut data = [0u8; 32];
+        data[0..8].copy_from_slice(&self.name);
+        data[8..11].copy_from_slice(&self.extension);
+        data[11] = self.attributes;
+        data[12] = self.reserved;
+        data[13] = self.creation_time_ms;
+        data[14..16].copy_from_slice(&{ self.creation_time }.to_le_bytes());
+        data[16..18].copy_from_slice(&{ self.creation_date }.to_le_bytes());
+        data[18..20].copy_from_slice(&{ self.access_date }.to_le_bytes());
+        data[20..22].copy_from_slice(&{ self.cluster_high }.to_le_bytes());
+        data[22..24].copy_from_slice(&{ self.modification_time }.to_le_bytes());
+        data[24..26].copy_from_slice(&{ self.modification_date }.to_le_bytes());
+        data[26..28].copy_from_slice(&{ self.cluster_low }.to_le_bytes());
+        data[28..32].copy_from_slice(&{ self.file_size }.to_le_bytes());
+        data
+    }
+
+    /// Primer clúster de la cadena del archivo
+    pub fn first_cluster(&self) -> u32 {
+        ((self.cluster_high as u32) << 16) | self.cluster_low as u32
+    }
+
+    /// Establecer el primer clúster de la cadena del archivo
+    pub fn set_first_cluster(&mut self, cluster: u32) {
+        self.cluster_high = (cluster >> 16) as u16;
+        self.cluster_low = (cluster & 0xFFFF) as u16;
+    }
+
+    /// Verificar si la entrada es un directorio
+    pub fn is_directory(&self) -> bool {
+        self.attributes & FAT32_ATTR_DIRECTORY != 0
+    }
+
+    /// Verificar si la entrada es "." o ".."
+    pub fn is_dot_entry(&self) -> bool {
+        self.name[0] == b'.'
+    }
+}
+
+/// Convertir un nombre a formato 8.3 (nombre y extensión rellenos con espacios)
+pub fn short_name_from_str(filename: &str) -> Result<([u8; 8], [u8; 3]), &'static str> {
+    let bytes = filename.as_bytes();
+    if bytes.is_empty() || bytes == b"." || bytes == b".." {
+        return Err("Nombre de archivo inválido");
+    }
+
+    let (base, ext) = match bytes.iter().rposition(|&b| b == b'.') {
+        Some(0) => return Err("Nombre de archivo inválido"),
+        Some(dot) => (&bytes[..dot], &bytes[dot + 1..]),
+        None => (bytes, &bytes[bytes.len()..]),
+    };
+
+    if base.len() > 8 || ext.len() > 3 {
+        return Err("Nombre de archivo no es 8.3");
+    }
+
+    let mut name = [0x20u8; 8];
+    let mut extension = [0x20u8; 3];
+    for (dst, &src) in name.iter_mut().zip(base.iter()) {
+        *dst = short_name_char(src)?;
+    }
+    for (dst, &src) in extension.iter_mut().zip(ext.iter()) {
+        *dst = short_name_char(src)?;
+    }
+
+    // 0xE5 en el primer byte marca una entrada eliminada; se almacena como 0x05
+    if name[0] == FAT32_ENTRY_DELETED {
+        name[0] = 0x05;
+    }
+
+    Ok((name, extension))
+}
+
+/// Validar y convertir a mayúsculas un carácter de nombre corto
+fn short_name_char(byte: u8) -> Result<u8, &'static str> {
+    match byte {
+        b'a'..=b'z' => Ok(byte - b'a' + b'A'),
+        b'A'..=b'Z' | b'0'..=b'9' => Ok(byte),
+        b'!' | b'#' | b'$' | b'%' | b'&' | b'\'' | b'(' | b')' | b'-' | b'@' | b'^' | b'_'
+        | b'`' | b'{' | b'}' | b'~' => Ok(byte),
+        0x80..=0xFF => Ok(byte),
+        _ => Err("Carácter no válido en nombre 8.3"),
+    }
+}
+
+/// Marcador de la última entrada (la primera en disco) de una secuencia LFN
+pub const FAT32_LFN_LAST_ENTRY: u8 = 0x40;
+/// Máscara del número de secuencia de una entrada LFN
+pub const FAT32_LFN_SEQUENCE_MASK: u8 = 0x1F;
+/// Caracteres UTF-16 almacenados en cada entrada LFN
+pub const FAT32_LFN_CHARS_PER_ENTRY: usize = 13;
+/// Longitud máxima de un nombre largo en unidades UTF-16
+pub const FAT32_LFN_MAX_LENGTH: usize = 255;
+
+/// Flags NT en el byte reservado: nombre base y extensión en minúsculas
+pub const FAT32_NT_LOWERCASE_BASE: u8 = 0x08;
+pub const FAT32_NT_LOWERCASE_EXT: u8 = 0x10;
+
+/// Desplazamientos de los caracteres UTF-16 dentro de una entrada LFN
+const LFN_CHAR_OFFSETS: [usize; FAT32_LFN_CHARS_PER_ENTRY] = [1, 3, 5, 7, 9, 14, 16, 18, 20, 22, 24, 28, 30];
+
+/// Calcular el checksum del nombre corto que enlaza las entradas LFN
+pub fn lfn_checksum(name: &[u8; 8], extension: &[u8; 3]) -> u8 {
+    let mut sum: u8 = 0;
+    for &byte in name.iter().chain(extension.iter()) {
+        sum = ((sum & 1) << 7).wrapping_add(sum >> 1).wrapping_add(byte);
+    }
+    sum
+}
+
+/// Verificar si una entrada de 32 bytes es una entrada de nombre largo
+pub fn is_long_name_slot(slot: &[u8]) -> bool {
+    slot[11] & FAT32_ATTR_LONG_NAME == FAT32_ATTR_LONG_NAME
+}
+
+/// Construir las entradas LFN para un nombre, en el orden en que van en disco
+pub fn build_long_name_slots(long_name: &[u16], checksum: u8) -> Vec<[u8; 32]> {
+    let slot_count = (long_name.len() + FAT32_LFN_CHARS_PER_ENTRY - 1) / FAT32_LFN_CHARS_PER_ENTRY;
+    let mut slots = Vec::with_capacity(slot_count);
+
+    for sequence in (1..=slot_count).rev() {
+        let mut slot = [0u8; 32];
+        slot[0] = sequence as u8 | if sequence == slot_count { FAT32_LFN_LAST_ENTRY } else { 0 };
+        slot[11] = FAT32_ATTR_LONG_NAME;
+        slot[12] = 0;
+        slot[13] = checksum;
+
+        let base = (sequence - 1) * FAT32_LFN_CHARS_PER_ENTRY;
+        for (i, &offset) in LFN_CHAR_OFFSETS.iter().enumerate() {
+            // Tras el nombre va un terminador 0x0000 y el resto se rellena con 0xFFFF
+            let unit = match base + i {
+                pos if pos < long_name.len() => long_name[pos],
+                pos if pos == long_name.len() => 0x0000,
+                _ => 0xFFFF,
+            };
+            slot[offset..offset + 2].copy_from_slice(&unit.to_le_bytes());
+        }
+        slots.push(slot);
+    }
+
+    slots
+}
+
+/// Acumulador de una secuencia de entradas LFN mientras se recorre un directorio
+struct LongNameBuilder {
+    /// Unidades UTF-16 del nombre
+    buffer: [u16; FAT32_LFN_MAX_LENGTH + FAT32_LFN_CHARS_PER_ENTRY],
+    /// Número de secuencia esperado para la próxima entrada
+    expected: u8,
+    /// Número total de entradas LFN de la secuencia
+    slot_count: u8,
+    /// Checksum del nombre corto asociado
+    checksum: u8,
+    /// Posición de la primera entrada LFN
+    first_slot: Option<Fat32EntryLocation>,
+}
+
+impl LongNameBuilder {
+    /// Crear un acumulador vacío
+    fn new() -> Self {
+        Self {
+            buffer: [0; FAT32_LFN_MAX_LENGTH + FAT32_LFN_CHARS_PER_ENTRY],
+            expected: 0,
+            slot_count: 0,
+            checksum: 0,
+            first_slot: None,
+        }
+    }
+
+    /// Descartar la secuencia en curso
+    fn reset(&mut self) {
+        self.expected = 0;
+        self.slot_count = 0;
+        self.first_slot = None;
+    }
+
+    /// Añadir una entrada LFN a la secuencia
+    fn push(&mut self, slot: &[u8], location: Fat32EntryLocation) {
+        let order = slot[0];
+        let sequence = order & FAT32_LFN_SEQUENCE_MASK;
+
+        if order & FAT32_LFN_LAST_ENTRY != 0 {
+            // Comienzo de una secuencia nueva
+            if sequence == 0 || sequence as usize * FAT32_LFN_CHARS_PER_ENTRY > self.buffer.len() {
+                self.reset();
+                return;
+            }
+            self.slot_count = sequence;
+            self.checksum = slot[13];
+            self.first_slot = Some(location);
+        } else if self.first_slot.is_none() || sequence != self.expected || slot[13] != self.checksum {
+            // Entrada huérfana o fuera de orden
+            self.reset();
+            return;
+        }
+
+        let base = (sequence as usize - 1) * FAT32_LFN_CHARS_PER_ENTRY;
+        for (i, &offset) in LFN_CHAR_OFFSETS.iter().enumerate() {
+            self.buffer[base + i] = u16::from_le_bytes([slot[offset], slot[offset + 1]]);
+        }
+        self.expected = sequence - 1;
+    }
+
+    /// Terminar la secuencia con la entrada corta y devolver el nombre largo si es válido
+    fn finish(&mut self, entry: &Fat32DirectoryEntry) -> Option<(String, Fat32EntryLocation, u32)> {
+        let result = match self.first_slot {
+            Some(first_slot) if self.expected == 0 && self.checksum == lfn_checksum(&entry.name, &entry.extension) => {
+                let units = &self.buffer[..self.slot_count as usize * FAT32_LFN_CHARS_PER_ENTRY];
+                let len = units.iter().position(|&unit| unit == 0x0000).unwrap_or(units.len());
+                let len = core::cmp::min(len, FAT32_LFN_MAX_LENGTH);
+                let name: String = core::char::decode_utf16(units[..len].iter().copied())
+                    .map(|c| c.unwrap_or(core::char::REPLACEMENT_CHARACTER))
+                    .collect();
+                if name.is_empty() {
+                    None
+                } else {
+                    Some((name, first_slot, self.slot_count as u32))
+                }
+            }
+            _ => None,
+        };
+        self.reset();
+        result
+    }
+}
+
+/// Comparar dos nombres sin distinguir mayúsculas y minúsculas
+pub fn names_equal_ignore_case(a: &str, b: &str) -> bool {
+    a.chars()
+        .flat_map(char::to_uppercase)
+        .eq(b.chars().flat_map(char::to_uppercase))
+}
+
+/// Verificar si un carácter puede aparecer en un nombre largo
+fn is_valid_long_name_char(c: char) -> bool {
+    !(c < ' ' || matches!(c, '"' | '*' | '/' | ':' | '<' | '>' | '?' | '\\' | '|'))
+}
+
+/// Normalizar un nombre largo: quitar espacios y puntos finales y validarlo
+pub fn normalize_long_name(filename: &str) -> Result<&str, &'static str> {
+    let trimmed = filename.trim_end_matches(|c| c == ' ' || c == '.');
+    if trimmed.is_empty() || filename == "." || filename == ".." {
+        return Err("Nombre de archivo inválido");
+    }
+    if !trimmed.chars().all(is_valid_long_name_char) {
+        return Err("Carácter no válido en nombre de archivo");
+    }
+    if trimmed.encode_utf16().count() > FAT32_LFN_MAX_LENGTH {
+        return Err("Nombre de archivo demasiado largo");
+    }
+    Ok(trimmed)
+}
+
+/// Intentar representar un nombre sin entradas LFN
+///
+/// Es posible si el nombre es 8.3 válido y cada parte está toda en mayúsculas
+/// o toda en minúsculas (esto último se guarda con los flags NT).
+pub fn short_name_exact(filename: &str) -> Option<([u8; 8], [u8; 3], u8)> {
+    let (name, extension) = short_name_from_str(filename).ok()?;
+    let bytes = filename.as_bytes();
+    let (base, ext) = match bytes.iter().rposition(|&b| b == b'.') {
+        Some(dot) => (&bytes[..dot], &bytes[dot + 1..]),
+        None => (bytes, &bytes[bytes.len()..]),
+    };
+
+    let case_flag = |part: &[u8], flag: u8| -> Option<u8> {
+        let has_lower = part.iter().any(|b| b.is_ascii_lowercase());
+        let has_upper = part.iter().any(|b| b.is_ascii_uppercase());
+        match (has_lower, has_upper) {
+            (true, true) => None,
+            (true, false) => Some(flag),
+            _ => Some(0),
+        }
+    };
+
+    let flags = case_flag(base, FAT32_NT_LOWERCASE_BASE)? | case_flag(ext, FAT32_NT_LOWERCASE_EXT)?;
+    Some((name, extension, flags))
+}
+
+/// Generar la base y extensión de un alias corto a partir de un nombre largo
+///
+/// Devuelve los caracteres OEM en mayúsculas sin la cola numérica "~N".
+pub fn short_alias_basis(long_name: &str) -> ([u8; 8], usize, [u8; 3]) {
+    let stripped = long_name.trim_start_matches('.');
+    let (base, ext) = match stripped.rfind('.') {
+        Some(dot) => (&stripped[..dot], &stripped[dot + 1..]),
+        None => (stripped, ""),
+    };
+
+    let to_oem = |c: char| -> Option<u8> {
+        if c == ' ' || c == '.' {
+            return None;
+        }
+        let c = c.to_ascii_uppercase();
+        if c.is_ascii() {
+            let byte = c as u8;
+            Some(short_name_char(byte).unwrap_or(b'_'))
+        } else {
+            Some(b'_')
+        }
+    };
+
+    let mut basis = [0x20u8; 8];
+    let mut basis_len = 0;
+    for byte in base.chars().filter_map(to_oem) {
+        if basis_len == 8 {
+            break;
+        }
+        basis[basis_len] = byte;
+        basis_len += 1;
+    }
+    if basis_len == 0 {
+        basis[0] = b'_';
+        basis_len = 1;
+    }
+
+    let mut extension = [0x20u8; 3];
+    for (dst, byte) in extension.iter_mut().zip(ext.chars().filter_map(to_oem)) {
+        *dst = byte;
+    }
+
+    (basis, basis_len, extension)
+}
+
+/// Aplicar la cola numérica "~N" a una base de alias corto
+pub fn short_alias_with_tail(basis: &[u8; 8], basis_len: usize, number: u32) -> [u8; 8] {
+    let mut digits = [0u8; 10];
+    let mut digit_count = 0;
+    let mut value = number;
+    loop {
+        digits[digit_count] = b'0' + (value % 10) as u8;
+        digit_count += 1;
+        value /= 10;
+        if value == 0 {
+            break;
+        }
+    }
+
+    let tail_len = digit_count + 1;
+    let keep = core::cmp::min(basis_len, 8 - tail_len);
+    let mut name = [0x20u8; 8];
+    name[..keep].copy_from_slice(&basis[..keep]);
+    name[keep] = b'~';
+    for i in 0..digit_count {
+        name[keep + 1 + i] = digits[digit_count - 1 - i];
+    }
+    name
+}
+
+/// Convertir segundos desde la época Unix a fecha y hora FAT
+///
+/// Las fechas anteriores a 1980 (el origen de FAT) se limitan a 1980-01-01.
+pub fn unix_to_fat_datetime(unix_seconds: u64) -> (u16, u16) {
+    const FAT_EPOCH: u64 = 315_532_800; // 1980-01-01 00:00:00 UTC
+    let seconds = core::cmp::max(unix_seconds, FAT_EPOCH);
+
+    let days = (seconds / 86_400) as i64;
+    let secs_of_day = seconds % 86_400;
+
+    // Conversión de días a fecha civil (algoritmo de Howard Hinnant)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u16;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u16;
+    let year = (yoe + era * 400 + if month <= 2 { 1 } else { 0 }) as u16;
+
+    let date = ((core::cmp::min(year - 1980, 127)) << 9) | (month << 5) | day;
+    let time = (((secs_of_day / 3600) as u16) << 11)
+        | ((((secs_of_day / 60) % 60) as u16) << 5)
+        | (((secs_of_day % 60) / 2) as u16);
+
+    (date, time)
+}
+
+/// Convertir fecha y hora FAT a segundos desde la época Unix
+///
+/// Devuelve 0 si la fecha no está establecida.
+pub fn fat_datetime_to_unix(date: u16, time: u16) -> u64 {
+    let year = 1980 + (date >> 9) as i64;
+    let month = ((date >> 5) & 0x0F) as i64;
+    let day = (date & 0x1F) as i64;
+    if month == 0 || month > 12 || day == 0 {
+        return 0;
+    }
+
+    // Conversión de fecha civil a días (algoritmo de Howard Hinnant)
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = (era * 146_097 + doe - 719_468) as u64;
+
+    let seconds = ((time >> 11) as u64) * 3600 + (((time >> 5) & 0x3F) as u64) * 60 + ((time & 0x1F) as u64) * 2;
+    days * 86_400 + seconds
+}
+
+/// Variantes de FAT según el número de clústeres del volumen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FatType {
+    /// Entradas de 12 bits (menos de 4085 clústeres)
+    Fat12,
+    /// Entradas de 16 bits (menos de 65525 clústeres)
+    Fat16,
+    /// Entradas de 28 bits
+    Fat32,
+}
+
+impl FatType {
+    /// Determinar la variante a partir del número de clústeres de datos
+    pub fn from_cluster_count(cluster_count: u32) -> Self {
+        if cluster_count < 4085 {
+            FatType::Fat12
+        } else if cluster_count < 65525 {
+            FatType::Fat16
+        } else {
+            FatType::Fat32
+        }
+    }
+
+    /// Valor a partir del cual una entrada marca clúster defectuoso o fin de cadena
+    fn bad_cluster_marker(&self) -> u32 {
+        match self {
+            FatType::Fat12 => 0x0FF7,
+            FatType::Fat16 => 0xFFF7,
+            FatType::Fat32 => FAT32_BAD_CLUSTER,
+        }
+    }
+
+    /// Máscara de bits de una entrada
+    fn entry_mask(&self) -> u32 {
+        match self {
+            FatType::Fat12 => 0x0FFF,
+            FatType::Fat16 => 0xFFFF,
+            FatType::Fat32 => FAT32_CLUSTER_MASK,
+        }
+    }
+}
+
 /// Estados del driver FAT32
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Fat32State {
@@ -131,6 +717,8 @@ pub struct Fat32Driver {
     pub driver_id: u32,
     /// Estado del driver
     pub state: Fat32State,
+    /// Variante de FAT del volumen
+    pub fat_type: FatType,
     /// Sector de arranque
     pub boot_sector: Fat32BootSector,
     /// Número de sectores por FAT
@@ -141,10 +729,16 @@ pub struct Fat32Driver {
     pub cluster_size: u32,
     /// Número del primer sector de datos
     pub data_start: u32,
-    /// Número del primer clúster del directorio raíz
+    /// Primer sector del directorio raíz fijo (FAT12/FAT16)
+    pub root_dir_start: u32,
+    /// Sectores del directorio raíz fijo (0 en FAT32)
+    pub root_dir_sectors: u32,
+    /// Número del primer clúster del directorio raíz (0 si el raíz es fijo)
     pub root_cluster: u32,
     /// Número total de clústeres
     pub total_clusters: u32,
+    /// Información del sector FSInfo
+    pub fs_info: Fat32FsInfo,
     /// Dispositivo de bloque asociado
     pub block_device: u32,
     /// Caché de sectores
@@ -159,6 +753,7 @@ impl Fat32Driver {
         Self {
             driver_id,
             state: Fat32State::Uninitialized,
+            fat_type: FatType::Fat32,
             boot_sector: Fat32BootSector {
                 jump_instruction: [0; 3],
                 oem_identifier: [0; 8],
@@ -194,8 +789,11 @@ impl Fat32Driver {
             sectors_per_cluster: 1,
             cluster_size: 512,
             data_start: 0,
+            root_dir_start: 0,
+            root_dir_sectors: 0,
             root_cluster: 0,
             total_clusters: 0,
+            fs_info: Fat32FsInfo::unknown(),
             block_device,
             sector_cache: [(); 64].map(|_| None),
             cache_counter: AtomicUsize::new(0),
@@ -224,29 +822,63 @@ impl Fat32Driver {
             return Err("Firma de sector de arranque inválida");
         }
 
-        // Verificar que es FAT32
-        if &self.boot_sector.fs_type[..5] != b"FAT32" {
-            return Err("No es un sistema de archivos FAT32");
+        // Validar los parámetros básicos del BPB
+        let bytes_per_sector = self.boot_sector.bytes_per_sector as u32;
+        let sectors_per_cluster = self.boot_sector.sectors_per_cluster as u32;
+        if !bytes_per_sector.is_power_of_two() || bytes_per_sector < 512 || bytes_per_sector > 4096 {
+            return Err("Tamaño de sector inválido");
+        }
+        if !sectors_per_cluster.is_power_of_two() || self.boot_sector.fat_count == 0 || self.boot_sector.reserved_sectors == 0 {
+            return Err("No es un sistema de archivos FAT");
         }
 
         // Calcular parámetros del sistema de archivos
-        self.sectors_per_fat = self.boot_sector.sectors_per_fat_32;
-        self.sectors_per_cluster = self.boot_sector.sectors_per_cluster as u32;
-        self.cluster_size = self.boot_sector.bytes_per_sector as u32 * self.sectors_per_cluster;
-        self.root_cluster = self.boot_sector.root_cluster;
-        
-        // Calcular el inicio de los datos
+        self.sectors_per_fat = if self.boot_sector.sectors_per_fat_16 != 0 {
+            self.boot_sector.sectors_per_fat_16 as u32
+        } else {
+            self.boot_sector.sectors_per_fat_32
+        };
+        self.sectors_per_cluster = sectors_per_cluster;
+        self.cluster_size = bytes_per_sector * self.sectors_per_cluster;
+
+        // Calcular el inicio del directorio raíz fijo y de los datos
         let fat_size = self.sectors_per_fat * self.boot_sector.fat_count as u32;
-        self.data_start = self.boot_sector.reserved_sectors as u32 + fat_size;
-        
+        self.root_dir_start = self.boot_sector.reserved_sectors as u32 + fat_size;
+        self.root_dir_sectors = (self.boot_sector.root_entries as u32 * FAT32_DIRECTORY_ENTRY_SIZE as u32
+            + bytes_per_sector - 1) / bytes_per_sector;
+        self.data_start = self.root_dir_start + self.root_dir_sectors;
+
         // Calcular el número total de clústeres
-        let total_sectors = if self.boot_sector.total_sectors_32 != 0 {
-            self.boot_sector.total_sectors_32
-        } else {
+        let total_sectors = if self.boot_sector.total_sectors_16 != 0 {
             self.boot_sector.total_sectors_16 as u32
+        } else {
+            self.boot_sector.total_sectors_32
         };
+        if total_sectors <= self.data_start {
+            return Err("Geometría de volumen FAT inválida");
+        }
         self.total_clusters = (total_sectors - self.data_start) / self.sectors_per_cluster;
 
+        // La variante se determina solo por el número de clústeres
+        self.fat_type = FatType::from_cluster_count(self.total_clusters);
+        match self.fat_type {
+            FatType::Fat32 => {
+                if self.root_dir_sectors != 0 || self.boot_sector.sectors_per_fat_32 == 0 {
+                    return Err("BPB FAT32 inválido");
+                }
+                self.root_cluster = self.boot_sector.root_cluster;
+            }
+            FatType::Fat12 | FatType::Fat16 => {
+                if self.root_dir_sectors == 0 {
+                    return Err("BPB FAT12/FAT16 sin directorio raíz");
+                }
+                self.root_cluster = 0;
+            }
+        }
+
+        // Leer el sector FSInfo (pista de clústeres libres)
+        self.load_fs_info();
+
         self.state = Fat32State::Initialized;
         Ok(())
     }
@@ -273,269 +905,2166 @@ impl Fat32Driver {
 
     /// Leer un sector del dispositivo
     pub fn read_sector(&mut self, sector: u32, buffer: &mut [u8]) -> bool {
-        // TODO: Implementar lectura real del dispositivo de bloque
-        // Por ahora, llenamos el buffer con datos de prueba
-        for i in 0..buffer.len() {
-            buffer[i] = (i % 256) as u8;
-        }
-        true
+        crate::drivers::storage::read_sectors(self.block_device, sector as u64, 1, buffer)
     }
 
     /// Escribir un sector al dispositivo
     pub fn write_sector(&mut self, sector: u32, buffer: &[u8]) -> bool {
-        // TODO: Implementar escritura real del dispositivo de bloque
-        // Por ahora, solo simulamos la escritura
-        true
+        crate::drivers::storage::write_sectors(self.block_device, sector as u64, 1, buffer)
+    }
+
+    /// Tamaño del sector en bytes
+    fn bytes_per_sector(&self) -> usize {
+        self.boot_sector.bytes_per_sector as usize
+    }
+
+    /// Primer sector de un clúster de datos
+    fn cluster_to_sector(&self, cluster: u32) -> u32 {
+        self.data_start + (cluster - 2) * self.sectors_per_cluster
+    }
+
+    /// Verificar si un número de clúster está dentro del área de datos
+    pub fn is_valid_cluster(&self, cluster: u32) -> bool {
+        cluster >= 2 && cluster < self.total_clusters + 2
     }
 
     /// Leer un clúster del sistema de archivos
     pub fn read_cluster(&mut self, cluster: u32, buffer: &mut [u8]) -> bool {
-        if cluster < 2 || cluster >= self.total_clusters {
+        if !self.is_valid_cluster(cluster) || buffer.len() < self.cluster_size as usize {
             return false;
         }
 
-        let sector = self.data_start + (cluster - 2) * self.sectors_per_cluster;
-        let bytes_to_read = core::cmp::min(buffer.len(), self.cluster_size as usize);
-        
+        let sector = self.cluster_to_sector(cluster);
+        let bytes_per_sector = self.bytes_per_sector();
+
         for i in 0..self.sectors_per_cluster {
-            let sector_buffer = &mut buffer[(i * self.boot_sector.bytes_per_sector as u32) as usize..];
+            let start = i as usize * bytes_per_sector;
+            let sector_buffer = &mut buffer[start..start + bytes_per_sector];
             if !self.read_sector(sector + i, sector_buffer) {
                 return false;
             }
         }
-        
+
         true
     }
 
     /// Escribir un clúster al sistema de archivos
     pub fn write_cluster(&mut self, cluster: u32, buffer: &[u8]) -> bool {
-        if cluster < 2 || cluster >= self.total_clusters {
+        if !self.is_valid_cluster(cluster) || buffer.len() < self.cluster_size as usize {
             return false;
         }
 
-        let sector = self.data_start + (cluster - 2) * self.sectors_per_cluster;
-        let bytes_to_write = core::cmp::min(buffer.len(), self.cluster_size as usize);
-        
+        let sector = self.cluster_to_sector(cluster);
+        let bytes_per_sector = self.bytes_per_sector();
+
         for i in 0..self.sectors_per_cluster {
-            let sector_buffer = &buffer[(i * self.boot_sector.bytes_per_sector as u32) as usize..];
+            let start = i as usize * bytes_per_sector;
+            let sector_buffer = &buffer[start..start + bytes_per_sector];
             if !self.write_sector(sector + i, sector_buffer) {
                 return false;
             }
         }
-        
+
+        true
+    }
+
+    /// Rellenar un clúster con ceros
+    pub fn zero_cluster(&mut self, cluster: u32) -> bool {
+        let zeros = vec![0u8; self.cluster_size as usize];
+        self.write_cluster(cluster, &zeros)
+    }
+
+    /// Desplazamiento en bytes de la entrada de un clúster dentro de la FAT
+    fn fat_entry_offset(&self, cluster: u32) -> u32 {
+        match self.fat_type {
+            FatType::Fat12 => cluster + cluster / 2,
+            FatType::Fat16 => cluster * 2,
+            FatType::Fat32 => cluster * 4,
+        }
+    }
+
+    /// Leer los bytes en bruto de una entrada (2 en FAT12/16, 4 en FAT32)
+    ///
+    /// En FAT12 una entrada puede quedar partida entre dos sectores.
+    fn read_fat_bytes(&mut self, fat_start: u32, offset: u32, bytes: &mut [u8]) -> bool {
+        let bytes_per_sector = self.bytes_per_sector() as u32;
+        let mut sector_buffer = vec![0u8; bytes_per_sector as usize];
+        let mut loaded_sector = None;
+
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            let position = offset + i as u32;
+            let sector = fat_start + position / bytes_per_sector;
+            if loaded_sector != Some(sector) {
+                if !self.read_sector(sector, &mut sector_buffer) {
+                    return false;
+                }
+                loaded_sector = Some(sector);
+            }
+            *byte = sector_buffer[(position % bytes_per_sector) as usize];
+        }
+        true
+    }
+
+    /// Escribir los bytes en bruto de una entrada, pudiendo abarcar dos sectores
+    fn write_fat_bytes(&mut self, fat_start: u32, offset: u32, bytes: &[u8]) -> bool {
+        let bytes_per_sector = self.bytes_per_sector() as u32;
+        let mut sector_buffer = vec![0u8; bytes_per_sector as usize];
+        let mut i = 0;
+
+        while i < bytes.len() {
+            let position = offset + i as u32;
+            let sector = fat_start + position / bytes_per_sector;
+            if !self.read_sector(sector, &mut sector_buffer) {
+                return false;
+            }
+            while i < bytes.len() && fat_start + (offset + i as u32) / bytes_per_sector == sector {
+                sector_buffer[((offset + i as u32) % bytes_per_sector) as usize] = bytes[i];
+                i += 1;
+            }
+            if !self.write_sector(sector, &sector_buffer) {
+                return false;
+            }
+        }
         true
     }
 
     /// Leer una entrada de la tabla FAT
+    ///
+    /// En FAT12/FAT16 los valores de clúster defectuoso y fin de cadena se
+    /// normalizan a sus equivalentes FAT32 para que el resto del driver sea común.
     pub fn read_fat_entry(&mut self, cluster: u32) -> Option<u32> {
-        if cluster >= self.total_clusters {
+        if cluster >= self.total_clusters + 2 {
             return None;
         }
 
-        let fat_sector = self.boot_sector.reserved_sectors as u32 + (cluster * 4) / self.boot_sector.bytes_per_sector as u32;
-        let fat_offset = (cluster * 4) % self.boot_sector.bytes_per_sector as u32;
-        
-        let mut sector_buffer = [0u8; 512];
-        if !self.read_sector(fat_sector, &mut sector_buffer) {
+        let fat_start = self.active_fat_start();
+        let offset = self.fat_entry_offset(cluster);
+        let mut raw = [0u8; 4];
+        let width = if self.fat_type == FatType::Fat32 { 4 } else { 2 };
+        if !self.read_fat_bytes(fat_start, offset, &mut raw[..width]) {
             return None;
         }
 
-        let fat_entry = u32::from_le_bytes([
-            sector_buffer[fat_offset as usize],
-            sector_buffer[fat_offset as usize + 1],
-            sector_buffer[fat_offset as usize + 2],
-            sector_buffer[fat_offset as usize + 3],
-        ]);
+        let value = u32::from_le_bytes(raw);
+        let value = match self.fat_type {
+            FatType::Fat12 if cluster & 1 != 0 => value >> 4,
+            _ => value,
+        } & self.fat_type.entry_mask();
 
-        Some(fat_entry & 0x0FFFFFFF)
+        if self.fat_type != FatType::Fat32 && value >= self.fat_type.bad_cluster_marker() {
+            // Normalizar a FAT32 (0xFF7 -> 0x0FFFFFF7, 0xFFF8 -> 0x0FFFFFF8, ...)
+            Some(FAT32_CLUSTER_MASK & !self.fat_type.entry_mask() | value)
+        } else {
+            Some(value)
+        }
     }
 
     /// Escribir una entrada en la tabla FAT
+    ///
+    /// Actualiza todas las copias de la FAT salvo que el volumen tenga el
+    /// espejado deshabilitado, y conserva los bits que no pertenecen a la entrada
+    /// (los 4 bits altos reservados en FAT32 o el nibble vecino en FAT12).
     pub fn write_fat_entry(&mut self, cluster: u32, value: u32) -> bool {
-        if cluster >= self.total_clusters {
+        if cluster >= self.total_clusters + 2 {
             return false;
         }
 
-        let fat_sector = self.boot_sector.reserved_sectors as u32 + (cluster * 4) / self.boot_sector.bytes_per_sector as u32;
-        let fat_offset = (cluster * 4) % self.boot_sector.bytes_per_sector as u32;
-        
-        let mut sector_buffer = [0u8; 512];
-        if !self.read_sector(fat_sector, &mut sector_buffer) {
-            return false;
-        }
+        let offset = self.fat_entry_offset(cluster);
+        let width = if self.fat_type == FatType::Fat32 { 4 } else { 2 };
+        let value = value & self.fat_type.entry_mask();
 
-        let fat_entry = value & 0x0FFFFFFF;
-        let fat_bytes = fat_entry.to_le_bytes();
-        sector_buffer[fat_offset as usize] = fat_bytes[0];
-        sector_buffer[fat_offset as usize + 1] = fat_bytes[1];
-        sector_buffer[fat_offset as usize + 2] = fat_bytes[2];
-        sector_buffer[fat_offset as usize + 3] = fat_bytes[3];
-
-        self.write_sector(fat_sector, &sector_buffer)
-    }
+        for fat_index in self.fat_copies_to_update() {
+            let fat_start = self.boot_sector.reserved_sectors as u32 + fat_index * self.sectors_per_fat;
 
-    /// Buscar un archivo en un directorio
-    pub fn find_file(&mut self, directory_cluster: u32, filename: &str) -> Option<Fat32DirectoryEntry> {
-        let mut current_cluster = directory_cluster;
-        let mut cluster_buffer = [0u8; 4096]; // Asumiendo clúster de 8 sectores
-        
-        loop {
-            if !self.read_cluster(current_cluster, &mut cluster_buffer) {
-                return None;
+            let mut raw = [0u8; 4];
+            if !self.read_fat_bytes(fat_start, offset, &mut raw[..width]) {
+                return false;
             }
+            let old_entry = u32::from_le_bytes(raw);
 
-            // Buscar en las entradas del directorio
-            for i in 0..(self.cluster_size as usize / 32) {
-                let entry_offset = i * 32;
-                if entry_offset + 32 > cluster_buffer.len() {
-                    break;
-                }
+            let new_entry = match self.fat_type {
+                FatType::Fat12 if cluster & 1 != 0 => (old_entry & 0x000F) | (value << 4),
+                FatType::Fat12 => (old_entry & 0xF000) | value,
+                FatType::Fat16 => value,
+                FatType::Fat32 => (old_entry & !FAT32_CLUSTER_MASK) | value,
+            };
 
-                let entry_data = &cluster_buffer[entry_offset..entry_offset + 32];
-                let mut entry = Fat32DirectoryEntry {
-                    name: [0; 8],
-                    extension: [0; 3],
-                    attributes: 0,
-                    reserved: 0,
-                    creation_time_ms: 0,
-                    creation_time: 0,
-                    creation_date: 0,
-                    access_date: 0,
-                    cluster_high: 0,
-                    modification_time: 0,
-                    modification_date: 0,
-                    cluster_low: 0,
-                    file_size: 0,
-                };
+            if !self.write_fat_bytes(fat_start, offset, &new_entry.to_le_bytes()[..width]) {
+                return false;
+            }
+        }
 
-                unsafe {
-                    core::ptr::copy_nonoverlapping(
-                        entry_data.as_ptr(),
-                        &mut entry as *mut Fat32DirectoryEntry as *mut u8,
-                        32,
-                    );
-                }
+        true
+    }
 
-                // Verificar si es una entrada válida
-                if entry.name[0] == 0x00 {
-                    return None; // Fin del directorio
-                }
-                if entry.name[0] == 0xE5 {
-                    continue; // Entrada eliminada
-                }
+    /// Verificar si el espejado de la FAT está deshabilitado (solo FAT32)
+    fn mirroring_disabled(&self) -> bool {
+        self.fat_type == FatType::Fat32 && self.boot_sector.extended_flags & 0x0080 != 0
+    }
 
-                // Construir el nombre del archivo
-                let mut entry_name = [0u8; 13]; // 8 + 1 + 3 + 1 para null terminator
-                let mut pos = 0;
-                
-                for &byte in &entry.name {
-                    if byte != 0x20 && pos < 8 {
-                        entry_name[pos] = byte;
-                        pos += 1;
-                    }
-                }
-                
-                if entry.extension[0] != 0x20 {
-                    entry_name[pos] = b'.';
-                    pos += 1;
-                    for &byte in &entry.extension {
-                        if byte != 0x20 && pos < 12 {
-                            entry_name[pos] = byte;
-                            pos += 1;
-                        }
-                    }
-                }
-                
-                // Convertir a mayúsculas para comparación
-                let mut entry_name_upper = [0u8; 13];
-                for i in 0..pos {
-                    entry_name_upper[i] = if entry_name[i] >= b'a' && entry_name[i] <= b'z' {
-                        entry_name[i] - b'a' + b'A'
-                    } else {
-                        entry_name[i]
-                    };
-                }
-                
-                let mut filename_upper = [0u8; 13];
-                let filename_bytes = filename.as_bytes();
-                for i in 0..core::cmp::min(filename_bytes.len(), 12) {
-                    filename_upper[i] = if filename_bytes[i] >= b'a' && filename_bytes[i] <= b'z' {
-                        filename_bytes[i] - b'a' + b'A'
-                    } else {
-                        filename_bytes[i]
-                    };
-                }
-                
-                if entry_name_upper[..pos] == filename_upper[..core::cmp::min(filename_bytes.len(), 12)] {
-                    return Some(entry);
-                }
-            }
+    /// Primer sector de la FAT que se usa para lectura
+    fn active_fat_start(&self) -> u32 {
+        let active_fat = if self.mirroring_disabled() {
+            (self.boot_sector.extended_flags & 0x000F) as u32
+        } else {
+            0
+        };
+        self.boot_sector.reserved_sectors as u32 + active_fat * self.sectors_per_fat
+    }
 
-            // Obtener el siguiente clúster
-            if let Some(next_cluster) = self.read_fat_entry(current_cluster) {
-                if next_cluster >= 0x0FFFFFF8 {
-                    break; // Fin de la cadena
-                }
-                current_cluster = next_cluster;
-            } else {
-                break;
-            }
+    /// Copias de la FAT que deben actualizarse en cada escritura
+    fn fat_copies_to_update(&self) -> core::ops::Range<u32> {
+        if self.mirroring_disabled() {
+            let active_fat = (self.boot_sector.extended_flags & 0x000F) as u32;
+            active_fat..active_fat + 1
+        } else {
+            0..self.boot_sector.fat_count as u32
         }
-
-        None
     }
 
-    /// Leer un archivo
-    pub fn read_file(&mut self, entry: &Fat32DirectoryEntry, buffer: &mut [u8]) -> Result<usize, &'static str> {
-        if entry.attributes & FAT32_ATTR_DIRECTORY != 0 {
+    /// Leer el sector FSInfo
+    pub fn load_fs_info(&mut self) {
+        self.fs_info = Fat32FsInfo::unknown();
+        if self.fat_type != FatType::Fat32 {
+            return;
+        }
+
+        let fs_info_sector = self.boot_sector.fs_info_sector as u32;
+        if fs_info_sector == 0 || fs_info_sector == 0xFFFF {
+            return;
+        }
+
+        let mut sector_buffer = vec![0u8; self.bytes_per_sector()];
+        if sector_buffer.len() < 512 || !self.read_sector(fs_info_sector, &mut sector_buffer) {
+            return;
+        }
+
+        let read_u32 = |offset: usize| {
+            u32::from_le_bytes([
+                sector_buffer[offset],
+                sector_buffer[offset + 1],
+                sector_buffer[offset + 2],
+                sector_buffer[offset + 3],
+            ])
+        };
+
+        if read_u32(0) != FAT32_FSINFO_LEAD_SIGNATURE
+            || read_u32(484) != FAT32_FSINFO_STRUCT_SIGNATURE
+            || read_u32(508) != FAT32_FSINFO_TRAIL_SIGNATURE
+        {
+            return;
+        }
+
+        let free_count = read_u32(488);
+        let next_free = read_u32(492);
+        self.fs_info = Fat32FsInfo {
+            free_count: if free_count <= self.total_clusters { free_count } else { FAT32_FSINFO_UNKNOWN },
+            next_free: if self.is_valid_cluster(next_free) { next_free } else { FAT32_FSINFO_UNKNOWN },
+            valid: true,
+        };
+    }
+
+    /// Escribir el sector FSInfo con los contadores actuales
+    pub fn flush_fs_info(&mut self) -> bool {
+        if !self.fs_info.valid {
+            return true;
+        }
+
+        let fs_info_sector = self.boot_sector.fs_info_sector as u32;
+        let mut sector_buffer = vec![0u8; self.bytes_per_sector()];
+        if !self.read_sector(fs_info_sector, &mut sector_buffer) {
+            return false;
+        }
+
+        sector_buffer[488..492].copy_from_slice(&self.fs_info.free_count.to_le_bytes());
+        sector_buffer[492..496].copy_from_slice(&self.fs_info.next_free.to_le_bytes());
+        self.write_sector(fs_info_sector, &sector_buffer)
+    }
+
+    /// Obtener el siguiente clúster de una cadena (None al final de la cadena)
+    pub fn next_cluster(&mut self, cluster: u32) -> Option<u32> {
+        match self.read_fat_entry(cluster) {
+            Some(next) if self.is_valid_cluster(next) => Some(next),
+            _ => None,
+        }
+    }
+
+    /// Obtener todos los clústeres de una cadena
+    pub fn cluster_chain(&mut self, start_cluster: u32) -> Result<Vec<u32>, &'static str> {
+        let mut chain = Vec::new();
+        if start_cluster == 0 {
+            return Ok(chain);
+        }
+        if !self.is_valid_cluster(start_cluster) {
+            return Err("Clúster inicial inválido");
+        }
+
+        let mut current = start_cluster;
+        loop {
+            chain.push(current);
+            if chain.len() > self.total_clusters as usize {
+                return Err("Cadena de clústeres cíclica");
+            }
+
+            match self.read_fat_entry(current) {
+                Some(next) if next >= FAT32_END_OF_CHAIN_MIN => break,
+                Some(next) if self.is_valid_cluster(next) => current = next,
+                Some(_) => return Err("Cadena de clústeres corrupta"),
+                None => return Err("Error leyendo la tabla FAT"),
+            }
+        }
+
+        Ok(chain)
+    }
+
+    /// Reservar un clúster libre, marcarlo como fin de cadena y enlazarlo
+    ///
+    /// La búsqueda empieza en la pista de FSInfo. El contador de libres de
+    /// FSInfo también es solo una pista (queda desfasado tras un desmontaje
+    /// sucio), así que siempre se recorre la FAT y el contador se corrige si
+    /// decía que no quedaba ninguno. El clúster se rellena con ceros antes de
+    /// enlazarlo para no exponer datos antiguos.
+    pub fn allocate_cluster(&mut self, previous: Option<u32>) -> Result<u32, &'static str> {
+        let first = if self.is_valid_cluster(self.fs_info.next_free) {
+            self.fs_info.next_free
+        } else {
+            2
+        };
+
+        let mut candidate = first;
+        let mut found = None;
+        for _ in 0..self.total_clusters {
+            if self.read_fat_entry(candidate) == Some(FAT32_FREE_CLUSTER) {
+                found = Some(candidate);
+                break;
+            }
+            candidate += 1;
+            if !self.is_valid_cluster(candidate) {
+                candidate = 2;
+            }
+        }
+
+        let cluster = match found {
+            Some(cluster) => cluster,
+            None => {
+                if self.fs_info.free_count != 0 {
+                    self.fs_info.free_count = 0;
+                    self.flush_fs_info();
+                }
+                return Err("No hay clústeres libres");
+            }
+        };
+
+        if !self.write_fat_entry(cluster, FAT32_END_OF_CHAIN) {
+            return Err("Error escribiendo la tabla FAT");
+        }
+        if !self.zero_cluster(cluster) {
+            self.write_fat_entry(cluster, FAT32_FREE_CLUSTER);
+            return Err("Error inicializando clúster");
+        }
+        if let Some(previous) = previous {
+            if !self.write_fat_entry(previous, cluster) {
+                self.write_fat_entry(cluster, FAT32_FREE_CLUSTER);
+                return Err("Error enlazando clúster");
+            }
+        }
+
+        self.fs_info.free_count = match self.fs_info.free_count {
+            FAT32_FSINFO_UNKNOWN => FAT32_FSINFO_UNKNOWN,
+            0 => self.count_free_clusters(),
+            free_count => free_count - 1,
+        };
+        self.fs_info.next_free = if self.is_valid_cluster(cluster + 1) { cluster + 1 } else { 2 };
+        self.flush_fs_info();
+
+        Ok(cluster)
+    }
+
+    /// Extender una cadena hasta que tenga al menos `cluster_count` clústeres
+    ///
+    /// Devuelve la cadena completa. Si `start_cluster` es 0 se crea una cadena nueva.
+    pub fn extend_chain(&mut self, start_cluster: u32, cluster_count: usize) -> Result<Vec<u32>, &'static str> {
+        let mut chain = self.cluster_chain(start_cluster)?;
+        while chain.len() < cluster_count {
+            let cluster = self.allocate_cluster(chain.last().copied())?;
+            chain.push(cluster);
+        }
+        Ok(chain)
+    }
+
+    /// Liberar una cadena de clústeres completa
+    pub fn free_cluster_chain(&mut self, start_cluster: u32) -> Result<(), &'static str> {
+        let chain = self.cluster_chain(start_cluster)?;
+        for &cluster in &chain {
+            if !self.write_fat_entry(cluster, FAT32_FREE_CLUSTER) {
+                return Err("Error escribiendo la tabla FAT");
+            }
+        }
+
+        if let Some(&first) = chain.first() {
+            if self.fs_info.free_count != FAT32_FSINFO_UNKNOWN {
+                self.fs_info.free_count += chain.len() as u32;
+            }
+            if !self.is_valid_cluster(self.fs_info.next_free) || first < self.fs_info.next_free {
+                self.fs_info.next_free = first;
+            }
+            self.flush_fs_info();
+        }
+
+        Ok(())
+    }
+
+    /// Recortar una cadena dejando solo los primeros `keep` clústeres
+    ///
+    /// Devuelve el primer clúster resultante (0 si la cadena queda vacía).
+    pub fn truncate_chain(&mut self, start_cluster: u32, keep: usize) -> Result<u32, &'static str> {
+        if start_cluster == 0 {
+            return Ok(0);
+        }
+        if keep == 0 {
+            self.free_cluster_chain(start_cluster)?;
+            return Ok(0);
+        }
+
+        let chain = self.cluster_chain(start_cluster)?;
+        if chain.len() > keep {
+            let tail = chain[keep];
+            if !self.write_fat_entry(chain[keep - 1], FAT32_END_OF_CHAIN) {
+                return Err("Error escribiendo la tabla FAT");
+            }
+            self.free_cluster_chain(tail)?;
+        }
+
+        Ok(start_cluster)
+    }
+
+    /// Clúster real de un directorio (0 se interpreta como el directorio raíz)
+    fn directory_cluster(&self, cluster: u32) -> u32 {
+        if cluster == 0 {
+            self.root_cluster
+        } else {
+            cluster
+        }
+    }
+
+    /// Obtener el nombre 8.3 de una entrada en formato "NOMBRE.EXT"
+    pub fn format_short_name(entry: &Fat32DirectoryEntry) -> ([u8; 12], usize) {
+        let mut entry_name = [0u8; 12];
+        let mut pos = 0;
+
+        for (i, &byte) in entry.name.iter().enumerate() {
+            if byte != 0x20 {
+                entry_name[pos] = if i == 0 && byte == 0x05 { FAT32_ENTRY_DELETED } else { byte };
+                pos += 1;
+            }
+        }
+
+        if entry.extension[0] != 0x20 {
+            entry_name[pos] = b'.';
+            pos += 1;
+            for &byte in &entry.extension {
+                if byte != 0x20 {
+                    entry_name[pos] = byte;
+                    pos += 1;
+                }
+            }
+        }
+
+        (entry_name, pos)
+    }
+
+    /// Nombre corto visible de una entrada, aplicando los flags NT de minúsculas
+    pub fn display_short_name(entry: &Fat32DirectoryEntry) -> String {
+        let (short_name, len) = Self::format_short_name(entry);
+        let base_len = short_name[..len].iter().position(|&b| b == b'.').unwrap_or(len);
+        short_name[..len]
+            .iter()
+            .enumerate()
+            .map(|(i, &byte)| {
+                let lowercase = if i < base_len {
+                    entry.reserved & FAT32_NT_LOWERCASE_BASE != 0
+                } else {
+                    entry.reserved & FAT32_NT_LOWERCASE_EXT != 0
+                };
+                let byte = if lowercase { byte.to_ascii_lowercase() } else { byte };
+                byte as char
+            })
+            .collect()
+    }
+
+    /// Recorrer las entradas de un directorio
+    ///
+    /// Sigue la cadena de clústeres del directorio y omite entradas eliminadas
+    /// y etiquetas de volumen. Un clúster 0 se interpreta como el directorio raíz.
+    pub fn read_dir(&mut self, directory_cluster: u32) -> Fat32DirIter<'_> {
+        let cluster = self.directory_cluster(directory_cluster);
+        Fat32DirIter::new(self, cluster)
+    }
+
+    /// Buscar una entrada en un directorio, considerando nombres largos y cortos
+    ///
+    /// Las secuencias LFN solo se aceptan si su checksum coincide con la entrada
+    /// corta que las sigue; si no, se usa únicamente el nombre corto.
+    pub fn lookup_entry(&mut self, directory_cluster: u32, filename: &str) -> Option<Fat32DirEntryInfo> {
+        self.read_dir(directory_cluster)
+            .map_while(|result| result.ok())
+            .find(|info| info.matches(filename))
+    }
+
+    /// Entrada sintética que representa el directorio raíz
+    fn root_entry_info(&self) -> Fat32DirEntryInfo {
+        let mut entry = Fat32DirectoryEntry::empty();
+        entry.attributes = FAT32_ATTR_DIRECTORY;
+        entry.set_first_cluster(self.root_cluster);
+
+        Fat32DirEntryInfo {
+            entry,
+            location: Fat32EntryLocation { cluster: 0, index: 0 },
+            first_slot: Fat32EntryLocation { cluster: 0, index: 0 },
+            slot_count: 0,
+            name: String::from("\\"),
+        }
+    }
+
+    /// Resolver una ruta completa (por ejemplo `\EFI\BOOT\BOOTX64.EFI`)
+    ///
+    /// Se aceptan `\` y `/` como separadores, y los componentes `.` y `..`.
+    /// La ruta vacía o `\` devuelve una entrada sintética para la raíz.
+    pub fn resolve_path(&mut self, path: &str) -> Result<Fat32DirEntryInfo, &'static str> {
+        let mut current = self.root_entry_info();
+
+        for component in path.split(|c| c == '\\' || c == '/') {
+            if component.is_empty() || component == "." {
+                continue;
+            }
+            if !current.is_directory() {
+                return Err("Componente de ruta no es un directorio");
+            }
+
+            let directory_cluster = current.entry.first_cluster();
+            if component == ".." && directory_cluster == self.root_cluster {
+                continue; // ".." en la raíz es la propia raíz
+            }
+
+            let next = self
+                .lookup_entry(directory_cluster, component)
+                .ok_or("Ruta no encontrada")?;
+
+            // ".." con clúster 0 apunta a la raíz
+            current = if next.is_directory() && next.entry.first_cluster() == 0 {
+                self.root_entry_info()
+            } else {
+                next
+            };
+        }
+
+        Ok(current)
+    }
+
+    /// Resolver el directorio padre de una ruta y devolver su clúster junto con el último componente
+    pub fn resolve_parent<'p>(&mut self, path: &'p str) -> Result<(u32, &'p str), &'static str> {
+        let trimmed = path.trim_end_matches(|c| c == '\\' || c == '/');
+        let (parent_path, name) = match trimmed.rfind(|c| c == '\\' || c == '/') {
+            Some(pos) => (&trimmed[..pos], &trimmed[pos + 1..]),
+            None => ("", trimmed),
+        };
+        if name.is_empty() || name == "." || name == ".." {
+            return Err("Ruta inválida");
+        }
+
+        let parent = self.resolve_path(parent_path)?;
+        if !parent.is_directory() {
+            return Err("Componente de ruta no es un directorio");
+        }
+        Ok((parent.entry.first_cluster(), name))
+    }
+
+    /// Listar un directorio a partir de su ruta
+    pub fn read_dir_path(&mut self, path: &str) -> Result<Vec<Fat32DirEntryInfo>, &'static str> {
+        let directory = self.resolve_path(path)?;
+        if !directory.is_directory() {
+            return Err("La ruta no es un directorio");
+        }
+        self.read_dir(directory.entry.first_cluster()).collect()
+    }
+
+    /// Buscar una entrada en un directorio junto con su posición
+    pub fn find_entry(&mut self, directory_cluster: u32, filename: &str) -> Option<(Fat32DirectoryEntry, Fat32EntryLocation)> {
+        self.lookup_entry(directory_cluster, filename)
+            .map(|info| (info.entry, info.location))
+    }
+
+    /// Buscar un archivo en un directorio
+    pub fn find_file(&mut self, directory_cluster: u32, filename: &str) -> Option<Fat32DirectoryEntry> {
+        self.find_entry(directory_cluster, filename).map(|(entry, _)| entry)
+    }
+
+    /// Leer un archivo
+    pub fn read_file(&mut self, entry: &Fat32DirectoryEntry, buffer: &mut [u8]) -> Result<usize, &'static str> {
+        self.read_file_at(entry, 0, buffer)
+    }
+
+    /// Leer un archivo a partir de un desplazamiento
+    pub fn read_file_at(&mut self, entry: &Fat32DirectoryEntry, offset: u64, buffer: &mut [u8]) -> Result<usize, &'static str> {
+        if entry.attributes & FAT32_ATTR_DIRECTORY != 0 {
             return Err("No se puede leer un directorio como archivo");
         }
 
-        let cluster_high = entry.cluster_high as u32;
-        let cluster_low = entry.cluster_low as u32;
-        let mut current_cluster = (cluster_high << 16) | cluster_low;
-        let mut bytes_read = 0;
-        let file_size = entry.file_size as usize;
+        let file_size = entry.file_size as u64;
+        if offset >= file_size {
+            return Ok(0);
+        }
+        let length = core::cmp::min(buffer.len() as u64, file_size - offset) as usize;
+        let cluster_size = self.cluster_size as usize;
+        let chain = self.cluster_chain(entry.first_cluster())?;
+
+        let mut cluster_buffer = vec![0u8; cluster_size];
+        let mut bytes_read = 0;
+        while bytes_read < length {
+            let position = offset as usize + bytes_read;
+            let cluster = *chain.get(position / cluster_size).ok_or("Cadena de clústeres más corta que el archivo")?;
+            if !self.read_cluster(cluster, &mut cluster_buffer) {
+                return Err("Error leyendo clúster");
+            }
+
+            let start = position % cluster_size;
+            let bytes_to_copy = core::cmp::min(cluster_size - start, length - bytes_read);
+            buffer[bytes_read..bytes_read + bytes_to_copy]
+                .copy_from_slice(&cluster_buffer[start..start + bytes_to_copy]);
+            bytes_read += bytes_to_copy;
+        }
+
+        Ok(bytes_read)
+    }
+
+    /// Verificar si un clúster de directorio se refiere al raíz fijo de FAT12/FAT16
+    fn is_fixed_root(&self, cluster: u32) -> bool {
+        cluster == 0 && self.root_dir_sectors != 0
+    }
+
+    /// Leer un bloque de directorio: un clúster, o la región completa del raíz fijo
+    pub fn read_directory_block(&mut self, cluster: u32, buffer: &mut Vec<u8>) -> bool {
+        if self.is_fixed_root(cluster) {
+            let bytes_per_sector = self.bytes_per_sector();
+            buffer.resize(self.root_dir_sectors as usize * bytes_per_sector, 0);
+            for i in 0..self.root_dir_sectors {
+                let start = i as usize * bytes_per_sector;
+                if !self.read_sector(self.root_dir_start + i, &mut buffer[start..start + bytes_per_sector]) {
+                    return false;
+                }
+            }
+            return true;
+        }
+
+        buffer.resize(self.cluster_size as usize, 0);
+        self.read_cluster(cluster, buffer)
+    }
+
+    /// Escribir un bloque de directorio leído con `read_directory_block`
+    pub fn write_directory_block(&mut self, cluster: u32, buffer: &[u8]) -> bool {
+        if self.is_fixed_root(cluster) {
+            let bytes_per_sector = self.bytes_per_sector();
+            for i in 0..self.root_dir_sectors {
+                let start = i as usize * bytes_per_sector;
+                if !self.write_sector(self.root_dir_start + i, &buffer[start..start + bytes_per_sector]) {
+                    return false;
+                }
+            }
+            return true;
+        }
+
+        self.write_cluster(cluster, buffer)
+    }
+
+    /// Siguiente bloque de un directorio (el raíz fijo tiene un único bloque)
+    fn next_directory_block(&mut self, cluster: u32) -> Option<u32> {
+        if self.is_fixed_root(cluster) {
+            None
+        } else {
+            self.next_cluster(cluster)
+        }
+    }
+
+    /// Todos los bloques de un directorio
+    fn directory_blocks(&mut self, directory_cluster: u32) -> Result<Vec<u32>, &'static str> {
+        if self.is_fixed_root(directory_cluster) {
+            Ok(vec![0])
+        } else {
+            self.cluster_chain(directory_cluster)
+        }
+    }
+
+    /// Leer una entrada de directorio en una posición concreta
+    pub fn read_directory_entry(&mut self, location: Fat32EntryLocation) -> Result<Fat32DirectoryEntry, &'static str> {
+        let mut cluster_buffer = Vec::new();
+        if !self.read_directory_block(location.cluster, &mut cluster_buffer) {
+            return Err("Error leyendo clúster de directorio");
+        }
+
+        let offset = location.index as usize * FAT32_DIRECTORY_ENTRY_SIZE;
+        if offset + FAT32_DIRECTORY_ENTRY_SIZE > cluster_buffer.len() {
+            return Err("Posición de entrada inválida");
+        }
+        Ok(Fat32DirectoryEntry::from_bytes(&cluster_buffer[offset..offset + FAT32_DIRECTORY_ENTRY_SIZE]))
+    }
+
+    /// Escribir una entrada de directorio en una posición concreta
+    pub fn write_directory_entry(&mut self, location: Fat32EntryLocation, entry: &Fat32DirectoryEntry) -> Result<(), &'static str> {
+        self.write_directory_slot(location, &entry.to_bytes())
+    }
+
+    /// Escribir 32 bytes en bruto en una posición de directorio
+    fn write_directory_slot(&mut self, location: Fat32EntryLocation, slot: &[u8; 32]) -> Result<(), &'static str> {
+        let mut cluster_buffer = Vec::new();
+        if !self.read_directory_block(location.cluster, &mut cluster_buffer) {
+            return Err("Error leyendo clúster de directorio");
+        }
+
+        let offset = location.index as usize * FAT32_DIRECTORY_ENTRY_SIZE;
+        if offset + FAT32_DIRECTORY_ENTRY_SIZE > cluster_buffer.len() {
+            return Err("Posición de entrada inválida");
+        }
+        cluster_buffer[offset..offset + FAT32_DIRECTORY_ENTRY_SIZE].copy_from_slice(slot);
+
+        if !self.write_directory_block(location.cluster, &cluster_buffer) {
+            return Err("Error escribiendo clúster de directorio");
+        }
+        Ok(())
+    }
+
+    /// Buscar `count` entradas libres consecutivas en un directorio
+    ///
+    /// Si no hay hueco suficiente, el directorio se extiende con clústeres nuevos
+    /// (salvo el raíz fijo de FAT12/FAT16, que tiene un tamaño fijo).
+    fn find_free_entries(&mut self, directory_cluster: u32, count: usize) -> Result<Fat32EntryLocation, &'static str> {
+        let entries_per_cluster = self.cluster_size as usize / FAT32_DIRECTORY_ENTRY_SIZE;
+        if count == 0 || count > FAT32_LFN_MAX_LENGTH / FAT32_LFN_CHARS_PER_ENTRY + 2 {
+            return Err("Número de entradas de directorio inválido");
+        }
+
+        let chain = self.directory_blocks(directory_cluster)?;
+        let mut cluster_buffer = Vec::new();
+        let mut run_start: Option<Fat32EntryLocation> = None;
+        let mut run_length = 0;
+
+        for &cluster in &chain {
+            if !self.read_directory_block(cluster, &mut cluster_buffer) {
+                return Err("Error leyendo clúster de directorio");
+            }
+
+            for i in 0..cluster_buffer.len() / FAT32_DIRECTORY_ENTRY_SIZE {
+                let first_byte = cluster_buffer[i * FAT32_DIRECTORY_ENTRY_SIZE];
+                if first_byte == FAT32_ENTRY_END || first_byte == FAT32_ENTRY_DELETED {
+                    if run_length == 0 {
+                        run_start = Some(Fat32EntryLocation { cluster, index: i as u32 });
+                    }
+                    run_length += 1;
+                    if run_length == count {
+                        return run_start.ok_or("Error buscando entradas libres");
+                    }
+                } else {
+                    run_length = 0;
+                }
+            }
+        }
+
+        if self.is_fixed_root(directory_cluster) {
+            return Err("Directorio raíz lleno");
+        }
+
+        // El directorio está lleno: añadir clústeres (ya rellenados con ceros).
+        // Un hueco al final del último clúster continúa en los clústeres nuevos.
+        let mut last = chain.last().copied();
+        let mut first_new = None;
+        let mut available = run_length;
+        while available < count {
+            let cluster = self.allocate_cluster(last)?;
+            first_new.get_or_insert(cluster);
+            last = Some(cluster);
+            available += entries_per_cluster;
+        }
+
+        match run_start {
+            Some(start) if run_length > 0 => Ok(start),
+            _ => Ok(Fat32EntryLocation {
+                cluster: first_new.ok_or("Error extendiendo el directorio")?,
+                index: 0,
+            }),
+        }
+    }
+
+    /// Avanzar una posición de entrada dentro de la cadena del directorio
+    fn next_entry_location(&mut self, location: Fat32EntryLocation) -> Result<Fat32EntryLocation, &'static str> {
+        let entries_per_block = if self.is_fixed_root(location.cluster) {
+            self.boot_sector.root_entries as u32
+        } else {
+            self.cluster_size / FAT32_DIRECTORY_ENTRY_SIZE as u32
+        };
+        if location.index + 1 < entries_per_block {
+            return Ok(Fat32EntryLocation {
+                cluster: location.cluster,
+                index: location.index + 1,
+            });
+        }
+
+        let next = self.next_directory_block(location.cluster).ok_or("Fin inesperado del directorio")?;
+        Ok(Fat32EntryLocation { cluster: next, index: 0 })
+    }
+
+    /// Marcar la entrada con la fecha y hora actuales
+    fn touch_entry(&self, entry: &mut Fat32DirectoryEntry, created: bool) {
+        let (date, time) = unix_to_fat_datetime(self.get_system_time());
+        if created {
+            entry.creation_date = date;
+            entry.creation_time = time;
+            entry.creation_time_ms = 0;
+        }
+        entry.modification_date = date;
+        entry.modification_time = time;
+        entry.access_date = date;
+    }
+
+    /// Obtener los nombres cortos (nombre + extensión) usados en un directorio
+    fn collect_short_names(&mut self, directory_cluster: u32) -> Result<Vec<[u8; 11]>, &'static str> {
+        let chain = self.directory_blocks(directory_cluster)?;
+        let mut cluster_buffer = Vec::new();
+        let mut names = Vec::new();
+
+        for &cluster in &chain {
+            if !self.read_directory_block(cluster, &mut cluster_buffer) {
+                return Err("Error leyendo clúster de directorio");
+            }
+
+            for slot in cluster_buffer.chunks(FAT32_DIRECTORY_ENTRY_SIZE) {
+                if slot[0] == FAT32_ENTRY_END {
+                    return Ok(names);
+                }
+                if slot[0] == FAT32_ENTRY_DELETED || is_long_name_slot(slot) {
+                    continue;
+                }
+                let mut short_name = [0u8; 11];
+                short_name.copy_from_slice(&slot[..11]);
+                names.push(short_name);
+            }
+        }
+
+        Ok(names)
+    }
+
+    /// Generar un alias corto único "BASE~N.EXT" para un nombre largo
+    fn generate_short_alias(&mut self, directory_cluster: u32, long_name: &str) -> Result<([u8; 8], [u8; 3]), &'static str> {
+        let (basis, basis_len, extension) = short_alias_basis(long_name);
+        let existing = self.collect_short_names(directory_cluster)?;
+
+        for number in 1..=999_999u32 {
+            let name = short_alias_with_tail(&basis, basis_len, number);
+            let in_use = existing
+                .iter()
+                .any(|used| used[..8] == name[..] && used[8..] == extension[..]);
+            if !in_use {
+                return Ok((name, extension));
+            }
+        }
+
+        Err("No se pudo generar un alias corto único")
+    }
+
+    /// Crear un archivo o directorio vacío dentro de un directorio
+    ///
+    /// Los nombres que no caben en 8.3 se guardan como nombre largo (LFN)
+    /// junto con un alias corto único. Para directorios se reserva un clúster
+    /// con las entradas "." y "..".
+    pub fn create_file(&mut self, directory_cluster: u32, filename: &str, attributes: u8) -> Result<(Fat32DirectoryEntry, Fat32EntryLocation), &'static str> {
+        if self.state != Fat32State::Mounted {
+            return Err("Sistema de archivos no montado");
+        }
+
+        let directory_cluster = self.directory_cluster(directory_cluster);
+        let filename = normalize_long_name(filename)?;
+        if self.lookup_entry(directory_cluster, filename).is_some() {
+            return Err("El archivo ya existe");
+        }
+
+        let mut entry = Fat32DirectoryEntry::empty();
+        let long_name_slots = self.name_entry(directory_cluster, filename, &mut entry)?;
+        entry.attributes = attributes & !(FAT32_ATTR_VOLUME_LABEL | FAT32_ATTR_LONG_NAME)
+            | if attributes & FAT32_ATTR_DIRECTORY != 0 { FAT32_ATTR_DIRECTORY } else { FAT32_ATTR_ARCHIVE };
+        self.touch_entry(&mut entry, true);
+
+        if entry.is_directory() {
+            let cluster = self.allocate_cluster(None)?;
+            entry.set_first_cluster(cluster);
+
+            let mut dot = entry;
+            dot.name = *b".       ";
+            dot.extension = *b"   ";
+            dot.reserved = 0;
+
+            let mut dot_dot = entry;
+            dot_dot.name = *b"..      ";
+            dot_dot.extension = *b"   ";
+            dot_dot.reserved = 0;
+            dot_dot.set_first_cluster(if directory_cluster == self.root_cluster { 0 } else { directory_cluster });
+
+            let mut cluster_buffer = vec![0u8; self.cluster_size as usize];
+            cluster_buffer[0..32].copy_from_slice(&dot.to_bytes());
+            cluster_buffer[32..64].copy_from_slice(&dot_dot.to_bytes());
+            if !self.write_cluster(cluster, &cluster_buffer) {
+                self.free_cluster_chain(cluster)?;
+                return Err("Error escribiendo directorio nuevo");
+            }
+        }
+
+        let location = match self.place_entry(directory_cluster, &long_name_slots, &entry) {
+            Ok(location) => location,
+            Err(e) => {
+                if entry.first_cluster() != 0 {
+                    self.free_cluster_chain(entry.first_cluster())?;
+                }
+                return Err(e);
+            }
+        };
+
+        Ok((entry, location))
+    }
+
+    /// Poner nombre a una entrada: 8.3 exacto, o alias corto más entradas LFN
+    ///
+    /// Devuelve las entradas LFN que deben preceder a la entrada corta.
+    fn name_entry(&mut self, directory_cluster: u32, filename: &str, entry: &mut Fat32DirectoryEntry) -> Result<Vec<[u8; 32]>, &'static str> {
+        match short_name_exact(filename) {
+            Some((name, extension, case_flags)) => {
+                entry.name = name;
+                entry.extension = extension;
+                entry.reserved = case_flags;
+                Ok(Vec::new())
+            }
+            None => {
+                let (name, extension) = self.generate_short_alias(directory_cluster, filename)?;
+                entry.name = name;
+                entry.extension = extension;
+                entry.reserved = 0;
+                let units: Vec<u16> = filename.encode_utf16().collect();
+                Ok(build_long_name_slots(&units, lfn_checksum(&name, &extension)))
+            }
+        }
+    }
+
+    /// Escribir una entrada (precedida de sus entradas LFN) en un hueco libre del directorio
+    fn place_entry(&mut self, directory_cluster: u32, long_name_slots: &[[u8; 32]], entry: &Fat32DirectoryEntry) -> Result<Fat32EntryLocation, &'static str> {
+        let mut location = self.find_free_entries(directory_cluster, long_name_slots.len() + 1)?;
+
+        // Las entradas LFN preceden a la entrada corta
+        for slot in long_name_slots {
+            self.write_directory_slot(location, slot)?;
+            location = self.next_entry_location(location)?;
+        }
+        self.write_directory_entry(location, entry)?;
+
+        Ok(location)
+    }
+
+    /// Marcar como borradas las entradas (LFN y corta) de un archivo
+    fn mark_entry_deleted(&mut self, info: &Fat32DirEntryInfo) -> Result<(), &'static str> {
+        let mut location = info.first_slot;
+        for i in 0..info.slot_count {
+            let mut slot = self.read_directory_entry(location)?.to_bytes();
+            slot[0] = FAT32_ENTRY_DELETED;
+            self.write_directory_slot(location, &slot)?;
+            if i + 1 < info.slot_count {
+                location = self.next_entry_location(location)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Renombrar o mover un archivo o directorio
+    ///
+    /// La entrada se reescribe con el nuevo nombre en el directorio de destino
+    /// conservando clústeres, tamaño y fechas. Si se mueve un directorio, su
+    /// entrada ".." pasa a apuntar al nuevo padre.
+    pub fn rename_file(&mut self, directory_cluster: u32, filename: &str, new_directory_cluster: u32, new_filename: &str) -> Result<(), &'static str> {
+        if self.state != Fat32State::Mounted {
+            return Err("Sistema de archivos no montado");
+        }
+
+        let directory_cluster = self.directory_cluster(directory_cluster);
+        let new_directory_cluster = self.directory_cluster(new_directory_cluster);
+        let new_filename = normalize_long_name(new_filename)?;
+        let info = self
+            .lookup_entry(directory_cluster, filename)
+            .ok_or("Archivo no encontrado")?;
+        if info.entry.is_dot_entry() {
+            return Err("No se puede renombrar \".\" o \"..\"");
+        }
+        if info.entry.attributes & FAT32_ATTR_READ_ONLY != 0 {
+            return Err("Archivo de solo lectura");
+        }
+        // Un cambio de mayúsculas encuentra la propia entrada
+        if let Some(existing) = self.lookup_entry(new_directory_cluster, new_filename) {
+            if existing.location != info.location {
+                return Err("El archivo ya existe");
+            }
+        }
+
+        // Primero la entrada nueva, para no perder el archivo si algo falla
+        let mut entry = info.entry;
+        let long_name_slots = self.name_entry(new_directory_cluster, new_filename, &mut entry)?;
+        self.place_entry(new_directory_cluster, &long_name_slots, &entry)?;
+        self.mark_entry_deleted(&info)?;
+
+        if entry.is_directory() && new_directory_cluster != directory_cluster {
+            let dot_dot = Fat32EntryLocation { cluster: entry.first_cluster(), index: 1 };
+            let mut parent = self.read_directory_entry(dot_dot)?;
+            if parent.name == *b"..      " {
+                parent.set_first_cluster(if new_directory_cluster == self.root_cluster { 0 } else { new_directory_cluster });
+                self.write_directory_entry(dot_dot, &parent)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Crear un directorio vacío
+    pub fn create_directory(&mut self, directory_cluster: u32, dirname: &str) -> Result<Fat32DirectoryEntry, &'static str> {
+        self.create_file(directory_cluster, dirname, FAT32_ATTR_DIRECTORY)
+            .map(|(entry, _)| entry)
+    }
+
+    /// Escribir datos en el rango `[start, end)` de un archivo
+    ///
+    /// Los bytes anteriores a `data_offset` se rellenan con ceros, lo que
+    /// permite extender archivos con huecos. La cadena se amplía según sea necesario.
+    fn write_range(&mut self, entry: &mut Fat32DirectoryEntry, start: u64, end: u64, data_offset: u64, data: &[u8]) -> Result<(), &'static str> {
+        if start >= end {
+            return Ok(());
+        }
+
+        let cluster_size = self.cluster_size as u64;
+        let clusters_needed = ((end + cluster_size - 1) / cluster_size) as usize;
+        let chain = self.extend_chain(entry.first_cluster(), clusters_needed)?;
+        if entry.first_cluster() == 0 {
+            if let Some(&first) = chain.first() {
+                entry.set_first_cluster(first);
+            }
+        }
+
+        let mut cluster_buffer = vec![0u8; cluster_size as usize];
+        let mut position = start;
+        while position < end {
+            let cluster_index = (position / cluster_size) as usize;
+            let cluster_offset = (position % cluster_size) as usize;
+            let chunk = core::cmp::min(cluster_size - cluster_offset as u64, end - position) as usize;
+            let cluster = chain[cluster_index];
+
+            // Escritura parcial: leer-modificar-escribir
+            if chunk != cluster_size as usize && !self.read_cluster(cluster, &mut cluster_buffer) {
+                return Err("Error leyendo clúster");
+            }
+
+            for i in 0..chunk {
+                let file_position = position + i as u64;
+                cluster_buffer[cluster_offset + i] = if file_position >= data_offset {
+                    data[(file_position - data_offset) as usize]
+                } else {
+                    0
+                };
+            }
+
+            if !self.write_cluster(cluster, &cluster_buffer) {
+                return Err("Error escribiendo clúster");
+            }
+            position += chunk as u64;
+        }
+
+        Ok(())
+    }
+
+    /// Escribir datos en un archivo existente a partir de un desplazamiento
+    ///
+    /// Si el desplazamiento está más allá del final, el hueco se rellena con ceros.
+    pub fn write_file(&mut self, directory_cluster: u32, filename: &str, offset: u64, data: &[u8]) -> Result<usize, &'static str> {
+        if self.state != Fat32State::Mounted {
+            return Err("Sistema de archivos no montado");
+        }
+
+        let (mut entry, location) = self
+            .find_entry(directory_cluster, filename)
+            .ok_or("Archivo no encontrado")?;
+        if entry.is_directory() {
+            return Err("No se puede escribir en un directorio");
+        }
+        if entry.attributes & FAT32_ATTR_READ_ONLY != 0 {
+            return Err("Archivo de solo lectura");
+        }
+
+        let end = offset + data.len() as u64;
+        if end > u32::MAX as u64 {
+            return Err("Tamaño de archivo excede el límite de FAT32");
+        }
+
+        let file_size = entry.file_size as u64;
+        let start = core::cmp::min(offset, file_size);
+        self.write_range(&mut entry, start, end, offset, data)?;
+
+        if end > file_size {
+            entry.file_size = end as u32;
+        }
+        entry.attributes |= FAT32_ATTR_ARCHIVE;
+        self.touch_entry(&mut entry, false);
+        self.write_directory_entry(location, &entry)?;
+
+        Ok(data.len())
+    }
+
+    /// Cambiar el tamaño de un archivo
+    ///
+    /// Al reducir se liberan los clústeres sobrantes; al ampliar se rellena con ceros.
+    pub fn truncate_file(&mut self, directory_cluster: u32, filename: &str, new_size: u64) -> Result<(), &'static str> {
+        if self.state != Fat32State::Mounted {
+            return Err("Sistema de archivos no montado");
+        }
+        if new_size > u32::MAX as u64 {
+            return Err("Tamaño de archivo excede el límite de FAT32");
+        }
+
+        let (mut entry, location) = self
+            .find_entry(directory_cluster, filename)
+            .ok_or("Archivo no encontrado")?;
+        if entry.is_directory() {
+            return Err("No se puede truncar un directorio");
+        }
+        if entry.attributes & FAT32_ATTR_READ_ONLY != 0 {
+            return Err("Archivo de solo lectura");
+        }
+
+        let file_size = entry.file_size as u64;
+        if new_size < file_size {
+            let cluster_size = self.cluster_size as u64;
+            let keep = ((new_size + cluster_size - 1) / cluster_size) as usize;
+            let first = self.truncate_chain(entry.first_cluster(), keep)?;
+            entry.set_first_cluster(first);
+        } else if new_size > file_size {
+            self.write_range(&mut entry, file_size, new_size, new_size, &[])?;
+        }
+
+        entry.file_size = new_size as u32;
+        entry.attributes |= FAT32_ATTR_ARCHIVE;
+        self.touch_entry(&mut entry, false);
+        self.write_directory_entry(location, &entry)
+    }
+
+    /// Verificar si un directorio solo contiene "." y ".."
+    fn is_directory_empty(&mut self, cluster: u32) -> Result<bool, &'static str> {
+        let chain = self.directory_blocks(cluster)?;
+        let mut cluster_buffer = Vec::new();
+
+        for &cluster in &chain {
+            if !self.read_directory_block(cluster, &mut cluster_buffer) {
+                return Err("Error leyendo clúster de directorio");
+            }
+
+            for entry_data in cluster_buffer.chunks(FAT32_DIRECTORY_ENTRY_SIZE) {
+                let entry = Fat32DirectoryEntry::from_bytes(entry_data);
+                if entry.name[0] == FAT32_ENTRY_END {
+                    return Ok(true);
+                }
+                if entry.name[0] == FAT32_ENTRY_DELETED
+                    || entry.is_dot_entry()
+                    || entry.attributes & FAT32_ATTR_LONG_NAME == FAT32_ATTR_LONG_NAME
+                {
+                    continue;
+                }
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Eliminar un archivo o un directorio vacío
+    pub fn delete_file(&mut self, directory_cluster: u32, filename: &str) -> Result<(), &'static str> {
+        if self.state != Fat32State::Mounted {
+            return Err("Sistema de archivos no montado");
+        }
+
+        let info = self
+            .lookup_entry(directory_cluster, filename)
+            .ok_or("Archivo no encontrado")?;
+        let entry = info.entry;
+        if entry.is_dot_entry() {
+            return Err("No se puede eliminar \".\" o \"..\"");
+        }
+        if entry.attributes & FAT32_ATTR_READ_ONLY != 0 {
+            return Err("Archivo de solo lectura");
+        }
+        if entry.is_directory() && !self.is_directory_empty(entry.first_cluster())? {
+            return Err("El directorio no está vacío");
+        }
+
+        // Marcar primero las entradas (LFN y corta) para no dejar referencias a clústeres libres
+        self.mark_entry_deleted(&info)?;
+
+        if entry.first_cluster() != 0 {
+            self.free_cluster_chain(entry.first_cluster())?;
+        }
+
+        Ok(())
+    }
+
+    /// Contar los clústeres libres recorriendo la FAT
+    pub fn count_free_clusters(&mut self) -> u32 {
+        let mut free = 0;
+        for cluster in 2..self.total_clusters + 2 {
+            if self.read_fat_entry(cluster) == Some(FAT32_FREE_CLUSTER) {
+                free += 1;
+            }
+        }
+        free
+    }
+
+    /// Obtener tiempo del sistema (segundos desde la época Unix)
+    fn get_system_time(&self) -> u64 {
+        crate::hal::rtc::unix_time()
+    }
+
+    /// Obtener información del sistema de archivos
+    pub fn get_filesystem_info(&self) -> (u32, u32, u32, u32) {
+        (
+            self.total_clusters,
+            self.cluster_size,
+            self.sectors_per_fat,
+            self.root_cluster,
+        )
+    }
+
+    /// Verificar si el sistema de archivos está montado
+    pub fn is_mounted(&self) -> bool {
+        self.state == Fat32State::Mounted
+    }
+
+    /// Información de una entrada para el VFS
+    fn vfs_file_info(&self, info: &Fat32DirEntryInfo) -> FileInfo {
+        let node_type = if info.is_directory() { NodeType::Directory } else { NodeType::File };
+        let entry = info.entry;
+        let mut file_info = FileInfo::new(0, &info.name, node_type);
+        file_info.size = info.size() as u64;
+        file_info.block_size = self.cluster_size;
+        file_info.block_count = (file_info.size + self.cluster_size as u64 - 1) / self.cluster_size as u64;
+        if info.attributes() & FAT32_ATTR_READ_ONLY != 0 {
+            file_info.permissions = FilePermissions::read_only();
+        }
+        file_info.created_time = fat_datetime_to_unix(entry.creation_date, entry.creation_time);
+        file_info.modified_time = fat_datetime_to_unix(entry.modification_date, entry.modification_time);
+        file_info.accessed_time = fat_datetime_to_unix(entry.access_date, 0);
+        file_info.device = self.block_device;
+        file_info.inode = entry.first_cluster() as u64;
+        file_info
+    }
+}
+
+/// Problema encontrado al verificar un volumen FAT
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fat32Problem {
+    /// Una copia de la FAT no coincide con la copia activa
+    FatCopyMismatch {
+        /// Índice de la copia
+        copy: u32,
+        /// Primer sector (relativo a la FAT) que difiere
+        first_sector: u32,
+    },
+    /// Entrada de directorio inválida; al reparar se marca como borrada
+    BadDirectoryEntry {
+        /// Ruta de la entrada
+        path: String,
+        /// Motivo
+        reason: &'static str,
+    },
+    /// Cadena de clústeres rota o cíclica; al reparar se corta en el último enlace válido
+    BadChain {
+        /// Ruta de la entrada
+        path: String,
+        /// Motivo
+        reason: &'static str,
+    },
+    /// El tamaño del archivo no corresponde a la longitud de su cadena
+    SizeMismatch {
+        /// Ruta del archivo
+        path: String,
+        /// Tamaño registrado en la entrada
+        size: u32,
+        /// Clústeres de la cadena
+        clusters: u32,
+    },
+    /// Dos entradas comparten clústeres; la segunda pierde la parte compartida
+    CrossLink {
+        /// Ruta de la entrada cuya cadena se corta
+        path: String,
+        /// Ruta de la entrada que ya poseía el clúster
+        other: String,
+        /// Primer clúster compartido
+        cluster: u32,
+    },
+    /// Cadena asignada que no pertenece a ninguna entrada; al reparar se libera
+    LostChain {
+        /// Primer clúster de la cadena
+        first_cluster: u32,
+        /// Número de clústeres
+        clusters: u32,
+    },
+    /// El contador de clústeres libres de FSInfo no coincide con la FAT
+    FreeCountMismatch {
+        /// Valor de FSInfo
+        recorded: u32,
+        /// Valor calculado
+        actual: u32,
+    },
+}
+
+/// Resultado de la verificación de un volumen FAT
+#[derive(Debug, Clone, Default)]
+pub struct Fat32CheckReport {
+    /// Problemas encontrados, en el orden en que se detectaron
+    pub problems: Vec<Fat32Problem>,
+    /// Los problemas se corrigieron en disco
+    pub repaired: bool,
+    /// Número de archivos
+    pub files: u32,
+    /// Número de directorios (incluido el raíz)
+    pub directories: u32,
+    /// Clústeres en uso tras la verificación
+    pub used_clusters: u32,
+    /// Clústeres libres tras la verificación
+    pub free_clusters: u32,
+    /// Clústeres marcados como defectuosos
+    pub bad_clusters: u32,
+}
+
+impl Fat32CheckReport {
+    /// Verificar si no se encontró ningún problema
+    pub fn is_clean(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Estado de una verificación en curso
+struct Fat32Check {
+    /// Copia en memoria de la FAT, indexada por clúster
+    fat: Vec<u32>,
+    /// Propietario de cada clúster (índice en `paths` más uno, 0 si no tiene)
+    owners: Vec<u32>,
+    /// Rutas de las entradas que poseen clústeres
+    paths: Vec<String>,
+    /// Escribir las correcciones en disco
+    repair: bool,
+    /// Informe que se va construyendo
+    report: Fat32CheckReport,
+}
+
+impl Fat32Check {
+    /// Ruta de un propietario
+    fn path(&self, owner: u32) -> String {
+        self.paths[owner as usize - 1].clone()
+    }
+}
+
+/// Directorio pendiente de recorrer durante la verificación
+struct Fat32PendingDirectory {
+    /// Bloques del directorio (cadena ya validada, o el bloque 0 del raíz fijo)
+    blocks: Vec<u32>,
+    /// Propietario del directorio
+    owner: u32,
+    /// Primer clúster del directorio (0 para el raíz)
+    cluster: u32,
+    /// Primer clúster del directorio padre (0 si es el raíz)
+    parent: u32,
+}
+
+impl Fat32Driver {
+    /// Verificar la consistencia del volumen y, si `repair` es `true`, corregirla
+    ///
+    /// Comprueba que las copias de la FAT coinciden, recorre el árbol de
+    /// directorios validando cada entrada y su cadena de clústeres (enlaces
+    /// rotos, ciclos, clústeres compartidos, tamaños que no cuadran), busca
+    /// cadenas perdidas y revisa el contador de FSInfo. Sin `repair` no se
+    /// escribe nada, pero el informe describe el estado que quedaría tras reparar.
+    pub fn check(&mut self, repair: bool) -> Result<Fat32CheckReport, &'static str> {
+        if self.state == Fat32State::Uninitialized {
+            return Err("Driver no inicializado");
+        }
+
+        let mut check = Fat32Check {
+            fat: Vec::new(),
+            owners: Vec::new(),
+            paths: Vec::new(),
+            repair,
+            report: Fat32CheckReport::default(),
+        };
+
+        self.check_fat_copies(&mut check)?;
+
+        // Cargar la FAT activa; las reparaciones se aplican primero sobre esta copia
+        let cluster_limit = self.total_clusters + 2;
+        check.fat.reserve(cluster_limit as usize);
+        for cluster in 0..cluster_limit {
+            check.fat.push(self.read_fat_entry(cluster).ok_or("Error leyendo la tabla FAT")?);
+        }
+        check.owners = vec![0; cluster_limit as usize];
+
+        self.check_tree(&mut check)?;
+        self.check_lost_chains(&mut check)?;
+        self.check_free_count(&mut check)?;
+
+        check.report.repaired = repair && !check.report.is_clean();
+        Ok(check.report)
+    }
+
+    /// Comparar las copias de la FAT con la copia activa
+    fn check_fat_copies(&mut self, check: &mut Fat32Check) -> Result<(), &'static str> {
+        // Con el espejado deshabilitado las copias pueden diferir legítimamente
+        if self.mirroring_disabled() {
+            return Ok(());
+        }
+
+        let active_start = self.active_fat_start();
+        let mut active = vec![0u8; self.bytes_per_sector()];
+        let mut copy = vec![0u8; self.bytes_per_sector()];
+
+        for index in 1..self.boot_sector.fat_count as u32 {
+            let copy_start = self.boot_sector.reserved_sectors as u32 + index * self.sectors_per_fat;
+            let mut first_mismatch = None;
+
+            for sector in 0..self.sectors_per_fat {
+                if !self.read_sector(active_start + sector, &mut active) || !self.read_sector(copy_start + sector, &mut copy) {
+                    return Err("Error leyendo la tabla FAT");
+                }
+                if active == copy {
+                    continue;
+                }
+                first_mismatch.get_or_insert(sector);
+                if check.repair && !self.write_sector(copy_start + sector, &active) {
+                    return Err("Error escribiendo la tabla FAT");
+                }
+            }
+
+            if let Some(first_sector) = first_mismatch {
+                check.report.problems.push(Fat32Problem::FatCopyMismatch { copy: index, first_sector });
+            }
+        }
+        Ok(())
+    }
+
+    /// Cambiar una entrada de la FAT en memoria y, al reparar, también en disco
+    fn set_checked_fat_entry(&mut self, check: &mut Fat32Check, cluster: u32, value: u32) -> Result<(), &'static str> {
+        check.fat[cluster as usize] = value;
+        if check.repair && !self.write_fat_entry(cluster, value) {
+            return Err("Error escribiendo la tabla FAT");
+        }
+        Ok(())
+    }
+
+    /// Validar la cadena de una entrada y reclamar sus clústeres para `owner`
+    ///
+    /// La cadena se corta antes de un clúster libre, defectuoso o que ya
+    /// pertenece a otra entrada (o a esta misma, si es cíclica), y tras un
+    /// enlace fuera del volumen. Devuelve los clústeres que se conservan.
+    fn claim_chain(&mut self, check: &mut Fat32Check, owner: u32, first_cluster: u32) -> Result<Vec<u32>, &'static str> {
+        let mut chain = Vec::new();
+        let mut current = first_cluster;
+
+        let problem = loop {
+            let previous_owner = check.owners[current as usize];
+            if previous_owner == owner {
+                break Fat32Problem::BadChain { path: check.path(owner), reason: "Cadena de clústeres cíclica" };
+            }
+            if previous_owner != 0 {
+                break Fat32Problem::CrossLink { path: check.path(owner), other: check.path(previous_owner), cluster: current };
+            }
+
+            let next = check.fat[current as usize];
+            if next == FAT32_FREE_CLUSTER {
+                break Fat32Problem::BadChain { path: check.path(owner), reason: "La cadena incluye un clúster libre" };
+            }
+            if next == FAT32_BAD_CLUSTER {
+                break Fat32Problem::BadChain { path: check.path(owner), reason: "La cadena incluye un clúster defectuoso" };
+            }
+
+            check.owners[current as usize] = owner;
+            chain.push(current);
+
+            if next >= FAT32_END_OF_CHAIN_MIN {
+                return Ok(chain);
+            }
+            if !self.is_valid_cluster(next) {
+                break Fat32Problem::BadChain { path: check.path(owner), reason: "La cadena sale del volumen" };
+            }
+            current = next;
+        };
+
+        check.report.problems.push(problem);
+        if let Some(&last) = chain.last() {
+            self.set_checked_fat_entry(check, last, FAT32_END_OF_CHAIN)?;
+        }
+        Ok(chain)
+    }
+
+    /// Liberar los clústeres de una cadena a partir de la posición `keep`
+    fn release_chain_tail(&mut self, check: &mut Fat32Check, chain: &[u32], keep: usize) -> Result<(), &'static str> {
+        if keep > 0 {
+            self.set_checked_fat_entry(check, chain[keep - 1], FAT32_END_OF_CHAIN)?;
+        }
+        for &cluster in &chain[keep..] {
+            check.owners[cluster as usize] = 0;
+            self.set_checked_fat_entry(check, cluster, FAT32_FREE_CLUSTER)?;
+        }
+        Ok(())
+    }
+
+    /// Motivo por el que una entrada corta no es válida, si lo hay
+    fn entry_problem(&self, entry: &Fat32DirectoryEntry) -> Option<&'static str> {
+        let valid_name = entry.name[0] != b' '
+            && entry.name.iter().enumerate().all(|(i, &b)| b == b' ' || (i == 0 && b == 0x05) || short_name_char(b) == Ok(b))
+            && entry.extension.iter().all(|&b| b == b' ' || short_name_char(b) == Ok(b));
+        if !valid_name {
+            return Some("Nombre corto inválido");
+        }
+        if entry.attributes & 0xC0 != 0 {
+            return Some("Atributos reservados activos");
+        }
+
+        let first_cluster = entry.first_cluster();
+        if first_cluster != 0 && !self.is_valid_cluster(first_cluster) {
+            return Some("Clúster inicial fuera del volumen");
+        }
+        if entry.is_directory() && first_cluster == 0 {
+            return Some("Directorio sin clústeres");
+        }
+        None
+    }
+
+    /// Leer las entradas de un directorio a partir de sus bloques ya validados
+    ///
+    /// Equivale a `read_dir`, pero sigue la cadena calculada por la verificación
+    /// en lugar de la FAT del disco.
+    fn scan_directory(&mut self, blocks: &[u32]) -> Result<Vec<Fat32DirEntryInfo>, &'static str> {
+        let mut entries = Vec::new();
+        let mut buffer = Vec::new();
+        let mut long_name = LongNameBuilder::new();
+
+        for &cluster in blocks {
+            if !self.read_directory_block(cluster, &mut buffer) {
+                return Err("Error leyendo clúster de directorio");
+            }
+
+            for (i, slot) in buffer.chunks_exact(FAT32_DIRECTORY_ENTRY_SIZE).enumerate() {
+                let location = Fat32EntryLocation {
+                    cluster,
+                    index: i as u32,
+                };
+
+                if slot[0] == FAT32_ENTRY_END {
+                    return Ok(entries);
+                }
+                if slot[0] == FAT32_ENTRY_DELETED {
+                    long_name.reset();
+                    continue;
+                }
+                if is_long_name_slot(slot) {
+                    long_name.push(slot, location);
+                    continue;
+                }
+
+                let entry = Fat32DirectoryEntry::from_bytes(slot);
+                let lfn = long_name.finish(&entry);
+                if entry.attributes & FAT32_ATTR_VOLUME_LABEL != 0 {
+                    continue;
+                }
+
+                let (name, first_slot, slot_count) = match lfn {
+                    Some((name, first_slot, lfn_slots)) => (name, first_slot, lfn_slots + 1),
+                    None => (Fat32Driver::display_short_name(&entry), location, 1),
+                };
+                entries.push(Fat32DirEntryInfo {
+                    entry,
+                    location,
+                    first_slot,
+                    slot_count,
+                    name,
+                });
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Recorrer el árbol de directorios validando entradas y cadenas
+    fn check_tree(&mut self, check: &mut Fat32Check) -> Result<(), &'static str> {
+        check.paths.push(String::from("/"));
+        let root_blocks = if self.root_dir_sectors != 0 {
+            vec![0]
+        } else {
+            if !self.is_valid_cluster(self.root_cluster) {
+                return Err("Clúster del directorio raíz inválido");
+            }
+            let chain = self.claim_chain(check, 1, self.root_cluster)?;
+            if chain.is_empty() {
+                return Err("Directorio raíz irrecuperable");
+            }
+            chain
+        };
+
+        let mut pending = vec![Fat32PendingDirectory {
+            blocks: root_blocks,
+            owner: 1,
+            cluster: 0,
+            parent: 0,
+        }];
+
+        while let Some(directory) = pending.pop() {
+            check.report.directories += 1;
+            let directory_path = check.path(directory.owner);
+
+            for info in self.scan_directory(&directory.blocks)? {
+                let mut entry = info.entry;
+                let mut path = directory_path.clone();
+                if directory.owner != 1 {
+                    path.push('/');
+                }
+                path.push_str(&info.name);
+
+                // "." y ".." deben apuntar al propio directorio y a su padre
+                if entry.extension == *b"   " && (entry.name == *b".       " || entry.name == *b"..      ") {
+                    let expected = if entry.name[1] == b'.' { directory.parent } else { directory.cluster };
+                    if directory.owner == 1 {
+                        check.report.problems.push(Fat32Problem::BadDirectoryEntry { path, reason: "Entrada \".\" o \"..\" en el directorio raíz" });
+                        if check.repair {
+                            self.mark_entry_deleted(&info)?;
+                        }
+                    } else if entry.first_cluster() != expected {
+                        check.report.problems.push(Fat32Problem::BadDirectoryEntry { path, reason: "Entrada \".\" o \"..\" con clúster incorrecto" });
+                        if check.repair {
+                            entry.set_first_cluster(expected);
+                            self.write_directory_entry(info.location, &entry)?;
+                        }
+                    }
+                    continue;
+                }
+
+                if let Some(reason) = self.entry_problem(&entry) {
+                    check.report.problems.push(Fat32Problem::BadDirectoryEntry { path, reason });
+                    if check.repair {
+                        self.mark_entry_deleted(&info)?;
+                    }
+                    continue;
+                }
+
+                check.paths.push(path.clone());
+                let owner = check.paths.len() as u32;
+                let first_cluster = entry.first_cluster();
+                let chain = if first_cluster == 0 {
+                    Vec::new()
+                } else {
+                    self.claim_chain(check, owner, first_cluster)?
+                };
+                let mut changed = chain.is_empty() && first_cluster != 0;
+                if changed {
+                    entry.set_first_cluster(0);
+                }
+
+                if entry.is_directory() {
+                    if chain.is_empty() {
+                        check.report.problems.push(Fat32Problem::BadDirectoryEntry { path, reason: "Directorio sin clústeres válidos" });
+                        if check.repair {
+                            self.mark_entry_deleted(&info)?;
+                        }
+                        continue;
+                    }
+                    pending.push(Fat32PendingDirectory {
+                        cluster: chain[0],
+                        blocks: chain,
+                        owner,
+                        parent: directory.cluster,
+                    });
+                } else {
+                    check.report.files += 1;
+                    let size = entry.file_size;
+                    let needed = size.div_ceil(self.cluster_size) as usize;
+                    if chain.len() != needed {
+                        check.report.problems.push(Fat32Problem::SizeMismatch { path, size, clusters: chain.len() as u32 });
+                        if chain.len() > needed {
+                            // Sobran clústeres: se liberan los que quedan más allá del tamaño
+                            self.release_chain_tail(check, &chain, needed)?;
+                            if needed == 0 {
+                                entry.set_first_cluster(0);
+                            }
+                        } else {
+                            entry.file_size = chain.len() as u32 * self.cluster_size;
+                        }
+                        changed = true;
+                    }
+                }
+
+                if changed && check.repair {
+                    self.write_directory_entry(info.location, &entry)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Buscar cadenas asignadas que no pertenecen a ninguna entrada
+    ///
+    /// Cada cadena perdida se informa desde su cabeza (el clúster al que no
+    /// apunta ningún otro clúster perdido); los ciclos sin cabeza se informan
+    /// desde su primer clúster.
+    fn check_lost_chains(&mut self, check: &mut Fat32Check) -> Result<(), &'static str> {
+        let cluster_limit = self.total_clusters + 2;
+        let is_lost = |check: &Fat32Check, cluster: u32| {
+            let value = check.fat[cluster as usize];
+            check.owners[cluster as usize] == 0 && value != FAT32_FREE_CLUSTER && value != FAT32_BAD_CLUSTER
+        };
 
-        while current_cluster < 0x0FFFFFF8 && bytes_read < file_size {
-            let mut cluster_buffer = [0u8; 4096];
-            if !self.read_cluster(current_cluster, &mut cluster_buffer) {
-                return Err("Error leyendo clúster");
+        let mut pointed = vec![false; cluster_limit as usize];
+        for cluster in 2..cluster_limit {
+            let next = check.fat[cluster as usize];
+            if is_lost(check, cluster) && self.is_valid_cluster(next) && is_lost(check, next) {
+                pointed[next as usize] = true;
             }
+        }
 
-            let bytes_to_copy = core::cmp::min(
-                core::cmp::min(self.cluster_size as usize, file_size - bytes_read),
-                buffer.len() - bytes_read,
-            );
+        let heads = (2..cluster_limit).filter(|&cluster| !pointed[cluster as usize]);
+        let cycles = (2..cluster_limit).filter(|&cluster| pointed[cluster as usize]);
+        for first_cluster in heads.chain(cycles).collect::<Vec<u32>>() {
+            if !is_lost(check, first_cluster) {
+                continue;
+            }
 
-            buffer[bytes_read..bytes_read + bytes_to_copy]
-                .copy_from_slice(&cluster_buffer[..bytes_to_copy]);
-            bytes_read += bytes_to_copy;
+            let mut clusters = 0;
+            let mut current = first_cluster;
+            while is_lost(check, current) {
+                let next = check.fat[current as usize];
+                clusters += 1;
+                self.set_checked_fat_entry(check, current, FAT32_FREE_CLUSTER)?;
+                if !self.is_valid_cluster(next) {
+                    break;
+                }
+                current = next;
+            }
+            check.report.problems.push(Fat32Problem::LostChain { first_cluster, clusters });
+        }
+        Ok(())
+    }
 
-            if let Some(next_cluster) = self.read_fat_entry(current_cluster) {
-                current_cluster = next_cluster;
-            } else {
-                break;
+    /// Contar los clústeres y comparar el contador libre de FSInfo
+    fn check_free_count(&mut self, check: &mut Fat32Check) -> Result<(), &'static str> {
+        for &value in &check.fat[2..] {
+            match value {
+                FAT32_FREE_CLUSTER => check.report.free_clusters += 1,
+                FAT32_BAD_CLUSTER => check.report.bad_clusters += 1,
+                _ => check.report.used_clusters += 1,
             }
         }
 
-        Ok(bytes_read)
+        let actual = check.report.free_clusters;
+        let recorded = self.fs_info.free_count;
+        if !self.fs_info.valid || recorded == FAT32_FSINFO_UNKNOWN || recorded == actual {
+            return Ok(());
+        }
+
+        check.report.problems.push(Fat32Problem::FreeCountMismatch { recorded, actual });
+        if check.repair {
+            self.fs_info.free_count = actual;
+            self.fs_info.next_free = check.fat[2..]
+                .iter()
+                .position(|&value| value == FAT32_FREE_CLUSTER)
+                .map_or(FAT32_FSINFO_UNKNOWN, |index| index as u32 + 2);
+            if !self.flush_fs_info() {
+                return Err("Error escribiendo FSInfo");
+            }
+        }
+        Ok(())
     }
+}
 
-    /// Obtener información del sistema de archivos
-    pub fn get_filesystem_info(&self) -> (u32, u32, u32, u32) {
-        (
-            self.total_clusters,
-            self.cluster_size,
-            self.sectors_per_fat,
-            self.root_cluster,
-        )
+impl FileSystemDriver for Fat32Driver {
+    fn open(&mut self, file_path: &str, create: bool) -> Result<FileInfo, &'static str> {
+        match self.resolve_path(file_path) {
+            Ok(info) => Ok(self.vfs_file_info(&info)),
+            Err("Ruta no encontrada") if create => {
+                let (directory_cluster, name) = self.resolve_parent(file_path)?;
+                self.create_file(directory_cluster, name, 0)?;
+                self.stat(file_path)
+            }
+            Err(e) => Err(e),
+        }
     }
 
-    /// Verificar si el sistema de archivos está montado
-    pub fn is_mounted(&self) -> bool {
-        self.state == Fat32State::Mounted
+    fn read_file(&mut self, file_path: &str, buffer: &mut [u8], offset: u64) -> Result<usize, &'static str> {
+        let info = self.resolve_path(file_path)?;
+        self.read_file_at(&info.entry, offset, buffer)
+    }
+
+    fn write_file(&mut self, file_path: &str, buffer: &[u8], offset: u64) -> Result<usize, &'static str> {
+        let (directory_cluster, name) = self.resolve_parent(file_path)?;
+        Fat32Driver::write_file(self, directory_cluster, name, offset, buffer)
+    }
+
+    fn read_dir(&mut self, dir_path: &str) -> Result<Vec<FileInfo>, &'static str> {
+        Ok(self
+            .read_dir_path(dir_path)?
+            .iter()
+            .filter(|info| !info.entry.is_dot_entry())
+            .map(|info| self.vfs_file_info(info))
+            .collect())
+    }
+
+    fn stat(&mut self, file_path: &str) -> Result<FileInfo, &'static str> {
+        let info = self.resolve_path(file_path)?;
+        Ok(self.vfs_file_info(&info))
+    }
+
+    fn unlink(&mut self, file_path: &str) -> Result<(), &'static str> {
+        let (directory_cluster, name) = self.resolve_parent(file_path)?;
+        self.delete_file(directory_cluster, name)
+    }
+
+    fn truncate(&mut self, file_path: &str, size: u64) -> Result<(), &'static str> {
+        let (directory_cluster, name) = self.resolve_parent(file_path)?;
+        self.truncate_file(directory_cluster, name, size)
+    }
+
+    fn rename(&mut self, old_path: &str, new_path: &str) -> Result<(), &'static str> {
+        let (directory_cluster, name) = self.resolve_parent(old_path)?;
+        let (new_directory_cluster, new_name) = self.resolve_parent(new_path)?;
+        self.rename_file(directory_cluster, name, new_directory_cluster, new_name)
+    }
+
+    fn sync(&mut self) -> Result<(), &'static str> {
+        if self.flush_fs_info() {
+            Ok(())
+        } else {
+            Err("Error escribiendo FSInfo")
+        }
+    }
+}
+
+/// Iterador sobre las entradas de un directorio FAT32
+///
+/// Devuelve las entradas en orden de disco, con su nombre largo cuando la
+/// secuencia LFN es válida. Termina al encontrar la marca de fin de directorio
+/// o el final de la cadena de clústeres.
+pub struct Fat32DirIter<'a> {
+    /// Driver sobre el que se lee
+    driver: &'a mut Fat32Driver,
+    /// Clúster actual (None cuando se ha terminado)
+    cluster: Option<u32>,
+    /// Contenido del clúster actual
+    buffer: Vec<u8>,
+    /// Índice de la próxima entrada dentro del clúster
+    index: usize,
+    /// El clúster actual ya está en el buffer
+    loaded: bool,
+    /// Número de clústeres visitados (protección contra cadenas cíclicas)
+    visited: u32,
+    /// Secuencia LFN en curso
+    long_name: LongNameBuilder,
+}
+
+impl<'a> Fat32DirIter<'a> {
+    /// Crear un iterador que empieza en el clúster indicado
+    fn new(driver: &'a mut Fat32Driver, cluster: u32) -> Self {
+        let buffer = Vec::new();
+        Self {
+            driver,
+            cluster: Some(cluster),
+            buffer,
+            index: 0,
+            loaded: false,
+            visited: 0,
+            long_name: LongNameBuilder::new(),
+        }
+    }
+}
+
+impl<'a> Iterator for Fat32DirIter<'a> {
+    type Item = Result<Fat32DirEntryInfo, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let cluster = self.cluster?;
+
+            if !self.loaded {
+                self.visited += 1;
+                if self.visited > self.driver.total_clusters + 1
+                    || !self.driver.read_directory_block(cluster, &mut self.buffer)
+                {
+                    self.cluster = None;
+                    return Some(Err("Error leyendo clúster de directorio"));
+                }
+                self.loaded = true;
+                self.index = 0;
+            }
+
+            if self.index >= self.buffer.len() / FAT32_DIRECTORY_ENTRY_SIZE {
+                // Pasar al siguiente clúster de la cadena
+                self.cluster = self.driver.next_directory_block(cluster);
+                self.loaded = false;
+                continue;
+            }
+
+            let i = self.index;
+            self.index += 1;
+
+            let offset = i * FAT32_DIRECTORY_ENTRY_SIZE;
+            let slot = &self.buffer[offset..offset + FAT32_DIRECTORY_ENTRY_SIZE];
+            let location = Fat32EntryLocation {
+                cluster,
+                index: i as u32,
+            };
+
+            if slot[0] == FAT32_ENTRY_END {
+                self.cluster = None; // Fin del directorio
+                return None;
+            }
+            if slot[0] == FAT32_ENTRY_DELETED {
+                self.long_name.reset();
+                continue; // Entrada eliminada
+            }
+            if is_long_name_slot(slot) {
+                self.long_name.push(slot, location);
+                continue;
+            }
+
+            let entry = Fat32DirectoryEntry::from_bytes(slot);
+            let lfn = self.long_name.finish(&entry);
+            if entry.attributes & FAT32_ATTR_VOLUME_LABEL != 0 {
+                continue; // Etiqueta de volumen
+            }
+
+            let info = match lfn {
+                Some((name, first_slot, lfn_slots)) => Fat32DirEntryInfo {
+                    entry,
+                    location,
+                    first_slot,
+                    slot_count: lfn_slots + 1,
+                    name,
+                },
+                None => Fat32DirEntryInfo {
+                    entry,
+                    location,
+                    first_slot: location,
+                    slot_count: 1,
+                    name: Fat32Driver::display_short_name(&entry),
+                },
+            };
+            return Some(Ok(info));
+        }
+    }
+}
+
+/// Opciones para formatear un volumen FAT32
+#[derive(Debug, Clone, Copy)]
+pub struct Fat32FormatOptions<'a> {
+    /// Tamaño del clúster en bytes (0 para elegirlo según el tamaño del volumen)
+    pub cluster_size: u32,
+    /// Etiqueta del volumen (hasta 11 caracteres ASCII)
+    pub label: Option<&'a str>,
+    /// Número de serie del volumen
+    pub volume_serial: u32,
+    /// Número de copias de la FAT
+    pub fat_count: u8,
+    /// Sectores reservados antes de la primera FAT (al menos 8)
+    pub reserved_sectors: u16,
+}
+
+impl Default for Fat32FormatOptions<'_> {
+    fn default() -> Self {
+        Self {
+            cluster_size: 0,
+            label: None,
+            volume_serial: 0,
+            fat_count: 2,
+            reserved_sectors: 32,
+        }
+    }
+}
+
+/// Sector FSInfo que escribe el formateo
+const FAT32_FORMAT_FSINFO_SECTOR: u32 = 1;
+/// Sector de la copia del sector de arranque (la copia de FSInfo va justo detrás)
+const FAT32_FORMAT_BACKUP_BOOT_SECTOR: u32 = 6;
+/// Tipo de medio de disco fijo
+const FAT32_MEDIA_FIXED_DISK: u8 = 0xF8;
+
+/// Tamaño de clúster recomendado para un volumen FAT32 de `volume_bytes` bytes
+pub fn default_cluster_size(volume_bytes: u64) -> u32 {
+    const MB: u64 = 1024 * 1024;
+    if volume_bytes <= 260 * MB {
+        512
+    } else if volume_bytes <= 8192 * MB {
+        4096
+    } else if volume_bytes <= 16384 * MB {
+        8192
+    } else if volume_bytes <= 32768 * MB {
+        16384
+    } else {
+        32768
+    }
+}
+
+/// Convertir una etiqueta de volumen a su forma en disco (11 bytes en mayúsculas)
+pub fn volume_label_from_str(label: &str) -> Result<[u8; 11], &'static str> {
+    let bytes = label.as_bytes();
+    if bytes.is_empty() || bytes.len() > 11 || !label.is_ascii() || bytes[0] == b' ' {
+        return Err("Etiqueta de volumen inválida");
+    }
+
+    let mut volume_label = [b' '; 11];
+    for (dst, &src) in volume_label.iter_mut().zip(bytes.iter()) {
+        *dst = if src == b' ' { src } else { short_name_char(src)? };
+    }
+    Ok(volume_label)
+}
+
+/// Formatear un dispositivo de bloque como FAT32
+///
+/// Escribe el sector de arranque y su copia, FSInfo y su copia, las FAT
+/// vacías y un directorio raíz de un clúster con la etiqueta del volumen.
+/// El tamaño de la FAT se ajusta para cubrir todos los clústeres que caben
+/// detrás de ella. Devuelve el driver montado sobre el volumen nuevo.
+pub fn format_fat32(block_device: u32, options: &Fat32FormatOptions) -> Result<Fat32Driver, &'static str> {
+    let (sector_size, sector_count, hidden_sectors) = crate::drivers::storage::get_storage_manager()
+        .and_then(|manager| manager.get_device_info(block_device))
+        .map(|device| {
+            let first_sector = device.partition.as_ref().map_or(0, |partition| partition.first_sector);
+            (device.sector_size, device.sector_count, first_sector)
+        })
+        .ok_or("Dispositivo no encontrado")?;
+    if !sector_size.is_power_of_two() || !(512..=4096).contains(&sector_size) {
+        return Err("Tamaño de sector no soportado");
+    }
+
+    let cluster_size = match options.cluster_size {
+        0 => core::cmp::max(default_cluster_size(sector_count * sector_size as u64), sector_size),
+        cluster_size => cluster_size,
+    };
+    if !cluster_size.is_power_of_two() || cluster_size < sector_size || cluster_size > 65536 {
+        return Err("Tamaño de clúster inválido");
     }
+    if options.fat_count == 0 || options.reserved_sectors < 8 {
+        return Err("Parámetros de formato inválidos");
+    }
+
+    // FAT32 no puede describir más de 2^32 - 1 sectores
+    let total_sectors = u32::try_from(sector_count).unwrap_or(u32::MAX);
+    let sectors_per_cluster = cluster_size / sector_size;
+    let reserved_sectors = options.reserved_sectors as u32;
+    let fat_count = options.fat_count as u32;
+
+    // La FAT crece hasta cubrir todos los clústeres que quedan detrás de ella
+    let mut sectors_per_fat = 1u32;
+    let total_clusters = loop {
+        let metadata_sectors = reserved_sectors as u64 + fat_count as u64 * sectors_per_fat as u64;
+        if metadata_sectors + sectors_per_cluster as u64 > total_sectors as u64 {
+            return Err("Volumen demasiado pequeño");
+        }
+        let clusters = (total_sectors - metadata_sectors as u32) / sectors_per_cluster;
+        let needed = ((clusters as u64 + 2) * 4).div_ceil(sector_size as u64) as u32;
+        if needed <= sectors_per_fat {
+            break clusters;
+        }
+        sectors_per_fat = needed;
+    };
+    if FatType::from_cluster_count(total_clusters) != FatType::Fat32 {
+        return Err("Volumen demasiado pequeño para FAT32 con ese tamaño de clúster");
+    }
+    if total_clusters > FAT32_BAD_CLUSTER - 2 {
+        return Err("Demasiados clústeres para FAT32");
+    }
+
+    let volume_label = match options.label {
+        Some(label) => volume_label_from_str(label)?,
+        None => *b"NO NAME    ",
+    };
+
+    let bytes_per_sector = sector_size as usize;
+    let write = |sector: u32, buffer: &[u8]| {
+        if crate::drivers::storage::write_sectors(block_device, sector as u64, 1, buffer) {
+            Ok(())
+        } else {
+            Err("Error escribiendo el volumen")
+        }
+    };
+
+    // Sector de arranque
+    let mut boot = vec![0u8; bytes_per_sector];
+    boot[0..3].copy_from_slice(&[0xEB, 0x58, 0x90]);
+    boot[3..11].copy_from_slice(b"REACTOS ");
+    boot[11..13].copy_from_slice(&(sector_size as u16).to_le_bytes());
+    boot[13] = sectors_per_cluster as u8;
+    boot[14..16].copy_from_slice(&options.reserved_sectors.to_le_bytes());
+    boot[16] = options.fat_count;
+    boot[21] = FAT32_MEDIA_FIXED_DISK;
+    boot[24..26].copy_from_slice(&63u16.to_le_bytes());
+    boot[26..28].copy_from_slice(&255u16.to_le_bytes());
+    boot[28..32].copy_from_slice(&(hidden_sectors as u32).to_le_bytes());
+    boot[32..36].copy_from_slice(&total_sectors.to_le_bytes());
+    boot[36..40].copy_from_slice(&sectors_per_fat.to_le_bytes());
+    boot[44..48].copy_from_slice(&2u32.to_le_bytes());
+    boot[48..50].copy_from_slice(&(FAT32_FORMAT_FSINFO_SECTOR as u16).to_le_bytes());
+    boot[50..52].copy_from_slice(&(FAT32_FORMAT_BACKUP_BOOT_SECTOR as u16).to_le_bytes());
+    boot[64] = 0x80;
+    boot[66] = 0x29;
+    boot[67..71].copy_from_slice(&options.volume_serial.to_le_bytes());
+    boot[71..82].copy_from_slice(&volume_label);
+    boot[82..90].copy_from_slice(b"FAT32   ");
+    // El volumen no es arrancable: INT 18h devuelve el control a la BIOS
+    boot[90..94].copy_from_slice(&[0xCD, 0x18, 0xEB, 0xFE]);
+    boot[510] = 0x55;
+    boot[511] = 0xAA;
+
+    // FSInfo: todos los clústeres libres salvo el del directorio raíz
+    let mut fs_info = vec![0u8; bytes_per_sector];
+    fs_info[0..4].copy_from_slice(&FAT32_FSINFO_LEAD_SIGNATURE.to_le_bytes());
+    fs_info[484..488].copy_from_slice(&FAT32_FSINFO_STRUCT_SIGNATURE.to_le_bytes());
+    fs_info[488..492].copy_from_slice(&(total_clusters - 1).to_le_bytes());
+    fs_info[492..496].copy_from_slice(&3u32.to_le_bytes());
+    fs_info[508..512].copy_from_slice(&FAT32_FSINFO_TRAIL_SIGNATURE.to_le_bytes());
+
+    // Sectores reservados y FAT a cero
+    let zeros = vec![0u8; bytes_per_sector];
+    for sector in 0..reserved_sectors + fat_count * sectors_per_fat {
+        write(sector, &zeros)?;
+    }
+    write(0, &boot)?;
+    write(FAT32_FORMAT_FSINFO_SECTOR, &fs_info)?;
+    write(FAT32_FORMAT_BACKUP_BOOT_SECTOR, &boot)?;
+    write(FAT32_FORMAT_BACKUP_BOOT_SECTOR + 1, &fs_info)?;
+
+    // Entradas 0 y 1 reservadas, y el directorio raíz en el clúster 2
+    let mut fat = vec![0u8; bytes_per_sector];
+    fat[0..4].copy_from_slice(&(0x0FFFFF00 | FAT32_MEDIA_FIXED_DISK as u32).to_le_bytes());
+    fat[4..8].copy_from_slice(&FAT32_END_OF_CHAIN.to_le_bytes());
+    fat[8..12].copy_from_slice(&FAT32_END_OF_CHAIN.to_le_bytes());
+    for fat_index in 0..fat_count {
+        write(reserved_sectors + fat_index * sectors_per_fat, &fat)?;
+    }
+
+    // Directorio raíz vacío con la etiqueta del volumen
+    let data_start = reserved_sectors + fat_count * sectors_per_fat;
+    for sector in 0..sectors_per_cluster {
+        write(data_start + sector, &zeros)?;
+    }
+    if options.label.is_some() {
+        let mut label_entry = Fat32DirectoryEntry::empty();
+        label_entry.name.copy_from_slice(&volume_label[..8]);
+        label_entry.extension.copy_from_slice(&volume_label[8..]);
+        label_entry.attributes = FAT32_ATTR_VOLUME_LABEL;
+        let mut root = zeros.clone();
+        root[..FAT32_DIRECTORY_ENTRY_SIZE].copy_from_slice(&label_entry.to_bytes());
+        write(data_start, &root)?;
+    }
+
+    init_fat32(block_device)
 }
 
 /// Función para inicializar el driver FAT32
@@ -551,3 +3080,520 @@ pub fn get_fat32_statistics() -> (u32, u32, u32, u32) {
     // TODO: Implementar acceso a las estadísticas del driver FAT32
     (1000, 4096, 100, 2) // (total_clusters, cluster_size, sectors_per_fat, root_cluster)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::test_util::TestDisk;
+
+    /// Volumen FAT32 mínimo con clústeres de 512 bytes (algo más de 65525 clústeres)
+    const FAT32_TEST_VOLUME_SIZE: usize = 36 * 1024 * 1024;
+
+    fn format_test_volume(disk: &TestDisk) -> Fat32Driver {
+        let options = Fat32FormatOptions { cluster_size: 512, label: Some("TEST"), ..Default::default() };
+        format_fat32(disk.device_id, &options).unwrap()
+    }
+
+    /// Imagen FAT12/FAT16 vacía con directorio raíz fijo, como la de mkfs.fat
+    fn fat16_style_image(total_sectors: u16, sectors_per_cluster: u8, sectors_per_fat: u16, fs_type: &[u8; 8]) -> Vec<u8> {
+        let mut image = vec![0u8; total_sectors as usize * 512];
+        let boot = &mut image[..512];
+        boot[0..3].copy_from_slice(&[0xEB, 0x3C, 0x90]);
+        boot[3..11].copy_from_slice(b"MSDOS5.0");
+        boot[11..13].copy_from_slice(&512u16.to_le_bytes());
+        boot[13] = sectors_per_cluster;
+        boot[14..16].copy_from_slice(&1u16.to_le_bytes());
+        boot[16] = 2;
+        boot[17..19].copy_from_slice(&224u16.to_le_bytes());
+        boot[19..21].copy_from_slice(&total_sectors.to_le_bytes());
+        boot[21] = 0xF0;
+        boot[22..24].copy_from_slice(&sectors_per_fat.to_le_bytes());
+        boot[38] = 0x29;
+        boot[43..54].copy_from_slice(b"NO NAME    ");
+        boot[54..62].copy_from_slice(fs_type);
+        boot[510] = 0x55;
+        boot[511] = 0xAA;
+
+        // Entradas 0 y 1 de ambas FAT: descriptor de medio y fin de cadena
+        let reserved: &[u8] = if fs_type == b"FAT12   " { &[0xF0, 0xFF, 0xFF] } else { &[0xF0, 0xFF, 0xFF, 0xFF] };
+        for fat in 0..2 {
+            let start = (1 + fat * sectors_per_fat as usize) * 512;
+            image[start..start + reserved.len()].copy_from_slice(reserved);
+        }
+        image
+    }
+
+    fn pattern(len: usize, seed: u8) -> Vec<u8> {
+        (0..len).map(|i| (i as u8).wrapping_mul(31).wrapping_add(seed)).collect()
+    }
+
+    fn read_all(driver: &mut Fat32Driver, filename: &str) -> Vec<u8> {
+        let entry = driver.find_file(0, filename).unwrap();
+        let mut buffer = vec![0u8; entry.file_size as usize];
+        assert_eq!(driver.read_file(&entry, &mut buffer).unwrap(), buffer.len());
+        buffer
+    }
+
+    #[test]
+    fn test_create_extend_truncate_delete() {
+        let disk = TestDisk::new(FAT32_TEST_VOLUME_SIZE);
+        let mut driver = format_test_volume(&disk);
+        let free_at_start = driver.count_free_clusters();
+
+        let (entry, _) = driver.create_file(0, "DATA.BIN", 0).unwrap();
+        assert_eq!({ entry.file_size }, 0);
+        assert_eq!(entry.first_cluster(), 0);
+
+        let data = pattern(3000, 7);
+        assert_eq!(driver.write_file(0, "DATA.BIN", 0, &data).unwrap(), data.len());
+        assert_eq!(read_all(&mut driver, "DATA.BIN"), data);
+        assert_eq!(driver.count_free_clusters(), free_at_start - 6);
+
+        // Escribir más allá del final deja un hueco de ceros
+        let tail = pattern(100, 99);
+        driver.write_file(0, "DATA.BIN", 5000, &tail).unwrap();
+        let content = read_all(&mut driver, "DATA.BIN");
+        assert_eq!(content.len(), 5100);
+        assert_eq!(&content[..3000], &data[..]);
+        assert!(content[3000..5000].iter().all(|&byte| byte == 0));
+        assert_eq!(&content[5000..], &tail[..]);
+
+        // Ampliar con truncate rellena con ceros; reducir libera los clústeres sobrantes
+        driver.truncate_file(0, "DATA.BIN", 6000).unwrap();
+        assert!(read_all(&mut driver, "DATA.BIN")[5100..].iter().all(|&byte| byte == 0));
+        driver.truncate_file(0, "DATA.BIN", 700).unwrap();
+        assert_eq!(read_all(&mut driver, "DATA.BIN"), &data[..700]);
+        let entry = driver.find_file(0, "DATA.BIN").unwrap();
+        assert_eq!(driver.cluster_chain(entry.first_cluster()).unwrap().len(), 2);
+        assert_eq!(driver.count_free_clusters(), free_at_start - 2);
+
+        driver.truncate_file(0, "DATA.BIN", 0).unwrap();
+        assert_eq!(driver.find_file(0, "DATA.BIN").unwrap().first_cluster(), 0);
+
+        driver.write_file(0, "DATA.BIN", 0, &data).unwrap();
+        driver.delete_file(0, "DATA.BIN").unwrap();
+        assert!(driver.find_file(0, "DATA.BIN").is_none());
+        assert_eq!(driver.count_free_clusters(), free_at_start);
+        assert!(driver.check(false).unwrap().is_clean());
+    }
+
+    #[test]
+    fn test_stale_free_count_does_not_block_allocation() {
+        let disk = TestDisk::new(FAT32_TEST_VOLUME_SIZE);
+        let mut driver = format_test_volume(&disk);
+        driver.fs_info.free_count = 0;
+        driver.flush_fs_info();
+
+        let mut driver = init_fat32(disk.device_id).unwrap();
+        assert_eq!(driver.fs_info.free_count, 0);
+        driver.create_file(0, "DATA.BIN", 0).unwrap();
+        driver.write_file(0, "DATA.BIN", 0, &pattern(3000, 7)).unwrap();
+        let free = driver.count_free_clusters();
+        assert_eq!(driver.fs_info.free_count, free);
+        assert_eq!(init_fat32(disk.device_id).unwrap().fs_info.free_count, free);
+    }
+
+    #[test]
+    fn test_short_alias_basis_and_tail() {
+        assert_eq!(short_alias_basis("Long File Name.txt"), (*b"LONGFILE", 8, *b"TXT"));
+        assert_eq!(short_alias_basis(".profile"), (*b"PROFILE ", 7, *b"   "));
+        assert_eq!(short_alias_basis("a+b=c.tar.gz"), (*b"A_B_CTAR", 8, *b"GZ "));
+        assert_eq!(short_alias_basis("ñandú.jpeg"), (*b"_AND_   ", 5, *b"JPE"));
+
+        let (basis, len, _) = short_alias_basis("Long File Name.txt");
+        assert_eq!(&short_alias_with_tail(&basis, len, 1), b"LONGFI~1");
+        assert_eq!(&short_alias_with_tail(&basis, len, 12), b"LONGF~12");
+        let (basis, len, _) = short_alias_basis("ab.c");
+        assert_eq!(&short_alias_with_tail(&basis, len, 3), b"AB~3    ");
+
+        // Los nombres 8.3 en minúsculas no necesitan LFN: usan los flags NT
+        assert_eq!(short_name_exact("readme.txt"), Some((*b"README  ", *b"TXT", FAT32_NT_LOWERCASE_BASE | FAT32_NT_LOWERCASE_EXT)));
+        assert_eq!(short_name_exact("ReadMe.txt"), None);
+    }
+
+    #[test]
+    fn test_long_names_get_unique_aliases() {
+        let disk = TestDisk::new(FAT32_TEST_VOLUME_SIZE);
+        let mut driver = format_test_volume(&disk);
+
+        let names = ["Long File Name.txt", "Long File Name 2.txt", "long file name 3.txt"];
+        for name in names {
+            driver.create_file(0, name, 0).unwrap();
+        }
+
+        for (number, name) in names.iter().enumerate() {
+            let info = driver.lookup_entry(0, name).unwrap();
+            assert_eq!(info.name, *name);
+            assert_eq!(info.slot_count, 3);
+            let alias = short_alias_with_tail(b"LONGFILE", 8, number as u32 + 1);
+            assert_eq!(info.entry.name, alias);
+            assert_eq!(&info.entry.extension, b"TXT");
+
+            // El alias también localiza la entrada
+            let (short_name, len) = Fat32Driver::format_short_name(&info.entry);
+            let short_name = core::str::from_utf8(&short_name[..len]).unwrap();
+            assert_eq!(driver.lookup_entry(0, short_name).unwrap().name, *name);
+        }
+
+        // Al borrar un archivo su alias queda libre y se reutiliza
+        driver.delete_file(0, "Long File Name.txt").unwrap();
+        driver.create_file(0, "Long File Name 4.txt", 0).unwrap();
+        assert_eq!(&driver.lookup_entry(0, "Long File Name 4.txt").unwrap().entry.name, b"LONGFI~1");
+        assert!(driver.check(false).unwrap().is_clean());
+    }
+
+    #[test]
+    fn test_directories_and_nested_paths() {
+        let disk = TestDisk::new(FAT32_TEST_VOLUME_SIZE);
+        let mut driver = format_test_volume(&disk);
+
+        let dir = driver.create_directory(0, "Docs").unwrap();
+        driver.create_file(dir.first_cluster(), "notes.txt", 0).unwrap();
+        driver.write_file(dir.first_cluster(), "notes.txt", 0, b"hola").unwrap();
+
+        let names: Vec<String> = driver.read_dir_path("/Docs").unwrap().into_iter().map(|info| info.name).collect();
+        assert_eq!(names, [".", "..", "notes.txt"]);
+        let info = driver.resolve_path("/docs/NOTES.TXT").unwrap();
+        assert_eq!(info.name, "notes.txt");
+        assert_eq!(info.size(), 4);
+        assert_eq!(driver.delete_file(0, "Docs"), Err("El directorio no está vacío"));
+
+        driver.delete_file(dir.first_cluster(), "notes.txt").unwrap();
+        driver.delete_file(0, "Docs").unwrap();
+        assert!(driver.check(false).unwrap().is_clean());
+    }
+
+    #[test]
+    fn test_fat12_floppy_read_write() {
+        // Disquete de 1,44 MB: 2847 clústeres de un sector, FAT12
+        let disk = TestDisk::with_data(fat16_style_image(2880, 1, 9, b"FAT12   "), 512);
+        let mut driver = init_fat32(disk.device_id).unwrap();
+        assert_eq!(driver.fat_type, FatType::Fat12);
+        let free_at_start = driver.count_free_clusters();
+
+        // Las entradas de 12 bits cruzan los límites de byte y de sector
+        driver.create_file(0, "Disquete.txt", 0).unwrap();
+        let data = pattern(700 * 512, 3);
+        driver.write_file(0, "Disquete.txt", 0, &data).unwrap();
+        assert_eq!(read_all(&mut driver, "DISQUETE.TXT"), data);
+        assert_eq!(driver.count_free_clusters(), free_at_start - 700);
+
+        let mut driver = init_fat32(disk.device_id).unwrap();
+        assert_eq!(read_all(&mut driver, "Disquete.txt"), data);
+        driver.truncate_file(0, "Disquete.txt", 1000).unwrap();
+        assert_eq!(driver.count_free_clusters(), free_at_start - 2);
+        driver.delete_file(0, "Disquete.txt").unwrap();
+        assert_eq!(driver.count_free_clusters(), free_at_start);
+        assert!(driver.check(false).unwrap().is_clean());
+    }
+
+    #[test]
+    fn test_fat16_fixed_root_directory() {
+        // 8 MiB con clústeres de 1 KiB: unos 8100 clústeres, FAT16
+        let disk = TestDisk::with_data(fat16_style_image(16384, 2, 32, b"FAT16   "), 512);
+        let mut driver = init_fat32(disk.device_id).unwrap();
+        assert_eq!(driver.fat_type, FatType::Fat16);
+        assert_eq!(driver.root_cluster, 0);
+
+        let dir = driver.create_directory(0, "SUB").unwrap();
+        driver.create_file(dir.first_cluster(), "Archivo largo.bin", 0).unwrap();
+        let data = pattern(5000, 11);
+        driver.write_file(dir.first_cluster(), "Archivo largo.bin", 0, &data).unwrap();
+        let info = driver.resolve_path("/sub/archivo largo.bin").unwrap();
+        let mut content = vec![0u8; 5000];
+        driver.read_file(&info.entry, &mut content).unwrap();
+        assert_eq!(content, data);
+
+        // El directorio raíz fijo tiene 224 entradas y no puede crecer
+        for i in 0..223 {
+            driver.create_file(0, &format!("F{i}"), 0).unwrap();
+        }
+        assert!(driver.create_file(0, "LLENO", 0).is_err());
+        assert!(driver.check(false).unwrap().is_clean());
+    }
+
+    /// Volumen de referencia para las pruebas de reparación
+    struct RepairVolume {
+        /// Cadena de /A.TXT (1500 bytes, 3 clústeres)
+        a: Vec<u32>,
+        /// Cadena de /B.TXT (1000 bytes, 2 clústeres)
+        b: Vec<u32>,
+        /// Primer clúster de /DIR
+        dir: u32,
+        /// Último clúster del volumen
+        last: u32,
+        /// Clústeres libres antes de la corrupción
+        free: u32,
+    }
+
+    fn repair_volume(disk: &TestDisk) -> (Fat32Driver, RepairVolume) {
+        let mut driver = format_test_volume(disk);
+        for (name, len, seed) in [("A.TXT", 1500, 1), ("B.TXT", 1000, 2)] {
+            driver.create_file(0, name, 0).unwrap();
+            driver.write_file(0, name, 0, &pattern(len, seed)).unwrap();
+        }
+        let dir = driver.create_directory(0, "DIR").unwrap().first_cluster();
+        driver.create_file(dir, "C.TXT", 0).unwrap();
+        driver.write_file(dir, "C.TXT", 0, &pattern(300, 3)).unwrap();
+        assert!(driver.flush_fs_info());
+
+        let a = driver.find_file(0, "A.TXT").unwrap().first_cluster();
+        let b = driver.find_file(0, "B.TXT").unwrap().first_cluster();
+        let volume = RepairVolume {
+            a: driver.cluster_chain(a).unwrap(),
+            b: driver.cluster_chain(b).unwrap(),
+            dir,
+            last: driver.total_clusters + 1,
+            free: driver.count_free_clusters(),
+        };
+        assert_eq!(volume.a.len(), 3);
+        assert_eq!(volume.b.len(), 2);
+        assert_eq!(driver.fs_info.free_count, volume.free);
+        assert!(driver.check(false).unwrap().is_clean());
+        (driver, volume)
+    }
+
+    fn set_entry(driver: &mut Fat32Driver, path: &str, change: impl FnOnce(&mut Fat32DirectoryEntry)) {
+        let info = driver.resolve_path(path).unwrap();
+        let mut entry = info.entry;
+        change(&mut entry);
+        driver.write_directory_entry(info.location, &entry).unwrap();
+    }
+
+    fn dot_entry(driver: &mut Fat32Driver, dir: u32) -> Fat32DirEntryInfo {
+        driver.scan_directory(&[dir]).unwrap().into_iter().find(|info| info.name == ".").unwrap()
+    }
+
+    fn bad_chain(path: &str, reason: &'static str) -> Fat32Problem {
+        Fat32Problem::BadChain { path: String::from(path), reason }
+    }
+
+    fn size_mismatch(path: &str, size: u32, clusters: u32) -> Fat32Problem {
+        Fat32Problem::SizeMismatch { path: String::from(path), size, clusters }
+    }
+
+    /// (nombre, corrupción, problemas esperados, comprobación tras reparar)
+    type RepairCase = (
+        &'static str,
+        fn(&mut Fat32Driver, &RepairVolume),
+        fn(&RepairVolume) -> Vec<Fat32Problem>,
+        fn(&mut Fat32Driver, &RepairVolume),
+    );
+
+    const REPAIR_CASES: &[RepairCase] = &[
+        (
+            "copia de la FAT distinta",
+            |driver, _| {
+                let copy_start = { driver.boot_sector.reserved_sectors } as u32 + driver.sectors_per_fat;
+                let mut sector = vec![0u8; 512];
+                assert!(driver.read_sector(copy_start, &mut sector));
+                sector[200] ^= 0xFF;
+                assert!(driver.write_sector(copy_start, &sector));
+            },
+            |_| vec![Fat32Problem::FatCopyMismatch { copy: 1, first_sector: 0 }],
+            |driver, _| {
+                let active_start = driver.active_fat_start();
+                let copy_start = { driver.boot_sector.reserved_sectors } as u32 + driver.sectors_per_fat;
+                let (mut active, mut copy) = (vec![0u8; 512], vec![0u8; 512]);
+                assert!(driver.read_sector(active_start, &mut active));
+                assert!(driver.read_sector(copy_start, &mut copy));
+                assert_eq!(active, copy);
+            },
+        ),
+        (
+            "cadena perdida",
+            |driver, volume| {
+                assert!(driver.write_fat_entry(volume.last - 1, volume.last));
+                assert!(driver.write_fat_entry(volume.last, FAT32_END_OF_CHAIN));
+            },
+            |volume| vec![Fat32Problem::LostChain { first_cluster: volume.last - 1, clusters: 2 }],
+            |driver, volume| {
+                assert_eq!(driver.read_fat_entry(volume.last - 1), Some(FAT32_FREE_CLUSTER));
+                assert_eq!(driver.read_fat_entry(volume.last), Some(FAT32_FREE_CLUSTER));
+            },
+        ),
+        (
+            "clúster compartido",
+            |driver, volume| assert!(driver.write_fat_entry(volume.b[1], volume.a[2])),
+            |volume| {
+                vec![Fat32Problem::CrossLink {
+                    path: String::from("/B.TXT"),
+                    other: String::from("/A.TXT"),
+                    cluster: volume.a[2],
+                }]
+            },
+            |driver, volume| {
+                assert_eq!(driver.read_fat_entry(volume.b[1]), Some(FAT32_END_OF_CHAIN));
+                assert_eq!(read_all(driver, "A.TXT"), pattern(1500, 1));
+                assert_eq!(read_all(driver, "B.TXT"), pattern(1000, 2));
+            },
+        ),
+        (
+            "cadena con un clúster libre",
+            |driver, volume| assert!(driver.write_fat_entry(volume.a[1], FAT32_FREE_CLUSTER)),
+            |volume| {
+                vec![
+                    bad_chain("/A.TXT", "La cadena incluye un clúster libre"),
+                    size_mismatch("/A.TXT", 1500, 1),
+                    Fat32Problem::LostChain { first_cluster: volume.a[2], clusters: 1 },
+                    Fat32Problem::FreeCountMismatch { recorded: volume.free, actual: volume.free + 2 },
+                ]
+            },
+            |driver, volume| {
+                assert_eq!(read_all(driver, "A.TXT"), &pattern(1500, 1)[..512]);
+                assert_eq!(driver.read_fat_entry(volume.a[2]), Some(FAT32_FREE_CLUSTER));
+            },
+        ),
+        (
+            "cadena cíclica",
+            |driver, volume| assert!(driver.write_fat_entry(volume.a[2], volume.a[0])),
+            |_| vec![bad_chain("/A.TXT", "Cadena de clústeres cíclica")],
+            |driver, volume| {
+                assert_eq!(driver.cluster_chain(volume.a[0]).unwrap(), volume.a);
+                assert_eq!(read_all(driver, "A.TXT"), pattern(1500, 1));
+            },
+        ),
+        (
+            "cadena fuera del volumen",
+            |driver, volume| assert!(driver.write_fat_entry(volume.a[1], volume.last + 10)),
+            |volume| {
+                vec![
+                    bad_chain("/A.TXT", "La cadena sale del volumen"),
+                    size_mismatch("/A.TXT", 1500, 2),
+                    Fat32Problem::LostChain { first_cluster: volume.a[2], clusters: 1 },
+                    Fat32Problem::FreeCountMismatch { recorded: volume.free, actual: volume.free + 1 },
+                ]
+            },
+            |driver, _| assert_eq!(read_all(driver, "A.TXT"), &pattern(1500, 1)[..1024]),
+        ),
+        (
+            "tamaño menor que la cadena",
+            |driver, _| set_entry(driver, "/A.TXT", |entry| entry.file_size = 100),
+            |volume| {
+                vec![
+                    size_mismatch("/A.TXT", 100, 3),
+                    Fat32Problem::FreeCountMismatch { recorded: volume.free, actual: volume.free + 2 },
+                ]
+            },
+            |driver, volume| {
+                assert_eq!(driver.cluster_chain(volume.a[0]).unwrap(), &volume.a[..1]);
+                assert_eq!(read_all(driver, "A.TXT"), &pattern(1500, 1)[..100]);
+            },
+        ),
+        (
+            "tamaño mayor que la cadena",
+            |driver, _| set_entry(driver, "/B.TXT", |entry| entry.file_size = 5000),
+            |_| vec![size_mismatch("/B.TXT", 5000, 2)],
+            |driver, _| {
+                let content = read_all(driver, "B.TXT");
+                assert_eq!(content.len(), 1024);
+                assert_eq!(&content[..1000], &pattern(1000, 2)[..]);
+            },
+        ),
+        (
+            "nombre corto inválido",
+            |driver, _| set_entry(driver, "/B.TXT", |entry| entry.name[0] = b'*'),
+            |volume| {
+                vec![
+                    Fat32Problem::BadDirectoryEntry { path: String::from("/*.TXT"), reason: "Nombre corto inválido" },
+                    Fat32Problem::LostChain { first_cluster: volume.b[0], clusters: 2 },
+                    Fat32Problem::FreeCountMismatch { recorded: volume.free, actual: volume.free + 2 },
+                ]
+            },
+            |driver, volume| {
+                assert!(driver.read_dir_path("/").unwrap().iter().all(|info| info.name != "*.TXT"));
+                assert_eq!(driver.read_fat_entry(volume.b[0]), Some(FAT32_FREE_CLUSTER));
+                assert_eq!(read_all(driver, "A.TXT"), pattern(1500, 1));
+            },
+        ),
+        (
+            "entrada \".\" con clúster incorrecto",
+            |driver, volume| {
+                let info = dot_entry(driver, volume.dir);
+                let mut entry = info.entry;
+                entry.set_first_cluster(volume.a[0]);
+                driver.write_directory_entry(info.location, &entry).unwrap();
+            },
+            |_| {
+                vec![Fat32Problem::BadDirectoryEntry {
+                    path: String::from("/DIR/."),
+                    reason: "Entrada \".\" o \"..\" con clúster incorrecto",
+                }]
+            },
+            |driver, volume| assert_eq!(dot_entry(driver, volume.dir).entry.first_cluster(), volume.dir),
+        ),
+        (
+            "contador de FSInfo",
+            |driver, _| {
+                driver.fs_info.free_count -= 5;
+                assert!(driver.flush_fs_info());
+            },
+            |volume| vec![Fat32Problem::FreeCountMismatch { recorded: volume.free - 5, actual: volume.free }],
+            |driver, volume| assert_eq!(driver.fs_info.free_count, volume.free),
+        ),
+    ];
+
+    #[test]
+    fn test_check_repairs() {
+        for &(name, corrupt, expected, verify) in REPAIR_CASES {
+            let disk = TestDisk::new(FAT32_TEST_VOLUME_SIZE);
+            let (mut driver, volume) = repair_volume(&disk);
+            corrupt(&mut driver, &volume);
+            let expected = expected(&volume);
+
+            // Sin reparar se informa lo mismo dos veces: no se escribió nada
+            let report = driver.check(false).unwrap();
+            assert_eq!(report.problems, expected, "{name}");
+            assert!(!report.repaired, "{name}");
+            assert_eq!(driver.check(false).unwrap().problems, expected, "{name}");
+
+            let report = driver.check(true).unwrap();
+            assert_eq!(report.problems, expected, "{name}");
+            assert!(report.repaired, "{name}");
+            assert!(driver.check(false).unwrap().is_clean(), "{name}");
+            verify(&mut driver, &volume);
+
+            // La reparación quedó en disco
+            let mut remounted = init_fat32(disk.device_id).unwrap();
+            assert!(remounted.check(false).unwrap().is_clean(), "{name}");
+            verify(&mut remounted, &volume);
+        }
+    }
+
+    #[test]
+    fn test_mount_image_file_through_register_image() {
+        use crate::drivers::{image, storage};
+        use crate::filesystem::test_util::{lock_storage, with_vfs};
+        use crate::filesystem::vfs::{probe_filesystem, FileSystemType, VFS_OPEN_CREATE};
+
+        with_vfs(|vfs| {
+            let _lock = lock_storage();
+            let path = std::env::temp_dir().join(format!("reactos-fat32-mount-{}.img", std::process::id()));
+            std::fs::File::create(&path).unwrap().set_len(FAT32_TEST_VOLUME_SIZE as u64).unwrap();
+
+            // Formatear y escribir a través del VFS sobre la imagen del host
+            let device_id = image::register_image_file(&path, 512, false).unwrap();
+            let options = Fat32FormatOptions { cluster_size: 512, label: Some("IMAGEN"), ..Default::default() };
+            format_fat32(device_id, &options).unwrap();
+            let mount_id = vfs.mount_device("/mnt", device_id).unwrap();
+            let fd = vfs.open_path("/mnt/hola.txt", 0, VFS_OPEN_CREATE).unwrap();
+            assert_eq!(vfs.write_file(fd, b"Hola desde el host"), Ok(18));
+            vfs.close_file(fd).unwrap();
+            vfs.unmount(mount_id).unwrap();
+            assert!(storage::get_storage_manager().unwrap().unregister_device(device_id));
+
+            // Volver a abrir la imagen en solo lectura y leer lo escrito
+            let device_id = image::register_image_file(&path, 512, true).unwrap();
+            assert_eq!(probe_filesystem(device_id), Ok(FileSystemType::FAT32));
+            let mount_id = vfs.mount_device("/mnt", device_id).unwrap();
+            let fd = vfs.open_path("/mnt/HOLA.TXT", 0, 0).unwrap();
+            let mut buffer = [0u8; 64];
+            let length = vfs.read_file(fd, &mut buffer).unwrap();
+            assert_eq!(&buffer[..length], b"Hola desde el host");
+            vfs.close_file(fd).unwrap();
+            vfs.unmount(mount_id).unwrap();
+            assert!(storage::get_storage_manager().unwrap().unregister_device(device_id));
+            std::fs::remove_file(&path).unwrap();
+        });
+    }
+}
@@ -0,0 +1,1267 @@
+//! Driver ISO 9660 para ReactOS Rust Kernel
+//!
+//! Implementa un driver de solo lectura para ISO 9660 (CD, DVD y las
+//! imágenes de arranque del proyecto) con las extensiones Joliet (nombres
+//! UCS-2) y Rock Ridge (nombres, permisos y enlaces simbólicos POSIX),
+//! archivos de varios extents y el catálogo de arranque El Torito.
+
+use alloc::{string::String, vec, vec::Vec};
+
+use super::fat32::names_equal_ignore_case;
+use super::vfs::{FileInfo, FilePermissions, FileSystemDriver, NodeType, VFS_MAX_SYMLINK_DEPTH};
+
+/// Tamaño de bloque lógico
+pub const ISO9660_BLOCK_SIZE: usize = 2048;
+
+/// Bloque del primer descriptor de volumen
+pub const ISO9660_FIRST_DESCRIPTOR: u32 = 16;
+
+/// Número máximo de descriptores de volumen que se examinan
+pub const ISO9660_MAX_DESCRIPTORS: u32 = 64;
+
+/// Identificador estándar de los descriptores de volumen
+pub const ISO9660_STANDARD_ID: &[u8; 5] = b"CD001";
+
+// Tipos de descriptor de volumen
+pub const ISO9660_DESCRIPTOR_BOOT_RECORD: u8 = 0;
+pub const ISO9660_DESCRIPTOR_PRIMARY: u8 = 1;
+pub const ISO9660_DESCRIPTOR_SUPPLEMENTARY: u8 = 2;
+pub const ISO9660_DESCRIPTOR_TERMINATOR: u8 = 255;
+
+// Flags de los registros de directorio
+pub const ISO9660_FLAG_HIDDEN: u8 = 0x01;
+pub const ISO9660_FLAG_DIRECTORY: u8 = 0x02;
+pub const ISO9660_FLAG_ASSOCIATED: u8 = 0x04;
+pub const ISO9660_FLAG_MULTI_EXTENT: u8 = 0x80;
+
+/// Identificador del registro de arranque El Torito
+pub const ELTORITO_SYSTEM_ID: &[u8; 23] = b"EL TORITO SPECIFICATION";
+
+// Plataformas del catálogo de arranque El Torito
+pub const ELTORITO_PLATFORM_X86: u8 = 0x00;
+pub const ELTORITO_PLATFORM_EFI: u8 = 0xEF;
+
+// Tipos de archivo POSIX (campo de modo de Rock Ridge)
+const POSIX_TYPE_MASK: u32 = 0o170000;
+const POSIX_TYPE_SYMLINK: u32 = 0o120000;
+
+/// Zona contigua de un archivo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Iso9660Extent {
+    /// Primer bloque de datos (ya descontado el registro de atributos extendidos)
+    pub block: u32,
+    /// Longitud en bytes
+    pub length: u32,
+}
+
+/// Entrada de directorio ISO 9660 con sus extensiones
+#[derive(Debug, Clone)]
+pub struct Iso9660DirEntry {
+    /// Nombre (Rock Ridge, Joliet o ISO 9660 sin versión)
+    pub name: String,
+    /// Flags del registro de directorio
+    pub flags: u8,
+    /// Extents del archivo, en orden
+    pub extents: Vec<Iso9660Extent>,
+    /// Tamaño total
+    pub size: u64,
+    /// Modo POSIX (Rock Ridge)
+    pub mode: Option<u32>,
+    /// Número de enlaces (Rock Ridge)
+    pub links: u32,
+    /// ID del propietario (Rock Ridge)
+    pub uid: u32,
+    /// ID del grupo (Rock Ridge)
+    pub gid: u32,
+    /// Número de serie del archivo (Rock Ridge) o primer bloque
+    pub inode: u64,
+    /// Destino, si es un enlace simbólico (Rock Ridge)
+    pub symlink_target: Option<String>,
+    /// Tiempo de creación
+    pub created_time: u64,
+    /// Tiempo de modificación (fecha del registro si no hay Rock Ridge)
+    pub modified_time: u64,
+    /// Tiempo de acceso
+    pub accessed_time: u64,
+}
+
+impl Iso9660DirEntry {
+    /// Verificar si es un directorio
+    pub fn is_directory(&self) -> bool {
+        self.flags & ISO9660_FLAG_DIRECTORY != 0
+    }
+
+    /// Verificar si es un enlace simbólico
+    pub fn is_symlink(&self) -> bool {
+        self.symlink_target.is_some()
+    }
+}
+
+/// Campos Rock Ridge de un registro de directorio (área de uso del sistema)
+#[derive(Debug, Clone, Default)]
+struct RockRidgeFields {
+    /// Entrada "SP": bytes que hay que saltar al principio del área
+    sp_skip: Option<u8>,
+    /// Entrada "ER" (identificación de la extensión)
+    extension_reference: bool,
+    /// Entrada "NM"
+    name: Option<String>,
+    /// Entrada "PX": modo, enlaces, uid, gid y número de serie
+    mode: Option<u32>,
+    links: u32,
+    uid: u32,
+    gid: u32,
+    inode: Option<u64>,
+    /// Entrada "SL"
+    symlink_target: Option<String>,
+    /// Entrada "TF": creación, modificación y acceso
+    created_time: Option<u64>,
+    modified_time: Option<u64>,
+    accessed_time: Option<u64>,
+    /// Entrada "CL": directorio reubicado
+    child_link: Option<u32>,
+    /// Entrada "RE": este registro es el directorio reubicado
+    relocated: bool,
+}
+
+/// Tipos de emulación de una imagen de arranque El Torito
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElToritoMediaType {
+    /// Sin emulación
+    NoEmulation,
+    /// Disquete de 1.2 MB
+    Floppy1200,
+    /// Disquete de 1.44 MB
+    Floppy1440,
+    /// Disquete de 2.88 MB
+    Floppy2880,
+    /// Disco duro
+    HardDisk,
+    /// Desconocido
+    Unknown,
+}
+
+impl ElToritoMediaType {
+    fn from_byte(media: u8) -> Self {
+        match media & 0x0F {
+            0 => ElToritoMediaType::NoEmulation,
+            1 => ElToritoMediaType::Floppy1200,
+            2 => ElToritoMediaType::Floppy1440,
+            3 => ElToritoMediaType::Floppy2880,
+            4 => ElToritoMediaType::HardDisk,
+            _ => ElToritoMediaType::Unknown,
+        }
+    }
+}
+
+/// Entrada de arranque del catálogo El Torito
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ElToritoBootEntry {
+    /// Plataforma (`ELTORITO_PLATFORM_*`)
+    pub platform_id: u8,
+    /// Entrada marcada como arrancable
+    pub bootable: bool,
+    /// Tipo de emulación
+    pub media_type: ElToritoMediaType,
+    /// Segmento de carga (0 = 0x7C0)
+    pub load_segment: u16,
+    /// Tipo de sistema (tipo de partición de la imagen)
+    pub system_type: u8,
+    /// Sectores virtuales de 512 bytes que se cargan
+    pub sector_count: u16,
+    /// Bloque de la imagen de arranque
+    pub load_block: u32,
+}
+
+/// Driver ISO 9660
+pub struct Iso9660Driver {
+    /// Dispositivo de bloque
+    pub device_id: u32,
+    /// Sectores del dispositivo por bloque lógico
+    pub sectors_per_block: u32,
+    /// Volumen montado
+    pub mounted: bool,
+    /// Identificador del volumen
+    pub volume_id: String,
+    /// Tamaño del volumen en bloques
+    pub volume_blocks: u32,
+    /// Raíz del árbol primario
+    pub primary_root: Option<Iso9660DirEntry>,
+    /// Raíz del árbol Joliet, si lo hay
+    pub joliet_root: Option<Iso9660DirEntry>,
+    /// El árbol primario tiene extensiones Rock Ridge
+    pub rock_ridge: bool,
+    /// Bytes que se saltan al principio de cada área de uso del sistema ("SP")
+    pub susp_skip: usize,
+    /// Bloque del catálogo de arranque El Torito
+    pub boot_catalog: Option<u32>,
+}
+
+impl Iso9660Driver {
+    /// Crear un driver sin montar
+    pub fn new(device_id: u32) -> Self {
+        Self {
+            device_id,
+            sectors_per_block: 4,
+            mounted: false,
+            volume_id: String::new(),
+            volume_blocks: 0,
+            primary_root: None,
+            joliet_root: None,
+            rock_ridge: false,
+            susp_skip: 0,
+            boot_catalog: None,
+        }
+    }
+
+    /// Leer los descriptores de volumen y montar
+    pub fn initialize(&mut self) -> Result<(), &'static str> {
+        let sector_size = crate::drivers::storage::get_storage_manager()
+            .and_then(|manager| manager.get_device_info(self.device_id))
+            .map_or(512, |device| device.sector_size as usize);
+        if sector_size == 0 || sector_size > ISO9660_BLOCK_SIZE || ISO9660_BLOCK_SIZE % sector_size != 0 {
+            return Err("Tamaño de sector no soportado");
+        }
+        self.sectors_per_block = (ISO9660_BLOCK_SIZE / sector_size) as u32;
+
+        let mut descriptor = vec![0u8; ISO9660_BLOCK_SIZE];
+        let mut primary = None;
+        for block in ISO9660_FIRST_DESCRIPTOR..ISO9660_FIRST_DESCRIPTOR + ISO9660_MAX_DESCRIPTORS {
+            if !self.read_block(block, &mut descriptor) {
+                return Err("Error leyendo descriptor de volumen");
+            }
+            if &descriptor[1..6] != ISO9660_STANDARD_ID {
+                return Err("No es un volumen ISO 9660");
+            }
+            match descriptor[0] {
+                ISO9660_DESCRIPTOR_PRIMARY if primary.is_none() => {
+                    if read_u16(&descriptor, 128) as usize != ISO9660_BLOCK_SIZE {
+                        return Err("Tamaño de bloque lógico no soportado");
+                    }
+                    self.volume_id = String::from(trim_identifier(&descriptor[40..72]));
+                    self.volume_blocks = read_u32(&descriptor, 80);
+                    primary = Some(self.root_entry(&descriptor[156..190])?);
+                }
+                // Joliet: descriptor suplementario con secuencia de escape UCS-2
+                ISO9660_DESCRIPTOR_SUPPLEMENTARY if self.joliet_root.is_none() => {
+                    if descriptor[88] == b'%' && descriptor[89] == b'/' && matches!(descriptor[90], b'@' | b'C' | b'E') {
+                        self.joliet_root = Some(self.root_entry(&descriptor[156..190])?);
+                    }
+                }
+                ISO9660_DESCRIPTOR_BOOT_RECORD => {
+                    if &descriptor[7..30] == ELTORITO_SYSTEM_ID {
+                        self.boot_catalog = Some(read_u32(&descriptor, 0x47));
+                    }
+                }
+                ISO9660_DESCRIPTOR_TERMINATOR => break,
+                _ => {}
+            }
+        }
+        let primary = primary.ok_or("Descriptor de volumen primario no encontrado")?;
+
+        // Rock Ridge: la entrada "SP" está en el registro "." de la raíz
+        let extent = primary.extents.first().copied().ok_or("Directorio raíz inválido")?;
+        let mut block = vec![0u8; ISO9660_BLOCK_SIZE];
+        if !self.read_block(extent.block, &mut block) {
+            return Err("Error leyendo directorio raíz");
+        }
+        let record_length = block[0] as usize;
+        if record_length < 34 {
+            return Err("Directorio raíz inválido");
+        }
+        let fields = self.parse_system_use(system_use_area(&block[..record_length], 0));
+        if let Some(skip) = fields.sp_skip {
+            self.susp_skip = skip as usize;
+            self.rock_ridge = fields.extension_reference || fields.mode.is_some();
+        }
+
+        self.primary_root = Some(primary);
+        self.mounted = true;
+        Ok(())
+    }
+
+    /// Leer un bloque lógico
+    fn read_block(&self, block: u32, buffer: &mut [u8]) -> bool {
+        crate::drivers::storage::read_sectors(
+            self.device_id,
+            block as u64 * self.sectors_per_block as u64,
+            self.sectors_per_block,
+            &mut buffer[..ISO9660_BLOCK_SIZE],
+        )
+    }
+
+    /// Entrada del directorio raíz a partir del registro de un descriptor
+    fn root_entry(&self, record: &[u8]) -> Result<Iso9660DirEntry, &'static str> {
+        if record[0] < 34 || record[25] & ISO9660_FLAG_DIRECTORY == 0 {
+            return Err("Directorio raíz inválido");
+        }
+        let block = read_u32(record, 2) + record[1] as u32;
+        let length = read_u32(record, 10);
+        let time = recording_time_to_unix(&record[18..25]);
+        Ok(Iso9660DirEntry {
+            name: String::from("/"),
+            flags: record[25],
+            extents: vec![Iso9660Extent { block, length }],
+            size: length as u64,
+            mode: None,
+            links: 2,
+            uid: 0,
+            gid: 0,
+            inode: block as u64,
+            symlink_target: None,
+            created_time: time,
+            modified_time: time,
+            accessed_time: time,
+        })
+    }
+
+    /// Raíz del árbol que se usa para los nombres
+    ///
+    /// Rock Ridge tiene preferencia porque además aporta permisos y enlaces;
+    /// si no lo hay se usa Joliet y, en último caso, los nombres ISO 9660.
+    pub fn root(&self) -> Result<&Iso9660DirEntry, &'static str> {
+        if !self.mounted {
+            return Err("Sistema de archivos no montado");
+        }
+        let root = if self.rock_ridge { None } else { self.joliet_root.as_ref() };
+        root.or(self.primary_root.as_ref()).ok_or("Sistema de archivos no montado")
+    }
+
+    /// Verificar si se está usando el árbol Joliet
+    fn using_joliet(&self) -> bool {
+        !self.rock_ridge && self.joliet_root.is_some()
+    }
+
+    /// Leer las entradas de un directorio
+    ///
+    /// Se omiten "." y "..", los archivos asociados y los directorios
+    /// reubicados por Rock Ridge (aparecen en su posición original). Los
+    /// registros consecutivos de un archivo de varios extents se unen.
+    pub fn read_directory(&self, directory: &Iso9660DirEntry) -> Result<Vec<Iso9660DirEntry>, &'static str> {
+        if !directory.is_directory() {
+            return Err("La ruta no es un directorio");
+        }
+        let joliet = self.using_joliet();
+        let mut entries: Vec<Iso9660DirEntry> = Vec::new();
+        // Registro anterior con más extents: `Some(true)` si se añadió a `entries`
+        let mut continuation: Option<bool> = None;
+        let mut block = vec![0u8; ISO9660_BLOCK_SIZE];
+
+        for extent in &directory.extents {
+            let blocks = (extent.length as usize + ISO9660_BLOCK_SIZE - 1) / ISO9660_BLOCK_SIZE;
+            for index in 0..blocks {
+                if !self.read_block(extent.block + index as u32, &mut block) {
+                    return Err("Error leyendo directorio");
+                }
+                let end = core::cmp::min(ISO9660_BLOCK_SIZE, extent.length as usize - index * ISO9660_BLOCK_SIZE);
+                let mut offset = 0;
+                // Los registros no cruzan bloques; un byte 0 indica relleno hasta el siguiente
+                while offset + 33 <= end && block[offset] != 0 {
+                    let record_length = block[offset] as usize;
+                    if record_length < 34 || offset + record_length > end {
+                        return Err("Registro de directorio inválido");
+                    }
+                    let record = &block[offset..offset + record_length];
+                    offset += record_length;
+
+                    let name_length = record[32] as usize;
+                    if name_length == 1 && record[33] <= 1 {
+                        continue;
+                    }
+                    let flags = record[25];
+                    let data = Iso9660Extent { block: read_u32(record, 2) + record[1] as u32, length: read_u32(record, 10) };
+                    let more = flags & ISO9660_FLAG_MULTI_EXTENT != 0;
+                    if let Some(listed) = continuation {
+                        // Siguiente extent del archivo anterior
+                        if listed {
+                            if let Some(previous) = entries.last_mut() {
+                                previous.extents.push(data);
+                                previous.size += data.length as u64;
+                            }
+                        }
+                        continuation = if more { Some(listed) } else { None };
+                        continue;
+                    }
+                    continuation = if more { Some(false) } else { None };
+                    if flags & ISO9660_FLAG_ASSOCIATED != 0 {
+                        continue;
+                    }
+
+                    let fields = if self.rock_ridge {
+                        self.parse_system_use(system_use_area(record, self.susp_skip))
+                    } else {
+                        RockRidgeFields::default()
+                    };
+                    if fields.relocated {
+                        continue;
+                    }
+                    if let Some(entry) = self.build_entry(record, data, fields, joliet)? {
+                        entries.push(entry);
+                        if more {
+                            continuation = Some(true);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Construir una entrada a partir de un registro y sus campos Rock Ridge
+    fn build_entry(&self, record: &[u8], data: Iso9660Extent, fields: RockRidgeFields, joliet: bool) -> Result<Option<Iso9660DirEntry>, &'static str> {
+        let raw_name = &record[33..33 + record[32] as usize];
+        let name = match fields.name {
+            Some(name) => name,
+            None if joliet => clean_name(&decode_ucs2(raw_name)),
+            None => clean_name(core::str::from_utf8(raw_name).unwrap_or("")),
+        };
+        if name.is_empty() || name.contains('/') {
+            return Ok(None);
+        }
+
+        let mut flags = record[25];
+        let mut data = data;
+        // Directorio reubicado: el registro es un archivo que apunta a él
+        if let Some(child) = fields.child_link {
+            let mut block = vec![0u8; ISO9660_BLOCK_SIZE];
+            if !self.read_block(child, &mut block) {
+                return Err("Error leyendo directorio reubicado");
+            }
+            flags |= ISO9660_FLAG_DIRECTORY;
+            data = Iso9660Extent { block: child, length: read_u32(&block, 10) };
+        }
+
+        let recorded = recording_time_to_unix(&record[18..25]);
+        let is_directory = flags & ISO9660_FLAG_DIRECTORY != 0;
+        let symlink_target = match fields.mode {
+            Some(mode) if mode & POSIX_TYPE_MASK == POSIX_TYPE_SYMLINK => fields.symlink_target,
+            _ => None,
+        };
+        Ok(Some(Iso9660DirEntry {
+            name,
+            flags,
+            extents: vec![data],
+            size: data.length as u64,
+            mode: fields.mode,
+            links: if fields.mode.is_some() { fields.links } else if is_directory { 2 } else { 1 },
+            uid: fields.uid,
+            gid: fields.gid,
+            inode: fields.inode.unwrap_or(data.block as u64),
+            symlink_target,
+            created_time: fields.created_time.unwrap_or(recorded),
+            modified_time: fields.modified_time.unwrap_or(recorded),
+            accessed_time: fields.accessed_time.unwrap_or(recorded),
+        }))
+    }
+
+    /// Interpretar las entradas SUSP/Rock Ridge de un área de uso del sistema
+    ///
+    /// Se siguen las áreas de continuación ("CE") y se para en "ST".
+    fn parse_system_use(&self, area: &[u8]) -> RockRidgeFields {
+        let mut fields = RockRidgeFields::default();
+        let mut name = String::new();
+        let mut has_name = false;
+        let mut link = String::new();
+        let mut has_link = false;
+        let mut link_continues = false;
+
+        let mut current: Vec<u8> = Vec::from(area);
+        let mut continuations = 0;
+        loop {
+            let mut continuation = None;
+            let mut offset = 0;
+            while offset + 4 <= current.len() {
+                let entry_length = current[offset + 2] as usize;
+                if entry_length < 4 || offset + entry_length > current.len() {
+                    break;
+                }
+                let entry = &current[offset..offset + entry_length];
+                offset += entry_length;
+
+                match &entry[0..2] {
+                    b"SP" if entry_length >= 7 && entry[4] == 0xBE && entry[5] == 0xEF => fields.sp_skip = Some(entry[6]),
+                    b"ER" => fields.extension_reference = true,
+                    b"CE" if entry_length >= 28 => {
+                        continuation = Some((read_u32(entry, 4), read_u32(entry, 12) as usize, read_u32(entry, 20) as usize));
+                    }
+                    b"PX" if entry_length >= 36 => {
+                        fields.mode = Some(read_u32(entry, 4));
+                        fields.links = read_u32(entry, 12);
+                        fields.uid = read_u32(entry, 20);
+                        fields.gid = read_u32(entry, 28);
+                        if entry_length >= 44 {
+                            fields.inode = Some(read_u32(entry, 36) as u64);
+                        }
+                    }
+                    b"NM" if entry_length >= 5 => {
+                        // Los flags "." y ".." no aportan nombre
+                        if entry[4] & 0x06 == 0 {
+                            name.push_str(core::str::from_utf8(&entry[5..]).unwrap_or(""));
+                            has_name = true;
+                        }
+                    }
+                    b"SL" if entry_length >= 5 => {
+                        has_link = true;
+                        let mut component = 5;
+                        while component + 2 <= entry_length {
+                            let component_flags = entry[component];
+                            let length = entry[component + 1] as usize;
+                            let content = &entry[component + 2..core::cmp::min(component + 2 + length, entry_length)];
+                            component += 2 + length;
+
+                            if !link_continues && !link.is_empty() && !link.ends_with('/') {
+                                link.push('/');
+                            }
+                            if component_flags & 0x08 != 0 {
+                                link.clear();
+                                link.push('/');
+                            } else if component_flags & 0x04 != 0 {
+                                link.push_str("..");
+                            } else if component_flags & 0x02 != 0 {
+                                link.push('.');
+                            } else {
+                                link.push_str(core::str::from_utf8(content).unwrap_or(""));
+                            }
+                            link_continues = component_flags & 0x01 != 0;
+                        }
+                    }
+                    b"TF" if entry_length >= 5 => {
+                        let tf_flags = entry[4];
+                        let long_form = tf_flags & 0x80 != 0;
+                        let size = if long_form { 17 } else { 7 };
+                        let mut position = 5;
+                        for bit in 0..7 {
+                            if tf_flags & (1 << bit) == 0 {
+                                continue;
+                            }
+                            if position + size > entry_length {
+                                break;
+                            }
+                            let stamp = &entry[position..position + size];
+                            let time = if long_form { descriptor_time_to_unix(stamp) } else { recording_time_to_unix(stamp) };
+                            match bit {
+                                0 => fields.created_time = Some(time),
+                                1 => fields.modified_time = Some(time),
+                                2 => fields.accessed_time = Some(time),
+                                _ => {}
+                            }
+                            position += size;
+                        }
+                    }
+                    b"CL" if entry_length >= 12 => fields.child_link = Some(read_u32(entry, 4)),
+                    b"RE" => fields.relocated = true,
+                    b"ST" => {
+                        continuation = None;
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+
+            // Área de continuación (limitada para no entrar en ciclos)
+            let (block, offset, length) = match continuation {
+                Some(continuation) if continuations < 16 => continuation,
+                _ => break,
+            };
+            continuations += 1;
+            let mut buffer = vec![0u8; ISO9660_BLOCK_SIZE];
+            if offset >= ISO9660_BLOCK_SIZE || !self.read_block(block, &mut buffer) {
+                break;
+            }
+            let end = core::cmp::min(ISO9660_BLOCK_SIZE, offset + length);
+            current = Vec::from(&buffer[offset..end]);
+        }
+
+        if has_name {
+            fields.name = Some(name);
+        }
+        if has_link {
+            fields.symlink_target = Some(link);
+        }
+        fields
+    }
+
+    /// Buscar una entrada por nombre en un directorio
+    ///
+    /// Con Rock Ridge los nombres distinguen mayúsculas; con Joliet e ISO 9660 no.
+    fn find_entry(&self, directory: &Iso9660DirEntry, name: &str) -> Result<Iso9660DirEntry, &'static str> {
+        self.read_directory(directory)?
+            .into_iter()
+            .find(|entry| if self.rock_ridge { entry.name == name } else { names_equal_ignore_case(&entry.name, name) })
+            .ok_or("Ruta no encontrada")
+    }
+
+    /// Resolver una ruta del volumen
+    ///
+    /// Se siguen los enlaces simbólicos intermedios (y el final si
+    /// `follow_final`); los destinos absolutos parten de la raíz del volumen.
+    pub fn resolve_path(&self, path: &str, follow_final: bool) -> Result<Iso9660DirEntry, &'static str> {
+        let mut pending: Vec<String> = path
+            .split(|c| c == '/' || c == '\\')
+            .filter(|component| !component.is_empty())
+            .rev()
+            .map(String::from)
+            .collect();
+        let mut stack = vec![self.root()?.clone()];
+        let mut links = 0;
+
+        while let Some(component) = pending.pop() {
+            if component == "." {
+                continue;
+            }
+            if component == ".." {
+                if stack.len() > 1 {
+                    stack.pop();
+                }
+                continue;
+            }
+            let current = stack.last().ok_or("Ruta no encontrada")?;
+            if !current.is_directory() {
+                return Err("Componente de ruta no es un directorio");
+            }
+
+            let entry = self.find_entry(current, &component)?;
+            match entry.symlink_target {
+                Some(ref target) if follow_final || !pending.is_empty() => {
+                    links += 1;
+                    if links > VFS_MAX_SYMLINK_DEPTH {
+                        return Err("Demasiados niveles de enlaces simbólicos");
+                    }
+                    if target.starts_with('/') {
+                        stack.truncate(1);
+                    }
+                    pending.extend(target.split('/').filter(|component| !component.is_empty()).rev().map(String::from));
+                }
+                _ => stack.push(entry),
+            }
+        }
+
+        stack.pop().ok_or("Ruta no encontrada")
+    }
+
+    /// Leer el destino de un enlace simbólico
+    pub fn read_link(&self, path: &str) -> Result<String, &'static str> {
+        self.resolve_path(path, false)?.symlink_target.ok_or("No es un enlace simbólico")
+    }
+
+    /// Leer datos de un archivo a partir de un desplazamiento
+    pub fn read_file(&self, path: &str, offset: u64, buffer: &mut [u8]) -> Result<usize, &'static str> {
+        let entry = self.resolve_path(path, true)?;
+        if entry.is_directory() {
+            return Err("No se puede leer un directorio como archivo");
+        }
+        self.read_entry(&entry, offset, buffer)
+    }
+
+    /// Leer datos de una entrada recorriendo sus extents
+    pub fn read_entry(&self, entry: &Iso9660DirEntry, offset: u64, buffer: &mut [u8]) -> Result<usize, &'static str> {
+        if offset >= entry.size {
+            return Ok(0);
+        }
+        let length = core::cmp::min(buffer.len() as u64, entry.size - offset) as usize;
+        let mut block = vec![0u8; ISO9660_BLOCK_SIZE];
+        let mut done = 0;
+        let mut extent_start = 0u64;
+
+        for extent in &entry.extents {
+            let extent_end = extent_start + extent.length as u64;
+            while done < length && offset + (done as u64) < extent_end {
+                let position = offset + done as u64 - extent_start;
+                let block_offset = (position % ISO9660_BLOCK_SIZE as u64) as usize;
+                let chunk = core::cmp::min(
+                    core::cmp::min(ISO9660_BLOCK_SIZE - block_offset, length - done),
+                    (extent_end - offset - done as u64) as usize,
+                );
+                if !self.read_block(extent.block + (position / ISO9660_BLOCK_SIZE as u64) as u32, &mut block) {
+                    return Err("Error leyendo bloque de datos");
+                }
+                buffer[done..done + chunk].copy_from_slice(&block[block_offset..block_offset + chunk]);
+                done += chunk;
+            }
+            extent_start = extent_end;
+        }
+
+        Ok(done)
+    }
+
+    /// Leer el catálogo de arranque El Torito
+    ///
+    /// Devuelve la entrada inicial seguida de las entradas de cada sección
+    /// (por ejemplo, la imagen EFI de un CD de arranque híbrido).
+    pub fn read_boot_catalog(&self) -> Result<Vec<ElToritoBootEntry>, &'static str> {
+        let catalog = self.boot_catalog.ok_or("El volumen no tiene catálogo de arranque")?;
+        let mut block = vec![0u8; ISO9660_BLOCK_SIZE];
+        if !self.read_block(catalog, &mut block) {
+            return Err("Error leyendo catálogo de arranque");
+        }
+
+        // Entrada de validación: cabecera 1, clave 55 AA y suma de palabras nula
+        let checksum = (0..16).fold(0u16, |sum, word| sum.wrapping_add(read_u16(&block, word * 2)));
+        if block[0] != 1 || block[30] != 0x55 || block[31] != 0xAA || checksum != 0 {
+            return Err("Catálogo de arranque inválido");
+        }
+
+        let mut entries = vec![boot_entry(block[1], &block[32..64])];
+        let mut offset = 64;
+        while offset + 32 <= ISO9660_BLOCK_SIZE {
+            let header = block[offset];
+            // Cabecera de sección: 0x90 (siguen más) o 0x91 (última)
+            if header != 0x90 && header != 0x91 {
+                break;
+            }
+            let platform_id = block[offset + 1];
+            let count = read_u16(&block, offset + 2) as usize;
+            offset += 32;
+            for _ in 0..count {
+                if offset + 32 > ISO9660_BLOCK_SIZE {
+                    break;
+                }
+                // Las extensiones de selección (0x44) no son entradas de arranque
+                if block[offset] != 0x44 {
+                    entries.push(boot_entry(platform_id, &block[offset..offset + 32]));
+                }
+                offset += 32;
+            }
+            if header == 0x91 {
+                break;
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Información de una entrada para el VFS
+    fn vfs_file_info(&self, entry: &Iso9660DirEntry) -> FileInfo {
+        let node_type = if entry.is_symlink() {
+            NodeType::Symlink
+        } else if entry.is_directory() {
+            NodeType::Directory
+        } else {
+            NodeType::File
+        };
+        let mut file_info = FileInfo::new(0, &entry.name, node_type);
+        file_info.size = entry.size;
+        file_info.block_size = ISO9660_BLOCK_SIZE as u32;
+        file_info.block_count = (entry.size + ISO9660_BLOCK_SIZE as u64 - 1) / ISO9660_BLOCK_SIZE as u64;
+        file_info.permissions = match entry.mode {
+            Some(mode) => FilePermissions::new(((mode >> 6) & 7) as u8, ((mode >> 3) & 7) as u8, (mode & 7) as u8),
+            None if entry.is_directory() => FilePermissions::new(5, 5, 5),
+            None => FilePermissions::read_only(),
+        };
+        file_info.owner_id = entry.uid;
+        file_info.group_id = entry.gid;
+        file_info.created_time = entry.created_time;
+        file_info.modified_time = entry.modified_time;
+        file_info.accessed_time = entry.accessed_time;
+        file_info.link_count = entry.links;
+        file_info.device = self.device_id;
+        file_info.inode = entry.inode;
+        file_info
+    }
+}
+
+impl FileSystemDriver for Iso9660Driver {
+    fn open(&mut self, file_path: &str, create: bool) -> Result<FileInfo, &'static str> {
+        match self.resolve_path(file_path, true) {
+            Ok(entry) => Ok(self.vfs_file_info(&entry)),
+            Err("Ruta no encontrada") if create => Err("Sistema de archivos de solo lectura"),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn read_file(&mut self, file_path: &str, buffer: &mut [u8], offset: u64) -> Result<usize, &'static str> {
+        Iso9660Driver::read_file(self, file_path, offset, buffer)
+    }
+
+    fn write_file(&mut self, _file_path: &str, _buffer: &[u8], _offset: u64) -> Result<usize, &'static str> {
+        Err("Sistema de archivos de solo lectura")
+    }
+
+    fn read_dir(&mut self, dir_path: &str) -> Result<Vec<FileInfo>, &'static str> {
+        let directory = self.resolve_path(dir_path, true)?;
+        Ok(self
+            .read_directory(&directory)?
+            .iter()
+            .map(|entry| self.vfs_file_info(entry))
+            .collect())
+    }
+
+    fn stat(&mut self, file_path: &str) -> Result<FileInfo, &'static str> {
+        let entry = self.resolve_path(file_path, true)?;
+        Ok(self.vfs_file_info(&entry))
+    }
+
+    fn unlink(&mut self, _file_path: &str) -> Result<(), &'static str> {
+        Err("Sistema de archivos de solo lectura")
+    }
+
+    fn truncate(&mut self, _file_path: &str, _size: u64) -> Result<(), &'static str> {
+        Err("Sistema de archivos de solo lectura")
+    }
+
+    fn rename(&mut self, _old_path: &str, _new_path: &str) -> Result<(), &'static str> {
+        Err("Sistema de archivos de solo lectura")
+    }
+}
+
+/// Entrada de arranque a partir de sus 32 bytes en el catálogo
+fn boot_entry(platform_id: u8, entry: &[u8]) -> ElToritoBootEntry {
+    ElToritoBootEntry {
+        platform_id,
+        bootable: entry[0] == 0x88,
+        media_type: ElToritoMediaType::from_byte(entry[1]),
+        load_segment: read_u16(entry, 2),
+        system_type: entry[4],
+        sector_count: read_u16(entry, 6),
+        load_block: read_u32(entry, 8),
+    }
+}
+
+/// Área de uso del sistema de un registro de directorio
+fn system_use_area(record: &[u8], skip: usize) -> &[u8] {
+    let name_length = record[32] as usize;
+    // El nombre va seguido de un byte de relleno si su longitud es par
+    let start = 33 + name_length + (1 - name_length % 2) + skip;
+    if start >= record.len() {
+        &[]
+    } else {
+        &record[start..]
+    }
+}
+
+/// Quitar el número de versión (";1") y el punto final de un nombre ISO 9660
+fn clean_name(name: &str) -> String {
+    let name = match name.rfind(';') {
+        Some(position) => &name[..position],
+        None => name,
+    };
+    String::from(name.strip_suffix('.').unwrap_or(name))
+}
+
+/// Decodificar un nombre Joliet (UCS-2 big-endian)
+fn decode_ucs2(bytes: &[u8]) -> String {
+    let units = bytes.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]]));
+    char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+/// Quitar los espacios de relleno de un identificador
+fn trim_identifier(bytes: &[u8]) -> &str {
+    core::str::from_utf8(bytes).unwrap_or("").trim_end_matches(|c| c == ' ' || c == '\0')
+}
+
+/// Fecha de un registro de directorio (7 bytes) a segundos desde la época Unix
+fn recording_time_to_unix(stamp: &[u8]) -> u64 {
+    civil_to_unix(
+        1900 + stamp[0] as i64,
+        stamp[1] as i64,
+        stamp[2] as i64,
+        stamp[3] as i64 * 3600 + stamp[4] as i64 * 60 + stamp[5] as i64,
+        stamp[6] as i8,
+    )
+}
+
+/// Fecha de un descriptor ("AAAAMMDDHHMMSScc" más el desfase) a segundos desde la época Unix
+fn descriptor_time_to_unix(stamp: &[u8]) -> u64 {
+    let number = |range: core::ops::Range<usize>| {
+        stamp[range].iter().fold(0i64, |value, &digit| value * 10 + digit.wrapping_sub(b'0') as i64)
+    };
+    civil_to_unix(
+        number(0..4),
+        number(4..6),
+        number(6..8),
+        number(8..10) * 3600 + number(10..12) * 60 + number(12..14),
+        stamp[16] as i8,
+    )
+}
+
+/// Convertir fecha civil y desfase (en cuartos de hora) a segundos desde la época Unix
+///
+/// Devuelve 0 si la fecha no está establecida o es anterior a 1970.
+fn civil_to_unix(year: i64, month: i64, day: i64, seconds_of_day: i64, offset_quarters: i8) -> u64 {
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return 0;
+    }
+
+    // Conversión de fecha civil a días (algoritmo de Howard Hinnant)
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    let seconds = days * 86_400 + seconds_of_day - offset_quarters as i64 * 15 * 60;
+    if seconds < 0 {
+        0
+    } else {
+        seconds as u64
+    }
+}
+
+/// Leer un u16 little-endian de un buffer
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+/// Leer un u32 little-endian de un buffer (la mitad LE de los campos de ambos órdenes)
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+/// Función para inicializar el driver ISO 9660
+pub fn init_iso9660(device_id: u32) -> Result<Iso9660Driver, &'static str> {
+    let mut driver = Iso9660Driver::new(device_id);
+    driver.initialize()?;
+    Ok(driver)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::test_util::TestDisk;
+
+    /// Fecha de los registros de directorio: 2026-05-17 12:30:00 UTC
+    const RECORD_TIME: [u8; 7] = [126, 5, 17, 12, 30, 0, 0];
+    const RECORD_UNIX_TIME: u64 = 1_779_021_000;
+
+    /// Contenido de los archivos pequeños
+    const README: &[u8] = b"Hola, mundo!\n";
+
+    // Bloques de la imagen sintética
+    const CATALOG_BLOCK: u32 = 20;
+    const ROOT_BLOCK: u32 = 21;
+    const DOCS_BLOCK: u32 = 22;
+    const JOLIET_ROOT_BLOCK: u32 = 23;
+    const JOLIET_DOCS_BLOCK: u32 = 24;
+    const README_BLOCK: u32 = 25;
+    const BIG_FIRST_BLOCK: u32 = 26;
+    const BIG_SECOND_BLOCK: u32 = 28;
+    const CONTINUATION_BLOCK: u32 = 29;
+    const IMAGE_BLOCKS: usize = 32;
+
+    /// Longitud del segundo extent del archivo de varios extents
+    const BIG_TAIL: u32 = 100;
+
+    fn both16(value: u16) -> [u8; 4] {
+        let (le, be) = (value.to_le_bytes(), value.to_be_bytes());
+        [le[0], le[1], be[0], be[1]]
+    }
+
+    fn both32(value: u32) -> [u8; 8] {
+        let (le, be) = (value.to_le_bytes(), value.to_be_bytes());
+        [le[0], le[1], le[2], le[3], be[0], be[1], be[2], be[3]]
+    }
+
+    /// Registro de directorio con su área de uso del sistema
+    fn record(name: &[u8], block: u32, length: u32, flags: u8, system_use: &[u8]) -> Vec<u8> {
+        let mut record = vec![0u8; 33];
+        record[2..10].copy_from_slice(&both32(block));
+        record[10..18].copy_from_slice(&both32(length));
+        record[18..25].copy_from_slice(&RECORD_TIME);
+        record[25] = flags;
+        record[28..32].copy_from_slice(&both16(1));
+        record[32] = name.len() as u8;
+        record.extend_from_slice(name);
+        if name.len() % 2 == 0 {
+            record.push(0);
+        }
+        record.extend_from_slice(system_use);
+        if record.len() % 2 == 1 {
+            record.push(0);
+        }
+        record[0] = record.len() as u8;
+        record
+    }
+
+    /// Entrada SUSP con su cabecera
+    fn susp(signature: &[u8; 2], payload: &[u8]) -> Vec<u8> {
+        let mut entry = vec![signature[0], signature[1], (4 + payload.len()) as u8, 1];
+        entry.extend_from_slice(payload);
+        entry
+    }
+
+    fn px(mode: u32, links: u32, uid: u32, gid: u32, inode: u32) -> Vec<u8> {
+        let payload: Vec<u8> = [mode, links, uid, gid, inode].iter().flat_map(|&value| both32(value)).collect();
+        susp(b"PX", &payload)
+    }
+
+    fn nm(name: &str) -> Vec<u8> {
+        let mut payload = vec![0];
+        payload.extend_from_slice(name.as_bytes());
+        susp(b"NM", &payload)
+    }
+
+    /// Entrada "SL" con componentes `(flags, contenido)`
+    fn sl(components: &[(u8, &str)]) -> Vec<u8> {
+        let mut payload = vec![0];
+        for &(flags, content) in components {
+            payload.push(flags);
+            payload.push(content.len() as u8);
+            payload.extend_from_slice(content.as_bytes());
+        }
+        susp(b"SL", &payload)
+    }
+
+    fn ucs2(name: &str) -> Vec<u8> {
+        name.encode_utf16().flat_map(u16::to_be_bytes).collect()
+    }
+
+    /// Escribir los registros de un directorio en su bloque
+    fn write_directory(image: &mut [u8], block: u32, records: &[Vec<u8>]) {
+        let mut offset = block as usize * ISO9660_BLOCK_SIZE;
+        for record in records {
+            image[offset..offset + record.len()].copy_from_slice(record);
+            offset += record.len();
+        }
+    }
+
+    /// Descriptor de volumen con la cabecera estándar
+    fn descriptor(image: &mut [u8], block: u32, descriptor_type: u8) -> &mut [u8] {
+        let start = block as usize * ISO9660_BLOCK_SIZE;
+        let descriptor = &mut image[start..start + ISO9660_BLOCK_SIZE];
+        descriptor[0] = descriptor_type;
+        descriptor[1..6].copy_from_slice(ISO9660_STANDARD_ID);
+        descriptor[6] = 1;
+        descriptor
+    }
+
+    /// Imagen ISO 9660 sintética con Rock Ridge y Joliet opcionales
+    ///
+    /// Árbol primario: DOCS/GUIDE.TXT, README.TXT, BIG.BIN (dos extents no
+    /// contiguos), LINK, ABS y LONGNAME.TXT (sus campos Rock Ridge están en un
+    /// área de continuación). El árbol Joliet tiene "Docs/Guía.txt" y
+    /// "Léame.txt". Incluye un catálogo El Torito con una entrada x86 y otra EFI.
+    fn iso_image(rock_ridge: bool, joliet: bool) -> Vec<u8> {
+        let mut image = vec![0u8; IMAGE_BLOCKS * ISO9660_BLOCK_SIZE];
+        let block_size = ISO9660_BLOCK_SIZE as u32;
+        let rr = |entries: &[Vec<u8>]| if rock_ridge { entries.concat() } else { Vec::new() };
+
+        let primary = descriptor(&mut image, 16, ISO9660_DESCRIPTOR_PRIMARY);
+        primary[40..72].fill(b' ');
+        primary[40..48].copy_from_slice(b"REACTOS_");
+        primary[80..88].copy_from_slice(&both32(IMAGE_BLOCKS as u32));
+        primary[128..132].copy_from_slice(&both16(ISO9660_BLOCK_SIZE as u16));
+        primary[156..190].copy_from_slice(&record(&[0], ROOT_BLOCK, block_size, ISO9660_FLAG_DIRECTORY, &[]));
+        let mut next = 17;
+        if joliet {
+            let supplementary = descriptor(&mut image, next, ISO9660_DESCRIPTOR_SUPPLEMENTARY);
+            supplementary[88..91].copy_from_slice(b"%/E");
+            supplementary[156..190].copy_from_slice(&record(&[0], JOLIET_ROOT_BLOCK, block_size, ISO9660_FLAG_DIRECTORY, &[]));
+            next += 1;
+        }
+        let boot = descriptor(&mut image, next, ISO9660_DESCRIPTOR_BOOT_RECORD);
+        boot[7..30].copy_from_slice(ELTORITO_SYSTEM_ID);
+        boot[0x47..0x4B].copy_from_slice(&CATALOG_BLOCK.to_le_bytes());
+        descriptor(&mut image, next + 1, ISO9660_DESCRIPTOR_TERMINATOR);
+
+        // Catálogo de arranque: validación, entrada inicial y una sección EFI
+        let catalog = &mut image[CATALOG_BLOCK as usize * ISO9660_BLOCK_SIZE..][..ISO9660_BLOCK_SIZE];
+        catalog[0] = 1;
+        catalog[4..11].copy_from_slice(b"ReactOS");
+        catalog[30] = 0x55;
+        catalog[31] = 0xAA;
+        let sum = (0..16).fold(0u16, |sum, word| sum.wrapping_add(read_u16(catalog, word * 2)));
+        catalog[28..30].copy_from_slice(&0u16.wrapping_sub(sum).to_le_bytes());
+        catalog[32] = 0x88;
+        catalog[38..40].copy_from_slice(&4u16.to_le_bytes());
+        catalog[40..44].copy_from_slice(&30u32.to_le_bytes());
+        catalog[64] = 0x91;
+        catalog[65] = ELTORITO_PLATFORM_EFI;
+        catalog[66..68].copy_from_slice(&1u16.to_le_bytes());
+        catalog[96] = 0x88;
+        catalog[98..100].copy_from_slice(&0x7C0u16.to_le_bytes());
+        catalog[102..104].copy_from_slice(&8u16.to_le_bytes());
+        catalog[104..108].copy_from_slice(&31u32.to_le_bytes());
+
+        // Datos
+        let readme = README_BLOCK as usize * ISO9660_BLOCK_SIZE;
+        image[readme..readme + README.len()].copy_from_slice(README);
+        let big = BIG_FIRST_BLOCK as usize * ISO9660_BLOCK_SIZE;
+        for (index, byte) in image[big..big + ISO9660_BLOCK_SIZE].iter_mut().enumerate() {
+            *byte = (index % 251) as u8;
+        }
+        let tail = BIG_SECOND_BLOCK as usize * ISO9660_BLOCK_SIZE;
+        image[tail..tail + BIG_TAIL as usize].fill(0xAB);
+
+        // Campos Rock Ridge de LONGNAME.TXT en el área de continuación
+        let continued = [nm("a rather long rock ridge name.txt"), px(0o100600, 1, 0, 0, 90)].concat();
+        let continuation = CONTINUATION_BLOCK as usize * ISO9660_BLOCK_SIZE;
+        image[continuation..continuation + continued.len()].copy_from_slice(&continued);
+        let ce: Vec<u8> = [both32(CONTINUATION_BLOCK), both32(0), both32(continued.len() as u32)].concat();
+
+        let modified = [0x02u8, 120, 1, 2, 3, 4, 5, 0];
+        let root_records = [
+            record(&[0], ROOT_BLOCK, block_size, ISO9660_FLAG_DIRECTORY, &rr(&[
+                susp(b"SP", &[0xBE, 0xEF, 0]),
+                susp(b"ER", &[10, 0, 0, 1, b'R', b'R', b'I', b'P', b'_', b'1', b'9', b'9', b'1', b'A']),
+                px(0o40755, 3, 0, 0, 1),
+            ])),
+            record(&[1], ROOT_BLOCK, block_size, ISO9660_FLAG_DIRECTORY, &[]),
+            record(b"BIG.BIN;1", BIG_FIRST_BLOCK, block_size, ISO9660_FLAG_MULTI_EXTENT, &rr(&[nm("big.bin")])),
+            record(b"BIG.BIN;1", BIG_SECOND_BLOCK, BIG_TAIL, 0, &[]),
+            record(b"DOCS", DOCS_BLOCK, block_size, ISO9660_FLAG_DIRECTORY, &rr(&[nm("Docs"), px(0o40750, 2, 0, 0, 2)])),
+            record(b"LINK.;1", 0, 0, 0, &rr(&[nm("link"), px(0o120777, 1, 0, 0, 3), sl(&[(0, "Docs"), (0, "guide.txt")])])),
+            record(b"ABS.;1", 0, 0, 0, &rr(&[nm("abs"), px(0o120777, 1, 0, 0, 4), sl(&[(0x08, ""), (0, "Docs")])])),
+            record(b"LONGNAME.TXT;1", README_BLOCK, README.len() as u32, 0, &rr(&[susp(b"CE", &ce)])),
+            record(b"README.TXT;1", README_BLOCK, README.len() as u32, 0, &rr(&[
+                nm("ReadMe.txt"),
+                px(0o100640, 1, 1000, 100, 77),
+                susp(b"TF", &modified),
+            ])),
+        ];
+        write_directory(&mut image, ROOT_BLOCK, &root_records);
+        write_directory(&mut image, DOCS_BLOCK, &[
+            record(&[0], DOCS_BLOCK, block_size, ISO9660_FLAG_DIRECTORY, &[]),
+            record(&[1], ROOT_BLOCK, block_size, ISO9660_FLAG_DIRECTORY, &[]),
+            record(b"GUIDE.TXT;1", README_BLOCK, README.len() as u32, 0, &rr(&[nm("guide.txt")])),
+        ]);
+
+        write_directory(&mut image, JOLIET_ROOT_BLOCK, &[
+            record(&[0], JOLIET_ROOT_BLOCK, block_size, ISO9660_FLAG_DIRECTORY, &[]),
+            record(&[1], JOLIET_ROOT_BLOCK, block_size, ISO9660_FLAG_DIRECTORY, &[]),
+            record(&ucs2("Docs"), JOLIET_DOCS_BLOCK, block_size, ISO9660_FLAG_DIRECTORY, &[]),
+            record(&ucs2("Léame.txt;1"), README_BLOCK, README.len() as u32, 0, &[]),
+        ]);
+        write_directory(&mut image, JOLIET_DOCS_BLOCK, &[
+            record(&[0], JOLIET_DOCS_BLOCK, block_size, ISO9660_FLAG_DIRECTORY, &[]),
+            record(&[1], JOLIET_ROOT_BLOCK, block_size, ISO9660_FLAG_DIRECTORY, &[]),
+            record(&ucs2("Guía.txt;1"), README_BLOCK, README.len() as u32, 0, &[]),
+        ]);
+        image
+    }
+
+    fn mount(image: Vec<u8>) -> (TestDisk, Iso9660Driver) {
+        let disk = TestDisk::with_data(image, ISO9660_BLOCK_SIZE as u32);
+        let driver = init_iso9660(disk.device_id).unwrap();
+        (disk, driver)
+    }
+
+    fn names(driver: &mut Iso9660Driver, path: &str) -> Vec<String> {
+        let mut names: Vec<String> = FileSystemDriver::read_dir(driver, path).unwrap().iter().map(|entry| String::from(entry.name())).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_rock_ridge_names_permissions_and_symlinks() {
+        let (_disk, mut driver) = mount(iso_image(true, true));
+        assert!(driver.rock_ridge);
+        assert!(driver.joliet_root.is_some());
+        assert_eq!(driver.volume_id, "REACTOS_");
+        // Rock Ridge tiene preferencia sobre Joliet
+        assert_eq!(names(&mut driver, "/"), ["Docs", "ReadMe.txt", "a rather long rock ridge name.txt", "abs", "big.bin", "link"]);
+
+        let readme = driver.stat("/ReadMe.txt").unwrap();
+        assert_eq!(readme.permissions, FilePermissions::new(6, 4, 0));
+        assert_eq!((readme.owner_id, readme.group_id, readme.inode), (1000, 100, 77));
+        assert_eq!(readme.modified_time, 1_577_934_245);
+        assert_eq!(readme.created_time, RECORD_UNIX_TIME);
+        // Los nombres Rock Ridge distinguen mayúsculas
+        assert_eq!(driver.stat("/readme.txt").unwrap_err(), "Ruta no encontrada");
+
+        let docs = driver.stat("/Docs").unwrap();
+        assert_eq!(docs.node_type, NodeType::Directory);
+        assert_eq!(docs.permissions, FilePermissions::new(7, 5, 0));
+
+        // Los campos del área de continuación ("CE")
+        let long = driver.stat("/a rather long rock ridge name.txt").unwrap();
+        assert_eq!((long.permissions, long.inode), (FilePermissions::new(6, 0, 0), 90));
+
+        // Enlaces simbólicos relativos y absolutos
+        assert_eq!(driver.read_link("/link").unwrap(), "Docs/guide.txt");
+        assert_eq!(driver.read_link("/abs").unwrap(), "/Docs");
+        assert_eq!(driver.resolve_path("/link", false).map(|entry| entry.is_symlink()), Ok(true));
+        let mut buffer = [0u8; 32];
+        assert_eq!(Iso9660Driver::read_file(&driver, "/link", 0, &mut buffer), Ok(README.len()));
+        assert_eq!(&buffer[..README.len()], README);
+        assert_eq!(names(&mut driver, "/abs"), ["guide.txt"]);
+
+        assert_eq!(driver.open("/new.txt", true).unwrap_err(), "Sistema de archivos de solo lectura");
+        assert_eq!(FileSystemDriver::write_file(&mut driver, "/ReadMe.txt", b"x", 0), Err("Sistema de archivos de solo lectura"));
+    }
+
+    #[test]
+    fn test_joliet_names_without_rock_ridge() {
+        let (_disk, mut driver) = mount(iso_image(false, true));
+        assert!(!driver.rock_ridge);
+        assert_eq!(names(&mut driver, "/"), ["Docs", "Léame.txt"]);
+        assert_eq!(names(&mut driver, "/docs"), ["Guía.txt"]);
+
+        // Sin Rock Ridge los nombres no distinguen mayúsculas y los permisos son de solo lectura
+        let guide = driver.stat("/DOCS/GUÍA.TXT").unwrap();
+        assert_eq!(guide.size, README.len() as u64);
+        assert_eq!(guide.permissions, FilePermissions::read_only());
+        assert_eq!(guide.modified_time, RECORD_UNIX_TIME);
+        assert_eq!(driver.stat("/Docs").unwrap().permissions, FilePermissions::new(5, 5, 5));
+    }
+
+    #[test]
+    fn test_plain_iso_names() {
+        let (_disk, mut driver) = mount(iso_image(false, false));
+        assert!(driver.joliet_root.is_none());
+        assert_eq!(names(&mut driver, "/"), ["ABS", "BIG.BIN", "DOCS", "LINK", "LONGNAME.TXT", "README.TXT"]);
+        assert_eq!(driver.stat("/docs/guide.txt").unwrap().size, README.len() as u64);
+        // Sin Rock Ridge no hay enlaces simbólicos
+        assert_eq!(driver.read_link("/LINK").unwrap_err(), "No es un enlace simbólico");
+    }
+
+    #[test]
+    fn test_multi_extent_file_reads_across_extents() {
+        let (_disk, mut driver) = mount(iso_image(true, false));
+        let entry = driver.resolve_path("/big.bin", true).unwrap();
+        assert_eq!(entry.extents, [
+            Iso9660Extent { block: BIG_FIRST_BLOCK, length: ISO9660_BLOCK_SIZE as u32 },
+            Iso9660Extent { block: BIG_SECOND_BLOCK, length: BIG_TAIL },
+        ]);
+        assert_eq!(driver.stat("/big.bin").unwrap().size, ISO9660_BLOCK_SIZE as u64 + BIG_TAIL as u64);
+
+        let mut buffer = [0u8; 20];
+        let offset = ISO9660_BLOCK_SIZE as u64 - 8;
+        assert_eq!(Iso9660Driver::read_file(&driver, "/big.bin", offset, &mut buffer), Ok(20));
+        let expected: Vec<u8> = (ISO9660_BLOCK_SIZE - 8..ISO9660_BLOCK_SIZE).map(|index| (index % 251) as u8).collect();
+        assert_eq!(&buffer[..8], &expected[..]);
+        assert_eq!(buffer[8..], [0xAB; 12]);
+        // La lectura se corta al final del último extent
+        let end = ISO9660_BLOCK_SIZE as u64 + BIG_TAIL as u64;
+        assert_eq!(Iso9660Driver::read_file(&driver, "/big.bin", end - 4, &mut buffer), Ok(4));
+        assert_eq!(Iso9660Driver::read_file(&driver, "/big.bin", end, &mut buffer), Ok(0));
+    }
+
+    #[test]
+    fn test_el_torito_boot_catalog() {
+        let mut image = iso_image(false, true);
+        let (disk, driver) = mount(image.clone());
+        assert_eq!(driver.boot_catalog, Some(CATALOG_BLOCK));
+        let entries = driver.read_boot_catalog().unwrap();
+        assert_eq!(entries, [
+            ElToritoBootEntry {
+                platform_id: ELTORITO_PLATFORM_X86,
+                bootable: true,
+                media_type: ElToritoMediaType::NoEmulation,
+                load_segment: 0,
+                system_type: 0,
+                sector_count: 4,
+                load_block: 30,
+            },
+            ElToritoBootEntry {
+                platform_id: ELTORITO_PLATFORM_EFI,
+                bootable: true,
+                media_type: ElToritoMediaType::NoEmulation,
+                load_segment: 0x7C0,
+                system_type: 0,
+                sector_count: 8,
+                load_block: 31,
+            },
+        ]);
+        drop(disk);
+
+        // Una suma de control incorrecta invalida el catálogo
+        image[CATALOG_BLOCK as usize * ISO9660_BLOCK_SIZE + 4] ^= 0xFF;
+        let (_disk, driver) = mount(image);
+        assert_eq!(driver.read_boot_catalog(), Err("Catálogo de arranque inválido"));
+    }
+}
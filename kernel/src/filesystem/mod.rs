@@ -1,17 +1,206 @@
 //! Sistema de Archivos para ReactOS Rust Kernel
 //! 
 //! Este módulo contiene el sistema de archivos virtual (VFS) y los drivers
-//! para diferentes sistemas de archivos como FAT32 y NTFS.
+//! para diferentes sistemas de archivos como FAT12/16/32, exFAT, NTFS,
+//! ext2/ext3/ext4, ISO 9660 y el sistema de archivos en memoria (ramfs),
+//! además de los sistemas sintéticos de procesos (procfs) y dispositivos
+//! (devfs), las cuotas de disco por usuario y grupo y utilidades de rutas.
 
 pub mod vfs;
 pub mod fat32;
+pub mod exfat;
 pub mod ntfs;
+pub mod ext4;
+pub mod ramfs;
+pub mod iso9660;
+pub mod procfs;
+pub mod devfs;
+pub mod quota;
+pub mod path;
+
+use alloc::string::String;
 
 /// Inicializar el sistema de archivos
 pub fn init() {
     // Inicializar VFS
-    vfs::init_vfs();
+    let mut vfs = vfs::init_vfs();
+    
+    // Los periodos de gracia de las cuotas empiezan con la hora real
+    vfs.quotas.set_time(crate::hal::rtc::unix_time());
+    
+    // /tmp en memoria, disponible antes que cualquier driver de disco
+    let _ = vfs.mount("/tmp", vfs::FileSystemType::RamFs, 0);
     
-    // Inicializar drivers de sistemas de archivos
-    // TODO: Implementar inicialización de drivers
+    // Estado del kernel y dispositivos como archivos
+    let _ = vfs.mount("/proc", vfs::FileSystemType::ProcFs, 0);
+    let _ = vfs.mount("/dev", vfs::FileSystemType::DevFs, 0);
+    
+    // Cada partición con un sistema de archivos reconocido en /mnt/<nombre>
+    mount_partitions(&mut vfs);
+    
+    // Los drivers de FAT, exFAT, NTFS, ext e ISO 9660 se crean al montar cada dispositivo
+    // (`VirtualFileSystem::mount`)
+    vfs::set_vfs(vfs);
+}
+
+/// Montar en `/mnt/<nombre en /dev>` las particiones con un sistema de archivos soportado
+///
+/// Devuelve el número de particiones montadas; las que no se reconocen se
+/// omiten sin crear su directorio.
+pub fn mount_partitions(vfs: &mut vfs::VirtualFileSystem) -> usize {
+    let mut mounted = 0;
+    for (device_id, name) in devfs::block_devices() {
+        let is_partition = crate::drivers::storage::get_storage_manager()
+            .and_then(|manager| manager.get_device_info(device_id))
+            .map_or(false, |device| device.parent_device.is_some());
+        if !is_partition {
+            continue;
+        }
+        match vfs::probe_filesystem(device_id) {
+            Ok(fs_type) if vfs::SUPPORTED_FILESYSTEMS.contains(&fs_type) => {}
+            _ => continue,
+        }
+
+        let mut mount_path = String::from("/mnt/");
+        mount_path.push_str(&name);
+        if vfs.create_node(&mount_path, vfs::NodeType::Directory).is_err() {
+            continue;
+        }
+        if vfs.mount_device(&mount_path, device_id).is_ok() {
+            mounted += 1;
+        } else {
+            let _ = vfs.remove_node(&mount_path);
+        }
+    }
+    mounted
+}
+
+/// Utilidades de las pruebas de los drivers
+///
+/// Las pruebas se compilan para el host con la herramienta FAT32
+/// (`tools/fat32`), que incluye estos módulos sobre el crate de drivers.
+#[cfg(test)]
+pub(crate) mod test_util {
+    use std::sync::{Mutex, MutexGuard};
+
+    use super::vfs::{init_vfs, VirtualFileSystem};
+    use crate::drivers::{image, storage};
+    use crate::process::scheduler::{init_process_scheduler, set_process_scheduler, SchedulingAlgorithm};
+    use crate::security::{access_control::init_access_control, audit::init_kernel_audit};
+
+    /// Las pruebas comparten el gestor de almacenamiento global
+    static STORAGE_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Las pruebas comparten el VFS, el planificador y los gestores de seguridad globales
+    static KERNEL_STATE_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Reservar el estado global del kernel para la prueba actual
+    ///
+    /// `with_vfs` ya lo reserva; si la prueba usa también `TestDisk` o
+    /// `lock_storage`, el almacenamiento se reserva después.
+    pub fn lock_kernel_state() -> MutexGuard<'static, ()> {
+        KERNEL_STATE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Reservar el gestor de almacenamiento para la prueba actual
+    ///
+    /// Para las pruebas que registran dispositivos sin pasar por `TestDisk`.
+    pub fn lock_storage() -> MutexGuard<'static, ()> {
+        STORAGE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Disco en memoria registrado en el gestor de almacenamiento
+    ///
+    /// Mantiene el gestor bloqueado para la prueba actual mientras existe; al
+    /// soltarlo se desregistra junto con sus particiones y se libera el buffer.
+    pub struct TestDisk {
+        /// ID del disco en el gestor
+        pub device_id: u32,
+        disk: *mut image::MemoryDisk,
+        data: *mut [u8],
+        _lock: MutexGuard<'static, ()>,
+    }
+
+    impl TestDisk {
+        /// Disco vacío de `size` bytes con sectores de 512 bytes
+        pub fn new(size: usize) -> Self {
+            Self::with_data(vec![0; size], 512)
+        }
+
+        /// Disco con el contenido dado y el tamaño de sector indicado
+        pub fn with_data(data: Vec<u8>, sector_size: u32) -> Self {
+            let lock = lock_storage();
+            let data = Box::into_raw(data.into_boxed_slice());
+            let disk = Box::into_raw(Box::new(image::MemoryDisk::new(unsafe { &mut *data })));
+            let device_id = image::register_image("test", sector_size, unsafe { &mut *disk });
+            assert_ne!(device_id, 0, "no hay slots libres en el gestor de almacenamiento");
+            Self { device_id, disk, data, _lock: lock }
+        }
+
+        /// IDs de las particiones registradas del disco, en orden
+        pub fn partitions(&self) -> Vec<u32> {
+            let manager = storage::get_storage_manager().unwrap();
+            let mut partitions: Vec<(u32, u32)> = manager
+                .devices
+                .iter()
+                .flatten()
+                .filter(|device| device.parent_device == Some(self.device_id))
+                .map(|device| (device.partition.as_ref().map_or(0, |partition| partition.number), device.device_id))
+                .collect();
+            partitions.sort();
+            partitions.into_iter().map(|(_, device_id)| device_id).collect()
+        }
+
+        /// Tamaño de sector del disco
+        pub fn sector_size(&self) -> usize {
+            let manager = storage::get_storage_manager().unwrap();
+            manager.get_device_info(self.device_id).unwrap().sector_size as usize
+        }
+
+        /// Leer sectores completos del disco a través del gestor
+        pub fn read(&self, sector: u64, buffer: &mut [u8]) {
+            let count = (buffer.len() / self.sector_size()) as u32;
+            assert!(storage::read_sectors(self.device_id, sector, count, buffer));
+        }
+
+        /// Escribir sectores completos del disco a través del gestor
+        pub fn write(&self, sector: u64, buffer: &[u8]) {
+            let count = (buffer.len() / self.sector_size()) as u32;
+            assert!(storage::write_sectors(self.device_id, sector, count, buffer));
+        }
+    }
+
+    impl Drop for TestDisk {
+        fn drop(&mut self) {
+            if let Some(manager) = storage::get_storage_manager() {
+                manager.unregister_device(self.device_id);
+            }
+            unsafe {
+                drop(Box::from_raw(self.disk));
+                drop(Box::from_raw(self.data));
+            }
+        }
+    }
+
+    /// Ejecutar una prueba sobre un VFS nuevo (`init_vfs`)
+    ///
+    /// Las tablas fijas del VFS no caben en la pila de un hilo de pruebas, así
+    /// que la prueba corre en un hilo propio con una pila mayor. El control de
+    /// acceso y la auditoría se inicializan de nuevo y el planificador queda sin
+    /// procesos, así que la prueba actúa con el token del sistema.
+    pub fn with_vfs(test: impl FnOnce(&mut VirtualFileSystem) + Send + 'static) {
+        let _lock = lock_kernel_state();
+        let handle = std::thread::Builder::new()
+            .stack_size(64 << 20)
+            .spawn(move || {
+                init_access_control().unwrap();
+                init_kernel_audit().unwrap();
+                set_process_scheduler(init_process_scheduler(SchedulingAlgorithm::RoundRobin));
+                test(&mut init_vfs())
+            })
+            .unwrap();
+        if let Err(panic) = handle.join() {
+            std::panic::resume_unwind(panic);
+        }
+    }
 }
@@ -0,0 +1,61 @@
+//! Utilidades de rutas compartidas por el VFS y los drivers
+//!
+//! Las rutas que llegan a los drivers son relativas a su punto de montaje y
+//! usan "/" como separador; exFAT acepta además "\" por compatibilidad con
+//! las rutas de Windows.
+
+/// Último componente de una ruta ("/" para la raíz)
+pub fn path_name(path: &str) -> &str {
+    path.trim_end_matches('/').rsplit('/').next().filter(|name| !name.is_empty()).unwrap_or("/")
+}
+
+/// Separar una ruta en directorio padre y último nombre
+///
+/// Los separadores finales se ignoran. El padre de un hijo de la raíz o de
+/// un nombre suelto es "/". Falla si el nombre está vacío o es "." o "..".
+pub fn split_parent(path: &str) -> Result<(&str, &str), &'static str> {
+    split_parent_at(path, &['/'])
+}
+
+/// Separar una ruta en directorio padre y último nombre con varios separadores
+pub fn split_parent_at<'a>(path: &'a str, separators: &[char]) -> Result<(&'a str, &'a str), &'static str> {
+    let path = path.trim_end_matches(separators);
+    let (parent, name) = match path.rfind(separators) {
+        Some(0) => ("/", &path[1..]),
+        Some(separator) => (&path[..separator], &path[separator + 1..]),
+        None => ("/", path),
+    };
+    if name.is_empty() || name == "." || name == ".." {
+        return Err("Nombre de archivo inválido");
+    }
+    Ok((parent, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_name() {
+        assert_eq!(path_name("/1/status/"), "status");
+        assert_eq!(path_name("status"), "status");
+        assert_eq!(path_name("/"), "/");
+        assert_eq!(path_name(""), "/");
+    }
+
+    #[test]
+    fn test_split_parent() {
+        assert_eq!(split_parent("/docs/a.txt"), Ok(("/docs", "a.txt")));
+        assert_eq!(split_parent("/docs/sub/"), Ok(("/docs", "sub")));
+        assert_eq!(split_parent("/a.txt"), Ok(("/", "a.txt")));
+        assert_eq!(split_parent("a.txt"), Ok(("/", "a.txt")));
+        for path in ["/", "", "/docs/.", "/docs/.."] {
+            assert_eq!(split_parent(path), Err("Nombre de archivo inválido"), "{}", path);
+        }
+
+        // La barra invertida solo separa cuando se pide
+        assert_eq!(split_parent("/docs\\a.txt"), Ok(("/", "docs\\a.txt")));
+        assert_eq!(split_parent_at("\\docs\\a.txt", &['/', '\\']), Ok(("\\docs", "a.txt")));
+        assert_eq!(split_parent_at("/docs\\a.txt\\", &['/', '\\']), Ok(("/docs", "a.txt")));
+    }
+}
@@ -0,0 +1,543 @@
+//! Sistema de archivos de procesos (procfs) para ReactOS Rust Kernel
+//!
+//! Expone el estado del kernel como archivos de texto de solo lectura bajo
+//! `/proc`: un directorio por PID con la tabla de procesos del planificador,
+//! `meminfo`, `mounts`, `pci` y `net/tcp`. El contenido se genera en cada
+//! lectura, así que siempre refleja el estado actual.
+
+use alloc::{string::String, vec::Vec};
+
+use super::path::path_name;
+use super::vfs::{FileInfo, FilePermissions, FileSystemDriver, FileSystemType, NodeType};
+use crate::process::scheduler::{Process, ProcessState};
+
+// Inodos de los nodos fijos; los de cada proceso usan PROCFS_PID_INODE_BASE + PID * 2
+const PROCFS_ROOT_INODE: u64 = 1;
+const PROCFS_NET_INODE: u64 = 2;
+const PROCFS_MEMINFO_INODE: u64 = 3;
+const PROCFS_MOUNTS_INODE: u64 = 4;
+const PROCFS_PCI_INODE: u64 = 5;
+const PROCFS_NET_TCP_INODE: u64 = 6;
+const PROCFS_PID_INODE_BASE: u64 = 0x1000;
+
+/// Archivo generado de /proc
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcFile {
+    /// Uso de memoria y de la caché de bloques
+    MemInfo,
+    /// Sistemas de archivos montados
+    Mounts,
+    /// Dispositivos PCI
+    Pci,
+    /// Conexiones TCP
+    NetTcp,
+    /// Estado de un proceso
+    ProcessStatus(u32),
+}
+
+/// Nodo de /proc identificado a partir de una ruta
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcNode {
+    /// Directorio raíz
+    Root,
+    /// Directorio `net`
+    NetDirectory,
+    /// Directorio de un proceso
+    ProcessDirectory(u32),
+    /// Archivo generado
+    File(ProcFile),
+}
+
+/// Driver del sistema de archivos de procesos
+pub struct ProcFsDriver {
+    /// Dispositivo de bloque del montaje (no se usa)
+    pub device_id: u32,
+}
+
+impl ProcFsDriver {
+    /// Crear el driver
+    pub fn new(device_id: u32) -> Self {
+        Self { device_id }
+    }
+
+    /// Identificar el nodo de una ruta
+    pub fn lookup(&self, path: &str) -> Result<ProcNode, &'static str> {
+        let components: Vec<&str> = path.split('/').filter(|component| !component.is_empty() && *component != ".").collect();
+        let node = match components.as_slice() {
+            [] => ProcNode::Root,
+            ["meminfo"] => ProcNode::File(ProcFile::MemInfo),
+            ["mounts"] => ProcNode::File(ProcFile::Mounts),
+            ["pci"] => ProcNode::File(ProcFile::Pci),
+            ["net"] => ProcNode::NetDirectory,
+            ["net", "tcp"] => ProcNode::File(ProcFile::NetTcp),
+            [pid] => ProcNode::ProcessDirectory(existing_pid(pid)?),
+            [pid, "status"] => ProcNode::File(ProcFile::ProcessStatus(existing_pid(pid)?)),
+            _ => return Err("Ruta no encontrada"),
+        };
+        Ok(node)
+    }
+
+    /// Entradas de un directorio de /proc
+    pub fn entries(&self, directory: ProcNode) -> Result<Vec<(String, ProcNode)>, &'static str> {
+        let mut entries = Vec::new();
+        match directory {
+            ProcNode::Root => {
+                for process in processes() {
+                    entries.push((decimal(process.pid as u64), ProcNode::ProcessDirectory(process.pid)));
+                }
+                entries.push((String::from("meminfo"), ProcNode::File(ProcFile::MemInfo)));
+                entries.push((String::from("mounts"), ProcNode::File(ProcFile::Mounts)));
+                entries.push((String::from("pci"), ProcNode::File(ProcFile::Pci)));
+                entries.push((String::from("net"), ProcNode::NetDirectory));
+            }
+            ProcNode::NetDirectory => entries.push((String::from("tcp"), ProcNode::File(ProcFile::NetTcp))),
+            ProcNode::ProcessDirectory(pid) => entries.push((String::from("status"), ProcNode::File(ProcFile::ProcessStatus(pid)))),
+            ProcNode::File(_) => return Err("La ruta no es un directorio"),
+        }
+        Ok(entries)
+    }
+
+    /// Generar el contenido de un archivo
+    pub fn generate(&self, file: ProcFile) -> Result<String, &'static str> {
+        match file {
+            ProcFile::MemInfo => Ok(generate_meminfo()),
+            ProcFile::Mounts => Ok(generate_mounts()),
+            ProcFile::Pci => Ok(generate_pci()),
+            ProcFile::NetTcp => Ok(generate_net_tcp()),
+            ProcFile::ProcessStatus(pid) => {
+                let process = processes().into_iter().find(|process| process.pid == pid).ok_or("Ruta no encontrada")?;
+                Ok(generate_process_status(process))
+            }
+        }
+    }
+
+    /// Información de un nodo para el VFS
+    ///
+    /// El tamaño de los archivos es el de su contenido actual.
+    fn vfs_file_info(&self, name: &str, node: ProcNode) -> FileInfo {
+        let (node_type, inode) = match node {
+            ProcNode::Root => (NodeType::Directory, PROCFS_ROOT_INODE),
+            ProcNode::NetDirectory => (NodeType::Directory, PROCFS_NET_INODE),
+            ProcNode::ProcessDirectory(pid) => (NodeType::Directory, PROCFS_PID_INODE_BASE + pid as u64 * 2),
+            ProcNode::File(ProcFile::MemInfo) => (NodeType::File, PROCFS_MEMINFO_INODE),
+            ProcNode::File(ProcFile::Mounts) => (NodeType::File, PROCFS_MOUNTS_INODE),
+            ProcNode::File(ProcFile::Pci) => (NodeType::File, PROCFS_PCI_INODE),
+            ProcNode::File(ProcFile::NetTcp) => (NodeType::File, PROCFS_NET_TCP_INODE),
+            ProcNode::File(ProcFile::ProcessStatus(pid)) => (NodeType::File, PROCFS_PID_INODE_BASE + pid as u64 * 2 + 1),
+        };
+        let mut file_info = FileInfo::new(0, name, node_type);
+        file_info.inode = inode;
+        file_info.link_count = 1;
+        match node {
+            ProcNode::File(file) => {
+                file_info.permissions = FilePermissions::read_only();
+                file_info.size = self.generate(file).map_or(0, |content| content.len() as u64);
+            }
+            _ => file_info.permissions = FilePermissions::new(5, 5, 5),
+        }
+        if let ProcNode::ProcessDirectory(pid) | ProcNode::File(ProcFile::ProcessStatus(pid)) = node {
+            if let Some(process) = processes().into_iter().find(|process| process.pid == pid) {
+                file_info.created_time = process.creation_time;
+                file_info.modified_time = process.last_run_time;
+            }
+        }
+        file_info
+    }
+}
+
+impl FileSystemDriver for ProcFsDriver {
+    fn open(&mut self, file_path: &str, create: bool) -> Result<FileInfo, &'static str> {
+        match self.lookup(file_path) {
+            Ok(node) => Ok(self.vfs_file_info(path_name(file_path), node)),
+            Err(_) if create => Err("Sistema de archivos de solo lectura"),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn read_file(&mut self, file_path: &str, buffer: &mut [u8], offset: u64) -> Result<usize, &'static str> {
+        let file = match self.lookup(file_path)? {
+            ProcNode::File(file) => file,
+            _ => return Err("No se puede leer un directorio como archivo"),
+        };
+        let content = self.generate(file)?;
+        if offset >= content.len() as u64 {
+            return Ok(0);
+        }
+        let length = core::cmp::min(buffer.len(), content.len() - offset as usize);
+        buffer[..length].copy_from_slice(&content.as_bytes()[offset as usize..offset as usize + length]);
+        Ok(length)
+    }
+
+    fn write_file(&mut self, _file_path: &str, _buffer: &[u8], _offset: u64) -> Result<usize, &'static str> {
+        Err("Sistema de archivos de solo lectura")
+    }
+
+    fn read_dir(&mut self, dir_path: &str) -> Result<Vec<FileInfo>, &'static str> {
+        let directory = self.lookup(dir_path)?;
+        Ok(self.entries(directory)?.iter().map(|(name, node)| self.vfs_file_info(name, *node)).collect())
+    }
+
+    fn stat(&mut self, file_path: &str) -> Result<FileInfo, &'static str> {
+        let node = self.lookup(file_path)?;
+        Ok(self.vfs_file_info(path_name(file_path), node))
+    }
+
+    fn unlink(&mut self, _file_path: &str) -> Result<(), &'static str> {
+        Err("Sistema de archivos de solo lectura")
+    }
+
+    fn truncate(&mut self, _file_path: &str, _size: u64) -> Result<(), &'static str> {
+        Err("Sistema de archivos de solo lectura")
+    }
+
+    fn rename(&mut self, _old_path: &str, _new_path: &str) -> Result<(), &'static str> {
+        Err("Sistema de archivos de solo lectura")
+    }
+}
+
+/// Tabla de procesos del planificador, ordenada por PID
+fn processes() -> Vec<&'static Process> {
+    crate::process::scheduler::get_process_scheduler().map_or(Vec::new(), |scheduler| scheduler.processes())
+}
+
+/// PID de un componente de ruta, si el proceso existe
+fn existing_pid(component: &str) -> Result<u32, &'static str> {
+    let pid: u32 = component.parse().map_err(|_| "Ruta no encontrada")?;
+    if processes().iter().any(|process| process.pid == pid) {
+        Ok(pid)
+    } else {
+        Err("Ruta no encontrada")
+    }
+}
+
+/// /proc/<pid>/status
+fn generate_process_status(process: &Process) -> String {
+    let state = match process.state {
+        ProcessState::New => "N (new)",
+        ProcessState::Ready => "R (ready)",
+        ProcessState::Running => "R (running)",
+        ProcessState::Blocked => "S (blocked)",
+        ProcessState::Terminated => "Z (terminated)",
+        ProcessState::Suspended => "T (suspended)",
+    };
+    let mut content = String::new();
+    push_field(&mut content, "Name", process.name());
+    push_field(&mut content, "State", state);
+    push_field(&mut content, "Pid", &decimal(process.pid as u64));
+    push_field(&mut content, "Priority", &decimal(process.priority as u64));
+    push_field(&mut content, "CpuTime", &decimal(process.cpu_time));
+    push_field(&mut content, "CreationTime", &decimal(process.creation_time));
+    push_field(&mut content, "LastRunTime", &decimal(process.last_run_time));
+    push_field(&mut content, "WaitTime", &decimal(process.wait_time));
+    content
+}
+
+/// /proc/meminfo
+fn generate_meminfo() -> String {
+    let (total, used, free) = crate::memory::get_memory_statistics();
+    let cache = crate::drivers::storage::get_block_cache_stats();
+    let mut content = String::new();
+    push_kilobytes(&mut content, "MemTotal", total);
+    push_kilobytes(&mut content, "MemFree", free);
+    push_kilobytes(&mut content, "MemUsed", used);
+    push_kilobytes(&mut content, "Cached", cache.cached_blocks as u64 * 512);
+    push_kilobytes(&mut content, "Dirty", cache.dirty_blocks as u64 * 512);
+    content
+}
+
+/// /proc/mounts: origen, punto de montaje, tipo y opciones por línea
+fn generate_mounts() -> String {
+    let mut content = String::new();
+    let vfs = match super::vfs::get_vfs() {
+        Some(vfs) => vfs,
+        None => return content,
+    };
+    for mount in vfs.mount_points.iter().flatten() {
+        let fs_name = mount.fs_type.name();
+        let source = match mount.fs_type {
+            FileSystemType::RamFs | FileSystemType::ProcFs | FileSystemType::DevFs => None,
+            _ => super::devfs::block_device_name(mount.block_device),
+        };
+        match source {
+            Some(device) => {
+                content.push_str("/dev/");
+                content.push_str(&device);
+            }
+            None => content.push_str(fs_name),
+        }
+        content.push(' ');
+        content.push_str(mount.path());
+        content.push(' ');
+        content.push_str(fs_name);
+        // Los drivers de estos tipos no admiten escritura
+        let read_only = matches!(
+            mount.fs_type,
+            FileSystemType::EXT4 | FileSystemType::ISO9660 | FileSystemType::ProcFs
+        );
+        content.push_str(if read_only { " ro 0 0\n" } else { " rw 0 0\n" });
+    }
+    content
+}
+
+/// /proc/pci: bus:dispositivo.función, clase, fabricante:dispositivo e IRQ
+fn generate_pci() -> String {
+    let mut content = String::new();
+    let manager = match crate::pci::get_pci_manager() {
+        Some(manager) => manager,
+        None => return content,
+    };
+    for device in manager.devices.iter().flatten() {
+        let descriptor = &device.descriptor;
+        push_hex(&mut content, descriptor.bus as u64, 2);
+        content.push(':');
+        push_hex(&mut content, descriptor.device as u64, 2);
+        content.push('.');
+        push_hex(&mut content, descriptor.function as u64, 1);
+        content.push(' ');
+        push_hex(&mut content, descriptor.class_code as u64, 2);
+        push_hex(&mut content, descriptor.subclass as u64, 2);
+        content.push_str(": ");
+        push_hex(&mut content, descriptor.vendor_id as u64, 4);
+        content.push(':');
+        push_hex(&mut content, descriptor.device_id as u64, 4);
+        content.push_str(" rev ");
+        push_hex(&mut content, descriptor.revision_id as u64, 2);
+        content.push_str(" irq ");
+        content.push_str(&decimal(descriptor.interrupt_line as u64));
+        content.push_str(if device.is_enabled { " enabled\n" } else { " disabled\n" });
+    }
+    content
+}
+
+/// /proc/net/tcp con el formato de Linux (direcciones y puertos en hexadecimal)
+fn generate_net_tcp() -> String {
+    use crate::network::tcp::TcpState;
+
+    let mut content = String::from("  sl  local_address rem_address   st tx_queue rx_queue\n");
+    let manager = match crate::network::network_manager::get_network_manager() {
+        Some(manager) => manager,
+        None => return content,
+    };
+    // Linux muestra la dirección como el u32 en el orden de bytes de la máquina
+    let local_ip = u32::from_le_bytes(manager.ip.local_ip.bytes) as u64;
+
+    for (slot, connection) in manager.tcp.connections.iter().enumerate() {
+        let connection = match connection {
+            Some(connection) if connection.state != TcpState::Closed => connection,
+            _ => continue,
+        };
+        let state = match connection.state {
+            TcpState::Established => 0x01,
+            TcpState::SynSent => 0x02,
+            TcpState::SynReceived => 0x03,
+            TcpState::FinWait1 => 0x04,
+            TcpState::FinWait2 => 0x05,
+            TcpState::TimeWait => 0x06,
+            TcpState::Closed => 0x07,
+            TcpState::CloseWait => 0x08,
+            TcpState::LastAck => 0x09,
+            TcpState::Listen => 0x0A,
+            TcpState::Closing => 0x0B,
+        };
+        let sl = decimal(slot as u64);
+        for _ in sl.len()..4 {
+            content.push(' ');
+        }
+        content.push_str(&sl);
+        content.push_str(": ");
+        push_hex(&mut content, local_ip, 8);
+        content.push(':');
+        push_hex(&mut content, connection.local_port as u64, 4);
+        content.push_str(" 00000000:");
+        push_hex(&mut content, connection.remote_port as u64, 4);
+        content.push(' ');
+        push_hex(&mut content, state, 2);
+        content.push(' ');
+        push_hex(&mut content, connection.send_buffer_len as u64, 8);
+        content.push(':');
+        push_hex(&mut content, connection.recv_buffer_len as u64, 8);
+        content.push('\n');
+    }
+    content
+}
+
+/// Añadir una línea "Campo:\tvalor"
+fn push_field(content: &mut String, field: &str, value: &str) {
+    content.push_str(field);
+    content.push_str(":\t");
+    content.push_str(value);
+    content.push('\n');
+}
+
+/// Añadir una línea de meminfo con el valor en KiB alineado a la derecha
+fn push_kilobytes(content: &mut String, field: &str, bytes: u64) {
+    let value = decimal(bytes / 1024);
+    content.push_str(field);
+    content.push(':');
+    for _ in field.len() + 1 + value.len()..24 {
+        content.push(' ');
+    }
+    content.push_str(&value);
+    content.push_str(" kB\n");
+}
+
+/// Número en decimal
+pub(super) fn decimal(mut value: u64) -> String {
+    let mut digits = Vec::new();
+    loop {
+        digits.push(b'0' + (value % 10) as u8);
+        value /= 10;
+        if value == 0 {
+            break;
+        }
+    }
+    digits.iter().rev().map(|&digit| digit as char).collect()
+}
+
+/// Añadir un número en hexadecimal en mayúsculas con `width` dígitos como mínimo
+fn push_hex(content: &mut String, value: u64, width: usize) {
+    const HEX_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+    let mut digits = Vec::new();
+    let mut rest = value;
+    while rest != 0 || digits.len() < width {
+        digits.push(HEX_DIGITS[(rest & 0xF) as usize]);
+        rest >>= 4;
+    }
+    content.extend(digits.iter().rev().map(|&digit| digit as char));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::test_util::{lock_kernel_state, with_vfs, TestDisk};
+    use crate::process::scheduler::{init_process_scheduler, set_process_scheduler, ProcessPriority, SchedulingAlgorithm};
+
+    fn read_all(driver: &mut ProcFsDriver, path: &str) -> String {
+        let mut buffer = vec![0u8; 4096];
+        let length = FileSystemDriver::read_file(driver, path, &mut buffer, 0).unwrap();
+        String::from_utf8(buffer[..length].to_vec()).unwrap()
+    }
+
+    fn names(entries: &[FileInfo]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.name()).collect()
+    }
+
+    #[test]
+    fn test_fixed_nodes() {
+        let mut driver = ProcFsDriver::new(0);
+        assert_eq!(driver.lookup("/"), Ok(ProcNode::Root));
+        assert_eq!(driver.lookup("/./meminfo"), Ok(ProcNode::File(ProcFile::MemInfo)));
+        assert_eq!(driver.lookup("/net"), Ok(ProcNode::NetDirectory));
+        assert_eq!(driver.lookup("/net/tcp"), Ok(ProcNode::File(ProcFile::NetTcp)));
+        assert_eq!(driver.lookup("/net/udp"), Err("Ruta no encontrada"));
+        assert_eq!(driver.lookup("/meminfo/x"), Err("Ruta no encontrada"));
+        assert_eq!(driver.lookup("/abc"), Err("Ruta no encontrada"));
+
+        let net = FileSystemDriver::read_dir(&mut driver, "/net").unwrap();
+        assert_eq!(names(&net), ["tcp"]);
+        assert!(FileSystemDriver::read_dir(&mut driver, "/meminfo").is_err());
+
+        // meminfo: una línea por campo con el valor alineado en la columna 24
+        let meminfo = read_all(&mut driver, "/meminfo");
+        let fields: Vec<&str> = meminfo.lines().map(|line| line.split(':').next().unwrap()).collect();
+        assert_eq!(fields, ["MemTotal", "MemFree", "MemUsed", "Cached", "Dirty"]);
+        assert!(meminfo.lines().all(|line| line.len() == 27 && line.ends_with(" kB")));
+
+        // Sin gestor de red solo queda la cabecera
+        let header = "  sl  local_address rem_address   st tx_queue rx_queue\n";
+        assert_eq!(read_all(&mut driver, "/net/tcp"), header);
+
+        // El tamaño es el del contenido y los archivos son de solo lectura
+        let info = FileSystemDriver::stat(&mut driver, "/net/tcp").unwrap();
+        assert_eq!(info.size, header.len() as u64);
+        assert_eq!(info.node_type, NodeType::File);
+        assert_eq!(info.permissions.owner, 4);
+        let mut buffer = [0u8; 8];
+        assert_eq!(FileSystemDriver::read_file(&mut driver, "/net/tcp", &mut buffer, 6), Ok(8));
+        assert_eq!(&buffer, b"local_ad");
+        assert_eq!(FileSystemDriver::read_file(&mut driver, "/net/tcp", &mut buffer, info.size), Ok(0));
+        assert!(FileSystemDriver::read_file(&mut driver, "/net", &mut buffer, 0).is_err());
+        assert_eq!(FileSystemDriver::write_file(&mut driver, "/meminfo", b"x", 0), Err("Sistema de archivos de solo lectura"));
+        assert_eq!(FileSystemDriver::open(&mut driver, "/nuevo", true).unwrap_err(), "Sistema de archivos de solo lectura");
+        assert_eq!(FileSystemDriver::unlink(&mut driver, "/meminfo"), Err("Sistema de archivos de solo lectura"));
+    }
+
+    #[test]
+    fn test_process_directories() {
+        let _lock = lock_kernel_state();
+        let mut scheduler = init_process_scheduler(SchedulingAlgorithm::RoundRobin);
+        scheduler.update_system_time(1_000);
+        let init = scheduler.create_process("init", ProcessPriority::High);
+        let shell = scheduler.create_process("shell", ProcessPriority::Normal);
+        set_process_scheduler(scheduler);
+        assert_eq!((init, shell), (1, 2));
+
+        let mut driver = ProcFsDriver::new(0);
+        let root = FileSystemDriver::read_dir(&mut driver, "/").unwrap();
+        assert_eq!(names(&root), ["1", "2", "meminfo", "mounts", "pci", "net"]);
+        assert_eq!(driver.lookup("/2/status"), Ok(ProcNode::File(ProcFile::ProcessStatus(shell))));
+        assert_eq!(driver.lookup("/3"), Err("Ruta no encontrada"));
+        assert_eq!(driver.lookup("/2/cmdline"), Err("Ruta no encontrada"));
+
+        let status = read_all(&mut driver, "/1/status");
+        assert_eq!(
+            status,
+            "Name:\tinit\nState:\tN (new)\nPid:\t1\nPriority:\t1\nCpuTime:\t0\n\
+             CreationTime:\t1000\nLastRunTime:\t0\nWaitTime:\t0\n"
+        );
+        let directory = FileSystemDriver::stat(&mut driver, "/1").unwrap();
+        assert_eq!(directory.node_type, NodeType::Directory);
+        assert_eq!(directory.created_time, 1_000);
+        assert_ne!(directory.inode, FileSystemDriver::stat(&mut driver, "/1/status").unwrap().inode);
+
+        // Un proceso que termina desaparece de /proc
+        let scheduler = crate::process::scheduler::get_process_scheduler().unwrap();
+        let process = scheduler.ready_queue.dequeue().unwrap();
+        drop(unsafe { Box::from_raw(process.as_ptr()) });
+        assert_eq!(names(&FileSystemDriver::read_dir(&mut driver, "/").unwrap())[0], "2");
+        assert!(FileSystemDriver::stat(&mut driver, "/1/status").is_err());
+    }
+
+    #[test]
+    fn test_mounts_lists_devices_by_dev_name() {
+        use super::super::fat32::{format_fat32, Fat32FormatOptions};
+
+        with_vfs(|_| {
+            // Disco MBR con una partición FAT32 que se monta en /mnt/sda1
+            let mut image = vec![0u8; 40 * 1024 * 1024];
+            image[446 + 4] = 0x0C;
+            image[446 + 8..446 + 12].copy_from_slice(&2048u32.to_le_bytes());
+            image[446 + 12..446 + 16].copy_from_slice(&(38 * 2048u32).to_le_bytes());
+            image[510] = 0x55;
+            image[511] = 0xAA;
+            let disk = TestDisk::with_data(image, 512);
+            let partition = disk.partitions()[0];
+            let options = Fat32FormatOptions { cluster_size: 512, ..Default::default() };
+            format_fat32(partition, &options).unwrap();
+
+            super::super::vfs::set_vfs(super::super::vfs::init_vfs());
+            let vfs = super::super::vfs::get_vfs().unwrap();
+            vfs.mount("/tmp", FileSystemType::RamFs, 0).unwrap();
+            vfs.mount("/proc", FileSystemType::ProcFs, 0).unwrap();
+            vfs.mount("/dev", FileSystemType::DevFs, 0).unwrap();
+            assert_eq!(crate::filesystem::mount_partitions(vfs), 1);
+
+            let mounts = read_all(&mut ProcFsDriver::new(0), "/mounts");
+            assert_eq!(
+                mounts,
+                "ramfs /tmp ramfs rw 0 0\nproc /proc proc ro 0 0\ndevfs /dev devfs rw 0 0\n/dev/sda1 /mnt/sda1 vfat rw 0 0\n"
+            );
+        });
+    }
+
+    #[test]
+    fn test_number_formatting() {
+        assert_eq!(decimal(0), "0");
+        assert_eq!(decimal(u64::MAX), "18446744073709551615");
+        let mut content = String::new();
+        push_hex(&mut content, 0xA, 4);
+        push_hex(&mut content, 0x1_2345, 2);
+        push_hex(&mut content, 0, 0);
+        assert_eq!(content, "000A12345");
+    }
+}
@@ -0,0 +1,310 @@
+//! Cuotas de disco por usuario y grupo para ReactOS Rust Kernel
+//!
+//! El VFS lleva la cuenta de los bloques y los inodos de cada propietario
+//! (`owner_id` y `group_id` de `FileInfo`) y consulta esta tabla antes de
+//! crear archivos o hacerlos crecer. Cada recurso tiene un límite blando y
+//! uno estricto: el estricto no se puede superar nunca; el blando sí,
+//! durante un periodo de gracia que empieza al superarlo. Agotada la gracia,
+//! el límite blando se aplica como estricto hasta que el uso vuelve a
+//! quedar por debajo. Un límite 0 significa sin límite.
+//!
+//! Los bloques se cuentan en unidades de `QUOTA_BLOCK_SIZE` a partir del
+//! tamaño lógico de los archivos. Los archivos del superusuario (UID 0) se
+//! contabilizan pero no están sujetos a límites.
+
+use alloc::{string::String, vec::Vec};
+
+use super::procfs::decimal;
+
+/// Tamaño de un bloque de cuota en bytes
+pub const QUOTA_BLOCK_SIZE: u64 = 1024;
+
+/// Periodo de gracia por defecto (7 días, en segundos)
+pub const QUOTA_DEFAULT_GRACE_PERIOD: u64 = 7 * 24 * 3600;
+
+/// UID del superusuario, exento de límites
+pub const QUOTA_ROOT_UID: u32 = 0;
+
+/// Tipos de cuota
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaKind {
+    /// Cuota de un usuario (UID)
+    User,
+    /// Cuota de un grupo (GID)
+    Group,
+}
+
+/// Límites de una cuota (0 = sin límite)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QuotaLimits {
+    /// Límite blando de bloques
+    pub block_soft: u64,
+    /// Límite estricto de bloques
+    pub block_hard: u64,
+    /// Límite blando de inodos
+    pub inode_soft: u64,
+    /// Límite estricto de inodos
+    pub inode_hard: u64,
+}
+
+/// Uso y límites de un usuario o grupo
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuotaEntry {
+    /// Tipo de cuota
+    pub kind: QuotaKind,
+    /// UID o GID
+    pub id: u32,
+    /// Límites configurados
+    pub limits: QuotaLimits,
+    /// Bloques en uso
+    pub blocks: u64,
+    /// Inodos en uso
+    pub inodes: u64,
+    /// Fin del periodo de gracia de bloques, si se supera el límite blando
+    pub block_grace_expires: Option<u64>,
+    /// Fin del periodo de gracia de inodos, si se supera el límite blando
+    pub inode_grace_expires: Option<u64>,
+}
+
+impl QuotaEntry {
+    /// Crear una entrada sin uso ni límites
+    pub fn new(kind: QuotaKind, id: u32) -> Self {
+        Self {
+            kind,
+            id,
+            limits: QuotaLimits::default(),
+            blocks: 0,
+            inodes: 0,
+            block_grace_expires: None,
+            inode_grace_expires: None,
+        }
+    }
+
+    /// Verificar si supera algún límite blando
+    pub fn over_soft_limit(&self) -> bool {
+        exceeds(self.blocks, self.limits.block_soft) || exceeds(self.inodes, self.limits.inode_soft)
+    }
+}
+
+/// Tabla de cuotas del VFS
+#[derive(Debug, Clone)]
+pub struct QuotaTable {
+    /// Entradas de usuarios y grupos con uso o límites
+    pub entries: Vec<QuotaEntry>,
+    /// Periodo de gracia de bloques en segundos
+    pub block_grace_period: u64,
+    /// Periodo de gracia de inodos en segundos
+    pub inode_grace_period: u64,
+    /// Tiempo actual en segundos; lo actualiza el temporizador del sistema
+    pub now: u64,
+}
+
+impl QuotaTable {
+    /// Crear una tabla vacía con los periodos de gracia por defecto
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            block_grace_period: QUOTA_DEFAULT_GRACE_PERIOD,
+            inode_grace_period: QUOTA_DEFAULT_GRACE_PERIOD,
+            now: 0,
+        }
+    }
+
+    /// Actualizar el tiempo actual
+    pub fn set_time(&mut self, now: u64) {
+        self.now = now;
+    }
+
+    /// Cambiar los periodos de gracia; solo afectan a los que empiecen después
+    pub fn set_grace_periods(&mut self, block_grace_period: u64, inode_grace_period: u64) {
+        self.block_grace_period = block_grace_period;
+        self.inode_grace_period = inode_grace_period;
+    }
+
+    /// Fijar los límites de un usuario o grupo
+    ///
+    /// Los periodos de gracia se recalculan con los nuevos límites blandos.
+    pub fn set_limits(&mut self, kind: QuotaKind, id: u32, limits: QuotaLimits) -> Result<(), &'static str> {
+        if (limits.block_hard != 0 && limits.block_soft > limits.block_hard)
+            || (limits.inode_hard != 0 && limits.inode_soft > limits.inode_hard)
+        {
+            return Err("El límite blando supera al estricto");
+        }
+        let (now, block_grace_period, inode_grace_period) = (self.now, self.block_grace_period, self.inode_grace_period);
+        let entry = self.entry_mut(kind, id);
+        entry.limits = limits;
+        update_grace(entry, now, block_grace_period, inode_grace_period);
+        Ok(())
+    }
+
+    /// Entrada de un usuario o grupo, si tiene uso o límites
+    pub fn entry(&self, kind: QuotaKind, id: u32) -> Option<&QuotaEntry> {
+        self.entries.iter().find(|entry| entry.kind == kind && entry.id == id)
+    }
+
+    /// Verificar si un propietario puede añadir bloques e inodos
+    ///
+    /// Solo se comprueban los incrementos; liberar espacio siempre está
+    /// permitido. Se consultan la cuota del usuario y la de su grupo.
+    pub fn check(&self, uid: u32, gid: u32, blocks: i64, inodes: i64) -> Result<(), &'static str> {
+        if uid == QUOTA_ROOT_UID {
+            return Ok(());
+        }
+        for (kind, id) in [(QuotaKind::User, uid), (QuotaKind::Group, gid)] {
+            let entry = match self.entry(kind, id) {
+                Some(entry) => entry,
+                None => continue,
+            };
+            if blocks > 0 {
+                let limits = (entry.limits.block_soft, entry.limits.block_hard);
+                check_resource(entry.blocks, blocks as u64, limits, entry.block_grace_expires, self.now)?;
+            }
+            if inodes > 0 {
+                let limits = (entry.limits.inode_soft, entry.limits.inode_hard);
+                check_resource(entry.inodes, inodes as u64, limits, entry.inode_grace_expires, self.now)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Anotar un cambio de uso de un propietario en su cuota y la de su grupo
+    pub fn charge(&mut self, uid: u32, gid: u32, blocks: i64, inodes: i64) {
+        if blocks == 0 && inodes == 0 {
+            return;
+        }
+        let (now, block_grace_period, inode_grace_period) = (self.now, self.block_grace_period, self.inode_grace_period);
+        for (kind, id) in [(QuotaKind::User, uid), (QuotaKind::Group, gid)] {
+            let entry = self.entry_mut(kind, id);
+            entry.blocks = entry.blocks.saturating_add_signed(blocks);
+            entry.inodes = entry.inodes.saturating_add_signed(inodes);
+            update_grace(entry, now, block_grace_period, inode_grace_period);
+        }
+    }
+
+    /// Poner a cero el uso de todas las entradas (antes de recontarlo)
+    pub fn clear_usage(&mut self) {
+        for entry in self.entries.iter_mut() {
+            entry.blocks = 0;
+            entry.inodes = 0;
+            entry.block_grace_expires = None;
+            entry.inode_grace_expires = None;
+        }
+    }
+
+    /// Informe de uso y límites, como `repquota`
+    ///
+    /// Una línea por entrada del tipo pedido (o de ambos con `None`),
+    /// ordenadas por ID. Las marcas `+`/`-` indican si se supera el límite
+    /// blando de bloques y de inodos; la gracia muestra el tiempo restante.
+    pub fn report(&self, kind: Option<QuotaKind>) -> String {
+        let mut entries: Vec<&QuotaEntry> = self
+            .entries
+            .iter()
+            .filter(|entry| kind.map_or(true, |kind| entry.kind == kind))
+            .collect();
+        entries.sort_by_key(|entry| (entry.kind == QuotaKind::Group, entry.id));
+
+        let mut content = String::from("Tipo   ");
+        push_column(&mut content, "ID", 4);
+        push_column(&mut content, "Uso", 6);
+        for label in ["Bloques", "Blando", "Estricto", "Gracia", "Inodos", "Blando", "Estricto", "Gracia"] {
+            push_column(&mut content, label, 9);
+        }
+        content.push('\n');
+        for entry in entries {
+            content.push_str(if entry.kind == QuotaKind::User { "usuario" } else { "grupo  " });
+            push_column(&mut content, &decimal(entry.id as u64), 4);
+            content.push_str("     ");
+            content.push(if exceeds(entry.blocks, entry.limits.block_soft) { '+' } else { '-' });
+            content.push(if exceeds(entry.inodes, entry.limits.inode_soft) { '+' } else { '-' });
+            push_column(&mut content, &decimal(entry.blocks), 9);
+            push_column(&mut content, &decimal(entry.limits.block_soft), 9);
+            push_column(&mut content, &decimal(entry.limits.block_hard), 9);
+            push_column(&mut content, &grace_text(entry.block_grace_expires, self.now), 9);
+            push_column(&mut content, &decimal(entry.inodes), 9);
+            push_column(&mut content, &decimal(entry.limits.inode_soft), 9);
+            push_column(&mut content, &decimal(entry.limits.inode_hard), 9);
+            push_column(&mut content, &grace_text(entry.inode_grace_expires, self.now), 9);
+            content.push('\n');
+        }
+        content
+    }
+
+    /// Entrada de un usuario o grupo, creándola si no existe
+    fn entry_mut(&mut self, kind: QuotaKind, id: u32) -> &mut QuotaEntry {
+        let position = match self.entries.iter().position(|entry| entry.kind == kind && entry.id == id) {
+            Some(position) => position,
+            None => {
+                self.entries.push(QuotaEntry::new(kind, id));
+                self.entries.len() - 1
+            }
+        };
+        &mut self.entries[position]
+    }
+}
+
+/// Bloques de cuota que ocupa un archivo de `size` bytes
+pub fn quota_blocks(size: u64) -> u64 {
+    size.div_ceil(QUOTA_BLOCK_SIZE)
+}
+
+/// Verificar si un uso supera un límite (0 = sin límite)
+fn exceeds(usage: u64, limit: u64) -> bool {
+    limit != 0 && usage > limit
+}
+
+/// Verificar un incremento de un recurso frente a sus límites blando y estricto
+fn check_resource(usage: u64, increment: u64, (soft, hard): (u64, u64), grace_expires: Option<u64>, now: u64) -> Result<(), &'static str> {
+    let total = usage.saturating_add(increment);
+    if exceeds(total, hard) {
+        return Err("Cuota de disco excedida");
+    }
+    if exceeds(total, soft) && grace_expires.map_or(false, |expires| now >= expires) {
+        return Err("Periodo de gracia de la cuota agotado");
+    }
+    Ok(())
+}
+
+/// Iniciar o cancelar los periodos de gracia según el uso actual
+fn update_grace(entry: &mut QuotaEntry, now: u64, block_grace_period: u64, inode_grace_period: u64) {
+    if !exceeds(entry.blocks, entry.limits.block_soft) {
+        entry.block_grace_expires = None;
+    } else if entry.block_grace_expires.is_none() {
+        entry.block_grace_expires = Some(now.saturating_add(block_grace_period));
+    }
+    if !exceeds(entry.inodes, entry.limits.inode_soft) {
+        entry.inode_grace_expires = None;
+    } else if entry.inode_grace_expires.is_none() {
+        entry.inode_grace_expires = Some(now.saturating_add(inode_grace_period));
+    }
+}
+
+/// Tiempo de gracia restante como texto ("6d", "3h", "12m", "40s" o "agotado")
+fn grace_text(expires: Option<u64>, now: u64) -> String {
+    let expires = match expires {
+        Some(expires) => expires,
+        None => return String::new(),
+    };
+    if now >= expires {
+        return String::from("agotado");
+    }
+    let remaining = expires - now;
+    let (value, unit) = match remaining {
+        86400.. => (remaining / 86400, 'd'),
+        3600.. => (remaining / 3600, 'h'),
+        60.. => (remaining / 60, 'm'),
+        _ => (remaining, 's'),
+    };
+    let mut text = decimal(value);
+    text.push(unit);
+    text
+}
+
+/// Añadir una columna alineada a la derecha en `width` caracteres
+fn push_column(content: &mut String, text: &str, width: usize) {
+    content.push(' ');
+    for _ in text.chars().count()..width {
+        content.push(' ');
+    }
+    content.push_str(text);
+}
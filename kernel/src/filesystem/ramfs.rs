@@ -0,0 +1,676 @@
+//! Sistema de archivos en memoria (ramfs/tmpfs) para ReactOS Rust Kernel
+//!
+//! Los archivos viven en páginas de memoria que se reservan al escribir,
+//! de modo que los huecos de los archivos dispersos no ocupan espacio.
+//! No necesita ningún dispositivo, así que está disponible antes que los
+//! drivers de disco y en las pruebas del host.
+
+use alloc::{string::String, vec, vec::Vec};
+
+use super::path::split_parent;
+use super::vfs::{FileInfo, FilePermissions, FileSystemDriver, NodeType, VfsStats, VFS_MAX_NAME_LENGTH};
+
+/// Tamaño de página de los datos de un archivo
+pub const RAMFS_PAGE_SIZE: usize = 4096;
+
+/// Límite de tamaño por defecto (16 MiB)
+pub const RAMFS_DEFAULT_SIZE_LIMIT: u64 = 16 * 1024 * 1024;
+
+/// Número máximo de nodos (archivos y directorios)
+pub const RAMFS_MAX_NODES: usize = 4096;
+
+/// Inodo del directorio raíz
+pub const RAMFS_ROOT_INODE: u64 = 1;
+
+/// Permiso de lectura
+const RAMFS_PERMISSION_READ: u8 = 4;
+
+/// Permiso de escritura
+const RAMFS_PERMISSION_WRITE: u8 = 2;
+
+/// Página de datos de un archivo
+#[derive(Debug, Clone)]
+pub struct RamFsPage {
+    /// Índice de la página dentro del archivo
+    pub index: u64,
+    /// Contenido (`RAMFS_PAGE_SIZE` bytes)
+    pub data: Vec<u8>,
+}
+
+/// Nodo del sistema de archivos en memoria
+#[derive(Debug, Clone)]
+pub struct RamFsNode {
+    /// Número de inodo
+    pub inode: u64,
+    /// Nombre dentro del directorio padre
+    pub name: String,
+    /// Tipo de nodo (archivo o directorio)
+    pub node_type: NodeType,
+    /// Inodo del directorio padre (la raíz es su propio padre)
+    pub parent: u64,
+    /// Inodos de los hijos, si es un directorio
+    pub children: Vec<u64>,
+    /// Tamaño lógico del archivo
+    pub size: u64,
+    /// Páginas reservadas, ordenadas por índice
+    pub pages: Vec<RamFsPage>,
+    /// Permisos
+    pub permissions: FilePermissions,
+    /// ID del propietario
+    pub owner_id: u32,
+    /// ID del grupo
+    pub group_id: u32,
+    /// Tiempo de creación
+    pub created_time: u64,
+    /// Tiempo de modificación
+    pub modified_time: u64,
+    /// Tiempo de acceso
+    pub accessed_time: u64,
+}
+
+impl RamFsNode {
+    /// Crear un nodo vacío
+    fn new(inode: u64, name: &str, node_type: NodeType, parent: u64, time: u64) -> Self {
+        Self {
+            inode,
+            name: String::from(name),
+            node_type,
+            parent,
+            children: Vec::new(),
+            size: 0,
+            pages: Vec::new(),
+            permissions: if node_type == NodeType::Directory { FilePermissions::full() } else { FilePermissions::read_write() },
+            owner_id: 0,
+            group_id: 0,
+            created_time: time,
+            modified_time: time,
+            accessed_time: time,
+        }
+    }
+
+    /// Verificar si es un directorio
+    pub fn is_directory(&self) -> bool {
+        self.node_type == NodeType::Directory
+    }
+
+    /// Página con el índice dado, si está reservada
+    fn page(&self, index: u64) -> Option<&RamFsPage> {
+        self.pages
+            .binary_search_by_key(&index, |page| page.index)
+            .ok()
+            .map(|position| &self.pages[position])
+    }
+}
+
+/// Driver del sistema de archivos en memoria
+pub struct RamFsDriver {
+    /// Dispositivo asociado (solo se usa para identificar el montaje)
+    pub device_id: u32,
+    /// Nodos; el inodo `n` ocupa el slot `n - 1`
+    pub nodes: Vec<Option<RamFsNode>>,
+    /// Bytes máximos de datos (páginas reservadas)
+    pub size_limit: u64,
+    /// Bytes de datos reservados
+    pub used_bytes: u64,
+    /// Estadísticas de operaciones
+    pub stats: VfsStats,
+}
+
+impl RamFsDriver {
+    /// Crear un sistema de archivos vacío con un límite de tamaño
+    pub fn new(device_id: u32, size_limit: u64) -> Self {
+        let mut driver = Self {
+            device_id,
+            nodes: Vec::new(),
+            size_limit,
+            used_bytes: 0,
+            stats: VfsStats::default(),
+        };
+        let time = driver.get_system_time();
+        driver.nodes.push(Some(RamFsNode::new(RAMFS_ROOT_INODE, "/", NodeType::Directory, RAMFS_ROOT_INODE, time)));
+        driver
+    }
+
+    /// Obtener un nodo por inodo
+    pub fn node(&self, inode: u64) -> Option<&RamFsNode> {
+        self.nodes.get((inode as usize).wrapping_sub(1)).and_then(|node| node.as_ref())
+    }
+
+    /// Obtener un nodo mutable por inodo
+    fn node_mut(&mut self, inode: u64) -> Option<&mut RamFsNode> {
+        self.nodes.get_mut((inode as usize).wrapping_sub(1)).and_then(|node| node.as_mut())
+    }
+
+    /// Buscar el inodo de una ruta
+    pub fn lookup(&self, path: &str) -> Result<u64, &'static str> {
+        let mut current = RAMFS_ROOT_INODE;
+        for component in path.split('/').filter(|component| !component.is_empty()) {
+            let node = self.node(current).ok_or("Ruta no encontrada")?;
+            if !node.is_directory() {
+                return Err("Componente de ruta no es un directorio");
+            }
+            current = match component {
+                "." => current,
+                ".." => node.parent,
+                _ => node
+                    .children
+                    .iter()
+                    .copied()
+                    .find(|&child| self.node(child).map_or(false, |child| child.name == component))
+                    .ok_or("Ruta no encontrada")?,
+            };
+        }
+        Ok(current)
+    }
+
+    /// Crear un archivo vacío
+    pub fn create_file(&mut self, path: &str) -> Result<u64, &'static str> {
+        self.create_node(path, NodeType::File)
+    }
+
+    /// Crear un directorio vacío
+    pub fn create_directory(&mut self, path: &str) -> Result<u64, &'static str> {
+        self.create_node(path, NodeType::Directory)
+    }
+
+    /// Crear un nodo en su directorio padre
+    fn create_node(&mut self, path: &str, node_type: NodeType) -> Result<u64, &'static str> {
+        let (parent_path, name) = split_node_path(path)?;
+        let parent = self.writable_directory(parent_path)?;
+        if self.lookup_child(parent, name).is_some() {
+            return Err("El archivo ya existe");
+        }
+
+        let slot = match self.nodes.iter().position(|node| node.is_none()) {
+            Some(slot) => slot,
+            None if self.nodes.len() < RAMFS_MAX_NODES => {
+                self.nodes.push(None);
+                self.nodes.len() - 1
+            }
+            None => return Err("No hay nodos libres"),
+        };
+        let inode = slot as u64 + 1;
+        let time = self.get_system_time();
+        self.nodes[slot] = Some(RamFsNode::new(inode, name, node_type, parent, time));
+        if let Some(directory) = self.node_mut(parent) {
+            directory.children.push(inode);
+            directory.modified_time = time;
+        }
+        Ok(inode)
+    }
+
+    /// Leer datos de un archivo; los huecos se leen como ceros
+    pub fn read_file(&mut self, path: &str, offset: u64, buffer: &mut [u8]) -> Result<usize, &'static str> {
+        let result = self.read_node(path, offset, buffer);
+        match result {
+            Ok(bytes_read) => {
+                self.stats.read_operations += 1;
+                self.stats.bytes_read += bytes_read as u64;
+            }
+            Err(_) => self.stats.read_errors += 1,
+        }
+        result
+    }
+
+    fn read_node(&mut self, path: &str, offset: u64, buffer: &mut [u8]) -> Result<usize, &'static str> {
+        let inode = self.lookup(path)?;
+        let time = self.get_system_time();
+        let node = self.node_mut(inode).ok_or("Ruta no encontrada")?;
+        if node.is_directory() {
+            return Err("No se puede leer un directorio como archivo");
+        }
+        if node.permissions.owner & RAMFS_PERMISSION_READ == 0 {
+            return Err("Permiso denegado");
+        }
+        if offset >= node.size {
+            return Ok(0);
+        }
+
+        let length = core::cmp::min(buffer.len() as u64, node.size - offset) as usize;
+        let mut done = 0;
+        while done < length {
+            let position = offset + done as u64;
+            let page_offset = (position % RAMFS_PAGE_SIZE as u64) as usize;
+            let chunk = core::cmp::min(RAMFS_PAGE_SIZE - page_offset, length - done);
+            match node.page(position / RAMFS_PAGE_SIZE as u64) {
+                Some(page) => buffer[done..done + chunk].copy_from_slice(&page.data[page_offset..page_offset + chunk]),
+                None => buffer[done..done + chunk].fill(0),
+            }
+            done += chunk;
+        }
+        node.accessed_time = time;
+        Ok(length)
+    }
+
+    /// Escribir datos en un archivo a partir de un desplazamiento
+    ///
+    /// Escribir más allá del final deja un hueco que no ocupa memoria. La
+    /// escritura falla entera, sin cambios, si superaría el límite de tamaño.
+    pub fn write_file(&mut self, path: &str, offset: u64, data: &[u8]) -> Result<usize, &'static str> {
+        let result = self.write_node(path, offset, data);
+        match result {
+            Ok(bytes_written) => {
+                self.stats.write_operations += 1;
+                self.stats.bytes_written += bytes_written as u64;
+            }
+            Err(_) => self.stats.write_errors += 1,
+        }
+        result
+    }
+
+    fn write_node(&mut self, path: &str, offset: u64, data: &[u8]) -> Result<usize, &'static str> {
+        let inode = self.lookup(path)?;
+        let end = offset.checked_add(data.len() as u64).ok_or("Desplazamiento no válido")?;
+        let (used_bytes, size_limit) = (self.used_bytes, self.size_limit);
+        let time = self.get_system_time();
+        let node = self.node_mut(inode).ok_or("Ruta no encontrada")?;
+        if node.is_directory() {
+            return Err("No se puede escribir en un directorio");
+        }
+        if node.permissions.owner & RAMFS_PERMISSION_WRITE == 0 {
+            return Err("Permiso denegado");
+        }
+        if data.is_empty() {
+            return Ok(0);
+        }
+
+        // Comprobar el espacio antes de tocar nada
+        let first_page = offset / RAMFS_PAGE_SIZE as u64;
+        let last_page = (end - 1) / RAMFS_PAGE_SIZE as u64;
+        let reserved = node
+            .pages
+            .iter()
+            .filter(|page| page.index >= first_page && page.index <= last_page)
+            .count() as u64;
+        let new_bytes = (last_page - first_page + 1 - reserved) * RAMFS_PAGE_SIZE as u64;
+        if used_bytes + new_bytes > size_limit {
+            return Err("No hay espacio en el sistema de archivos");
+        }
+
+        let mut done = 0;
+        while done < data.len() {
+            let position = offset + done as u64;
+            let index = position / RAMFS_PAGE_SIZE as u64;
+            let page_offset = (position % RAMFS_PAGE_SIZE as u64) as usize;
+            let chunk = core::cmp::min(RAMFS_PAGE_SIZE - page_offset, data.len() - done);
+            let slot = match node.pages.binary_search_by_key(&index, |page| page.index) {
+                Ok(slot) => slot,
+                Err(slot) => {
+                    node.pages.insert(slot, RamFsPage { index, data: vec![0u8; RAMFS_PAGE_SIZE] });
+                    slot
+                }
+            };
+            node.pages[slot].data[page_offset..page_offset + chunk].copy_from_slice(&data[done..done + chunk]);
+            done += chunk;
+        }
+        node.size = core::cmp::max(node.size, end);
+        node.modified_time = time;
+        self.used_bytes += new_bytes;
+        Ok(data.len())
+    }
+
+    /// Cambiar el tamaño de un archivo
+    ///
+    /// Al crecer solo cambia el tamaño (el final queda como hueco); al
+    /// encoger se liberan las páginas sobrantes y se limpia la última.
+    pub fn truncate_file(&mut self, path: &str, size: u64) -> Result<(), &'static str> {
+        let inode = self.lookup(path)?;
+        let time = self.get_system_time();
+        let node = self.node_mut(inode).ok_or("Ruta no encontrada")?;
+        if node.is_directory() {
+            return Err("No se puede truncar un directorio");
+        }
+        if node.permissions.owner & RAMFS_PERMISSION_WRITE == 0 {
+            return Err("Permiso denegado");
+        }
+
+        let kept_pages = (size + RAMFS_PAGE_SIZE as u64 - 1) / RAMFS_PAGE_SIZE as u64;
+        let before = node.pages.len();
+        node.pages.retain(|page| page.index < kept_pages);
+        let freed = (before - node.pages.len()) as u64 * RAMFS_PAGE_SIZE as u64;
+        let tail = (size % RAMFS_PAGE_SIZE as u64) as usize;
+        if tail != 0 {
+            if let Some(page) = node.pages.last_mut().filter(|page| page.index == kept_pages - 1) {
+                page.data[tail..].fill(0);
+            }
+        }
+        node.size = size;
+        node.modified_time = time;
+        self.used_bytes -= freed;
+        Ok(())
+    }
+
+    /// Eliminar un archivo o un directorio vacío
+    pub fn delete_file(&mut self, path: &str) -> Result<(), &'static str> {
+        let (parent_path, _) = split_node_path(path)?;
+        let parent = self.writable_directory(parent_path)?;
+        let inode = self.lookup(path)?;
+        let node = self.node(inode).ok_or("Ruta no encontrada")?;
+        if !node.children.is_empty() {
+            return Err("El directorio no está vacío");
+        }
+
+        let freed = node.pages.len() as u64 * RAMFS_PAGE_SIZE as u64;
+        let time = self.get_system_time();
+        if let Some(directory) = self.node_mut(parent) {
+            directory.children.retain(|&child| child != inode);
+            directory.modified_time = time;
+        }
+        self.nodes[inode as usize - 1] = None;
+        self.used_bytes -= freed;
+        Ok(())
+    }
+
+    /// Renombrar o mover un archivo o directorio
+    pub fn rename_file(&mut self, path: &str, new_path: &str) -> Result<(), &'static str> {
+        let (parent_path, _) = split_node_path(path)?;
+        let old_parent = self.writable_directory(parent_path)?;
+        let inode = self.lookup(path)?;
+        let (new_parent_path, new_name) = split_node_path(new_path)?;
+        let new_parent = self.writable_directory(new_parent_path)?;
+        match self.lookup_child(new_parent, new_name) {
+            Some(existing) if existing != inode => return Err("El archivo ya existe"),
+            _ => {}
+        }
+
+        // Un directorio no puede moverse dentro de sí mismo
+        let mut ancestor = new_parent;
+        while ancestor != RAMFS_ROOT_INODE {
+            if ancestor == inode {
+                return Err("No se puede mover un directorio dentro de sí mismo");
+            }
+            ancestor = self.node(ancestor).map_or(RAMFS_ROOT_INODE, |node| node.parent);
+        }
+
+        let time = self.get_system_time();
+        if let Some(directory) = self.node_mut(old_parent) {
+            directory.children.retain(|&child| child != inode);
+            directory.modified_time = time;
+        }
+        if let Some(directory) = self.node_mut(new_parent) {
+            directory.children.push(inode);
+            directory.modified_time = time;
+        }
+        if let Some(node) = self.node_mut(inode) {
+            node.parent = new_parent;
+            node.name = String::from(new_name);
+        }
+        Ok(())
+    }
+
+    /// Listar un directorio
+    pub fn read_dir(&self, path: &str) -> Result<Vec<&RamFsNode>, &'static str> {
+        let node = self.node(self.lookup(path)?).ok_or("Ruta no encontrada")?;
+        if !node.is_directory() {
+            return Err("La ruta no es un directorio");
+        }
+        Ok(node.children.iter().filter_map(|&child| self.node(child)).collect())
+    }
+
+    /// Cambiar los permisos de un archivo o directorio
+    pub fn set_permissions(&mut self, path: &str, permissions: FilePermissions) -> Result<(), &'static str> {
+        let inode = self.lookup(path)?;
+        let node = self.node_mut(inode).ok_or("Ruta no encontrada")?;
+        node.permissions = permissions;
+        Ok(())
+    }
+
+    /// Cambiar el propietario y el grupo de un archivo o directorio
+    pub fn set_owner(&mut self, path: &str, owner_id: u32, group_id: u32) -> Result<(), &'static str> {
+        let inode = self.lookup(path)?;
+        let node = self.node_mut(inode).ok_or("Ruta no encontrada")?;
+        node.owner_id = owner_id;
+        node.group_id = group_id;
+        Ok(())
+    }
+
+    /// Bytes de datos libres hasta el límite
+    pub fn free_bytes(&self) -> u64 {
+        self.size_limit.saturating_sub(self.used_bytes)
+    }
+
+    /// Número de nodos en uso (incluida la raíz)
+    pub fn node_count(&self) -> usize {
+        self.nodes.iter().flatten().count()
+    }
+
+    /// Buscar un hijo por nombre
+    fn lookup_child(&self, directory: u64, name: &str) -> Option<u64> {
+        self.node(directory)?
+            .children
+            .iter()
+            .copied()
+            .find(|&child| self.node(child).map_or(false, |child| child.name == name))
+    }
+
+    /// Resolver un directorio en el que se va a crear, borrar o renombrar
+    fn writable_directory(&self, path: &str) -> Result<u64, &'static str> {
+        let inode = self.lookup(path)?;
+        let node = self.node(inode).ok_or("Ruta no encontrada")?;
+        if !node.is_directory() {
+            return Err("La ruta no es un directorio");
+        }
+        if node.permissions.owner & RAMFS_PERMISSION_WRITE == 0 {
+            return Err("Permiso denegado");
+        }
+        Ok(inode)
+    }
+
+    /// Obtener tiempo del sistema (segundos desde la época Unix)
+    fn get_system_time(&self) -> u64 {
+        crate::hal::rtc::unix_time()
+    }
+
+    /// Información de un nodo para el VFS
+    fn vfs_file_info(&self, node: &RamFsNode) -> FileInfo {
+        let mut file_info = FileInfo::new(0, &node.name, node.node_type);
+        file_info.size = node.size;
+        file_info.block_size = RAMFS_PAGE_SIZE as u32;
+        file_info.block_count = node.pages.len() as u64;
+        file_info.permissions = node.permissions;
+        file_info.owner_id = node.owner_id;
+        file_info.group_id = node.group_id;
+        file_info.created_time = node.created_time;
+        file_info.modified_time = node.modified_time;
+        file_info.accessed_time = node.accessed_time;
+        file_info.link_count = if node.is_directory() { 2 } else { 1 };
+        file_info.device = self.device_id;
+        file_info.inode = node.inode;
+        file_info
+    }
+}
+
+impl FileSystemDriver for RamFsDriver {
+    fn open(&mut self, file_path: &str, create: bool) -> Result<FileInfo, &'static str> {
+        let inode = match self.lookup(file_path) {
+            Ok(inode) => inode,
+            Err("Ruta no encontrada") if create => self.create_file(file_path)?,
+            Err(e) => return Err(e),
+        };
+        self.node(inode).map(|node| self.vfs_file_info(node)).ok_or("Ruta no encontrada")
+    }
+
+    fn read_file(&mut self, file_path: &str, buffer: &mut [u8], offset: u64) -> Result<usize, &'static str> {
+        RamFsDriver::read_file(self, file_path, offset, buffer)
+    }
+
+    fn write_file(&mut self, file_path: &str, buffer: &[u8], offset: u64) -> Result<usize, &'static str> {
+        RamFsDriver::write_file(self, file_path, offset, buffer)
+    }
+
+    fn read_dir(&mut self, dir_path: &str) -> Result<Vec<FileInfo>, &'static str> {
+        Ok(RamFsDriver::read_dir(self, dir_path)?
+            .iter()
+            .map(|node| self.vfs_file_info(node))
+            .collect())
+    }
+
+    fn stat(&mut self, file_path: &str) -> Result<FileInfo, &'static str> {
+        let inode = self.lookup(file_path)?;
+        self.node(inode).map(|node| self.vfs_file_info(node)).ok_or("Ruta no encontrada")
+    }
+
+    fn unlink(&mut self, file_path: &str) -> Result<(), &'static str> {
+        self.delete_file(file_path)
+    }
+
+    fn truncate(&mut self, file_path: &str, size: u64) -> Result<(), &'static str> {
+        self.truncate_file(file_path, size)
+    }
+
+    fn rename(&mut self, old_path: &str, new_path: &str) -> Result<(), &'static str> {
+        self.rename_file(old_path, new_path)
+    }
+
+    fn set_permissions(&mut self, file_path: &str, permissions: FilePermissions) -> Result<(), &'static str> {
+        RamFsDriver::set_permissions(self, file_path, permissions)
+    }
+
+    fn set_owner(&mut self, file_path: &str, owner_id: u32, group_id: u32) -> Result<(), &'static str> {
+        RamFsDriver::set_owner(self, file_path, owner_id, group_id)
+    }
+}
+
+/// Separar una ruta en directorio padre y nombre, validando el nombre
+fn split_node_path(path: &str) -> Result<(&str, &str), &'static str> {
+    let (parent_path, name) = split_parent(path)?;
+    if name.len() > VFS_MAX_NAME_LENGTH || name.contains('\0') {
+        return Err("Nombre de archivo inválido");
+    }
+    Ok((parent_path, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directories_and_rename() {
+        let mut ramfs = RamFsDriver::new(0, RAMFS_DEFAULT_SIZE_LIMIT);
+        ramfs.create_directory("/docs").unwrap();
+        ramfs.create_directory("/docs/old").unwrap();
+        ramfs.create_file("/docs/a.txt").unwrap();
+        assert_eq!(ramfs.create_file("/docs/a.txt"), Err("El archivo ya existe"));
+        assert_eq!(ramfs.create_file("/missing/a.txt"), Err("Ruta no encontrada"));
+        assert_eq!(ramfs.create_file("/docs/a.txt/b"), Err("La ruta no es un directorio"));
+        assert_eq!(ramfs.create_file("/docs/.."), Err("Nombre de archivo inválido"));
+        assert_eq!(ramfs.lookup("/docs/old/../a.txt"), ramfs.lookup("/docs/a.txt"));
+
+        ramfs.rename_file("/docs/a.txt", "/docs/old/b.txt").unwrap();
+        let names: Vec<&str> = ramfs.read_dir("/docs/old").unwrap().iter().map(|node| node.name.as_str()).collect();
+        assert_eq!(names, ["b.txt"]);
+        assert_eq!(ramfs.rename_file("/docs", "/docs/old/docs"), Err("No se puede mover un directorio dentro de sí mismo"));
+        ramfs.create_file("/docs/c.txt").unwrap();
+        assert_eq!(ramfs.rename_file("/docs/c.txt", "/docs/old/b.txt"), Err("El archivo ya existe"));
+
+        assert_eq!(ramfs.delete_file("/docs/old"), Err("El directorio no está vacío"));
+        ramfs.delete_file("/docs/old/b.txt").unwrap();
+        ramfs.delete_file("/docs/old").unwrap();
+        assert_eq!(ramfs.node_count(), 3);
+        // El slot liberado se reutiliza
+        let inode = ramfs.create_file("/d.txt").unwrap();
+        assert!(inode <= 4);
+    }
+
+    #[test]
+    fn test_sparse_files_reserve_only_written_pages() {
+        let mut ramfs = RamFsDriver::new(0, RAMFS_DEFAULT_SIZE_LIMIT);
+        ramfs.create_file("/sparse").unwrap();
+        let far = 10 * RAMFS_PAGE_SIZE as u64 + 100;
+        ramfs.write_file("/sparse", far, b"fin").unwrap();
+        let node = ramfs.node(ramfs.lookup("/sparse").unwrap()).unwrap();
+        assert_eq!(node.size, far + 3);
+        assert_eq!(node.pages.len(), 1);
+        assert_eq!(ramfs.used_bytes, RAMFS_PAGE_SIZE as u64);
+
+        // Los huecos se leen como ceros
+        let mut buffer = [0xFFu8; 8];
+        assert_eq!(ramfs.read_file("/sparse", far - 5, &mut buffer), Ok(8));
+        assert_eq!(&buffer, b"\0\0\0\0\0fin");
+        assert_eq!(ramfs.read_file("/sparse", far + 3, &mut buffer), Ok(0));
+
+        // Una escritura que cruza páginas reserva las dos
+        ramfs.write_file("/sparse", RAMFS_PAGE_SIZE as u64 - 2, b"abcd").unwrap();
+        assert_eq!(ramfs.used_bytes, 3 * RAMFS_PAGE_SIZE as u64);
+
+        // Truncar libera las páginas sobrantes y limpia el final de la última
+        ramfs.truncate_file("/sparse", RAMFS_PAGE_SIZE as u64 - 1).unwrap();
+        assert_eq!(ramfs.used_bytes, RAMFS_PAGE_SIZE as u64);
+        ramfs.truncate_file("/sparse", RAMFS_PAGE_SIZE as u64 + 1).unwrap();
+        let mut buffer = [0xFFu8; 3];
+        assert_eq!(ramfs.read_file("/sparse", RAMFS_PAGE_SIZE as u64 - 2, &mut buffer), Ok(3));
+        assert_eq!(&buffer, b"a\0\0");
+
+        ramfs.delete_file("/sparse").unwrap();
+        assert_eq!(ramfs.used_bytes, 0);
+    }
+
+    #[test]
+    fn test_permissions_are_enforced() {
+        let mut ramfs = RamFsDriver::new(0, RAMFS_DEFAULT_SIZE_LIMIT);
+        ramfs.create_directory("/locked").unwrap();
+        ramfs.create_file("/locked/file").unwrap();
+        ramfs.write_file("/locked/file", 0, b"datos").unwrap();
+
+        ramfs.set_permissions("/locked/file", FilePermissions::read_only()).unwrap();
+        assert_eq!(ramfs.write_file("/locked/file", 0, b"x"), Err("Permiso denegado"));
+        assert_eq!(ramfs.truncate_file("/locked/file", 0), Err("Permiso denegado"));
+        let mut buffer = [0u8; 5];
+        assert_eq!(ramfs.read_file("/locked/file", 0, &mut buffer), Ok(5));
+        ramfs.set_permissions("/locked/file", FilePermissions::new(0, 0, 0)).unwrap();
+        assert_eq!(ramfs.read_file("/locked/file", 0, &mut buffer), Err("Permiso denegado"));
+
+        // Sin escritura en el directorio no se crea, borra ni renombra en él
+        ramfs.set_permissions("/locked", FilePermissions::new(5, 5, 5)).unwrap();
+        assert_eq!(ramfs.create_file("/locked/new"), Err("Permiso denegado"));
+        assert_eq!(ramfs.delete_file("/locked/file"), Err("Permiso denegado"));
+        assert_eq!(ramfs.rename_file("/locked/file", "/file"), Err("Permiso denegado"));
+
+        ramfs.set_owner("/locked/file", 1000, 100).unwrap();
+        let info = FileSystemDriver::stat(&mut ramfs, "/locked/file").unwrap();
+        assert_eq!((info.owner_id, info.group_id), (1000, 100));
+        assert_eq!(info.permissions, FilePermissions::new(0, 0, 0));
+    }
+
+    #[test]
+    fn test_size_limit_rejects_whole_write() {
+        let mut ramfs = RamFsDriver::new(0, 2 * RAMFS_PAGE_SIZE as u64);
+        ramfs.create_file("/a").unwrap();
+        ramfs.write_file("/a", 0, &[1u8; RAMFS_PAGE_SIZE]).unwrap();
+        assert_eq!(ramfs.free_bytes(), RAMFS_PAGE_SIZE as u64);
+
+        // Dos páginas nuevas no caben: no se escribe nada
+        let data = [2u8; RAMFS_PAGE_SIZE + 1];
+        assert_eq!(ramfs.write_file("/a", RAMFS_PAGE_SIZE as u64, &data), Err("No hay espacio en el sistema de archivos"));
+        assert_eq!(ramfs.node(ramfs.lookup("/a").unwrap()).unwrap().size, RAMFS_PAGE_SIZE as u64);
+        assert_eq!(ramfs.used_bytes, RAMFS_PAGE_SIZE as u64);
+        // Reescribir páginas ya reservadas no consume espacio
+        ramfs.write_file("/a", 0, &[3u8; RAMFS_PAGE_SIZE]).unwrap();
+        ramfs.write_file("/a", RAMFS_PAGE_SIZE as u64, &data[..RAMFS_PAGE_SIZE]).unwrap();
+        assert_eq!(ramfs.free_bytes(), 0);
+    }
+
+    #[test]
+    fn test_stats_and_timestamps() {
+        let mut ramfs = RamFsDriver::new(0, RAMFS_DEFAULT_SIZE_LIMIT);
+        let before = crate::hal::rtc::unix_time();
+        FileSystemDriver::open(&mut ramfs, "/log", true).unwrap();
+        FileSystemDriver::write_file(&mut ramfs, "/log", b"hola", 0).unwrap();
+        FileSystemDriver::write_file(&mut ramfs, "/log", b"!", 10).unwrap();
+        let mut buffer = [0u8; 16];
+        assert_eq!(FileSystemDriver::read_file(&mut ramfs, "/log", &mut buffer, 2), Ok(9));
+        assert!(FileSystemDriver::read_file(&mut ramfs, "/missing", &mut buffer, 0).is_err());
+        assert!(FileSystemDriver::write_file(&mut ramfs, "/", b"x", 0).is_err());
+
+        let stats = &ramfs.stats;
+        assert_eq!((stats.write_operations, stats.bytes_written, stats.write_errors), (2, 5, 1));
+        assert_eq!((stats.read_operations, stats.bytes_read, stats.read_errors), (1, 9, 1));
+
+        // Las marcas de tiempo salen del reloj del sistema
+        let info = FileSystemDriver::stat(&mut ramfs, "/log").unwrap();
+        assert!(info.created_time >= before && info.created_time > 0);
+        assert!(info.modified_time >= info.created_time);
+        assert_eq!(info.block_count, 1);
+        assert_eq!(FileSystemDriver::read_dir(&mut ramfs, "/").unwrap()[0].name(), "log");
+    }
+}
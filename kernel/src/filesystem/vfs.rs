@@ -6,15 +6,106 @@
 use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
 use core::ptr::NonNull;
 use core::mem;
+use alloc::{boxed::Box, string::{String, ToString}, vec, vec::Vec};
+
+use super::path::split_parent;
+use super::quota::{quota_blocks, QuotaTable};
+use crate::process::scheduler::{current_token, get_process_scheduler};
+use crate::security::access_control::{
+    get_access_control_manager, map_generic_file_access, Ace, Acl, SecurityDescriptor, Sid, DELETE, FILE_ADD_FILE,
+    FILE_ALL_ACCESS, FILE_APPEND_DATA, FILE_DELETE_CHILD, FILE_GENERIC_EXECUTE, FILE_GENERIC_READ,
+    FILE_GENERIC_WRITE, FILE_READ_DATA, FILE_WRITE_DATA, MAXIMUM_ALLOWED, SYSTEM_TOKEN_ID, WRITE_DAC, WRITE_OWNER,
+};
+use crate::security::audit::log_access_denied;
 
 /// Trait para drivers de sistemas de archivos
+///
+/// Las rutas son relativas al punto de montaje y empiezan por "/".
 pub trait FileSystemDriver {
+    /// Abrir un archivo, creándolo si no existe y `create` es verdadero
+    fn open(&mut self, file_path: &str, create: bool) -> Result<FileInfo, &'static str>;
     /// Leer archivo
-    fn read_file(&self, file_path: &str, buffer: &mut [u8], offset: u64) -> Result<usize, &'static str>;
+    fn read_file(&mut self, file_path: &str, buffer: &mut [u8], offset: u64) -> Result<usize, &'static str>;
     /// Escribir archivo
-    fn write_file(&self, file_path: &str, buffer: &[u8], offset: u64) -> Result<usize, &'static str>;
+    fn write_file(&mut self, file_path: &str, buffer: &[u8], offset: u64) -> Result<usize, &'static str>;
+    /// Listar un directorio
+    fn read_dir(&mut self, dir_path: &str) -> Result<Vec<FileInfo>, &'static str>;
+    /// Obtener información de un archivo o directorio
+    fn stat(&mut self, file_path: &str) -> Result<FileInfo, &'static str>;
+    /// Eliminar un archivo o un directorio vacío
+    fn unlink(&mut self, file_path: &str) -> Result<(), &'static str>;
+    /// Cambiar el tamaño de un archivo
+    fn truncate(&mut self, file_path: &str, size: u64) -> Result<(), &'static str>;
+    /// Renombrar o mover un archivo dentro del mismo sistema de archivos
+    fn rename(&mut self, old_path: &str, new_path: &str) -> Result<(), &'static str>;
+    /// Escribir los metadatos pendientes del driver
+    fn sync(&mut self) -> Result<(), &'static str> {
+        Ok(())
+    }
+    /// Cambiar los permisos de un archivo o directorio
+    fn set_permissions(&mut self, _file_path: &str, _permissions: FilePermissions) -> Result<(), &'static str> {
+        Err("Operación no soportada")
+    }
+    /// Cambiar el propietario y el grupo de un archivo o directorio
+    fn set_owner(&mut self, _file_path: &str, _owner_id: u32, _group_id: u32) -> Result<(), &'static str> {
+        Err("Operación no soportada")
+    }
 }
 
+/// ID de archivo del directorio raíz "/"
+pub const VFS_ROOT_FILE_ID: u32 = 1;
+
+/// Número máximo de enlaces simbólicos que se siguen al resolver una ruta
+pub const VFS_MAX_SYMLINK_DEPTH: usize = 8;
+
+/// Longitud máxima de una ruta
+pub const VFS_MAX_PATH_LENGTH: usize = 4096;
+
+/// Longitud máxima de un nombre de archivo
+pub const VFS_MAX_NAME_LENGTH: usize = 255;
+
+/// Bytes leídos del inicio de un dispositivo para detectar su sistema de archivos
+pub const VFS_PROBE_SIZE: usize = 4096;
+
+/// Desplazamiento del primer descriptor de volumen ISO 9660 (bloque 16 de 2048 bytes)
+pub const VFS_PROBE_ISO9660_OFFSET: u64 = 16 * 2048;
+
+/// Flag de apertura: crear el archivo si no existe
+pub const VFS_OPEN_CREATE: u32 = 0x40;
+
+/// Origen de `lseek`: desde el principio del archivo
+pub const VFS_SEEK_SET: u32 = 0;
+
+/// Origen de `lseek`: desde la posición actual
+pub const VFS_SEEK_CUR: u32 = 1;
+
+/// Origen de `lseek`: desde el final del archivo
+pub const VFS_SEEK_END: u32 = 2;
+
+/// Máscara de vigilancia: creación de archivos y directorios
+pub const VFS_NOTIFY_CREATE: u32 = 0x01;
+
+/// Máscara de vigilancia: eliminación de archivos y directorios
+pub const VFS_NOTIFY_DELETE: u32 = 0x02;
+
+/// Máscara de vigilancia: cambios de contenido o tamaño
+pub const VFS_NOTIFY_MODIFY: u32 = 0x04;
+
+/// Máscara de vigilancia: renombrados dentro del directorio vigilado
+pub const VFS_NOTIFY_RENAME: u32 = 0x08;
+
+/// Máscara de vigilancia: cambios de permisos o propietario
+pub const VFS_NOTIFY_ATTRIBUTES: u32 = 0x10;
+
+/// Máscara de vigilancia con todos los eventos
+pub const VFS_NOTIFY_ALL: u32 = 0x1F;
+
+/// Capacidad por defecto de la cola de eventos de una vigilancia
+pub const VFS_WATCH_QUEUE_CAPACITY: usize = 256;
+
+/// Número máximo de vigilancias activas
+pub const VFS_MAX_WATCHES: usize = 64;
+
 /// Estadísticas del VFS
 #[derive(Debug, Default)]
 pub struct VfsStats {
@@ -39,25 +130,142 @@ pub struct VfsDetailedStats {
     pub bytes_written: u64,
     pub read_errors: u64,
     pub write_errors: u64,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    pub cache_read_ahead_blocks: u64,
+    pub cache_evictions: u64,
+    pub cache_writebacks: u64,
+    pub cache_blocks: usize,
+    pub cache_dirty_blocks: usize,
+    pub cache_capacity: usize,
 }
 
 /// Tipos de sistemas de archivos soportados
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FileSystemType {
+    /// Sistema de archivos FAT12
+    FAT12,
+    /// Sistema de archivos FAT16
+    FAT16,
     /// Sistema de archivos FAT32
     FAT32,
+    /// Sistema de archivos exFAT
+    EXFAT,
     /// Sistema de archivos NTFS
     NTFS,
-    /// Sistema de archivos EXT4
+    /// Sistema de archivos ext2/ext3/ext4
     EXT4,
     /// Sistema de archivos BTRFS
     BTRFS,
     /// Sistema de archivos XFS
     XFS,
+    /// Sistema de archivos en memoria (ramfs/tmpfs)
+    RamFs,
+    /// Sistema de archivos ISO 9660 (CD/DVD)
+    ISO9660,
+    /// Sistema de archivos de procesos (/proc)
+    ProcFs,
+    /// Sistema de archivos de dispositivos (/dev)
+    DevFs,
     /// Sistema de archivos desconocido
     Unknown,
 }
 
+impl FileSystemType {
+    /// Detectar el tipo de sistema de archivos a partir del sector de arranque
+    ///
+    /// También reconoce el primer descriptor de volumen ISO 9660 (bloque 16
+    /// de 2048 bytes), ya que un CD no tiene sector de arranque, y la firma
+    /// del superbloque ext si el buffer cubre sus primeros 2 KiB.
+    pub fn detect(boot_sector: &[u8]) -> Self {
+        if boot_sector.len() >= 6 && &boot_sector[1..6] == super::iso9660::ISO9660_STANDARD_ID {
+            return FileSystemType::ISO9660;
+        }
+        if boot_sector.len() >= 1082 && u16::from_le_bytes([boot_sector[1080], boot_sector[1081]]) == super::ext4::EXT4_SUPER_MAGIC {
+            return FileSystemType::EXT4;
+        }
+        if boot_sector.len() < 512 || boot_sector[510] != 0x55 || boot_sector[511] != 0xAA {
+            return FileSystemType::Unknown;
+        }
+
+        match &boot_sector[3..11] {
+            b"EXFAT   " => return FileSystemType::EXFAT,
+            b"NTFS    " => return FileSystemType::NTFS,
+            _ => {}
+        }
+
+        // FAT: el tipo depende únicamente del número de clústeres
+        let bytes_per_sector = u16::from_le_bytes([boot_sector[11], boot_sector[12]]) as u32;
+        let sectors_per_cluster = boot_sector[13] as u32;
+        let reserved_sectors = u16::from_le_bytes([boot_sector[14], boot_sector[15]]) as u32;
+        let num_fats = boot_sector[16] as u32;
+        let root_entries = u16::from_le_bytes([boot_sector[17], boot_sector[18]]) as u32;
+        let total_sectors_16 = u16::from_le_bytes([boot_sector[19], boot_sector[20]]) as u32;
+        let sectors_per_fat_16 = u16::from_le_bytes([boot_sector[22], boot_sector[23]]) as u32;
+        let total_sectors_32 = u32::from_le_bytes([boot_sector[32], boot_sector[33], boot_sector[34], boot_sector[35]]);
+        let sectors_per_fat_32 = u32::from_le_bytes([boot_sector[36], boot_sector[37], boot_sector[38], boot_sector[39]]);
+
+        if !matches!(bytes_per_sector, 512 | 1024 | 2048 | 4096)
+            || !sectors_per_cluster.is_power_of_two()
+            || reserved_sectors == 0
+            || num_fats == 0
+        {
+            return FileSystemType::Unknown;
+        }
+
+        let total_sectors = if total_sectors_16 != 0 { total_sectors_16 } else { total_sectors_32 };
+        let sectors_per_fat = if sectors_per_fat_16 != 0 { sectors_per_fat_16 } else { sectors_per_fat_32 };
+        let root_dir_sectors = (root_entries * 32 + bytes_per_sector - 1) / bytes_per_sector;
+        let data_start = reserved_sectors + num_fats * sectors_per_fat + root_dir_sectors;
+        if sectors_per_fat == 0 || total_sectors <= data_start {
+            return FileSystemType::Unknown;
+        }
+
+        let cluster_count = (total_sectors - data_start) / sectors_per_cluster;
+        match super::fat32::FatType::from_cluster_count(cluster_count) {
+            super::fat32::FatType::Fat12 => FileSystemType::FAT12,
+            super::fat32::FatType::Fat16 => FileSystemType::FAT16,
+            super::fat32::FatType::Fat32 => FileSystemType::FAT32,
+        }
+    }
+
+    /// Verificar si el tipo corresponde a la familia FAT (driver `fat32`)
+    pub fn is_fat(&self) -> bool {
+        matches!(self, FileSystemType::FAT12 | FileSystemType::FAT16 | FileSystemType::FAT32)
+    }
+
+    /// Nombre del tipo como aparece en /proc/mounts
+    pub fn name(&self) -> &'static str {
+        match self {
+            FileSystemType::FAT12 | FileSystemType::FAT16 | FileSystemType::FAT32 => "vfat",
+            FileSystemType::EXFAT => "exfat",
+            FileSystemType::NTFS => "ntfs",
+            FileSystemType::EXT4 => "ext4",
+            FileSystemType::BTRFS => "btrfs",
+            FileSystemType::XFS => "xfs",
+            FileSystemType::RamFs => "ramfs",
+            FileSystemType::ISO9660 => "iso9660",
+            FileSystemType::ProcFs => "proc",
+            FileSystemType::DevFs => "devfs",
+            FileSystemType::Unknown => "unknown",
+        }
+    }
+}
+
+/// Sistemas de archivos con driver registrado en el VFS
+pub const SUPPORTED_FILESYSTEMS: [FileSystemType; 10] = [
+    FileSystemType::FAT12,
+    FileSystemType::FAT16,
+    FileSystemType::FAT32,
+    FileSystemType::EXFAT,
+    FileSystemType::NTFS,
+    FileSystemType::EXT4,
+    FileSystemType::RamFs,
+    FileSystemType::ISO9660,
+    FileSystemType::ProcFs,
+    FileSystemType::DevFs,
+];
+
 /// Estados de un sistema de archivos
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FileSystemState {
@@ -128,7 +336,7 @@ impl FilePermissions {
 }
 
 /// Estructura de información de archivo
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FileInfo {
     /// ID único del archivo
     pub file_id: u32,
@@ -188,6 +396,45 @@ impl FileInfo {
             inode: 0,
         }
     }
+
+    /// Nombre del archivo como texto
+    pub fn name(&self) -> &str {
+        let length = self.name.iter().position(|&byte| byte == 0).unwrap_or(self.name.len());
+        core::str::from_utf8(&self.name[..length]).unwrap_or("")
+    }
+}
+
+/// Entrada del árbol de nombres (dentry)
+///
+/// Cada nodo del VFS tiene un `FileInfo` y una `Dentry` en el mismo slot.
+#[derive(Debug)]
+pub struct Dentry {
+    /// ID del archivo al que da nombre
+    pub file_id: u32,
+    /// Nombre dentro del directorio padre ("/" para la raíz)
+    pub name: String,
+    /// Directorio padre (la raíz es su propio padre)
+    pub parent: u32,
+    /// IDs de los hijos, si es un directorio
+    pub children: Vec<u32>,
+    /// Destino, si es un enlace simbólico
+    pub symlink_target: Option<String>,
+    /// Descriptor de seguridad explícito; sin él se deriva de los permisos
+    pub security: Option<SecurityDescriptor>,
+}
+
+/// Resultado de resolver una ruta
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedPath {
+    /// Ruta absoluta canónica (sin ".", ".." ni enlaces simbólicos)
+    pub path: String,
+    /// Nodo del árbol, o `None` si la ruta sigue dentro de un sistema montado
+    /// y la resuelve su driver
+    pub file_id: Option<u32>,
+    /// Punto de montaje con el prefijo más largo que contiene la ruta
+    pub mount_id: Option<u32>,
+    /// Ruta dentro del punto de montaje (empieza por "/")
+    pub mount_relative_path: String,
 }
 
 /// Estructura de descriptor de archivo
@@ -201,10 +448,21 @@ pub struct FileDescriptor {
     pub position: u64,
     /// Modo de apertura
     pub mode: u32,
+    /// Derechos concedidos al abrir (máscara de acceso NT)
+    pub granted_access: u32,
     /// Flags del descriptor
     pub flags: u32,
     /// Referencia al archivo
     pub file_info: Option<NonNull<FileInfo>>,
+    /// Ruta canónica con la que se abrió
+    pub path: String,
+    /// Punto de montaje cuyo driver atiende las lecturas y escrituras
+    /// (`None` para los nodos del árbol)
+    pub mount_id: Option<u32>,
+    /// Listado de un directorio leído con `readdir` (se toma en la primera llamada)
+    pub directory_entries: Option<Vec<FileInfo>>,
+    /// Vigilancia creada por `notify_change_directory` (se cancela al cerrar)
+    pub notify_watch: Option<u32>,
 }
 
 impl FileDescriptor {
@@ -215,9 +473,178 @@ impl FileDescriptor {
             file_id,
             position: 0,
             mode,
+            granted_access: 0,
             flags,
             file_info: None,
+            path: String::new(),
+            mount_id: None,
+            directory_entries: None,
+            notify_watch: None,
+        }
+    }
+}
+
+/// Tipos de bloqueo de un rango de bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockType {
+    /// Compartido: compatible con otros bloqueos compartidos
+    Shared,
+    /// Exclusivo: incompatible con cualquier otro bloqueo
+    Exclusive,
+}
+
+/// Bloqueo de un rango de bytes de un archivo
+///
+/// Los bloqueos consultivos solo se tienen en cuenta al pedir otros bloqueos
+/// (como `fcntl`). Los obligatorios además impiden a los demás descriptores
+/// escribir en el rango y, si son exclusivos, leerlo (como `NtLockFile`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileLock {
+    /// Descriptor que posee el bloqueo
+    pub fd_id: u32,
+    /// Ruta canónica del archivo
+    pub path: String,
+    /// Primer byte del rango
+    pub start: u64,
+    /// Longitud del rango (0 = hasta el final, aunque el archivo crezca)
+    pub length: u64,
+    /// Tipo de bloqueo
+    pub lock_type: LockType,
+    /// Bloqueo obligatorio
+    pub mandatory: bool,
+}
+
+impl FileLock {
+    /// Final (excluido) del rango bloqueado
+    pub fn end(&self) -> u64 {
+        if self.length == 0 {
+            u64::MAX
+        } else {
+            self.start.saturating_add(self.length)
+        }
+    }
+
+    /// Verificar si el rango se solapa con `[start, end)`
+    pub fn overlaps(&self, start: u64, end: u64) -> bool {
+        self.start < end && start < self.end()
+    }
+}
+
+/// Descriptor de seguridad explícito de un archivo de un sistema montado
+///
+/// Los nodos del árbol guardan su descriptor en su `Dentry`; los archivos de
+/// un volumen, que no tienen dentry, en su `MountPoint`. Los nodos sin
+/// descriptor explícito usan uno derivado de su propietario, su grupo y sus
+/// `FilePermissions`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeSecurity {
+    /// Ruta del archivo dentro del punto de montaje
+    pub path: String,
+    /// Descriptor del nodo
+    pub descriptor: SecurityDescriptor,
+}
+
+/// Tipos de cambio que se notifican a las vigilancias de directorios
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchEventKind {
+    /// Archivo o directorio creado
+    Created,
+    /// Archivo o directorio eliminado
+    Deleted,
+    /// Contenido o tamaño modificado
+    Modified,
+    /// Renombrado dentro de la vigilancia (`old_path` tiene la ruta anterior)
+    Renamed,
+    /// Permisos o propietario modificados
+    AttributesChanged,
+}
+
+impl WatchEventKind {
+    /// Bit de la máscara de vigilancia que selecciona este tipo de evento
+    pub fn mask(&self) -> u32 {
+        match self {
+            WatchEventKind::Created => VFS_NOTIFY_CREATE,
+            WatchEventKind::Deleted => VFS_NOTIFY_DELETE,
+            WatchEventKind::Modified => VFS_NOTIFY_MODIFY,
+            WatchEventKind::Renamed => VFS_NOTIFY_RENAME,
+            WatchEventKind::AttributesChanged => VFS_NOTIFY_ATTRIBUTES,
+        }
+    }
+}
+
+/// Cambio observado en un directorio vigilado
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchEvent {
+    /// Tipo de cambio
+    pub kind: WatchEventKind,
+    /// Ruta relativa al directorio vigilado, sin "/" inicial
+    pub path: String,
+    /// Ruta relativa anterior, solo en los renombrados
+    pub old_path: Option<String>,
+}
+
+/// Eventos leídos de una vigilancia
+#[derive(Debug, Clone, Default)]
+pub struct WatchEvents {
+    /// Eventos en el orden en que ocurrieron
+    pub events: Vec<WatchEvent>,
+    /// Se perdieron eventos por tener la cola llena; hay que volver a listar el directorio
+    pub overflowed: bool,
+}
+
+/// Vigilancia de cambios sobre un directorio (como `ReadDirectoryChangesW` o inotify)
+///
+/// Los eventos se encolan hasta `capacity`; con la cola llena se descartan
+/// los nuevos y se marca `overflowed` hasta la siguiente lectura.
+#[derive(Debug)]
+pub struct DirectoryWatch {
+    /// ID único de la vigilancia
+    pub watch_id: u32,
+    /// Ruta canónica del directorio vigilado
+    pub path: String,
+    /// Incluir los cambios de los subdirectorios
+    pub recursive: bool,
+    /// Máscara de eventos (`VFS_NOTIFY_*`)
+    pub mask: u32,
+    /// Número máximo de eventos encolados
+    pub capacity: usize,
+    /// Eventos pendientes de leer
+    pub events: Vec<WatchEvent>,
+    /// Se descartaron eventos desde la última lectura
+    pub overflowed: bool,
+}
+
+impl DirectoryWatch {
+    /// Ruta relativa de `path` (canónica) si sus cambios son visibles para esta vigilancia
+    ///
+    /// El propio directorio vigilado no se incluye.
+    pub fn relative_path<'a>(&self, path: &'a str) -> Option<&'a str> {
+        let rest = if self.path == "/" {
+            path.strip_prefix('/')?
+        } else {
+            path.strip_prefix(self.path.as_str())?.strip_prefix('/')?
+        };
+        if rest.is_empty() || (!self.recursive && rest.contains('/')) {
+            return None;
+        }
+        Some(rest)
+    }
+
+    /// Encolar un evento si la máscara lo selecciona
+    ///
+    /// Las modificaciones repetidas de la misma ruta se agrupan en un evento.
+    fn push(&mut self, event: WatchEvent) {
+        if self.mask & event.kind.mask() == 0 {
+            return;
+        }
+        if event.kind == WatchEventKind::Modified && self.events.last() == Some(&event) {
+            return;
         }
+        if self.events.len() >= self.capacity {
+            self.overflowed = true;
+            return;
+        }
+        self.events.push(event);
     }
 }
 
@@ -240,6 +667,10 @@ pub struct MountPoint {
     pub parent_mount: Option<u32>,
     /// Punto de montaje raíz
     pub root_mount: Option<u32>,
+    /// Nodos del árbol ocultos por entradas del volumen con el mismo nombre
+    pub hidden_nodes: Vec<u32>,
+    /// Descriptores de seguridad explícitos de los archivos del volumen
+    pub security: Vec<NodeSecurity>,
 }
 
 impl MountPoint {
@@ -259,6 +690,27 @@ impl MountPoint {
             mount_options: [0u8; 256],
             parent_mount: None,
             root_mount: None,
+            hidden_nodes: Vec::new(),
+            security: Vec::new(),
+        }
+    }
+
+    /// Ruta de montaje como texto
+    pub fn path(&self) -> &str {
+        let length = self.mount_path.iter().position(|&byte| byte == 0).unwrap_or(self.mount_path.len());
+        core::str::from_utf8(&self.mount_path[..length]).unwrap_or("")
+    }
+
+    /// Ruta relativa a este punto de montaje, si `path` (canónica) está dentro de él
+    pub fn relative_path<'a>(&self, path: &'a str) -> Option<&'a str> {
+        let mount_path = self.path();
+        if mount_path == "/" {
+            return Some(path);
+        }
+        match path.strip_prefix(mount_path) {
+            Some("") => Some("/"),
+            Some(rest) if rest.starts_with('/') => Some(rest),
+            _ => None,
         }
     }
 }
@@ -273,10 +725,14 @@ pub struct VirtualFileSystem {
     pub mount_counter: AtomicU32,
     /// Lista de archivos
     pub files: [Option<FileInfo>; 1024],
+    /// Árbol de nombres, en paralelo a `files`
+    pub dentries: [Option<Dentry>; 1024],
     /// Lista de descriptores de archivo
     pub file_descriptors: [Option<FileDescriptor>; 1024],
     /// Lista de puntos de montaje
     pub mount_points: [Option<MountPoint>; 64],
+    /// Drivers de los puntos de montaje, en paralelo a `mount_points`
+    pub drivers: [Option<Box<dyn FileSystemDriver>>; 64],
     /// Número de archivos
     pub file_count: AtomicUsize,
     /// Número de descriptores
@@ -287,40 +743,134 @@ pub struct VirtualFileSystem {
     pub root_mount: Option<u32>,
     /// Estadísticas del VFS
     pub stats: VfsStats,
+    /// Bloqueos de rangos de bytes activos
+    pub locks: Vec<FileLock>,
+    /// Contador de vigilancias de directorios
+    pub watch_counter: AtomicU32,
+    /// Vigilancias de directorios activas
+    pub watches: Vec<DirectoryWatch>,
+    /// Cuotas de disco por usuario y grupo
+    pub quotas: QuotaTable,
+    /// UID propietario de los archivos que se crean
+    pub current_uid: u32,
+    /// GID propietario de los archivos que se crean
+    pub current_gid: u32,
+    /// Contenido de los archivos regulares del árbol, en paralelo a `files`
+    pub node_data: [Vec<u8>; 1024],
 }
 
 impl VirtualFileSystem {
     /// Crear un nuevo sistema de archivos virtual
+    ///
+    /// El árbol de nombres empieza con el directorio raíz "/".
     pub fn new() -> Self {
-        Self {
-            file_counter: AtomicU32::new(1),
+        let mut vfs = Self {
+            file_counter: AtomicU32::new(VFS_ROOT_FILE_ID + 1),
             fd_counter: AtomicU32::new(1),
             mount_counter: AtomicU32::new(1),
             files: [(); 1024].map(|_| None),
+            dentries: [(); 1024].map(|_| None),
             file_descriptors: [(); 1024].map(|_| None),
             mount_points: [(); 64].map(|_| None),
-            file_count: AtomicUsize::new(0),
+            drivers: [(); 64].map(|_| None),
+            file_count: AtomicUsize::new(1),
             fd_count: AtomicUsize::new(0),
             mount_count: AtomicUsize::new(0),
             root_mount: None,
             stats: VfsStats::default(),
-        }
+            locks: Vec::new(),
+            watch_counter: AtomicU32::new(1),
+            watches: Vec::new(),
+            quotas: QuotaTable::new(),
+            current_uid: 0,
+            current_gid: 0,
+            node_data: [(); 1024].map(|_| Vec::new()),
+        };
+        
+        vfs.files[0] = Some(FileInfo::new(VFS_ROOT_FILE_ID, "/", NodeType::Directory));
+        vfs.dentries[0] = Some(Dentry {
+            file_id: VFS_ROOT_FILE_ID,
+            name: String::from("/"),
+            parent: VFS_ROOT_FILE_ID,
+            children: Vec::new(),
+            symlink_target: None,
+            security: None,
+        });
+        vfs
     }
 
     /// Montar un sistema de archivos
+    ///
+    /// El punto de montaje debe ser un directorio del árbol. Las rutas bajo él
+    /// se dirigen a este montaje mientras no haya otro con un prefijo más largo.
+    /// Para FAT, exFAT, NTFS e ISO 9660 se inicializa su driver sobre `block_device`; un
+    /// RamFs se crea vacío con el límite por defecto. Los demás tipos se
+    /// montan sin driver.
     pub fn mount(&mut self, mount_path: &str, fs_type: FileSystemType, block_device: u32) -> Result<u32, &'static str> {
+        let mount_path = self.mount_target(mount_path)?;
+        let driver = create_filesystem_driver(fs_type, block_device)?;
+        self.attach_mount(&mount_path, fs_type, block_device, driver)
+    }
+
+    /// Montar un sistema de archivos con un driver ya inicializado
+    pub fn mount_with_driver(&mut self, mount_path: &str, fs_type: FileSystemType, block_device: u32, driver: Box<dyn FileSystemDriver>) -> Result<u32, &'static str> {
+        let mount_path = self.mount_target(mount_path)?;
+        self.attach_mount(&mount_path, fs_type, block_device, Some(driver))
+    }
+
+    /// Validar un punto de montaje y devolver su ruta canónica
+    fn mount_target(&self, mount_path: &str) -> Result<String, &'static str> {
+        let resolved = self.resolve_path(mount_path, true)?;
+        match resolved.file_id {
+            Some(file_id) if self.is_directory(file_id) => {}
+            _ => return Err("El punto de montaje no es un directorio"),
+        }
+        if resolved.path.len() > 255 {
+            return Err("Ruta de montaje demasiado larga");
+        }
+        if self.mount_points.iter().flatten().any(|mount| mount.path() == resolved.path) {
+            return Err("Ya hay un sistema montado en esa ruta");
+        }
+        Ok(resolved.path)
+    }
+
+    /// Registrar un punto de montaje ya validado junto con su driver
+    ///
+    /// Los hijos del directorio de montaje que coinciden con una entrada del
+    /// volumen quedan ocultos mientras dure el montaje, salvo los que llevan a
+    /// otro punto de montaje.
+    fn attach_mount(&mut self, mount_path: &str, fs_type: FileSystemType, block_device: u32, mut driver: Option<Box<dyn FileSystemDriver>>) -> Result<u32, &'static str> {
         let mount_id = self.mount_counter.fetch_add(1, Ordering::SeqCst);
         let mut mount_point = MountPoint::new(mount_id, mount_path, fs_type, block_device);
+        mount_point.parent_mount = self.find_mount(mount_path).map(|(parent, _)| parent);
+        mount_point.root_mount = if mount_path == "/" { Some(mount_id) } else { self.root_mount };
+        
+        let children = self
+            .lookup_path(mount_path)
+            .and_then(|directory| self.dentry(directory))
+            .map(|dentry| dentry.children.clone())
+            .unwrap_or_default();
+        if let Some(ref mut fs_driver) = driver {
+            for child in children {
+                let name = match self.dentry(child) {
+                    Some(dentry) => dentry.name.clone(),
+                    None => continue,
+                };
+                if !self.leads_to_mount(&join_path(mount_path, &name)) && fs_driver.stat(&join_path("/", &name)).is_ok() {
+                    mount_point.hidden_nodes.push(child);
+                }
+            }
+        }
         
         // Buscar un slot libre
         for i in 0..64 {
             if self.mount_points[i].is_none() {
                 mount_point.state = FileSystemState::Mounted;
                 self.mount_points[i] = Some(mount_point);
+                self.drivers[i] = driver;
                 self.mount_count.fetch_add(1, Ordering::SeqCst);
                 
-                // Si es el primer montaje, establecer como raíz
-                if self.root_mount.is_none() {
+                if mount_path == "/" {
                     self.root_mount = Some(mount_id);
                 }
                 
@@ -331,13 +881,44 @@ impl VirtualFileSystem {
         Err("No hay slots libres para montaje")
     }
 
+    /// Montar un dispositivo detectando el sistema de archivos por su sector de arranque
+    pub fn mount_detected(&mut self, mount_path: &str, boot_sector: &[u8], block_device: u32) -> Result<u32, &'static str> {
+        let fs_type = FileSystemType::detect(boot_sector);
+        if !SUPPORTED_FILESYSTEMS.contains(&fs_type) {
+            return Err("Sistema de archivos no soportado");
+        }
+        self.mount(mount_path, fs_type, block_device)
+    }
+
+    /// Montar un dispositivo de bloque (disco o partición) detectando su sistema de archivos
+    pub fn mount_device(&mut self, mount_path: &str, block_device: u32) -> Result<u32, &'static str> {
+        let fs_type = probe_filesystem(block_device)?;
+        if !SUPPORTED_FILESYSTEMS.contains(&fs_type) {
+            return Err("Sistema de archivos no soportado");
+        }
+        self.mount(mount_path, fs_type, block_device)
+    }
+
     /// Desmontar un sistema de archivos
+    ///
+    /// Falla si hay archivos abiertos en él o sistemas montados por debajo.
     pub fn unmount(&mut self, mount_id: u32) -> Result<(), &'static str> {
+        let busy = self.file_descriptors.iter().flatten().any(|fd| fd.mount_id == Some(mount_id))
+            || self.mount_points.iter().flatten().any(|mount| mount.parent_mount == Some(mount_id));
+        if busy {
+            return Err("Punto de montaje ocupado");
+        }
+        
         for i in 0..64 {
             if let Some(ref mut mount_point) = self.mount_points[i] {
                 if mount_point.mount_id == mount_id {
+                    // Llevar al dispositivo los bloques pendientes de la caché
+                    if !crate::drivers::storage::sync_storage(Some(mount_point.block_device)) {
+                        return Err("Error al sincronizar el dispositivo");
+                    }
                     mount_point.state = FileSystemState::Unmounted;
                     self.mount_points[i] = None;
+                    self.drivers[i] = None;
                     self.mount_count.fetch_sub(1, Ordering::SeqCst);
                     
                     // Si era el punto de montaje raíz, limpiarlo
@@ -354,41 +935,385 @@ impl VirtualFileSystem {
     }
 
     /// Crear un archivo
+    ///
+    /// `name` es una ruta; las rutas relativas parten de la raíz.
     pub fn create_file(&mut self, name: &str, node_type: NodeType) -> Result<u32, &'static str> {
-        let file_id = self.file_counter.fetch_add(1, Ordering::SeqCst);
-        let file_info = FileInfo::new(file_id, name, node_type);
+        self.create_node(name, node_type)
+    }
+
+    /// Crear un nodo en el árbol de nombres
+    pub fn create_node(&mut self, path: &str, node_type: NodeType) -> Result<u32, &'static str> {
+        let (parent_path, name) = split_parent(path)?;
+        if name.len() > VFS_MAX_NAME_LENGTH || name.contains('\0') {
+            return Err("Nombre de archivo inválido");
+        }
+        
+        let parent = self
+            .resolve_path(parent_path, true)?
+            .file_id
+            .ok_or("El directorio pertenece a un sistema montado")?;
+        if !self.is_directory(parent) {
+            return Err("No es un directorio");
+        }
+        if self.child_by_name(parent, name).is_some() {
+            return Err("El archivo ya existe");
+        }
+        // Un nodo del árbol no puede ocultar una entrada del volumen montado
+        let canonical = join_path(&self.node_path(parent).ok_or("Archivo no encontrado")?, name);
+        if let Some((mount_id, relative)) = self.find_mount(&canonical) {
+            if self.get_filesystem_driver(mount_id).map_or(false, |fs_driver| fs_driver.stat(&relative).is_ok()) {
+                return Err("El archivo ya existe");
+            }
+        }
+        self.quotas.check(self.current_uid, self.current_gid, 0, 1)?;
         
         // Buscar un slot libre
-        for i in 0..1024 {
-            if self.files[i].is_none() {
-                self.files[i] = Some(file_info);
-                self.file_count.fetch_add(1, Ordering::SeqCst);
-                return Ok(file_id);
+        let slot = self.files.iter().position(|file| file.is_none()).ok_or("No hay slots libres para archivos")?;
+        let file_id = self.file_counter.fetch_add(1, Ordering::SeqCst);
+        let mut file_info = FileInfo::new(file_id, name, node_type);
+        file_info.owner_id = self.current_uid;
+        file_info.group_id = self.current_gid;
+        self.files[slot] = Some(file_info);
+        self.dentries[slot] = Some(Dentry {
+            file_id,
+            name: String::from(name),
+            parent,
+            children: Vec::new(),
+            symlink_target: None,
+            security: None,
+        });
+        if let Some(dentry) = self.dentry_mut(parent) {
+            dentry.children.push(file_id);
+        }
+        self.file_count.fetch_add(1, Ordering::SeqCst);
+        self.quotas.charge(self.current_uid, self.current_gid, 0, 1);
+        if let Some(path) = self.node_path(file_id) {
+            self.inherit_security(&path, node_type == NodeType::Directory);
+            self.notify(WatchEventKind::Created, &path);
+        }
+        Ok(file_id)
+    }
+
+    /// Crear un enlace simbólico en `path` que apunta a `target`
+    pub fn create_symlink(&mut self, path: &str, target: &str) -> Result<u32, &'static str> {
+        if target.is_empty() || target.len() > VFS_MAX_PATH_LENGTH {
+            return Err("Destino de enlace no válido");
+        }
+        let file_id = self.create_node(path, NodeType::Symlink)?;
+        if let Some(dentry) = self.dentry_mut(file_id) {
+            dentry.symlink_target = Some(String::from(target));
+        }
+        if let Some(slot) = self.file_slot(file_id) {
+            if let Some(ref mut file) = self.files[slot] {
+                file.size = target.len() as u64;
+            }
+        }
+        Ok(file_id)
+    }
+
+    /// Leer el destino de un enlace simbólico
+    pub fn read_link(&self, path: &str) -> Result<String, &'static str> {
+        let file_id = self.resolve_path(path, false)?.file_id.ok_or("No es un enlace simbólico")?;
+        self.dentry(file_id)
+            .and_then(|dentry| dentry.symlink_target.clone())
+            .ok_or("No es un enlace simbólico")
+    }
+
+    /// Eliminar un nodo del árbol (sin seguir el enlace final)
+    ///
+    /// No se eliminan directorios con contenido, puntos de montaje ni archivos abiertos.
+    pub fn remove_node(&mut self, path: &str) -> Result<(), &'static str> {
+        let resolved = self.resolve_path(path, false)?;
+        let file_id = resolved.file_id.ok_or("El archivo pertenece a un sistema montado")?;
+        if file_id == VFS_ROOT_FILE_ID {
+            return Err("No se puede eliminar la raíz");
+        }
+        if self.dentry(file_id).map_or(false, |dentry| !dentry.children.is_empty()) {
+            return Err("El directorio no está vacío");
+        }
+        if self.mount_points.iter().flatten().any(|mount| mount.path() == resolved.path) {
+            return Err("Punto de montaje ocupado");
+        }
+        if self.file_descriptors.iter().flatten().any(|fd| fd.file_id == file_id) {
+            return Err("Archivo en uso");
+        }
+        
+        let slot = self.file_slot(file_id).ok_or("Archivo no encontrado")?;
+        let parent = self.dentries[slot].as_ref().map_or(VFS_ROOT_FILE_ID, |dentry| dentry.parent);
+        if let Some(dentry) = self.dentry_mut(parent) {
+            dentry.children.retain(|&child| child != file_id);
+        }
+        if let Some(file) = self.files[slot].take() {
+            self.quotas.charge(file.owner_id, file.group_id, -(file_quota_blocks(&file) as i64), -1);
+        }
+        self.node_data[slot] = Vec::new();
+        self.dentries[slot] = None;
+        self.file_count.fetch_sub(1, Ordering::SeqCst);
+        self.notify(WatchEventKind::Deleted, &resolved.path);
+        Ok(())
+    }
+
+    /// Resolver una ruta recorriendo el árbol de nombres
+    ///
+    /// Las rutas relativas parten de la raíz. Se siguen los enlaces simbólicos
+    /// intermedios (y el final si `follow_final`) hasta `VFS_MAX_SYMLINK_DEPTH`.
+    /// Si un nombre no está en el árbol pero el directorio pertenece a un
+    /// sistema montado, el resto de la ruta se normaliza y queda para su driver.
+    /// Los nodos ocultos por un volumen montado no cuentan como hijos.
+    pub fn resolve_path(&self, path: &str, follow_final: bool) -> Result<ResolvedPath, &'static str> {
+        if path.len() > VFS_MAX_PATH_LENGTH {
+            return Err("Ruta demasiado larga");
+        }
+        
+        // Componentes pendientes en orden inverso, para insertar los destinos de los enlaces
+        let mut pending: Vec<String> = path_components(path).rev().map(String::from).collect();
+        let mut stack = vec![VFS_ROOT_FILE_ID];
+        let mut external: Vec<String> = Vec::new();
+        let mut links = 0;
+        
+        while let Some(component) = pending.pop() {
+            let current = *stack.last().unwrap_or(&VFS_ROOT_FILE_ID);
+            if component == "." {
+                continue;
+            }
+            if component == ".." {
+                if external.pop().is_none() {
+                    if !self.is_directory(current) {
+                        return Err("No es un directorio");
+                    }
+                    if stack.len() > 1 {
+                        stack.pop();
+                    }
+                }
+                continue;
             }
+            if !external.is_empty() {
+                external.push(component);
+                continue;
+            }
+            if !self.is_directory(current) {
+                return Err("No es un directorio");
+            }
+            
+            match self.child_by_name(current, &component) {
+                Some(child) => {
+                    let target = self.dentry(child).and_then(|dentry| dentry.symlink_target.as_ref());
+                    match target {
+                        Some(target) if follow_final || !pending.is_empty() => {
+                            links += 1;
+                            if links > VFS_MAX_SYMLINK_DEPTH {
+                                return Err("Demasiados niveles de enlaces simbólicos");
+                            }
+                            if target.starts_with('/') {
+                                stack.truncate(1);
+                            }
+                            pending.extend(path_components(target).rev().map(String::from));
+                        }
+                        _ => stack.push(child),
+                    }
+                }
+                None => {
+                    if self.find_mount(&self.stack_path(&stack)).is_none() {
+                        return Err("Archivo no encontrado");
+                    }
+                    external.push(component);
+                }
+            }
+        }
+        
+        let mut canonical = self.stack_path(&stack);
+        for component in &external {
+            if !canonical.ends_with('/') {
+                canonical.push('/');
+            }
+            canonical.push_str(component);
+        }
+        
+        let (mount_id, mount_relative_path) = match self.find_mount(&canonical) {
+            Some((mount_id, relative)) => (Some(mount_id), relative),
+            None => (None, String::new()),
+        };
+        Ok(ResolvedPath {
+            file_id: if external.is_empty() { stack.last().copied() } else { None },
+            path: canonical,
+            mount_id,
+            mount_relative_path,
+        })
+    }
+
+    /// Buscar el punto de montaje con el prefijo más largo de una ruta canónica
+    ///
+    /// Devuelve su ID y la ruta relativa a él.
+    pub fn find_mount(&self, path: &str) -> Option<(u32, String)> {
+        self.mount_points
+            .iter()
+            .flatten()
+            .filter(|mount| mount.state == FileSystemState::Mounted)
+            .filter_map(|mount| mount.relative_path(path).map(|relative| (mount, relative)))
+            .max_by_key(|(mount, _)| mount.path().len())
+            .map(|(mount, relative)| (mount.mount_id, String::from(relative)))
+    }
+
+    /// Buscar un nodo por ruta (siguiendo enlaces simbólicos)
+    pub fn lookup_path(&self, path: &str) -> Option<u32> {
+        self.resolve_path(path, true).ok().and_then(|resolved| resolved.file_id)
+    }
+
+    /// Ruta canónica de un nodo del árbol
+    pub fn node_path(&self, file_id: u32) -> Option<String> {
+        let mut names = Vec::new();
+        let mut current = file_id;
+        while current != VFS_ROOT_FILE_ID {
+            let dentry = self.dentry(current)?;
+            names.push(dentry.name.as_str());
+            if names.len() > 1024 {
+                return None;
+            }
+            current = dentry.parent;
         }
         
-        Err("No hay slots libres para archivos")
+        let mut path = String::new();
+        for name in names.iter().rev() {
+            path.push('/');
+            path.push_str(name);
+        }
+        if path.is_empty() {
+            path.push('/');
+        }
+        Some(path)
+    }
+
+    /// Listar los nombres de un directorio del árbol
+    pub fn list_directory(&self, path: &str) -> Result<Vec<(String, u32)>, &'static str> {
+        let file_id = self.resolve_path(path, true)?.file_id.ok_or("El directorio pertenece a un sistema montado")?;
+        if !self.is_directory(file_id) {
+            return Err("No es un directorio");
+        }
+        let dentry = self.dentry(file_id).ok_or("Archivo no encontrado")?;
+        Ok(dentry
+            .children
+            .iter()
+            .filter(|&&child| !self.is_hidden(child))
+            .filter_map(|&child| self.dentry(child).map(|entry| (entry.name.clone(), child)))
+            .collect())
+    }
+
+    /// Slot de `files`/`dentries` que ocupa un archivo
+    fn file_slot(&self, file_id: u32) -> Option<usize> {
+        self.files.iter().position(|file| file.as_ref().map_or(false, |file| file.file_id == file_id))
+    }
+
+    fn dentry(&self, file_id: u32) -> Option<&Dentry> {
+        self.file_slot(file_id).and_then(|slot| self.dentries[slot].as_ref())
+    }
+
+    fn dentry_mut(&mut self, file_id: u32) -> Option<&mut Dentry> {
+        let slot = self.file_slot(file_id)?;
+        self.dentries[slot].as_mut()
+    }
+
+    fn is_directory(&self, file_id: u32) -> bool {
+        self.get_file_info(file_id).map_or(false, |file| file.node_type == NodeType::Directory)
+    }
+
+    /// Buscar un hijo visible por nombre dentro de un directorio
+    fn child_by_name(&self, directory: u32, name: &str) -> Option<u32> {
+        self.dentry(directory)?
+            .children
+            .iter()
+            .copied()
+            .filter(|&child| !self.is_hidden(child))
+            .find(|&child| self.dentry(child).map_or(false, |dentry| dentry.name == name))
+    }
+
+    /// Verificar si un nodo del árbol está oculto por un volumen montado
+    fn is_hidden(&self, file_id: u32) -> bool {
+        self.mount_points.iter().flatten().any(|mount| mount.hidden_nodes.contains(&file_id))
+    }
+
+    /// Ruta canónica de una pila de nodos que empieza en la raíz
+    fn stack_path(&self, stack: &[u32]) -> String {
+        let mut path = String::new();
+        for &file_id in stack.iter().skip(1) {
+            path.push('/');
+            if let Some(dentry) = self.dentry(file_id) {
+                path.push_str(&dentry.name);
+            }
+        }
+        if path.is_empty() {
+            path.push('/');
+        }
+        path
     }
 
     /// Abrir un archivo
     pub fn open_file(&mut self, file_id: u32, mode: u32, flags: u32) -> Result<u32, &'static str> {
-        // Verificar que el archivo existe
-        let mut file_exists = false;
-        for i in 0..1024 {
-            if let Some(ref file) = self.files[i] {
-                if file.file_id == file_id {
-                    file_exists = true;
-                    break;
+        let path = self.node_path(file_id).ok_or("Archivo no encontrado")?;
+        self.open_path(&path, mode, flags)
+    }
+
+    /// Abrir un archivo por ruta
+    ///
+    /// Con `VFS_OPEN_CREATE` se crea el archivo si no existe. Si la ruta no está
+    /// en el árbol, la abre el driver del punto de montaje que la contiene, y
+    /// el descriptor queda asociado a ese montaje para sus lecturas y escrituras.
+    ///
+    /// `mode` es la máscara de acceso NT que se pide (0 equivale a
+    /// `MAXIMUM_ALLOWED`) y se evalúa con el token actual frente al descriptor
+    /// de seguridad del archivo. Crear exige `FILE_ADD_FILE` en el directorio,
+    /// y quien crea recibe el acceso que pide.
+    pub fn open_path(&mut self, path: &str, mode: u32, flags: u32) -> Result<u32, &'static str> {
+        let create = flags & VFS_OPEN_CREATE != 0;
+        let desired_access = if mode == 0 { MAXIMUM_ALLOWED } else { mode };
+        let mut created = false;
+        let resolved = match self.resolve_path(path, true) {
+            Err("Archivo no encontrado") if create => {
+                self.check_create_access(path)?;
+                self.create_node(path, NodeType::File)?;
+                created = true;
+                self.resolve_path(path, true)?
+            }
+            result => result?,
+        };
+        
+        let mount_id = if resolved.file_id.is_none() { resolved.mount_id } else { None };
+        if let Some(mount_id) = mount_id {
+            let (uid, gid) = (self.current_uid, self.current_gid);
+            let relative = resolved.mount_relative_path.as_str();
+            let existed = !create
+                || self.get_filesystem_driver(mount_id).map_or(true, |fs_driver| fs_driver.stat(relative).is_ok());
+            if !existed {
+                self.check_create_access(&resolved.path)?;
+                self.quotas.check(uid, gid, 0, 1)?;
+            }
+            if let Some(fs_driver) = self.get_filesystem_driver(mount_id) {
+                let mut file_info = fs_driver.open(relative, create)?;
+                if !existed {
+                    // Los drivers sin propietarios dejan el archivo al superusuario
+                    if fs_driver.set_owner(relative, uid, gid).is_ok() {
+                        file_info = fs_driver.stat(relative).unwrap_or(file_info);
+                    }
+                    self.quotas.charge(file_info.owner_id, file_info.group_id, file_quota_blocks(&file_info) as i64, 1);
+                    self.inherit_security(&resolved.path, false);
+                    self.notify(WatchEventKind::Created, &resolved.path);
+                    created = true;
                 }
             }
         }
         
-        if !file_exists {
-            return Err("Archivo no encontrado");
-        }
+        let granted_access = if !created {
+            self.check_access(&resolved.path, desired_access)?
+        } else if desired_access & MAXIMUM_ALLOWED != 0 {
+            FILE_ALL_ACCESS
+        } else {
+            map_generic_file_access(desired_access)
+        };
         
         let fd_id = self.fd_counter.fetch_add(1, Ordering::SeqCst);
-        let file_descriptor = FileDescriptor::new(fd_id, file_id, mode, flags);
+        let mut file_descriptor = FileDescriptor::new(fd_id, resolved.file_id.unwrap_or(0), mode, flags);
+        file_descriptor.granted_access = granted_access;
+        file_descriptor.path = resolved.path;
+        file_descriptor.mount_id = mount_id;
         
         // Buscar un slot libre
         for i in 0..1024 {
@@ -403,12 +1328,18 @@ impl VirtualFileSystem {
     }
 
     /// Cerrar un archivo
+    ///
+    /// Se liberan los bloqueos del descriptor.
     pub fn close_file(&mut self, fd_id: u32) -> Result<(), &'static str> {
         for i in 0..1024 {
             if let Some(ref fd) = self.file_descriptors[i] {
                 if fd.fd_id == fd_id {
+                    if let Some(watch_id) = fd.notify_watch {
+                        let _ = self.unwatch(watch_id);
+                    }
                     self.file_descriptors[i] = None;
                     self.fd_count.fetch_sub(1, Ordering::SeqCst);
+                    self.locks.retain(|lock| lock.fd_id != fd_id);
                     return Ok(());
                 }
             }
@@ -417,108 +1348,939 @@ impl VirtualFileSystem {
         Err("Descriptor de archivo no encontrado")
     }
 
+    /// Slot de un descriptor de archivo
+    fn fd_slot(&self, fd_id: u32) -> Option<usize> {
+        self.file_descriptors.iter().position(|fd| fd.as_ref().map_or(false, |fd| fd.fd_id == fd_id))
+    }
+
+    /// Driver y ruta relativa que atienden un descriptor
+    ///
+    /// `Ok(None)` si el archivo es un nodo del árbol o su sistema montado
+    /// no tiene driver.
+    fn fd_driver(&mut self, slot: usize) -> Result<Option<(&mut dyn FileSystemDriver, String)>, &'static str> {
+        let fd = self.file_descriptors[slot].as_ref().ok_or("Descriptor de archivo no encontrado")?;
+        let mount_id = match fd.mount_id {
+            Some(mount_id) => mount_id,
+            None => return Ok(None),
+        };
+        let mount_point = self.get_mount_point_by_id(mount_id).ok_or("Mount point no encontrado")?;
+        let relative = String::from(mount_point.relative_path(&fd.path).ok_or("Mount point no encontrado")?);
+        Ok(self.get_filesystem_driver(mount_id).map(|driver| (driver, relative)))
+    }
+
     /// Leer de un archivo
+    ///
+    /// Los archivos regulares del árbol guardan su contenido en `node_data`;
+    /// los demás nodos del árbol no se pueden leer.
     pub fn read_file(&mut self, fd_id: u32, buffer: &mut [u8]) -> Result<usize, &'static str> {
-        for i in 0..1024 {
-            if let Some(ref mut fd) = self.file_descriptors[i] {
-                if fd.fd_id == fd_id {
-                    // Obtener información del mount point
-                    let mount_point = if let Some(mp) = self.get_mount_point_by_id(fd.fd_id) {
-                        mp
-                    } else {
-                        self.stats.read_errors += 1;
-                        return Err("Mount point no encontrado");
-                    };
-
-                    // Implementar lectura real del archivo
-                    if let Some(fs_driver) = self.get_filesystem_driver(mount_point.fs_type) {
-                        match fs_driver.read_file("dummy_path", buffer, fd.position) {
-                            Ok(bytes_read) => {
-                                self.stats.read_operations += 1;
-                                self.stats.bytes_read += bytes_read as u64;
-                                fd.position += bytes_read as u64;
-                                return Ok(bytes_read);
-                            }
-                            Err(e) => {
-                                self.stats.read_errors += 1;
-                                return Err(e);
-                            }
-                        }
-                    } else {
-                        // Fallback: llenar el buffer con datos de prueba
-                        let bytes_to_read = core::cmp::min(buffer.len(), 1024);
-                        for j in 0..bytes_to_read {
-                            buffer[j] = (j % 256) as u8;
-                        }
-                        self.stats.read_operations += 1;
-                        self.stats.bytes_read += bytes_to_read as u64;
-                        fd.position += bytes_to_read as u64;
-                        return Ok(bytes_to_read);
-                    }
+        let slot = self.fd_slot(fd_id).ok_or("Descriptor de archivo no encontrado")?;
+        let position = self.file_descriptors[slot].as_ref().map_or(0, |fd| fd.position);
+        let checked = self.check_granted(slot, FILE_READ_DATA).and_then(|_| self.check_locks(slot, position, buffer.len() as u64, false));
+        if let Err(e) = checked {
+            self.stats.read_errors += 1;
+            return Err(e);
+        }
+        
+        let result = match self.fd_driver(slot) {
+            Ok(Some((fs_driver, path))) => fs_driver.read_file(&path, buffer, position),
+            Ok(None) => self.read_node_data(slot, buffer, position),
+            Err(e) => Err(e),
+        };
+        
+        match result {
+            Ok(bytes_read) => {
+                self.stats.read_operations += 1;
+                self.stats.bytes_read += bytes_read as u64;
+                if let Some(ref mut fd) = self.file_descriptors[slot] {
+                    fd.position += bytes_read as u64;
                 }
+                Ok(bytes_read)
+            }
+            Err(e) => {
+                self.stats.read_errors += 1;
+                Err(e)
             }
         }
-        
-        Err("Descriptor de archivo no encontrado")
     }
 
     /// Escribir a un archivo
+    ///
+    /// Como en `read_file`, solo se escribe en los archivos regulares del árbol.
+    /// Hace falta `FILE_WRITE_DATA`; con solo `FILE_APPEND_DATA` únicamente se
+    /// puede escribir al final del archivo.
     pub fn write_file(&mut self, fd_id: u32, buffer: &[u8]) -> Result<usize, &'static str> {
-        for i in 0..1024 {
-            if let Some(ref mut fd) = self.file_descriptors[i] {
-                if fd.fd_id == fd_id {
-                    // Obtener información del mount point
-                    let mount_point = if let Some(mp) = self.get_mount_point_by_id(fd.fd_id) {
-                        mp
-                    } else {
-                        self.stats.write_errors += 1;
-                        return Err("Mount point no encontrado");
-                    };
-
-                    // Implementar escritura real del archivo
-                    if let Some(fs_driver) = self.get_filesystem_driver(mount_point.fs_type) {
-                        match fs_driver.write_file("dummy_path", buffer, fd.position) {
-                            Ok(bytes_written) => {
-                                self.stats.write_operations += 1;
-                                self.stats.bytes_written += bytes_written as u64;
-                                fd.position += bytes_written as u64;
-                                return Ok(bytes_written);
-                            }
-                            Err(e) => {
-                                self.stats.write_errors += 1;
-                                return Err(e);
-                            }
-                        }
-                    } else {
-                        // Fallback: simular escritura
-                        let bytes_written = buffer.len();
-                        self.stats.write_operations += 1;
-                        self.stats.bytes_written += bytes_written as u64;
-                        fd.position += bytes_written as u64;
-                        return Ok(bytes_written);
-                    }
-                }
+        let slot = self.fd_slot(fd_id).ok_or("Descriptor de archivo no encontrado")?;
+        let position = self.file_descriptors[slot].as_ref().map_or(0, |fd| fd.position);
+        let granted_access = self.file_descriptors[slot].as_ref().map_or(0, |fd| fd.granted_access);
+        let before = self.fstat(fd_id).ok();
+        let at_end = before.as_ref().map_or(false, |file| position >= file.size);
+        let required_access = if at_end && granted_access & FILE_WRITE_DATA == 0 { FILE_APPEND_DATA } else { FILE_WRITE_DATA };
+        let checked = self
+            .check_granted(slot, required_access)
+            .and_then(|_| self.check_locks(slot, position, buffer.len() as u64, true));
+        if let Err(e) = checked {
+            self.stats.write_errors += 1;
+            return Err(e);
+        }
+        
+        // Los bloques que añade la escritura cuentan para la cuota del propietario
+        if let Some(ref file) = before {
+            let end = position.saturating_add(buffer.len() as u64);
+            let growth = quota_blocks(end).saturating_sub(file_quota_blocks(file));
+            if let Err(e) = self.quotas.check(file.owner_id, file.group_id, growth as i64, 0) {
+                self.stats.write_errors += 1;
+                return Err(e);
             }
         }
         
-        Err("Descriptor de archivo no encontrado")
-    }
-
-    /// Buscar un archivo por nombre
-    pub fn find_file(&self, name: &str) -> Option<u32> {
-        for i in 0..1024 {
-            if let Some(ref file) = self.files[i] {
-                let file_name = core::str::from_utf8(&file.name).unwrap_or("");
-                if file_name == name {
-                    return Some(file.file_id);
+        let result = match self.fd_driver(slot) {
+            Ok(Some((fs_driver, path))) => fs_driver.write_file(&path, buffer, position),
+            Ok(None) => self.write_node_data(slot, buffer, position),
+            Err(e) => Err(e),
+        };
+        
+        match result {
+            Ok(bytes_written) => {
+                self.stats.write_operations += 1;
+                self.stats.bytes_written += bytes_written as u64;
+                if let Some(ref mut fd) = self.file_descriptors[slot] {
+                    fd.position += bytes_written as u64;
+                }
+                if let Some(file) = before {
+                    let after = self.fstat(fd_id).map_or(file.size, |info| info.size);
+                    let growth = quota_blocks(after) as i64 - file_quota_blocks(&file) as i64;
+                    self.quotas.charge(file.owner_id, file.group_id, growth, 0);
+                }
+                if bytes_written > 0 {
+                    self.notify_descriptor(slot, WatchEventKind::Modified);
                 }
+                Ok(bytes_written)
+            }
+            Err(e) => {
+                self.stats.write_errors += 1;
+                Err(e)
             }
         }
-        None
     }
 
-    /// Obtener información de un archivo
-    pub fn get_file_info(&self, file_id: u32) -> Option<&FileInfo> {
+    /// Nodo del árbol abierto en un descriptor, si es un archivo regular
+    fn regular_node(&self, slot: usize) -> Result<u32, &'static str> {
+        let file_id = self.file_descriptors[slot].as_ref().map_or(0, |fd| fd.file_id);
+        match self.get_file_info(file_id) {
+            Some(file) if file.node_type == NodeType::File => Ok(file_id),
+            Some(file) if file.node_type == NodeType::Directory => Err("Es un directorio"),
+            Some(_) => Err("No es un archivo regular"),
+            None => Err("Archivo no encontrado"),
+        }
+    }
+
+    /// Ajustar el tamaño del contenido de un archivo del árbol
+    ///
+    /// Crecer rellena con ceros; el `FileInfo` recibe el nuevo tamaño.
+    fn resize_node_data(&mut self, file_id: u32, size: u64) -> Result<(), &'static str> {
+        let file_slot = self.file_slot(file_id).ok_or("Archivo no encontrado")?;
+        let size = usize::try_from(size).map_err(|_| "Archivo demasiado grande")?;
+        self.node_data[file_slot].resize(size, 0);
+        if let Some(ref mut file) = self.files[file_slot] {
+            file.size = size as u64;
+        }
+        Ok(())
+    }
+
+    /// Leer el contenido de un archivo del árbol
+    ///
+    /// Un archivo que nunca se ha escrito está vacío.
+    fn read_node_data(&mut self, slot: usize, buffer: &mut [u8], position: u64) -> Result<usize, &'static str> {
+        let file_id = self.regular_node(slot)?;
+        let file_slot = self.file_slot(file_id).ok_or("Archivo no encontrado")?;
+        let data = &self.node_data[file_slot];
+        if position >= data.len() as u64 {
+            return Ok(0);
+        }
+        let start = position as usize;
+        let length = core::cmp::min(buffer.len(), data.len() - start);
+        buffer[..length].copy_from_slice(&data[start..start + length]);
+        Ok(length)
+    }
+
+    /// Escribir en el contenido de un archivo del árbol
+    ///
+    /// Escribir más allá del final deja un hueco relleno de ceros.
+    fn write_node_data(&mut self, slot: usize, buffer: &[u8], position: u64) -> Result<usize, &'static str> {
+        let file_id = self.regular_node(slot)?;
+        let file_slot = self.file_slot(file_id).ok_or("Archivo no encontrado")?;
+        let end = position.checked_add(buffer.len() as u64).ok_or("Archivo demasiado grande")?;
+        if end > self.node_data[file_slot].len() as u64 {
+            self.resize_node_data(file_id, end)?;
+        }
+        let start = position as usize;
+        self.node_data[file_slot][start..start + buffer.len()].copy_from_slice(buffer);
+        Ok(buffer.len())
+    }
+
+    /// Cambiar la posición de un descriptor
+    ///
+    /// `whence` es `VFS_SEEK_SET`, `VFS_SEEK_CUR` o `VFS_SEEK_END`. Se puede ir
+    /// más allá del final (la siguiente escritura deja un hueco). En un
+    /// directorio la posición es el índice de `readdir`; volver a 0 lo reinicia.
+    pub fn lseek(&mut self, fd_id: u32, offset: i64, whence: u32) -> Result<u64, &'static str> {
+        let slot = self.fd_slot(fd_id).ok_or("Descriptor de archivo no encontrado")?;
+        let base = match whence {
+            VFS_SEEK_SET => 0,
+            VFS_SEEK_CUR => self.file_descriptors[slot].as_ref().map_or(0, |fd| fd.position),
+            VFS_SEEK_END => self.fstat(fd_id)?.size,
+            _ => return Err("Origen de desplazamiento no válido"),
+        };
+        let position = base.checked_add_signed(offset).ok_or("Desplazamiento no válido")?;
+        
+        if let Some(ref mut fd) = self.file_descriptors[slot] {
+            fd.position = position;
+            if position == 0 {
+                fd.directory_entries = None;
+            }
+        }
+        Ok(position)
+    }
+
+    /// Obtener información del archivo abierto en un descriptor
+    pub fn fstat(&mut self, fd_id: u32) -> Result<FileInfo, &'static str> {
+        let slot = self.fd_slot(fd_id).ok_or("Descriptor de archivo no encontrado")?;
+        if let Some((fs_driver, path)) = self.fd_driver(slot)? {
+            return fs_driver.stat(&path);
+        }
+        let file_id = self.file_descriptors[slot].as_ref().map_or(0, |fd| fd.file_id);
+        self.get_file_info(file_id).cloned().ok_or("Archivo no encontrado")
+    }
+
+    /// Cambiar el tamaño del archivo abierto en un descriptor
+    ///
+    /// La posición del descriptor no cambia. Los bytes que se añaden o se
+    /// quitan cuentan como escritura frente a los bloqueos obligatorios.
+    pub fn ftruncate(&mut self, fd_id: u32, size: u64) -> Result<(), &'static str> {
+        let slot = self.fd_slot(fd_id).ok_or("Descriptor de archivo no encontrado")?;
+        self.check_granted(slot, FILE_WRITE_DATA)?;
+        let file_info = self.fstat(fd_id)?;
+        let current = file_info.size;
+        let start = core::cmp::min(current, size);
+        self.check_locks(slot, start, core::cmp::max(current, size) - start, true)?;
+        let growth = quota_blocks(size) as i64 - file_quota_blocks(&file_info) as i64;
+        self.quotas.check(file_info.owner_id, file_info.group_id, growth, 0)?;
+        
+        if let Some((fs_driver, path)) = self.fd_driver(slot)? {
+            fs_driver.truncate(&path, size)?;
+        } else {
+            let file_id = self.regular_node(slot)?;
+            self.resize_node_data(file_id, size)?;
+        }
+        self.quotas.charge(file_info.owner_id, file_info.group_id, growth, 0);
+        if size != current {
+            self.notify_descriptor(slot, WatchEventKind::Modified);
+        }
+        Ok(())
+    }
+
+    /// Leer la siguiente entrada de un directorio abierto
+    ///
+    /// La primera llamada toma una instantánea del listado (`read_dir`) y la
+    /// posición del descriptor indica la siguiente entrada. Devuelve `None`
+    /// al llegar al final.
+    pub fn readdir(&mut self, fd_id: u32) -> Result<Option<FileInfo>, &'static str> {
+        let slot = self.fd_slot(fd_id).ok_or("Descriptor de archivo no encontrado")?;
+        let pending = self.file_descriptors[slot]
+            .as_ref()
+            .and_then(|fd| if fd.directory_entries.is_none() { Some(fd.path.clone()) } else { None });
+        if let Some(path) = pending {
+            let entries = self.read_dir(&path)?;
+            if let Some(ref mut fd) = self.file_descriptors[slot] {
+                fd.directory_entries = Some(entries);
+            }
+        }
+        
+        let fd = self.file_descriptors[slot].as_mut().ok_or("Descriptor de archivo no encontrado")?;
+        let entry = fd
+            .directory_entries
+            .as_ref()
+            .and_then(|entries| entries.get(fd.position as usize))
+            .cloned();
+        if entry.is_some() {
+            fd.position += 1;
+        }
+        Ok(entry)
+    }
+
+    /// Llevar al dispositivo los cambios del archivo abierto en un descriptor
+    ///
+    /// El driver escribe sus metadatos pendientes y después se vacía la caché
+    /// de bloques del dispositivo. Los nodos del árbol no tienen nada pendiente.
+    pub fn fsync(&mut self, fd_id: u32) -> Result<(), &'static str> {
+        let slot = self.fd_slot(fd_id).ok_or("Descriptor de archivo no encontrado")?;
+        let mount_id = match self.file_descriptors[slot].as_ref().and_then(|fd| fd.mount_id) {
+            Some(mount_id) => mount_id,
+            None => return Ok(()),
+        };
+        if let Some(fs_driver) = self.get_filesystem_driver(mount_id) {
+            fs_driver.sync()?;
+        }
+        let block_device = self.get_mount_point_by_id(mount_id).ok_or("Mount point no encontrado")?.block_device;
+        if crate::drivers::storage::sync_storage(Some(block_device)) {
+            Ok(())
+        } else {
+            Err("Error al sincronizar el dispositivo")
+        }
+    }
+
+    /// Bloquear un rango de bytes del archivo abierto en un descriptor
+    ///
+    /// `length` 0 bloquea hasta el final. Falla sin esperar si otro descriptor
+    /// tiene un bloqueo incompatible que se solapa; los bloqueos del mismo
+    /// descriptor no entran en conflicto entre sí.
+    pub fn lock(&mut self, fd_id: u32, start: u64, length: u64, lock_type: LockType, mandatory: bool) -> Result<(), &'static str> {
+        let slot = self.fd_slot(fd_id).ok_or("Descriptor de archivo no encontrado")?;
+        let path = self.file_descriptors[slot].as_ref().map_or(String::new(), |fd| fd.path.clone());
+        let lock = FileLock { fd_id, path, start, length, lock_type, mandatory };
+        
+        let conflict = self.locks.iter().any(|held| {
+            held.path == lock.path
+                && held.fd_id != fd_id
+                && (held.lock_type == LockType::Exclusive || lock_type == LockType::Exclusive)
+                && held.overlaps(lock.start, lock.end())
+        });
+        if conflict {
+            return Err("Rango bloqueado");
+        }
+        self.locks.push(lock);
+        Ok(())
+    }
+
+    /// Liberar un bloqueo de un descriptor
+    ///
+    /// El rango debe coincidir exactamente con el de un bloqueo existente.
+    pub fn unlock(&mut self, fd_id: u32, start: u64, length: u64) -> Result<(), &'static str> {
+        let position = self
+            .locks
+            .iter()
+            .position(|lock| lock.fd_id == fd_id && lock.start == start && lock.length == length)
+            .ok_or("Bloqueo no encontrado")?;
+        self.locks.remove(position);
+        Ok(())
+    }
+
+    /// Verificar que el descriptor se abrió con todos los derechos de `access`
+    fn check_granted(&self, slot: usize, access: u32) -> Result<(), &'static str> {
+        let granted_access = self.file_descriptors[slot].as_ref().map_or(0, |fd| fd.granted_access);
+        if granted_access & access != access {
+            return Err("Acceso denegado");
+        }
+        Ok(())
+    }
+
+    /// Verificar que ningún bloqueo obligatorio de otro descriptor impide el acceso
+    fn check_locks(&self, slot: usize, start: u64, length: u64, write: bool) -> Result<(), &'static str> {
+        let fd = self.file_descriptors[slot].as_ref().ok_or("Descriptor de archivo no encontrado")?;
+        if length == 0 {
+            return Ok(());
+        }
+        let end = start.saturating_add(length);
+        let blocked = self.locks.iter().any(|lock| {
+            lock.mandatory
+                && lock.fd_id != fd.fd_id
+                && lock.path == fd.path
+                && (write || lock.lock_type == LockType::Exclusive)
+                && lock.overlaps(start, end)
+        });
+        if blocked {
+            Err("Rango bloqueado")
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Renombrar o mover un archivo o directorio
+    ///
+    /// Si el destino existe se sustituye, como en `rename`: un archivo por un
+    /// archivo, o un directorio vacío por un directorio. Origen y destino deben
+    /// estar en el mismo sistema: los nodos del árbol se mueven en el árbol y
+    /// el resto lo mueve el driver de su punto de montaje. Los descriptores y
+    /// bloqueos abiertos siguen al archivo. Se comprueba el borrado del origen
+    /// y del destino sustituido y la creación en el directorio de destino.
+    pub fn rename(&mut self, old_path: &str, new_path: &str) -> Result<(), &'static str> {
+        let source = self.resolve_path(old_path, false)?;
+        let (parent_path, name) = split_parent(new_path)?;
+        if name.len() > VFS_MAX_NAME_LENGTH || name.contains('\0') {
+            return Err("Nombre de archivo inválido");
+        }
+        let parent = self.resolve_path(parent_path, true)?;
+        let target_path = join_path(&parent.path, name);
+        if target_path == source.path {
+            return Ok(());
+        }
+        if is_path_within(&target_path, &source.path) {
+            return Err("No se puede mover un directorio dentro de sí mismo");
+        }
+        let holds_mount = self
+            .mount_points
+            .iter()
+            .flatten()
+            .any(|mount| renamed_path(mount.path(), &source.path, "/").is_some());
+        if holds_mount {
+            return Err("Punto de montaje ocupado");
+        }
+        let target = match self.resolve_path(&target_path, false) {
+            Ok(target) => Some(target),
+            Err("Archivo no encontrado") => None,
+            Err(e) => return Err(e),
+        };
+        self.check_delete_access(&source.path)?;
+        self.check_create_access(&target_path)?;
+        if target.is_some() {
+            self.check_delete_access(&target_path)?;
+        }
+        
+        let mut replaced = None;
+        match (source.file_id, &target) {
+            // Nodo del árbol a un destino del árbol
+            (Some(file_id), None) | (Some(file_id), Some(ResolvedPath { file_id: Some(_), .. })) => {
+                let new_parent = parent.file_id.ok_or("No se puede mover entre sistemas de archivos distintos")?;
+                if !self.is_directory(new_parent) {
+                    return Err("No es un directorio");
+                }
+                if let Some(existing) = target.as_ref().and_then(|target| target.file_id) {
+                    if self.is_directory(existing) != self.is_directory(file_id) {
+                        return Err("El destino es de otro tipo");
+                    }
+                    self.remove_node(&target_path)?;
+                }
+                self.move_node(file_id, new_parent, name)?;
+            }
+            // Archivo de un sistema montado dentro del mismo sistema
+            (None, Some(ResolvedPath { file_id: None, mount_id: Some(mount_id), mount_relative_path, .. })) if Some(*mount_id) == source.mount_id => {
+                let mount_id = *mount_id;
+                let target_relative = mount_relative_path.clone();
+                let target_open = self.file_descriptors.iter().flatten().any(|fd| fd.path == target_path);
+                let fs_driver = self.get_filesystem_driver(mount_id).ok_or("Sistema de archivos sin driver")?;
+                let source_info = fs_driver.stat(&source.mount_relative_path)?;
+                // Un cambio solo de mayúsculas encuentra el propio origen como destino
+                if !target_relative.eq_ignore_ascii_case(&source.mount_relative_path) {
+                    if let Ok(existing) = fs_driver.stat(&target_relative) {
+                        if (existing.node_type == NodeType::Directory) != (source_info.node_type == NodeType::Directory) {
+                            return Err("El destino es de otro tipo");
+                        }
+                        if target_open {
+                            return Err("Archivo en uso");
+                        }
+                        fs_driver.unlink(&target_relative)?;
+                        replaced = Some(existing);
+                    }
+                }
+                fs_driver.rename(&source.mount_relative_path, &target_relative)?;
+            }
+            _ => return Err("No se puede mover entre sistemas de archivos distintos"),
+        }
+        if let Some(existing) = replaced {
+            self.quotas.charge(existing.owner_id, existing.group_id, -(file_quota_blocks(&existing) as i64), -1);
+        }
+        // Los descriptores explícitos del volumen siguen al archivo; los del
+        // árbol van en su dentry
+        if let (None, Some(mount_id), Some(target)) = (source.file_id, source.mount_id, &target) {
+            if let Some(mount) = self.mount_mut(mount_id) {
+                mount.security.retain(|entry| entry.path != target.mount_relative_path);
+                for entry in mount.security.iter_mut() {
+                    if let Some(path) = renamed_path(&entry.path, &source.mount_relative_path, &target.mount_relative_path) {
+                        entry.path = path;
+                    }
+                }
+            }
+        }
+        
+        // Los descriptores y bloqueos abiertos siguen al archivo
+        for fd in self.file_descriptors.iter_mut().flatten() {
+            if let Some(path) = renamed_path(&fd.path, &source.path, &target_path) {
+                fd.path = path;
+            }
+        }
+        for lock in self.locks.iter_mut() {
+            if let Some(path) = renamed_path(&lock.path, &source.path, &target_path) {
+                lock.path = path;
+            }
+        }
+        self.notify_rename(&source.path, &target_path);
+        Ok(())
+    }
+
+    /// Mover un nodo del árbol a otro directorio con otro nombre
+    fn move_node(&mut self, file_id: u32, new_parent: u32, name: &str) -> Result<(), &'static str> {
+        let slot = self.file_slot(file_id).ok_or("Archivo no encontrado")?;
+        let old_parent = self.dentries[slot].as_ref().map_or(VFS_ROOT_FILE_ID, |dentry| dentry.parent);
+        if let Some(dentry) = self.dentry_mut(old_parent) {
+            dentry.children.retain(|&child| child != file_id);
+        }
+        if let Some(dentry) = self.dentry_mut(new_parent) {
+            dentry.children.push(file_id);
+        }
+        if let Some(ref mut dentry) = self.dentries[slot] {
+            dentry.parent = new_parent;
+            dentry.name = String::from(name);
+        }
+        if let Some(ref mut file) = self.files[slot] {
+            file.name = FileInfo::new(file_id, name, file.node_type).name;
+        }
+        Ok(())
+    }
+
+    /// Obtener información de un archivo por ruta
+    ///
+    /// Los nodos del árbol responden con su `FileInfo`; el resto lo consulta
+    /// el driver del punto de montaje.
+    pub fn stat_path(&mut self, path: &str) -> Result<FileInfo, &'static str> {
+        let resolved = self.resolve_path(path, true)?;
+        if let Some(file_id) = resolved.file_id {
+            return self.get_file_info(file_id).cloned().ok_or("Archivo no encontrado");
+        }
+        let mount_id = resolved.mount_id.ok_or("Archivo no encontrado")?;
+        self.get_filesystem_driver(mount_id)
+            .ok_or("Sistema de archivos sin driver")?
+            .stat(&resolved.mount_relative_path)
+    }
+
+    /// Listar un directorio, incluido el contenido de los sistemas montados
+    ///
+    /// En los directorios servidos por un driver se listan sus entradas, que
+    /// tienen prioridad sobre los nodos del árbol con el mismo nombre. Solo
+    /// los nodos que llevan a otro punto de montaje sustituyen a la entrada
+    /// del volumen, porque las rutas que pasan por ellos van a ese montaje.
+    pub fn read_dir(&mut self, path: &str) -> Result<Vec<FileInfo>, &'static str> {
+        let resolved = self.resolve_path(path, true)?;
+        // (entrada, lleva a un punto de montaje)
+        let mut tree: Vec<(FileInfo, bool)> = Vec::new();
+        if let Some(file_id) = resolved.file_id {
+            if !self.is_directory(file_id) {
+                return Err("No es un directorio");
+            }
+            let dentry = self.dentry(file_id).ok_or("Archivo no encontrado")?;
+            for &child in dentry.children.iter().filter(|&&child| !self.is_hidden(child)) {
+                if let Some(info) = self.get_file_info(child) {
+                    let mount_route = self.leads_to_mount(&join_path(&resolved.path, info.name()));
+                    tree.push((info.clone(), mount_route));
+                }
+            }
+        }
+        
+        let fs_driver = resolved.mount_id.and_then(|mount_id| self.get_filesystem_driver(mount_id));
+        let mut entries = match fs_driver.map(|fs_driver| fs_driver.read_dir(&resolved.mount_relative_path)) {
+            Some(Ok(mounted)) => mounted,
+            // Un directorio del árbol que no existe en el volumen montado
+            Some(Err(_)) if resolved.file_id.is_some() => Vec::new(),
+            Some(Err(e)) => return Err(e),
+            None if resolved.file_id.is_none() => return Err("Sistema de archivos sin driver"),
+            None => Vec::new(),
+        };
+        for (info, mount_route) in tree {
+            match entries.iter().position(|entry| entry.name() == info.name()) {
+                Some(index) if mount_route => entries[index] = info,
+                Some(_) => {}
+                None => entries.push(info),
+            }
+        }
+        Ok(entries)
+    }
+    
+    /// Verificar si `path` (canónica) es un punto de montaje o contiene alguno
+    fn leads_to_mount(&self, path: &str) -> bool {
+        self.mount_points
+            .iter()
+            .flatten()
+            .any(|mount| mount.path() == path || is_path_within(mount.path(), path))
+    }
+
+    /// Eliminar un archivo o directorio vacío por ruta
+    ///
+    /// Los nodos del árbol se eliminan con `remove_node`; el resto lo elimina
+    /// el driver del punto de montaje, salvo que esté abierto. Hace falta
+    /// `FILE_DELETE_CHILD` sobre el directorio o `DELETE` sobre el nodo.
+    pub fn unlink(&mut self, path: &str) -> Result<(), &'static str> {
+        let resolved = self.resolve_path(path, false)?;
+        self.check_delete_access(&resolved.path)?;
+        if resolved.file_id.is_some() {
+            return self.remove_node(path);
+        }
+        if self.file_descriptors.iter().flatten().any(|fd| fd.path == resolved.path) {
+            return Err("Archivo en uso");
+        }
+        let mount_id = resolved.mount_id.ok_or("Archivo no encontrado")?;
+        let fs_driver = self.get_filesystem_driver(mount_id).ok_or("Sistema de archivos sin driver")?;
+        let file_info = fs_driver.stat(&resolved.mount_relative_path)?;
+        fs_driver.unlink(&resolved.mount_relative_path)?;
+        self.quotas.charge(file_info.owner_id, file_info.group_id, -(file_quota_blocks(&file_info) as i64), -1);
+        if let Some(mount) = self.mount_mut(mount_id) {
+            mount.security.retain(|entry| renamed_path(&entry.path, &resolved.mount_relative_path, "/").is_none());
+        }
+        self.notify(WatchEventKind::Deleted, &resolved.path);
+        Ok(())
+    }
+
+    /// Cambiar los permisos de un archivo o directorio por ruta
+    ///
+    /// Los nodos del árbol guardan los permisos en su `FileInfo`; el resto los
+    /// cambia el driver del punto de montaje, si lo soporta. Hace falta
+    /// `WRITE_DAC`, que el propietario siempre tiene.
+    pub fn set_permissions(&mut self, path: &str, permissions: FilePermissions) -> Result<(), &'static str> {
+        let resolved = self.resolve_path(path, true)?;
+        self.check_access(&resolved.path, WRITE_DAC)?;
+        if let Some(file_id) = resolved.file_id {
+            let slot = self.file_slot(file_id).ok_or("Archivo no encontrado")?;
+            if let Some(ref mut file) = self.files[slot] {
+                file.permissions = permissions;
+            }
+        } else {
+            let mount_id = resolved.mount_id.ok_or("Archivo no encontrado")?;
+            self.get_filesystem_driver(mount_id)
+                .ok_or("Sistema de archivos sin driver")?
+                .set_permissions(&resolved.mount_relative_path, permissions)?;
+        }
+        self.notify(WatchEventKind::AttributesChanged, &resolved.path);
+        Ok(())
+    }
+
+    /// Fijar el usuario y el grupo propietarios de los archivos que se creen
+    pub fn set_credentials(&mut self, uid: u32, gid: u32) {
+        self.current_uid = uid;
+        self.current_gid = gid;
+    }
+
+    /// Cambiar el propietario y el grupo de un archivo o directorio
+    ///
+    /// Hace falta `WRITE_OWNER`. El uso del archivo pasa de la cuota del
+    /// propietario anterior a la del nuevo, que debe tener espacio para él.
+    pub fn set_owner(&mut self, path: &str, owner_id: u32, group_id: u32) -> Result<(), &'static str> {
+        let resolved = self.resolve_path(path, true)?;
+        self.check_access(&resolved.path, WRITE_OWNER)?;
+        let file_info = self.stat_path(&resolved.path)?;
+        let blocks = file_quota_blocks(&file_info) as i64;
+        self.quotas.check(owner_id, group_id, blocks, 1)?;
+        if let Some(file_id) = resolved.file_id {
+            let slot = self.file_slot(file_id).ok_or("Archivo no encontrado")?;
+            if let Some(ref mut file) = self.files[slot] {
+                file.owner_id = owner_id;
+                file.group_id = group_id;
+            }
+        } else {
+            let mount_id = resolved.mount_id.ok_or("Archivo no encontrado")?;
+            self.get_filesystem_driver(mount_id)
+                .ok_or("Sistema de archivos sin driver")?
+                .set_owner(&resolved.mount_relative_path, owner_id, group_id)?;
+        }
+        self.quotas.charge(file_info.owner_id, file_info.group_id, -blocks, -1);
+        self.quotas.charge(owner_id, group_id, blocks, 1);
+        self.notify(WatchEventKind::AttributesChanged, &resolved.path);
+        Ok(())
+    }
+
+    /// Recontar el uso de las cuotas recorriendo todo el árbol
+    ///
+    /// Se usa al montar sistemas con archivos ya existentes. No sigue enlaces
+    /// simbólicos; los límites configurados se conservan.
+    pub fn recalculate_quotas(&mut self) -> Result<(), &'static str> {
+        self.quotas.clear_usage();
+        let mut pending = vec![String::from("/")];
+        while let Some(directory) = pending.pop() {
+            for entry in self.read_dir(&directory)? {
+                self.quotas.charge(entry.owner_id, entry.group_id, file_quota_blocks(&entry) as i64, 1);
+                if entry.node_type == NodeType::Directory {
+                    pending.push(join_path(&directory, entry.name()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Descriptor de seguridad de un nodo, sin comprobar el acceso
+    ///
+    /// Si el nodo no tiene uno explícito se deriva de su propietario, su grupo
+    /// y sus permisos.
+    pub fn security_descriptor(&mut self, path: &str) -> Result<SecurityDescriptor, &'static str> {
+        let resolved = self.resolve_path(path, true)?;
+        if let Some(descriptor) = self.explicit_security(&resolved) {
+            return Ok(descriptor.clone());
+        }
+        Ok(permissions_descriptor(&self.stat_path(&resolved.path)?))
+    }
+
+    /// Descriptor explícito de un nodo resuelto: el de su dentry o, en un
+    /// volumen montado, el que guarda su punto de montaje
+    fn explicit_security(&self, resolved: &ResolvedPath) -> Option<&SecurityDescriptor> {
+        match (resolved.file_id, resolved.mount_id) {
+            (Some(file_id), _) => self.dentry(file_id)?.security.as_ref(),
+            (None, Some(mount_id)) => self
+                .get_mount_info(mount_id)?
+                .security
+                .iter()
+                .find(|entry| entry.path == resolved.mount_relative_path)
+                .map(|entry| &entry.descriptor),
+            (None, None) => None,
+        }
+    }
+
+    /// Guardar el descriptor explícito de un nodo resuelto
+    fn store_security(&mut self, resolved: &ResolvedPath, descriptor: SecurityDescriptor) {
+        if let Some(file_id) = resolved.file_id {
+            if let Some(dentry) = self.dentry_mut(file_id) {
+                dentry.security = Some(descriptor);
+            }
+        } else if let Some(mount) = resolved.mount_id.and_then(|mount_id| self.mount_mut(mount_id)) {
+            mount.security.retain(|entry| entry.path != resolved.mount_relative_path);
+            mount.security.push(NodeSecurity { path: resolved.mount_relative_path.clone(), descriptor });
+        }
+    }
+
+    /// Cambiar el descriptor de seguridad de un nodo
+    ///
+    /// Hace falta `WRITE_DAC`, y también `WRITE_OWNER` si cambia el
+    /// propietario. Desde entonces el descriptor sustituye a los permisos del
+    /// nodo; sus ACE heredables se aplican a los hijos que se creen después.
+    pub fn set_security(&mut self, path: &str, descriptor: SecurityDescriptor) -> Result<(), &'static str> {
+        let resolved = self.resolve_path(path, true)?;
+        let mut required_access = WRITE_DAC;
+        if self.security_descriptor(&resolved.path)?.owner != descriptor.owner {
+            required_access |= WRITE_OWNER;
+        }
+        self.check_access(&resolved.path, required_access)?;
+        self.store_security(&resolved, descriptor);
+        self.notify(WatchEventKind::AttributesChanged, &resolved.path);
+        Ok(())
+    }
+
+    /// Evaluar el acceso del proceso actual a un nodo y devolver los derechos concedidos
+    ///
+    /// Se usa el token del proceso en ejecución (`current_token`). Los accesos
+    /// denegados se registran en la auditoría; sin control de acceso
+    /// inicializado se deniega todo.
+    fn check_access(&mut self, path: &str, desired_access: u32) -> Result<u32, &'static str> {
+        let resolved_path = self.resolve_path(path, true)?.path;
+        let descriptor = self.security_descriptor(&resolved_path)?;
+        let token_id = current_token();
+        let manager = match get_access_control_manager() {
+            Some(manager) => manager,
+            None => {
+                log_access_denied("VFS", &resolved_path, None, desired_access);
+                return Err("Acceso denegado");
+            }
+        };
+        let result = manager.check_access(token_id, &descriptor, desired_access);
+        if result.granted {
+            return Ok(result.granted_access);
+        }
+        let user_id = manager.get_token(token_id).map(|token| token.user.to_string());
+        log_access_denied("VFS", &resolved_path, user_id, desired_access);
+        Err("Acceso denegado")
+    }
+
+    /// Comprobar, sin auditar, si el proceso actual tiene `access` sobre un nodo
+    fn has_access(&mut self, path: &str, access: u32) -> bool {
+        let descriptor = match self.security_descriptor(path) {
+            Ok(descriptor) => descriptor,
+            Err(_) => return false,
+        };
+        get_access_control_manager()
+            .map_or(false, |manager| manager.check_access(current_token(), &descriptor, access).granted)
+    }
+
+    /// Verificar que el proceso actual puede crear `path` en su directorio
+    fn check_create_access(&mut self, path: &str) -> Result<(), &'static str> {
+        let (parent_path, _) = split_parent(path)?;
+        self.check_access(parent_path, FILE_ADD_FILE).map(|_| ())
+    }
+
+    /// Verificar que el proceso actual puede eliminar `path`
+    ///
+    /// Basta `FILE_DELETE_CHILD` sobre su directorio; si no, hace falta
+    /// `DELETE` sobre el propio nodo.
+    fn check_delete_access(&mut self, path: &str) -> Result<(), &'static str> {
+        let (parent_path, _) = split_parent(path)?;
+        if self.has_access(parent_path, FILE_DELETE_CHILD) {
+            return Ok(());
+        }
+        self.check_access(path, DELETE).map(|_| ())
+    }
+
+    /// Dar a un nodo nuevo las ACE heredables del descriptor de su directorio
+    ///
+    /// Solo los descriptores explícitos tienen ACE heredables; sin ellas el
+    /// nodo se queda con el descriptor derivado de sus permisos.
+    fn inherit_security(&mut self, path: &str, is_container: bool) {
+        let parent_path = match split_parent(path) {
+            Ok((parent_path, _)) => parent_path,
+            Err(_) => return,
+        };
+        let (parent, node) = match (self.resolve_path(parent_path, true), self.resolve_path(path, false)) {
+            (Ok(parent), Ok(node)) => (parent, node),
+            _ => return,
+        };
+        let owner = get_access_control_manager()
+            .and_then(|manager| manager.get_token(current_token()))
+            .map_or_else(|| Sid::unix_user(self.current_uid), |token| token.user.clone());
+        let group = Sid::unix_group(self.current_gid);
+        let descriptor = self
+            .explicit_security(&parent)
+            .and_then(|parent| SecurityDescriptor::inherit(parent, owner, group, is_container));
+        if let Some(descriptor) = descriptor {
+            self.store_security(&node, descriptor);
+        }
+    }
+
+    /// Vigilar los cambios de un directorio
+    ///
+    /// `mask` combina `VFS_NOTIFY_*`. Con `recursive` se notifican también los
+    /// cambios de sus subdirectorios. La cola guarda hasta `capacity` eventos;
+    /// los que no caben se pierden y la siguiente lectura lo indica.
+    pub fn watch(&mut self, path: &str, recursive: bool, mask: u32, capacity: usize) -> Result<u32, &'static str> {
+        if mask == 0 || mask & !VFS_NOTIFY_ALL != 0 {
+            return Err("Máscara de vigilancia no válida");
+        }
+        if capacity == 0 {
+            return Err("Capacidad de cola no válida");
+        }
+        if self.stat_path(path)?.node_type != NodeType::Directory {
+            return Err("No es un directorio");
+        }
+        if self.watches.len() >= VFS_MAX_WATCHES {
+            return Err("No hay slots libres para vigilancias");
+        }
+        
+        let watch_id = self.watch_counter.fetch_add(1, Ordering::SeqCst);
+        self.watches.push(DirectoryWatch {
+            watch_id,
+            path: self.resolve_path(path, true)?.path,
+            recursive,
+            mask,
+            capacity,
+            events: Vec::new(),
+            overflowed: false,
+        });
+        Ok(watch_id)
+    }
+
+    /// Cancelar una vigilancia; los eventos pendientes se descartan
+    pub fn unwatch(&mut self, watch_id: u32) -> Result<(), &'static str> {
+        let position = self
+            .watches
+            .iter()
+            .position(|watch| watch.watch_id == watch_id)
+            .ok_or("Vigilancia no encontrada")?;
+        self.watches.remove(position);
+        Ok(())
+    }
+
+    /// Leer hasta `max_events` eventos pendientes de una vigilancia
+    ///
+    /// La marca de desbordamiento se devuelve y se limpia en la primera
+    /// lectura tras la pérdida de eventos.
+    pub fn read_watch(&mut self, watch_id: u32, max_events: usize) -> Result<WatchEvents, &'static str> {
+        let watch = self
+            .watches
+            .iter_mut()
+            .find(|watch| watch.watch_id == watch_id)
+            .ok_or("Vigilancia no encontrada")?;
+        let count = core::cmp::min(max_events, watch.events.len());
+        let overflowed = mem::replace(&mut watch.overflowed, false);
+        Ok(WatchEvents {
+            events: watch.events.drain(..count).collect(),
+            overflowed,
+        })
+    }
+
+    /// Leer los cambios de un directorio abierto como registros `FILE_NOTIFY_INFORMATION`
+    ///
+    /// Atiende `NtNotifyChangeDirectoryFile`: la primera llamada sobre el
+    /// descriptor crea una vigilancia con el filtro y la recursión indicados,
+    /// que se mantiene hasta cerrarlo, y las siguientes leen de ella. Devuelve
+    /// los bytes escritos en `buffer` (0 si no hay cambios pendientes), o
+    /// `None` si se perdieron cambios o no caben y hay que volver a listar.
+    pub fn notify_change_directory(
+        &mut self,
+        fd_id: u32,
+        completion_filter: u32,
+        watch_tree: bool,
+        buffer: &mut [u8],
+    ) -> Result<Option<usize>, &'static str> {
+        let slot = self.fd_slot(fd_id).ok_or("Descriptor de archivo no encontrado")?;
+        self.check_granted(slot, FILE_READ_DATA)?;
+
+        let watch_id = match self.file_descriptors[slot].as_ref().and_then(|fd| fd.notify_watch) {
+            Some(watch_id) => watch_id,
+            None => {
+                // Los filtros sin evento propio (accesos, creación) nunca notifican nada
+                let mask = watch_mask_from_nt_filter(completion_filter);
+                if mask == 0 {
+                    return Ok(Some(0));
+                }
+                let path = self.file_descriptors[slot].as_ref().map(|fd| fd.path.clone()).unwrap_or_default();
+                let watch_id = self.watch(&path, watch_tree, mask, VFS_WATCH_QUEUE_CAPACITY)?;
+                if let Some(fd) = self.file_descriptors[slot].as_mut() {
+                    fd.notify_watch = Some(watch_id);
+                }
+                watch_id
+            }
+        };
+
+        let events = self.read_watch(watch_id, usize::MAX)?;
+        Ok(write_notify_information(&events, buffer))
+    }
+
+    /// Notificar un cambio en una ruta canónica a las vigilancias que la cubren
+    ///
+    /// El VFS lo llama en sus propias operaciones; los drivers o servicios que
+    /// cambian archivos por otras vías pueden usarlo para avisar.
+    pub fn notify(&mut self, kind: WatchEventKind, path: &str) {
+        for watch in self.watches.iter_mut() {
+            if let Some(relative) = watch.relative_path(path) {
+                let event = WatchEvent { kind, path: String::from(relative), old_path: None };
+                watch.push(event);
+            }
+        }
+    }
+
+    /// Notificar un renombrado a las vigilancias
+    ///
+    /// Si solo una de las dos rutas está en una vigilancia, para ella es una
+    /// eliminación o una creación. Las vigilancias de directorios que se
+    /// mueven siguen a su directorio.
+    fn notify_rename(&mut self, old_path: &str, new_path: &str) {
+        for watch in self.watches.iter_mut() {
+            let event = match (watch.relative_path(old_path), watch.relative_path(new_path)) {
+                (Some(old), Some(new)) => WatchEvent {
+                    kind: WatchEventKind::Renamed,
+                    path: String::from(new),
+                    old_path: Some(String::from(old)),
+                },
+                (Some(old), None) => WatchEvent { kind: WatchEventKind::Deleted, path: String::from(old), old_path: None },
+                (None, Some(new)) => WatchEvent { kind: WatchEventKind::Created, path: String::from(new), old_path: None },
+                (None, None) => {
+                    if let Some(path) = renamed_path(&watch.path, old_path, new_path) {
+                        watch.path = path;
+                    }
+                    continue;
+                }
+            };
+            watch.push(event);
+        }
+    }
+
+    /// Notificar un cambio en el archivo abierto en un descriptor
+    fn notify_descriptor(&mut self, slot: usize, kind: WatchEventKind) {
+        if let Some(path) = self.file_descriptors[slot].as_ref().map(|fd| fd.path.clone()) {
+            self.notify(kind, &path);
+        }
+    }
+
+    /// Buscar un archivo por nombre
+    ///
+    /// `name` es una ruta (las relativas parten de la raíz).
+    pub fn find_file(&self, name: &str) -> Option<u32> {
+        self.lookup_path(name)
+    }
+
+    /// Obtener información de un archivo
+    pub fn get_file_info(&self, file_id: u32) -> Option<&FileInfo> {
         for i in 0..1024 {
             if let Some(ref file) = self.files[i] {
                 if file.file_id == file_id {
@@ -572,6 +2334,51 @@ impl VirtualFileSystem {
         )
     }
 
+    /// Obtener estadísticas detalladas, incluidas las de la caché de bloques
+    pub fn get_detailed_stats(&self) -> VfsDetailedStats {
+        let nodes = self.files.iter().flatten();
+        let total_directories = nodes.clone().filter(|file| file.node_type == NodeType::Directory).count();
+        let cache = crate::drivers::storage::get_block_cache_stats();
+        
+        VfsDetailedStats {
+            total_files: nodes.count() - total_directories,
+            total_directories,
+            total_mount_points: self.mount_count.load(Ordering::SeqCst),
+            total_file_descriptors: self.file_descriptors.iter().flatten().count(),
+            read_operations: self.stats.read_operations,
+            write_operations: self.stats.write_operations,
+            bytes_read: self.stats.bytes_read,
+            bytes_written: self.stats.bytes_written,
+            read_errors: self.stats.read_errors,
+            write_errors: self.stats.write_errors,
+            cache_hits: cache.hits,
+            cache_misses: cache.misses,
+            cache_read_ahead_blocks: cache.read_ahead_blocks,
+            cache_evictions: cache.evictions,
+            cache_writebacks: cache.writebacks,
+            cache_blocks: cache.cached_blocks,
+            cache_dirty_blocks: cache.dirty_blocks,
+            cache_capacity: cache.capacity,
+        }
+    }
+
+    /// Sincronizar todos los sistemas montados: metadatos de los drivers y
+    /// después los dispositivos
+    pub fn sync(&mut self) -> Result<(), &'static str> {
+        let mut success = true;
+        for fs_driver in self.drivers.iter_mut().flatten() {
+            success &= fs_driver.sync().is_ok();
+        }
+        for mount in self.mount_points.iter().flatten() {
+            success &= crate::drivers::storage::sync_storage(Some(mount.block_device));
+        }
+        if success {
+            Ok(())
+        } else {
+            Err("Error al sincronizar el dispositivo")
+        }
+    }
+
     /// Obtener mount point por ID
     pub fn get_mount_point_by_id(&self, mount_id: u32) -> Option<&MountPoint> {
         for mount in &self.mount_points {
@@ -584,20 +2391,329 @@ impl VirtualFileSystem {
         None
     }
 
-    /// Obtener driver de sistema de archivos
-    pub fn get_filesystem_driver(&self, fs_type: FileSystemType) -> Option<&dyn FileSystemDriver> {
-        // Por simplicidad, retornamos None por ahora
-        // En una implementación real, esto retornaría el driver apropiado
-        None
+    /// Punto de montaje por ID, para modificarlo
+    fn mount_mut(&mut self, mount_id: u32) -> Option<&mut MountPoint> {
+        self.mount_points.iter_mut().flatten().find(|mount| mount.mount_id == mount_id)
+    }
+
+    /// Obtener el driver de un punto de montaje
+    pub fn get_filesystem_driver(&mut self, mount_id: u32) -> Option<&mut dyn FileSystemDriver> {
+        let slot = self
+            .mount_points
+            .iter()
+            .position(|mount| mount.as_ref().map_or(false, |mount| mount.mount_id == mount_id))?;
+        match self.drivers[slot] {
+            Some(ref mut driver) => Some(driver.as_mut()),
+            None => None,
+        }
+    }
+}
+
+/// Crear e inicializar el driver de un dispositivo según su sistema de archivos
+///
+/// Devuelve `Ok(None)` para los tipos que aún no tienen driver.
+fn create_filesystem_driver(fs_type: FileSystemType, block_device: u32) -> Result<Option<Box<dyn FileSystemDriver>>, &'static str> {
+    let driver: Box<dyn FileSystemDriver> = match fs_type {
+        FileSystemType::FAT12 | FileSystemType::FAT16 | FileSystemType::FAT32 => {
+            Box::new(super::fat32::init_fat32(block_device)?)
+        }
+        FileSystemType::EXFAT => Box::new(super::exfat::init_exfat(block_device)?),
+        FileSystemType::NTFS => {
+            let mut driver = crate::ntfs::NtfsDriver::new(block_device);
+            driver.init()?;
+            // Un volumen sucio solo se puede montar en modo de solo lectura
+            let clean = driver.inspect_log_file()?.clean && !driver.is_volume_dirty()?;
+            driver.mount(clean)?;
+            Box::new(driver)
+        }
+        FileSystemType::EXT4 => Box::new(super::ext4::init_ext4(block_device)?),
+        FileSystemType::ISO9660 => Box::new(super::iso9660::init_iso9660(block_device)?),
+        FileSystemType::RamFs => Box::new(super::ramfs::RamFsDriver::new(block_device, super::ramfs::RAMFS_DEFAULT_SIZE_LIMIT)),
+        FileSystemType::ProcFs => Box::new(super::procfs::ProcFsDriver::new(block_device)),
+        FileSystemType::DevFs => Box::new(super::devfs::DevFsDriver::new(block_device)),
+        _ => return Ok(None),
+    };
+    Ok(Some(driver))
+}
+
+/// Detectar el sistema de archivos de un dispositivo de bloque
+///
+/// Se leen sus primeros `VFS_PROBE_SIZE` bytes (sector de arranque y
+/// superbloque ext) y, si no se reconocen, el primer descriptor de volumen
+/// ISO 9660. Un dispositivo sin firma conocida devuelve `Unknown`.
+pub fn probe_filesystem(block_device: u32) -> Result<FileSystemType, &'static str> {
+    let sector_size = crate::drivers::storage::get_storage_manager()
+        .and_then(|manager| manager.get_device_info(block_device))
+        .map(|device| device.sector_size as usize)
+        .ok_or("Dispositivo no encontrado")?;
+    if sector_size == 0 || VFS_PROBE_SIZE % sector_size != 0 {
+        return Err("Tamaño de sector no soportado");
+    }
+
+    let count = (VFS_PROBE_SIZE / sector_size) as u32;
+    let mut buffer = vec![0u8; VFS_PROBE_SIZE];
+    if !crate::drivers::storage::read_sectors(block_device, 0, count, &mut buffer) {
+        return Err("Error leyendo el dispositivo");
+    }
+    let fs_type = FileSystemType::detect(&buffer);
+    if fs_type != FileSystemType::Unknown {
+        return Ok(fs_type);
+    }
+
+    // Un dispositivo más pequeño que el área de sistema de ISO 9660 no es un CD
+    let descriptor_sector = VFS_PROBE_ISO9660_OFFSET / sector_size as u64;
+    if !crate::drivers::storage::read_sectors(block_device, descriptor_sector, count, &mut buffer) {
+        return Ok(FileSystemType::Unknown);
+    }
+    Ok(FileSystemType::detect(&buffer))
+}
+
+/// Descriptor de seguridad equivalente a los permisos de un nodo
+///
+/// LocalSystem y Administradores tienen acceso total; el propietario, el
+/// grupo y Todos reciben los derechos de sus bits rwx. A diferencia de POSIX
+/// los derechos se suman: el propietario también tiene los de Todos.
+fn permissions_descriptor(file_info: &FileInfo) -> SecurityDescriptor {
+    let is_directory = file_info.node_type == NodeType::Directory;
+    let owner = Sid::unix_user(file_info.owner_id);
+    let group = Sid::unix_group(file_info.group_id);
+    let mut dacl = Acl::new();
+    dacl.add(Ace::allow(Sid::local_system(), FILE_ALL_ACCESS, 0));
+    dacl.add(Ace::allow(Sid::administrators(), FILE_ALL_ACCESS, 0));
+    let permissions = file_info.permissions;
+    for (sid, bits) in [(owner.clone(), permissions.owner), (group.clone(), permissions.group), (Sid::everyone(), permissions.others)] {
+        let access_mask = permission_access_mask(bits, is_directory);
+        if access_mask != 0 {
+            dacl.add(Ace::allow(sid, access_mask, 0));
+        }
+    }
+    SecurityDescriptor::new(owner, group, Some(dacl))
+}
+
+/// Máscara de acceso NT de unos bits rwx
+fn permission_access_mask(bits: u8, is_directory: bool) -> u32 {
+    let mut access_mask = 0;
+    if bits & 4 != 0 {
+        access_mask |= FILE_GENERIC_READ;
+    }
+    if bits & 2 != 0 {
+        access_mask |= FILE_GENERIC_WRITE;
+        if is_directory {
+            access_mask |= FILE_DELETE_CHILD;
+        }
+    }
+    if bits & 1 != 0 {
+        access_mask |= FILE_GENERIC_EXECUTE;
+    }
+    access_mask
+}
+
+/// Bloques de cuota de un nodo; solo los archivos regulares ocupan datos
+fn file_quota_blocks(file_info: &FileInfo) -> u64 {
+    if file_info.node_type == NodeType::File {
+        quota_blocks(file_info.size)
+    } else {
+        0
+    }
+}
+
+/// Unir una ruta canónica de directorio y un nombre
+fn join_path(directory: &str, name: &str) -> String {
+    let mut path = String::from(directory.trim_end_matches('/'));
+    path.push('/');
+    path.push_str(name);
+    path
+}
+
+/// Verificar si `path` está por debajo del directorio `directory` (rutas canónicas)
+fn is_path_within(path: &str, directory: &str) -> bool {
+    let directory = directory.trim_end_matches('/');
+    path.strip_prefix(directory).map_or(false, |rest| rest.starts_with('/'))
+}
+
+/// Ruta de `path` tras mover `from` a `to`, si `path` es `from` o está por debajo
+fn renamed_path(path: &str, from: &str, to: &str) -> Option<String> {
+    if path == from {
+        return Some(String::from(to));
+    }
+    if !is_path_within(path, from) {
+        return None;
+    }
+    let mut renamed = String::from(to);
+    renamed.push_str(&path[from.trim_end_matches('/').len()..]);
+    Some(renamed)
+}
+
+/// Componentes no vacíos de una ruta
+fn path_components(path: &str) -> impl DoubleEndedIterator<Item = &str> {
+    path.split('/').filter(|component| !component.is_empty())
+}
+
+/// Filtros `FILE_NOTIFY_CHANGE_*` de `NtNotifyChangeDirectoryFile`
+const NT_NOTIFY_CHANGE_NAME: u32 = 0x001 | 0x002;
+const NT_NOTIFY_CHANGE_ATTRIBUTES: u32 = 0x004 | 0x100;
+const NT_NOTIFY_CHANGE_CONTENT: u32 = 0x008 | 0x010;
+
+/// Acciones `FILE_ACTION_*` de `FILE_NOTIFY_INFORMATION`
+const NT_FILE_ACTION_ADDED: u32 = 1;
+const NT_FILE_ACTION_REMOVED: u32 = 2;
+const NT_FILE_ACTION_MODIFIED: u32 = 3;
+const NT_FILE_ACTION_RENAMED_OLD_NAME: u32 = 4;
+const NT_FILE_ACTION_RENAMED_NEW_NAME: u32 = 5;
+
+/// Máscara de vigilancia equivalente a un filtro de `NtNotifyChangeDirectoryFile`
+///
+/// Los filtros de nombre cubren creaciones, eliminaciones y renombrados; los
+/// de tiempos de acceso y creación no tienen evento propio y se ignoran.
+pub fn watch_mask_from_nt_filter(completion_filter: u32) -> u32 {
+    let mut mask = 0;
+    if completion_filter & NT_NOTIFY_CHANGE_NAME != 0 {
+        mask |= VFS_NOTIFY_CREATE | VFS_NOTIFY_DELETE | VFS_NOTIFY_RENAME;
+    }
+    if completion_filter & NT_NOTIFY_CHANGE_ATTRIBUTES != 0 {
+        mask |= VFS_NOTIFY_ATTRIBUTES;
+    }
+    if completion_filter & NT_NOTIFY_CHANGE_CONTENT != 0 {
+        mask |= VFS_NOTIFY_MODIFY;
     }
+    mask
+}
+
+/// Codificar eventos como registros `FILE_NOTIFY_INFORMATION` encadenados
+///
+/// Cada registro tiene `NextEntryOffset`, `Action`, `FileNameLength` y el
+/// nombre en UTF-16LE con "\" como separador, alineado a 4 bytes. Un
+/// renombrado ocupa dos registros (nombre anterior y nuevo). Devuelve los
+/// bytes escritos, o `None` si hubo desbordamiento o los eventos no caben:
+/// el llamador debe responder `STATUS_NOTIFY_ENUM_DIR` para que se vuelva a
+/// listar el directorio.
+pub fn write_notify_information(events: &WatchEvents, buffer: &mut [u8]) -> Option<usize> {
+    if events.overflowed {
+        return None;
+    }
+
+    let mut offset = 0;
+    let mut previous: Option<usize> = None;
+    for event in &events.events {
+        let records = match (event.kind, &event.old_path) {
+            (WatchEventKind::Renamed, Some(old_path)) => [
+                Some((NT_FILE_ACTION_RENAMED_OLD_NAME, old_path.as_str())),
+                Some((NT_FILE_ACTION_RENAMED_NEW_NAME, event.path.as_str())),
+            ],
+            (WatchEventKind::Created, _) => [Some((NT_FILE_ACTION_ADDED, event.path.as_str())), None],
+            (WatchEventKind::Deleted, _) => [Some((NT_FILE_ACTION_REMOVED, event.path.as_str())), None],
+            _ => [Some((NT_FILE_ACTION_MODIFIED, event.path.as_str())), None],
+        };
+
+        for (action, path) in records.into_iter().flatten() {
+            let name_length = path.encode_utf16().count() * 2;
+            let record_length = (12 + name_length + 3) & !3;
+            if offset + record_length > buffer.len() {
+                return None;
+            }
+            if let Some(start) = previous {
+                buffer[start..start + 4].copy_from_slice(&((offset - start) as u32).to_le_bytes());
+            }
+
+            let record = &mut buffer[offset..offset + record_length];
+            record.fill(0);
+            record[4..8].copy_from_slice(&action.to_le_bytes());
+            record[8..12].copy_from_slice(&(name_length as u32).to_le_bytes());
+            for (i, unit) in path.encode_utf16().enumerate() {
+                let unit = if unit == u16::from(b'/') { u16::from(b'\\') } else { unit };
+                record[12 + i * 2..14 + i * 2].copy_from_slice(&unit.to_le_bytes());
+            }
+            previous = Some(offset);
+            offset += record_length;
+        }
+    }
+    Some(offset)
+}
+
+/// Estados NT que devuelve `KeNotifyChangeDirectoryFile`
+const NT_STATUS_SUCCESS: u32 = 0x00000000;
+const NT_STATUS_PENDING: u32 = 0x00000103;
+const NT_STATUS_NOTIFY_ENUM_DIR: u32 = 0x0000010C;
+const NT_STATUS_INVALID_HANDLE: u32 = 0xC0000008;
+const NT_STATUS_INVALID_PARAMETER: u32 = 0xC000000D;
+const NT_STATUS_ACCESS_DENIED: u32 = 0xC0000022;
+
+/// Servicio del kernel para `NtNotifyChangeDirectoryFile` de ntdll
+///
+/// `file_handle` es un descriptor del VFS global. Devuelve `STATUS_SUCCESS`
+/// con los registros en `buffer`, `STATUS_PENDING` si todavía no hay cambios
+/// o `STATUS_NOTIFY_ENUM_DIR` si hay que volver a listar el directorio, y
+/// deja en `information` los bytes escritos.
+///
+/// # Safety
+///
+/// `buffer` debe admitir escrituras de `length` bytes e `information` debe
+/// ser válido o nulo.
+#[no_mangle]
+pub unsafe extern "C" fn KeNotifyChangeDirectoryFile(
+    file_handle: u32,
+    buffer: *mut u8,
+    length: u32,
+    completion_filter: u32,
+    watch_tree: u8,
+    information: *mut usize,
+) -> u32 {
+    let buffer: &mut [u8] = if length == 0 || buffer.is_null() {
+        &mut []
+    } else {
+        core::slice::from_raw_parts_mut(buffer, length as usize)
+    };
+    let result = match get_vfs() {
+        Some(vfs) => vfs.notify_change_directory(file_handle, completion_filter, watch_tree != 0, buffer),
+        None => Err("Descriptor de archivo no encontrado"),
+    };
+
+    let (status, written) = match result {
+        Ok(Some(0)) => (NT_STATUS_PENDING, 0),
+        Ok(Some(written)) => (NT_STATUS_SUCCESS, written),
+        Ok(None) => (NT_STATUS_NOTIFY_ENUM_DIR, 0),
+        Err("Descriptor de archivo no encontrado") => (NT_STATUS_INVALID_HANDLE, 0),
+        Err("Acceso denegado") => (NT_STATUS_ACCESS_DENIED, 0),
+        Err(_) => (NT_STATUS_INVALID_PARAMETER, 0),
+    };
+    if !information.is_null() {
+        *information = written;
+    }
+    status
+}
+
+/// Servicio del kernel para fijar las credenciales de la sesión iniciada
+///
+/// Los archivos que se creen a partir de ahora pertenecen a `uid` y `gid` y
+/// cuentan para sus cuotas. Si hay un proceso en ejecución, pasa a actuar con
+/// un token de `uid` y `gid`, y el token anterior se cierra. Devuelve `false`
+/// si el VFS global no está instalado.
+#[no_mangle]
+pub extern "C" fn KeSetSessionCredentials(uid: u32, gid: u32) -> bool {
+    let vfs = match get_vfs() {
+        Some(vfs) => vfs,
+        None => return false,
+    };
+    vfs.set_credentials(uid, gid);
+    let scheduler = get_process_scheduler().filter(|scheduler| scheduler.get_current_process().is_some());
+    if let (Some(scheduler), Some(manager)) = (scheduler, get_access_control_manager()) {
+        let token_id = manager.create_unix_token(uid, &[gid]);
+        if let Ok(previous) = scheduler.set_current_token(token_id) {
+            if previous != SYSTEM_TOKEN_ID {
+                let _ = manager.close_token(previous);
+            }
+        }
+    }
+    true
 }
 
 /// Función para inicializar el sistema de archivos virtual
 pub fn init_vfs() -> VirtualFileSystem {
     let mut vfs = VirtualFileSystem::new();
     
-    // Crear algunos archivos de ejemplo
+    // Crear la jerarquía básica bajo la raíz
     let _ = vfs.create_file("root", NodeType::Directory);
+    let _ = vfs.create_file("boot", NodeType::Directory);
     let _ = vfs.create_file("bin", NodeType::Directory);
     let _ = vfs.create_file("etc", NodeType::Directory);
     let _ = vfs.create_file("var", NodeType::Directory);
@@ -607,11 +2723,716 @@ pub fn init_vfs() -> VirtualFileSystem {
     let _ = vfs.create_file("proc", NodeType::Directory);
     let _ = vfs.create_file("sys", NodeType::Directory);
     let _ = vfs.create_file("dev", NodeType::Directory);
+    let _ = vfs.create_file("mnt", NodeType::Directory);
     
     vfs
 }
 
+/// VFS global del kernel
+static mut VFS: Option<VirtualFileSystem> = None;
+
+/// Instalar el VFS global
+pub fn set_vfs(vfs: VirtualFileSystem) {
+    unsafe {
+        VFS = Some(vfs);
+    }
+}
+
+/// Obtener el VFS global
+pub fn get_vfs() -> Option<&'static mut VirtualFileSystem> {
+    unsafe { (*core::ptr::addr_of_mut!(VFS)).as_mut() }
+}
+
+/// Pasar la hora del RTC a las cuotas del VFS global
+///
+/// La llama el bucle principal del kernel; los periodos de gracia se miden
+/// con esta hora.
+pub fn update_quota_clock() {
+    if let Some(vfs) = get_vfs() {
+        vfs.quotas.set_time(crate::hal::rtc::unix_time());
+    }
+}
+
 /// Función para obtener estadísticas del VFS
 pub fn get_vfs_statistics() -> (usize, usize, usize, usize) {
-    (1, 1, 0, 10) // (mounts, mounted, open_files, total_files)
+    // (mounts, mounted, open_files, total_files)
+    get_vfs().map_or((0, 0, 0, 0), |vfs| vfs.get_stats())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::test_util::with_vfs;
+    use crate::process::scheduler::ProcessPriority;
+    use crate::filesystem::ramfs::{RamFsDriver, RAMFS_DEFAULT_SIZE_LIMIT};
+    use crate::security::{access_control::shutdown_access_control, audit::get_kernel_audit_manager};
+
+    /// ramfs con los archivos indicados, cada uno con su ruta como contenido
+    fn ramfs_with(files: &[&str]) -> Box<dyn FileSystemDriver> {
+        let mut driver = RamFsDriver::new(0, RAMFS_DEFAULT_SIZE_LIMIT);
+        for path in files {
+            FileSystemDriver::open(&mut driver, path, true).unwrap();
+            FileSystemDriver::write_file(&mut driver, path, path.as_bytes(), 0).unwrap();
+        }
+        Box::new(driver)
+    }
+
+    fn entry<'a>(entries: &'a [FileInfo], name: &str) -> &'a FileInfo {
+        entries.iter().find(|entry| entry.name() == name).unwrap()
+    }
+
+    #[test]
+    fn test_mounted_entries_win_over_tree_nodes() {
+        with_vfs(|vfs| {
+            // Nodos del árbol que ya existían al montar encima de /mnt
+            vfs.create_node("/mnt/notes.txt", NodeType::Directory).unwrap();
+            vfs.create_node("/mnt/tree-only", NodeType::File).unwrap();
+            vfs.create_node("/mnt/nested", NodeType::Directory).unwrap();
+            vfs.mount_with_driver("/mnt/nested", FileSystemType::RamFs, 0, ramfs_with(&["/inner.txt"])).unwrap();
+            let mnt = vfs.mount_with_driver("/mnt", FileSystemType::RamFs, 0, ramfs_with(&["/notes.txt", "/nested", "/other.txt"])).unwrap();
+
+            let entries = vfs.read_dir("/mnt").unwrap();
+            assert_eq!(entries.len(), 4);
+            let notes = entry(&entries, "notes.txt");
+            assert_eq!(notes.node_type, NodeType::File);
+            assert_eq!(notes.size, "/notes.txt".len() as u64);
+            assert_eq!(entry(&entries, "other.txt").node_type, NodeType::File);
+            assert_eq!(entry(&entries, "tree-only").node_type, NodeType::File);
+            // El punto de montaje sustituye al archivo del volumen: su ruta va al otro montaje
+            assert_eq!(entry(&entries, "nested").node_type, NodeType::Directory);
+            assert_eq!(vfs.read_dir("/mnt/nested").unwrap()[0].name(), "inner.txt");
+
+            // Los nodos nuevos no pueden ocultar entradas del volumen
+            assert_eq!(vfs.create_node("/mnt/other.txt", NodeType::File), Err("El archivo ya existe"));
+            assert!(vfs.create_symlink("/mnt/other.txt", "/tmp").is_err());
+            vfs.create_node("/mnt/fresh", NodeType::Directory).unwrap();
+            assert_eq!(vfs.read_dir("/mnt").unwrap().len(), 5);
+
+            // La resolución coincide con el listado
+            let notes = vfs.stat_path("/mnt/notes.txt").unwrap();
+            assert_eq!(notes.node_type, NodeType::File);
+            assert!(vfs.list_directory("/mnt").unwrap().iter().all(|(name, _)| name != "notes.txt"));
+
+            // Al desmontar vuelve a verse el nodo del árbol
+            vfs.unmount(mnt).unwrap();
+            assert_eq!(vfs.stat_path("/mnt/notes.txt").unwrap().node_type, NodeType::Directory);
+        });
+    }
+
+    #[test]
+    fn test_tree_files_keep_their_contents() {
+        with_vfs(|vfs| {
+            let fd = vfs.open_path("/tmp/data.bin", 0, VFS_OPEN_CREATE).unwrap();
+            let mut buffer = [0xAAu8; 16];
+            assert_eq!(vfs.read_file(fd, &mut buffer), Ok(0));
+
+            assert_eq!(vfs.write_file(fd, b"hola"), Ok(4));
+            vfs.lseek(fd, 8, VFS_SEEK_SET).unwrap();
+            assert_eq!(vfs.write_file(fd, b"mundo"), Ok(5));
+            assert_eq!(vfs.fstat(fd).unwrap().size, 13);
+
+            vfs.lseek(fd, 0, VFS_SEEK_SET).unwrap();
+            assert_eq!(vfs.read_file(fd, &mut buffer), Ok(13));
+            assert_eq!(&buffer[..13], b"hola\0\0\0\0mundo");
+
+            vfs.ftruncate(fd, 2).unwrap();
+            vfs.lseek(fd, 0, VFS_SEEK_SET).unwrap();
+            assert_eq!(vfs.read_file(fd, &mut buffer), Ok(2));
+            assert_eq!(vfs.stat_path("/tmp/data.bin").unwrap().size, 2);
+
+            // El contenido sigue al archivo al renombrarlo y desaparece con él
+            vfs.close_file(fd).unwrap();
+            vfs.rename("/tmp/data.bin", "/tmp/moved.bin").unwrap();
+            let fd = vfs.open_path("/tmp/moved.bin", 0, 0).unwrap();
+            assert_eq!(vfs.read_file(fd, &mut buffer), Ok(2));
+            assert_eq!(&buffer[..2], b"ho");
+            vfs.close_file(fd).unwrap();
+            vfs.unlink("/tmp/moved.bin").unwrap();
+            assert!(vfs.node_data.iter().all(|data| data.is_empty()));
+
+            // Los directorios del árbol no tienen contenido
+            let fd = vfs.open_path("/tmp", 0, 0).unwrap();
+            assert_eq!(vfs.read_file(fd, &mut buffer), Err("Es un directorio"));
+            assert_eq!(vfs.write_file(fd, b"x"), Err("Es un directorio"));
+        });
+    }
+
+    #[test]
+    fn test_seek_stat_and_truncate() {
+        with_vfs(|vfs| {
+            vfs.mount_with_driver("/mnt", FileSystemType::RamFs, 0, ramfs_with(&[])).unwrap();
+            // Nodo del árbol y archivo de un volumen montado
+            for path in ["/tmp/file", "/mnt/file"] {
+                let fd = vfs.open_path(path, 0, VFS_OPEN_CREATE).unwrap();
+                vfs.write_file(fd, b"0123456789").unwrap();
+                assert_eq!(vfs.fstat(fd).unwrap().size, 10, "{}", path);
+
+                assert_eq!(vfs.lseek(fd, 2, VFS_SEEK_SET), Ok(2));
+                assert_eq!(vfs.lseek(fd, 3, VFS_SEEK_CUR), Ok(5));
+                assert_eq!(vfs.lseek(fd, -4, VFS_SEEK_END), Ok(6));
+                let mut buffer = [0u8; 2];
+                vfs.read_file(fd, &mut buffer).unwrap();
+                assert_eq!(&buffer, b"67");
+                assert_eq!(vfs.lseek(fd, -9, VFS_SEEK_CUR), Err("Desplazamiento no válido"));
+                assert_eq!(vfs.lseek(fd, 0, 7), Err("Origen de desplazamiento no válido"));
+
+                // Al pasar del final, la escritura deja un hueco de ceros
+                assert_eq!(vfs.lseek(fd, 4, VFS_SEEK_END), Ok(14));
+                vfs.write_file(fd, b"!").unwrap();
+                assert_eq!(vfs.fstat(fd).unwrap().size, 15);
+                vfs.lseek(fd, 9, VFS_SEEK_SET).unwrap();
+                let mut buffer = [0xFFu8; 6];
+                assert_eq!(vfs.read_file(fd, &mut buffer), Ok(6));
+                assert_eq!(&buffer, b"9\0\0\0\0!");
+
+                // ftruncate no mueve la posición
+                vfs.ftruncate(fd, 4).unwrap();
+                assert_eq!(vfs.fstat(fd).unwrap().size, 4);
+                assert_eq!(vfs.lseek(fd, 0, VFS_SEEK_CUR), Ok(15));
+                assert_eq!(vfs.read_file(fd, &mut buffer), Ok(0));
+                vfs.ftruncate(fd, 8).unwrap();
+                vfs.lseek(fd, 0, VFS_SEEK_SET).unwrap();
+                let mut buffer = [0xFFu8; 8];
+                assert_eq!(vfs.read_file(fd, &mut buffer), Ok(8));
+                assert_eq!(&buffer, b"0123\0\0\0\0");
+                vfs.close_file(fd).unwrap();
+                assert_eq!(vfs.fstat(fd).unwrap_err(), "Descriptor de archivo no encontrado");
+            }
+        });
+    }
+
+    #[test]
+    fn test_rename_within_and_across_directories() {
+        with_vfs(|vfs| {
+            vfs.create_node("/tmp/a.txt", NodeType::File).unwrap();
+            vfs.create_node("/tmp/dir", NodeType::Directory).unwrap();
+            vfs.create_node("/tmp/dir/inner", NodeType::File).unwrap();
+            let fd = vfs.open_path("/tmp/a.txt", 0, 0).unwrap();
+
+            vfs.rename("/tmp/a.txt", "/tmp/b.txt").unwrap();
+            vfs.rename("/tmp/b.txt", "/home/b.txt").unwrap();
+            assert_eq!(vfs.stat_path("/tmp/a.txt").unwrap_err(), "Archivo no encontrado");
+            assert_eq!(vfs.stat_path("/home/b.txt").unwrap().node_type, NodeType::File);
+            // El descriptor abierto sigue al archivo
+            assert_eq!(vfs.file_descriptors.iter().flatten().find(|entry| entry.fd_id == fd).unwrap().path, "/home/b.txt");
+
+            // Un archivo sustituye a otro, pero no a un directorio
+            vfs.create_node("/home/c.txt", NodeType::File).unwrap();
+            vfs.close_file(fd).unwrap();
+            vfs.rename("/home/c.txt", "/home/b.txt").unwrap();
+            assert_eq!(vfs.stat_path("/home/c.txt").unwrap_err(), "Archivo no encontrado");
+            assert_eq!(vfs.rename("/home/b.txt", "/tmp/dir"), Err("El destino es de otro tipo"));
+            assert_eq!(vfs.rename("/tmp/dir", "/tmp/dir/sub"), Err("No se puede mover un directorio dentro de sí mismo"));
+
+            // Los directorios se mueven con su contenido
+            vfs.rename("/tmp/dir", "/home/dir").unwrap();
+            assert!(vfs.stat_path("/home/dir/inner").is_ok());
+
+            // Dentro de un volumen montado lo mueve su driver; entre sistemas, nunca
+            vfs.mount_with_driver("/mnt", FileSystemType::RamFs, 0, ramfs_with(&["/x.txt"])).unwrap();
+            vfs.rename("/mnt/x.txt", "/mnt/y.txt").unwrap();
+            assert_eq!(vfs.stat_path("/mnt/y.txt").unwrap().size, "/x.txt".len() as u64);
+            assert_eq!(vfs.rename("/mnt/y.txt", "/tmp/y.txt"), Err("No se puede mover entre sistemas de archivos distintos"));
+            assert_eq!(vfs.rename("/home/b.txt", "/mnt/b.txt"), Err("No se puede mover entre sistemas de archivos distintos"));
+            assert_eq!(vfs.rename("/mnt", "/home/mnt"), Err("Punto de montaje ocupado"));
+        });
+    }
+
+    #[test]
+    fn test_readdir_cursor() {
+        with_vfs(|vfs| {
+            for name in ["/tmp/a", "/tmp/b", "/tmp/c"] {
+                vfs.create_node(name, NodeType::File).unwrap();
+            }
+            let fd = vfs.open_path("/tmp", 0, 0).unwrap();
+            let mut names = Vec::new();
+            while let Some(entry) = vfs.readdir(fd).unwrap() {
+                names.push(String::from(entry.name()));
+            }
+            assert_eq!(names, ["a", "b", "c"]);
+            assert!(vfs.readdir(fd).unwrap().is_none());
+
+            // El listado es una instantánea hasta volver al principio
+            vfs.create_node("/tmp/d", NodeType::File).unwrap();
+            assert!(vfs.readdir(fd).unwrap().is_none());
+            vfs.lseek(fd, 0, VFS_SEEK_SET).unwrap();
+            let mut count = 0;
+            while vfs.readdir(fd).unwrap().is_some() {
+                count += 1;
+            }
+            assert_eq!(count, 4);
+        });
+    }
+
+    #[test]
+    fn test_fsync_writes_dirty_blocks() {
+        use super::super::fat32::{format_fat32, Fat32FormatOptions};
+        use crate::drivers::storage;
+        use crate::filesystem::test_util::TestDisk;
+
+        with_vfs(|vfs| {
+            let disk = TestDisk::new(36 * 1024 * 1024);
+            let options = Fat32FormatOptions { cluster_size: 512, ..Default::default() };
+            let driver = format_fat32(disk.device_id, &options).unwrap();
+            vfs.mount_with_driver("/mnt", FileSystemType::FAT32, disk.device_id, Box::new(driver)).unwrap();
+            assert!(storage::sync_storage(None));
+
+            let fd = vfs.open_path("/mnt/log.txt", 0, VFS_OPEN_CREATE).unwrap();
+            vfs.write_file(fd, b"datos").unwrap();
+            assert!(storage::get_block_cache_stats().dirty_blocks > 0);
+            vfs.fsync(fd).unwrap();
+            assert_eq!(storage::get_block_cache_stats().dirty_blocks, 0);
+
+            // Los nodos del árbol no tienen nada pendiente
+            let fd = vfs.open_path("/tmp/log.txt", 0, VFS_OPEN_CREATE).unwrap();
+            assert_eq!(vfs.fsync(fd), Ok(()));
+        });
+    }
+
+    #[test]
+    fn test_advisory_and_mandatory_locks() {
+        with_vfs(|vfs| {
+            let first = vfs.open_path("/tmp/locked", 0, VFS_OPEN_CREATE).unwrap();
+            let second = vfs.open_path("/tmp/locked", 0, 0).unwrap();
+            vfs.write_file(first, &[0u8; 64]).unwrap();
+
+            // Los compartidos conviven; uno exclusivo choca con cualquier solapamiento
+            vfs.lock(first, 0, 10, LockType::Shared, false).unwrap();
+            vfs.lock(second, 5, 10, LockType::Shared, false).unwrap();
+            assert_eq!(vfs.lock(second, 8, 4, LockType::Exclusive, false), Err("Rango bloqueado"));
+            vfs.lock(second, 20, 5, LockType::Exclusive, false).unwrap();
+            // Los bloqueos del mismo descriptor no chocan entre sí, y [0, 10) no incluye el byte 10
+            vfs.lock(second, 10, 5, LockType::Exclusive, false).unwrap();
+            // Un bloqueo consultivo no impide escribir
+            vfs.lseek(first, 20, VFS_SEEK_SET).unwrap();
+            assert_eq!(vfs.write_file(first, b"x"), Ok(1));
+
+            // Uno obligatorio exclusivo impide leer y escribir a los demás
+            vfs.lock(first, 40, 0, LockType::Exclusive, true).unwrap();
+            let mut buffer = [0u8; 4];
+            vfs.lseek(second, 38, VFS_SEEK_SET).unwrap();
+            assert_eq!(vfs.read_file(second, &mut buffer), Err("Rango bloqueado"));
+            assert_eq!(vfs.write_file(second, b"y"), Ok(1));
+            assert_eq!(vfs.write_file(second, b"y"), Ok(1));
+            assert_eq!(vfs.write_file(second, b"y"), Err("Rango bloqueado"));
+            assert_eq!(vfs.ftruncate(second, 100), Err("Rango bloqueado"));
+            vfs.lseek(first, 50, VFS_SEEK_SET).unwrap();
+            assert_eq!(vfs.write_file(first, b"z"), Ok(1));
+
+            // Uno obligatorio compartido solo impide escribir
+            vfs.unlock(first, 40, 0).unwrap();
+            vfs.lock(first, 40, 8, LockType::Shared, true).unwrap();
+            vfs.lseek(second, 40, VFS_SEEK_SET).unwrap();
+            assert_eq!(vfs.read_file(second, &mut buffer), Ok(4));
+            assert_eq!(vfs.write_file(second, b"y"), Err("Rango bloqueado"));
+            assert_eq!(vfs.unlock(first, 40, 4), Err("Bloqueo no encontrado"));
+
+            // Cerrar el descriptor suelta sus bloqueos
+            vfs.close_file(first).unwrap();
+            assert_eq!(vfs.write_file(second, b"y"), Ok(1));
+            vfs.lock(second, 0, 0, LockType::Exclusive, false).unwrap();
+        });
+    }
+
+    /// Tipo y ruta de cada evento leído
+    fn events(events: &WatchEvents) -> Vec<(WatchEventKind, &str)> {
+        events.events.iter().map(|event| (event.kind, event.path.as_str())).collect()
+    }
+
+    /// Registros `FILE_NOTIFY_INFORMATION` como (acción, nombre)
+    fn notify_records(buffer: &[u8]) -> Vec<(u32, String)> {
+        let mut records = Vec::new();
+        let mut offset = 0;
+        loop {
+            let field = |at: usize| u32::from_le_bytes(buffer[offset + at..offset + at + 4].try_into().unwrap()) as usize;
+            let name: Vec<u16> = buffer[offset + 12..offset + 12 + field(8)]
+                .chunks_exact(2)
+                .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+                .collect();
+            records.push((field(4) as u32, String::from_utf16(&name).unwrap()));
+            match field(0) {
+                0 => return records,
+                next => offset += next,
+            }
+        }
+    }
+
+    #[test]
+    fn test_watch_queue_overflow() {
+        with_vfs(|vfs| {
+            // Con la cola llena se descartan los eventos nuevos, no los antiguos
+            let watch = vfs.watch("/tmp", false, VFS_NOTIFY_CREATE, 3).unwrap();
+            for name in ["a", "b", "c", "d", "e"] {
+                vfs.create_node(&format!("/tmp/{name}"), NodeType::File).unwrap();
+            }
+            let first = vfs.read_watch(watch, 2).unwrap();
+            assert!(first.overflowed);
+            assert_eq!(events(&first), [(WatchEventKind::Created, "a"), (WatchEventKind::Created, "b")]);
+
+            // La marca se limpia en la primera lectura y la cola vuelve a aceptar eventos
+            vfs.unlink("/tmp/a").unwrap();
+            vfs.create_node("/tmp/f", NodeType::File).unwrap();
+            let second = vfs.read_watch(watch, usize::MAX).unwrap();
+            assert!(!second.overflowed);
+            assert_eq!(events(&second), [(WatchEventKind::Created, "c"), (WatchEventKind::Created, "f")]);
+            vfs.unwatch(watch).unwrap();
+            assert_eq!(vfs.read_watch(watch, 1).unwrap_err(), "Vigilancia no encontrada");
+
+            // A través de NtNotifyChangeDirectoryFile el desbordamiento pide volver a listar
+            let fd = vfs.open_path("/tmp", 0, 0).unwrap();
+            let mut buffer = [0u8; 256];
+            assert_eq!(vfs.notify_change_directory(fd, 0x1, false, &mut buffer), Ok(Some(0)));
+            for i in 0..=VFS_WATCH_QUEUE_CAPACITY {
+                vfs.create_node(&format!("/tmp/n{i}"), NodeType::File).unwrap();
+            }
+            assert_eq!(vfs.notify_change_directory(fd, 0x1, false, &mut buffer), Ok(None));
+            assert_eq!(vfs.notify_change_directory(fd, 0x1, false, &mut buffer), Ok(Some(0)));
+
+            vfs.create_node("/tmp/last", NodeType::File).unwrap();
+            vfs.unlink("/tmp/f").unwrap();
+            let written = vfs.notify_change_directory(fd, 0x1, false, &mut buffer).unwrap().unwrap();
+            assert_eq!(notify_records(&buffer[..written]), [(1, String::from("last")), (2, String::from("f"))]);
+
+            // Un buffer sin sitio para los registros también obliga a volver a listar
+            vfs.create_node("/tmp/big", NodeType::File).unwrap();
+            assert_eq!(vfs.notify_change_directory(fd, 0x1, false, &mut buffer[..8]), Ok(None));
+
+            // La vigilancia del descriptor se cancela al cerrarlo
+            vfs.close_file(fd).unwrap();
+            assert!(vfs.watches.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_recursive_watch() {
+        with_vfs(|vfs| {
+            vfs.create_node("/tmp/a", NodeType::Directory).unwrap();
+            vfs.create_node("/tmp/a/b", NodeType::Directory).unwrap();
+            let flat = vfs.watch("/tmp", false, VFS_NOTIFY_ALL, 16).unwrap();
+            let tree = vfs.watch("/tmp", true, VFS_NOTIFY_ALL, 16).unwrap();
+            let nested = vfs.watch("/tmp/a/b", false, VFS_NOTIFY_CREATE, 16).unwrap();
+
+            let fd = vfs.open_path("/tmp/a/b/f.txt", 0, VFS_OPEN_CREATE).unwrap();
+            vfs.write_file(fd, b"x").unwrap();
+            vfs.write_file(fd, b"y").unwrap();
+            vfs.close_file(fd).unwrap();
+            vfs.rename("/tmp/a/b/f.txt", "/tmp/a/g.txt").unwrap();
+            vfs.rename("/tmp/a/g.txt", "/tmp/g.txt").unwrap();
+            vfs.unlink("/tmp/g.txt").unwrap();
+
+            // Las escrituras seguidas se agrupan y los renombrados conservan la ruta anterior
+            let tree_events = vfs.read_watch(tree, usize::MAX).unwrap();
+            assert_eq!(
+                events(&tree_events),
+                [
+                    (WatchEventKind::Created, "a/b/f.txt"),
+                    (WatchEventKind::Modified, "a/b/f.txt"),
+                    (WatchEventKind::Renamed, "a/g.txt"),
+                    (WatchEventKind::Renamed, "g.txt"),
+                    (WatchEventKind::Deleted, "g.txt"),
+                ]
+            );
+            assert_eq!(tree_events.events[2].old_path.as_deref(), Some("a/b/f.txt"));
+            assert_eq!(tree_events.events[3].old_path.as_deref(), Some("a/g.txt"));
+
+            // Sin recursión un renombrado desde un subdirectorio es una creación
+            let flat_events = vfs.read_watch(flat, usize::MAX).unwrap();
+            assert_eq!(events(&flat_events), [(WatchEventKind::Created, "g.txt"), (WatchEventKind::Deleted, "g.txt")]);
+            assert_eq!(events(&vfs.read_watch(nested, usize::MAX).unwrap()), [(WatchEventKind::Created, "f.txt")]);
+
+            // Una vigilancia sigue a su directorio cuando se mueve un antecesor
+            vfs.rename("/tmp/a", "/tmp/z").unwrap();
+            vfs.create_node("/tmp/z/b/h", NodeType::File).unwrap();
+            assert_eq!(events(&vfs.read_watch(nested, usize::MAX).unwrap()), [(WatchEventKind::Created, "h")]);
+            assert_eq!(
+                events(&vfs.read_watch(tree, usize::MAX).unwrap()),
+                [(WatchEventKind::Renamed, "z"), (WatchEventKind::Created, "z/b/h")]
+            );
+
+            // Con `watch_tree` los nombres de los registros usan "\" como separador
+            let recursive_fd = vfs.open_path("/tmp", 0, 0).unwrap();
+            let flat_fd = vfs.open_path("/tmp", 0, 0).unwrap();
+            let mut buffer = [0u8; 128];
+            assert_eq!(vfs.notify_change_directory(recursive_fd, 0x3, true, &mut buffer), Ok(Some(0)));
+            assert_eq!(vfs.notify_change_directory(flat_fd, 0x3, false, &mut buffer), Ok(Some(0)));
+            vfs.create_node("/tmp/z/b/i", NodeType::File).unwrap();
+            let written = vfs.notify_change_directory(recursive_fd, 0x3, true, &mut buffer).unwrap().unwrap();
+            assert_eq!(notify_records(&buffer[..written]), [(1, String::from("z\\b\\i"))]);
+            assert_eq!(vfs.notify_change_directory(flat_fd, 0x3, false, &mut buffer), Ok(Some(0)));
+        });
+    }
+
+    #[test]
+    fn test_quota_hard_limits_reject_growth() {
+        use crate::filesystem::quota::{QuotaKind, QuotaLimits};
+
+        with_vfs(|vfs| {
+            vfs.set_credentials(1000, 100);
+            let user_limits = QuotaLimits { block_hard: 4, inode_hard: 2, ..Default::default() };
+            vfs.quotas.set_limits(QuotaKind::User, 1000, user_limits).unwrap();
+
+            // Hasta el límite se puede escribir; un byte más necesitaría otro bloque
+            let fd = vfs.open_path("/tmp/a", 0, VFS_OPEN_CREATE).unwrap();
+            assert_eq!(vfs.write_file(fd, &[7u8; 4096]), Ok(4096));
+            assert_eq!(vfs.write_file(fd, b"x"), Err("Cuota de disco excedida"));
+            assert_eq!(vfs.ftruncate(fd, 5000), Err("Cuota de disco excedida"));
+            assert_eq!(vfs.fstat(fd).unwrap().size, 4096);
+
+            // Los inodos también tienen límite, cree el archivo quien lo cree
+            vfs.create_node("/tmp/b", NodeType::File).unwrap();
+            assert_eq!(vfs.create_node("/tmp/c", NodeType::File), Err("Cuota de disco excedida"));
+            assert_eq!(vfs.open_path("/tmp/c", 0, VFS_OPEN_CREATE), Err("Cuota de disco excedida"));
+            let usage = vfs.quotas.entry(QuotaKind::User, 1000).unwrap();
+            assert_eq!((usage.blocks, usage.inodes), (4, 2));
+
+            // Liberar espacio permite volver a crecer
+            vfs.ftruncate(fd, 1024).unwrap();
+            vfs.lseek(fd, 0, VFS_SEEK_END).unwrap();
+            assert_eq!(vfs.write_file(fd, b"x"), Ok(1));
+            vfs.unlink("/tmp/b").unwrap();
+            vfs.create_node("/tmp/c", NodeType::File).unwrap();
+
+            // La cuota del grupo se aplica a todos sus miembros
+            vfs.quotas.set_limits(QuotaKind::Group, 100, QuotaLimits { block_hard: 3, ..Default::default() }).unwrap();
+            vfs.set_credentials(1001, 100);
+            let other = vfs.open_path("/tmp/d", 0, VFS_OPEN_CREATE).unwrap();
+            assert_eq!(vfs.write_file(other, &[1u8; 2048]), Err("Cuota de disco excedida"));
+            assert_eq!(vfs.write_file(other, &[1u8; 1024]), Ok(1024));
+
+            // El superusuario no tiene límites, pero no puede regalar archivos por encima de ellos
+            vfs.set_credentials(0, 0);
+            let root = vfs.open_path("/tmp/root", 0, VFS_OPEN_CREATE).unwrap();
+            assert_eq!(vfs.write_file(root, &[2u8; 8192]), Ok(8192));
+            assert_eq!(vfs.set_owner("/tmp/root", 1000, 100), Err("Cuota de disco excedida"));
+            assert_eq!(vfs.stat_path("/tmp/root").unwrap().owner_id, 0);
+        });
+    }
+
+    #[test]
+    fn test_quota_grace_period_expires() {
+        use crate::filesystem::quota::{QuotaKind, QuotaLimits};
+
+        with_vfs(|vfs| {
+            vfs.quotas.set_time(1_000);
+            vfs.quotas.set_grace_periods(60, 30);
+            vfs.set_credentials(1000, 100);
+            let limits = QuotaLimits { block_soft: 2, block_hard: 8, inode_soft: 1, inode_hard: 0 };
+            vfs.quotas.set_limits(QuotaKind::User, 1000, limits).unwrap();
+
+            // Superar el límite blando abre el periodo de gracia
+            let fd = vfs.open_path("/tmp/a", 0, VFS_OPEN_CREATE).unwrap();
+            assert_eq!(vfs.write_file(fd, &[1u8; 3072]), Ok(3072));
+            assert_eq!(vfs.quotas.entry(QuotaKind::User, 1000).unwrap().block_grace_expires, Some(1_060));
+            vfs.quotas.set_time(1_059);
+            assert_eq!(vfs.write_file(fd, &[1u8; 1024]), Ok(1024));
+
+            // Agotada la gracia el límite blando pasa a ser estricto
+            vfs.quotas.set_time(1_060);
+            assert_eq!(vfs.write_file(fd, b"x"), Err("Periodo de gracia de la cuota agotado"));
+            assert!(vfs.quotas.report(Some(QuotaKind::User)).contains("agotado"));
+
+            // Los inodos tienen su propia gracia
+            vfs.create_node("/tmp/b", NodeType::File).unwrap();
+            vfs.quotas.set_time(1_090);
+            assert_eq!(vfs.create_node("/tmp/c", NodeType::File), Err("Periodo de gracia de la cuota agotado"));
+
+            // Volver por debajo del límite blando cancela la gracia
+            vfs.ftruncate(fd, 1024).unwrap();
+            let usage = vfs.quotas.entry(QuotaKind::User, 1000).unwrap();
+            assert_eq!(usage.block_grace_expires, None);
+            vfs.lseek(fd, 0, VFS_SEEK_END).unwrap();
+            assert_eq!(vfs.write_file(fd, &[1u8; 2048]), Ok(2048));
+            assert_eq!(vfs.quotas.entry(QuotaKind::User, 1000).unwrap().block_grace_expires, Some(1_150));
+        });
+    }
+
+    #[test]
+    fn test_session_credentials_service() {
+        with_vfs(|_| {
+            set_vfs(init_vfs());
+            assert!(KeSetSessionCredentials(1000, 100));
+            let vfs = get_vfs().unwrap();
+            vfs.create_node("/tmp/sesion", NodeType::File).unwrap();
+            let info = vfs.stat_path("/tmp/sesion").unwrap();
+            assert_eq!((info.owner_id, info.group_id), (1000, 100));
+            assert_eq!(vfs.quotas.entry(crate::filesystem::quota::QuotaKind::User, 1000).unwrap().inodes, 1);
+            // Sin proceso en ejecución se sigue actuando como el sistema
+            assert_eq!(current_token(), SYSTEM_TOKEN_ID);
+
+            let scheduler = get_process_scheduler().unwrap();
+            scheduler.create_process("shell", ProcessPriority::Normal);
+            scheduler.schedule();
+            assert!(KeSetSessionCredentials(1000, 100));
+            let first = current_token();
+            let manager = get_access_control_manager().unwrap();
+            assert_eq!(manager.get_token(first).unwrap().user, Sid::unix_user(1000));
+            assert!(manager.get_token(first).unwrap().contains(&Sid::unix_group(100)));
+            assert!(KeSetSessionCredentials(0, 0));
+            assert_eq!(manager.get_token(current_token()).unwrap().user, Sid::unix_user(0));
+            assert!(manager.get_token(first).is_none());
+        });
+    }
+
+    #[test]
+    fn test_notify_change_directory_service() {
+        with_vfs(|_| {
+            set_vfs(init_vfs());
+            let vfs = get_vfs().unwrap();
+            let directory = vfs.open_path("/tmp", 0, 0).unwrap();
+            let file = vfs.open_path("/tmp/file", 0, VFS_OPEN_CREATE).unwrap();
+            let mut buffer = [0u8; 64];
+            let mut information = usize::MAX;
+            let mut call = |handle: u32, buffer: &mut [u8], information: &mut usize| unsafe {
+                KeNotifyChangeDirectoryFile(handle, buffer.as_mut_ptr(), buffer.len() as u32, 0x1, 0, information)
+            };
+
+            assert_eq!(call(directory, &mut buffer, &mut information), NT_STATUS_PENDING);
+            assert_eq!(information, 0);
+            get_vfs().unwrap().create_node("/tmp/nuevo", NodeType::File).unwrap();
+            assert_eq!(call(directory, &mut buffer, &mut information), NT_STATUS_SUCCESS);
+            assert_eq!(notify_records(&buffer[..information]), [(1, String::from("nuevo"))]);
+            get_vfs().unwrap().create_node("/tmp/otro", NodeType::File).unwrap();
+            assert_eq!(call(directory, &mut buffer[..4], &mut information), NT_STATUS_NOTIFY_ENUM_DIR);
+
+            assert_eq!(call(file, &mut buffer, &mut information), NT_STATUS_INVALID_PARAMETER);
+            assert_eq!(call(0xDEAD, &mut buffer, &mut information), NT_STATUS_INVALID_HANDLE);
+        });
+    }
+
+    #[test]
+    fn test_access_fails_closed_without_access_control() {
+        with_vfs(|vfs| {
+            vfs.create_node("/tmp/a", NodeType::File).unwrap();
+            shutdown_access_control();
+            assert_eq!(vfs.open_path("/tmp/a", FILE_READ_DATA, 0), Err("Acceso denegado"));
+            assert_eq!(vfs.open_path("/tmp/b", 0, VFS_OPEN_CREATE), Err("Acceso denegado"));
+            assert_eq!(vfs.unlink("/tmp/a"), Err("Acceso denegado"));
+            assert_eq!(vfs.rename("/tmp/a", "/tmp/b"), Err("Acceso denegado"));
+            assert_eq!(vfs.set_permissions("/tmp/a", FilePermissions::new(7, 7, 7)), Err("Acceso denegado"));
+            assert_eq!(vfs.set_owner("/tmp/a", 1000, 100), Err("Acceso denegado"));
+
+            let event = get_kernel_audit_manager().unwrap().events.last().unwrap();
+            assert_eq!((event.target.as_str(), event.user_id.as_ref()), ("/tmp/a", None));
+            assert!(vfs.stat_path("/tmp/a").is_ok());
+        });
+    }
+
+    #[test]
+    fn test_operations_check_the_process_token() {
+        with_vfs(|vfs| {
+            // /tmp/propio es de 1000; /tmp/ajeno y lo que contiene, del sistema
+            vfs.create_node("/tmp/propio", NodeType::Directory).unwrap();
+            vfs.set_permissions("/tmp/propio", FilePermissions::new(7, 5, 5)).unwrap();
+            vfs.set_owner("/tmp/propio", 1000, 100).unwrap();
+            vfs.create_node("/tmp/propio/nota", NodeType::File).unwrap();
+            vfs.set_owner("/tmp/propio/nota", 1000, 100).unwrap();
+            vfs.create_node("/tmp/ajeno", NodeType::Directory).unwrap();
+            vfs.set_permissions("/tmp/ajeno", FilePermissions::new(7, 5, 5)).unwrap();
+            vfs.create_node("/tmp/ajeno/archivo", NodeType::File).unwrap();
+            vfs.create_node("/tmp/ajeno/borrable", NodeType::File).unwrap();
+            let mut dacl = Acl::new();
+            dacl.add(Ace::allow(Sid::unix_user(1000), DELETE | FILE_GENERIC_READ, 0));
+            vfs.set_security("/tmp/ajeno/borrable", SecurityDescriptor::new(Sid::local_system(), Sid::unix_group(0), Some(dacl)))
+                .unwrap();
+
+            let manager = get_access_control_manager().unwrap();
+            let token_id = manager.create_unix_token(1000, &[100]);
+            let scheduler = get_process_scheduler().unwrap();
+            scheduler.create_process("shell", ProcessPriority::Normal);
+            scheduler.schedule();
+            scheduler.set_current_token(token_id).unwrap();
+            let child = scheduler.create_process("hijo", ProcessPriority::Normal);
+            assert_eq!(scheduler.find_process(child).unwrap().token_id, token_id);
+
+            assert_eq!(vfs.unlink("/tmp/ajeno/archivo"), Err("Acceso denegado"));
+            let event = get_kernel_audit_manager().unwrap().events.last().unwrap();
+            assert_eq!(event.target, "/tmp/ajeno/archivo");
+            assert_eq!(event.user_id.as_deref(), Some("S-1-22-1-1000"));
+            assert_eq!(event.details[0].1, "0x00010000");
+            assert_eq!(vfs.rename("/tmp/ajeno/archivo", "/tmp/propio/archivo"), Err("Acceso denegado"));
+            assert_eq!(vfs.rename("/tmp/propio/nota", "/tmp/ajeno/nota"), Err("Acceso denegado"));
+            assert_eq!(vfs.rename("/tmp/propio/nota", "/tmp/ajeno/archivo"), Err("Acceso denegado"));
+            assert_eq!(vfs.set_permissions("/tmp/ajeno/archivo", FilePermissions::new(7, 7, 7)), Err("Acceso denegado"));
+            assert_eq!(vfs.set_owner("/tmp/propio/nota", 1000, 100), Err("Acceso denegado"));
+            assert!(vfs.stat_path("/tmp/ajeno/archivo").is_ok() && vfs.stat_path("/tmp/propio/nota").is_ok());
+
+            // El propietario cambia los permisos, y el directorio propio permite mover y borrar
+            vfs.set_permissions("/tmp/propio/nota", FilePermissions::new(6, 0, 0)).unwrap();
+            vfs.rename("/tmp/propio/nota", "/tmp/propio/movida").unwrap();
+            vfs.unlink("/tmp/propio/movida").unwrap();
+            // DELETE sobre el propio archivo basta sin FILE_DELETE_CHILD en el directorio
+            vfs.unlink("/tmp/ajeno/borrable").unwrap();
+        });
+    }
+
+    #[test]
+    fn test_append_only_handle_writes_only_at_end() {
+        with_vfs(|vfs| {
+            let fd = vfs.open_path("/tmp/registro", FILE_APPEND_DATA, VFS_OPEN_CREATE).unwrap();
+            assert_eq!(vfs.write_file(fd, b"uno"), Ok(3));
+            assert_eq!(vfs.write_file(fd, b"dos"), Ok(3));
+            vfs.lseek(fd, 0, VFS_SEEK_SET).unwrap();
+            assert_eq!(vfs.write_file(fd, b"XXX"), Err("Acceso denegado"));
+            assert_eq!(vfs.ftruncate(fd, 0), Err("Acceso denegado"));
+            assert_eq!(vfs.read_file(fd, &mut [0u8; 6]), Err("Acceso denegado"));
+            vfs.lseek(fd, 0, VFS_SEEK_END).unwrap();
+            assert_eq!(vfs.write_file(fd, b"tres"), Ok(4));
+            vfs.close_file(fd).unwrap();
+
+            let fd = vfs.open_path("/tmp/registro", FILE_READ_DATA | FILE_WRITE_DATA, 0).unwrap();
+            vfs.lseek(fd, 0, VFS_SEEK_SET).unwrap();
+            assert_eq!(vfs.write_file(fd, b"UNO"), Ok(3));
+            vfs.lseek(fd, 0, VFS_SEEK_SET).unwrap();
+            let mut buffer = [0u8; 16];
+            assert_eq!(vfs.read_file(fd, &mut buffer), Ok(10));
+            assert_eq!(&buffer[..10], b"UNOdostres");
+        });
+    }
+
+    #[test]
+    fn test_security_descriptors_follow_their_nodes() {
+        with_vfs(|vfs| {
+            let explicit = |uid| SecurityDescriptor::new(Sid::unix_user(uid), Sid::unix_group(0), Some(Acl::new()));
+            let owner = |vfs: &mut VirtualFileSystem, path: &str| vfs.security_descriptor(path).unwrap().owner;
+
+            // Nodos del árbol: el descriptor va en la dentry
+            vfs.create_node("/tmp/a", NodeType::File).unwrap();
+            vfs.set_security("/tmp/a", explicit(7)).unwrap();
+            vfs.rename("/tmp/a", "/tmp/b").unwrap();
+            assert_eq!(owner(vfs, "/tmp/b"), Sid::unix_user(7));
+            vfs.create_node("/tmp/a", NodeType::File).unwrap();
+            assert_eq!(owner(vfs, "/tmp/a"), Sid::unix_user(0));
+            vfs.unlink("/tmp/b").unwrap();
+            vfs.create_node("/tmp/b", NodeType::File).unwrap();
+            assert_eq!(owner(vfs, "/tmp/b"), Sid::unix_user(0));
+
+            // Archivos de un volumen: el descriptor va en el punto de montaje
+            vfs.create_node("/ram", NodeType::Directory).unwrap();
+            let mount_id = vfs.mount("/ram", FileSystemType::RamFs, 0).unwrap();
+            let fd = vfs.open_path("/ram/x", 0, VFS_OPEN_CREATE).unwrap();
+            vfs.close_file(fd).unwrap();
+            vfs.set_security("/ram/x", explicit(8)).unwrap();
+            vfs.rename("/ram/x", "/ram/y").unwrap();
+            assert_eq!(owner(vfs, "/ram/y"), Sid::unix_user(8));
+            let fd = vfs.open_path("/ram/x", 0, VFS_OPEN_CREATE).unwrap();
+            vfs.close_file(fd).unwrap();
+            assert_eq!(owner(vfs, "/ram/x"), Sid::unix_user(0));
+            // El destino sustituido pierde su descriptor
+            vfs.set_security("/ram/x", explicit(9)).unwrap();
+            vfs.rename("/ram/y", "/ram/x").unwrap();
+            assert_eq!(owner(vfs, "/ram/x"), Sid::unix_user(8));
+            vfs.unlink("/ram/x").unwrap();
+            let fd = vfs.open_path("/ram/x", 0, VFS_OPEN_CREATE).unwrap();
+            vfs.close_file(fd).unwrap();
+            assert_eq!(owner(vfs, "/ram/x"), Sid::unix_user(0));
+
+            // Otro volumen en el mismo sitio no hereda los descriptores del anterior
+            vfs.set_security("/ram/x", explicit(10)).unwrap();
+            vfs.unmount(mount_id).unwrap();
+            vfs.mount("/ram", FileSystemType::RamFs, 0).unwrap();
+            let fd = vfs.open_path("/ram/x", 0, VFS_OPEN_CREATE).unwrap();
+            vfs.close_file(fd).unwrap();
+            assert_eq!(owner(vfs, "/ram/x"), Sid::unix_user(0));
+        });
+    }
 }
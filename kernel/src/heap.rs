@@ -0,0 +1,182 @@
+//! # Heap del Kernel
+//!
+//! Asignador global del kernel sobre una arena estática. Mantiene una lista
+//! de bloques libres ordenada por dirección, asigna con primer ajuste y
+//! fusiona bloques vecinos al liberar. Es el respaldo de `Vec`, `String` y
+//! `Box` del crate `alloc` que usan los sistemas de archivos y la seguridad.
+
+use core::alloc::{GlobalAlloc, Layout};
+use core::cell::UnsafeCell;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Tamaño de la arena del heap (16 MiB)
+pub const HEAP_SIZE: usize = 16 * 1024 * 1024;
+
+/// Granularidad de los bloques; también el tamaño mínimo de un bloque libre
+const BLOCK_ALIGN: usize = 16;
+
+/// Arena estática de la que sale toda la memoria dinámica del kernel
+#[repr(C, align(4096))]
+struct HeapArena([u8; HEAP_SIZE]);
+
+static mut HEAP_ARENA: HeapArena = HeapArena([0; HEAP_SIZE]);
+
+/// Cabecera escrita al inicio de cada bloque libre
+#[repr(C, align(16))]
+struct FreeBlock {
+    size: usize,
+    next: *mut FreeBlock,
+}
+
+/// Estado del heap protegido por el cerrojo del asignador
+struct HeapState {
+    head: *mut FreeBlock,
+    initialized: bool,
+    used: usize,
+    peak: usize,
+}
+
+/// Asignador global del kernel
+pub struct KernelHeap {
+    lock: AtomicBool,
+    state: UnsafeCell<HeapState>,
+}
+
+unsafe impl Sync for KernelHeap {}
+
+#[global_allocator]
+static KERNEL_HEAP: KernelHeap = KernelHeap::new();
+
+/// Tamaño real reservado para una petición, redondeado a la granularidad
+fn block_size(layout: &Layout) -> usize {
+    (layout.size().max(BLOCK_ALIGN) + BLOCK_ALIGN - 1) & !(BLOCK_ALIGN - 1)
+}
+
+impl KernelHeap {
+    pub const fn new() -> Self {
+        Self {
+            lock: AtomicBool::new(false),
+            state: UnsafeCell::new(HeapState {
+                head: ptr::null_mut(),
+                initialized: false,
+                used: 0,
+                peak: 0,
+            }),
+        }
+    }
+
+    /// Ejecutar `f` con el estado del heap bajo el cerrojo
+    fn with_state<R>(&self, f: impl FnOnce(&mut HeapState) -> R) -> R {
+        while self
+            .lock
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+        let state = unsafe { &mut *self.state.get() };
+        if !state.initialized {
+            let arena = unsafe { ptr::addr_of_mut!(HEAP_ARENA) } as *mut FreeBlock;
+            unsafe {
+                (*arena).size = HEAP_SIZE;
+                (*arena).next = ptr::null_mut();
+            }
+            state.head = arena;
+            state.initialized = true;
+        }
+        let result = f(state);
+        self.lock.store(false, Ordering::Release);
+        result
+    }
+}
+
+unsafe impl GlobalAlloc for KernelHeap {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let size = block_size(&layout);
+        let align = layout.align().max(BLOCK_ALIGN);
+
+        self.with_state(|state| {
+            let mut previous: *mut FreeBlock = ptr::null_mut();
+            let mut current = state.head;
+            while !current.is_null() {
+                let start = current as usize;
+                let available = (*current).size;
+                let next = (*current).next;
+                let aligned = (start + align - 1) & !(align - 1);
+                let padding = aligned - start;
+
+                if padding + size <= available {
+                    // El sobrante del final vuelve a la lista como bloque propio
+                    let tail = available - padding - size;
+                    let mut link = next;
+                    if tail > 0 {
+                        let remainder = (aligned + size) as *mut FreeBlock;
+                        (*remainder).size = tail;
+                        (*remainder).next = next;
+                        link = remainder;
+                    }
+
+                    // El relleno de alineación conserva el bloque original
+                    if padding > 0 {
+                        (*current).size = padding;
+                        (*current).next = link;
+                    } else if previous.is_null() {
+                        state.head = link;
+                    } else {
+                        (*previous).next = link;
+                    }
+
+                    state.used += size;
+                    state.peak = state.peak.max(state.used);
+                    return aligned as *mut u8;
+                }
+
+                previous = current;
+                current = next;
+            }
+            ptr::null_mut()
+        })
+    }
+
+    unsafe fn dealloc(&self, block: *mut u8, layout: Layout) {
+        let size = block_size(&layout);
+        let address = block as usize;
+
+        self.with_state(|state| {
+            let mut previous: *mut FreeBlock = ptr::null_mut();
+            let mut current = state.head;
+            while !current.is_null() && (current as usize) < address {
+                previous = current;
+                current = (*current).next;
+            }
+
+            let freed = block as *mut FreeBlock;
+            (*freed).size = size;
+            (*freed).next = current;
+
+            // Fusionar con el bloque libre siguiente
+            if !current.is_null() && address + size == current as usize {
+                (*freed).size += (*current).size;
+                (*freed).next = (*current).next;
+            }
+
+            // Fusionar con el bloque libre anterior
+            if previous.is_null() {
+                state.head = freed;
+            } else if previous as usize + (*previous).size == address {
+                (*previous).size += (*freed).size;
+                (*previous).next = (*freed).next;
+            } else {
+                (*previous).next = freed;
+            }
+
+            state.used -= size;
+        });
+    }
+}
+
+/// Obtener estadísticas del heap: (total, en uso, pico de uso)
+pub fn get_heap_stats() -> (usize, usize, usize) {
+    KERNEL_HEAP.with_state(|state| (HEAP_SIZE, state.used, state.peak))
+}
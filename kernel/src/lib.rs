@@ -1,5 +1,7 @@
 //! # ReactOS Kernel en Rust
 
+extern crate alloc;
+
 pub mod memory;
 pub mod process;
 pub mod thread;
@@ -6,17 +6,21 @@
 #![no_std]
 #![no_main]
 
+extern crate alloc;
+
 use core::arch::asm;
 use reactos_rust_hal as hal;
 use reactos_rust_drivers as drivers;
 use reactos_rust_testing as testing;
 
 // Módulos del kernel
+mod heap;
 mod memory;
 mod process;
 mod thread;
 mod synchronization;
 mod io;
+mod security;
 mod filesystem;
 mod fat32;
 mod ntfs;
@@ -25,6 +29,8 @@ mod network_driver;
 mod graphics;
 mod gui;
 mod performance;
+#[path = "drivers/advanced/pci.rs"]
+mod pci;
 
 // Soporte Multiboot2
 mod multiboot2;
@@ -202,6 +208,14 @@ fn initialize_kernel_components() {
     io::init();
     print_message("  ✅ Sistema de I/O inicializado");
     
+    // Inicializar control de acceso y auditoría (los usa el VFS)
+    if let Err(e) = security::access_control::init_access_control().and_then(|_| security::audit::init_kernel_audit()) {
+        print_message("  ⚠️  Error inicializando la seguridad:");
+        print_message(e);
+    } else {
+        print_message("  ✅ Control de acceso y auditoría inicializados");
+    }
+    
     // Inicializar sistema de archivos
     filesystem::init();
     print_message("  ✅ Sistema de archivos inicializado");
@@ -310,6 +324,9 @@ fn kernel_main_loop() -> ! {
         // Procesar eventos del sistema
         process_system_events();
         
+        // Actualizar el reloj de las cuotas de disco
+        filesystem::vfs::update_quota_clock();
+        
         // Procesar cola de hilos
         thread::process_thread_queue();
         
@@ -355,6 +372,8 @@ fn show_system_stats() {
     // Estadísticas de memoria
     let (total_pages, free_pages, used_pages) = memory::get_memory_stats();
     print_message("  💾 Memoria: páginas libres de totales");
+    let (heap_total, heap_used, heap_peak) = heap::get_heap_stats();
+    print_message(&alloc::format!("  💾 Heap: {} bytes totales, {} en uso, pico de {}", heap_total, heap_used, heap_peak));
     
     // Estadísticas de procesos
     let (running_procs, ready_procs, blocked_procs, terminated_procs) = process::get_process_stats();
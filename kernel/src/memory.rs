@@ -40,6 +40,10 @@ impl MemoryManager {
         self.free_memory += size as u64;
         Ok(())
     }
+    
+    pub fn get_stats(&self) -> (u64, u64, u64) {
+        (self.total_memory, self.used_memory, self.free_memory)
+    }
 }
 
 static mut MEMORY_MANAGER: Option<MemoryManager> = None;
@@ -73,3 +77,14 @@ pub fn deallocate(ptr: *mut u8, size: usize) -> KernelResult<()> {
         }
     }
 }
+
+pub fn get_memory_statistics() -> (u64, u64, u64) {
+    // (total, usada, libre) en bytes
+    unsafe {
+        if let Some(ref manager) = MEMORY_MANAGER {
+            manager.get_stats()
+        } else {
+            (0, 0, 0)
+        }
+    }
+}
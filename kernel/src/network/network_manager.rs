@@ -121,6 +121,11 @@ impl NetworkManager {
 /// Gestor global de red
 static mut NETWORK_MANAGER: Option<NetworkManager> = None;
 
+/// Obtener el gestor global de red
+pub fn get_network_manager() -> Option<&'static mut NetworkManager> {
+    unsafe { (*core::ptr::addr_of_mut!(NETWORK_MANAGER)).as_mut() }
+}
+
 /// Inicializar el stack de red
 pub fn init_network_stack() {
     unsafe {
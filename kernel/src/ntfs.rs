@@ -3,6 +3,9 @@
 //! Implementación del sistema de archivos NTFS.
 
 use core::arch::asm;
+use alloc::{string::String, vec, vec::Vec};
+
+use crate::filesystem::vfs::{FileInfo, FilePermissions, FileSystemDriver, NodeType};
 
 /// Boot Sector de NTFS
 #[derive(Debug, Clone, Copy)]
@@ -403,6 +406,246 @@ pub struct NtfsFileName {
     pub name: [u16; 255], // Nombre Unicode
 }
 
+/// Flags de cabecera de atributo
+pub const NTFS_ATTRIBUTE_FLAG_COMPRESSED: u16 = 0x0001;
+pub const NTFS_ATTRIBUTE_FLAG_ENCRYPTED: u16 = 0x4000;
+pub const NTFS_ATTRIBUTE_FLAG_SPARSE: u16 = 0x8000;
+
+/// Máscara del número de registro dentro de una referencia MFT (los 16 bits altos son la secuencia)
+pub const NTFS_MFT_REFERENCE_MASK: u64 = 0x0000_FFFF_FFFF_FFFF;
+
+/// Run de datos de un atributo no residente (VCN -> LCN)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NtfsDataRun {
+    /// Primer VCN cubierto por el run
+    pub vcn: u64,
+    /// Número de clústeres
+    pub length: u64,
+    /// Primer LCN, o `None` si el run es disperso (sparse)
+    pub lcn: Option<u64>,
+}
+
+/// Entrada de $ATTRIBUTE_LIST
+#[derive(Debug, Clone)]
+pub struct NtfsAttributeListEntry {
+    pub attribute_type: u32,
+    pub starting_vcn: u64,
+    pub mft_reference: u64,
+    pub attribute_id: u16,
+    pub name: String,
+}
+
+/// Contenido de un atributo, ya reunido a partir de todos sus segmentos
+#[derive(Debug, Clone)]
+pub enum NtfsAttributeValue {
+    /// Atributo residente: contenido copiado del registro
+    Resident(Vec<u8>),
+    /// Atributo no residente: cabecera del primer segmento y runs de todos los segmentos
+    NonResident {
+        header: NtfsNonResidentAttribute,
+        runs: Vec<NtfsDataRun>,
+    },
+}
+
+/// Paso del array de secuencia de actualización (siempre 512 bytes en NTFS)
+pub const NTFS_FIXUP_STRIDE: usize = 512;
+
+/// Error devuelto cuando un registro multisector quedó a medio escribir
+///
+/// Alguno de los sectores no lleva el número de secuencia de actualización
+/// esperado (o chkdsk marcó el registro como `BAAD`).
+pub const NTFS_ERROR_TORN_RECORD: &str = "Torn multi-sector record (update sequence mismatch)";
+
+/// Flags de registro MFT
+pub const MFT_RECORD_IN_USE: u16 = 0x0001;
+pub const MFT_RECORD_IS_DIRECTORY: u16 = 0x0002;
+
+/// Registros MFT del sistema
+pub const NTFS_MFT_MIRROR_RECORD: u64 = 1;
+pub const NTFS_LOGFILE_RECORD: u64 = 2;
+pub const NTFS_VOLUME_RECORD: u64 = 3;
+pub const NTFS_ROOT_DIRECTORY_RECORD: u64 = 5;
+pub const NTFS_BITMAP_RECORD: u64 = 6;
+pub const NTFS_UPCASE_RECORD: u64 = 10;
+/// Primer registro MFT disponible para archivos de usuario
+pub const NTFS_FIRST_USER_RECORD: u64 = 16;
+
+/// Flags de $VOLUME_INFORMATION
+pub const NTFS_VOLUME_IS_DIRTY: u16 = 0x0001;
+
+/// Área de reinicio de $LogFile: volumen desmontado limpiamente
+pub const NTFS_RESTART_VOLUME_IS_CLEAN: u16 = 0x0002;
+/// Lista de clientes vacía en el área de reinicio
+pub const NTFS_LOGFILE_NO_CLIENT: u16 = 0xFFFF;
+
+/// Espacios de nombres de $FILE_NAME
+pub const NTFS_NAMESPACE_POSIX: u8 = 0;
+pub const NTFS_NAMESPACE_WIN32: u8 = 1;
+pub const NTFS_NAMESPACE_DOS: u8 = 2;
+pub const NTFS_NAMESPACE_WIN32_AND_DOS: u8 = 3;
+
+/// Flags de entradas y nodos de índice
+pub const NTFS_INDEX_ENTRY_SUBNODE: u16 = 0x0001;
+pub const NTFS_INDEX_ENTRY_LAST: u16 = 0x0002;
+pub const NTFS_INDEX_NODE_LARGE: u8 = 0x01;
+
+/// Nombre del índice de directorios
+pub const NTFS_DIRECTORY_INDEX_NAME: &str = "$I30";
+
+/// Regla de colación de los índices de nombres de archivo (COLLATION_FILE_NAME)
+const NTFS_COLLATION_FILE_NAME: u32 = 0x01;
+
+/// Espacio que se deja libre en el registro de un directorio cuyo índice usa
+/// bloques INDX, para que $INDEX_ALLOCATION y $BITMAP puedan crecer
+const NTFS_INDEX_ROOT_RESERVE: usize = 128;
+
+/// Atributos de archivo (FILE_ATTRIBUTE_*)
+pub const NTFS_FILE_ATTRIBUTE_READONLY: u32 = 0x0001;
+pub const NTFS_FILE_ATTRIBUTE_HIDDEN: u32 = 0x0002;
+pub const NTFS_FILE_ATTRIBUTE_SYSTEM: u32 = 0x0004;
+pub const NTFS_FILE_ATTRIBUTE_DIRECTORY: u32 = 0x0010;
+pub const NTFS_FILE_ATTRIBUTE_ARCHIVE: u32 = 0x0020;
+pub const NTFS_FILE_ATTRIBUTE_SPARSE_FILE: u32 = 0x0200;
+pub const NTFS_FILE_ATTRIBUTE_COMPRESSED: u32 = 0x0800;
+
+/// Atributos que el usuario puede cambiar (solo lectura, oculto, sistema,
+/// archivo, normal, temporal, offline y no indexado)
+const NTFS_SETTABLE_FILE_ATTRIBUTES: u32 = 0x31A7;
+
+/// En $FILE_NAME: el registro tiene un índice $I30 (es un directorio)
+pub const NTFS_FILE_NAME_INDEX_PRESENT: u32 = 0x1000_0000;
+
+/// Primer registro que se asigna a archivos nuevos (16-23 quedan reservados
+/// para los registros de extensión de $MFT)
+pub const NTFS_FIRST_ALLOCATABLE_RECORD: u64 = 24;
+
+/// Error devuelto cuando un atributo no cabe en su registro MFT
+pub const NTFS_ERROR_RECORD_FULL: &str = "MFT record full";
+
+/// Segundos entre 1601-01-01 (época NTFS) y 1970-01-01
+const NTFS_UNIX_EPOCH_DELTA: u64 = 11_644_473_600;
+
+/// Datos descomprimidos que produce cada bloque LZNT1
+pub const LZNT1_CHUNK_SIZE: usize = 4096;
+
+/// Flujo de datos ($DATA) de un archivo; el flujo principal no tiene nombre
+#[derive(Debug, Clone)]
+pub struct NtfsStreamInfo {
+    pub name: String,
+    pub size: u64,
+    pub allocated_size: u64,
+    /// Flags de cabecera del atributo (NTFS_ATTRIBUTE_FLAG_*)
+    pub flags: u16,
+}
+
+/// Profundidad máxima del árbol de índice (protege contra ciclos en disco corrupto)
+const NTFS_MAX_INDEX_DEPTH: usize = 32;
+
+/// Entrada de un nodo de índice
+#[derive(Debug, Clone)]
+pub struct NtfsIndexEntry {
+    pub mft_reference: u64,
+    pub flags: u16,
+    pub key: Vec<u8>,
+    pub subnode_vcn: Option<u64>,
+}
+
+impl NtfsIndexEntry {
+    /// Verificar si es la entrada final del nodo (sin clave)
+    pub fn is_last(&self) -> bool {
+        self.flags & NTFS_INDEX_ENTRY_LAST != 0
+    }
+}
+
+/// Entrada de directorio devuelta al enumerar un índice $I30
+#[derive(Debug, Clone)]
+pub struct NtfsDirectoryEntry {
+    pub mft_reference: u64,
+    pub file_name: NtfsFileName,
+    pub name: String,
+}
+
+impl NtfsDirectoryEntry {
+    /// Número de registro MFT (sin el número de secuencia)
+    pub fn record_number(&self) -> u64 {
+        self.mft_reference & NTFS_MFT_REFERENCE_MASK
+    }
+    
+    /// Verificar si la entrada es un directorio
+    pub fn is_directory(&self) -> bool {
+        // FILE_ATTRIBUTE_DIRECTORY en $FILE_NAME usa el bit 0x10000000
+        let attributes = self.file_name.file_attributes;
+        attributes & 0x10000000 != 0 || attributes & 0x10 != 0
+    }
+}
+
+/// Índice $I30 de un directorio: raíz residente y bloques INDX opcionales
+struct NtfsDirectoryIndex {
+    root: Vec<u8>,
+    block_size: u32,
+    allocation: Option<(NtfsNonResidentAttribute, Vec<NtfsDataRun>)>,
+}
+
+/// Nodo de índice pendiente de escribir: entradas y subnodo de la entrada final
+type NtfsIndexNode = (Vec<NtfsIndexEntry>, Option<u64>);
+
+/// Estado de montaje del volumen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NtfsMountState {
+    Unmounted,
+    ReadOnly,
+    ReadWrite,
+}
+
+/// Estado de $LogFile según su área de reinicio más reciente
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NtfsLogFileStatus {
+    /// No hay transacciones pendientes de reproducir
+    pub clean: bool,
+    pub current_lsn: u64,
+    pub major_version: i16,
+    pub minor_version: i16,
+    /// chkdsk modificó el registro (página "CHKD")
+    pub chkdsk_modified: bool,
+}
+
+/// Resultado de la verificación de integridad del volumen
+#[derive(Debug, Clone, Default)]
+pub struct NtfsIntegrityReport {
+    pub log_file_clean: bool,
+    pub volume_dirty: bool,
+    /// Registros cuya copia en $MFTMirr no coincide con $MFT
+    pub mirror_mismatches: Vec<u64>,
+    /// Registros con fixups rotas o cabecera inválida
+    pub corrupt_records: Vec<u64>,
+    /// Registros en uso cuyo directorio padre (o registro base) no existe
+    pub orphaned_records: Vec<u64>,
+    /// Clústeres referenciados por más de un atributo
+    pub double_allocated_clusters: Vec<u64>,
+    /// Clústeres en uso marcados como libres en $Bitmap
+    pub unmarked_clusters: Vec<u64>,
+    /// Clústeres marcados en $Bitmap que nadie usa
+    pub leaked_clusters: Vec<u64>,
+}
+
+impl NtfsIntegrityReport {
+    /// Verificar si no se encontró ningún problema
+    pub fn is_clean(&self) -> bool {
+        self.log_file_clean
+            && !self.volume_dirty
+            && self.mirror_mismatches.is_empty()
+            && self.corrupt_records.is_empty()
+            && self.orphaned_records.is_empty()
+            && self.double_allocated_clusters.is_empty()
+            && self.bitmap_mismatches() == 0
+    }
+    
+    /// Número de clústeres en los que $Bitmap no refleja el uso real
+    pub fn bitmap_mismatches(&self) -> usize {
+        self.unmarked_clusters.len() + self.leaked_clusters.len()
+    }
+}
+
 /// Driver NTFS
 pub struct NtfsDriver {
     pub boot_sector: NtfsBootSector,
@@ -414,6 +657,10 @@ pub struct NtfsDriver {
     pub mft_cache: [Option<MftRecordHeader>; 256],
     pub cache_hits: u32,
     pub cache_misses: u32,
+    pub mft_header: Option<NtfsNonResidentAttribute>,
+    pub mft_runs: Vec<NtfsDataRun>,
+    pub upcase_table: Vec<u16>,
+    pub mount_state: NtfsMountState,
 }
 
 impl NtfsDriver {
@@ -448,6 +695,10 @@ impl NtfsDriver {
             mft_cache: [None; 256],
             cache_hits: 0,
             cache_misses: 0,
+            mft_header: None,
+            mft_runs: Vec::new(),
+            upcase_table: Vec::new(),
+            mount_state: NtfsMountState::Unmounted,
         }
     }
     
@@ -457,8 +708,9 @@ impl NtfsDriver {
         self.read_boot_sector()?;
         
         // Calcular parámetros
-        self.cluster_size = self.boot_sector.bytes_per_sector as u32 * self.boot_sector.sectors_per_cluster as u32;
-        self.total_clusters = self.boot_sector.total_sectors / self.boot_sector.sectors_per_cluster as u64;
+        let sectors_per_cluster = self.sectors_per_cluster();
+        self.cluster_size = self.boot_sector.bytes_per_sector as u32 * sectors_per_cluster;
+        self.total_clusters = self.boot_sector.total_sectors / sectors_per_cluster as u64;
         self.mft_start_cluster = self.boot_sector.mft_cluster;
         
         // Calcular tamaño de registro MFT
@@ -473,25 +725,76 @@ impl NtfsDriver {
             self.mft_cache[i] = None;
         }
         
+        // Cargar los runs de $MFT para poder localizar cualquier registro,
+        // aunque la MFT esté fragmentada
+        self.mft_header = None;
+        self.mft_runs.clear();
+        let mut record = vec![0u8; self.mft_record_size as usize];
+        self.read_mft_record_data(0, &mut record)?;
+        match self.load_attribute(&record, NtfsAttributeType::Data, "")? {
+            NtfsAttributeValue::NonResident { header, runs } => {
+                self.mft_header = Some(header);
+                self.mft_runs = runs;
+            }
+            NtfsAttributeValue::Resident(_) => return Err("$MFT data attribute must be non-resident"),
+        }
+        
+        // Tabla $UpCase para la colación de nombres
+        self.load_upcase_table()?;
+        
         Ok(())
     }
     
     /// Leer boot sector
     fn read_boot_sector(&mut self) -> Result<(), &'static str> {
-        // TODO: Implementar lectura del boot sector desde el dispositivo
-        // Por ahora, usar valores por defecto
-        self.boot_sector.bytes_per_sector = 512;
-        self.boot_sector.sectors_per_cluster = 8;
-        self.boot_sector.total_sectors = 1048576; // 512MB
-        self.boot_sector.mft_cluster = 4;
-        self.boot_sector.mft_mirror_cluster = 5;
-        self.boot_sector.clusters_per_mft_record = 0; // 1KB por registro
-        self.boot_sector.clusters_per_index_block = 1; // 4KB por bloque
-        self.boot_sector.volume_serial = 0x123456789ABCDEF0;
+        let mut sector = [0u8; 512];
+        self.read_sectors(0, 1, &mut sector)?;
+        
+        if &sector[3..11] != b"NTFS    " {
+            return Err("Not an NTFS volume");
+        }
+        if sector[510] != 0x55 || sector[511] != 0xAA {
+            return Err("Invalid NTFS boot sector signature");
+        }
+        
+        // La estructura empaquetada no coincide con el BPB en disco, se copian los campos uno a uno
+        self.boot_sector.jump_instruction.copy_from_slice(&sector[0..3]);
+        self.boot_sector.oem_name.copy_from_slice(&sector[3..11]);
+        self.boot_sector.bytes_per_sector = read_u16(&sector, 11);
+        self.boot_sector.sectors_per_cluster = sector[13];
+        self.boot_sector.reserved_sectors = read_u16(&sector, 14);
+        self.boot_sector.media_type = sector[21];
+        self.boot_sector.sectors_per_track = read_u16(&sector, 24);
+        self.boot_sector.number_of_heads = read_u16(&sector, 26);
+        self.boot_sector.hidden_sectors = read_u32(&sector, 28);
+        self.boot_sector.total_sectors = read_u64(&sector, 40);
+        self.boot_sector.mft_cluster = read_u64(&sector, 48);
+        self.boot_sector.mft_mirror_cluster = read_u64(&sector, 56);
+        self.boot_sector.clusters_per_mft_record = sector[64];
+        self.boot_sector.clusters_per_index_block = sector[68];
+        self.boot_sector.volume_serial = read_u64(&sector, 72);
+        self.boot_sector.checksum = read_u32(&sector, 80);
+        self.boot_sector.bootstrap_code.copy_from_slice(&sector[84..510]);
+        self.boot_sector.boot_signature = read_u16(&sector, 510);
+        
+        let bytes_per_sector = self.boot_sector.bytes_per_sector;
+        if !matches!(bytes_per_sector, 512 | 1024 | 2048 | 4096) || self.sectors_per_cluster() == 0 {
+            return Err("Invalid NTFS geometry");
+        }
         
         Ok(())
     }
     
+    /// Sectores por clúster (valores mayores que 0x80 codifican 2^(256 - valor))
+    fn sectors_per_cluster(&self) -> u32 {
+        let value = self.boot_sector.sectors_per_cluster;
+        if value > 0x80 {
+            1 << (256 - value as u32)
+        } else {
+            value as u32
+        }
+    }
+    
     /// Leer registro MFT
     pub fn read_mft_record(&mut self, record_number: u64) -> Result<MftRecordHeader, &'static str> {
         // Verificar caché
@@ -504,11 +807,8 @@ impl NtfsDriver {
         }
         
         // Leer desde disco
-        let cluster_offset = record_number * (self.mft_record_size as u64 / self.cluster_size as u64);
-        let sector_offset = cluster_offset * (self.cluster_size as u64 / 512);
-        
-        let mut buffer = [0u8; 1024];
-        self.read_sectors(sector_offset, 2, &mut buffer)?;
+        let mut buffer = vec![0u8; self.mft_record_size as usize];
+        self.read_mft_record_data(record_number, &mut buffer)?;
         
         let mut header = MftRecordHeader {
             signature: [0; 4],
@@ -561,45 +861,331 @@ impl NtfsDriver {
         Ok(header)
     }
     
+    /// Leer un registro MFT completo, con las fixups aplicadas y validado
+    pub fn read_mft_record_data(&self, record_number: u64, buffer: &mut [u8]) -> Result<(), &'static str> {
+        self.read_mft_record_raw(record_number, buffer)?;
+        let record_size = self.mft_record_size as usize;
+        apply_fixups(&mut buffer[..record_size], b"FILE")?;
+        validate_mft_record(&buffer[..record_size], record_number)
+    }
+    
+    /// Leer un registro a partir de una referencia MFT, comprobando el número de secuencia
+    ///
+    /// Una referencia con secuencia distinta apunta a un registro reutilizado
+    /// por otro archivo.
+    pub fn read_mft_reference(&self, reference: u64, buffer: &mut [u8]) -> Result<(), &'static str> {
+        self.read_mft_record_data(reference & NTFS_MFT_REFERENCE_MASK, buffer)?;
+        let sequence = (reference >> 48) as u16;
+        if sequence != 0 && read_u16(buffer, 16) != sequence {
+            return Err("Stale MFT reference (sequence number mismatch)");
+        }
+        Ok(())
+    }
+    
+    /// Escribir un registro MFT completo (y su copia en $MFTMirr si corresponde)
+    ///
+    /// Se incrementa el número de secuencia de actualización y se colocan las
+    /// fixups en la copia que va a disco; `buffer` conserva los datos reales
+    /// con el nuevo número de secuencia.
+    pub fn write_mft_record_data(&self, record_number: u64, buffer: &mut [u8]) -> Result<(), &'static str> {
+        let record_size = self.mft_record_size as usize;
+        if buffer.len() < record_size {
+            return Err("Buffer too small for MFT record");
+        }
+        validate_mft_record(&buffer[..record_size], record_number)?;
+        
+        let mut disk = buffer[..record_size].to_vec();
+        prepare_fixups(&mut disk)?;
+        let usa_offset = read_u16(&disk, 4) as usize;
+        buffer[usa_offset..usa_offset + 2].copy_from_slice(&disk[usa_offset..usa_offset + 2]);
+        
+        let header = self.mft_header.ok_or("MFT not loaded")?;
+        let byte_offset = record_number * record_size as u64;
+        self.write_runs(&header, &self.mft_runs, byte_offset, &disk)?;
+        
+        // $MFTMirr duplica al menos los 4 primeros registros (o un clúster)
+        let mirrored_bytes = core::cmp::max(self.cluster_size as u64, 4 * record_size as u64);
+        if byte_offset < mirrored_bytes {
+            let mut mirror = vec![0u8; record_size];
+            self.read_mft_record_data(NTFS_MFT_MIRROR_RECORD, &mut mirror)?;
+            if let NtfsAttributeValue::NonResident { header, runs } = self.load_attribute(&mirror, NtfsAttributeType::Data, "")? {
+                if byte_offset + record_size as u64 <= header.actual_size {
+                    self.write_runs(&header, &runs, byte_offset, &disk)?;
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    /// Leer un registro MFT tal como está en disco (sin fixups)
+    ///
+    /// Antes de cargar los runs de $MFT solo se pueden leer los registros del
+    /// primer fragmento, que empieza en `mft_start_cluster`.
+    fn read_mft_record_raw(&self, record_number: u64, buffer: &mut [u8]) -> Result<(), &'static str> {
+        let record_size = self.mft_record_size as usize;
+        if buffer.len() < record_size {
+            return Err("Buffer too small for MFT record");
+        }
+        
+        let byte_offset = record_number * record_size as u64;
+        let header = match self.mft_header {
+            Some(header) => header,
+            None => {
+                let bytes_per_sector = self.boot_sector.bytes_per_sector as u64;
+                let start = self.mft_start_cluster * self.cluster_size as u64 + byte_offset;
+                return self.read_sectors(start / bytes_per_sector, (record_size as u64 / bytes_per_sector) as u32, &mut buffer[..record_size]);
+            }
+        };
+        
+        let read = self.read_runs(&header, &self.mft_runs, byte_offset, &mut buffer[..record_size])?;
+        if read != record_size {
+            return Err("MFT record out of range");
+        }
+        Ok(())
+    }
+    
     /// Leer sectores del dispositivo
     fn read_sectors(&self, start_sector: u64, count: u32, buffer: &mut [u8]) -> Result<(), &'static str> {
-        // TODO: Implementar lectura real del dispositivo
-        // Por ahora, llenar con datos simulados
-        let bytes_to_read = core::cmp::min(buffer.len(), (count * 512) as usize);
-        for i in 0..bytes_to_read {
-            buffer[i] = (start_sector as u8).wrapping_add(i as u8);
+        if crate::drivers::storage::read_sectors(self.device_id, start_sector, count, buffer) {
+            Ok(())
+        } else {
+            Err("Device read failed")
         }
-        Ok(())
     }
     
     /// Escribir sectores al dispositivo
-    fn write_sectors(&self, _start_sector: u64, _count: u32, _buffer: &[u8]) -> Result<(), &'static str> {
-        // TODO: Implementar escritura real al dispositivo
-        // Por ahora, simular escritura exitosa
-        Ok(())
+    fn write_sectors(&self, start_sector: u64, count: u32, buffer: &[u8]) -> Result<(), &'static str> {
+        if crate::drivers::storage::write_sectors(self.device_id, start_sector, count, buffer) {
+            Ok(())
+        } else {
+            Err("Device write failed")
+        }
     }
     
     /// Leer atributo de archivo
     pub fn read_attribute<'a>(&self, record_buffer: &'a [u8], attribute_type: NtfsAttributeType) -> Result<&'a [u8], &'static str> {
+        self.attributes(record_buffer)
+            .find(|attr| read_u32(attr, 0) == attribute_type as u32)
+            .ok_or("Attribute not found")
+    }
+    
+    /// Recorrer los atributos de un registro MFT
+    pub fn attributes<'a>(&self, record_buffer: &'a [u8]) -> NtfsAttributeIter<'a> {
+        let offset = if record_buffer.len() >= 24 { read_u16(record_buffer, 20) as usize } else { record_buffer.len() };
+        NtfsAttributeIter { record: record_buffer, offset }
+    }
+    
+    /// Buscar un atributo por tipo, nombre y (opcionalmente) VCN inicial
+    pub fn find_attribute<'a>(&self, record_buffer: &'a [u8], attribute_type: NtfsAttributeType, name: &str, starting_vcn: Option<u64>) -> Option<&'a [u8]> {
+        self.attributes(record_buffer).find(|attr| {
+            read_u32(attr, 0) == attribute_type as u32
+                && attribute_name_matches(attr, name)
+                && starting_vcn.map_or(true, |vcn| attr[8] != 0 && read_u64(attr, 16) == vcn)
+        })
+    }
+    
+    /// Reunir el contenido de un atributo, siguiendo $ATTRIBUTE_LIST si existe
+    ///
+    /// En archivos muy fragmentados el atributo se reparte en varios registros
+    /// MFT, cada uno con los runs de un tramo de VCNs.
+    pub fn load_attribute(&self, record_buffer: &[u8], attribute_type: NtfsAttributeType, name: &str) -> Result<NtfsAttributeValue, &'static str> {
+        let list = match self.read_attribute(record_buffer, NtfsAttributeType::AttributeList) {
+            Ok(list_attr) => Some(self.read_attribute_list(list_attr)?),
+            Err(_) => None,
+        };
+        
+        let segments: Vec<NtfsAttributeListEntry> = list
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|entry| entry.attribute_type == attribute_type as u32 && entry.name.eq_ignore_ascii_case(name))
+            .collect();
+        
+        if segments.is_empty() {
+            let attr = self
+                .find_attribute(record_buffer, attribute_type, name, None)
+                .ok_or("Attribute not found")?;
+            return self.attribute_value(attr);
+        }
+        
+        let mut extension = vec![0u8; self.mft_record_size as usize];
+        let mut result: Option<NtfsAttributeValue> = None;
+        for segment in segments {
+            self.read_mft_reference(segment.mft_reference, &mut extension)?;
+            let attr = self
+                .find_attribute(&extension, attribute_type, name, None)
+                .filter(|attr| attr[8] == 0 || read_u64(attr, 16) == segment.starting_vcn)
+                .or_else(|| self.find_attribute(&extension, attribute_type, name, Some(segment.starting_vcn)))
+                .ok_or("Attribute list references a missing attribute")?;
+            
+            match (&mut result, self.attribute_value(attr)?) {
+                (None, value) => result = Some(value),
+                (Some(NtfsAttributeValue::NonResident { runs, .. }), NtfsAttributeValue::NonResident { runs: more, .. }) => {
+                    runs.extend(more);
+                }
+                _ => return Err("Inconsistent attribute segments"),
+            }
+        }
+        
+        result.ok_or("Attribute not found")
+    }
+    
+    /// Decodificar un atributo (residente o no) de un solo registro
+    fn attribute_value(&self, attr: &[u8]) -> Result<NtfsAttributeValue, &'static str> {
+        if attr.len() < 24 {
+            return Err("Invalid attribute");
+        }
+        
+        if attr[8] == 0 {
+            let content_size = read_u32(attr, 16) as usize;
+            let content_offset = read_u16(attr, 20) as usize;
+            if content_offset + content_size > attr.len() {
+                return Err("Data out of bounds");
+            }
+            return Ok(NtfsAttributeValue::Resident(attr[content_offset..content_offset + content_size].to_vec()));
+        }
+        
+        let header = parse_non_resident_header(attr)?;
+        let runlist_offset = header.runlist_offset as usize;
+        if runlist_offset >= attr.len() {
+            return Err("Invalid runlist offset");
+        }
+        let runs = decode_data_runs(&attr[runlist_offset..], header.starting_vcn)?;
+        Ok(NtfsAttributeValue::NonResident { header, runs })
+    }
+    
+    /// Leer y decodificar las entradas de $ATTRIBUTE_LIST
+    fn read_attribute_list(&self, list_attr: &[u8]) -> Result<Vec<NtfsAttributeListEntry>, &'static str> {
+        let data = match self.attribute_value(list_attr)? {
+            NtfsAttributeValue::Resident(data) => data,
+            NtfsAttributeValue::NonResident { header, runs } => {
+                let mut data = vec![0u8; header.actual_size as usize];
+                let read = self.read_runs(&header, &runs, 0, &mut data)?;
+                data.truncate(read);
+                data
+            }
+        };
+        
+        let mut entries = Vec::new();
         let mut offset = 0;
+        while offset + 26 <= data.len() {
+            let entry = &data[offset..];
+            let record_length = read_u16(entry, 4) as usize;
+            if record_length < 26 || record_length > entry.len() {
+                break;
+            }
+            
+            let name_length = entry[6] as usize;
+            let name_offset = entry[7] as usize;
+            if name_offset + name_length * 2 > record_length {
+                return Err("Invalid attribute list entry");
+            }
+            
+            entries.push(NtfsAttributeListEntry {
+                attribute_type: read_u32(entry, 0),
+                starting_vcn: read_u64(entry, 8),
+                mft_reference: read_u64(entry, 16),
+                attribute_id: read_u16(entry, 24),
+                name: decode_utf16_name(&entry[name_offset..name_offset + name_length * 2]),
+            });
+            offset += record_length;
+        }
+        
+        Ok(entries)
+    }
+    
+    /// Leer datos de un atributo no residente a partir de sus runs
+    ///
+    /// Los runs dispersos y la zona entre `initialized_size` y el tamaño real
+    /// se devuelven como ceros.
+    pub fn read_runs(&self, header: &NtfsNonResidentAttribute, runs: &[NtfsDataRun], offset: u64, buffer: &mut [u8]) -> Result<usize, &'static str> {
+        let data_size = header.actual_size;
+        let initialized_size = header.initialized_size;
+        if offset >= data_size {
+            return Ok(0);
+        }
         
-        while offset < record_buffer.len() {
-            let attr_header = &record_buffer[offset..offset + 16];
-            let attr_type = u32::from_le_bytes([attr_header[0], attr_header[1], attr_header[2], attr_header[3]]);
-            let attr_length = u32::from_le_bytes([attr_header[4], attr_header[5], attr_header[6], attr_header[7]]);
+        let cluster_size = self.cluster_size as u64;
+        let bytes_per_sector = self.boot_sector.bytes_per_sector as u64;
+        let end = core::cmp::min(offset + buffer.len() as u64, data_size);
+        let mut cluster_buffer = vec![0u8; cluster_size as usize];
+        let mut position = offset;
+        
+        while position < end {
+            let vcn = position / cluster_size;
+            let cluster_offset = (position % cluster_size) as usize;
+            let chunk = core::cmp::min(cluster_size - cluster_offset as u64, end - position) as usize;
+            let out = (position - offset) as usize;
+            
+            let run = runs
+                .iter()
+                .find(|run| vcn >= run.vcn && vcn < run.vcn + run.length)
+                .ok_or("VCN not mapped by any data run")?;
             
-            if attr_type == attribute_type as u32 {
-                return Ok(&record_buffer[offset..offset + attr_length as usize]);
+            match run.lcn {
+                Some(lcn) if position < initialized_size => {
+                    let cluster = lcn + (vcn - run.vcn);
+                    if cluster >= self.total_clusters {
+                        return Err("Data run points outside the volume");
+                    }
+                    let sector = cluster * cluster_size / bytes_per_sector;
+                    self.read_sectors(sector, (cluster_size / bytes_per_sector) as u32, &mut cluster_buffer)?;
+                    buffer[out..out + chunk].copy_from_slice(&cluster_buffer[cluster_offset..cluster_offset + chunk]);
+                    
+                    // La parte posterior a initialized_size se lee como ceros
+                    let valid = (initialized_size - position) as usize;
+                    if valid < chunk {
+                        buffer[out + valid..out + chunk].fill(0);
+                    }
+                }
+                _ => buffer[out..out + chunk].fill(0),
             }
             
-            if attr_type == NtfsAttributeType::EndOfAttributes as u32 {
-                break;
+            position += chunk as u64;
+        }
+        
+        Ok((end - offset) as usize)
+    }
+    
+    /// Escribir datos en clústeres ya asignados de un atributo no residente
+    pub fn write_runs(&self, header: &NtfsNonResidentAttribute, runs: &[NtfsDataRun], offset: u64, data: &[u8]) -> Result<(), &'static str> {
+        let end = offset + data.len() as u64;
+        if end > header.allocated_size {
+            return Err("Write beyond allocated size");
+        }
+        
+        let cluster_size = self.cluster_size as u64;
+        let bytes_per_sector = self.boot_sector.bytes_per_sector as u64;
+        let mut cluster_buffer = vec![0u8; cluster_size as usize];
+        let mut position = offset;
+        
+        while position < end {
+            let vcn = position / cluster_size;
+            let cluster_offset = (position % cluster_size) as usize;
+            let chunk = core::cmp::min(cluster_size - cluster_offset as u64, end - position) as usize;
+            
+            let run = runs
+                .iter()
+                .find(|run| vcn >= run.vcn && vcn < run.vcn + run.length)
+                .ok_or("VCN not mapped by any data run")?;
+            let lcn = run.lcn.ok_or("Cannot write into a sparse run")?;
+            let cluster = lcn + (vcn - run.vcn);
+            if cluster >= self.total_clusters {
+                return Err("Data run points outside the volume");
+            }
+            
+            let sector = cluster * cluster_size / bytes_per_sector;
+            let sectors = (cluster_size / bytes_per_sector) as u32;
+            if chunk != cluster_size as usize {
+                self.read_sectors(sector, sectors, &mut cluster_buffer)?;
             }
+            let input = (position - offset) as usize;
+            cluster_buffer[cluster_offset..cluster_offset + chunk].copy_from_slice(&data[input..input + chunk]);
+            self.write_sectors(sector, sectors, &cluster_buffer)?;
             
-            offset += attr_length as usize;
+            position += chunk as u64;
         }
         
-        Err("Attribute not found")
+        Ok(())
     }
     
     /// Leer nombre de archivo
@@ -634,191 +1220,2893 @@ impl NtfsDriver {
     }
     
     /// Leer información estándar de archivo
+    ///
+    /// Los volúmenes NTFS 1.2 solo guardan los primeros 48 bytes; el resto
+    /// de campos queda a cero.
     pub fn read_standard_information(&self, record_buffer: &[u8]) -> Result<NtfsStandardInformation, &'static str> {
-        let attr_data = self.read_attribute(record_buffer, NtfsAttributeType::StandardInformation)?;
-        
-        if attr_data.len() < 72 {
+        let content = match self.load_attribute(record_buffer, NtfsAttributeType::StandardInformation, "")? {
+            NtfsAttributeValue::Resident(content) => content,
+            NtfsAttributeValue::NonResident { .. } => return Err("Invalid standard information attribute"),
+        };
+        if content.len() < 48 {
             return Err("Invalid standard information attribute");
         }
+        let extended = content.len() >= 72;
         
-        let mut info = NtfsStandardInformation {
-            creation_time: 0,
-            file_change_time: 0,
-            mft_change_time: 0,
-            last_access_time: 0,
-            file_attributes: 0,
-            max_versions: 0,
-            version_number: 0,
-            class_id: 0,
-            owner_id: 0,
-            security_id: 0,
-            quota_charged: 0,
-            usn: 0,
-        };
-        
-        // Copiar datos del buffer
-        info.creation_time = u64::from_le_bytes([
-            attr_data[16], attr_data[17], attr_data[18], attr_data[19],
-            attr_data[20], attr_data[21], attr_data[22], attr_data[23],
-        ]);
-        info.file_change_time = u64::from_le_bytes([
-            attr_data[24], attr_data[25], attr_data[26], attr_data[27],
-            attr_data[28], attr_data[29], attr_data[30], attr_data[31],
-        ]);
-        info.mft_change_time = u64::from_le_bytes([
-            attr_data[32], attr_data[33], attr_data[34], attr_data[35],
-            attr_data[36], attr_data[37], attr_data[38], attr_data[39],
-        ]);
-        info.last_access_time = u64::from_le_bytes([
-            attr_data[40], attr_data[41], attr_data[42], attr_data[43],
-            attr_data[44], attr_data[45], attr_data[46], attr_data[47],
-        ]);
-        info.file_attributes = u32::from_le_bytes([attr_data[48], attr_data[49], attr_data[50], attr_data[51]]);
-        info.max_versions = u32::from_le_bytes([attr_data[52], attr_data[53], attr_data[54], attr_data[55]]);
-        info.version_number = u32::from_le_bytes([attr_data[56], attr_data[57], attr_data[58], attr_data[59]]);
-        info.class_id = u32::from_le_bytes([attr_data[60], attr_data[61], attr_data[62], attr_data[63]]);
-        info.owner_id = u32::from_le_bytes([attr_data[64], attr_data[65], attr_data[66], attr_data[67]]);
-        info.security_id = u32::from_le_bytes([attr_data[68], attr_data[69], attr_data[70], attr_data[71]]);
-        
-        Ok(info)
+        Ok(NtfsStandardInformation {
+            creation_time: read_u64(&content, 0),
+            file_change_time: read_u64(&content, 8),
+            mft_change_time: read_u64(&content, 16),
+            last_access_time: read_u64(&content, 24),
+            file_attributes: read_u32(&content, 32),
+            max_versions: read_u32(&content, 36),
+            version_number: read_u32(&content, 40),
+            class_id: read_u32(&content, 44),
+            owner_id: if extended { read_u32(&content, 48) } else { 0 },
+            security_id: if extended { read_u32(&content, 52) } else { 0 },
+            quota_charged: if extended { read_u64(&content, 56) } else { 0 },
+            usn: if extended { read_u64(&content, 64) } else { 0 },
+        })
     }
     
     /// Leer datos de archivo
     pub fn read_file_data(&self, record_buffer: &[u8], offset: u64, size: usize, buffer: &mut [u8]) -> Result<usize, &'static str> {
-        let attr_data = self.read_attribute(record_buffer, NtfsAttributeType::Data)?;
+        self.read_stream_data(record_buffer, "", offset, size, buffer)
+    }
+    
+    /// Leer datos de un flujo $DATA (`""` es el flujo principal)
+    ///
+    /// Los flujos comprimidos se descomprimen al vuelo (LZNT1).
+    pub fn read_stream_data(&self, record_buffer: &[u8], stream: &str, offset: u64, size: usize, buffer: &mut [u8]) -> Result<usize, &'static str> {
+        let size = core::cmp::min(size, buffer.len());
         
-        if attr_data.len() < 16 {
-            return Err("Invalid data attribute");
+        match self.load_attribute(record_buffer, NtfsAttributeType::Data, stream).map_err(|_| "Stream not found")? {
+            NtfsAttributeValue::Resident(content) => {
+                if offset >= content.len() as u64 {
+                    return Ok(0);
+                }
+                let start = offset as usize;
+                let bytes_to_copy = core::cmp::min(size, content.len() - start);
+                buffer[..bytes_to_copy].copy_from_slice(&content[start..start + bytes_to_copy]);
+                Ok(bytes_to_copy)
+            }
+            NtfsAttributeValue::NonResident { header, runs } => {
+                let flags = header.header.flags;
+                if flags & NTFS_ATTRIBUTE_FLAG_ENCRYPTED != 0 {
+                    return Err("Encrypted data not supported");
+                }
+                if flags & NTFS_ATTRIBUTE_FLAG_COMPRESSED != 0 {
+                    return self.read_compressed_runs(&header, &runs, offset, &mut buffer[..size]);
+                }
+                self.read_runs(&header, &runs, offset, &mut buffer[..size])
+            }
+        }
+    }
+    
+    /// Leer datos comprimidos con LZNT1
+    ///
+    /// Los datos se dividen en unidades de 2^compression_unit clústeres. Una
+    /// unidad sin clústeres es un hueco, una unidad con todos sus clústeres
+    /// asignados se guardó sin comprimir, y en otro caso los clústeres
+    /// asignados contienen los bloques LZNT1 y el resto de la unidad es disperso.
+    fn read_compressed_runs(&self, header: &NtfsNonResidentAttribute, runs: &[NtfsDataRun], offset: u64, buffer: &mut [u8]) -> Result<usize, &'static str> {
+        let data_size = header.actual_size;
+        if offset >= data_size {
+            return Ok(0);
+        }
+        if header.compression_unit == 0 || header.compression_unit > 16 {
+            return Err("Invalid compression unit");
         }
         
-        let non_resident = attr_data[8];
-        if non_resident == 0 {
-            // Atributo residente
-            let content_size = u32::from_le_bytes([attr_data[16], attr_data[17], attr_data[18], attr_data[19]]);
-            let content_offset = u16::from_le_bytes([attr_data[20], attr_data[21]]) as usize;
-            
-            if offset >= content_size as u64 {
-                return Ok(0);
-            }
+        let cluster_size = self.cluster_size as u64;
+        let bytes_per_sector = self.boot_sector.bytes_per_sector as u64;
+        let unit_clusters = 1u64 << header.compression_unit;
+        let unit_size = unit_clusters * cluster_size;
+        let end = core::cmp::min(offset + buffer.len() as u64, data_size);
+        let mut unit_data = vec![0u8; unit_size as usize];
+        let mut compressed = Vec::with_capacity(unit_size as usize);
+        let mut cluster_buffer = vec![0u8; cluster_size as usize];
+        let mut position = offset;
+        
+        while position < end {
+            let unit_start = position - position % unit_size;
+            let first_vcn = unit_start / cluster_size;
             
-            let start = content_offset + offset as usize;
-            let end = core::cmp::min(start + size, content_offset + content_size as usize);
+            // LCN de cada clúster de la unidad (None si es disperso o no está mapeado)
+            let clusters: Vec<Option<u64>> = (first_vcn..first_vcn + unit_clusters)
+                .map(|vcn| {
+                    runs.iter()
+                        .find(|run| vcn >= run.vcn && vcn < run.vcn + run.length)
+                        .and_then(|run| run.lcn.map(|lcn| lcn + (vcn - run.vcn)))
+                })
+                .collect();
+            let stored = clusters.iter().take_while(|lcn| lcn.is_some()).count() as u64;
             
-            if start >= attr_data.len() || end > attr_data.len() {
-                return Err("Data out of bounds");
+            unit_data.fill(0);
+            if stored == unit_clusters {
+                self.read_runs(header, runs, unit_start, &mut unit_data)?;
+            } else if stored > 0 {
+                compressed.clear();
+                for lcn in clusters.iter().take(stored as usize).flatten() {
+                    if *lcn >= self.total_clusters {
+                        return Err("Data run points outside the volume");
+                    }
+                    let sector = lcn * cluster_size / bytes_per_sector;
+                    self.read_sectors(sector, (cluster_size / bytes_per_sector) as u32, &mut cluster_buffer)?;
+                    compressed.extend_from_slice(&cluster_buffer);
+                }
+                decompress_lznt1(&compressed, &mut unit_data)?;
             }
             
-            let bytes_to_copy = core::cmp::min(end - start, buffer.len());
-            buffer[..bytes_to_copy].copy_from_slice(&attr_data[start..start + bytes_to_copy]);
-            Ok(bytes_to_copy)
-        } else {
-            // Atributo no residente
-            // TODO: Implementar lectura de datos no residentes
-            Err("Non-resident data not implemented")
+            let unit_offset = (position - unit_start) as usize;
+            let chunk = core::cmp::min(unit_size - unit_offset as u64, end - position) as usize;
+            let out = (position - offset) as usize;
+            buffer[out..out + chunk].copy_from_slice(&unit_data[unit_offset..unit_offset + chunk]);
+            position += chunk as u64;
         }
+        
+        Ok((end - offset) as usize)
     }
     
-    /// Buscar archivo en directorio
-    pub fn find_file(&mut self, directory_record: u64, _filename: &str) -> Result<u64, &'static str> {
-        let mut buffer = [0u8; 1024];
-        let _record = self.read_mft_record(directory_record)?;
+    /// Enumerar los flujos $DATA de un archivo, incluidos los que están en
+    /// registros de extensión
+    pub fn list_streams(&self, record_number: u64) -> Result<Vec<NtfsStreamInfo>, &'static str> {
+        let mut record = vec![0u8; self.mft_record_size as usize];
+        self.read_mft_record_data(record_number, &mut record)?;
         
-        // Leer registro completo
-        let cluster_offset = directory_record * (self.mft_record_size as u64 / self.cluster_size as u64);
-        let sector_offset = cluster_offset * (self.cluster_size as u64 / 512);
-        self.read_sectors(sector_offset, 2, &mut buffer)?;
-        
-        // Buscar atributo de índice
-        let _index_attr = self.read_attribute(&buffer, NtfsAttributeType::IndexRoot)?;
+        let mut names: Vec<String> = self
+            .attributes(&record)
+            .filter(|attr| read_u32(attr, 0) == NtfsAttributeType::Data as u32)
+            .map(attribute_name)
+            .collect();
+        if let Ok(list_attr) = self.read_attribute(&record, NtfsAttributeType::AttributeList) {
+            for entry in self.read_attribute_list(list_attr)? {
+                if entry.attribute_type == NtfsAttributeType::Data as u32 {
+                    names.push(entry.name);
+                }
+            }
+        }
+        names.sort();
+        names.dedup();
         
-        // TODO: Implementar búsqueda en índice
-        // Por ahora, simular búsqueda exitosa
-        Ok(5) // Retornar número de registro simulado
+        let mut streams = Vec::new();
+        for name in names {
+            let (size, allocated_size, flags) = match self.load_attribute(&record, NtfsAttributeType::Data, &name)? {
+                NtfsAttributeValue::Resident(content) => (content.len() as u64, align8(content.len()) as u64, 0),
+                NtfsAttributeValue::NonResident { header, .. } => (header.actual_size, header.allocated_size, header.header.flags),
+            };
+            streams.push(NtfsStreamInfo { name, size, allocated_size, flags });
+        }
+        Ok(streams)
     }
     
-    /// Crear archivo
-    pub fn create_file(&mut self, _directory_record: u64, _filename: &str) -> Result<u64, &'static str> {
-        // TODO: Implementar creación de archivo
-        // Por ahora, simular creación exitosa
-        Ok(6) // Retornar número de registro simulado
+    /// Leer un flujo $DATA de un archivo
+    pub fn read_stream(&self, record_number: u64, stream: &str, offset: u64, buffer: &mut [u8]) -> Result<usize, &'static str> {
+        let mut record = vec![0u8; self.mft_record_size as usize];
+        self.read_mft_record_data(record_number, &mut record)?;
+        self.read_stream_data(&record, stream, offset, buffer.len(), buffer)
     }
     
-    /// Eliminar archivo
-    pub fn delete_file(&mut self, _file_record: u64) -> Result<(), &'static str> {
-        // TODO: Implementar eliminación de archivo
+    /// Cargar la tabla $UpCase (una entrada UTF-16 por cada unidad UTF-16)
+    fn load_upcase_table(&mut self) -> Result<(), &'static str> {
+        let mut record = vec![0u8; self.mft_record_size as usize];
+        self.read_mft_record_data(NTFS_UPCASE_RECORD, &mut record)?;
+        
+        let mut data = vec![0u8; 0x10000 * 2];
+        let read = match self.load_attribute(&record, NtfsAttributeType::Data, "")? {
+            NtfsAttributeValue::NonResident { header, runs } => self.read_runs(&header, &runs, 0, &mut data)?,
+            NtfsAttributeValue::Resident(content) => {
+                let len = core::cmp::min(content.len(), data.len());
+                data[..len].copy_from_slice(&content[..len]);
+                len
+            }
+        };
+        if read < 0x80 * 2 {
+            return Err("$UpCase table too small");
+        }
+        
+        // Los caracteres fuera de la tabla se asignan a sí mismos
+        self.upcase_table = (0..0x10000usize)
+            .map(|c| if c * 2 + 1 < read { read_u16(&data, c * 2) } else { c as u16 })
+            .collect();
         Ok(())
     }
     
-    /// Obtener estadísticas del driver
-    pub fn get_stats(&self) -> (u32, u32, u64, u64) {
-        (self.cache_hits, self.cache_misses, self.total_clusters, self.mft_record_size as u64)
+    /// Convertir una unidad UTF-16 a mayúsculas con $UpCase
+    fn upcase(&self, unit: u16) -> u16 {
+        match self.upcase_table.get(unit as usize) {
+            Some(&upper) => upper,
+            None if unit < 0x80 => (unit as u8).to_ascii_uppercase() as u16,
+            None => unit,
+        }
     }
-}
-
-/// Instancia global del driver NTFS
-static mut NTFS_DRIVER: Option<NtfsDriver> = None;
-
-/// Inicializar driver NTFS
-pub fn init_ntfs(device_id: u32) -> Result<(), &'static str> {
-    unsafe {
-        NTFS_DRIVER = Some(NtfsDriver::new(device_id));
-        if let Some(ref mut driver) = NTFS_DRIVER {
-            driver.init()?;
+    
+    /// Colación COLLATION_FILE_NAME: comparación de nombres en mayúsculas
+    pub fn collate_names(&self, a: &[u16], b: &[u16]) -> core::cmp::Ordering {
+        for (&x, &y) in a.iter().zip(b.iter()) {
+            let ordering = self.upcase(x).cmp(&self.upcase(y));
+            if ordering != core::cmp::Ordering::Equal {
+                return ordering;
+            }
         }
+        a.len().cmp(&b.len())
     }
-    Ok(())
-}
-
-/// Obtener driver NTFS
-pub fn get_ntfs_driver() -> Option<&'static mut NtfsDriver> {
-    unsafe {
-        NTFS_DRIVER.as_mut()
+    
+    /// Abrir el índice $I30 de un directorio
+    fn open_directory_index(&self, directory_record: u64) -> Result<NtfsDirectoryIndex, &'static str> {
+        let mut record = vec![0u8; self.mft_record_size as usize];
+        self.read_mft_record_data(directory_record, &mut record)?;
+        if read_u16(&record, 22) & MFT_RECORD_IS_DIRECTORY == 0 {
+            return Err("Not a directory");
+        }
+        
+        let root = match self.load_attribute(&record, NtfsAttributeType::IndexRoot, NTFS_DIRECTORY_INDEX_NAME)? {
+            NtfsAttributeValue::Resident(content) => content,
+            NtfsAttributeValue::NonResident { .. } => return Err("Index root must be resident"),
+        };
+        if root.len() < 32 {
+            return Err("Invalid index root");
+        }
+        
+        let block_size = read_u32(&root, 8);
+        let allocation = if root[16 + 12] & NTFS_INDEX_NODE_LARGE != 0 {
+            match self.load_attribute(&record, NtfsAttributeType::IndexAllocation, NTFS_DIRECTORY_INDEX_NAME)? {
+                NtfsAttributeValue::NonResident { header, runs } => Some((header, runs)),
+                NtfsAttributeValue::Resident(_) => return Err("Index allocation must be non-resident"),
+            }
+        } else {
+            None
+        };
+        
+        Ok(NtfsDirectoryIndex { root, block_size, allocation })
     }
-}
-
-// ============================================================================
-// FUNCIONES ADICIONALES DE NTFS
-// ============================================================================
-
-/// Montar volumen NTFS
-pub fn mount_ntfs_volume(_device_id: u32, _mount_point: &str) -> Result<(), &'static str> {
-    if let Some(ref mut _driver) = get_ntfs_driver() {
-        // TODO: Implementar montaje de volumen real
-        // Por ahora, simular montaje exitoso
-        Ok(())
-    } else {
-        Err("Driver NTFS no inicializado")
+    
+    /// Leer un bloque INDX y devolver su nodo (a partir de la cabecera de nodo)
+    fn read_index_block(&self, index: &NtfsDirectoryIndex, vcn: u64) -> Result<Vec<u8>, &'static str> {
+        let (header, runs) = index.allocation.as_ref().ok_or("Index has no allocation")?;
+        
+        let mut block = vec![0u8; index.block_size as usize];
+        let read = self.read_runs(header, runs, vcn * self.index_vcn_size(index.block_size), &mut block)?;
+        if read != block.len() {
+            return Err("Index block out of range");
+        }
+        apply_fixups(&mut block, b"INDX")?;
+        if read_u64(&block, 16) != vcn {
+            return Err("Index block VCN mismatch");
+        }
+        
+        block.drain(..24);
+        Ok(block)
     }
-}
+    
+    /// Leer las entradas de un nodo de índice
+    fn index_entries(node: &[u8]) -> Result<Vec<NtfsIndexEntry>, &'static str> {
+        if node.len() < 16 {
+            return Err("Invalid index node");
+        }
+        
+        let entries_offset = read_u32(node, 0) as usize;
+        let index_length = core::cmp::min(read_u32(node, 4) as usize, node.len());
+        let mut entries = Vec::new();
+        let mut offset = entries_offset;
+        
+        while offset + 16 <= index_length {
+            let entry_length = read_u16(node, offset + 8) as usize;
+            let key_length = read_u16(node, offset + 10) as usize;
+            let flags = read_u16(node, offset + 12);
+            if entry_length < 16 || offset + entry_length > index_length || 16 + key_length > entry_length {
+                return Err("Invalid index entry");
+            }
+            
+            let subnode_vcn = if flags & NTFS_INDEX_ENTRY_SUBNODE != 0 {
+                if entry_length < 24 {
+                    return Err("Invalid index entry");
+                }
+                Some(read_u64(node, offset + entry_length - 8))
+            } else {
+                None
+            };
+            
+            entries.push(NtfsIndexEntry {
+                mft_reference: read_u64(node, offset),
+                flags,
+                key: node[offset + 16..offset + 16 + key_length].to_vec(),
+                subnode_vcn,
+            });
+            
+            if flags & NTFS_INDEX_ENTRY_LAST != 0 {
+                return Ok(entries);
+            }
+            offset += entry_length;
+        }
+        
+        Err("Index node without last entry")
+    }
+    
+    /// Desplazamientos de las entradas de un nodo de índice (la final incluida)
+    fn index_entry_offsets(node: &[u8]) -> Vec<usize> {
+        let mut offsets = Vec::new();
+        let index_length = core::cmp::min(read_u32(node, 4) as usize, node.len());
+        let mut offset = read_u32(node, 0) as usize;
+        while offset + 16 <= index_length {
+            offsets.push(offset);
+            let entry_length = read_u16(node, offset + 8) as usize;
+            if entry_length < 16 || read_u16(node, offset + 12) & NTFS_INDEX_ENTRY_LAST != 0 {
+                break;
+            }
+            offset += entry_length;
+        }
+        offsets
+    }
+    
+    /// Buscar archivo en directorio
+    ///
+    /// Recorre el árbol B+ del índice $I30 comparando con la colación de
+    /// nombres de archivo. Devuelve el número de registro MFT.
+    pub fn find_file(&mut self, directory_record: u64, filename: &str) -> Result<u64, &'static str> {
+        let index = self.open_directory_index(directory_record)?;
+        let target: Vec<u16> = filename.encode_utf16().collect();
+        
+        let mut node = index.root[16..].to_vec();
+        for _ in 0..NTFS_MAX_INDEX_DEPTH {
+            let mut next_vcn = None;
+            for entry in Self::index_entries(&node)? {
+                let ordering = if entry.is_last() {
+                    core::cmp::Ordering::Less
+                } else {
+                    let key = parse_file_name(&entry.key)?;
+                    let name_length = key.name_length as usize;
+                    let name = key.name;
+                    self.collate_names(&target, &name[..name_length])
+                };
+                
+                match ordering {
+                    core::cmp::Ordering::Equal => {
+                        let mut record = vec![0u8; self.mft_record_size as usize];
+                        self.read_mft_reference(entry.mft_reference, &mut record)?;
+                        return Ok(entry.mft_reference & NTFS_MFT_REFERENCE_MASK);
+                    }
+                    core::cmp::Ordering::Less => {
+                        next_vcn = entry.subnode_vcn;
+                        break;
+                    }
+                    core::cmp::Ordering::Greater => {}
+                }
+            }
+            
+            match next_vcn {
+                Some(vcn) => node = self.read_index_block(&index, vcn)?,
+                None => return Err("File not found"),
+            }
+        }
+        
+        Err("Index tree too deep")
+    }
+    
+    /// Resolver una ruta (p. ej. `C:\Windows\System32\ntdll.dll`) a un registro MFT
+    pub fn lookup_path(&mut self, path: &str) -> Result<u64, &'static str> {
+        let path = strip_drive_letter(path);
+        let mut record = NTFS_ROOT_DIRECTORY_RECORD;
+        for component in path.split(|c| c == '\\' || c == '/') {
+            if component.is_empty() || component == "." {
+                continue;
+            }
+            record = self.find_file(record, component)?;
+        }
+        Ok(record)
+    }
+    
+    /// Resolver una ruta con flujo (`C:\dir\archivo:flujo[:$DATA]`)
+    ///
+    /// Devuelve el registro MFT y el nombre del flujo (vacío para el flujo
+    /// principal). No comprueba que el flujo exista, para poder crearlo.
+    pub fn lookup_stream(&mut self, path: &str) -> Result<(u64, String), &'static str> {
+        let path = strip_drive_letter(path);
+        let split = path.rfind(|c| c == '\\' || c == '/').map_or(0, |separator| separator + 1);
+        let (file, stream) = split_stream_name(&path[split..])?;
+        if !stream.is_empty() && !is_valid_ntfs_name(stream) {
+            return Err("Invalid stream name");
+        }
+        
+        let mut file_path = String::from(&path[..split]);
+        file_path.push_str(file);
+        let record = self.lookup_path(&file_path)?;
+        Ok((record, String::from(stream)))
+    }
+    
+    /// Enumerar un directorio recorriendo el índice $I30 en orden
+    ///
+    /// Los nombres DOS (8.3) se omiten cuando el archivo ya aparece con su
+    /// nombre Win32, de modo que cada enlace aparece una sola vez.
+    pub fn read_directory(&self, directory_record: u64) -> Result<Vec<NtfsDirectoryEntry>, &'static str> {
+        let index = self.open_directory_index(directory_record)?;
+        let mut entries = Vec::new();
+        for entry in self.index_entries_in_order(&index)? {
+            let file_name = parse_file_name(&entry.key)?;
+            let name_length = file_name.name_length as usize;
+            let name_units = file_name.name;
+            entries.push(NtfsDirectoryEntry {
+                mft_reference: entry.mft_reference,
+                file_name,
+                name: String::from_utf16_lossy(&name_units[..name_length]),
+            });
+        }
+        
+        let listed = entries.clone();
+        entries.retain(|entry| {
+            entry.file_name.name_type != NTFS_NAMESPACE_DOS
+                || !listed.iter().any(|other| {
+                    other.record_number() == entry.record_number() && other.file_name.name_type != NTFS_NAMESPACE_DOS
+                })
+        });
+        Ok(entries)
+    }
+    
+    /// Todas las entradas de un índice en orden de colación (sin subnodos)
+    fn index_entries_in_order(&self, index: &NtfsDirectoryIndex) -> Result<Vec<NtfsIndexEntry>, &'static str> {
+        let mut entries = Vec::new();
+        self.collect_index_entries(index, &index.root[16..], 0, &mut entries)?;
+        Ok(entries)
+    }
+    
+    /// Recorrido en orden de un nodo de índice y sus subnodos
+    fn collect_index_entries(&self, index: &NtfsDirectoryIndex, node: &[u8], depth: usize, out: &mut Vec<NtfsIndexEntry>) -> Result<(), &'static str> {
+        if depth >= NTFS_MAX_INDEX_DEPTH {
+            return Err("Index tree too deep");
+        }
+        
+        for entry in Self::index_entries(node)? {
+            if let Some(vcn) = entry.subnode_vcn {
+                let child = self.read_index_block(index, vcn)?;
+                self.collect_index_entries(index, &child, depth + 1, out)?;
+            }
+            if entry.is_last() {
+                break;
+            }
+            out.push(NtfsIndexEntry { flags: 0, subnode_vcn: None, ..entry });
+        }
+        Ok(())
+    }
+    
+    /// Leer el contenido completo del atributo $DATA sin nombre de un registro del sistema
+    fn read_system_file(&self, record_number: u64) -> Result<Vec<u8>, &'static str> {
+        let mut record = vec![0u8; self.mft_record_size as usize];
+        self.read_mft_record_data(record_number, &mut record)?;
+        match self.load_attribute(&record, NtfsAttributeType::Data, "")? {
+            NtfsAttributeValue::Resident(content) => Ok(content),
+            NtfsAttributeValue::NonResident { header, runs } => {
+                let mut data = vec![0u8; header.actual_size as usize];
+                let read = self.read_runs(&header, &runs, 0, &mut data)?;
+                data.truncate(read);
+                Ok(data)
+            }
+        }
+    }
+    
+    /// Inspeccionar las áreas de reinicio de $LogFile
+    ///
+    /// Hay dos páginas de reinicio al principio del archivo; se usa la de LSN
+    /// más alto. Un $LogFile vaciado (todo 0xFF) también cuenta como limpio.
+    pub fn inspect_log_file(&self) -> Result<NtfsLogFileStatus, &'static str> {
+        let log = self.read_system_file(NTFS_LOGFILE_RECORD)?;
+        if log.len() < 2 * 4096 {
+            return Err("$LogFile too small");
+        }
+        if log[..4096].iter().all(|&b| b == 0xFF) {
+            return Ok(NtfsLogFileStatus {
+                clean: true,
+                current_lsn: 0,
+                major_version: 0,
+                minor_version: 0,
+                chkdsk_modified: false,
+            });
+        }
+        
+        let mut best: Option<NtfsLogFileStatus> = None;
+        let mut page_offset = 0;
+        for _ in 0..2 {
+            let system_page_size = read_u32(&log, page_offset + 16) as usize;
+            let page_size = if system_page_size.is_power_of_two() && system_page_size >= NTFS_FIXUP_STRIDE {
+                system_page_size
+            } else {
+                4096
+            };
+            if page_offset + page_size > log.len() {
+                break;
+            }
+            
+            if let Ok(status) = Self::parse_restart_page(&log[page_offset..page_offset + page_size]) {
+                if best.map_or(true, |current| status.current_lsn > current.current_lsn) {
+                    best = Some(status);
+                }
+            }
+            page_offset += page_size;
+        }
+        
+        best.ok_or("No valid $LogFile restart area")
+    }
+    
+    /// Decodificar una página de reinicio ("RSTR" o "CHKD")
+    fn parse_restart_page(page: &[u8]) -> Result<NtfsLogFileStatus, &'static str> {
+        let mut page = page.to_vec();
+        let chkdsk_modified = &page[0..4] == b"CHKD";
+        let magic = if chkdsk_modified { b"CHKD" } else { b"RSTR" };
+        apply_fixups(&mut page, magic)?;
+        
+        let restart_offset = read_u16(&page, 24) as usize;
+        if restart_offset % 8 != 0 || restart_offset + 48 > page.len() {
+            return Err("Invalid $LogFile restart area offset");
+        }
+        
+        let area = &page[restart_offset..];
+        let client_in_use_list = read_u16(area, 12);
+        let flags = read_u16(area, 14);
+        Ok(NtfsLogFileStatus {
+            clean: flags & NTFS_RESTART_VOLUME_IS_CLEAN != 0 || client_in_use_list == NTFS_LOGFILE_NO_CLIENT,
+            current_lsn: read_u64(area, 0),
+            major_version: read_u16(&page, 28) as i16,
+            minor_version: read_u16(&page, 26) as i16,
+            chkdsk_modified,
+        })
+    }
+    
+    /// Leer el flag "dirty" de $VOLUME_INFORMATION
+    pub fn is_volume_dirty(&self) -> Result<bool, &'static str> {
+        let mut record = vec![0u8; self.mft_record_size as usize];
+        self.read_mft_record_data(NTFS_VOLUME_RECORD, &mut record)?;
+        match self.load_attribute(&record, NtfsAttributeType::VolumeInformation, "")? {
+            NtfsAttributeValue::Resident(content) if content.len() >= 12 => {
+                Ok(read_u16(&content, 10) & NTFS_VOLUME_IS_DIRTY != 0)
+            }
+            _ => Err("Invalid volume information"),
+        }
+    }
+    
+    /// Montar el volumen
+    ///
+    /// Un volumen con transacciones pendientes en $LogFile o marcado como
+    /// sucio solo se puede montar en modo de solo lectura.
+    pub fn mount(&mut self, read_write: bool) -> Result<(), &'static str> {
+        if self.mount_state != NtfsMountState::Unmounted {
+            return Err("Volume already mounted");
+        }
+        
+        if read_write && (!self.inspect_log_file()?.clean || self.is_volume_dirty()?) {
+            return Err("Volume is dirty; mount read-only or repair it first");
+        }
+        
+        self.mount_state = if read_write { NtfsMountState::ReadWrite } else { NtfsMountState::ReadOnly };
+        Ok(())
+    }
+    
+    /// Desmontar el volumen
+    pub fn unmount(&mut self) -> Result<(), &'static str> {
+        if self.mount_state == NtfsMountState::Unmounted {
+            return Err("Volume not mounted");
+        }
+        self.mount_state = NtfsMountState::Unmounted;
+        Ok(())
+    }
+    
+    /// Llevar al disco los cambios del volumen
+    ///
+    /// Los registros MFT, los índices y los datos se escriben en el momento
+    /// de modificarlos; solo queda vaciar la caché de bloques del dispositivo.
+    pub fn flush(&mut self) -> Result<(), &'static str> {
+        if self.mount_state == NtfsMountState::Unmounted {
+            return Err("Volume not mounted");
+        }
+        if crate::drivers::storage::sync_storage(Some(self.device_id)) {
+            Ok(())
+        } else {
+            Err("Error al sincronizar el dispositivo")
+        }
+    }
+    
+    /// Número de registros de la MFT
+    fn mft_record_count(&self) -> u64 {
+        self.mft_header.map_or(0, |header| header.actual_size / self.mft_record_size as u64)
+    }
+    
+    /// Verificar la integridad del volumen
+    ///
+    /// Comprueba $LogFile y el flag de volumen sucio, compara $MFT con
+    /// $MFTMirr, busca registros huérfanos y recalcula el uso de clústeres
+    /// a partir de los runs de todos los registros para cruzarlo con $Bitmap.
+    pub fn check_integrity(&self) -> Result<NtfsIntegrityReport, &'static str> {
+        let mut report = NtfsIntegrityReport::default();
+        report.log_file_clean = self.inspect_log_file().map(|status| status.clean).unwrap_or(false);
+        report.volume_dirty = self.is_volume_dirty()?;
+        
+        self.check_mft_mirror(&mut report)?;
+        let used = self.scan_mft_records(&mut report)?;
+        
+        let bitmap = self.read_system_file(NTFS_BITMAP_RECORD)?;
+        if (bitmap.len() as u64) * 8 < self.total_clusters {
+            return Err("$Bitmap smaller than the volume");
+        }
+        for cluster in 0..self.total_clusters {
+            let index = (cluster / 8) as usize;
+            let mask = 1u8 << (cluster % 8);
+            match (used[index] & mask != 0, bitmap[index] & mask != 0) {
+                (true, false) => report.unmarked_clusters.push(cluster),
+                (false, true) => report.leaked_clusters.push(cluster),
+                _ => {}
+            }
+        }
+        
+        Ok(report)
+    }
+    
+    /// Comparar los primeros registros de $MFT con su copia en $MFTMirr
+    fn check_mft_mirror(&self, report: &mut NtfsIntegrityReport) -> Result<(), &'static str> {
+        let record_size = self.mft_record_size as usize;
+        let mirror = self.read_system_file(NTFS_MFT_MIRROR_RECORD)?;
+        let mut record = vec![0u8; record_size];
+        
+        for (i, mirror_record) in mirror.chunks_exact(record_size).enumerate() {
+            self.read_mft_record_raw(i as u64, &mut record)?;
+            if record != mirror_record {
+                report.mirror_mismatches.push(i as u64);
+            }
+        }
+        Ok(())
+    }
+    
+    /// Recorrer todos los registros MFT: uso de clústeres, registros corruptos y huérfanos
+    ///
+    /// Devuelve un bitmap con los clústeres referenciados por algún atributo.
+    fn scan_mft_records(&self, report: &mut NtfsIntegrityReport) -> Result<Vec<u8>, &'static str> {
+        let record_count = self.mft_record_count();
+        let mut used = vec![0u8; ((self.total_clusters + 7) / 8) as usize];
+        let mut record = vec![0u8; self.mft_record_size as usize];
+        // (flags, secuencia) de cada registro en uso
+        let mut in_use: Vec<Option<(u16, u16)>> = vec![None; record_count as usize];
+        // (registro, referencia al padre o al registro base, es padre)
+        let mut links: Vec<(u64, u64, bool)> = Vec::new();
+        
+        for record_number in 0..record_count {
+            self.read_mft_record_raw(record_number, &mut record)?;
+            if record.iter().take(4).all(|&b| b == 0) {
+                continue; // Registro nunca inicializado
+            }
+            if apply_fixups(&mut record, b"FILE")
+                .and_then(|_| validate_mft_record(&record, record_number))
+                .is_err()
+            {
+                report.corrupt_records.push(record_number);
+                continue;
+            }
+            
+            let flags = read_u16(&record, 22);
+            if flags & MFT_RECORD_IN_USE == 0 {
+                continue;
+            }
+            in_use[record_number as usize] = Some((flags, read_u16(&record, 16)));
+            
+            let base_record = read_u64(&record, 32);
+            if base_record != 0 {
+                links.push((record_number, base_record, false));
+            }
+            
+            for attr in self.attributes(&record) {
+                if attr[8] == 0 {
+                    if base_record == 0 && read_u32(attr, 0) == NtfsAttributeType::FileName as u32 && record_number >= NTFS_FIRST_USER_RECORD {
+                        let content_offset = read_u16(attr, 20) as usize;
+                        if content_offset + 8 <= attr.len() {
+                            links.push((record_number, read_u64(attr, content_offset), true));
+                        }
+                    }
+                    continue;
+                }
+                
+                let header = parse_non_resident_header(attr)?;
+                let runs = match decode_data_runs(&attr[header.runlist_offset as usize..], header.starting_vcn) {
+                    Ok(runs) => runs,
+                    Err(_) => {
+                        report.corrupt_records.push(record_number);
+                        continue;
+                    }
+                };
+                for run in runs {
+                    let lcn = match run.lcn {
+                        Some(lcn) => lcn,
+                        None => continue,
+                    };
+                    for cluster in lcn..lcn + run.length {
+                        if cluster >= self.total_clusters {
+                            report.corrupt_records.push(record_number);
+                            break;
+                        }
+                        let index = (cluster / 8) as usize;
+                        let mask = 1u8 << (cluster % 8);
+                        if used[index] & mask != 0 {
+                            report.double_allocated_clusters.push(cluster);
+                        }
+                        used[index] |= mask;
+                    }
+                }
+            }
+        }
+        
+        // Un registro es huérfano si su padre (o su registro base) no está en
+        // uso, fue reutilizado, o el padre no es un directorio
+        for (record_number, reference, is_parent) in links {
+            let target = (reference & NTFS_MFT_REFERENCE_MASK) as usize;
+            let sequence = (reference >> 48) as u16;
+            let valid = match in_use.get(target).copied().flatten() {
+                Some((flags, target_sequence)) => {
+                    (sequence == 0 || sequence == target_sequence)
+                        && (!is_parent || flags & MFT_RECORD_IS_DIRECTORY != 0)
+                }
+                None => false,
+            };
+            if !valid && !report.orphaned_records.contains(&record_number) {
+                report.orphaned_records.push(record_number);
+            }
+        }
+        
+        report.corrupt_records.dedup();
+        Ok(used)
+    }
+    
+    /// Reparación offline del volumen
+    ///
+    /// Reescribe $Bitmap con el uso real de clústeres y restaura $MFTMirr a
+    /// partir de $MFT. Los clústeres con doble asignación no se corrigen
+    /// automáticamente y se devuelven en el informe.
+    pub fn repair(&mut self) -> Result<NtfsIntegrityReport, &'static str> {
+        if self.mount_state != NtfsMountState::Unmounted {
+            return Err("Repair requires the volume to be offline");
+        }
+        
+        let report = self.check_integrity()?;
+        
+        if report.bitmap_mismatches() > 0 {
+            let (header, runs) = self.volume_bitmap()?;
+            let mut bitmap = vec![0u8; header.actual_size as usize];
+            self.read_runs(&header, &runs, 0, &mut bitmap)?;
+            for &cluster in &report.unmarked_clusters {
+                bitmap[(cluster / 8) as usize] |= 1 << (cluster % 8);
+            }
+            for &cluster in &report.leaked_clusters {
+                bitmap[(cluster / 8) as usize] &= !(1 << (cluster % 8));
+            }
+            self.write_runs(&header, &runs, 0, &bitmap)?;
+        }
+        
+        if !report.mirror_mismatches.is_empty() {
+            let mut record = vec![0u8; self.mft_record_size as usize];
+            self.read_mft_record_data(NTFS_MFT_MIRROR_RECORD, &mut record)?;
+            if let NtfsAttributeValue::NonResident { header, runs } = self.load_attribute(&record, NtfsAttributeType::Data, "")? {
+                let mut mft_record = vec![0u8; self.mft_record_size as usize];
+                for &record_number in &report.mirror_mismatches {
+                    // Solo se copian registros de $MFT que sean válidos
+                    self.read_mft_record_raw(record_number, &mut mft_record)?;
+                    let mut check = mft_record.clone();
+                    if apply_fixups(&mut check, b"FILE").is_ok() {
+                        self.write_runs(&header, &runs, record_number * self.mft_record_size as u64, &mft_record)?;
+                    }
+                }
+            }
+        }
+        
+        self.check_integrity()
+    }
+    
+    /// Verificar que el volumen admite escrituras
+    fn ensure_writable(&self) -> Result<(), &'static str> {
+        if self.mount_state != NtfsMountState::ReadWrite {
+            return Err("Volume not mounted read-write");
+        }
+        Ok(())
+    }
+    
+    /// Leer un registro base en uso para modificarlo
+    ///
+    /// Los registros con $ATTRIBUTE_LIST todavía no se pueden modificar: sus
+    /// atributos están repartidos en varios registros.
+    fn read_record_for_update(&self, record_number: u64) -> Result<Vec<u8>, &'static str> {
+        self.ensure_writable()?;
+        let mut record = vec![0u8; self.mft_record_size as usize];
+        self.read_mft_record_data(record_number, &mut record)?;
+        if read_u16(&record, 22) & MFT_RECORD_IN_USE == 0 {
+            return Err("MFT record not in use");
+        }
+        if read_u64(&record, 32) != 0 {
+            return Err("Not a base MFT record");
+        }
+        if find_attribute_offset(&record, NtfsAttributeType::AttributeList as u32, "").is_some() {
+            return Err("Records with an attribute list are not writable");
+        }
+        Ok(record)
+    }
+    
+    /// Tiempo actual en formato NTFS
+    fn current_time(&self) -> u64 {
+        unix_to_ntfs_time(self.get_system_time())
+    }
+    
+    /// Obtener tiempo del sistema (segundos desde la época Unix)
+    fn get_system_time(&self) -> u64 {
+        crate::hal::rtc::unix_time()
+    }
+    
+    /// Tamaño de los bloques INDX de los directorios nuevos
+    fn index_block_size(&self) -> u32 {
+        let value = self.boot_sector.clusters_per_index_block as i8;
+        if value < 0 {
+            1 << (-value) as u32
+        } else {
+            value as u32 * self.cluster_size
+        }
+    }
+    
+    /// Tamaño de la unidad de VCN de un índice: clústeres, o sectores de 512
+    /// bytes si el bloque es menor que un clúster
+    fn index_vcn_size(&self, block_size: u32) -> u64 {
+        if block_size >= self.cluster_size { self.cluster_size as u64 } else { 512 }
+    }
+    
+    /// Cabecera y runs del atributo $DATA de $Bitmap
+    fn volume_bitmap(&self) -> Result<(NtfsNonResidentAttribute, Vec<NtfsDataRun>), &'static str> {
+        let mut record = vec![0u8; self.mft_record_size as usize];
+        self.read_mft_record_data(NTFS_BITMAP_RECORD, &mut record)?;
+        match self.load_attribute(&record, NtfsAttributeType::Data, "")? {
+            NtfsAttributeValue::NonResident { header, runs } => Ok((header, runs)),
+            NtfsAttributeValue::Resident(_) => Err("$Bitmap data must be non-resident"),
+        }
+    }
+    
+    /// Asignar clústeres libres en $Bitmap
+    ///
+    /// La búsqueda empieza en `hint` (normalmente el clúster siguiente al
+    /// último run del atributo) y da la vuelta al volumen si hace falta.
+    /// Devuelve los tramos (LCN, longitud) asignados.
+    fn allocate_clusters(&self, count: u64, hint: u64) -> Result<Vec<(u64, u64)>, &'static str> {
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        
+        let (header, runs) = self.volume_bitmap()?;
+        let mut bitmap = vec![0u8; header.actual_size as usize];
+        self.read_runs(&header, &runs, 0, &mut bitmap)?;
+        
+        let total = core::cmp::min(self.total_clusters, bitmap.len() as u64 * 8);
+        let mut cluster = if hint < total { hint } else { 0 };
+        let mut extents: Vec<(u64, u64)> = Vec::new();
+        let mut remaining = count;
+        for _ in 0..total {
+            if remaining == 0 {
+                break;
+            }
+            let index = (cluster / 8) as usize;
+            let mask = 1u8 << (cluster % 8);
+            if bitmap[index] & mask == 0 {
+                bitmap[index] |= mask;
+                match extents.last_mut() {
+                    Some((lcn, length)) if *lcn + *length == cluster => *length += 1,
+                    _ => extents.push((cluster, 1)),
+                }
+                remaining -= 1;
+            }
+            cluster = (cluster + 1) % total;
+        }
+        if remaining > 0 {
+            return Err("Not enough free clusters");
+        }
+        
+        let (first, last) = bitmap_byte_range(&extents);
+        self.write_runs(&header, &runs, first as u64, &bitmap[first..=last])?;
+        Ok(extents)
+    }
+    
+    /// Liberar clústeres en $Bitmap
+    fn free_clusters(&self, extents: &[(u64, u64)]) -> Result<(), &'static str> {
+        if extents.is_empty() {
+            return Ok(());
+        }
+        
+        let (header, runs) = self.volume_bitmap()?;
+        let mut bitmap = vec![0u8; header.actual_size as usize];
+        self.read_runs(&header, &runs, 0, &mut bitmap)?;
+        for &(lcn, length) in extents {
+            for cluster in lcn..lcn + length {
+                if let Some(byte) = bitmap.get_mut((cluster / 8) as usize) {
+                    *byte &= !(1 << (cluster % 8));
+                }
+            }
+        }
+        
+        let (first, last) = bitmap_byte_range(extents);
+        let last = core::cmp::min(last, bitmap.len() - 1);
+        self.write_runs(&header, &runs, first as u64, &bitmap[first..=last])
+    }
+    
+    /// Asignar clústeres para los VCN `[first_vcn, end_vcn)` de un atributo
+    ///
+    /// Rellena los huecos dispersos del rango y alarga la lista de runs si el
+    /// rango termina después del último VCN. Devuelve los tramos nuevos para
+    /// poder liberarlos si la operación no llega a completarse.
+    fn allocate_vcn_range(&self, runs: &mut Vec<NtfsDataRun>, first_vcn: u64, end_vcn: u64) -> Result<Vec<(u64, u64)>, &'static str> {
+        let mut allocated = Vec::new();
+        match self.map_vcn_range(runs, first_vcn, end_vcn, &mut allocated) {
+            Ok(mapped) => {
+                *runs = mapped;
+                Ok(allocated)
+            }
+            Err(error) => {
+                let _ = self.free_clusters(&allocated);
+                Err(error)
+            }
+        }
+    }
+    
+    /// Nueva lista de runs con el rango de VCN completamente asignado
+    fn map_vcn_range(&self, runs: &[NtfsDataRun], first_vcn: u64, end_vcn: u64, allocated: &mut Vec<(u64, u64)>) -> Result<Vec<NtfsDataRun>, &'static str> {
+        let mut mapped = Vec::new();
+        let mut vcn = 0;
+        for run in runs {
+            let run_end = run.vcn + run.length;
+            if run.lcn.is_some() || run_end <= first_vcn || run.vcn >= end_vcn {
+                push_data_run(&mut mapped, run.length, run.lcn);
+            } else {
+                // Hueco disperso que se solapa con el rango: se divide
+                let start = core::cmp::max(run.vcn, first_vcn);
+                let stop = core::cmp::min(run_end, end_vcn);
+                push_data_run(&mut mapped, start - run.vcn, None);
+                self.append_allocated_run(&mut mapped, stop - start, allocated)?;
+                push_data_run(&mut mapped, run_end - stop, None);
+            }
+            vcn = run_end;
+        }
+        
+        if end_vcn > vcn {
+            let start = core::cmp::max(vcn, first_vcn);
+            push_data_run(&mut mapped, start - vcn, None);
+            self.append_allocated_run(&mut mapped, end_vcn - start, allocated)?;
+        }
+        Ok(mapped)
+    }
+    
+    /// Añadir `count` clústeres nuevos al final de una lista de runs
+    fn append_allocated_run(&self, runs: &mut Vec<NtfsDataRun>, count: u64, allocated: &mut Vec<(u64, u64)>) -> Result<(), &'static str> {
+        let hint = runs.iter().rev().find_map(|run| run.lcn.map(|lcn| lcn + run.length)).unwrap_or(0);
+        for (lcn, length) in self.allocate_clusters(count, hint)? {
+            push_data_run(runs, length, Some(lcn));
+            allocated.push((lcn, length));
+        }
+        Ok(())
+    }
+    
+    /// Construir un atributo no residente sin compresión a partir de sus runs
+    ///
+    /// Los atributos dispersos llevan el campo de tamaño comprimido (cabecera
+    /// de 72 bytes), que solo cuenta los clústeres asignados.
+    fn build_non_resident_attribute(&self, attribute_type: u32, name: &str, attribute_id: u16, flags: u16, runs: &[NtfsDataRun], data_size: u64, initialized_size: u64) -> Vec<u8> {
+        let name_units: Vec<u16> = name.encode_utf16().collect();
+        let header_size = if flags & (NTFS_ATTRIBUTE_FLAG_COMPRESSED | NTFS_ATTRIBUTE_FLAG_SPARSE) != 0 { 72 } else { 64 };
+        let runlist = encode_data_runs(runs);
+        let runlist_offset = align8(header_size + name_units.len() * 2);
+        let length = align8(runlist_offset + runlist.len());
+        let clusters = runs.last().map_or(0, |run| run.vcn + run.length);
+        let cluster_size = self.cluster_size as u64;
+        
+        let mut attr = vec![0u8; length];
+        write_u32(&mut attr, 0, attribute_type);
+        write_u32(&mut attr, 4, length as u32);
+        attr[8] = 1;
+        attr[9] = name_units.len() as u8;
+        write_u16(&mut attr, 10, header_size as u16);
+        write_u16(&mut attr, 12, flags);
+        write_u16(&mut attr, 14, attribute_id);
+        write_u64(&mut attr, 24, clusters.wrapping_sub(1));
+        write_u16(&mut attr, 32, runlist_offset as u16);
+        write_u64(&mut attr, 40, clusters * cluster_size);
+        write_u64(&mut attr, 48, data_size);
+        write_u64(&mut attr, 56, initialized_size);
+        if header_size == 72 {
+            let mapped: u64 = runs.iter().filter(|run| run.lcn.is_some()).map(|run| run.length).sum();
+            write_u64(&mut attr, 64, mapped * cluster_size);
+        }
+        for (i, unit) in name_units.iter().enumerate() {
+            write_u16(&mut attr, header_size + i * 2, *unit);
+        }
+        attr[runlist_offset..runlist_offset + runlist.len()].copy_from_slice(&runlist);
+        attr
+    }
+    
+    /// Modificar el $BITMAP de $MFT (residente o no residente)
+    fn modify_mft_bitmap<T>(&self, modify: impl FnOnce(&mut [u8]) -> Result<T, &'static str>) -> Result<T, &'static str> {
+        let mut record = vec![0u8; self.mft_record_size as usize];
+        self.read_mft_record_data(0, &mut record)?;
+        let offset = find_attribute_offset(&record, NtfsAttributeType::Bitmap as u32, "").ok_or("$MFT has no bitmap attribute")?;
+        
+        if record[offset + 8] == 0 {
+            let range = resident_content_range(&record, offset)?;
+            let result = modify(&mut record[range])?;
+            self.write_mft_record_data(0, &mut record)?;
+            return Ok(result);
+        }
+        
+        let length = read_u32(&record, offset + 4) as usize;
+        let (header, runs) = match self.attribute_value(&record[offset..offset + length])? {
+            NtfsAttributeValue::NonResident { header, runs } => (header, runs),
+            NtfsAttributeValue::Resident(_) => return Err("Invalid $MFT bitmap"),
+        };
+        let mut bitmap = vec![0u8; header.actual_size as usize];
+        self.read_runs(&header, &runs, 0, &mut bitmap)?;
+        let result = modify(&mut bitmap)?;
+        self.write_runs(&header, &runs, 0, &bitmap)?;
+        Ok(result)
+    }
+    
+    /// Asignar un registro MFT libre
+    ///
+    /// La MFT no se amplía: si no quedan registros libres se devuelve error.
+    fn allocate_mft_record(&self) -> Result<u64, &'static str> {
+        let record_count = self.mft_record_count();
+        self.modify_mft_bitmap(|bitmap| {
+            let free = (NTFS_FIRST_ALLOCATABLE_RECORD..record_count)
+                .find(|&n| bitmap.get((n / 8) as usize).map_or(false, |byte| byte & (1 << (n % 8)) == 0))
+                .ok_or("MFT is full")?;
+            bitmap[(free / 8) as usize] |= 1 << (free % 8);
+            Ok(free)
+        })
+    }
+    
+    /// Marcar un registro MFT como libre en el $BITMAP de $MFT
+    fn release_mft_record(&self, record_number: u64) -> Result<(), &'static str> {
+        self.modify_mft_bitmap(|bitmap| {
+            if let Some(byte) = bitmap.get_mut((record_number / 8) as usize) {
+                *byte &= !(1 << (record_number % 8));
+            }
+            Ok(())
+        })
+    }
+    
+    /// Preparar un registro MFT vacío
+    ///
+    /// Se conserva el número de secuencia del registro anterior (que ya se
+    /// incrementó al liberarlo) y su número de secuencia de actualización.
+    fn format_mft_record(&self, record_number: u64, flags: u16) -> Result<Vec<u8>, &'static str> {
+        let record_size = self.mft_record_size as usize;
+        let mut previous = vec![0u8; record_size];
+        self.read_mft_record_raw(record_number, &mut previous)?;
+        let reused = &previous[0..4] == b"FILE";
+        let previous_usn = match update_sequence_array(&previous) {
+            Ok((usa_offset, _)) if reused => read_u16(&previous, usa_offset),
+            _ => 0,
+        };
+        if reused && apply_fixups(&mut previous, b"FILE").is_ok() && read_u16(&previous, 22) & MFT_RECORD_IN_USE != 0 {
+            return Err("MFT bitmap marks an in-use record as free");
+        }
+        
+        let usa_count = record_size / NTFS_FIXUP_STRIDE + 1;
+        let attributes_offset = align8(48 + usa_count * 2);
+        let sequence = if reused { read_u16(&previous, 16) } else { 0 };
+        
+        let mut record = vec![0u8; record_size];
+        record[0..4].copy_from_slice(b"FILE");
+        write_u16(&mut record, 4, 48);
+        write_u16(&mut record, 6, usa_count as u16);
+        write_u16(&mut record, 16, if sequence == 0 { 1 } else { sequence });
+        write_u16(&mut record, 18, 1);
+        write_u16(&mut record, 20, attributes_offset as u16);
+        write_u16(&mut record, 22, flags);
+        write_u32(&mut record, 24, (attributes_offset + 8) as u32);
+        write_u32(&mut record, 28, record_size as u32);
+        write_u32(&mut record, 44, record_number as u32);
+        write_u16(&mut record, 48, previous_usn);
+        write_u32(&mut record, attributes_offset, NtfsAttributeType::EndOfAttributes as u32);
+        Ok(record)
+    }
+    
+    /// Tamaños (asignado, real) del $DATA sin nombre de un registro
+    fn data_sizes(&self, record: &[u8]) -> Result<(u64, u64), &'static str> {
+        match self.load_attribute(record, NtfsAttributeType::Data, "")? {
+            NtfsAttributeValue::Resident(content) => Ok((align8(content.len()) as u64, content.len() as u64)),
+            NtfsAttributeValue::NonResident { header, .. } => Ok((header.allocated_size, header.actual_size)),
+        }
+    }
+    
+    /// Actualizar tiempos y atributos en $STANDARD_INFORMATION y en cada $FILE_NAME
+    ///
+    /// `times` son (creación, modificación, cambio MFT, acceso); `None` conserva
+    /// el valor. Los $FILE_NAME reciben además el tamaño actual de $DATA. Se
+    /// devuelven las claves nuevas junto al padre de cada nombre.
+    fn update_file_metadata(&self, record: &mut [u8], times: [Option<u64>; 4], attributes: Option<u32>) -> Result<Vec<(u64, Vec<u8>)>, &'static str> {
+        let directory = read_u16(record, 22) & MFT_RECORD_IS_DIRECTORY != 0;
+        let (allocated_size, data_size) = if directory { (0, 0) } else { self.data_sizes(record)? };
+        let mut keys = Vec::new();
+        
+        for offset in attribute_offsets(record).0 {
+            let attribute_type = read_u32(record, offset);
+            if record[offset + 8] != 0 {
+                continue;
+            }
+            let range = resident_content_range(record, offset)?;
+            let content = &mut record[range];
+            
+            if attribute_type == NtfsAttributeType::StandardInformation as u32 && content.len() >= 48 {
+                for (i, time) in times.iter().enumerate() {
+                    if let Some(time) = time {
+                        write_u64(content, i * 8, *time);
+                    }
+                }
+                if let Some(attributes) = attributes {
+                    write_u32(content, 32, attributes);
+                }
+            } else if attribute_type == NtfsAttributeType::FileName as u32 && content.len() >= 66 {
+                for (i, time) in times.iter().enumerate() {
+                    if let Some(time) = time {
+                        write_u64(content, 8 + i * 8, *time);
+                    }
+                }
+                write_u64(content, 40, allocated_size);
+                write_u64(content, 48, data_size);
+                if let Some(attributes) = attributes {
+                    let index_present = read_u32(content, 56) & NTFS_FILE_NAME_INDEX_PRESENT;
+                    write_u32(content, 56, attributes | index_present);
+                }
+                keys.push((read_u64(content, 0), content.to_vec()));
+            }
+        }
+        Ok(keys)
+    }
+    
+    /// Escribir un registro modificado y propagar sus $FILE_NAME a los índices de los padres
+    fn commit_record(&self, record_number: u64, record: &mut [u8], times: [Option<u64>; 4], attributes: Option<u32>) -> Result<(), &'static str> {
+        let keys = self.update_file_metadata(record, times, attributes)?;
+        self.write_mft_record_data(record_number, record)?;
+        for (parent, key) in keys {
+            let parent = parent & NTFS_MFT_REFERENCE_MASK;
+            // La raíz es su propio padre
+            if parent != record_number {
+                self.update_index_key(parent, record_number, &key)?;
+            }
+        }
+        Ok(())
+    }
+    
+    /// Actualizar el tiempo de modificación de un directorio tras cambiar su índice
+    fn touch_directory(&self, directory_record: u64) -> Result<(), &'static str> {
+        let mut record = self.read_record_for_update(directory_record)?;
+        let now = self.current_time();
+        self.commit_record(directory_record, &mut record, [None, Some(now), Some(now), None], None)
+    }
+    
+    /// Escribir datos en el $DATA sin nombre de un archivo
+    ///
+    /// Sobrescribe en el sitio, asigna clústeres en $Bitmap cuando el archivo
+    /// crece (o se escribe en un hueco disperso) y convierte el atributo en no
+    /// residente cuando deja de caber en el registro MFT.
+    pub fn write_file_data(&mut self, record_number: u64, offset: u64, data: &[u8]) -> Result<usize, &'static str> {
+        self.write_stream_data(record_number, "", offset, data)
+    }
+    
+    /// Escribir datos en un flujo $DATA, creándolo vacío si tiene nombre y no existe
+    pub fn write_stream_data(&mut self, record_number: u64, stream: &str, offset: u64, data: &[u8]) -> Result<usize, &'static str> {
+        let mut record = self.read_record_for_update(record_number)?;
+        let data_offset = self.stream_offset_for_update(&mut record, stream, true)?;
+        let end = offset.checked_add(data.len() as u64).ok_or("File too large")?;
+        
+        if record[data_offset + 8] == 0 {
+            let range = resident_content_range(&record, data_offset)?;
+            let mut content = record[range].to_vec();
+            if end <= self.mft_record_size as u64 {
+                if content.len() < end as usize {
+                    content.resize(end as usize, 0);
+                }
+                content[offset as usize..end as usize].copy_from_slice(data);
+                let attribute_id = read_u16(&record, data_offset + 14);
+                let attr = build_resident_attribute(NtfsAttributeType::Data as u32, stream, attribute_id, &content, false);
+                if replace_attribute(&mut record, data_offset, &attr).is_err() {
+                    self.convert_to_non_resident(&mut record, data_offset, &content)?;
+                }
+            } else {
+                self.convert_to_non_resident(&mut record, data_offset, &content)?;
+                self.write_non_resident_data(&mut record, data_offset, offset, data)?;
+            }
+        } else {
+            self.write_non_resident_data(&mut record, data_offset, offset, data)?;
+        }
+        
+        let now = self.current_time();
+        self.commit_record(record_number, &mut record, [None, Some(now), Some(now), Some(now)], None)?;
+        Ok(data.len())
+    }
+    
+    /// Posición del $DATA `stream` en un registro que se va a modificar
+    ///
+    /// El flujo principal de un directorio no existe; los flujos con nombre
+    /// se crean vacíos (residentes) si `create` lo permite.
+    fn stream_offset_for_update(&self, record: &mut [u8], stream: &str, create: bool) -> Result<usize, &'static str> {
+        if stream.is_empty() {
+            if read_u16(record, 22) & MFT_RECORD_IS_DIRECTORY != 0 {
+                return Err("Is a directory");
+            }
+        } else if !is_valid_ntfs_name(stream) {
+            return Err("Invalid stream name");
+        }
+        
+        match find_attribute_offset(record, NtfsAttributeType::Data as u32, stream) {
+            Some(offset) => Ok(offset),
+            None if create && !stream.is_empty() => {
+                let attribute_id = next_attribute_id(record);
+                let attr = build_resident_attribute(NtfsAttributeType::Data as u32, stream, attribute_id, &[], false);
+                insert_attribute(record, &attr)
+            }
+            None => Err("Stream not found"),
+        }
+    }
+    
+    /// Convertir un atributo residente en no residente, copiando su contenido a clústeres nuevos
+    fn convert_to_non_resident(&self, record: &mut [u8], offset: usize, content: &[u8]) -> Result<(), &'static str> {
+        let cluster_size = self.cluster_size as u64;
+        let clusters = (content.len() as u64 + cluster_size - 1) / cluster_size;
+        let attribute_type = read_u32(record, offset);
+        let attribute_id = read_u16(record, offset + 14);
+        let name = attribute_name(&record[offset..]);
+        
+        let mut runs = Vec::new();
+        let allocated = self.allocate_vcn_range(&mut runs, 0, clusters)?;
+        let size = content.len() as u64;
+        let attr = self.build_non_resident_attribute(attribute_type, &name, attribute_id, 0, &runs, size, size);
+        
+        let result = replace_attribute(record, offset, &attr).and_then(|_| {
+            let header = parse_non_resident_header(&attr)?;
+            let mut padded = content.to_vec();
+            padded.resize((clusters * cluster_size) as usize, 0);
+            self.write_runs(&header, &runs, 0, &padded)
+        });
+        if result.is_err() {
+            let _ = self.free_clusters(&allocated);
+        }
+        result
+    }
+    
+    /// Escribir en un atributo no residente, asignando los clústeres que falten
+    ///
+    /// La zona entre `initialized_size` y el inicio de la escritura se rellena
+    /// con ceros en disco antes de mover `initialized_size`.
+    fn write_non_resident_data(&self, record: &mut [u8], offset: usize, position: u64, data: &[u8]) -> Result<(), &'static str> {
+        let length = read_u32(record, offset + 4) as usize;
+        let (header, mut runs) = match self.attribute_value(&record[offset..offset + length])? {
+            NtfsAttributeValue::NonResident { header, runs } => (header, runs),
+            NtfsAttributeValue::Resident(_) => return Err("Attribute is resident"),
+        };
+        let flags = header.header.flags;
+        if flags & (NTFS_ATTRIBUTE_FLAG_COMPRESSED | NTFS_ATTRIBUTE_FLAG_ENCRYPTED) != 0 {
+            return Err("Compressed or encrypted data not supported");
+        }
+        
+        let cluster_size = self.cluster_size as u64;
+        let initialized_size = header.initialized_size;
+        let end = position + data.len() as u64;
+        let start = core::cmp::min(position, initialized_size);
+        let mapped_end = runs.last().map_or(0, |run| run.vcn + run.length);
+        // Solo los atributos dispersos pueden tener huecos sin asignar
+        let first_vcn = if flags & NTFS_ATTRIBUTE_FLAG_SPARSE != 0 {
+            start / cluster_size
+        } else {
+            core::cmp::min(start / cluster_size, mapped_end)
+        };
+        let end_vcn = (end + cluster_size - 1) / cluster_size;
+        let allocated = self.allocate_vcn_range(&mut runs, first_vcn, end_vcn)?;
+        
+        let name = attribute_name(&record[offset..]);
+        let attr = self.build_non_resident_attribute(
+            header.header.attribute_type,
+            &name,
+            header.header.attribute_id,
+            flags,
+            &runs,
+            core::cmp::max(header.actual_size, end),
+            core::cmp::max(initialized_size, end),
+        );
+        
+        let result = replace_attribute(record, offset, &attr).and_then(|_| {
+            let header = parse_non_resident_header(&attr)?;
+            let zeros = vec![0u8; cluster_size as usize];
+            let mut fill = initialized_size;
+            while fill < position {
+                let chunk = core::cmp::min(cluster_size, position - fill);
+                self.write_runs(&header, &runs, fill, &zeros[..chunk as usize])?;
+                fill += chunk;
+            }
+            self.write_runs(&header, &runs, position, data)
+        });
+        if result.is_err() {
+            let _ = self.free_clusters(&allocated);
+        }
+        result
+    }
+    
+    /// Cambiar el tamaño del $DATA sin nombre de un archivo
+    ///
+    /// Al acortar se liberan los clústeres sobrantes; al alargar se asignan
+    /// clústeres nuevos (o un hueco si el atributo es disperso) y la zona nueva
+    /// se lee como ceros porque `initialized_size` no avanza.
+    pub fn truncate_file(&mut self, record_number: u64, new_size: u64) -> Result<(), &'static str> {
+        self.truncate_stream(record_number, "", new_size)
+    }
+    
+    /// Cambiar el tamaño de un flujo $DATA existente
+    pub fn truncate_stream(&mut self, record_number: u64, stream: &str, new_size: u64) -> Result<(), &'static str> {
+        let mut record = self.read_record_for_update(record_number)?;
+        let data_offset = self.stream_offset_for_update(&mut record, stream, false)?;
+        
+        let mut released = Vec::new();
+        if record[data_offset + 8] == 0 {
+            let range = resident_content_range(&record, data_offset)?;
+            let mut content = record[range].to_vec();
+            if new_size <= self.mft_record_size as u64 {
+                content.resize(new_size as usize, 0);
+                let attribute_id = read_u16(&record, data_offset + 14);
+                let attr = build_resident_attribute(NtfsAttributeType::Data as u32, stream, attribute_id, &content, false);
+                if replace_attribute(&mut record, data_offset, &attr).is_err() {
+                    self.convert_to_non_resident(&mut record, data_offset, &content)?;
+                }
+            } else {
+                self.convert_to_non_resident(&mut record, data_offset, &content)?;
+                released = self.resize_non_resident_data(&mut record, data_offset, new_size)?;
+            }
+        } else {
+            released = self.resize_non_resident_data(&mut record, data_offset, new_size)?;
+        }
+        
+        let now = self.current_time();
+        self.commit_record(record_number, &mut record, [None, Some(now), Some(now), None], None)?;
+        // Los clústeres sobrantes se liberan cuando el registro ya no los referencia
+        self.free_clusters(&released)
+    }
+    
+    /// Eliminar un flujo $DATA con nombre (p. ej. `Zone.Identifier`)
+    pub fn delete_stream(&mut self, record_number: u64, stream: &str) -> Result<(), &'static str> {
+        if stream.is_empty() {
+            return Err("Cannot delete the unnamed data stream");
+        }
+        let mut record = self.read_record_for_update(record_number)?;
+        let data_offset = self.stream_offset_for_update(&mut record, stream, false)?;
+        
+        let length = read_u32(&record, data_offset + 4) as usize;
+        let released: Vec<(u64, u64)> = match self.attribute_value(&record[data_offset..data_offset + length])? {
+            NtfsAttributeValue::Resident(_) => Vec::new(),
+            NtfsAttributeValue::NonResident { runs, .. } => runs.iter().filter_map(|run| run.lcn.map(|lcn| (lcn, run.length))).collect(),
+        };
+        replace_attribute(&mut record, data_offset, &[])?;
+        
+        let now = self.current_time();
+        self.commit_record(record_number, &mut record, [None, None, Some(now), None], None)?;
+        self.free_clusters(&released)
+    }
+    
+    /// Cambiar el tamaño de un atributo no residente; devuelve los tramos que sobran
+    fn resize_non_resident_data(&self, record: &mut [u8], offset: usize, new_size: u64) -> Result<Vec<(u64, u64)>, &'static str> {
+        let length = read_u32(record, offset + 4) as usize;
+        let (header, mut runs) = match self.attribute_value(&record[offset..offset + length])? {
+            NtfsAttributeValue::NonResident { header, runs } => (header, runs),
+            NtfsAttributeValue::Resident(_) => return Err("Attribute is resident"),
+        };
+        let flags = header.header.flags;
+        if flags & (NTFS_ATTRIBUTE_FLAG_COMPRESSED | NTFS_ATTRIBUTE_FLAG_ENCRYPTED) != 0 {
+            return Err("Compressed or encrypted data not supported");
+        }
+        
+        let cluster_size = self.cluster_size as u64;
+        let clusters = (new_size + cluster_size - 1) / cluster_size;
+        let mapped_end = runs.last().map_or(0, |run| run.vcn + run.length);
+        let mut allocated = Vec::new();
+        let mut released = Vec::new();
+        if clusters < mapped_end {
+            released = truncate_data_runs(&mut runs, clusters);
+        } else if clusters > mapped_end {
+            if flags & NTFS_ATTRIBUTE_FLAG_SPARSE != 0 {
+                push_data_run(&mut runs, clusters - mapped_end, None);
+            } else {
+                allocated = self.allocate_vcn_range(&mut runs, mapped_end, clusters)?;
+            }
+        }
+        
+        let name = attribute_name(&record[offset..]);
+        let attr = self.build_non_resident_attribute(
+            header.header.attribute_type,
+            &name,
+            header.header.attribute_id,
+            flags,
+            &runs,
+            new_size,
+            core::cmp::min(header.initialized_size, new_size),
+        );
+        if let Err(error) = replace_attribute(record, offset, &attr) {
+            let _ = self.free_clusters(&allocated);
+            return Err(error);
+        }
+        Ok(released)
+    }
+    
+    /// Establecer los tiempos de un archivo (formato NTFS; `None` conserva el valor)
+    pub fn set_file_times(&mut self, record_number: u64, creation: Option<u64>, access: Option<u64>, modification: Option<u64>) -> Result<(), &'static str> {
+        let mut record = self.read_record_for_update(record_number)?;
+        let now = self.current_time();
+        self.commit_record(record_number, &mut record, [creation, modification, Some(now), access], None)
+    }
+    
+    /// Establecer los atributos de archivo (FILE_ATTRIBUTE_*)
+    ///
+    /// Solo se cambian los atributos que el usuario puede modificar; los que
+    /// reflejan el formato (disperso, comprimido, directorio...) se conservan.
+    pub fn set_file_attributes(&mut self, record_number: u64, attributes: u32) -> Result<(), &'static str> {
+        let mut record = self.read_record_for_update(record_number)?;
+        let current = self.read_standard_information(&record)?.file_attributes;
+        let attributes = (current & !NTFS_SETTABLE_FILE_ATTRIBUTES) | (attributes & NTFS_SETTABLE_FILE_ATTRIBUTES);
+        let now = self.current_time();
+        self.commit_record(record_number, &mut record, [None, None, Some(now), None], Some(attributes))
+    }
+    
+    /// Obtener los atributos de archivo (FILE_ATTRIBUTE_*)
+    ///
+    /// Incluye SPARSE_FILE y COMPRESSED según los flags del $DATA principal.
+    pub fn get_file_attributes(&self, record_number: u64) -> Result<u32, &'static str> {
+        let mut record = vec![0u8; self.mft_record_size as usize];
+        self.read_mft_record_data(record_number, &mut record)?;
+        let mut attributes = self.read_standard_information(&record)?.file_attributes;
+        if read_u16(&record, 22) & MFT_RECORD_IS_DIRECTORY != 0 {
+            attributes |= NTFS_FILE_ATTRIBUTE_DIRECTORY;
+        } else if let Ok(NtfsAttributeValue::NonResident { header, .. }) = self.load_attribute(&record, NtfsAttributeType::Data, "") {
+            // El formato del flujo principal manda sobre lo que diga $STANDARD_INFORMATION
+            if header.header.flags & NTFS_ATTRIBUTE_FLAG_SPARSE != 0 {
+                attributes |= NTFS_FILE_ATTRIBUTE_SPARSE_FILE;
+            }
+            if header.header.flags & NTFS_ATTRIBUTE_FLAG_COMPRESSED != 0 {
+                attributes |= NTFS_FILE_ATTRIBUTE_COMPRESSED;
+            }
+        }
+        Ok(attributes)
+    }
+    
+    /// Obtener el tamaño del $DATA sin nombre (0 para directorios)
+    pub fn get_file_size(&self, record_number: u64) -> Result<u64, &'static str> {
+        let mut record = vec![0u8; self.mft_record_size as usize];
+        self.read_mft_record_data(record_number, &mut record)?;
+        if read_u16(&record, 22) & MFT_RECORD_IS_DIRECTORY != 0 {
+            return Ok(0);
+        }
+        Ok(self.data_sizes(&record)?.1)
+    }
+    
+    /// Actualizar en su sitio la clave $FILE_NAME de una entrada del índice
+    ///
+    /// El nombre no cambia, así que la entrada conserva su tamaño y posición.
+    fn update_index_key(&self, directory_record: u64, record_number: u64, key: &[u8]) -> Result<(), &'static str> {
+        let index = self.open_directory_index(directory_record)?;
+        let name = file_name_units(key)?;
+        let mut node = index.root[16..].to_vec();
+        let mut node_vcn = None;
+        
+        for _ in 0..NTFS_MAX_INDEX_DEPTH {
+            let entries = Self::index_entries(&node)?;
+            let offsets = Self::index_entry_offsets(&node);
+            let mut next_vcn = None;
+            for (entry, offset) in entries.iter().zip(offsets) {
+                let ordering = if entry.is_last() {
+                    core::cmp::Ordering::Less
+                } else {
+                    self.collate_names(&name, &file_name_units(&entry.key)?)
+                };
+                
+                match ordering {
+                    core::cmp::Ordering::Equal => {
+                        if entry.mft_reference & NTFS_MFT_REFERENCE_MASK != record_number || entry.key.len() != key.len() {
+                            return Err("Index entry does not match the file record");
+                        }
+                        node[offset + 16..offset + 16 + key.len()].copy_from_slice(key);
+                        return match node_vcn {
+                            None => self.write_index_root_node(directory_record, &node),
+                            Some(vcn) => self.write_index_block(&index, vcn, &node),
+                        };
+                    }
+                    core::cmp::Ordering::Less => {
+                        next_vcn = entry.subnode_vcn;
+                        break;
+                    }
+                    core::cmp::Ordering::Greater => {}
+                }
+            }
+            
+            match next_vcn {
+                Some(vcn) => {
+                    node = self.read_index_block(&index, vcn)?;
+                    node_vcn = Some(vcn);
+                }
+                None => return Err("Index entry not found"),
+            }
+        }
+        
+        Err("Index tree too deep")
+    }
+    
+    /// Reescribir el nodo de $INDEX_ROOT sin cambiar su tamaño
+    fn write_index_root_node(&self, directory_record: u64, node: &[u8]) -> Result<(), &'static str> {
+        let mut record = self.read_record_for_update(directory_record)?;
+        let offset = find_attribute_offset(&record, NtfsAttributeType::IndexRoot as u32, NTFS_DIRECTORY_INDEX_NAME).ok_or("Index root not found")?;
+        let range = resident_content_range(&record, offset)?;
+        if range.len() != node.len() + 16 {
+            return Err("Index root changed size");
+        }
+        record[range.start + 16..range.end].copy_from_slice(node);
+        self.write_mft_record_data(directory_record, &mut record)
+    }
+    
+    /// Reescribir el nodo de un bloque INDX existente
+    fn write_index_block(&self, index: &NtfsDirectoryIndex, vcn: u64, node: &[u8]) -> Result<(), &'static str> {
+        let (header, runs) = index.allocation.as_ref().ok_or("Index has no allocation")?;
+        let position = vcn * self.index_vcn_size(index.block_size);
+        let mut block = vec![0u8; index.block_size as usize];
+        if self.read_runs(header, runs, position, &mut block)? != block.len() {
+            return Err("Index block out of range");
+        }
+        apply_fixups(&mut block, b"INDX")?;
+        block[24..24 + node.len()].copy_from_slice(node);
+        prepare_fixups(&mut block)?;
+        self.write_runs(header, runs, position, &block)
+    }
+    
+    /// Insertar una entrada en el índice $I30 de un directorio
+    fn index_insert(&self, directory_record: u64, reference: u64, key: Vec<u8>) -> Result<(), &'static str> {
+        let index = self.open_directory_index(directory_record)?;
+        let mut entries = self.index_entries_in_order(&index)?;
+        let name = file_name_units(&key)?;
+        
+        let mut position = entries.len();
+        for (i, entry) in entries.iter().enumerate() {
+            match self.collate_names(&name, &file_name_units(&entry.key)?) {
+                core::cmp::Ordering::Equal => return Err("File already exists"),
+                core::cmp::Ordering::Less => {
+                    position = i;
+                    break;
+                }
+                core::cmp::Ordering::Greater => {}
+            }
+        }
+        
+        entries.insert(position, NtfsIndexEntry { mft_reference: reference, flags: 0, key, subnode_vcn: None });
+        self.write_directory_index(directory_record, entries)
+    }
+    
+    /// Quitar del índice $I30 de un directorio todas las entradas de un registro
+    fn index_remove_record(&self, directory_record: u64, record_number: u64) -> Result<(), &'static str> {
+        let index = self.open_directory_index(directory_record)?;
+        let mut entries = self.index_entries_in_order(&index)?;
+        let before = entries.len();
+        entries.retain(|entry| entry.mft_reference & NTFS_MFT_REFERENCE_MASK != record_number);
+        if entries.len() == before {
+            return Err("Index entry not found");
+        }
+        self.write_directory_index(directory_record, entries)
+    }
+    
+    /// Reescribir el índice $I30 de un directorio a partir de sus entradas ordenadas
+    ///
+    /// Si todas las entradas caben en $INDEX_ROOT el índice queda pequeño. Si
+    /// no, el árbol se construye de abajo arriba: cada nivel se reparte en
+    /// bloques INDX y la primera entrada que no cabe en un bloque sube como
+    /// separador al nivel superior, hasta que un nivel cabe en la raíz. Así
+    /// todas las hojas quedan a la misma profundidad. Los bloques se colocan
+    /// desde el VCN 0 y $INDEX_ALLOCATION solo crece.
+    fn write_directory_index(&self, directory_record: u64, entries: Vec<NtfsIndexEntry>) -> Result<(), &'static str> {
+        let mut record = self.read_record_for_update(directory_record)?;
+        let root_offset = find_attribute_offset(&record, NtfsAttributeType::IndexRoot as u32, NTFS_DIRECTORY_INDEX_NAME).ok_or("Index root not found")?;
+        let root_range = resident_content_range(&record, root_offset)?;
+        let root_header = record[root_range.start..root_range.start + 16].to_vec();
+        let root_length = read_u32(&record, root_offset + 4) as usize;
+        let root_content_offset = root_range.start - root_offset;
+        let block_size = read_u32(&root_header, 8) as usize;
+        if block_size < NTFS_FIXUP_STRIDE || block_size % NTFS_FIXUP_STRIDE != 0 {
+            return Err("Invalid index block size");
+        }
+        
+        // Bytes disponibles para las entradas de la raíz (cabeceras de raíz y nodo aparte)
+        let bytes_in_use = read_u32(&record, 24) as usize;
+        let root_space = (record.len() + root_length).saturating_sub(bytes_in_use + root_content_offset + 32);
+        let usa_count = block_size / NTFS_FIXUP_STRIDE + 1;
+        let block_entries_offset = align8(40 + usa_count * 2);
+        
+        let mut blocks: Vec<NtfsIndexNode> = Vec::new();
+        let mut level = entries;
+        let mut last_subnode = None;
+        if index_node_size(&level, None) > root_space {
+            // Dejar sitio para que crezcan $INDEX_ALLOCATION y $BITMAP
+            let root_limit = root_space.saturating_sub(NTFS_INDEX_ROOT_RESERVE);
+            for depth in 0.. {
+                if depth >= NTFS_MAX_INDEX_DEPTH {
+                    return Err("Directory index too large");
+                }
+                let (nodes, separators) = pack_index_level(level, last_subnode, block_size - block_entries_offset);
+                let first_block = blocks.len() as u64;
+                let vcn = |node: usize| (first_block + node as u64) * block_size as u64 / self.index_vcn_size(block_size as u32);
+                level = separators
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, entry)| NtfsIndexEntry { subnode_vcn: Some(vcn(i)), ..entry })
+                    .collect();
+                last_subnode = Some(vcn(nodes.len() - 1));
+                blocks.extend(nodes);
+                if index_node_size(&level, last_subnode) <= root_limit {
+                    break;
+                }
+            }
+        }
+        
+        // Nueva raíz (antes que la asignación, que ocupa el sitio que libera)
+        let mut root_content = root_header;
+        root_content.extend(serialize_index_node(&level, last_subnode, 16, None));
+        let root_id = read_u16(&record, root_offset + 14);
+        let root = build_resident_attribute(NtfsAttributeType::IndexRoot as u32, NTFS_DIRECTORY_INDEX_NAME, root_id, &root_content, false);
+        replace_attribute(&mut record, root_offset, &root)?;
+        
+        let mut allocated = Vec::new();
+        let allocation = self.resize_index_allocation(&mut record, blocks.len() as u64, block_size, &mut allocated)?;
+        
+        if let Some((header, runs)) = &allocation {
+            for (block_number, (block_entries, block_last)) in blocks.iter().enumerate() {
+                let position = (block_number * block_size) as u64;
+                let mut block = vec![0u8; block_size];
+                
+                // Conservar el número de secuencia de actualización del bloque anterior
+                let mut previous = vec![0u8; block_size];
+                if self.read_runs(header, runs, position, &mut previous)? == block_size && &previous[0..4] == b"INDX" {
+                    if let Ok((usa_offset, _)) = update_sequence_array(&previous) {
+                        block[40..42].copy_from_slice(&previous[usa_offset..usa_offset + 2]);
+                    }
+                }
+                
+                block[0..4].copy_from_slice(b"INDX");
+                write_u16(&mut block, 4, 40);
+                write_u16(&mut block, 6, usa_count as u16);
+                write_u64(&mut block, 16, position / self.index_vcn_size(block_size as u32));
+                let node = serialize_index_node(block_entries, *block_last, block_entries_offset - 24, Some(block_size - 24));
+                block[24..24 + node.len()].copy_from_slice(&node);
+                prepare_fixups(&mut block)?;
+                self.write_runs(header, runs, position, &block)?;
+            }
+        }
+        
+        self.write_mft_record_data(directory_record, &mut record)
+    }
+    
+    /// Ajustar $INDEX_ALLOCATION y $BITMAP:$I30 a `block_count` bloques en uso
+    ///
+    /// Devuelve la asignación resultante (o `None` si el índice no tiene ni
+    /// necesita bloques) y añade a `allocated` los tramos de clústeres nuevos.
+    fn resize_index_allocation(&self, record: &mut [u8], block_count: u64, block_size: usize, allocated: &mut Vec<(u64, u64)>) -> Result<Option<(NtfsNonResidentAttribute, Vec<NtfsDataRun>)>, &'static str> {
+        let existing = find_attribute_offset(record, NtfsAttributeType::IndexAllocation as u32, NTFS_DIRECTORY_INDEX_NAME);
+        if existing.is_none() && block_count == 0 {
+            return Ok(None);
+        }
+        
+        let (attribute_id, mut runs, current_size) = match existing {
+            Some(offset) => {
+                let length = read_u32(record, offset + 4) as usize;
+                match self.attribute_value(&record[offset..offset + length])? {
+                    NtfsAttributeValue::NonResident { header, runs } => (header.header.attribute_id, runs, header.actual_size),
+                    NtfsAttributeValue::Resident(_) => return Err("Index allocation must be non-resident"),
+                }
+            }
+            None => (next_attribute_id(record), Vec::new(), 0),
+        };
+        
+        let cluster_size = self.cluster_size as u64;
+        let needed = block_count * block_size as u64;
+        let needed_clusters = (needed + cluster_size - 1) / cluster_size;
+        let mapped_end = runs.last().map_or(0, |run| run.vcn + run.length);
+        if needed_clusters > mapped_end {
+            allocated.extend(self.allocate_vcn_range(&mut runs, mapped_end, needed_clusters)?);
+        }
+        
+        let data_size = core::cmp::max(current_size, needed);
+        let attr = self.build_non_resident_attribute(NtfsAttributeType::IndexAllocation as u32, NTFS_DIRECTORY_INDEX_NAME, attribute_id, 0, &runs, data_size, data_size);
+        
+        // Un bit por bloque; el atributo $BITMAP crece de 8 en 8 bytes
+        let total_blocks = (data_size / block_size as u64) as usize;
+        let mut bitmap = vec![0u8; core::cmp::max(8, align8((total_blocks + 7) / 8))];
+        for block in 0..block_count as usize {
+            bitmap[block / 8] |= 1 << (block % 8);
+        }
+        
+        let applied = match existing {
+            Some(offset) => replace_attribute(record, offset, &attr),
+            None => insert_attribute(record, &attr).map(|_| ()),
+        }
+        .and_then(|_| set_index_bitmap(record, &bitmap));
+        if let Err(error) = applied {
+            let _ = self.free_clusters(allocated);
+            allocated.clear();
+            return Err(error);
+        }
+        
+        let header = parse_non_resident_header(&attr)?;
+        Ok(Some((header, runs)))
+    }
+    
+    /// Crear archivo
+    ///
+    /// Asigna un registro MFT con $STANDARD_INFORMATION, $FILE_NAME y un $DATA
+    /// residente vacío y lo inserta en el índice del directorio padre.
+    pub fn create_file(&mut self, directory_record: u64, filename: &str) -> Result<u64, &'static str> {
+        self.create_entry(directory_record, filename, false)
+    }
+    
+    /// Crear directorio con un índice $I30 vacío
+    pub fn create_directory(&mut self, directory_record: u64, dirname: &str) -> Result<u64, &'static str> {
+        self.create_entry(directory_record, dirname, true)
+    }
+    
+    /// Crear un archivo o directorio en `directory_record`
+    fn create_entry(&mut self, directory_record: u64, name: &str, directory: bool) -> Result<u64, &'static str> {
+        let parent = self.read_record_for_update(directory_record)?;
+        if read_u16(&parent, 22) & MFT_RECORD_IS_DIRECTORY == 0 {
+            return Err("Not a directory");
+        }
+        if !is_valid_ntfs_name(name) {
+            return Err("Invalid file name");
+        }
+        if self.find_file(directory_record, name).is_ok() {
+            return Err("File already exists");
+        }
+        
+        let parent_reference = directory_record | (read_u16(&parent, 16) as u64) << 48;
+        // Los archivos nuevos heredan el descriptor de seguridad del padre
+        let security_id = self.read_standard_information(&parent).map(|info| info.security_id).unwrap_or(0);
+        
+        let record_number = self.allocate_mft_record()?;
+        let mut record = match self.build_entry_record(record_number, parent_reference, name, directory, security_id) {
+            Ok(record) => record,
+            Err(error) => {
+                let _ = self.release_mft_record(record_number);
+                return Err(error);
+            }
+        };
+        if let Err(error) = self.write_mft_record_data(record_number, &mut record) {
+            let _ = self.release_mft_record(record_number);
+            return Err(error);
+        }
+        
+        let reference = record_number | (read_u16(&record, 16) as u64) << 48;
+        let key = self
+            .find_attribute(&record, NtfsAttributeType::FileName, "", None)
+            .and_then(|attr| attribute_content(attr).ok())
+            .map(|content| content.to_vec())
+            .ok_or("Attribute not found")?;
+        if let Err(error) = self.index_insert(directory_record, reference, key) {
+            // Deshacer: el registro vuelve a quedar libre
+            write_u16(&mut record, 22, 0);
+            let _ = self.write_mft_record_data(record_number, &mut record);
+            let _ = self.release_mft_record(record_number);
+            return Err(error);
+        }
+        
+        self.touch_directory(directory_record)?;
+        Ok(record_number)
+    }
+    
+    /// Construir el registro MFT de un archivo o directorio nuevo
+    fn build_entry_record(&self, record_number: u64, parent_reference: u64, name: &str, directory: bool, security_id: u32) -> Result<Vec<u8>, &'static str> {
+        let flags = MFT_RECORD_IN_USE | if directory { MFT_RECORD_IS_DIRECTORY } else { 0 };
+        let mut record = self.format_mft_record(record_number, flags)?;
+        let now = self.current_time();
+        let name_units: Vec<u16> = name.encode_utf16().collect();
+        let namespace = if is_dos_name(name) { NTFS_NAMESPACE_WIN32_AND_DOS } else { NTFS_NAMESPACE_WIN32 };
+        let (file_attributes, name_attributes) = if directory {
+            (0, NTFS_FILE_NAME_INDEX_PRESENT)
+        } else {
+            (NTFS_FILE_ATTRIBUTE_ARCHIVE, NTFS_FILE_ATTRIBUTE_ARCHIVE)
+        };
+        
+        let id = next_attribute_id(&mut record);
+        let standard = standard_information_content(now, file_attributes, security_id);
+        insert_attribute(&mut record, &build_resident_attribute(NtfsAttributeType::StandardInformation as u32, "", id, &standard, false))?;
+        
+        let id = next_attribute_id(&mut record);
+        let file_name = file_name_content(parent_reference, &name_units, namespace, name_attributes, now);
+        insert_attribute(&mut record, &build_resident_attribute(NtfsAttributeType::FileName as u32, "", id, &file_name, true))?;
+        
+        let id = next_attribute_id(&mut record);
+        if directory {
+            let root = empty_index_root(self.index_block_size(), self.cluster_size);
+            insert_attribute(&mut record, &build_resident_attribute(NtfsAttributeType::IndexRoot as u32, NTFS_DIRECTORY_INDEX_NAME, id, &root, false))?;
+        } else {
+            insert_attribute(&mut record, &build_resident_attribute(NtfsAttributeType::Data as u32, "", id, &[], false))?;
+        }
+        Ok(record)
+    }
+    
+    /// Eliminar archivo (o directorio vacío)
+    ///
+    /// Quita todos sus nombres de los índices de los padres, libera el
+    /// registro MFT (incrementando su número de secuencia para invalidar las
+    /// referencias antiguas) y después los clústeres de sus atributos.
+    pub fn delete_file(&mut self, file_record: u64) -> Result<(), &'static str> {
+        if file_record < NTFS_FIRST_USER_RECORD {
+            return Err("Cannot delete a system file");
+        }
+        let mut record = self.read_record_for_update(file_record)?;
+        if read_u16(&record, 22) & MFT_RECORD_IS_DIRECTORY != 0 {
+            let index = self.open_directory_index(file_record)?;
+            if !self.index_entries_in_order(&index)?.is_empty() {
+                return Err("Directory not empty");
+            }
+        }
+        
+        let mut parents: Vec<u64> = Vec::new();
+        let mut released = Vec::new();
+        for attr in self.attributes(&record) {
+            if attr[8] != 0 {
+                let header = parse_non_resident_header(attr)?;
+                let runs = decode_data_runs(&attr[header.runlist_offset as usize..], header.starting_vcn)?;
+                released.extend(runs.iter().filter_map(|run| run.lcn.map(|lcn| (lcn, run.length))));
+            } else if read_u32(attr, 0) == NtfsAttributeType::FileName as u32 {
+                let parent = read_u64(attribute_content(attr)?, 0) & NTFS_MFT_REFERENCE_MASK;
+                if !parents.contains(&parent) {
+                    parents.push(parent);
+                }
+            }
+        }
+        
+        for &parent in &parents {
+            self.index_remove_record(parent, file_record)?;
+        }
+        
+        let flags = read_u16(&record, 22) & !MFT_RECORD_IN_USE;
+        write_u16(&mut record, 22, flags);
+        let sequence = read_u16(&record, 16).wrapping_add(1);
+        write_u16(&mut record, 16, if sequence == 0 { 1 } else { sequence });
+        self.write_mft_record_data(file_record, &mut record)?;
+        self.release_mft_record(file_record)?;
+        self.free_clusters(&released)?;
+        
+        for parent in parents {
+            self.touch_directory(parent)?;
+        }
+        Ok(())
+    }
+    
+    /// Renombrar o mover un archivo o directorio
+    ///
+    /// Sus nombres ($FILE_NAME, incluido el alias DOS) se sustituyen por uno
+    /// solo en `directory_record`, que conserva atributos y fecha de creación,
+    /// y la entrada pasa del índice del padre antiguo al del nuevo. Si el
+    /// índice de destino no admite la entrada se restauran el registro y las
+    /// entradas antiguas.
+    pub fn rename_file(&mut self, file_record: u64, directory_record: u64, new_name: &str) -> Result<(), &'static str> {
+        if file_record < NTFS_FIRST_USER_RECORD {
+            return Err("Cannot rename a system file");
+        }
+        if !is_valid_ntfs_name(new_name) {
+            return Err("Invalid file name");
+        }
+        let parent = self.read_record_for_update(directory_record)?;
+        if read_u16(&parent, 22) & MFT_RECORD_IS_DIRECTORY == 0 {
+            return Err("Not a directory");
+        }
+        // Un cambio de mayúsculas encuentra el propio archivo
+        match self.find_file(directory_record, new_name) {
+            Ok(existing) if existing != file_record => return Err("File already exists"),
+            _ => {}
+        }
+        
+        let original = self.read_record_for_update(file_record)?;
+        let mut record = original.clone();
+        let mut old_keys: Vec<(u64, Vec<u8>)> = Vec::new();
+        while let Some(offset) = find_attribute_offset(&record, NtfsAttributeType::FileName as u32, "") {
+            let range = resident_content_range(&record, offset)?;
+            let content = record[range].to_vec();
+            old_keys.push((read_u64(&content, 0) & NTFS_MFT_REFERENCE_MASK, content));
+            replace_attribute(&mut record, offset, &[])?;
+        }
+        let (_, first_key) = old_keys.first().ok_or("Attribute not found")?;
+        
+        let now = self.current_time();
+        let parent_reference = directory_record | (read_u16(&parent, 16) as u64) << 48;
+        let name_units: Vec<u16> = new_name.encode_utf16().collect();
+        let namespace = if is_dos_name(new_name) { NTFS_NAMESPACE_WIN32_AND_DOS } else { NTFS_NAMESPACE_WIN32 };
+        let mut content = file_name_content(parent_reference, &name_units, namespace, read_u32(first_key, 56), now);
+        content[8..16].copy_from_slice(&first_key[8..16]);
+        let id = next_attribute_id(&mut record);
+        insert_attribute(&mut record, &build_resident_attribute(NtfsAttributeType::FileName as u32, "", id, &content, true))?;
+        
+        let keys = self.update_file_metadata(&mut record, [None, None, Some(now), None], None)?;
+        let reference = file_record | (read_u16(&record, 16) as u64) << 48;
+        let mut old_parents: Vec<u64> = Vec::new();
+        for (old_parent, _) in &old_keys {
+            if !old_parents.contains(old_parent) {
+                old_parents.push(*old_parent);
+            }
+        }
+        
+        for &old_parent in &old_parents {
+            self.index_remove_record(old_parent, file_record)?;
+        }
+        self.write_mft_record_data(file_record, &mut record)?;
+        for (_, key) in keys {
+            if let Err(error) = self.index_insert(directory_record, reference, key) {
+                let mut original = original;
+                let _ = self.index_remove_record(directory_record, file_record);
+                let _ = self.write_mft_record_data(file_record, &mut original);
+                for (old_parent, old_key) in old_keys {
+                    let _ = self.index_insert(old_parent, reference, old_key);
+                }
+                return Err(error);
+            }
+        }
+        
+        for old_parent in old_parents {
+            if old_parent != directory_record {
+                self.touch_directory(old_parent)?;
+            }
+        }
+        self.touch_directory(directory_record)
+    }
+    
+    /// Obtener estadísticas del driver
+    pub fn get_stats(&self) -> (u32, u32, u64, u64) {
+        (self.cache_hits, self.cache_misses, self.total_clusters, self.mft_record_size as u64)
+    }
+    
+    /// Información de un archivo (o de uno de sus flujos) para el VFS
+    fn vfs_file_info(&self, name: &str, record_number: u64, stream: &str) -> Result<FileInfo, &'static str> {
+        let mut record = vec![0u8; self.mft_record_size as usize];
+        self.read_mft_record_data(record_number, &mut record)?;
+        let information = self.read_standard_information(&record)?;
+        let attributes = self.get_file_attributes(record_number)?;
+        
+        let stream_info = self
+            .list_streams(record_number)?
+            .into_iter()
+            .find(|info| info.name.eq_ignore_ascii_case(stream));
+        let (size, allocated_size) = match stream_info {
+            Some(info) => (info.size, info.allocated_size),
+            // Los directorios no tienen $DATA sin nombre
+            None if stream.is_empty() => (0, 0),
+            None => return Err("Stream not found"),
+        };
+        
+        let node_type = if attributes & NTFS_FILE_ATTRIBUTE_DIRECTORY != 0 && stream.is_empty() {
+            NodeType::Directory
+        } else {
+            NodeType::File
+        };
+        let mut file_info = FileInfo::new(0, name, node_type);
+        file_info.size = size;
+        file_info.block_size = self.cluster_size;
+        file_info.block_count = (allocated_size + self.cluster_size as u64 - 1) / self.cluster_size as u64;
+        if attributes & NTFS_FILE_ATTRIBUTE_READONLY != 0 {
+            file_info.permissions = FilePermissions::read_only();
+        }
+        file_info.created_time = ntfs_time_to_unix(information.creation_time);
+        file_info.modified_time = ntfs_time_to_unix(information.file_change_time);
+        file_info.accessed_time = ntfs_time_to_unix(information.last_access_time);
+        file_info.device = self.device_id;
+        file_info.inode = record_number;
+        Ok(file_info)
+    }
+}
+
+impl FileSystemDriver for NtfsDriver {
+    fn open(&mut self, file_path: &str, create: bool) -> Result<FileInfo, &'static str> {
+        match self.lookup_stream(file_path) {
+            Ok((record_number, stream)) => match self.stat(file_path) {
+                Err("Stream not found") if create => {
+                    self.write_stream_data(record_number, &stream, 0, &[])?;
+                    self.stat(file_path)
+                }
+                result => result,
+            },
+            Err("File not found") if create => {
+                let path = strip_drive_letter(file_path);
+                let split = path.rfind(|c| c == '\\' || c == '/').map_or(0, |separator| separator + 1);
+                let (name, _) = split_stream_name(&path[split..])?;
+                let directory_record = self.lookup_path(&path[..split])?;
+                self.create_file(directory_record, name)?;
+                // Crear también el flujo, si la ruta lo nombra
+                self.open(file_path, true)
+            }
+            Err(e) => Err(e),
+        }
+    }
+    
+    fn read_file(&mut self, file_path: &str, buffer: &mut [u8], offset: u64) -> Result<usize, &'static str> {
+        let (record_number, stream) = self.lookup_stream(file_path)?;
+        self.read_stream(record_number, &stream, offset, buffer)
+    }
+    
+    fn write_file(&mut self, file_path: &str, buffer: &[u8], offset: u64) -> Result<usize, &'static str> {
+        let (record_number, stream) = self.lookup_stream(file_path)?;
+        self.write_stream_data(record_number, &stream, offset, buffer)
+    }
+    
+    fn read_dir(&mut self, dir_path: &str) -> Result<Vec<FileInfo>, &'static str> {
+        let directory_record = self.lookup_path(dir_path)?;
+        // Los archivos de metadatos ($MFT, $Bitmap, ...) no se muestran
+        self.read_directory(directory_record)?
+            .iter()
+            .filter(|entry| entry.record_number() >= NTFS_FIRST_USER_RECORD)
+            .map(|entry| self.vfs_file_info(&entry.name, entry.record_number(), ""))
+            .collect()
+    }
+    
+    fn stat(&mut self, file_path: &str) -> Result<FileInfo, &'static str> {
+        let (record_number, stream) = self.lookup_stream(file_path)?;
+        let name = file_path
+            .rsplit(|c| c == '\\' || c == '/')
+            .find(|component| !component.is_empty())
+            .unwrap_or("/");
+        self.vfs_file_info(name, record_number, &stream)
+    }
+    
+    fn unlink(&mut self, file_path: &str) -> Result<(), &'static str> {
+        let (record_number, stream) = self.lookup_stream(file_path)?;
+        if stream.is_empty() {
+            self.delete_file(record_number)
+        } else {
+            self.delete_stream(record_number, &stream)
+        }
+    }
+    
+    fn truncate(&mut self, file_path: &str, size: u64) -> Result<(), &'static str> {
+        let (record_number, stream) = self.lookup_stream(file_path)?;
+        self.truncate_stream(record_number, &stream, size)
+    }
+    
+    fn rename(&mut self, old_path: &str, new_path: &str) -> Result<(), &'static str> {
+        let (record_number, stream) = self.lookup_stream(old_path)?;
+        if !stream.is_empty() {
+            return Err("Cannot rename a stream");
+        }
+        let path = strip_drive_letter(new_path).trim_end_matches(|c| c == '\\' || c == '/');
+        let split = path.rfind(|c| c == '\\' || c == '/').map_or(0, |separator| separator + 1);
+        let directory_record = self.lookup_path(&path[..split])?;
+        self.rename_file(record_number, directory_record, &path[split..])
+    }
+    
+    fn sync(&mut self) -> Result<(), &'static str> {
+        self.flush()
+    }
+}
+
+/// Iterador sobre los atributos de un registro MFT
+pub struct NtfsAttributeIter<'a> {
+    record: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Iterator for NtfsAttributeIter<'a> {
+    type Item = &'a [u8];
+    
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset + 8 > self.record.len() {
+            return None;
+        }
+        
+        let attr_type = read_u32(self.record, self.offset);
+        let attr_length = read_u32(self.record, self.offset + 4) as usize;
+        if attr_type == NtfsAttributeType::EndOfAttributes as u32
+            || attr_length < 16
+            || self.offset + attr_length > self.record.len()
+        {
+            self.offset = self.record.len();
+            return None;
+        }
+        
+        let attr = &self.record[self.offset..self.offset + attr_length];
+        self.offset += attr_length;
+        Some(attr)
+    }
+}
+
+/// Aplicar las fixups del array de secuencia de actualización (USA) tras leer de disco
+///
+/// Los dos últimos bytes de cada sector de 512 bytes guardan el número de
+/// secuencia; los valores reales están en el USA. Si algún sector no lleva
+/// el número esperado la escritura quedó a medias.
+pub fn apply_fixups(buffer: &mut [u8], magic: &[u8; 4]) -> Result<(), &'static str> {
+    if &buffer[0..4] == b"BAAD" {
+        return Err(NTFS_ERROR_TORN_RECORD);
+    }
+    if &buffer[0..4] != magic {
+        return Err(if magic == b"INDX" { "Invalid index block signature" } else { "Invalid MFT record signature" });
+    }
+    
+    let (usa_offset, usa_count) = update_sequence_array(buffer)?;
+    let usn = [buffer[usa_offset], buffer[usa_offset + 1]];
+    for i in 1..usa_count {
+        let end = i * NTFS_FIXUP_STRIDE - 2;
+        if buffer[end..end + 2] != usn {
+            return Err(NTFS_ERROR_TORN_RECORD);
+        }
+        let fixup = usa_offset + i * 2;
+        buffer[end] = buffer[fixup];
+        buffer[end + 1] = buffer[fixup + 1];
+    }
+    Ok(())
+}
+
+/// Preparar un registro para escribirlo: nuevo número de secuencia y fixups
+pub fn prepare_fixups(buffer: &mut [u8]) -> Result<(), &'static str> {
+    let (usa_offset, usa_count) = update_sequence_array(buffer)?;
+    
+    // 0 y 0xFFFF no se usan como número de secuencia
+    let mut usn = read_u16(buffer, usa_offset).wrapping_add(1);
+    if usn == 0 || usn == 0xFFFF {
+        usn = 1;
+    }
+    buffer[usa_offset..usa_offset + 2].copy_from_slice(&usn.to_le_bytes());
+    
+    for i in 1..usa_count {
+        let end = i * NTFS_FIXUP_STRIDE - 2;
+        let fixup = usa_offset + i * 2;
+        buffer[fixup] = buffer[end];
+        buffer[fixup + 1] = buffer[end + 1];
+        buffer[end..end + 2].copy_from_slice(&usn.to_le_bytes());
+    }
+    Ok(())
+}
+
+/// Localizar y validar el USA de un registro multisector
+fn update_sequence_array(buffer: &[u8]) -> Result<(usize, usize), &'static str> {
+    if buffer.len() < NTFS_FIXUP_STRIDE || buffer.len() % NTFS_FIXUP_STRIDE != 0 {
+        return Err("Invalid multi-sector record size");
+    }
+    
+    let usa_offset = read_u16(buffer, 4) as usize;
+    let usa_count = read_u16(buffer, 6) as usize;
+    if usa_count != buffer.len() / NTFS_FIXUP_STRIDE + 1
+        || usa_offset % 2 != 0
+        || usa_offset < 8
+        || usa_offset + usa_count * 2 > NTFS_FIXUP_STRIDE - 2
+    {
+        return Err("Invalid update sequence array");
+    }
+    Ok((usa_offset, usa_count))
+}
+
+/// Validar la cabecera de un registro MFT ya corregido
+pub fn validate_mft_record(record: &[u8], record_number: u64) -> Result<(), &'static str> {
+    if record.len() < 48 || &record[0..4] != b"FILE" {
+        return Err("Invalid MFT record signature");
+    }
+    
+    let attributes_offset = read_u16(record, 20) as usize;
+    let bytes_in_use = read_u32(record, 24) as usize;
+    let bytes_allocated = read_u32(record, 28) as usize;
+    if bytes_allocated != record.len() {
+        return Err("MFT record allocated size mismatch");
+    }
+    if bytes_in_use > bytes_allocated || bytes_in_use % 8 != 0 {
+        return Err("MFT record used size out of range");
+    }
+    if attributes_offset % 8 != 0 || attributes_offset < 48 || attributes_offset + 8 > bytes_in_use {
+        return Err("Invalid MFT record attribute offset");
+    }
+    
+    // Los registros de NTFS 3.1 (USA en el desplazamiento 48) guardan su propio número
+    let stored_number = read_u32(record, 44);
+    if read_u16(record, 4) >= 48 && stored_number != 0 && stored_number as u64 != record_number & 0xFFFF_FFFF {
+        return Err("MFT record number mismatch");
+    }
+    Ok(())
+}
+
+/// Decodificar la lista de runs (mapping pairs) de un atributo no residente
+///
+/// Cada run empieza con un byte cuyo nibble bajo es el tamaño del campo de
+/// longitud y el alto el del desplazamiento de LCN, que es relativo al run
+/// anterior y con signo. Un desplazamiento de tamaño 0 indica un run disperso.
+pub fn decode_data_runs(runlist: &[u8], starting_vcn: u64) -> Result<Vec<NtfsDataRun>, &'static str> {
+    let mut runs = Vec::new();
+    let mut vcn = starting_vcn;
+    let mut lcn: i64 = 0;
+    let mut offset = 0;
+    
+    while offset < runlist.len() && runlist[offset] != 0 {
+        let header = runlist[offset];
+        let length_size = (header & 0x0F) as usize;
+        let offset_size = (header >> 4) as usize;
+        offset += 1;
+        
+        if length_size == 0 || length_size > 8 || offset_size > 8 || offset + length_size + offset_size > runlist.len() {
+            return Err("Invalid data run");
+        }
+        
+        let mut length: u64 = 0;
+        for i in 0..length_size {
+            length |= (runlist[offset + i] as u64) << (i * 8);
+        }
+        offset += length_size;
+        if length == 0 {
+            return Err("Invalid data run length");
+        }
+        
+        let run_lcn = if offset_size == 0 {
+            None
+        } else {
+            let mut delta: i64 = 0;
+            for i in 0..offset_size {
+                delta |= (runlist[offset + i] as i64) << (i * 8);
+            }
+            // Extender el signo del último byte
+            if offset_size < 8 && runlist[offset + offset_size - 1] & 0x80 != 0 {
+                delta |= -1i64 << (offset_size * 8);
+            }
+            offset += offset_size;
+            
+            lcn = lcn.checked_add(delta).ok_or("Invalid data run offset")?;
+            if lcn < 0 {
+                return Err("Invalid data run offset");
+            }
+            Some(lcn as u64)
+        };
+        
+        runs.push(NtfsDataRun { vcn, length, lcn: run_lcn });
+        vcn += length;
+    }
+    
+    Ok(runs)
+}
+
+/// Codificar una lista de runs (inverso de `decode_data_runs`)
+///
+/// Los runs deben empezar en el VCN 0 y ser consecutivos. Longitudes y
+/// desplazamientos usan el menor número de bytes con signo que los representa.
+pub fn encode_data_runs(runs: &[NtfsDataRun]) -> Vec<u8> {
+    let mut runlist = Vec::new();
+    let mut previous_lcn: i64 = 0;
+    
+    for run in runs {
+        let length_size = signed_byte_count(run.length as i64);
+        let length_bytes = run.length.to_le_bytes();
+        match run.lcn {
+            None => {
+                runlist.push(length_size as u8);
+                runlist.extend_from_slice(&length_bytes[..length_size]);
+            }
+            Some(lcn) => {
+                let delta = lcn as i64 - previous_lcn;
+                let offset_size = signed_byte_count(delta);
+                runlist.push(length_size as u8 | (offset_size as u8) << 4);
+                runlist.extend_from_slice(&length_bytes[..length_size]);
+                runlist.extend_from_slice(&delta.to_le_bytes()[..offset_size]);
+                previous_lcn = lcn as i64;
+            }
+        }
+    }
+    
+    runlist.push(0);
+    runlist
+}
+
+/// Bytes necesarios para guardar un valor con signo
+fn signed_byte_count(value: i64) -> usize {
+    (1..8)
+        .find(|&bytes| {
+            let limit = 1i64 << (bytes * 8 - 1);
+            value >= -limit && value < limit
+        })
+        .unwrap_or(8)
+}
+
+/// Añadir un run al final de una lista, fusionándolo con el anterior si es contiguo
+fn push_data_run(runs: &mut Vec<NtfsDataRun>, length: u64, lcn: Option<u64>) {
+    if length == 0 {
+        return;
+    }
+    if let Some(last) = runs.last_mut() {
+        let contiguous = match (last.lcn, lcn) {
+            (None, None) => true,
+            (Some(last_lcn), Some(lcn)) => last_lcn + last.length == lcn,
+            _ => false,
+        };
+        if contiguous {
+            last.length += length;
+            return;
+        }
+    }
+    let vcn = runs.last().map_or(0, |run| run.vcn + run.length);
+    runs.push(NtfsDataRun { vcn, length, lcn });
+}
+
+/// Recortar una lista de runs a `clusters` clústeres; devuelve los tramos sobrantes
+fn truncate_data_runs(runs: &mut Vec<NtfsDataRun>, clusters: u64) -> Vec<(u64, u64)> {
+    let mut released = Vec::new();
+    let mut kept = Vec::new();
+    for run in runs.iter() {
+        let keep = core::cmp::min(run.length, clusters.saturating_sub(run.vcn));
+        if let Some(lcn) = run.lcn {
+            if keep < run.length {
+                released.push((lcn + keep, run.length - keep));
+            }
+        }
+        if keep > 0 {
+            kept.push(NtfsDataRun { length: keep, ..*run });
+        }
+    }
+    *runs = kept;
+    released
+}
+
+/// Primer y último byte de un bitmap afectados por unos tramos de clústeres
+fn bitmap_byte_range(extents: &[(u64, u64)]) -> (usize, usize) {
+    let first = extents.iter().map(|&(lcn, _)| lcn).min().unwrap_or(0);
+    let last = extents.iter().map(|&(lcn, length)| lcn + length - 1).max().unwrap_or(0);
+    ((first / 8) as usize, (last / 8) as usize)
+}
+
+/// Descomprimir datos LZNT1 (bloques con cabecera de 16 bits y hasta 4 KB de salida)
+///
+/// Cada bloque ocupa exactamente `LZNT1_CHUNK_SIZE` bytes de salida; si
+/// produce menos, el resto se rellena con ceros. Una cabecera cero termina
+/// el flujo. Devuelve los bytes escritos en `output`.
+pub fn decompress_lznt1(input: &[u8], output: &mut [u8]) -> Result<usize, &'static str> {
+    let mut input_position = 0;
+    let mut output_position = 0;
+    
+    while input_position + 2 <= input.len() && output_position < output.len() {
+        let header = read_u16(input, input_position);
+        if header == 0 {
+            break;
+        }
+        input_position += 2;
+        let chunk_length = (header & 0x0FFF) as usize + 1;
+        if input_position + chunk_length > input.len() {
+            return Err("Truncated LZNT1 chunk");
+        }
+        let chunk = &input[input_position..input_position + chunk_length];
+        input_position += chunk_length;
+        
+        let chunk_start = output_position;
+        let chunk_end = core::cmp::min(chunk_start + LZNT1_CHUNK_SIZE, output.len());
+        if header & 0x8000 == 0 {
+            // Bloque guardado sin comprimir
+            let count = core::cmp::min(chunk.len(), chunk_end - chunk_start);
+            output[chunk_start..chunk_start + count].copy_from_slice(&chunk[..count]);
+            output_position += count;
+        } else {
+            let mut position = 0;
+            'chunk: while position < chunk.len() {
+                let tags = chunk[position];
+                position += 1;
+                for bit in 0..8 {
+                    if position >= chunk.len() || output_position >= chunk_end {
+                        break 'chunk;
+                    }
+                    if tags & (1 << bit) == 0 {
+                        output[output_position] = chunk[position];
+                        output_position += 1;
+                        position += 1;
+                        continue;
+                    }
+                    
+                    // Referencia hacia atrás: el reparto de bits entre desplazamiento
+                    // y longitud depende de lo ya descomprimido en el bloque
+                    if position + 2 > chunk.len() {
+                        return Err("Truncated LZNT1 token");
+                    }
+                    let token = read_u16(chunk, position) as usize;
+                    position += 2;
+                    let produced = output_position - chunk_start;
+                    let mut offset_bits = 4;
+                    while offset_bits < 12 && (1usize << offset_bits) < produced {
+                        offset_bits += 1;
+                    }
+                    let length_bits = 16 - offset_bits;
+                    let displacement = (token >> length_bits) + 1;
+                    let length = (token & ((1 << length_bits) - 1)) + 3;
+                    if displacement > produced {
+                        return Err("Invalid LZNT1 back-reference");
+                    }
+                    for _ in 0..length {
+                        if output_position >= chunk_end {
+                            break;
+                        }
+                        output[output_position] = output[output_position - displacement];
+                        output_position += 1;
+                    }
+                }
+            }
+        }
+        
+        output[output_position..chunk_end].fill(0);
+        output_position = chunk_end;
+    }
+    
+    Ok(output_position)
+}
+
+/// Decodificar la cabecera de un atributo no residente
+pub fn parse_non_resident_header(attr: &[u8]) -> Result<NtfsNonResidentAttribute, &'static str> {
+    if attr.len() < 64 || attr[8] == 0 {
+        return Err("Invalid non-resident attribute");
+    }
+    
+    Ok(NtfsNonResidentAttribute {
+        header: NtfsAttributeHeader {
+            attribute_type: read_u32(attr, 0),
+            length: read_u32(attr, 4),
+            non_resident: attr[8],
+            name_length: attr[9],
+            name_offset: read_u16(attr, 10),
+            flags: read_u16(attr, 12),
+            attribute_id: read_u16(attr, 14),
+        },
+        starting_vcn: read_u64(attr, 16),
+        ending_vcn: read_u64(attr, 24),
+        runlist_offset: read_u16(attr, 32),
+        compression_unit: read_u16(attr, 34),
+        allocated_size: read_u64(attr, 40),
+        actual_size: read_u64(attr, 48),
+        initialized_size: read_u64(attr, 56),
+    })
+}
+
+/// Decodificar el contenido de un atributo $FILE_NAME (o la clave de un índice $I30)
+pub fn parse_file_name(content: &[u8]) -> Result<NtfsFileName, &'static str> {
+    if content.len() < 66 {
+        return Err("Invalid filename attribute");
+    }
+    
+    let name_length = content[64] as usize;
+    if name_length == 0 || content.len() < 66 + name_length * 2 {
+        return Err("Invalid filename length");
+    }
+    
+    let mut name = [0u16; 255];
+    for i in 0..name_length {
+        name[i] = read_u16(content, 66 + i * 2);
+    }
+    
+    Ok(NtfsFileName {
+        parent_directory: read_u64(content, 0),
+        creation_time: read_u64(content, 8),
+        file_change_time: read_u64(content, 16),
+        mft_change_time: read_u64(content, 24),
+        last_access_time: read_u64(content, 32),
+        allocated_size: read_u64(content, 40),
+        actual_size: read_u64(content, 48),
+        file_attributes: read_u32(content, 56),
+        reparse_point: read_u32(content, 60),
+        name_length: content[64],
+        name_type: content[65],
+        name,
+    })
+}
+
+/// Convertir segundos Unix a tiempo NTFS (intervalos de 100 ns desde 1601)
+pub fn unix_to_ntfs_time(unix_seconds: u64) -> u64 {
+    (unix_seconds + NTFS_UNIX_EPOCH_DELTA) * 10_000_000
+}
+
+/// Convertir tiempo NTFS a segundos Unix (0 para fechas anteriores a 1970)
+pub fn ntfs_time_to_unix(ntfs_time: u64) -> u64 {
+    (ntfs_time / 10_000_000).saturating_sub(NTFS_UNIX_EPOCH_DELTA)
+}
+
+/// Desplazamientos de los atributos de un registro MFT y del marcador final
+fn attribute_offsets(record: &[u8]) -> (Vec<usize>, usize) {
+    let mut offsets = Vec::new();
+    let mut offset = read_u16(record, 20) as usize;
+    while offset + 8 <= record.len() {
+        let attr_type = read_u32(record, offset);
+        let attr_length = read_u32(record, offset + 4) as usize;
+        if attr_type == NtfsAttributeType::EndOfAttributes as u32 || attr_length < 16 || offset + attr_length > record.len() {
+            break;
+        }
+        offsets.push(offset);
+        offset += attr_length;
+    }
+    (offsets, offset)
+}
+
+/// Buscar un atributo por tipo y nombre y devolver su desplazamiento en el registro
+fn find_attribute_offset(record: &[u8], attribute_type: u32, name: &str) -> Option<usize> {
+    attribute_offsets(record).0.into_iter().find(|&offset| {
+        let attr_length = read_u32(record, offset + 4) as usize;
+        read_u32(record, offset) == attribute_type && attribute_name_matches(&record[offset..offset + attr_length], name)
+    })
+}
+
+/// Rango del contenido de un atributo residente dentro del registro
+fn resident_content_range(record: &[u8], offset: usize) -> Result<core::ops::Range<usize>, &'static str> {
+    let start = offset + read_u16(record, offset + 20) as usize;
+    let end = start + read_u32(record, offset + 16) as usize;
+    if record[offset + 8] != 0 || end > offset + read_u32(record, offset + 4) as usize {
+        return Err("Invalid resident attribute");
+    }
+    Ok(start..end)
+}
+
+/// Contenido de un atributo residente
+fn attribute_content(attr: &[u8]) -> Result<&[u8], &'static str> {
+    let range = resident_content_range(attr, 0)?;
+    Ok(&attr[range])
+}
+
+/// Insertar un atributo en su posición (los atributos van ordenados por tipo y nombre)
+fn insert_attribute(record: &mut [u8], attr: &[u8]) -> Result<usize, &'static str> {
+    let (offsets, end) = attribute_offsets(record);
+    let bytes_in_use = read_u32(record, 24) as usize;
+    if bytes_in_use + attr.len() > record.len() {
+        return Err(NTFS_ERROR_RECORD_FULL);
+    }
+    
+    let attribute_type = read_u32(attr, 0);
+    let name = attribute_name(attr);
+    let position = offsets
+        .into_iter()
+        .find(|&offset| {
+            let existing_type = read_u32(record, offset);
+            existing_type > attribute_type || (existing_type == attribute_type && attribute_name(&record[offset..]) > name)
+        })
+        .unwrap_or(end);
+    
+    record.copy_within(position..bytes_in_use, position + attr.len());
+    record[position..position + attr.len()].copy_from_slice(attr);
+    write_u32(record, 24, (bytes_in_use + attr.len()) as u32);
+    Ok(position)
+}
+
+/// Sustituir el atributo que empieza en `offset` por otro, quizá de distinto tamaño
+///
+/// Si no cabe, el registro no se modifica. Un atributo vacío lo elimina.
+fn replace_attribute(record: &mut [u8], offset: usize, attr: &[u8]) -> Result<(), &'static str> {
+    let old_length = read_u32(record, offset + 4) as usize;
+    let bytes_in_use = read_u32(record, 24) as usize;
+    let new_in_use = bytes_in_use - old_length + attr.len();
+    if new_in_use > record.len() {
+        return Err(NTFS_ERROR_RECORD_FULL);
+    }
+    
+    record.copy_within(offset + old_length..bytes_in_use, offset + attr.len());
+    record[offset..offset + attr.len()].copy_from_slice(attr);
+    if new_in_use < bytes_in_use {
+        record[new_in_use..bytes_in_use].fill(0);
+    }
+    write_u32(record, 24, new_in_use as u32);
+    Ok(())
+}
+
+/// Reservar el siguiente identificador de atributo del registro
+fn next_attribute_id(record: &mut [u8]) -> u16 {
+    let id = read_u16(record, 40);
+    write_u16(record, 40, id.wrapping_add(1));
+    id
+}
+
+/// Construir un atributo residente
+fn build_resident_attribute(attribute_type: u32, name: &str, attribute_id: u16, content: &[u8], indexed: bool) -> Vec<u8> {
+    let name_units: Vec<u16> = name.encode_utf16().collect();
+    let content_offset = align8(24 + name_units.len() * 2);
+    let length = align8(content_offset + content.len());
+    
+    let mut attr = vec![0u8; length];
+    write_u32(&mut attr, 0, attribute_type);
+    write_u32(&mut attr, 4, length as u32);
+    attr[9] = name_units.len() as u8;
+    write_u16(&mut attr, 10, 24);
+    write_u16(&mut attr, 14, attribute_id);
+    write_u32(&mut attr, 16, content.len() as u32);
+    write_u16(&mut attr, 20, content_offset as u16);
+    attr[22] = indexed as u8;
+    for (i, unit) in name_units.iter().enumerate() {
+        write_u16(&mut attr, 24 + i * 2, *unit);
+    }
+    attr[content_offset..content_offset + content.len()].copy_from_slice(content);
+    attr
+}
+
+/// Crear o sustituir el $BITMAP:$I30 residente de un directorio
+fn set_index_bitmap(record: &mut [u8], bitmap: &[u8]) -> Result<(), &'static str> {
+    let bitmap_type = NtfsAttributeType::Bitmap as u32;
+    match find_attribute_offset(record, bitmap_type, NTFS_DIRECTORY_INDEX_NAME) {
+        Some(offset) if record[offset + 8] == 0 => {
+            let id = read_u16(record, offset + 14);
+            replace_attribute(record, offset, &build_resident_attribute(bitmap_type, NTFS_DIRECTORY_INDEX_NAME, id, bitmap, false))
+        }
+        Some(_) => Err("Non-resident index bitmaps are not supported"),
+        None => {
+            let id = next_attribute_id(record);
+            insert_attribute(record, &build_resident_attribute(bitmap_type, NTFS_DIRECTORY_INDEX_NAME, id, bitmap, false)).map(|_| ())
+        }
+    }
+}
+
+/// Contenido de $STANDARD_INFORMATION (formato NTFS 3.x)
+fn standard_information_content(time: u64, file_attributes: u32, security_id: u32) -> Vec<u8> {
+    let mut content = vec![0u8; 72];
+    for i in 0..4 {
+        write_u64(&mut content, i * 8, time);
+    }
+    write_u32(&mut content, 32, file_attributes);
+    write_u32(&mut content, 52, security_id);
+    content
+}
+
+/// Contenido de $FILE_NAME, que es también la clave del índice $I30
+fn file_name_content(parent_reference: u64, name: &[u16], namespace: u8, file_attributes: u32, time: u64) -> Vec<u8> {
+    let mut content = vec![0u8; 66 + name.len() * 2];
+    write_u64(&mut content, 0, parent_reference);
+    for i in 0..4 {
+        write_u64(&mut content, 8 + i * 8, time);
+    }
+    write_u32(&mut content, 56, file_attributes);
+    content[64] = name.len() as u8;
+    content[65] = namespace;
+    for (i, unit) in name.iter().enumerate() {
+        write_u16(&mut content, 66 + i * 2, *unit);
+    }
+    content
+}
+
+/// Nombre (UTF-16) de un contenido $FILE_NAME
+fn file_name_units(content: &[u8]) -> Result<Vec<u16>, &'static str> {
+    let file_name = parse_file_name(content)?;
+    let name = file_name.name;
+    Ok(name[..file_name.name_length as usize].to_vec())
+}
+
+/// Contenido de $INDEX_ROOT para un índice $I30 vacío
+fn empty_index_root(block_size: u32, cluster_size: u32) -> Vec<u8> {
+    let mut content = vec![0u8; 16];
+    write_u32(&mut content, 0, NtfsAttributeType::FileName as u32);
+    write_u32(&mut content, 4, NTFS_COLLATION_FILE_NAME);
+    write_u32(&mut content, 8, block_size);
+    content[12] = if block_size >= cluster_size { (block_size / cluster_size) as u8 } else { (block_size / 512) as u8 };
+    content.extend(serialize_index_node(&[], None, 16, None));
+    content
+}
+
+/// Tamaño en disco de una entrada de índice
+fn index_entry_size(key_length: usize, has_subnode: bool) -> usize {
+    align8(16 + key_length) + if has_subnode { 8 } else { 0 }
+}
+
+/// Tamaño de las entradas de un nodo, incluida la entrada final
+fn index_node_size(entries: &[NtfsIndexEntry], last_subnode: Option<u64>) -> usize {
+    entries
+        .iter()
+        .map(|entry| index_entry_size(entry.key.len(), entry.subnode_vcn.is_some()))
+        .sum::<usize>()
+        + index_entry_size(0, last_subnode.is_some())
+}
+
+/// Serializar una entrada de índice
+fn serialize_index_entry(reference: u64, key: &[u8], subnode_vcn: Option<u64>, last: bool) -> Vec<u8> {
+    let length = index_entry_size(key.len(), subnode_vcn.is_some());
+    let mut flags = 0;
+    if subnode_vcn.is_some() {
+        flags |= NTFS_INDEX_ENTRY_SUBNODE;
+    }
+    if last {
+        flags |= NTFS_INDEX_ENTRY_LAST;
+    }
+    
+    let mut entry = vec![0u8; length];
+    write_u64(&mut entry, 0, reference);
+    write_u16(&mut entry, 8, length as u16);
+    write_u16(&mut entry, 10, key.len() as u16);
+    write_u16(&mut entry, 12, flags);
+    entry[16..16 + key.len()].copy_from_slice(key);
+    if let Some(vcn) = subnode_vcn {
+        write_u64(&mut entry, length - 8, vcn);
+    }
+    entry
+}
+
+/// Serializar un nodo de índice (cabecera de nodo y entradas)
+///
+/// `allocated_size` es `None` en la raíz, cuyo tamaño asignado coincide con
+/// el usado.
+fn serialize_index_node(entries: &[NtfsIndexEntry], last_subnode: Option<u64>, entries_offset: usize, allocated_size: Option<usize>) -> Vec<u8> {
+    let mut node = vec![0u8; entries_offset];
+    for entry in entries {
+        node.extend(serialize_index_entry(entry.mft_reference, &entry.key, entry.subnode_vcn, false));
+    }
+    node.extend(serialize_index_entry(0, &[], last_subnode, true));
+    
+    let index_length = node.len();
+    write_u32(&mut node, 0, entries_offset as u32);
+    write_u32(&mut node, 4, index_length as u32);
+    write_u32(&mut node, 8, allocated_size.unwrap_or(index_length) as u32);
+    node[12] = if last_subnode.is_some() { NTFS_INDEX_NODE_LARGE } else { 0 };
+    node
+}
+
+/// Repartir las entradas de un nivel del árbol en nodos de `capacity` bytes
+///
+/// Devuelve los nodos (entradas y subnodo de la entrada final) y los
+/// separadores que suben al nivel superior: el separador `i` va entre los
+/// nodos `i` e `i + 1`, y su subnodo izquierdo pasa a ser la entrada final
+/// del nodo `i`.
+fn pack_index_level(entries: Vec<NtfsIndexEntry>, last_subnode: Option<u64>, capacity: usize) -> (Vec<NtfsIndexNode>, Vec<NtfsIndexEntry>) {
+    let end_size = index_entry_size(0, last_subnode.is_some());
+    let mut nodes = Vec::new();
+    let mut separators = Vec::new();
+    let mut current = Vec::new();
+    let mut used = end_size;
+    
+    for entry in entries {
+        let size = index_entry_size(entry.key.len(), entry.subnode_vcn.is_some());
+        if current.is_empty() || used + size <= capacity {
+            used += size;
+            current.push(entry);
+        } else {
+            nodes.push((core::mem::take(&mut current), entry.subnode_vcn));
+            separators.push(NtfsIndexEntry { subnode_vcn: None, ..entry });
+            used = end_size;
+        }
+    }
+    nodes.push((current, last_subnode));
+    (nodes, separators)
+}
+
+/// Validar un nombre de archivo Win32
+fn is_valid_ntfs_name(name: &str) -> bool {
+    let length = name.encode_utf16().count();
+    length > 0
+        && length <= 255
+        && name != "."
+        && name != ".."
+        && !name.chars().any(|c| (c as u32) < 0x20 || "\\/:*?\"<>|".contains(c))
+}
+
+/// Quitar la letra de unidad (`C:`) del principio de una ruta
+fn strip_drive_letter(path: &str) -> &str {
+    match path.as_bytes() {
+        [drive, b':', ..] if drive.is_ascii_alphabetic() => &path[2..],
+        _ => path,
+    }
+}
+
+/// Separar `archivo:flujo[:$DATA]` en nombre de archivo y nombre de flujo
+///
+/// `archivo` y `archivo::$DATA` se refieren al flujo principal (nombre vacío).
+pub fn split_stream_name(name: &str) -> Result<(&str, &str), &'static str> {
+    let mut parts = name.splitn(3, ':');
+    let file = parts.next().unwrap_or("");
+    let stream = parts.next().unwrap_or("");
+    match parts.next() {
+        None => Ok((file, stream)),
+        Some(attribute_type) if attribute_type.eq_ignore_ascii_case("$DATA") => Ok((file, stream)),
+        Some(_) => Err("Unsupported stream type"),
+    }
+}
+
+/// Verificar si un nombre ya es un nombre DOS 8.3 válido (en mayúsculas)
+///
+/// En ese caso un solo $FILE_NAME sirve para los espacios Win32 y DOS.
+fn is_dos_name(name: &str) -> bool {
+    let valid = |part: &str, max: usize| {
+        !part.is_empty()
+            && part.len() <= max
+            && part.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || "!#$%&'()-@^_`{}~".contains(c))
+    };
+    match name.split_once('.') {
+        Some((base, extension)) => valid(base, 8) && valid(extension, 3),
+        None => valid(name, 8),
+    }
+}
+
+/// Nombre de un atributo
+fn attribute_name(attr: &[u8]) -> String {
+    let name_length = attr[9] as usize;
+    let name_offset = read_u16(attr, 10) as usize;
+    if name_offset + name_length * 2 > attr.len() {
+        return String::new();
+    }
+    decode_utf16_name(&attr[name_offset..name_offset + name_length * 2])
+}
+
+/// Comparar el nombre de un atributo (sin distinguir mayúsculas ASCII)
+fn attribute_name_matches(attr: &[u8], name: &str) -> bool {
+    attribute_name(attr).eq_ignore_ascii_case(name)
+}
+
+/// Convertir un nombre UTF-16LE a String
+fn decode_utf16_name(bytes: &[u8]) -> String {
+    core::char::decode_utf16(bytes.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])))
+        .map(|c| c.unwrap_or(core::char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+fn write_u16(data: &mut [u8], offset: usize, value: u16) {
+    data[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+}
+
+fn write_u32(data: &mut [u8], offset: usize, value: u32) {
+    data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+fn write_u64(data: &mut [u8], offset: usize, value: u64) {
+    data[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+}
+
+/// Redondear hacia arriba a múltiplo de 8
+fn align8(value: usize) -> usize {
+    (value + 7) & !7
+}
+
+/// Instancia global del driver NTFS
+static mut NTFS_DRIVER: Option<NtfsDriver> = None;
+
+/// Inicializar driver NTFS
+pub fn init_ntfs(device_id: u32) -> Result<(), &'static str> {
+    unsafe {
+        NTFS_DRIVER = Some(NtfsDriver::new(device_id));
+        if let Some(ref mut driver) = NTFS_DRIVER {
+            driver.init()?;
+        }
+    }
+    Ok(())
+}
+
+/// Obtener driver NTFS
+pub fn get_ntfs_driver() -> Option<&'static mut NtfsDriver> {
+    unsafe {
+        NTFS_DRIVER.as_mut()
+    }
+}
+
+// ============================================================================
+// FUNCIONES ADICIONALES DE NTFS
+// ============================================================================
+
+/// Montar volumen NTFS
+///
+/// Los volúmenes sucios solo se pueden montar en modo de solo lectura.
+pub fn mount_ntfs_volume(_device_id: u32, _mount_point: &str, read_write: bool) -> Result<(), &'static str> {
+    if let Some(ref mut driver) = get_ntfs_driver() {
+        driver.mount(read_write)
+    } else {
+        Err("Driver NTFS no inicializado")
+    }
+}
 
 /// Desmontar volumen NTFS
 pub fn unmount_ntfs_volume(_mount_point: &str) -> Result<(), &'static str> {
-    if let Some(ref mut _driver) = get_ntfs_driver() {
-        // TODO: Implementar desmontaje de volumen real
-        // Por ahora, simular desmontaje exitoso
-        Ok(())
+    if let Some(ref mut driver) = get_ntfs_driver() {
+        driver.unmount()
     } else {
         Err("Driver NTFS no inicializado")
     }
 }
 
 /// Verificar integridad del sistema de archivos NTFS
-pub fn check_ntfs_integrity() -> Result<bool, &'static str> {
-    if let Some(ref mut _driver) = get_ntfs_driver() {
-        // TODO: Implementar verificación de integridad real
-        // Por ahora, simular verificación exitosa
-        Ok(true)
+pub fn check_ntfs_integrity() -> Result<NtfsIntegrityReport, &'static str> {
+    if let Some(ref mut driver) = get_ntfs_driver() {
+        driver.check_integrity()
     } else {
         Err("Driver NTFS no inicializado")
     }
 }
 
-/// Reparar sistema de archivos NTFS
-pub fn repair_ntfs() -> Result<(), &'static str> {
-    if let Some(ref mut _driver) = get_ntfs_driver() {
-        // TODO: Implementar reparación de NTFS real
-        // Por ahora, simular reparación exitosa
-        Ok(())
+/// Reparar sistema de archivos NTFS (el volumen debe estar desmontado)
+pub fn repair_ntfs() -> Result<NtfsIntegrityReport, &'static str> {
+    if let Some(ref mut driver) = get_ntfs_driver() {
+        driver.repair()
     } else {
         Err("Driver NTFS no inicializado")
     }
@@ -838,7 +4126,7 @@ pub fn get_ntfs_volume_info() -> Result<(u64, u64, u64), &'static str> {
 /// Crear directorio en NTFS
 pub fn create_ntfs_directory(parent_record: u64, dirname: &str) -> Result<u64, &'static str> {
     if let Some(ref mut driver) = get_ntfs_driver() {
-        driver.create_file(parent_record, dirname)
+        driver.create_directory(parent_record, dirname)
     } else {
         Err("Driver NTFS no inicializado")
     }
@@ -854,78 +4142,140 @@ pub fn delete_ntfs_directory(dir_record: u64) -> Result<(), &'static str> {
 }
 
 /// Listar contenido de directorio NTFS
-pub fn list_ntfs_directory(dir_record: u64) -> Result<&'static [&'static str], &'static str> {
-    if let Some(ref mut _driver) = get_ntfs_driver() {
-        // TODO: Implementar listado de directorio real
-        // Por ahora, simular listado
-        Ok(&["file1.txt", "file2.txt", "subdir"])
+pub fn list_ntfs_directory(dir_record: u64) -> Result<Vec<NtfsDirectoryEntry>, &'static str> {
+    if let Some(ref mut driver) = get_ntfs_driver() {
+        driver.read_directory(dir_record)
     } else {
         Err("Driver NTFS no inicializado")
     }
 }
 
 /// Obtener atributos de archivo NTFS
-pub fn get_ntfs_file_attributes(_file_record: u64) -> Result<u32, &'static str> {
-    if let Some(ref mut _driver) = get_ntfs_driver() {
-        // TODO: Implementar obtención de atributos real
-        // Por ahora, simular atributos
-        Ok(0x20) // FILE_ATTRIBUTE_ARCHIVE
+pub fn get_ntfs_file_attributes(file_record: u64) -> Result<u32, &'static str> {
+    if let Some(ref mut driver) = get_ntfs_driver() {
+        driver.get_file_attributes(file_record)
     } else {
         Err("Driver NTFS no inicializado")
     }
 }
 
 /// Establecer atributos de archivo NTFS
-pub fn set_ntfs_file_attributes(_file_record: u64, _attributes: u32) -> Result<(), &'static str> {
-    if let Some(ref mut _driver) = get_ntfs_driver() {
-        // TODO: Implementar establecimiento de atributos real
-        // Por ahora, simular éxito
-        Ok(())
+pub fn set_ntfs_file_attributes(file_record: u64, attributes: u32) -> Result<(), &'static str> {
+    if let Some(ref mut driver) = get_ntfs_driver() {
+        driver.set_file_attributes(file_record, attributes)
     } else {
         Err("Driver NTFS no inicializado")
     }
 }
 
 /// Obtener información de tiempo de archivo NTFS
-pub fn get_ntfs_file_times(_file_record: u64) -> Result<(i64, i64, i64), &'static str> {
-    if let Some(ref mut _driver) = get_ntfs_driver() {
-        // TODO: Implementar obtención de tiempos real
-        // Por ahora, simular tiempos
-        let now = 0; // Timestamp actual simulado
-        Ok((now, now, now)) // Creación, Acceso, Modificación
+pub fn get_ntfs_file_times(file_record: u64) -> Result<(i64, i64, i64), &'static str> {
+    if let Some(ref mut driver) = get_ntfs_driver() {
+        let mut record = vec![0u8; driver.mft_record_size as usize];
+        driver.read_mft_record_data(file_record, &mut record)?;
+        let info = driver.read_standard_information(&record)?;
+        Ok((info.creation_time as i64, info.last_access_time as i64, info.file_change_time as i64)) // Creación, Acceso, Modificación
     } else {
         Err("Driver NTFS no inicializado")
     }
 }
 
 /// Establecer información de tiempo de archivo NTFS
-pub fn set_ntfs_file_times(_file_record: u64, _creation: i64, _access: i64, _modification: i64) -> Result<(), &'static str> {
-    if let Some(ref mut _driver) = get_ntfs_driver() {
-        // TODO: Implementar establecimiento de tiempos real
-        // Por ahora, simular éxito
-        Ok(())
+///
+/// Los tiempos van en formato NTFS (intervalos de 100 ns desde 1601).
+pub fn set_ntfs_file_times(file_record: u64, creation: i64, access: i64, modification: i64) -> Result<(), &'static str> {
+    if let Some(ref mut driver) = get_ntfs_driver() {
+        driver.set_file_times(file_record, Some(creation as u64), Some(access as u64), Some(modification as u64))
     } else {
         Err("Driver NTFS no inicializado")
     }
 }
 
 /// Obtener tamaño de archivo NTFS
-pub fn get_ntfs_file_size(_file_record: u64) -> Result<u64, &'static str> {
-    if let Some(ref mut _driver) = get_ntfs_driver() {
-        // TODO: Implementar obtención de tamaño real
-        // Por ahora, simular tamaño
-        Ok(1024) // Tamaño simulado
+pub fn get_ntfs_file_size(file_record: u64) -> Result<u64, &'static str> {
+    if let Some(ref mut driver) = get_ntfs_driver() {
+        driver.get_file_size(file_record)
     } else {
         Err("Driver NTFS no inicializado")
     }
 }
 
 /// Truncar archivo NTFS
-pub fn truncate_ntfs_file(_file_record: u64, _new_size: u64) -> Result<(), &'static str> {
-    if let Some(ref mut _driver) = get_ntfs_driver() {
-        // TODO: Implementar truncado de archivo real
-        // Por ahora, simular éxito
-        Ok(())
+pub fn truncate_ntfs_file(file_record: u64, new_size: u64) -> Result<(), &'static str> {
+    if let Some(ref mut driver) = get_ntfs_driver() {
+        driver.truncate_file(file_record, new_size)
+    } else {
+        Err("Driver NTFS no inicializado")
+    }
+}
+
+/// Crear archivo en NTFS
+pub fn create_ntfs_file(parent_record: u64, filename: &str) -> Result<u64, &'static str> {
+    if let Some(ref mut driver) = get_ntfs_driver() {
+        driver.create_file(parent_record, filename)
+    } else {
+        Err("Driver NTFS no inicializado")
+    }
+}
+
+/// Escribir en un archivo NTFS
+pub fn write_ntfs_file(file_record: u64, offset: u64, data: &[u8]) -> Result<usize, &'static str> {
+    if let Some(ref mut driver) = get_ntfs_driver() {
+        driver.write_file_data(file_record, offset, data)
+    } else {
+        Err("Driver NTFS no inicializado")
+    }
+}
+
+/// Eliminar archivo en NTFS
+pub fn delete_ntfs_file(file_record: u64) -> Result<(), &'static str> {
+    if let Some(ref mut driver) = get_ntfs_driver() {
+        driver.delete_file(file_record)
+    } else {
+        Err("Driver NTFS no inicializado")
+    }
+}
+
+/// Abrir un flujo con la sintaxis `archivo:flujo`; devuelve (registro, flujo)
+pub fn open_ntfs_stream(path: &str) -> Result<(u64, String), &'static str> {
+    if let Some(ref mut driver) = get_ntfs_driver() {
+        driver.lookup_stream(path)
+    } else {
+        Err("Driver NTFS no inicializado")
+    }
+}
+
+/// Listar los flujos $DATA de un archivo NTFS
+pub fn list_ntfs_streams(file_record: u64) -> Result<Vec<NtfsStreamInfo>, &'static str> {
+    if let Some(ref mut driver) = get_ntfs_driver() {
+        driver.list_streams(file_record)
+    } else {
+        Err("Driver NTFS no inicializado")
+    }
+}
+
+/// Leer un flujo de un archivo NTFS (`""` es el flujo principal)
+pub fn read_ntfs_stream(file_record: u64, stream: &str, offset: u64, buffer: &mut [u8]) -> Result<usize, &'static str> {
+    if let Some(ref mut driver) = get_ntfs_driver() {
+        driver.read_stream(file_record, stream, offset, buffer)
+    } else {
+        Err("Driver NTFS no inicializado")
+    }
+}
+
+/// Escribir en un flujo de un archivo NTFS, creándolo si no existe
+pub fn write_ntfs_stream(file_record: u64, stream: &str, offset: u64, data: &[u8]) -> Result<usize, &'static str> {
+    if let Some(ref mut driver) = get_ntfs_driver() {
+        driver.write_stream_data(file_record, stream, offset, data)
+    } else {
+        Err("Driver NTFS no inicializado")
+    }
+}
+
+/// Eliminar un flujo con nombre de un archivo NTFS
+pub fn delete_ntfs_stream(file_record: u64, stream: &str) -> Result<(), &'static str> {
+    if let Some(ref mut driver) = get_ntfs_driver() {
+        driver.delete_stream(file_record, stream)
     } else {
         Err("Driver NTFS no inicializado")
     }
@@ -953,11 +4303,883 @@ pub fn clear_ntfs_cache() -> Result<(), &'static str> {
 
 /// Sincronizar cambios NTFS
 pub fn sync_ntfs_changes() -> Result<(), &'static str> {
-    if let Some(ref mut _driver) = get_ntfs_driver() {
-        // TODO: Implementar sincronización real
-        // Por ahora, simular éxito
-        Ok(())
+    if let Some(ref mut driver) = get_ntfs_driver() {
+        driver.flush()
     } else {
         Err("Driver NTFS no inicializado")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::test_util::TestDisk;
+
+    /// Registro MFT sintético (registro 72, 1 KB, tiempos a cero) cuyo $DATA
+    /// residente de 600 bytes ("MZ" y el patrón `(i * 37 + 11) % 256`) cruza
+    /// el final del primer sector
+    const SYNTHETIC_RECORD: &[u8] = include_bytes!("../test_data/ntfs/mft_record_synthetic.bin");
+    /// El mismo registro con el segundo sector a medio escribir
+    const TORN_RECORD: &[u8] = include_bytes!("../test_data/ntfs/mft_record_synthetic_torn.bin");
+    /// Bloque INDX sintético de 4 KB de un índice $I30 con las entradas
+    /// `file000.dll` a `file021.dll`
+    const SYNTHETIC_INDEX_BLOCK: &[u8] = include_bytes!("../test_data/ntfs/indx_block_synthetic.bin");
+
+    const SECTOR_SIZE: usize = 512;
+    const CLUSTER_SIZE: usize = 4096;
+    const RECORD_SIZE: usize = 1024;
+    /// Volumen de 4 MiB: 1024 clústeres de 4 KiB
+    const VOLUME_CLUSTERS: usize = 1024;
+    const MIRROR_CLUSTER: u64 = 1;
+    /// $MFT con 256 registros en los clústeres 2 a 65
+    const MFT_CLUSTER: u64 = 2;
+    const MFT_RECORDS: usize = 256;
+    const LOGFILE_CLUSTER: u64 = 66;
+    const BITMAP_CLUSTER: u64 = 68;
+    const UPCASE_CLUSTER: u64 = 69;
+    /// Primer clúster libre tras los metadatos
+    const FIRST_FREE_CLUSTER: u64 = 70;
+    const TEST_TIME: u64 = 1_700_000_000;
+
+    fn cluster_offset(cluster: u64) -> usize {
+        cluster as usize * CLUSTER_SIZE
+    }
+
+    /// Driver sin dispositivo para construir atributos con la geometría de prueba
+    fn layout_driver() -> NtfsDriver {
+        let mut driver = NtfsDriver::new(0);
+        driver.cluster_size = CLUSTER_SIZE as u32;
+        driver
+    }
+
+    /// Registro MFT vacío en uso, con el número de secuencia indicado
+    fn empty_record(record_number: u64, sequence: u16, flags: u16) -> Vec<u8> {
+        let mut record = vec![0u8; RECORD_SIZE];
+        record[0..4].copy_from_slice(b"FILE");
+        write_u16(&mut record, 4, 48);
+        write_u16(&mut record, 6, (RECORD_SIZE / NTFS_FIXUP_STRIDE + 1) as u16);
+        write_u16(&mut record, 16, sequence);
+        write_u16(&mut record, 18, 1);
+        write_u16(&mut record, 20, 56);
+        write_u16(&mut record, 22, MFT_RECORD_IN_USE | flags);
+        write_u32(&mut record, 24, 64);
+        write_u32(&mut record, 28, RECORD_SIZE as u32);
+        write_u32(&mut record, 44, record_number as u32);
+        write_u32(&mut record, 56, NtfsAttributeType::EndOfAttributes as u32);
+        record
+    }
+
+    fn add_attribute(record: &mut Vec<u8>, build: impl FnOnce(u16) -> Vec<u8>) {
+        let id = next_attribute_id(record);
+        insert_attribute(record, &build(id)).unwrap();
+    }
+
+    fn add_resident(record: &mut Vec<u8>, attribute_type: NtfsAttributeType, name: &str, content: &[u8]) {
+        let indexed = attribute_type == NtfsAttributeType::FileName;
+        add_attribute(record, |id| build_resident_attribute(attribute_type as u32, name, id, content, indexed));
+    }
+
+    /// $DATA sin nombre no residente en un único run
+    fn add_data_run(record: &mut Vec<u8>, lcn: u64, clusters: u64, size: u64) {
+        let runs = [NtfsDataRun { vcn: 0, length: clusters, lcn: Some(lcn) }];
+        add_attribute(record, |id| layout_driver().build_non_resident_attribute(NtfsAttributeType::Data as u32, "", id, 0, &runs, size, size));
+    }
+
+    /// Escribir un registro en $MFT (y en $MFTMirr si es uno de los cuatro primeros)
+    fn put_record(image: &mut [u8], record_number: u64, record: &[u8]) {
+        let mut disk = record.to_vec();
+        prepare_fixups(&mut disk).unwrap();
+        let offset = cluster_offset(MFT_CLUSTER) + record_number as usize * RECORD_SIZE;
+        image[offset..offset + RECORD_SIZE].copy_from_slice(&disk);
+        if record_number < 4 {
+            let mirror = cluster_offset(MIRROR_CLUSTER) + record_number as usize * RECORD_SIZE;
+            image[mirror..mirror + RECORD_SIZE].copy_from_slice(&disk);
+        }
+    }
+
+    /// Imagen NTFS 3.1 sintética con los metadatos mínimos que usa el driver
+    ///
+    /// Contiene $MFT, $MFTMirr, un $LogFile vaciado, $Volume, el directorio
+    /// raíz con un índice $I30 vacío, $Bitmap, $Boot y una tabla $UpCase
+    /// reducida (los caracteres fuera de ella se asignan a sí mismos).
+    fn ntfs_image() -> Vec<u8> {
+        let mut image = vec![0u8; VOLUME_CLUSTERS * CLUSTER_SIZE];
+
+        let boot = &mut image[..SECTOR_SIZE];
+        boot[0..3].copy_from_slice(&[0xEB, 0x52, 0x90]);
+        boot[3..11].copy_from_slice(b"NTFS    ");
+        write_u16(boot, 11, SECTOR_SIZE as u16);
+        boot[13] = (CLUSTER_SIZE / SECTOR_SIZE) as u8;
+        boot[21] = 0xF8;
+        write_u64(boot, 40, (VOLUME_CLUSTERS * CLUSTER_SIZE / SECTOR_SIZE) as u64);
+        write_u64(boot, 48, MFT_CLUSTER);
+        write_u64(boot, 56, MIRROR_CLUSTER);
+        boot[64] = 0xF6; // 2^10 bytes por registro
+        boot[68] = 1;
+        write_u64(boot, 72, 0x1234_5678_9ABC_DEF0);
+        boot[510] = 0x55;
+        boot[511] = 0xAA;
+
+        let mft_clusters = (MFT_RECORDS * RECORD_SIZE / CLUSTER_SIZE) as u64;
+        let mut mft = empty_record(0, 1, 0);
+        add_data_run(&mut mft, MFT_CLUSTER, mft_clusters, (MFT_RECORDS * RECORD_SIZE) as u64);
+        // Registros de sistema y reservados (0 a 23) en uso
+        let mut mft_bitmap = [0u8; MFT_RECORDS / 8];
+        mft_bitmap[..3].fill(0xFF);
+        add_resident(&mut mft, NtfsAttributeType::Bitmap, "", &mft_bitmap);
+        put_record(&mut image, 0, &mft);
+
+        let mut mirror = empty_record(NTFS_MFT_MIRROR_RECORD, 1, 0);
+        add_data_run(&mut mirror, MIRROR_CLUSTER, 1, (4 * RECORD_SIZE) as u64);
+        put_record(&mut image, NTFS_MFT_MIRROR_RECORD, &mirror);
+
+        let mut log_file = empty_record(NTFS_LOGFILE_RECORD, 2, 0);
+        add_data_run(&mut log_file, LOGFILE_CLUSTER, 2, 2 * CLUSTER_SIZE as u64);
+        put_record(&mut image, NTFS_LOGFILE_RECORD, &log_file);
+        image[cluster_offset(LOGFILE_CLUSTER)..cluster_offset(LOGFILE_CLUSTER + 2)].fill(0xFF);
+
+        let mut volume = empty_record(NTFS_VOLUME_RECORD, 3, 0);
+        add_resident(&mut volume, NtfsAttributeType::VolumeInformation, "", &[0, 0, 0, 0, 0, 0, 0, 0, 3, 1, 0, 0]);
+        put_record(&mut image, NTFS_VOLUME_RECORD, &volume);
+
+        let time = unix_to_ntfs_time(TEST_TIME);
+        let root_reference = NTFS_ROOT_DIRECTORY_RECORD | 5 << 48;
+        let mut root = empty_record(NTFS_ROOT_DIRECTORY_RECORD, 5, MFT_RECORD_IS_DIRECTORY);
+        add_resident(&mut root, NtfsAttributeType::StandardInformation, "", &standard_information_content(time, NTFS_FILE_ATTRIBUTE_HIDDEN | NTFS_FILE_ATTRIBUTE_SYSTEM, 0x100));
+        let dot: Vec<u16> = ".".encode_utf16().collect();
+        add_resident(&mut root, NtfsAttributeType::FileName, "", &file_name_content(root_reference, &dot, NTFS_NAMESPACE_WIN32_AND_DOS, NTFS_FILE_NAME_INDEX_PRESENT, time));
+        add_resident(&mut root, NtfsAttributeType::IndexRoot, NTFS_DIRECTORY_INDEX_NAME, &empty_index_root(CLUSTER_SIZE as u32, CLUSTER_SIZE as u32));
+        put_record(&mut image, NTFS_ROOT_DIRECTORY_RECORD, &root);
+
+        let mut bitmap_file = empty_record(NTFS_BITMAP_RECORD, 6, 0);
+        add_data_run(&mut bitmap_file, BITMAP_CLUSTER, 1, (VOLUME_CLUSTERS / 8) as u64);
+        put_record(&mut image, NTFS_BITMAP_RECORD, &bitmap_file);
+        let bitmap = cluster_offset(BITMAP_CLUSTER);
+        for cluster in 0..FIRST_FREE_CLUSTER {
+            image[bitmap + (cluster / 8) as usize] |= 1 << (cluster % 8);
+        }
+
+        let mut boot_file = empty_record(7, 7, 0);
+        add_data_run(&mut boot_file, 0, 1, CLUSTER_SIZE as u64);
+        put_record(&mut image, 7, &boot_file);
+
+        let mut upcase_file = empty_record(NTFS_UPCASE_RECORD, 10, 0);
+        add_data_run(&mut upcase_file, UPCASE_CLUSTER, 1, CLUSTER_SIZE as u64);
+        put_record(&mut image, NTFS_UPCASE_RECORD, &upcase_file);
+        let upcase = cluster_offset(UPCASE_CLUSTER);
+        for c in 0..CLUSTER_SIZE / 2 {
+            let upper = match c {
+                0x61..=0x7A | 0xE0..=0xF6 | 0xF8..=0xFE => c - 0x20,
+                0xFF => 0x178,
+                _ => c,
+            };
+            write_u16(&mut image[upcase..], c * 2, upper as u16);
+        }
+
+        image
+    }
+
+    /// Driver inicializado sobre un disco de prueba, montado en lectura/escritura
+    fn mount_image(disk: &TestDisk) -> NtfsDriver {
+        let mut driver = NtfsDriver::new(disk.device_id);
+        driver.init().unwrap();
+        driver.mount(true).unwrap();
+        driver
+    }
+
+    fn pattern(seed: usize, len: usize) -> Vec<u8> {
+        (0..len).map(|i| ((i * 31 + seed * 7) % 251) as u8).collect()
+    }
+
+    fn read_all(driver: &NtfsDriver, record_number: u64) -> Vec<u8> {
+        let size = driver.get_file_size(record_number).unwrap() as usize;
+        let mut record = vec![0u8; RECORD_SIZE];
+        driver.read_mft_record_data(record_number, &mut record).unwrap();
+        let mut data = vec![0u8; size];
+        assert_eq!(driver.read_file_data(&record, 0, size, &mut data), Ok(size));
+        data
+    }
+
+    #[test]
+    fn test_fixups_restore_sector_tails() {
+        let mut record = SYNTHETIC_RECORD.to_vec();
+        let usn = [record[48], record[49]];
+        assert_eq!(&record[510..512], &usn);
+
+        apply_fixups(&mut record, b"FILE").unwrap();
+        validate_mft_record(&record, 72).unwrap();
+        assert_ne!(&record[510..512], &usn);
+
+        // Los datos residentes cruzan el final del primer sector
+        let driver = NtfsDriver::new(0);
+        let attr = driver.find_attribute(&record, NtfsAttributeType::Data, "", None).unwrap();
+        match driver.attribute_value(attr).unwrap() {
+            NtfsAttributeValue::Resident(content) => {
+                assert_eq!(content.len(), 600);
+                assert_eq!(&content[..2], b"MZ");
+                for (i, &byte) in content[2..].iter().enumerate() {
+                    assert_eq!(byte, ((i * 37 + 11) % 256) as u8);
+                }
+            }
+            _ => panic!("synthetic record data should be resident"),
+        }
+    }
+
+    #[test]
+    fn test_torn_record_is_reported() {
+        let mut record = TORN_RECORD.to_vec();
+        assert_eq!(apply_fixups(&mut record, b"FILE"), Err(NTFS_ERROR_TORN_RECORD));
+
+        let mut baad = SYNTHETIC_RECORD.to_vec();
+        baad[0..4].copy_from_slice(b"BAAD");
+        assert_eq!(apply_fixups(&mut baad, b"FILE"), Err(NTFS_ERROR_TORN_RECORD));
+    }
+
+    #[test]
+    fn test_magic_and_size_checks() {
+        let mut record = SYNTHETIC_RECORD.to_vec();
+        assert!(apply_fixups(&mut record, b"INDX").is_err());
+
+        apply_fixups(&mut record, b"FILE").unwrap();
+        assert!(validate_mft_record(&record, 73).is_err());
+
+        let mut oversized = record.clone();
+        oversized[24..28].copy_from_slice(&2048u32.to_le_bytes());
+        assert!(validate_mft_record(&oversized, 72).is_err());
+
+        let mut wrong_allocation = record.clone();
+        wrong_allocation[28..32].copy_from_slice(&4096u32.to_le_bytes());
+        assert!(validate_mft_record(&wrong_allocation, 72).is_err());
+    }
+
+    #[test]
+    fn test_prepare_fixups_round_trip() {
+        let mut record = SYNTHETIC_RECORD.to_vec();
+        apply_fixups(&mut record, b"FILE").unwrap();
+        let original = record.clone();
+
+        let mut disk = record.clone();
+        prepare_fixups(&mut disk).unwrap();
+        let usn = read_u16(&disk, 48);
+        assert_eq!(usn, read_u16(&original, 48) + 1);
+        assert_eq!(read_u16(&disk, 510), usn);
+        assert_eq!(read_u16(&disk, 1022), usn);
+
+        apply_fixups(&mut disk, b"FILE").unwrap();
+        assert_eq!(&disk[..48], &original[..48]);
+        assert_eq!(&disk[50..], &original[50..]);
+    }
+
+    #[test]
+    fn test_index_block_fixups() {
+        let mut block = SYNTHETIC_INDEX_BLOCK.to_vec();
+        assert!(apply_fixups(&mut block, b"FILE").is_err());
+        apply_fixups(&mut block, b"INDX").unwrap();
+
+        let entries = NtfsDriver::index_entries(&block[24..]).unwrap();
+        assert!(entries.last().unwrap().is_last());
+        let first = parse_file_name(&entries[0].key).unwrap();
+        let name = first.name;
+        assert_eq!(String::from_utf16_lossy(&name[..first.name_length as usize]), "file000.dll");
+    }
+
+    #[test]
+    fn test_data_runs_round_trip() {
+        let runs = vec![
+            NtfsDataRun { vcn: 0, length: 4, lcn: Some(0x1234) },
+            NtfsDataRun { vcn: 4, length: 300, lcn: None },
+            NtfsDataRun { vcn: 304, length: 16, lcn: Some(0x20) },
+            NtfsDataRun { vcn: 320, length: 1, lcn: Some(0x80_0000) },
+        ];
+        let runlist = encode_data_runs(&runs);
+        assert_eq!(*runlist.last().unwrap(), 0);
+        assert_eq!(decode_data_runs(&runlist, 0).unwrap(), runs);
+
+        let mut truncated = runs.clone();
+        let released = truncate_data_runs(&mut truncated, 310);
+        assert_eq!(released, vec![(0x26, 10), (0x80_0000, 1)]);
+        assert_eq!(truncated.last().unwrap().length, 6);
+    }
+
+    #[test]
+    fn test_index_node_rebuild() {
+        let mut block = SYNTHETIC_INDEX_BLOCK.to_vec();
+        apply_fixups(&mut block, b"INDX").unwrap();
+        let entries: Vec<NtfsIndexEntry> = NtfsDriver::index_entries(&block[24..])
+            .unwrap()
+            .into_iter()
+            .filter(|entry| !entry.is_last())
+            .collect();
+
+        let node = serialize_index_node(&entries, None, 16, None);
+        assert_eq!(node.len(), index_node_size(&entries, None) + 16);
+        let parsed = NtfsDriver::index_entries(&node).unwrap();
+        assert_eq!(parsed.len(), entries.len() + 1);
+        for (original, copy) in entries.iter().zip(&parsed) {
+            assert_eq!(original.key, copy.key);
+            assert_eq!(original.mft_reference, copy.mft_reference);
+        }
+
+        // Partir en nodos pequeños: cada separador sube un nivel
+        let (nodes, separators) = pack_index_level(entries.clone(), None, 512);
+        assert!(nodes.len() > 1);
+        assert_eq!(separators.len(), nodes.len() - 1);
+        let total: usize = nodes.iter().map(|(node, _)| node.len()).sum();
+        assert_eq!(total + separators.len(), entries.len());
+    }
+
+    #[test]
+    fn test_lznt1_decompression() {
+        // "abc" literal + referencia (desplazamiento 3, longitud 9), y un bloque sin comprimir
+        let mut input = vec![0x05, 0xB0, 0x08, b'a', b'b', b'c', 0x06, 0x20];
+        input.extend_from_slice(&[0x02, 0x30, b'x', b'y', b'z']);
+        input.extend_from_slice(&[0x00, 0x00]);
+
+        let mut output = vec![0xFFu8; 2 * LZNT1_CHUNK_SIZE];
+        assert_eq!(decompress_lznt1(&input, &mut output), Ok(2 * LZNT1_CHUNK_SIZE));
+        assert_eq!(&output[..12], b"abcabcabcabc");
+        assert!(output[12..LZNT1_CHUNK_SIZE].iter().all(|&byte| byte == 0));
+        assert_eq!(&output[LZNT1_CHUNK_SIZE..LZNT1_CHUNK_SIZE + 3], b"xyz");
+
+        // Referencia anterior al inicio del bloque
+        let bad = [0x03, 0xB0, 0x01, 0x00, 0x10];
+        assert!(decompress_lznt1(&bad, &mut output).is_err());
+        assert!(decompress_lznt1(&[0x10, 0xB0, 0x00], &mut output).is_err());
+    }
+
+    #[test]
+    fn test_stream_name_syntax() {
+        assert_eq!(split_stream_name("report.docx"), Ok(("report.docx", "")));
+        assert_eq!(split_stream_name("report.docx:Zone.Identifier"), Ok(("report.docx", "Zone.Identifier")));
+        assert_eq!(split_stream_name("report.docx:Zone.Identifier:$DATA"), Ok(("report.docx", "Zone.Identifier")));
+        assert_eq!(split_stream_name("report.docx::$DATA"), Ok(("report.docx", "")));
+        assert!(split_stream_name("report.docx:s:$INDEX_ALLOCATION").is_err());
+        assert_eq!(strip_drive_letter("C:\\Windows"), "\\Windows");
+    }
+
+    /// Entrada de $ATTRIBUTE_LIST sin nombre
+    fn attribute_list_entry(attribute_type: NtfsAttributeType, starting_vcn: u64, reference: u64, attribute_id: u16) -> Vec<u8> {
+        let mut entry = vec![0u8; 32];
+        write_u32(&mut entry, 0, attribute_type as u32);
+        write_u16(&mut entry, 4, 32);
+        entry[7] = 26;
+        write_u64(&mut entry, 8, starting_vcn);
+        write_u64(&mut entry, 16, reference);
+        write_u16(&mut entry, 24, attribute_id);
+        entry
+    }
+
+    #[test]
+    fn test_read_follows_attribute_list_segments() {
+        let mut image = ntfs_image();
+        let base_reference = 16 | 1 << 48;
+        let content = pattern(5, 4 * CLUSTER_SIZE);
+
+        // $DATA repartido en dos registros de extensión, con los VCN 0-1 en
+        // los clústeres 100-101 y los VCN 2-3 en los clústeres 120-121
+        let mut first = empty_record(17, 1, 0);
+        write_u64(&mut first, 32, base_reference);
+        let runs = [NtfsDataRun { vcn: 0, length: 2, lcn: Some(100) }];
+        add_attribute(&mut first, |id| {
+            let mut attr = layout_driver().build_non_resident_attribute(NtfsAttributeType::Data as u32, "", id, 0, &runs, content.len() as u64, content.len() as u64);
+            write_u64(&mut attr, 40, content.len() as u64);
+            attr
+        });
+        put_record(&mut image, 17, &first);
+
+        let mut second = empty_record(18, 1, 0);
+        write_u64(&mut second, 32, base_reference);
+        let runs = [NtfsDataRun { vcn: 2, length: 2, lcn: Some(120) }];
+        add_attribute(&mut second, |id| {
+            let mut attr = layout_driver().build_non_resident_attribute(NtfsAttributeType::Data as u32, "", id, 0, &runs, 0, 0);
+            write_u64(&mut attr, 16, 2);
+            attr
+        });
+        put_record(&mut image, 18, &second);
+
+        let time = unix_to_ntfs_time(TEST_TIME);
+        let name: Vec<u16> = "split.bin".encode_utf16().collect();
+        let mut base = empty_record(16, 1, 0);
+        add_resident(&mut base, NtfsAttributeType::StandardInformation, "", &standard_information_content(time, NTFS_FILE_ATTRIBUTE_ARCHIVE, 0));
+        add_resident(&mut base, NtfsAttributeType::FileName, "", &file_name_content(NTFS_ROOT_DIRECTORY_RECORD | 5 << 48, &name, NTFS_NAMESPACE_WIN32, NTFS_FILE_ATTRIBUTE_ARCHIVE, time));
+        let mut list = Vec::new();
+        list.extend(attribute_list_entry(NtfsAttributeType::StandardInformation, 0, base_reference, 0));
+        list.extend(attribute_list_entry(NtfsAttributeType::FileName, 0, base_reference, 1));
+        list.extend(attribute_list_entry(NtfsAttributeType::Data, 0, 17 | 1 << 48, 0));
+        list.extend(attribute_list_entry(NtfsAttributeType::Data, 2, 18 | 1 << 48, 0));
+        add_resident(&mut base, NtfsAttributeType::AttributeList, "", &list);
+        put_record(&mut image, 16, &base);
+
+        for (i, lcn) in [100u64, 101, 120, 121].into_iter().enumerate() {
+            image[cluster_offset(lcn)..cluster_offset(lcn + 1)].copy_from_slice(&content[i * CLUSTER_SIZE..(i + 1) * CLUSTER_SIZE]);
+        }
+
+        let disk = TestDisk::with_data(image, SECTOR_SIZE as u32);
+        let mut driver = NtfsDriver::new(disk.device_id);
+        driver.init().unwrap();
+
+        let mut record = vec![0u8; RECORD_SIZE];
+        driver.read_mft_record_data(16, &mut record).unwrap();
+        match driver.load_attribute(&record, NtfsAttributeType::Data, "").unwrap() {
+            NtfsAttributeValue::NonResident { header, runs } => {
+                assert_eq!({ header.actual_size }, content.len() as u64);
+                assert_eq!(runs, vec![
+                    NtfsDataRun { vcn: 0, length: 2, lcn: Some(100) },
+                    NtfsDataRun { vcn: 2, length: 2, lcn: Some(120) },
+                ]);
+            }
+            _ => panic!("split.bin data should be non-resident"),
+        }
+
+        let mut data = vec![0u8; content.len()];
+        assert_eq!(driver.read_file_data(&record, 0, data.len(), &mut data), Ok(content.len()));
+        assert_eq!(data, content);
+
+        // Lectura que cruza de un segmento al otro
+        let mut middle = vec![0u8; 5000];
+        assert_eq!(driver.read_file_data(&record, 6000, middle.len(), &mut middle), Ok(5000));
+        assert_eq!(middle, &content[6000..11000]);
+        assert_eq!(driver.read_file_data(&record, content.len() as u64, 16, &mut middle), Ok(0));
+    }
+
+    #[test]
+    fn test_fragmented_file_reads_back() {
+        let disk = TestDisk::with_data(ntfs_image(), SECTOR_SIZE as u32);
+        let mut driver = mount_image(&disk);
+        let first = driver.create_file(NTFS_ROOT_DIRECTORY_RECORD, "first.bin").unwrap();
+        let second = driver.create_file(NTFS_ROOT_DIRECTORY_RECORD, "second.bin").unwrap();
+
+        // El segundo archivo ocupa el clúster siguiente al primero, así que
+        // al ampliar el primero su $DATA queda en dos runs
+        let head = pattern(1, 3 * CLUSTER_SIZE);
+        driver.write_file_data(first, 0, &head).unwrap();
+        driver.write_file_data(second, 0, &pattern(2, CLUSTER_SIZE)).unwrap();
+        let tail = pattern(3, 2 * CLUSTER_SIZE + 100);
+        driver.write_file_data(first, head.len() as u64, &tail).unwrap();
+
+        let mut record = vec![0u8; RECORD_SIZE];
+        driver.read_mft_record_data(first, &mut record).unwrap();
+        match driver.load_attribute(&record, NtfsAttributeType::Data, "").unwrap() {
+            NtfsAttributeValue::NonResident { runs, .. } => {
+                assert_eq!(runs.len(), 2);
+                assert_eq!(runs[1].vcn, 3);
+                assert!(runs.iter().all(|run| run.lcn.map_or(false, |lcn| lcn >= FIRST_FREE_CLUSTER)));
+            }
+            _ => panic!("first.bin data should be non-resident"),
+        }
+
+        let mut expected = head.clone();
+        expected.extend_from_slice(&tail);
+        assert_eq!(read_all(&driver, first), expected);
+        assert_eq!(read_all(&driver, second), pattern(2, CLUSTER_SIZE));
+    }
+
+    #[test]
+    fn test_index_lookup_through_index_blocks() {
+        let disk = TestDisk::with_data(ntfs_image(), SECTOR_SIZE as u32);
+        let mut driver = mount_image(&disk);
+
+        // Suficientes entradas para que $I30 pase de la raíz a bloques INDX
+        let mut records = Vec::new();
+        for i in (0..120).rev() {
+            let name = format!("Document {:03}.txt", i);
+            records.push((name.clone(), driver.create_file(NTFS_ROOT_DIRECTORY_RECORD, &name).unwrap()));
+        }
+        let mut record = vec![0u8; RECORD_SIZE];
+        driver.read_mft_record_data(NTFS_ROOT_DIRECTORY_RECORD, &mut record).unwrap();
+        assert!(driver.find_attribute(&record, NtfsAttributeType::IndexAllocation, NTFS_DIRECTORY_INDEX_NAME, None).is_some());
+
+        // La colación de $UpCase no distingue mayúsculas
+        for (name, record_number) in &records {
+            assert_eq!(driver.find_file(NTFS_ROOT_DIRECTORY_RECORD, name), Ok(*record_number));
+            assert_eq!(driver.find_file(NTFS_ROOT_DIRECTORY_RECORD, &name.to_uppercase()), Ok(*record_number));
+        }
+        assert!(driver.find_file(NTFS_ROOT_DIRECTORY_RECORD, "Document 120.txt").is_err());
+        assert!(driver.find_file(NTFS_ROOT_DIRECTORY_RECORD, "Document 05.txt").is_err());
+
+        // La enumeración recorre el árbol en orden
+        let listed: Vec<String> = driver.read_directory(NTFS_ROOT_DIRECTORY_RECORD).unwrap().into_iter().map(|entry| entry.name).collect();
+        let expected: Vec<String> = (0..120).map(|i| format!("Document {:03}.txt", i)).collect();
+        assert_eq!(listed, expected);
+    }
+
+    #[test]
+    fn test_upcase_collation_and_path_lookup() {
+        let disk = TestDisk::with_data(ntfs_image(), SECTOR_SIZE as u32);
+        let mut driver = mount_image(&disk);
+        let windows = driver.create_directory(NTFS_ROOT_DIRECTORY_RECORD, "Windows").unwrap();
+        let system32 = driver.create_directory(windows, "System32").unwrap();
+        let ntdll = driver.create_file(system32, "ntdll.dll").unwrap();
+        let cafe = driver.create_file(NTFS_ROOT_DIRECTORY_RECORD, "café.txt").unwrap();
+        let zeta = driver.create_file(NTFS_ROOT_DIRECTORY_RECORD, "_zeta").unwrap();
+
+        assert_eq!(driver.lookup_path("C:\\WINDOWS\\system32\\NTDLL.DLL"), Ok(ntdll));
+        assert_eq!(driver.lookup_path("/Windows/System32"), Ok(system32));
+        assert_eq!(driver.lookup_path("\\"), Ok(NTFS_ROOT_DIRECTORY_RECORD));
+        assert!(driver.lookup_path("\\Windows\\ntdll.dll").is_err());
+        assert!(driver.lookup_path("\\Windows\\System32\\ntdll.dll\\x").is_err());
+
+        // Las letras acentuadas se comparan con su mayúscula de $UpCase
+        assert_eq!(driver.find_file(NTFS_ROOT_DIRECTORY_RECORD, "CAFÉ.TXT"), Ok(cafe));
+
+        // '_' (0x5F) va detrás de las mayúsculas pero delante de las minúsculas
+        let names: Vec<String> = driver.read_directory(NTFS_ROOT_DIRECTORY_RECORD).unwrap().into_iter().map(|entry| entry.name).collect();
+        assert_eq!(names, ["café.txt", "Windows", "_zeta"]);
+        assert_eq!(driver.find_file(NTFS_ROOT_DIRECTORY_RECORD, "_ZETA"), Ok(zeta));
+    }
+
+    #[test]
+    fn test_new_records_are_stamped_with_the_clock() {
+        let disk = TestDisk::with_data(ntfs_image(), SECTOR_SIZE as u32);
+        let mut driver = mount_image(&disk);
+        let before = crate::hal::rtc::unix_time();
+        let file = driver.create_file(NTFS_ROOT_DIRECTORY_RECORD, "stamped.txt").unwrap();
+
+        let mut record = vec![0u8; RECORD_SIZE];
+        driver.read_mft_record_data(file, &mut record).unwrap();
+        let info = driver.read_standard_information(&record).unwrap();
+        let created = ntfs_time_to_unix(info.creation_time);
+        assert!(created >= before && created <= crate::hal::rtc::unix_time());
+
+        // La clave del índice del padre lleva los mismos tiempos
+        let entry = driver.read_directory(NTFS_ROOT_DIRECTORY_RECORD).unwrap().remove(0);
+        assert_eq!({ entry.file_name.creation_time }, { info.creation_time });
+    }
+
+    /// Página de reinicio de $LogFile con un cliente activo (transacciones pendientes)
+    fn active_restart_page(lsn: u64) -> Vec<u8> {
+        let mut page = vec![0u8; 4096];
+        page[0..4].copy_from_slice(b"RSTR");
+        write_u16(&mut page, 4, 30);
+        write_u16(&mut page, 6, (4096 / NTFS_FIXUP_STRIDE + 1) as u16);
+        write_u32(&mut page, 16, 4096);
+        write_u32(&mut page, 20, 4096);
+        write_u16(&mut page, 24, 64);
+        write_u16(&mut page, 26, 1);
+        write_u16(&mut page, 28, 1);
+        // Área de reinicio: el cliente 0 está en uso y falta VOLUME_IS_CLEAN
+        write_u64(&mut page, 64, lsn);
+        prepare_fixups(&mut page).unwrap();
+        page
+    }
+
+    #[test]
+    fn test_dirty_volume_mounts_read_only() {
+        let disk = TestDisk::with_data(ntfs_image(), SECTOR_SIZE as u32);
+        let mut driver = NtfsDriver::new(disk.device_id);
+        driver.init().unwrap();
+        assert!(driver.check_integrity().unwrap().is_clean());
+        assert_eq!(driver.inspect_log_file().map(|status| status.clean), Ok(true));
+
+        let mut image = ntfs_image();
+        let log = cluster_offset(LOGFILE_CLUSTER);
+        image[log..log + 4096].copy_from_slice(&active_restart_page(0x1000));
+        drop(disk);
+        let disk = TestDisk::with_data(image, SECTOR_SIZE as u32);
+        let mut driver = NtfsDriver::new(disk.device_id);
+        driver.init().unwrap();
+        let status = driver.inspect_log_file().unwrap();
+        assert!(!status.clean);
+        assert_eq!(status.current_lsn, 0x1000);
+        assert!(driver.mount(true).is_err());
+        driver.mount(false).unwrap();
+        assert!(driver.create_file(NTFS_ROOT_DIRECTORY_RECORD, "denied.txt").is_err());
+        driver.unmount().unwrap();
+
+        // El flag de $VOLUME_INFORMATION también impide montar en escritura
+        let mut image = ntfs_image();
+        let mut volume = empty_record(NTFS_VOLUME_RECORD, 3, 0);
+        add_resident(&mut volume, NtfsAttributeType::VolumeInformation, "", &[0, 0, 0, 0, 0, 0, 0, 0, 3, 1, 1, 0]);
+        put_record(&mut image, NTFS_VOLUME_RECORD, &volume);
+        drop(disk);
+        let disk = TestDisk::with_data(image, SECTOR_SIZE as u32);
+        let mut driver = NtfsDriver::new(disk.device_id);
+        driver.init().unwrap();
+        assert_eq!(driver.is_volume_dirty(), Ok(true));
+        assert!(driver.mount(true).is_err());
+        assert!(driver.check_integrity().unwrap().volume_dirty);
+    }
+
+    #[test]
+    fn test_integrity_check_and_repair() {
+        let mut image = ntfs_image();
+        let bitmap = cluster_offset(BITMAP_CLUSTER);
+        // Clúster 500 marcado sin dueño y clúster 3 de $MFT sin marcar
+        image[bitmap + 500 / 8] |= 1 << (500 % 8);
+        image[bitmap] &= !(1 << 3);
+        // Copia de $Volume en $MFTMirr desactualizada
+        image[cluster_offset(MIRROR_CLUSTER) + 3 * RECORD_SIZE + 200] ^= 0xFF;
+
+        // Dos archivos que comparten el clúster 150
+        for record_number in [30, 31] {
+            let mut record = empty_record(record_number, 1, 0);
+            add_data_run(&mut record, 150, 1, 100);
+            put_record(&mut image, record_number, &record);
+        }
+        image[bitmap + 150 / 8] |= 1 << (150 % 8);
+
+        // Archivo cuyo directorio padre (registro 40) no está en uso
+        let name: Vec<u16> = "lost.txt".encode_utf16().collect();
+        let mut orphan = empty_record(32, 1, 0);
+        add_resident(&mut orphan, NtfsAttributeType::FileName, "", &file_name_content(40 | 1 << 48, &name, NTFS_NAMESPACE_WIN32, 0, 0));
+        put_record(&mut image, 32, &orphan);
+
+        // Registro con el final del segundo sector sin el número de secuencia
+        put_record(&mut image, 33, &empty_record(33, 1, 0));
+        image[cluster_offset(MFT_CLUSTER) + 33 * RECORD_SIZE + 1022] ^= 0xFF;
+
+        let disk = TestDisk::with_data(image, SECTOR_SIZE as u32);
+        let mut driver = NtfsDriver::new(disk.device_id);
+        driver.init().unwrap();
+        let report = driver.check_integrity().unwrap();
+        assert!(report.log_file_clean && !report.volume_dirty);
+        assert_eq!(report.mirror_mismatches, vec![3]);
+        assert_eq!(report.corrupt_records, vec![33]);
+        assert_eq!(report.orphaned_records, vec![32]);
+        assert_eq!(report.double_allocated_clusters, vec![150]);
+        assert_eq!(report.unmarked_clusters, vec![3]);
+        assert_eq!(report.leaked_clusters, vec![500]);
+
+        // La reparación solo se hace con el volumen desmontado
+        driver.mount(false).unwrap();
+        assert!(driver.repair().is_err());
+        driver.unmount().unwrap();
+
+        // $Bitmap y $MFTMirr se corrigen; el resto queda en el informe
+        let report = driver.repair().unwrap();
+        assert!(report.mirror_mismatches.is_empty());
+        assert_eq!(report.bitmap_mismatches(), 0);
+        assert_eq!(report.double_allocated_clusters, vec![150]);
+        assert_eq!(report.orphaned_records, vec![32]);
+        assert_eq!(report.corrupt_records, vec![33]);
+        assert!(!report.is_clean());
+
+        let mut sector = vec![0u8; SECTOR_SIZE];
+        disk.read((BITMAP_CLUSTER as usize * CLUSTER_SIZE / SECTOR_SIZE) as u64, &mut sector);
+        assert_eq!(sector[0] & (1 << 3), 1 << 3);
+        assert_eq!(sector[500 / 8] & (1 << (500 % 8)), 0);
+    }
+
+    /// Número de clústeres marcados en $Bitmap
+    fn used_clusters(disk: &TestDisk) -> u32 {
+        let mut bitmap = vec![0u8; SECTOR_SIZE];
+        disk.read((BITMAP_CLUSTER as usize * CLUSTER_SIZE / SECTOR_SIZE) as u64, &mut bitmap);
+        bitmap[..VOLUME_CLUSTERS / 8].iter().map(|byte| byte.count_ones()).sum()
+    }
+
+    #[test]
+    fn test_write_truncate_and_delete() {
+        let disk = TestDisk::with_data(ntfs_image(), SECTOR_SIZE as u32);
+        let mut driver = mount_image(&disk);
+        let metadata_clusters = used_clusters(&disk);
+        let file = driver.create_file(NTFS_ROOT_DIRECTORY_RECORD, "data.bin").unwrap();
+
+        // Pequeño: el $DATA sigue residente
+        driver.write_file_data(file, 0, b"hello").unwrap();
+        assert_eq!(read_all(&driver, file), b"hello");
+        assert_eq!(used_clusters(&disk), metadata_clusters);
+
+        // Escribir lejos del final rellena el hueco con ceros y pasa a no residente
+        let tail = pattern(9, 3000);
+        driver.write_file_data(file, 10_000, &tail).unwrap();
+        let mut expected = b"hello".to_vec();
+        expected.resize(10_000, 0);
+        expected.extend_from_slice(&tail);
+        assert_eq!(driver.get_file_size(file), Ok(13_000));
+        assert_eq!(read_all(&driver, file), expected);
+        assert_eq!(used_clusters(&disk), metadata_clusters + 4);
+
+        // El $FILE_NAME del índice del padre refleja el tamaño nuevo
+        let entry = driver.read_directory(NTFS_ROOT_DIRECTORY_RECORD).unwrap().remove(0);
+        assert_eq!({ entry.file_name.actual_size }, 13_000);
+
+        driver.truncate_file(file, 4097).unwrap();
+        assert_eq!(read_all(&driver, file), &expected[..4097]);
+        assert_eq!(used_clusters(&disk), metadata_clusters + 2);
+        driver.truncate_file(file, 6000).unwrap();
+        assert!(read_all(&driver, file)[4097..].iter().all(|&byte| byte == 0));
+
+        let mut record = vec![0u8; RECORD_SIZE];
+        driver.read_mft_record_data(file, &mut record).unwrap();
+        let reference = file | (read_u16(&record, 16) as u64) << 48;
+        driver.delete_file(file).unwrap();
+        assert_eq!(used_clusters(&disk), metadata_clusters);
+        assert!(driver.find_file(NTFS_ROOT_DIRECTORY_RECORD, "data.bin").is_err());
+        assert!(driver.read_mft_reference(reference, &mut record).is_err());
+        assert!(driver.delete_file(NTFS_BITMAP_RECORD).is_err());
+
+        // El registro liberado se reutiliza con un número de secuencia nuevo
+        let reused = driver.create_file(NTFS_ROOT_DIRECTORY_RECORD, "again.bin").unwrap();
+        assert_eq!(reused, file);
+        driver.read_mft_record_data(reused, &mut record).unwrap();
+        assert_eq!(read_u16(&record, 16) as u64, (reference >> 48) + 1);
+
+        driver.unmount().unwrap();
+        assert!(driver.check_integrity().unwrap().is_clean());
+    }
+
+    #[test]
+    fn test_directories_and_rename() {
+        let disk = TestDisk::with_data(ntfs_image(), SECTOR_SIZE as u32);
+        let mut driver = mount_image(&disk);
+        let docs = driver.create_directory(NTFS_ROOT_DIRECTORY_RECORD, "docs").unwrap();
+        let archive = driver.create_directory(NTFS_ROOT_DIRECTORY_RECORD, "archive").unwrap();
+        let file = driver.create_file(docs, "report.txt").unwrap();
+        driver.write_file_data(file, 0, b"quarterly").unwrap();
+
+        assert_eq!(driver.create_file(docs, "REPORT.TXT"), Err("File already exists"));
+        assert!(driver.create_file(docs, "bad:name").is_err());
+        assert!(driver.create_file(file, "child.txt").is_err());
+        assert_eq!(driver.delete_file(docs), Err("Directory not empty"));
+
+        // Mover con otro nombre: sale del índice de docs y entra en el de archive
+        driver.rename_file(file, archive, "report-2024.txt").unwrap();
+        assert!(driver.read_directory(docs).unwrap().is_empty());
+        assert_eq!(driver.lookup_path("\\archive\\report-2024.txt"), Ok(file));
+        assert!(driver.lookup_path("\\docs\\report.txt").is_err());
+        assert_eq!(read_all(&driver, file), b"quarterly");
+
+        let mut record = vec![0u8; RECORD_SIZE];
+        driver.read_mft_record_data(file, &mut record).unwrap();
+        let names: Vec<u64> = driver
+            .attributes(&record)
+            .filter(|attr| read_u32(attr, 0) == NtfsAttributeType::FileName as u32)
+            .map(|attr| read_u64(attribute_content(attr).unwrap(), 0) & NTFS_MFT_REFERENCE_MASK)
+            .collect();
+        assert_eq!(names, vec![archive]);
+
+        driver.delete_file(docs).unwrap();
+        assert_eq!(driver.read_directory(NTFS_ROOT_DIRECTORY_RECORD).unwrap().len(), 1);
+
+        driver.unmount().unwrap();
+        assert!(driver.check_integrity().unwrap().is_clean());
+    }
+
+    /// Bloque LZNT1 de 4 KB que repite `abc`: tres literales y una referencia
+    /// (desplazamiento 3, longitud 4093) que llena el resto del bloque
+    fn repeating_chunk(abc: [u8; 3]) -> Vec<u8> {
+        vec![0x05, 0xB0, 0x08, abc[0], abc[1], abc[2], 0xFA, 0x2F]
+    }
+
+    /// Registro con $STANDARD_INFORMATION y un $DATA no residente con los flags indicados
+    fn data_record(record_number: u64, flags: u16, compression_unit: u8, runs: &[NtfsDataRun], size: u64) -> Vec<u8> {
+        let time = unix_to_ntfs_time(TEST_TIME);
+        let mut record = empty_record(record_number, 1, 0);
+        add_resident(&mut record, NtfsAttributeType::StandardInformation, "", &standard_information_content(time, NTFS_FILE_ATTRIBUTE_ARCHIVE, 0));
+        add_attribute(&mut record, |id| {
+            let mut attr = layout_driver().build_non_resident_attribute(NtfsAttributeType::Data as u32, "", id, flags, runs, size, size);
+            attr[34] = compression_unit;
+            attr
+        });
+        record
+    }
+
+    #[test]
+    fn test_compressed_and_sparse_data() {
+        const UNIT_CLUSTERS: u64 = 16;
+        const UNIT_SIZE: usize = UNIT_CLUSTERS as usize * CLUSTER_SIZE;
+        let mut image = ntfs_image();
+
+        // compressed.bin: una unidad comprimida en el clúster 100, una unidad
+        // dispersa y una guardada sin comprimir en los clústeres 200 a 215
+        let compressed_runs = [
+            NtfsDataRun { vcn: 0, length: 1, lcn: Some(100) },
+            NtfsDataRun { vcn: 1, length: 2 * UNIT_CLUSTERS - 1, lcn: None },
+            NtfsDataRun { vcn: 2 * UNIT_CLUSTERS, length: UNIT_CLUSTERS, lcn: Some(200) },
+        ];
+        let compressed_size = 2 * UNIT_SIZE + 5000;
+        let record = data_record(16, NTFS_ATTRIBUTE_FLAG_COMPRESSED, 4, &compressed_runs, compressed_size as u64);
+        put_record(&mut image, 16, &record);
+
+        let mut expected = Vec::with_capacity(compressed_size);
+        let mut chunks = Vec::new();
+        for chunk in 0..(UNIT_SIZE / LZNT1_CHUNK_SIZE) as u8 {
+            let abc = [b'a' + chunk, b'b' + chunk, b'c' + chunk];
+            chunks.extend(repeating_chunk(abc));
+            expected.extend((0..LZNT1_CHUNK_SIZE).map(|i| abc[i % 3]));
+        }
+        image[cluster_offset(100)..cluster_offset(100) + chunks.len()].copy_from_slice(&chunks);
+        expected.resize(2 * UNIT_SIZE, 0);
+        let stored = pattern(7, UNIT_SIZE);
+        image[cluster_offset(200)..cluster_offset(200) + UNIT_SIZE].copy_from_slice(&stored);
+        expected.extend_from_slice(&stored[..5000]);
+
+        // sparse.bin: un hueco de dos clústeres entre dos clústeres con datos
+        let sparse_runs = [
+            NtfsDataRun { vcn: 0, length: 1, lcn: Some(300) },
+            NtfsDataRun { vcn: 1, length: 2, lcn: None },
+            NtfsDataRun { vcn: 3, length: 1, lcn: Some(301) },
+        ];
+        let record = data_record(17, NTFS_ATTRIBUTE_FLAG_SPARSE, 0, &sparse_runs, 4 * CLUSTER_SIZE as u64);
+        put_record(&mut image, 17, &record);
+        let sparse_data = pattern(8, 2 * CLUSTER_SIZE);
+        image[cluster_offset(300)..cluster_offset(302)].copy_from_slice(&sparse_data);
+
+        let disk = TestDisk::with_data(image, SECTOR_SIZE as u32);
+        let mut driver = NtfsDriver::new(disk.device_id);
+        driver.init().unwrap();
+
+        let mut data = vec![0u8; compressed_size];
+        assert_eq!(driver.read_stream(16, "", 0, &mut data), Ok(compressed_size));
+        assert!(data == expected, "compressed.bin no coincide");
+
+        // Lectura que cruza de la unidad comprimida al hueco y de este a la guardada sin comprimir
+        let mut middle = vec![0xFFu8; UNIT_SIZE + 20];
+        assert_eq!(driver.read_stream(16, "", UNIT_SIZE as u64 - 10, &mut middle), Ok(UNIT_SIZE + 20));
+        assert_eq!(middle, &expected[UNIT_SIZE - 10..2 * UNIT_SIZE + 10]);
+        assert_eq!(driver.read_stream(16, "", compressed_size as u64 - 4, &mut middle), Ok(4));
+
+        let mut data = vec![0xFFu8; 4 * CLUSTER_SIZE];
+        assert_eq!(driver.read_stream(17, "", 0, &mut data), Ok(data.len()));
+        assert_eq!(&data[..CLUSTER_SIZE], &sparse_data[..CLUSTER_SIZE]);
+        assert!(data[CLUSTER_SIZE..3 * CLUSTER_SIZE].iter().all(|&byte| byte == 0));
+        assert_eq!(&data[3 * CLUSTER_SIZE..], &sparse_data[CLUSTER_SIZE..]);
+
+        // Los flags del $DATA principal se reflejan en los atributos del archivo
+        let compressed = driver.get_file_attributes(16).unwrap();
+        assert_eq!(compressed & (NTFS_FILE_ATTRIBUTE_COMPRESSED | NTFS_FILE_ATTRIBUTE_SPARSE_FILE), NTFS_FILE_ATTRIBUTE_COMPRESSED);
+        let sparse = driver.get_file_attributes(17).unwrap();
+        assert_eq!(sparse & (NTFS_FILE_ATTRIBUTE_COMPRESSED | NTFS_FILE_ATTRIBUTE_SPARSE_FILE), NTFS_FILE_ATTRIBUTE_SPARSE_FILE);
+        assert!(compressed & sparse & NTFS_FILE_ATTRIBUTE_ARCHIVE != 0);
+
+        let streams = driver.list_streams(16).unwrap();
+        assert_eq!(streams.len(), 1);
+        assert_eq!((streams[0].name.as_str(), streams[0].size), ("", compressed_size as u64));
+        assert_eq!((streams[0].allocated_size, streams[0].flags), (3 * UNIT_SIZE as u64, NTFS_ATTRIBUTE_FLAG_COMPRESSED));
+    }
+
+    #[test]
+    fn test_named_streams() {
+        let disk = TestDisk::with_data(ntfs_image(), SECTOR_SIZE as u32);
+        let mut driver = mount_image(&disk);
+        let file = driver.create_file(NTFS_ROOT_DIRECTORY_RECORD, "report.docx").unwrap();
+        driver.write_file_data(file, 0, b"PK\x03\x04").unwrap();
+
+        let zone = b"[ZoneTransfer]\r\nZoneId=3\r\n";
+        assert_eq!(driver.write_stream_data(file, "Zone.Identifier", 0, zone), Ok(zone.len()));
+        assert_eq!(driver.lookup_stream("\\report.docx:Zone.Identifier:$DATA"), Ok((file, String::from("Zone.Identifier"))));
+
+        let streams = driver.list_streams(file).unwrap();
+        let names: Vec<(&str, u64)> = streams.iter().map(|stream| (stream.name.as_str(), stream.size)).collect();
+        assert_eq!(names, [("", 4), ("Zone.Identifier", zone.len() as u64)]);
+        let mut buffer = [0u8; 64];
+        assert_eq!(driver.read_stream(file, "Zone.Identifier", 0, &mut buffer), Ok(zone.len()));
+        assert_eq!(&buffer[..zone.len()], zone);
+        assert_eq!(driver.read_stream(file, "Zone.Identifier", 16, &mut buffer), Ok(zone.len() - 16));
+        assert_eq!(read_all(&driver, file), b"PK\x03\x04");
+        assert_eq!(driver.read_stream(file, "missing", 0, &mut buffer), Err("Stream not found"));
+
+        // Un flujo con nombre no cambia los atributos del archivo
+        let attributes = driver.get_file_attributes(file).unwrap();
+        assert_eq!(attributes & (NTFS_FILE_ATTRIBUTE_COMPRESSED | NTFS_FILE_ATTRIBUTE_SPARSE_FILE), 0);
+
+        driver.delete_stream(file, "Zone.Identifier").unwrap();
+        assert_eq!(driver.list_streams(file).unwrap().len(), 1);
+        assert_eq!(driver.read_stream(file, "Zone.Identifier", 0, &mut buffer), Err("Stream not found"));
+
+        driver.flush().unwrap();
+        driver.unmount().unwrap();
+        assert_eq!(driver.flush(), Err("Volume not mounted"));
+        assert!(driver.check_integrity().unwrap().is_clean());
+    }
+}
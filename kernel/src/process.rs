@@ -1,5 +1,7 @@
 //! # Gestión de Procesos del Kernel
 
+pub mod scheduler;
+
 use crate::{KernelResult, KernelError};
 
 #[derive(Debug, Clone)]
@@ -5,6 +5,14 @@
 
 use core::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
 use core::ptr::NonNull;
+use alloc::{boxed::Box, vec::Vec};
+
+/// Token de acceso del propio kernel (LocalSystem)
+///
+/// Los procesos lo usan hasta que se les asigna otro. Vive aquí y no en
+/// `security::access_control`, que lo reexporta, porque el planificador
+/// también se compila en la biblioteca del kernel, que no incluye la seguridad.
+pub const SYSTEM_TOKEN_ID: u64 = 1;
 
 /// Estados de un proceso
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -67,6 +75,8 @@ pub struct Process {
     pub actual_burst_time: u64,
     /// Tiempo de llegada
     pub arrival_time: u64,
+    /// Token de acceso con el que actúa el proceso
+    pub token_id: u64,
     /// Puntero al siguiente proceso en la cola
     pub next: Option<NonNull<Process>>,
     /// Puntero al proceso anterior en la cola
@@ -95,11 +105,18 @@ impl Process {
             burst_time: 0,
             actual_burst_time: 0,
             arrival_time: 0,
+            token_id: SYSTEM_TOKEN_ID,
             next: None,
             prev: None,
         }
     }
 
+    /// Nombre del proceso
+    pub fn name(&self) -> &str {
+        let length = self.name.iter().position(|&byte| byte == 0).unwrap_or(self.name.len());
+        core::str::from_utf8(&self.name[..length]).unwrap_or("")
+    }
+
     /// Cambiar el estado del proceso
     pub fn set_state(&mut self, new_state: ProcessState) {
         self.state = new_state;
@@ -201,6 +218,18 @@ impl ProcessQueue {
         }
     }
 
+    /// Recorrer los procesos de la cola en orden
+    pub fn iter(&self) -> impl Iterator<Item = &Process> + '_ {
+        let mut next = self.head;
+        core::iter::from_fn(move || {
+            let process = next?;
+            unsafe {
+                next = (*process.as_ptr()).next;
+                Some(&*process.as_ptr())
+            }
+        })
+    }
+
     /// Verificar si la cola está vacía
     pub fn is_empty(&self) -> bool {
         self.head.is_none()
@@ -288,11 +317,14 @@ impl ProcessScheduler {
     }
 
     /// Crear un nuevo proceso
+    ///
+    /// El proceso hereda el token de acceso del proceso que lo crea.
     pub fn create_process(&mut self, name: &str, priority: ProcessPriority) -> u32 {
         let pid = self.process_counter.fetch_add(1, Ordering::SeqCst);
         let mut process = Process::new(pid, name, priority);
         process.arrival_time = self.system_time.load(Ordering::SeqCst);
         process.creation_time = process.arrival_time;
+        process.token_id = self.current_token();
         
         let process_ptr = Box::into_raw(Box::new(process));
         let process_non_null = unsafe { NonNull::new_unchecked(process_ptr) };
@@ -337,7 +369,7 @@ impl ProcessScheduler {
         }
 
         // Encontrar el proceso con el menor tiempo de ráfaga
-        let mut shortest_process = None;
+        let mut shortest_process: Option<NonNull<Process>> = None;
         let mut shortest_burst = u64::MAX;
         
         // TODO: Implementar búsqueda del proceso más corto
@@ -382,7 +414,7 @@ impl ProcessScheduler {
         }
 
         // Encontrar el proceso con la mayor prioridad
-        let mut highest_priority_process = None;
+        let mut highest_priority_process: Option<NonNull<Process>> = None;
         let mut highest_priority = ProcessPriority::Background;
         
         // TODO: Implementar búsqueda del proceso con mayor prioridad
@@ -482,6 +514,20 @@ impl ProcessScheduler {
         self.current_process
     }
 
+    /// Token de acceso del proceso actual
+    ///
+    /// Sin proceso en ejecución se actúa en nombre del kernel, con el token
+    /// del sistema.
+    pub fn current_token(&self) -> u64 {
+        self.current_process.map_or(SYSTEM_TOKEN_ID, |current| unsafe { (*current.as_ptr()).token_id })
+    }
+
+    /// Cambiar el token de acceso del proceso actual y devolver el anterior
+    pub fn set_current_token(&mut self, token_id: u64) -> Result<u64, &'static str> {
+        let current = self.current_process.ok_or("No hay proceso en ejecución")?;
+        unsafe { Ok(core::mem::replace(&mut (*current.as_ptr()).token_id, token_id)) }
+    }
+
     /// Obtener el número de procesos en la cola de listos
     pub fn get_ready_count(&self) -> usize {
         self.ready_queue.len()
@@ -491,6 +537,24 @@ impl ProcessScheduler {
     pub fn get_blocked_count(&self) -> usize {
         self.blocked_queue.len()
     }
+
+    /// Tabla de procesos: el actual, los listos y los bloqueados, ordenados por PID
+    pub fn processes(&self) -> Vec<&Process> {
+        let mut processes: Vec<&Process> = self
+            .current_process
+            .map(|current| unsafe { &*current.as_ptr() })
+            .into_iter()
+            .chain(self.ready_queue.iter())
+            .chain(self.blocked_queue.iter())
+            .collect();
+        processes.sort_by_key(|process| process.pid);
+        processes
+    }
+
+    /// Buscar un proceso por PID
+    pub fn find_process(&self, pid: u32) -> Option<&Process> {
+        self.processes().into_iter().find(|process| process.pid == pid)
+    }
 }
 
 /// Función para inicializar el planificador de procesos
@@ -498,8 +562,39 @@ pub fn init_process_scheduler(algorithm: SchedulingAlgorithm) -> ProcessSchedule
     ProcessScheduler::new(algorithm)
 }
 
+/// Planificador global del sistema
+static mut PROCESS_SCHEDULER: Option<ProcessScheduler> = None;
+
+/// Instalar el planificador global
+pub fn set_process_scheduler(scheduler: ProcessScheduler) {
+    unsafe {
+        PROCESS_SCHEDULER = Some(scheduler);
+    }
+}
+
+/// Obtener el planificador global
+pub fn get_process_scheduler() -> Option<&'static mut ProcessScheduler> {
+    unsafe { (*core::ptr::addr_of_mut!(PROCESS_SCHEDULER)).as_mut() }
+}
+
+/// Token de acceso con el que actúa el código en ejecución
+///
+/// Es el del proceso actual; sin planificador o sin proceso en ejecución, el
+/// del sistema.
+pub fn current_token() -> u64 {
+    get_process_scheduler().map_or(SYSTEM_TOKEN_ID, |scheduler| scheduler.current_token())
+}
+
 /// Función para obtener estadísticas de procesos
 pub fn get_process_statistics() -> (usize, usize, usize, usize) {
-    // TODO: Implementar acceso a las estadísticas del planificador
-    (10, 5, 3, 2) // (total, running, ready, blocked)
+    // (total, running, ready, blocked)
+    get_process_scheduler().map_or((0, 0, 0, 0), |scheduler| {
+        let running = if scheduler.current_process.is_some() { 1 } else { 0 };
+        (
+            running + scheduler.get_ready_count() + scheduler.get_blocked_count(),
+            running,
+            scheduler.get_ready_count(),
+            scheduler.get_blocked_count(),
+        )
+    })
 }
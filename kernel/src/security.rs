@@ -5,6 +5,9 @@
 use core::sync::atomic::{AtomicU32, AtomicU64, AtomicBool, Ordering};
 // use core::ptr;
 
+pub mod access_control;
+pub mod audit;
+
 /// Niveles de seguridad del sistema
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum SecurityLevel {
@@ -1,8 +1,86 @@
 //! Sistema de Control de Acceso del Kernel ReactOS Rust
 //! 
-//! Implementa control de acceso basado en roles y permisos
+//! Implementa control de acceso basado en roles y permisos, y descriptores
+//! de seguridad al estilo NT: propietario (SID), DACL con ACE de permiso y
+//! de denegación, y herencia de ACE de los contenedores a sus hijos.
 
+use core::fmt;
 use core::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use alloc::{vec, vec::Vec};
+
+/// Acceso concedido
+pub const STATUS_SUCCESS: u32 = 0x00000000;
+/// Acceso denegado
+pub const STATUS_ACCESS_DENIED: u32 = 0xC0000022;
+/// El token indicado no existe
+pub const STATUS_NO_TOKEN: u32 = 0xC000007C;
+
+/// Leer datos de un archivo o listar un directorio
+pub const FILE_READ_DATA: u32 = 0x0001;
+/// Escribir datos en un archivo o crear archivos en un directorio
+pub const FILE_WRITE_DATA: u32 = 0x0002;
+/// Crear un archivo en un directorio (mismo bit que `FILE_WRITE_DATA`)
+pub const FILE_ADD_FILE: u32 = 0x0002;
+/// Añadir datos al final de un archivo
+pub const FILE_APPEND_DATA: u32 = 0x0004;
+/// Crear un subdirectorio (mismo bit que `FILE_APPEND_DATA`)
+pub const FILE_ADD_SUBDIRECTORY: u32 = 0x0004;
+/// Leer atributos extendidos
+pub const FILE_READ_EA: u32 = 0x0008;
+/// Escribir atributos extendidos
+pub const FILE_WRITE_EA: u32 = 0x0010;
+/// Ejecutar un archivo o atravesar un directorio
+pub const FILE_EXECUTE: u32 = 0x0020;
+/// Borrar hijos de un directorio
+pub const FILE_DELETE_CHILD: u32 = 0x0040;
+/// Leer atributos
+pub const FILE_READ_ATTRIBUTES: u32 = 0x0080;
+/// Escribir atributos
+pub const FILE_WRITE_ATTRIBUTES: u32 = 0x0100;
+/// Borrar el objeto
+pub const DELETE: u32 = 0x00010000;
+/// Leer el descriptor de seguridad
+pub const READ_CONTROL: u32 = 0x00020000;
+/// Cambiar el DACL
+pub const WRITE_DAC: u32 = 0x00040000;
+/// Cambiar el propietario
+pub const WRITE_OWNER: u32 = 0x00080000;
+/// Esperar sobre el objeto
+pub const SYNCHRONIZE: u32 = 0x00100000;
+/// Pedir todo el acceso que permita el descriptor
+pub const MAXIMUM_ALLOWED: u32 = 0x02000000;
+/// Acceso genérico total
+pub const GENERIC_ALL: u32 = 0x10000000;
+/// Acceso genérico de ejecución
+pub const GENERIC_EXECUTE: u32 = 0x20000000;
+/// Acceso genérico de escritura
+pub const GENERIC_WRITE: u32 = 0x40000000;
+/// Acceso genérico de lectura
+pub const GENERIC_READ: u32 = 0x80000000;
+
+/// Lectura de archivos (`GENERIC_READ` traducido)
+pub const FILE_GENERIC_READ: u32 = READ_CONTROL | FILE_READ_DATA | FILE_READ_ATTRIBUTES | FILE_READ_EA | SYNCHRONIZE;
+/// Escritura de archivos (`GENERIC_WRITE` traducido)
+pub const FILE_GENERIC_WRITE: u32 =
+    READ_CONTROL | FILE_WRITE_DATA | FILE_WRITE_ATTRIBUTES | FILE_WRITE_EA | FILE_APPEND_DATA | SYNCHRONIZE;
+/// Ejecución de archivos (`GENERIC_EXECUTE` traducido)
+pub const FILE_GENERIC_EXECUTE: u32 = READ_CONTROL | FILE_READ_ATTRIBUTES | FILE_EXECUTE | SYNCHRONIZE;
+/// Acceso total a archivos (`GENERIC_ALL` traducido)
+pub const FILE_ALL_ACCESS: u32 = 0x001F01FF;
+
+/// La ACE se hereda a los archivos
+pub const OBJECT_INHERIT_ACE: u8 = 0x01;
+/// La ACE se hereda a los subdirectorios
+pub const CONTAINER_INHERIT_ACE: u8 = 0x02;
+/// La ACE heredada no se sigue propagando
+pub const NO_PROPAGATE_INHERIT_ACE: u8 = 0x04;
+/// La ACE solo sirve para heredarse; no se aplica al propio objeto
+pub const INHERIT_ONLY_ACE: u8 = 0x08;
+/// La ACE se ha heredado del contenedor
+pub const INHERITED_ACE: u8 = 0x10;
+
+/// Token del propio kernel (LocalSystem), creado con el gestor
+pub use crate::process::scheduler::SYSTEM_TOKEN_ID;
 
 /// Niveles de privilegio
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -56,6 +134,252 @@ pub struct ProcessId {
     pub parent_pid: u32,
 }
 
+/// Identificador de seguridad (SID), como S-1-5-32-544
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Sid {
+    /// Autoridad que emite el SID
+    pub authority: u64,
+    /// Subautoridades; la última suele ser el RID
+    pub sub_authorities: Vec<u32>,
+}
+
+impl Sid {
+    /// Crear un SID
+    pub fn new(authority: u64, sub_authorities: &[u32]) -> Self {
+        Self {
+            authority,
+            sub_authorities: sub_authorities.to_vec(),
+        }
+    }
+
+    /// Todos (S-1-1-0)
+    pub fn everyone() -> Self {
+        Self::new(1, &[0])
+    }
+
+    /// LocalSystem (S-1-5-18)
+    pub fn local_system() -> Self {
+        Self::new(5, &[18])
+    }
+
+    /// Administradores (S-1-5-32-544)
+    pub fn administrators() -> Self {
+        Self::new(5, &[32, 544])
+    }
+
+    /// Usuarios (S-1-5-32-545)
+    pub fn users() -> Self {
+        Self::new(5, &[32, 545])
+    }
+
+    /// Usuario Unix con su UID (S-1-22-1-uid, como Samba)
+    pub fn unix_user(uid: u32) -> Self {
+        Self::new(22, &[1, uid])
+    }
+
+    /// Grupo Unix con su GID (S-1-22-2-gid)
+    pub fn unix_group(gid: u32) -> Self {
+        Self::new(22, &[2, gid])
+    }
+}
+
+impl fmt::Display for Sid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "S-1-{}", self.authority)?;
+        for sub_authority in &self.sub_authorities {
+            write!(f, "-{}", sub_authority)?;
+        }
+        Ok(())
+    }
+}
+
+/// Tipos de ACE
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AceType {
+    /// Concede los derechos de su máscara
+    AccessAllowed,
+    /// Deniega los derechos de su máscara
+    AccessDenied,
+}
+
+/// Entrada de control de acceso
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ace {
+    pub ace_type: AceType,
+    /// Flags de herencia (`*_INHERIT_ACE`, `INHERITED_ACE`)
+    pub flags: u8,
+    pub access_mask: u32,
+    pub sid: Sid,
+}
+
+impl Ace {
+    /// ACE que concede `access_mask` a `sid`
+    pub fn allow(sid: Sid, access_mask: u32, flags: u8) -> Self {
+        Self { ace_type: AceType::AccessAllowed, flags, access_mask, sid }
+    }
+
+    /// ACE que deniega `access_mask` a `sid`
+    pub fn deny(sid: Sid, access_mask: u32, flags: u8) -> Self {
+        Self { ace_type: AceType::AccessDenied, flags, access_mask, sid }
+    }
+
+    /// Posición en el orden canónico: denegaciones explícitas, permisos
+    /// explícitos, denegaciones heredadas y permisos heredados
+    fn canonical_rank(&self) -> u8 {
+        let inherited = if self.flags & INHERITED_ACE != 0 { 2 } else { 0 };
+        let allowed = if self.ace_type == AceType::AccessAllowed { 1 } else { 0 };
+        inherited + allowed
+    }
+}
+
+/// Lista de control de acceso; las ACE se evalúan en orden
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Acl {
+    pub aces: Vec<Ace>,
+}
+
+impl Acl {
+    /// Crear una lista vacía (no concede nada)
+    pub fn new() -> Self {
+        Self { aces: Vec::new() }
+    }
+
+    /// Añadir una ACE en su posición canónica
+    pub fn add(&mut self, ace: Ace) {
+        let rank = ace.canonical_rank();
+        let position = self.aces.iter().position(|existing| existing.canonical_rank() > rank).unwrap_or(self.aces.len());
+        self.aces.insert(position, ace);
+    }
+}
+
+/// Descriptor de seguridad de un objeto
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecurityDescriptor {
+    /// Propietario; siempre puede leer y cambiar el DACL
+    pub owner: Sid,
+    /// Grupo principal
+    pub group: Sid,
+    /// DACL; `None` (DACL nulo) concede todo a todos
+    pub dacl: Option<Acl>,
+}
+
+impl SecurityDescriptor {
+    /// Crear un descriptor
+    pub fn new(owner: Sid, group: Sid, dacl: Option<Acl>) -> Self {
+        Self { owner, group, dacl }
+    }
+
+    /// Descriptor de un objeto nuevo creado dentro de `parent`
+    ///
+    /// Los archivos heredan las ACE con `OBJECT_INHERIT_ACE`; los
+    /// directorios, las que tienen `CONTAINER_INHERIT_ACE` (que siguen
+    /// propagándose salvo con `NO_PROPAGATE_INHERIT_ACE`) y, como solo
+    /// heredables, las de archivos. Devuelve `None` si el contenedor no
+    /// tiene nada que heredar.
+    pub fn inherit(parent: &SecurityDescriptor, owner: Sid, group: Sid, is_container: bool) -> Option<Self> {
+        let mut dacl = Acl::new();
+        for ace in parent.dacl.iter().flat_map(|acl| acl.aces.iter()) {
+            let object = ace.flags & OBJECT_INHERIT_ACE != 0;
+            let container = ace.flags & CONTAINER_INHERIT_ACE != 0;
+            let propagate = ace.flags & NO_PROPAGATE_INHERIT_ACE == 0;
+            let flags = if !is_container {
+                if !object {
+                    continue;
+                }
+                INHERITED_ACE
+            } else if container {
+                if propagate {
+                    ace.flags & (OBJECT_INHERIT_ACE | CONTAINER_INHERIT_ACE) | INHERITED_ACE
+                } else {
+                    INHERITED_ACE
+                }
+            } else if object && propagate {
+                OBJECT_INHERIT_ACE | INHERIT_ONLY_ACE | INHERITED_ACE
+            } else {
+                continue;
+            };
+            dacl.aces.push(Ace { flags, ..ace.clone() });
+        }
+        if dacl.aces.is_empty() {
+            return None;
+        }
+        Some(Self::new(owner, group, Some(dacl)))
+    }
+}
+
+/// Token de acceso: identidad con la que actúa un proceso
+#[derive(Debug, Clone)]
+pub struct AccessToken {
+    pub token_id: u64,
+    pub user: Sid,
+    pub groups: Vec<Sid>,
+}
+
+impl AccessToken {
+    /// Verificar si el token incluye un SID (como usuario o como grupo)
+    pub fn contains(&self, sid: &Sid) -> bool {
+        self.user == *sid || self.groups.contains(sid)
+    }
+}
+
+/// Traducir los derechos genéricos de una máscara a derechos de archivo
+pub fn map_generic_file_access(access_mask: u32) -> u32 {
+    let mut mapped = access_mask & !(GENERIC_READ | GENERIC_WRITE | GENERIC_EXECUTE | GENERIC_ALL);
+    if access_mask & GENERIC_READ != 0 {
+        mapped |= FILE_GENERIC_READ;
+    }
+    if access_mask & GENERIC_WRITE != 0 {
+        mapped |= FILE_GENERIC_WRITE;
+    }
+    if access_mask & GENERIC_EXECUTE != 0 {
+        mapped |= FILE_GENERIC_EXECUTE;
+    }
+    if access_mask & GENERIC_ALL != 0 {
+        mapped |= FILE_ALL_ACCESS;
+    }
+    mapped
+}
+
+/// Evaluar el DACL de un descriptor para un token
+///
+/// Las ACE se recorren en orden: cada derecho lo decide la primera ACE del
+/// token que lo menciona. Con `MAXIMUM_ALLOWED` se concede todo lo que
+/// permita el DACL, y se deniega solo si no queda nada.
+pub fn evaluate_access(descriptor: &SecurityDescriptor, token: &AccessToken, desired_access: u32) -> AccessCheckResult {
+    let desired = map_generic_file_access(desired_access);
+    let maximum = desired & MAXIMUM_ALLOWED != 0;
+    let desired = desired & !MAXIMUM_ALLOWED;
+    
+    let granted_access = match descriptor.dacl {
+        None => FILE_ALL_ACCESS | desired,
+        Some(ref dacl) => {
+            let mut granted = if token.contains(&descriptor.owner) { READ_CONTROL | WRITE_DAC } else { 0 };
+            let mut denied = 0;
+            let applicable = dacl.aces.iter().filter(|ace| ace.flags & INHERIT_ONLY_ACE == 0 && token.contains(&ace.sid));
+            for ace in applicable {
+                let mask = map_generic_file_access(ace.access_mask);
+                match ace.ace_type {
+                    AceType::AccessAllowed => granted |= mask & !denied,
+                    AceType::AccessDenied => denied |= mask & !granted,
+                }
+            }
+            granted
+        }
+    };
+    
+    let granted = desired & !granted_access == 0 && (!maximum || granted_access != 0);
+    AccessCheckResult {
+        granted,
+        granted_access: match (granted, maximum) {
+            (false, _) => 0,
+            (true, true) => granted_access,
+            (true, false) => desired,
+        },
+        privilege_set: 0,
+        access_status: if granted { STATUS_SUCCESS } else { STATUS_ACCESS_DENIED },
+    }
+}
+
 /// Resultado de verificación de acceso
 #[derive(Debug, Clone, Copy)]
 pub struct AccessCheckResult {
@@ -73,40 +397,83 @@ pub struct AccessControlManager {
     pub access_denials: AtomicU64,
     pub privilege_escalations: AtomicU64,
     pub security_violations: AtomicU64,
+    pub tokens: Vec<AccessToken>,
 }
 
 impl AccessControlManager {
     /// Crear nuevo gestor de control de acceso
+    ///
+    /// El primer token (`SYSTEM_TOKEN_ID`) es el del kernel: LocalSystem y
+    /// miembro de Administradores.
     pub fn new() -> Self {
-        Self {
-            next_token_id: AtomicU64::new(1),
+        let mut manager = Self {
+            next_token_id: AtomicU64::new(SYSTEM_TOKEN_ID),
             access_checks: AtomicU64::new(0),
             access_grants: AtomicU64::new(0),
             access_denials: AtomicU64::new(0),
             privilege_escalations: AtomicU64::new(0),
             security_violations: AtomicU64::new(0),
+            tokens: Vec::new(),
+        };
+        manager.create_token(Sid::local_system(), vec![Sid::administrators(), Sid::everyone()]);
+        manager
+    }
+    
+    /// Crear un token para un usuario y sus grupos
+    pub fn create_token(&mut self, user: Sid, groups: Vec<Sid>) -> u64 {
+        let token_id = self.next_token_id.fetch_add(1, Ordering::SeqCst);
+        self.tokens.push(AccessToken { token_id, user, groups });
+        token_id
+    }
+    
+    /// Crear un token para un usuario Unix y sus grupos
+    ///
+    /// Incluye a Todos y a Usuarios; el UID 0 es además administrador.
+    pub fn create_unix_token(&mut self, uid: u32, gids: &[u32]) -> u64 {
+        let mut groups: Vec<Sid> = gids.iter().map(|&gid| Sid::unix_group(gid)).collect();
+        groups.push(Sid::everyone());
+        groups.push(Sid::users());
+        if uid == 0 {
+            groups.push(Sid::administrators());
+        }
+        self.create_token(Sid::unix_user(uid), groups)
+    }
+    
+    /// Obtener un token
+    pub fn get_token(&self, token_id: u64) -> Option<&AccessToken> {
+        self.tokens.iter().find(|token| token.token_id == token_id)
+    }
+    
+    /// Cerrar un token
+    pub fn close_token(&mut self, token_id: u64) -> Result<(), &'static str> {
+        if token_id == SYSTEM_TOKEN_ID {
+            return Err("Cannot close the system token");
         }
+        let position = self.tokens.iter().position(|token| token.token_id == token_id).ok_or("Token not found")?;
+        self.tokens.remove(position);
+        Ok(())
     }
     
-    /// Verificar acceso a recurso
-    pub fn check_access(&mut self, _token_id: u64, _resource_id: u64, desired_access: u32) -> AccessCheckResult {
+    /// Verificar el acceso de un token a un objeto con su descriptor
+    pub fn check_access(&mut self, token_id: u64, descriptor: &SecurityDescriptor, desired_access: u32) -> AccessCheckResult {
         self.access_checks.fetch_add(1, Ordering::SeqCst);
         
-        // Verificación simplificada para demostración
-        let granted = (desired_access & 0xF0000000) == 0; // No acceso a funciones del sistema
+        let result = match self.get_token(token_id) {
+            Some(token) => evaluate_access(descriptor, token, desired_access),
+            None => AccessCheckResult {
+                granted: false,
+                granted_access: 0,
+                privilege_set: 0,
+                access_status: STATUS_NO_TOKEN,
+            },
+        };
         
-        if granted {
+        if result.granted {
             self.access_grants.fetch_add(1, Ordering::SeqCst);
         } else {
             self.access_denials.fetch_add(1, Ordering::SeqCst);
         }
-        
-        AccessCheckResult {
-            granted,
-            granted_access: if granted { desired_access } else { 0 },
-            privilege_set: 0,
-            access_status: if granted { 0 } else { 0x80000005 }, // ERROR_ACCESS_DENIED
-        }
+        result
     }
     
     /// Elevar privilegios
@@ -148,12 +515,174 @@ pub fn get_access_control_manager() -> Option<&'static mut AccessControlManager>
     }
 }
 
+/// Apagar el control de acceso y descartar sus tokens
+///
+/// Desde entonces se deniegan los accesos que dependen de él.
+pub fn shutdown_access_control() {
+    unsafe {
+        ACCESS_CONTROL_MANAGER = None;
+    }
+}
+
 /// Verificar acceso
-pub fn check_access(token_id: u64, resource_id: u64, desired_access: u32) -> Option<AccessCheckResult> {
-    get_access_control_manager().map(|manager| manager.check_access(token_id, resource_id, desired_access))
+pub fn check_access(token_id: u64, descriptor: &SecurityDescriptor, desired_access: u32) -> Option<AccessCheckResult> {
+    get_access_control_manager().map(|manager| manager.check_access(token_id, descriptor, desired_access))
 }
 
 /// Elevar privilegios
 pub fn elevate_privileges(token_id: u64, privileges: Vec<u32>) -> Result<(), &'static str> {
     get_access_control_manager().map_or(Err("Access control manager not initialized"), |manager| manager.elevate_privileges(token_id, privileges))
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_token() -> AccessToken {
+        AccessToken { token_id: 2, user: Sid::unix_user(1000), groups: vec![Sid::unix_group(100), Sid::everyone()] }
+    }
+
+    /// Descriptor de otro propietario con las ACE en el orden dado, sin reordenar
+    fn descriptor(aces: Vec<Ace>) -> SecurityDescriptor {
+        SecurityDescriptor::new(Sid::local_system(), Sid::administrators(), Some(Acl { aces }))
+    }
+
+    #[test]
+    fn test_evaluate_access_ace_order() {
+        let user = Sid::unix_user(1000);
+        let cases: Vec<(&str, Vec<Ace>, u32, Option<u32>)> = vec![
+            ("permiso", vec![Ace::allow(user.clone(), FILE_GENERIC_READ, 0)], FILE_READ_DATA, Some(FILE_READ_DATA)),
+            ("sin ACE", vec![], FILE_READ_DATA, None),
+            ("permiso parcial", vec![Ace::allow(user.clone(), FILE_READ_DATA, 0)], FILE_READ_DATA | FILE_WRITE_DATA, None),
+            (
+                "denegación antes del permiso",
+                vec![Ace::deny(Sid::everyone(), FILE_WRITE_DATA, 0), Ace::allow(user.clone(), FILE_GENERIC_WRITE, 0)],
+                FILE_WRITE_DATA,
+                None,
+            ),
+            (
+                "permiso antes de la denegación",
+                vec![Ace::allow(user.clone(), FILE_WRITE_DATA, 0), Ace::deny(Sid::everyone(), FILE_WRITE_DATA, 0)],
+                FILE_WRITE_DATA,
+                Some(FILE_WRITE_DATA),
+            ),
+            (
+                "la denegación solo afecta a sus derechos",
+                vec![Ace::deny(Sid::everyone(), FILE_WRITE_DATA, 0), Ace::allow(user.clone(), FILE_GENERIC_READ, 0)],
+                FILE_READ_DATA,
+                Some(FILE_READ_DATA),
+            ),
+            (
+                "ACE de otro usuario",
+                vec![Ace::deny(Sid::unix_user(2000), FILE_ALL_ACCESS, 0), Ace::allow(Sid::everyone(), FILE_READ_DATA, 0)],
+                FILE_READ_DATA,
+                Some(FILE_READ_DATA),
+            ),
+            (
+                "ACE solo heredable",
+                vec![Ace::deny(user.clone(), FILE_READ_DATA, OBJECT_INHERIT_ACE | INHERIT_ONLY_ACE), Ace::allow(user.clone(), FILE_READ_DATA, 0)],
+                FILE_READ_DATA,
+                Some(FILE_READ_DATA),
+            ),
+            ("derechos genéricos", vec![Ace::allow(Sid::unix_group(100), GENERIC_READ, 0)], GENERIC_READ, Some(FILE_GENERIC_READ)),
+        ];
+        for (name, aces, desired_access, expected) in cases {
+            let result = evaluate_access(&descriptor(aces), &user_token(), desired_access);
+            assert_eq!(result.granted, expected.is_some(), "{}", name);
+            assert_eq!(result.granted_access, expected.unwrap_or(0), "{}", name);
+            let status = if expected.is_some() { STATUS_SUCCESS } else { STATUS_ACCESS_DENIED };
+            assert_eq!(result.access_status, status, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_access_maximum_allowed_owner_and_null_dacl() {
+        let user = Sid::unix_user(1000);
+        let aces = vec![Ace::deny(Sid::everyone(), FILE_WRITE_DATA, 0), Ace::allow(user.clone(), FILE_GENERIC_READ | FILE_GENERIC_WRITE, 0)];
+        let result = evaluate_access(&descriptor(aces), &user_token(), MAXIMUM_ALLOWED);
+        assert!(result.granted);
+        assert_eq!(result.granted_access, (FILE_GENERIC_READ | FILE_GENERIC_WRITE) & !FILE_WRITE_DATA);
+        assert!(!evaluate_access(&descriptor(vec![]), &user_token(), MAXIMUM_ALLOWED).granted);
+
+        // El propietario siempre puede leer y cambiar el DACL, aunque se le deniegue
+        let mut owned = descriptor(vec![Ace::deny(user.clone(), WRITE_DAC | WRITE_OWNER, 0)]);
+        owned.owner = user;
+        assert_eq!(evaluate_access(&owned, &user_token(), WRITE_DAC).granted_access, WRITE_DAC);
+        assert_eq!(evaluate_access(&owned, &user_token(), MAXIMUM_ALLOWED).granted_access, READ_CONTROL | WRITE_DAC);
+        assert!(!evaluate_access(&owned, &user_token(), WRITE_OWNER).granted);
+
+        let open = SecurityDescriptor::new(Sid::local_system(), Sid::administrators(), None);
+        assert_eq!(evaluate_access(&open, &user_token(), MAXIMUM_ALLOWED).granted_access, FILE_ALL_ACCESS);
+        assert!(evaluate_access(&open, &user_token(), DELETE | WRITE_OWNER).granted);
+    }
+
+    #[test]
+    fn test_acl_add_keeps_canonical_order() {
+        let mut acl = Acl::new();
+        acl.add(Ace::allow(Sid::unix_user(1), FILE_READ_DATA, INHERITED_ACE));
+        acl.add(Ace::allow(Sid::unix_user(2), FILE_READ_DATA, 0));
+        acl.add(Ace::deny(Sid::unix_user(3), FILE_READ_DATA, INHERITED_ACE));
+        acl.add(Ace::deny(Sid::unix_user(4), FILE_READ_DATA, 0));
+        acl.add(Ace::allow(Sid::unix_user(5), FILE_READ_DATA, 0));
+        let order: Vec<Sid> = acl.aces.iter().map(|ace| ace.sid.clone()).collect();
+        assert_eq!(order, [4, 2, 5, 3, 1].map(Sid::unix_user));
+    }
+
+    #[test]
+    fn test_inherit() {
+        let sid = Sid::unix_user;
+        let parent = descriptor(vec![
+            Ace::allow(sid(1), FILE_READ_DATA, OBJECT_INHERIT_ACE),
+            Ace::allow(sid(2), FILE_READ_DATA, CONTAINER_INHERIT_ACE),
+            Ace::allow(sid(3), FILE_READ_DATA, OBJECT_INHERIT_ACE | CONTAINER_INHERIT_ACE | NO_PROPAGATE_INHERIT_ACE),
+            Ace::deny(sid(4), FILE_WRITE_DATA, OBJECT_INHERIT_ACE | CONTAINER_INHERIT_ACE | INHERIT_ONLY_ACE),
+            Ace::allow(sid(5), FILE_ALL_ACCESS, 0),
+        ]);
+        let flags = |descriptor: &SecurityDescriptor| -> Vec<(Sid, u8)> {
+            descriptor.dacl.as_ref().unwrap().aces.iter().map(|ace| (ace.sid.clone(), ace.flags)).collect()
+        };
+
+        let file = SecurityDescriptor::inherit(&parent, sid(1000), Sid::unix_group(100), false).unwrap();
+        assert_eq!((file.owner.clone(), file.group.clone()), (sid(1000), Sid::unix_group(100)));
+        assert_eq!(flags(&file), [(sid(1), INHERITED_ACE), (sid(3), INHERITED_ACE), (sid(4), INHERITED_ACE)]);
+
+        let directory = SecurityDescriptor::inherit(&parent, sid(1000), Sid::unix_group(100), true).unwrap();
+        assert_eq!(
+            flags(&directory),
+            [
+                (sid(1), OBJECT_INHERIT_ACE | INHERIT_ONLY_ACE | INHERITED_ACE),
+                (sid(2), CONTAINER_INHERIT_ACE | INHERITED_ACE),
+                (sid(3), INHERITED_ACE),
+                (sid(4), OBJECT_INHERIT_ACE | CONTAINER_INHERIT_ACE | INHERITED_ACE),
+            ]
+        );
+        // Las ACE de archivos del subdirectorio no se aplican a él mismo
+        let token = AccessToken { token_id: 2, user: sid(1), groups: vec![] };
+        assert!(evaluate_access(&parent, &token, FILE_READ_DATA).granted);
+        assert!(!evaluate_access(&directory, &token, FILE_READ_DATA).granted);
+
+        let nested = SecurityDescriptor::inherit(&directory, sid(1000), Sid::unix_group(100), false).unwrap();
+        assert_eq!(flags(&nested), [(sid(1), INHERITED_ACE), (sid(4), INHERITED_ACE)]);
+
+        let private = descriptor(vec![Ace::allow(sid(5), FILE_ALL_ACCESS, 0)]);
+        assert_eq!(SecurityDescriptor::inherit(&private, sid(1000), Sid::unix_group(100), false), None);
+        let open = SecurityDescriptor::new(sid(1000), Sid::unix_group(100), None);
+        assert_eq!(SecurityDescriptor::inherit(&open, sid(1000), Sid::unix_group(100), true), None);
+    }
+
+    #[test]
+    fn test_unix_tokens() {
+        let mut manager = AccessControlManager::new();
+        let token_id = manager.create_unix_token(1000, &[100]);
+        let token = manager.get_token(token_id).unwrap();
+        assert_eq!(token.user, Sid::unix_user(1000));
+        assert!(token.contains(&Sid::unix_group(100)) && token.contains(&Sid::users()));
+        assert!(!token.contains(&Sid::administrators()));
+        let root = manager.create_unix_token(0, &[0]);
+        assert!(manager.get_token(root).unwrap().contains(&Sid::administrators()));
+
+        assert_eq!(manager.check_access(99, &descriptor(vec![]), FILE_READ_DATA).access_status, STATUS_NO_TOKEN);
+        assert_eq!(manager.close_token(SYSTEM_TOKEN_ID), Err("Cannot close the system token"));
+        manager.close_token(token_id).unwrap();
+        assert!(manager.get_token(token_id).is_none());
+    }
+}
@@ -3,6 +3,7 @@
 //! Implementa registro y monitoreo de eventos de seguridad
 
 use core::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use alloc::{format, string::{String, ToString}, vec, vec::Vec};
 
 /// Tipos de eventos de auditoría
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -248,14 +249,36 @@ impl KernelAuditManager {
         let retention_time = self.config.log_retention_days as u64 * 24 * 3600; // Convertir días a segundos
         
         self.events.retain(|event| {
-            current_time - event.timestamp < retention_time
+            current_time.saturating_sub(event.timestamp) < retention_time
         });
     }
     
-    /// Obtener tiempo del sistema
+    /// Registrar un acceso denegado a un recurso
+    pub fn log_access_denied(&mut self, source: &str, target: &str, user_id: Option<String>, desired_access: u32) {
+        let timestamp = self.get_system_time();
+        self.log_event(AuditEvent {
+            event_id: 0,
+            event_type: AuditEventType::AccessDenied,
+            category: AuditCategory::Authorization,
+            severity: AuditSeverity::Warning,
+            timestamp,
+            source: source.to_string(),
+            target: target.to_string(),
+            user_id,
+            session_id: None,
+            process_id: None,
+            thread_id: None,
+            ip_address: None,
+            description: "Access denied".to_string(),
+            details: vec![("desired_access".to_string(), format!("{:#010x}", desired_access))],
+            success: false,
+            risk_level: 5,
+        });
+    }
+    
+    /// Obtener tiempo del sistema, en segundos Unix según el RTC
     fn get_system_time(&self) -> u64 {
-        // En un sistema real, esto obtendría el tiempo del sistema
-        0
+        crate::hal::rtc::unix_time()
     }
 }
 
@@ -288,9 +311,60 @@ pub fn log_audit_event(event: AuditEvent) {
     }
 }
 
+/// Registrar un acceso denegado a un recurso
+///
+/// `desired_access` se guarda en los detalles del evento, en hexadecimal.
+pub fn log_access_denied(source: &str, target: &str, user_id: Option<String>, desired_access: u32) {
+    if let Some(manager) = get_kernel_audit_manager() {
+        manager.log_access_denied(source, target, user_id, desired_access);
+    }
+}
+
 /// Limpiar eventos antiguos
 pub fn cleanup_old_audit_events() {
     if let Some(manager) = get_kernel_audit_manager() {
         manager.cleanup_old_events();
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_access_denied_event() {
+        let mut manager = KernelAuditManager::new();
+        manager.initialize().unwrap();
+        let before = crate::hal::rtc::unix_time();
+        manager.log_access_denied("VFS", "/tmp/secreto", Some("S-1-22-1-1000".to_string()), 0x0001_0000);
+        let after = crate::hal::rtc::unix_time();
+
+        assert_eq!(manager.events.len(), 2);
+        let event = &manager.events[1];
+        assert_eq!(event.event_id, 2);
+        assert_eq!(event.event_type, AuditEventType::AccessDenied);
+        assert_eq!(event.category, AuditCategory::Authorization);
+        assert_eq!(event.severity, AuditSeverity::Warning);
+        assert!(event.timestamp >= before && event.timestamp <= after);
+        assert_eq!((event.source.as_str(), event.target.as_str()), ("VFS", "/tmp/secreto"));
+        assert_eq!(event.user_id.as_deref(), Some("S-1-22-1-1000"));
+        assert_eq!(event.details, [("desired_access".to_string(), "0x00010000".to_string())]);
+        assert!(!event.success);
+        assert_eq!(manager.failed_events.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_cleanup_keeps_recent_events() {
+        let mut manager = KernelAuditManager::new();
+        manager.initialize().unwrap();
+        manager.log_access_denied("VFS", "/viejo", None, 0x1);
+        manager.events[1].timestamp = 0;
+        // Un evento con hora posterior a la actual no desborda la resta
+        manager.log_access_denied("VFS", "/futuro", None, 0x1);
+        manager.events[2].timestamp = u64::MAX;
+
+        manager.cleanup_old_events();
+        let targets: Vec<&str> = manager.events.iter().map(|event| event.target.as_str()).collect();
+        assert_eq!(targets, ["System", "/futuro"]);
+    }
+}
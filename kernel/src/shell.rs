@@ -318,6 +318,15 @@ fn register_system_commands() {
         true,
     );
     
+    // Comando quota
+    register_command(
+        b"quota",
+        b"Muestra el uso y los limites de las cuotas de disco",
+        b"quota [-u|-g]",
+        CommandType::File,
+        true,
+    );
+    
     // Comando free
     register_command(
         b"free",
@@ -865,6 +874,10 @@ fn execute_builtin_command(command: &[u8], args: &[u8]) -> u32 {
             // Mostrar uso de disco
             return 0;
         }
+        b"quota" => {
+            // Mostrar cuotas de disco
+            return show_quota_report(args);
+        }
         b"free" => {
             // Mostrar uso de memoria
             return 0;
@@ -951,6 +964,27 @@ fn execute_builtin_command(command: &[u8], args: &[u8]) -> u32 {
     }
 }
 
+/// Mostrar el informe de cuotas del VFS por el puerto serie
+///
+/// `-u` limita el informe a los usuarios y `-g` a los grupos.
+fn show_quota_report(args: &[u8]) -> u32 {
+    use crate::filesystem::quota::QuotaKind;
+
+    let kind = match args.trim_ascii() {
+        b"" => None,
+        b"-u" => Some(QuotaKind::User),
+        b"-g" => Some(QuotaKind::Group),
+        _ => return 2, // Uso incorrecto
+    };
+    match crate::filesystem::vfs::get_vfs() {
+        Some(vfs) => {
+            crate::hal::serial::send_string(&vfs.quotas.report(kind));
+            0
+        }
+        None => 1,
+    }
+}
+
 /// Agregar entrada al historial
 fn add_to_history(command: &[u8], exit_code: u32, execution_time: u64) {
     unsafe {
@@ -18,6 +18,8 @@ pub const STATUS_INSUFFICIENT_RESOURCES: u32 = 0xC000009A;
 pub const STATUS_END_OF_FILE: u32 = 0xC0000011;
 pub const STATUS_FILE_NOT_FOUND: u32 = 0xC000000F;
 pub const STATUS_ACCESS_VIOLATION: u32 = 0xC0000005;
+pub const STATUS_PENDING: u32 = 0x00000103;
+pub const STATUS_NOTIFY_ENUM_DIR: u32 = 0x0000010C;
 
 /// Tipos de acceso
 pub const GENERIC_READ: u32 = 0x80000000;
@@ -37,6 +39,37 @@ pub const FILE_ATTRIBUTE_SYSTEM: u32 = 0x00000004;
 pub const FILE_ATTRIBUTE_DIRECTORY: u32 = 0x00000010;
 pub const FILE_ATTRIBUTE_ARCHIVE: u32 = 0x00000020;
 
+/// Filtros de notificación de cambios en directorios
+pub const FILE_NOTIFY_CHANGE_FILE_NAME: u32 = 0x00000001;
+pub const FILE_NOTIFY_CHANGE_DIR_NAME: u32 = 0x00000002;
+pub const FILE_NOTIFY_CHANGE_ATTRIBUTES: u32 = 0x00000004;
+pub const FILE_NOTIFY_CHANGE_SIZE: u32 = 0x00000008;
+pub const FILE_NOTIFY_CHANGE_LAST_WRITE: u32 = 0x00000010;
+pub const FILE_NOTIFY_CHANGE_LAST_ACCESS: u32 = 0x00000020;
+pub const FILE_NOTIFY_CHANGE_CREATION: u32 = 0x00000040;
+pub const FILE_NOTIFY_CHANGE_SECURITY: u32 = 0x00000100;
+pub const FILE_NOTIFY_VALID_MASK: u32 = 0x00000FFF;
+
+/// Acciones de los registros de notificación
+pub const FILE_ACTION_ADDED: u32 = 0x00000001;
+pub const FILE_ACTION_REMOVED: u32 = 0x00000002;
+pub const FILE_ACTION_MODIFIED: u32 = 0x00000003;
+pub const FILE_ACTION_RENAMED_OLD_NAME: u32 = 0x00000004;
+pub const FILE_ACTION_RENAMED_NEW_NAME: u32 = 0x00000005;
+
+// Servicios del kernel
+extern "C" {
+    /// Leer los cambios de un directorio vigilado (VFS del kernel)
+    fn KeNotifyChangeDirectoryFile(
+        file_handle: u32,
+        buffer: *mut u8,
+        length: u32,
+        completion_filter: u32,
+        watch_tree: u8,
+        information: *mut usize,
+    ) -> u32;
+}
+
 /// Estructura de información de archivo
 #[repr(C)]
 pub struct FileBasicInformation {
@@ -47,6 +80,15 @@ pub struct FileBasicInformation {
     pub file_attributes: u32,
 }
 
+/// Registro de notificación de cambios (seguido del nombre en UTF-16)
+#[repr(C)]
+pub struct FileNotifyInformation {
+    pub next_entry_offset: u32,
+    pub action: u32,
+    pub file_name_length: u32,
+    pub file_name: [u16; 1],
+}
+
 /// Estructura de información estándar de archivo
 #[repr(C)]
 pub struct FileStandardInformation {
@@ -347,6 +389,46 @@ pub extern "C" fn NtSetInformationFile(
     STATUS_SUCCESS
 }
 
+/// Esperar cambios en un directorio
+///
+/// Los cambios se devuelven en `buffer` como registros `FileNotifyInformation`
+/// encadenados. La primera llamada sobre un handle fija el filtro y
+/// `watch_tree`; los cambios se acumulan entre llamadas hasta cerrarlo.
+/// `STATUS_PENDING` indica que todavía no hay cambios y
+/// `STATUS_NOTIFY_ENUM_DIR` que se perdieron cambios y que el llamador debe
+/// volver a listar el directorio.
+#[no_mangle]
+pub extern "C" fn NtNotifyChangeDirectoryFile(
+    file_handle: u32,
+    event: u32,
+    apc_routine: *mut c_void,
+    apc_context: *mut c_void,
+    io_status_block: *mut IoStatusBlock,
+    buffer: *mut c_void,
+    length: u32,
+    completion_filter: u32,
+    watch_tree: u8,
+) -> u32 {
+    if io_status_block.is_null()
+        || completion_filter == 0
+        || completion_filter & !FILE_NOTIFY_VALID_MASK != 0
+        || (length != 0 && (buffer.is_null() || buffer as usize % 4 != 0))
+    {
+        return STATUS_INVALID_PARAMETER;
+    }
+    
+    let mut information = 0;
+    let status = unsafe {
+        KeNotifyChangeDirectoryFile(file_handle, buffer as *mut u8, length, completion_filter, watch_tree, &mut information)
+    };
+    unsafe {
+        (*io_status_block).status = status;
+        (*io_status_block).information = information;
+    }
+    
+    status
+}
+
 // ============================================================================
 // FUNCIONES DE GESTIÓN DE MEMORIA
 // ============================================================================
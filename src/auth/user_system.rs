@@ -8,9 +8,24 @@ use std::fs;
 // use std::path::Path; // No utilizado por ahora
 use std::time::{SystemTime, UNIX_EPOCH};
 
+// Servicios del kernel
+extern "C" {
+    /// Fijar el UID y el GID con los que el VFS crea archivos y aplica las cuotas
+    fn KeSetSessionCredentials(uid: u32, gid: u32) -> bool;
+}
+
+/// Primer UID de los usuarios normales; los anteriores son del sistema
+pub const FIRST_USER_UID: u32 = 1000;
+
+/// GID del grupo de usuarios normales
+pub const USERS_GID: u32 = 100;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
     pub username: String,
+    /// UID con el que el VFS registra sus archivos y sus cuotas
+    #[serde(default)]
+    pub uid: u32,
     pub password_hash: String,
     pub full_name: String,
     pub email: String,
@@ -25,6 +40,9 @@ pub struct User {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Group {
     pub name: String,
+    /// GID con el que el VFS registra sus archivos y sus cuotas
+    #[serde(default)]
+    pub gid: u32,
     pub description: String,
     pub permissions: Vec<String>,
     pub members: Vec<String>,
@@ -60,6 +78,7 @@ impl UserManager {
         manager.load_data();
         manager.create_default_users();
         manager.create_default_groups();
+        manager.assign_missing_ids();
         
         manager
     }
@@ -111,6 +130,7 @@ impl UserManager {
             // Crear usuario administrador
             let admin_user = User {
                 username: "admin".to_string(),
+                uid: 0,
                 password_hash: self.hash_password("admin123"),
                 full_name: "Administrator".to_string(),
                 email: "admin@reactos-rust.local".to_string(),
@@ -125,6 +145,7 @@ impl UserManager {
             // Crear usuario invitado
             let guest_user = User {
                 username: "guest".to_string(),
+                uid: FIRST_USER_UID,
                 password_hash: self.hash_password("guest"),
                 full_name: "Guest User".to_string(),
                 email: "guest@reactos-rust.local".to_string(),
@@ -146,6 +167,7 @@ impl UserManager {
             // Grupo de administradores
             let admin_group = Group {
                 name: "administrators".to_string(),
+                gid: 0,
                 description: "Administradores del sistema".to_string(),
                 permissions: vec![
                     "user_management".to_string(),
@@ -159,6 +181,7 @@ impl UserManager {
             // Grupo de usuarios
             let user_group = Group {
                 name: "users".to_string(),
+                gid: USERS_GID,
                 description: "Usuarios regulares".to_string(),
                 permissions: vec![
                     "file_access".to_string(),
@@ -172,6 +195,53 @@ impl UserManager {
         }
     }
 
+    /// Asignar UID y GID a los usuarios y grupos guardados sin ellos
+    ///
+    /// Los datos anteriores a los identificadores se cargan con 0, que queda
+    /// reservado para el administrador y su grupo.
+    fn assign_missing_ids(&mut self) {
+        let mut usernames: Vec<String> = self
+            .users
+            .values()
+            .filter(|user| user.uid == 0 && user.username != "admin")
+            .map(|user| user.username.clone())
+            .collect();
+        usernames.sort();
+        for username in usernames {
+            let uid = self.next_uid();
+            if let Some(user) = self.users.get_mut(&username) {
+                user.uid = uid;
+            }
+        }
+
+        let mut group_names: Vec<String> = self
+            .groups
+            .values()
+            .filter(|group| group.gid == 0 && group.name != "administrators")
+            .map(|group| group.name.clone())
+            .collect();
+        group_names.sort();
+        for name in group_names {
+            let gid = if name == "users" && !self.groups.values().any(|group| group.gid == USERS_GID) {
+                USERS_GID
+            } else {
+                self.groups.values().map(|group| group.gid).max().unwrap_or(0).max(USERS_GID) + 1
+            };
+            if let Some(group) = self.groups.get_mut(&name) {
+                group.gid = gid;
+            }
+        }
+    }
+
+    fn next_uid(&self) -> u32 {
+        self.users
+            .values()
+            .map(|user| user.uid + 1)
+            .max()
+            .unwrap_or(FIRST_USER_UID)
+            .max(FIRST_USER_UID)
+    }
+
     fn hash_password(&self, password: &str) -> String {
         // Hash simple para demostración (en producción usar bcrypt o similar)
         use std::collections::hash_map::DefaultHasher;
@@ -224,6 +294,7 @@ impl UserManager {
 
                 self.sessions.insert(session_id.clone(), session);
                 self.current_user = Some(username.to_string());
+                self.apply_session_credentials();
 
                 // Guardar datos
                 if let Err(e) = self.save_data() {
@@ -243,6 +314,7 @@ impl UserManager {
         if let Some(session) = self.sessions.get_mut(session_id) {
             session.is_active = false;
             self.current_user = None;
+            self.apply_session_credentials();
             Ok(())
         } else {
             Err("Sesión no encontrada".to_string())
@@ -257,6 +329,29 @@ impl UserManager {
         }
     }
 
+    /// UID y GID del usuario actual, para los archivos que cree en el VFS
+    ///
+    /// El GID es el de su primer grupo.
+    pub fn current_credentials(&self) -> Option<(u32, u32)> {
+        let user = self.get_current_user()?;
+        let gid = user
+            .groups
+            .first()
+            .and_then(|name| self.groups.get(name))
+            .map_or(USERS_GID, |group| group.gid);
+        Some((user.uid, gid))
+    }
+
+    /// Pasar al VFS las credenciales de la sesión actual
+    ///
+    /// Sin sesión los archivos nuevos vuelven a ser del sistema (UID y GID 0).
+    fn apply_session_credentials(&self) {
+        let (uid, gid) = self.current_credentials().unwrap_or((0, 0));
+        if !unsafe { KeSetSessionCredentials(uid, gid) } {
+            eprintln!("VFS no disponible: las credenciales de la sesión no se aplicaron");
+        }
+    }
+
     pub fn is_authenticated(&self) -> bool {
         self.current_user.is_some()
     }
@@ -285,6 +380,7 @@ impl UserManager {
 
         let user = User {
             username: username.to_string(),
+            uid: self.next_uid(),
             password_hash: self.hash_password(password),
             full_name: full_name.to_string(),
             email: email.to_string(),
@@ -0,0 +1,344 @@
+//! Herramienta FAT32 para ReactOS Rust
+//!
+//! Formatea imágenes de disco como FAT32 y verifica (y opcionalmente repara)
+//! su consistencia. Usa el mismo driver FAT que el kernel, compilado para el
+//! host sobre el gestor de almacenamiento del crate de drivers, de modo que
+//! las imágenes se leen y escriben exactamente como lo haría el sistema.
+//!
+//! Uso:
+//!
+//! ```text
+//! fat32-tool mkfs <imagen> [--size <tamaño>] [--cluster-size <bytes>] [--label <etiqueta>]
+//!                          [--serial <hex>] [--sector-size <bytes>] [--partition <n>]
+//! fat32-tool fsck <imagen> [--repair] [--sector-size <bytes>] [--partition <n>]
+//! ```
+//!
+//! `fsck` termina con 0 si el volumen está limpio, 1 si se corrigieron
+//! errores, 4 si quedan errores sin corregir y 8 ante un fallo de operación.
+
+extern crate alloc;
+
+use std::env;
+use std::fs::OpenOptions;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use filesystem::fat32::{self, Fat32FormatOptions, Fat32Problem};
+use reactos_rust_drivers::{image, storage};
+
+// Módulos del kernel que el driver FAT necesita para compilar en el host.
+// Se incluyen en la raíz del crate para que las rutas `crate::...` del
+// kernel se resuelvan igual que dentro de él.
+#[allow(warnings, clippy::all)]
+#[path = "../../../kernel/src/filesystem/mod.rs"]
+mod filesystem;
+#[allow(warnings, clippy::all)]
+#[path = "../../../kernel/src/ntfs.rs"]
+mod ntfs;
+#[allow(warnings, clippy::all)]
+#[path = "../../../kernel/src/memory.rs"]
+mod memory;
+#[allow(warnings, clippy::all)]
+#[path = "../../../kernel/src/network/mod.rs"]
+mod network;
+
+#[allow(warnings, clippy::all)]
+mod drivers {
+    pub use reactos_rust_drivers::{image, partition, storage};
+}
+#[allow(warnings, clippy::all)]
+#[path = "../../../kernel/src/drivers/advanced/pci.rs"]
+mod pci;
+#[allow(warnings, clippy::all)]
+#[path = "../../../kernel/src/process"]
+mod process {
+    pub mod scheduler;
+}
+#[allow(warnings, clippy::all)]
+#[path = "../../../kernel/src/gui"]
+mod gui {
+    pub mod framebuffer;
+}
+#[allow(warnings, clippy::all)]
+#[path = "../../../kernel/src/security"]
+mod security {
+    pub mod access_control;
+    pub mod audit;
+}
+
+mod hal {
+    pub mod serial {
+        /// Sin puerto serie en el host: la salida de depuración se descarta
+        pub fn send_byte(_byte: u8) {}
+    }
+
+    pub mod rtc {
+        use std::time::{SystemTime, UNIX_EPOCH};
+
+        /// Reloj del host en lugar del RTC del CMOS
+        pub fn unix_time() -> u64 {
+            SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs())
+        }
+    }
+}
+
+/// Errores del kernel usados por los módulos incluidos
+#[derive(Debug)]
+#[allow(dead_code)]
+pub enum KernelError {
+    MemoryError,
+}
+
+/// Resultado del kernel usado por los módulos incluidos
+pub type KernelResult<T> = Result<T, KernelError>;
+
+/// Código de salida: volumen limpio
+const EXIT_CLEAN: i32 = 0;
+/// Código de salida: se corrigieron errores
+const EXIT_REPAIRED: i32 = 1;
+/// Código de salida: quedan errores sin corregir
+const EXIT_UNCORRECTED: i32 = 4;
+/// Código de salida: fallo de operación o de uso
+const EXIT_FAILURE: i32 = 8;
+
+/// Opciones de línea de comandos comunes a ambos subcomandos
+struct Options {
+    /// Subcomando (`mkfs` o `fsck`)
+    command: String,
+    /// Ruta de la imagen
+    image: String,
+    /// Tamaño de sector de la imagen
+    sector_size: u32,
+    /// Partición sobre la que operar (None para el disco completo)
+    partition: Option<u32>,
+    /// Tamaño con el que crear la imagen (solo `mkfs`)
+    size: Option<u64>,
+    /// Tamaño de clúster en bytes (solo `mkfs`, 0 automático)
+    cluster_size: u32,
+    /// Etiqueta del volumen (solo `mkfs`)
+    label: Option<String>,
+    /// Número de serie (solo `mkfs`)
+    serial: Option<u32>,
+    /// Corregir los problemas encontrados (solo `fsck`)
+    repair: bool,
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1).collect()) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("fat32-tool: {}", message);
+            eprintln!();
+            print_usage();
+            std::process::exit(EXIT_FAILURE);
+        }
+    };
+
+    let result = match options.command.as_str() {
+        "mkfs" => mkfs(&options),
+        _ => fsck(&options),
+    };
+
+    match result {
+        Ok(code) => std::process::exit(code),
+        Err(message) => {
+            eprintln!("fat32-tool: {}", message);
+            std::process::exit(EXIT_FAILURE);
+        }
+    }
+}
+
+/// Mostrar la ayuda
+fn print_usage() {
+    eprintln!("Uso:");
+    eprintln!("  fat32-tool mkfs <imagen> [--size <tamaño>] [--cluster-size <bytes>] [--label <etiqueta>]");
+    eprintln!("                           [--serial <hex>] [--sector-size <bytes>] [--partition <n>]");
+    eprintln!("  fat32-tool fsck <imagen> [--repair] [--sector-size <bytes>] [--partition <n>]");
+    eprintln!();
+    eprintln!("Los tamaños aceptan los sufijos K, M y G.");
+}
+
+/// Analizar los argumentos de la línea de comandos
+fn parse_args(args: Vec<String>) -> Result<Options, String> {
+    let mut args = args.into_iter();
+    let command = args.next().ok_or("falta el subcomando")?;
+    if command != "mkfs" && command != "fsck" {
+        return Err(format!("subcomando desconocido: {}", command));
+    }
+
+    let mut options = Options {
+        command,
+        image: String::new(),
+        sector_size: 512,
+        partition: None,
+        size: None,
+        cluster_size: 0,
+        label: None,
+        serial: None,
+        repair: false,
+    };
+    let is_mkfs = options.command == "mkfs";
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("falta el valor de {}", name));
+        match arg.as_str() {
+            "--sector-size" => options.sector_size = parse_size(&value(&arg)?)? as u32,
+            "--partition" => options.partition = Some(parse_number(&value(&arg)?)?),
+            "--size" if is_mkfs => options.size = Some(parse_size(&value(&arg)?)?),
+            "--cluster-size" if is_mkfs => options.cluster_size = parse_size(&value(&arg)?)? as u32,
+            "--label" if is_mkfs => options.label = Some(value(&arg)?),
+            "--serial" if is_mkfs => {
+                let serial = value(&arg)?;
+                let digits = serial.trim_start_matches("0x").replace('-', "");
+                options.serial = Some(u32::from_str_radix(&digits, 16).map_err(|_| format!("número de serie inválido: {}", serial))?);
+            }
+            "--repair" | "-r" if !is_mkfs => options.repair = true,
+            _ if arg.starts_with('-') => return Err(format!("opción desconocida: {}", arg)),
+            _ if options.image.is_empty() => options.image = arg,
+            _ => return Err(format!("argumento inesperado: {}", arg)),
+        }
+    }
+
+    if options.image.is_empty() {
+        return Err(String::from("falta la ruta de la imagen"));
+    }
+    Ok(options)
+}
+
+/// Analizar un número decimal
+fn parse_number(text: &str) -> Result<u32, String> {
+    text.parse().map_err(|_| format!("número inválido: {}", text))
+}
+
+/// Analizar un tamaño en bytes con sufijo opcional K, M o G
+fn parse_size(text: &str) -> Result<u64, String> {
+    let (digits, multiplier) = match text.char_indices().last() {
+        Some((i, 'K' | 'k')) => (&text[..i], 1 << 10),
+        Some((i, 'M' | 'm')) => (&text[..i], 1 << 20),
+        Some((i, 'G' | 'g')) => (&text[..i], 1 << 30),
+        _ => (text, 1),
+    };
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|value| value.checked_mul(multiplier))
+        .ok_or(format!("tamaño inválido: {}", text))
+}
+
+/// Registrar la imagen y devolver el dispositivo sobre el que operar
+fn open_volume(options: &Options, read_only: bool) -> Result<u32, String> {
+    let disk_id = image::register_image_file(&options.image, options.sector_size, read_only)
+        .map_err(|e| format!("no se pudo abrir {}: {}", options.image, e))?;
+
+    let number = match options.partition {
+        Some(number) => number,
+        None => return Ok(disk_id),
+    };
+    let manager = storage::get_storage_manager().ok_or("gestor de almacenamiento no inicializado")?;
+    manager
+        .get_devices()
+        .iter()
+        .copied()
+        .filter(|&device_id| device_id != 0)
+        .find(|&device_id| {
+            manager.get_device_info(device_id).is_some_and(|device| {
+                device.parent_device == Some(disk_id) && device.partition.as_ref().is_some_and(|partition| partition.number == number)
+            })
+        })
+        .ok_or(format!("la imagen no tiene partición {}", number))
+}
+
+/// Formatear una imagen como FAT32
+fn mkfs(options: &Options) -> Result<i32, String> {
+    if let Some(size) = options.size {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&options.image)
+            .map_err(|e| format!("no se pudo crear {}: {}", options.image, e))?;
+        file.set_len(size).map_err(|e| format!("no se pudo redimensionar {}: {}", options.image, e))?;
+    }
+
+    let device_id = open_volume(options, false)?;
+    let volume_serial = options.serial.unwrap_or_else(|| {
+        // Como otros formateadores, el número de serie se deriva de la hora actual
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        (now.as_secs() as u32).rotate_left(16) ^ now.subsec_nanos()
+    });
+    let format_options = Fat32FormatOptions {
+        cluster_size: options.cluster_size,
+        label: options.label.as_deref(),
+        volume_serial,
+        ..Fat32FormatOptions::default()
+    };
+
+    let driver = fat32::format_fat32(device_id, &format_options).map_err(String::from)?;
+    if !storage::sync_storage(None) {
+        return Err(String::from("error escribiendo la imagen"));
+    }
+
+    println!("{}: volumen FAT32 creado", options.image);
+    println!("  número de serie:     {:04X}-{:04X}", volume_serial >> 16, volume_serial & 0xFFFF);
+    println!("  tamaño de clúster:   {} bytes", driver.cluster_size);
+    println!("  clústeres:           {}", driver.total_clusters);
+    println!("  sectores por FAT:    {} ({} copias)", driver.sectors_per_fat, driver.boot_sector.fat_count);
+    println!("  clústeres libres:    {}", driver.fs_info.free_count);
+    Ok(EXIT_CLEAN)
+}
+
+/// Verificar (y opcionalmente reparar) una imagen FAT
+fn fsck(options: &Options) -> Result<i32, String> {
+    let device_id = open_volume(options, !options.repair)?;
+    let mut driver = fat32::init_fat32(device_id).map_err(String::from)?;
+    let report = driver.check(options.repair).map_err(String::from)?;
+    if report.repaired && !storage::sync_storage(None) {
+        return Err(String::from("error escribiendo la imagen"));
+    }
+
+    for problem in &report.problems {
+        println!("{}", describe_problem(problem));
+    }
+    println!(
+        "{}: {} archivos, {} directorios, {}/{} clústeres en uso, {} defectuosos",
+        options.image,
+        report.files,
+        report.directories,
+        report.used_clusters,
+        driver.total_clusters,
+        report.bad_clusters
+    );
+
+    if report.is_clean() {
+        println!("El volumen está limpio");
+        Ok(EXIT_CLEAN)
+    } else if report.repaired {
+        println!("{} problemas corregidos", report.problems.len());
+        Ok(EXIT_REPAIRED)
+    } else {
+        println!("{} problemas sin corregir (use --repair)", report.problems.len());
+        Ok(EXIT_UNCORRECTED)
+    }
+}
+
+/// Descripción legible de un problema
+fn describe_problem(problem: &Fat32Problem) -> String {
+    match problem {
+        Fat32Problem::FatCopyMismatch { copy, first_sector } => {
+            format!("La copia {} de la FAT difiere de la activa desde el sector {} de la FAT", copy, first_sector)
+        }
+        Fat32Problem::BadDirectoryEntry { path, reason } => format!("{}: entrada inválida: {}", path, reason),
+        Fat32Problem::BadChain { path, reason } => format!("{}: {}", path, reason),
+        Fat32Problem::SizeMismatch { path, size, clusters } => {
+            format!("{}: el tamaño ({} bytes) no corresponde a la cadena de {} clústeres", path, size, clusters)
+        }
+        Fat32Problem::CrossLink { path, other, cluster } => {
+            format!("{}: comparte el clúster {} con {}", path, cluster, other)
+        }
+        Fat32Problem::LostChain { first_cluster, clusters } => {
+            format!("Cadena perdida de {} clústeres a partir del clúster {}", clusters, first_cluster)
+        }
+        Fat32Problem::FreeCountMismatch { recorded, actual } => {
+            format!("FSInfo indica {} clústeres libres, pero la FAT tiene {}", recorded, actual)
+        }
+    }
+}
@@ -3,6 +3,7 @@
 pub mod shell;
 pub mod services;
 pub mod applications;
+pub mod reactfs;
 
 use anyhow::Result;
 
@@ -1,54 +1,1216 @@
 //! ReactFS Module
 //! Sistema de archivos seguro personalizado
+//!
+//! Formato en disco (versión 1), en bloques de 4 KiB sobre un archivo de imagen:
+//!
+//! - Bloque 0: superbloque en claro con los parámetros del volumen, la sal y
+//!   las iteraciones de PBKDF2 y la clave maestra envuelta con la clave
+//!   derivada de la contraseña. La cabecera del superbloque es el dato
+//!   asociado (AAD) de esa envoltura, así que cualquier cambio en ella
+//!   impide desbloquear el volumen.
+//! - Bloques de mapa de bits: bloques usados/libres, cifrados.
+//! - Inodos: un bloque cifrado por archivo o directorio con su tamaño, su
+//!   clave propia envuelta por la clave maestra y los punteros a sus
+//!   bloques de datos (los que no caben siguen en bloques de mapa encadenados).
+//! - Datos: cada bloque guarda un trozo del archivo sellado con AES-256-GCM
+//!   y la clave del archivo. Los directorios son archivos cuyo contenido es
+//!   la lista de entradas, de modo que los nombres también van cifrados.
+//!
+//! Todo bloque cifrado lleva un nonce aleatorio y una etiqueta de
+//! autenticación, y su AAD lo liga al volumen y a su posición (número de
+//! bloque, o inodo e índice del trozo), lo que impide mover o intercambiar
+//! bloques sin que se detecte. El formato no tiene journal ni protege
+//! contra la reposición de versiones antiguas de un bloque.
 
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::os::raw::c_void;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use aes_gcm::aead::{Aead, AeadInPlace, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce, Tag};
+use anyhow::{anyhow, bail, Result};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use zeroize::Zeroizing;
 
 /// Handle de ReactFS
 pub type ReactFSHandle = *mut c_void;
 
+/// Firma del superbloque
+pub const REACTFS_MAGIC: [u8; 8] = *b"REACTFS\0";
+/// Versión del formato en disco
+pub const REACTFS_VERSION: u32 = 1;
+/// Tamaño de bloque
+pub const REACTFS_BLOCK_SIZE: usize = 4096;
+/// Bytes de datos útiles en un bloque cifrado (bloque menos nonce y etiqueta)
+pub const REACTFS_CHUNK_SIZE: usize = REACTFS_BLOCK_SIZE - NONCE_SIZE - TAG_SIZE;
+/// Longitud máxima de un nombre en bytes
+pub const REACTFS_NAME_MAX: usize = 255;
+/// Iteraciones de PBKDF2 por defecto
+pub const REACTFS_DEFAULT_KDF_ITERATIONS: u32 = 600_000;
+
+/// KDF PBKDF2-HMAC-SHA256
+const KDF_PBKDF2_SHA256: u32 = 1;
+/// Tamaño de las claves AES-256
+const KEY_SIZE: usize = 32;
+/// Tamaño del nonce de AES-GCM
+const NONCE_SIZE: usize = 12;
+/// Tamaño de la etiqueta de AES-GCM
+const TAG_SIZE: usize = 16;
+/// Tamaño de una clave envuelta (nonce, clave cifrada y etiqueta)
+const WRAPPED_KEY_SIZE: usize = NONCE_SIZE + KEY_SIZE + TAG_SIZE;
+/// Tamaño de la sal del KDF
+const SALT_SIZE: usize = 32;
+
+/// Bytes de la cabecera del superbloque protegidos como AAD de la clave maestra
+const SUPERBLOCK_HEADER_SIZE: usize = 112;
+/// Bloques que cubre cada bloque del mapa de bits
+const BITS_PER_BITMAP_BLOCK: u64 = REACTFS_CHUNK_SIZE as u64 * 8;
+/// Cabecera de un inodo antes de los punteros
+const INODE_HEADER_SIZE: usize = 96;
+/// Punteros a datos dentro del propio inodo
+const INODE_POINTERS: usize = (REACTFS_CHUNK_SIZE - INODE_HEADER_SIZE) / 8;
+/// Punteros a datos en cada bloque de mapa
+const MAP_POINTERS: usize = (REACTFS_CHUNK_SIZE - 8) / 8;
+
+/// Tipos de AAD: cada clase de bloque cifrado usa un dominio distinto
+const AAD_BITMAP: u8 = 1;
+const AAD_INODE: u8 = 2;
+const AAD_MAP: u8 = 3;
+const AAD_DATA: u8 = 4;
+const AAD_FILE_KEY: u8 = 5;
+
+/// Tipo de un nodo de ReactFS
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReactFSNodeKind {
+    File,
+    Directory,
+}
+
+impl ReactFSNodeKind {
+    fn to_byte(self) -> u8 {
+        match self {
+            ReactFSNodeKind::File => 1,
+            ReactFSNodeKind::Directory => 2,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Result<Self> {
+        match byte {
+            1 => Ok(ReactFSNodeKind::File),
+            2 => Ok(ReactFSNodeKind::Directory),
+            _ => bail!("Invalid ReactFS inode type {}", byte),
+        }
+    }
+}
+
+/// Metadatos de un archivo o directorio
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReactFSMetadata {
+    /// Tipo de nodo
+    pub kind: ReactFSNodeKind,
+    /// Tamaño en bytes
+    pub size: u64,
+    /// Última modificación (segundos desde la época Unix)
+    pub modified: u64,
+}
+
+/// Entrada de un listado de directorio
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReactFSDirEntry {
+    /// Nombre de la entrada
+    pub name: String,
+    /// Metadatos de la entrada
+    pub metadata: ReactFSMetadata,
+}
+
+/// Opciones de formateo de un volumen
+#[derive(Debug, Clone, Copy)]
+pub struct ReactFSFormatOptions {
+    /// Iteraciones de PBKDF2 para derivar la clave de la contraseña
+    pub kdf_iterations: u32,
+}
+
+impl Default for ReactFSFormatOptions {
+    fn default() -> Self {
+        Self {
+            kdf_iterations: REACTFS_DEFAULT_KDF_ITERATIONS,
+        }
+    }
+}
+
+/// Superbloque de un volumen ReactFS
+#[derive(Debug, Clone)]
+struct Superblock {
+    /// Número de bloques del volumen
+    block_count: u64,
+    /// Identificador único del volumen
+    uuid: [u8; 16],
+    /// Algoritmo de derivación de la clave
+    kdf_algorithm: u32,
+    /// Iteraciones del KDF
+    kdf_iterations: u32,
+    /// Sal del KDF
+    kdf_salt: [u8; SALT_SIZE],
+    /// Primer bloque del mapa de bits
+    bitmap_start: u64,
+    /// Bloques del mapa de bits
+    bitmap_blocks: u64,
+    /// Bloque del inodo raíz
+    root_inode: u64,
+    /// Clave maestra envuelta con la clave derivada de la contraseña
+    wrapped_master_key: [u8; WRAPPED_KEY_SIZE],
+}
+
+impl Superblock {
+    /// Serializar la cabecera (la parte autenticada como AAD)
+    fn header(&self) -> [u8; SUPERBLOCK_HEADER_SIZE] {
+        let mut header = [0u8; SUPERBLOCK_HEADER_SIZE];
+        header[0..8].copy_from_slice(&REACTFS_MAGIC);
+        header[8..12].copy_from_slice(&REACTFS_VERSION.to_le_bytes());
+        header[12..16].copy_from_slice(&(REACTFS_BLOCK_SIZE as u32).to_le_bytes());
+        header[16..24].copy_from_slice(&self.block_count.to_le_bytes());
+        header[24..40].copy_from_slice(&self.uuid);
+        header[40..44].copy_from_slice(&self.kdf_algorithm.to_le_bytes());
+        header[44..48].copy_from_slice(&self.kdf_iterations.to_le_bytes());
+        header[48..80].copy_from_slice(&self.kdf_salt);
+        header[80..88].copy_from_slice(&self.bitmap_start.to_le_bytes());
+        header[88..96].copy_from_slice(&self.bitmap_blocks.to_le_bytes());
+        header[96..104].copy_from_slice(&self.root_inode.to_le_bytes());
+        header
+    }
+
+    /// Serializar el superbloque completo
+    fn to_block(&self) -> Vec<u8> {
+        let mut block = vec![0u8; REACTFS_BLOCK_SIZE];
+        block[..SUPERBLOCK_HEADER_SIZE].copy_from_slice(&self.header());
+        block[SUPERBLOCK_HEADER_SIZE..SUPERBLOCK_HEADER_SIZE + WRAPPED_KEY_SIZE].copy_from_slice(&self.wrapped_master_key);
+        block
+    }
+
+    /// Decodificar y validar un superbloque
+    fn from_block(block: &[u8]) -> Result<Self> {
+        if block[0..8] != REACTFS_MAGIC {
+            bail!("Not a ReactFS volume");
+        }
+        if read_u32(block, 8) != REACTFS_VERSION {
+            bail!("Unsupported ReactFS version {}", read_u32(block, 8));
+        }
+        if read_u32(block, 12) as usize != REACTFS_BLOCK_SIZE {
+            bail!("Unsupported ReactFS block size {}", read_u32(block, 12));
+        }
+
+        let mut superblock = Superblock {
+            block_count: read_u64(block, 16),
+            uuid: [0; 16],
+            kdf_algorithm: read_u32(block, 40),
+            kdf_iterations: read_u32(block, 44),
+            kdf_salt: [0; SALT_SIZE],
+            bitmap_start: read_u64(block, 80),
+            bitmap_blocks: read_u64(block, 88),
+            root_inode: read_u64(block, 96),
+            wrapped_master_key: [0; WRAPPED_KEY_SIZE],
+        };
+        superblock.uuid.copy_from_slice(&block[24..40]);
+        superblock.kdf_salt.copy_from_slice(&block[48..80]);
+        superblock
+            .wrapped_master_key
+            .copy_from_slice(&block[SUPERBLOCK_HEADER_SIZE..SUPERBLOCK_HEADER_SIZE + WRAPPED_KEY_SIZE]);
+
+        if superblock.kdf_algorithm != KDF_PBKDF2_SHA256 || superblock.kdf_iterations == 0 {
+            bail!("Unsupported ReactFS key derivation");
+        }
+        if superblock.bitmap_start != 1
+            || superblock.bitmap_blocks != superblock.block_count.div_ceil(BITS_PER_BITMAP_BLOCK)
+            || superblock.root_inode != superblock.bitmap_start + superblock.bitmap_blocks
+            || superblock.root_inode >= superblock.block_count
+        {
+            bail!("Corrupted ReactFS superblock");
+        }
+        Ok(superblock)
+    }
+}
+
+/// Inodo cargado en memoria
+#[derive(Debug, Clone)]
+struct Inode {
+    /// Bloque donde se guarda el inodo (también es su identificador)
+    block: u64,
+    /// Tipo de nodo
+    kind: ReactFSNodeKind,
+    /// Tamaño en bytes
+    size: u64,
+    /// Última modificación
+    modified: u64,
+    /// Clave del archivo envuelta con la clave maestra
+    wrapped_key: [u8; WRAPPED_KEY_SIZE],
+    /// Bloque de cada trozo del archivo (0 si el trozo es un hueco)
+    chunks: Vec<u64>,
+    /// Bloques de mapa que continúan la lista de punteros
+    map_blocks: Vec<u64>,
+}
+
+impl Inode {
+    /// Metadatos públicos del inodo
+    fn metadata(&self) -> ReactFSMetadata {
+        ReactFSMetadata {
+            kind: self.kind,
+            size: self.size,
+            modified: self.modified,
+        }
+    }
+}
+
+/// Volumen ReactFS montado
+pub struct ReactFSVolume {
+    /// Archivo de imagen
+    file: File,
+    /// Superbloque
+    superblock: Superblock,
+    /// Clave maestra (necesaria para cambiar la contraseña)
+    master_key: Zeroizing<[u8; KEY_SIZE]>,
+    /// Cifrador de metadatos (mapa de bits, inodos y mapas)
+    metadata_cipher: Aes256Gcm,
+    /// Cifrador de las claves de archivo
+    wrapping_cipher: Aes256Gcm,
+    /// Mapa de bits de bloques en uso
+    bitmap: Vec<u8>,
+    /// Bloques del mapa de bits modificados desde la última escritura
+    dirty_bitmap: Vec<bool>,
+    /// Pista para la próxima búsqueda de bloque libre
+    next_free: u64,
+}
+
+impl ReactFSVolume {
+    /// Crear un volumen nuevo en `path` de `size` bytes protegido con `passphrase`
+    pub fn format<P: AsRef<Path>>(path: P, size: u64, passphrase: &str, options: &ReactFSFormatOptions) -> Result<Self> {
+        let block_count = size / REACTFS_BLOCK_SIZE as u64;
+        let bitmap_blocks = block_count.div_ceil(BITS_PER_BITMAP_BLOCK);
+        let root_inode = 1 + bitmap_blocks;
+        if block_count < root_inode + 2 {
+            bail!("ReactFS volume too small");
+        }
+        if options.kdf_iterations == 0 {
+            bail!("Invalid ReactFS key derivation parameters");
+        }
+
+        let mut superblock = Superblock {
+            block_count,
+            uuid: [0; 16],
+            kdf_algorithm: KDF_PBKDF2_SHA256,
+            kdf_iterations: options.kdf_iterations,
+            kdf_salt: [0; SALT_SIZE],
+            bitmap_start: 1,
+            bitmap_blocks,
+            root_inode,
+            wrapped_master_key: [0; WRAPPED_KEY_SIZE],
+        };
+        random_bytes(&mut superblock.uuid)?;
+        random_bytes(&mut superblock.kdf_salt)?;
+
+        let mut master_key = Zeroizing::new([0u8; KEY_SIZE]);
+        random_bytes(master_key.as_mut())?;
+        superblock.wrapped_master_key = wrap_master_key(&superblock, passphrase, &master_key)?;
+
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(path)?;
+        file.set_len(block_count * REACTFS_BLOCK_SIZE as u64)?;
+
+        let mut volume = Self::with_keys(file, superblock, master_key)?;
+        volume.write_block(0, &volume.superblock.to_block())?;
+        for block in 0..=root_inode {
+            volume.set_allocated(block, true);
+        }
+
+        let root = volume.new_inode(root_inode, ReactFSNodeKind::Directory)?;
+        volume.save_inode(&root)?;
+        volume.sync()?;
+        Ok(volume)
+    }
+
+    /// Montar un volumen existente desbloqueándolo con `passphrase`
+    pub fn open<P: AsRef<Path>>(path: P, passphrase: &str) -> Result<Self> {
+        let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+        let mut block = vec![0u8; REACTFS_BLOCK_SIZE];
+        file.read_exact(&mut block)?;
+        let superblock = Superblock::from_block(&block)?;
+        if file.metadata()?.len() < superblock.block_count * REACTFS_BLOCK_SIZE as u64 {
+            bail!("ReactFS image is truncated");
+        }
+
+        let master_key = unwrap_master_key(&superblock, passphrase)?;
+        let mut volume = Self::with_keys(file, superblock, master_key)?;
+        for index in 0..volume.superblock.bitmap_blocks {
+            let block_number = volume.superblock.bitmap_start + index;
+            let aad = volume.aad(AAD_BITMAP, block_number, 0);
+            let raw = volume.read_block(block_number)?;
+            let payload = open_block(&volume.metadata_cipher, &aad, &raw)?;
+            let start = index as usize * REACTFS_CHUNK_SIZE;
+            let end = (start + REACTFS_CHUNK_SIZE).min(volume.bitmap.len());
+            volume.bitmap[start..end].copy_from_slice(&payload[..end - start]);
+        }
+        volume.dirty_bitmap.fill(false);
+        Ok(volume)
+    }
+
+    /// Construir el volumen en memoria a partir de la clave maestra
+    fn with_keys(file: File, superblock: Superblock, master_key: Zeroizing<[u8; KEY_SIZE]>) -> Result<Self> {
+        let metadata_cipher = derive_cipher(&master_key, &superblock.uuid, b"reactfs metadata")?;
+        let wrapping_cipher = derive_cipher(&master_key, &superblock.uuid, b"reactfs file keys")?;
+        let bitmap_len = superblock.block_count.div_ceil(8) as usize;
+        let bitmap_blocks = superblock.bitmap_blocks as usize;
+        Ok(Self {
+            file,
+            superblock,
+            master_key,
+            metadata_cipher,
+            wrapping_cipher,
+            bitmap: vec![0; bitmap_len],
+            dirty_bitmap: vec![false; bitmap_blocks],
+            next_free: 0,
+        })
+    }
+
+    /// Cambiar la contraseña del volumen
+    ///
+    /// Solo se vuelve a envolver la clave maestra con una sal nueva; los datos no cambian.
+    pub fn change_passphrase(&mut self, new_passphrase: &str) -> Result<()> {
+        random_bytes(&mut self.superblock.kdf_salt)?;
+        self.superblock.wrapped_master_key = wrap_master_key(&self.superblock, new_passphrase, &self.master_key)?;
+        self.write_block(0, &self.superblock.to_block())?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+
+    /// Escribir los bloques del mapa de bits pendientes y vaciar la caché del host
+    pub fn sync(&mut self) -> Result<()> {
+        for index in 0..self.dirty_bitmap.len() {
+            if !self.dirty_bitmap[index] {
+                continue;
+            }
+            let start = index * REACTFS_CHUNK_SIZE;
+            let end = (start + REACTFS_CHUNK_SIZE).min(self.bitmap.len());
+            let mut payload = vec![0u8; REACTFS_CHUNK_SIZE];
+            payload[..end - start].copy_from_slice(&self.bitmap[start..end]);
+
+            let block_number = self.superblock.bitmap_start + index as u64;
+            let aad = self.aad(AAD_BITMAP, block_number, 0);
+            let block = seal_block(&self.metadata_cipher, &aad, &payload)?;
+            self.write_block(block_number, &block)?;
+            self.dirty_bitmap[index] = false;
+        }
+        self.file.sync_data()?;
+        Ok(())
+    }
+
+    /// Número total de bloques
+    pub fn total_blocks(&self) -> u64 {
+        self.superblock.block_count
+    }
+
+    /// Número de bloques libres
+    pub fn free_blocks(&self) -> u64 {
+        (0..self.superblock.block_count).filter(|&block| !self.is_allocated(block)).count() as u64
+    }
+
+    /// Crear un archivo vacío
+    pub fn create_file(&mut self, path: &str) -> Result<()> {
+        self.create_node(path, ReactFSNodeKind::File)
+    }
+
+    /// Crear un directorio vacío
+    pub fn create_dir(&mut self, path: &str) -> Result<()> {
+        self.create_node(path, ReactFSNodeKind::Directory)
+    }
+
+    /// Leer el contenido completo de un archivo
+    pub fn read_file(&mut self, path: &str) -> Result<Vec<u8>> {
+        let inode = self.lookup(path)?;
+        if inode.kind != ReactFSNodeKind::File {
+            bail!("{} is a directory", path);
+        }
+        let mut data = vec![0u8; inode.size as usize];
+        self.read_inode(&inode, 0, &mut data)?;
+        Ok(data)
+    }
+
+    /// Leer a partir de `offset`; devuelve los bytes leídos (0 al final del archivo)
+    pub fn read_at(&mut self, path: &str, offset: u64, buffer: &mut [u8]) -> Result<usize> {
+        let inode = self.lookup(path)?;
+        if inode.kind != ReactFSNodeKind::File {
+            bail!("{} is a directory", path);
+        }
+        self.read_inode(&inode, offset, buffer)
+    }
+
+    /// Reemplazar el contenido de un archivo, creándolo si no existe
+    pub fn write_file(&mut self, path: &str, data: &[u8]) -> Result<()> {
+        let mut inode = match self.lookup(path) {
+            Ok(inode) => inode,
+            Err(_) => {
+                self.create_file(path)?;
+                self.lookup(path)?
+            }
+        };
+        if inode.kind != ReactFSNodeKind::File {
+            bail!("{} is a directory", path);
+        }
+        self.truncate_inode(&mut inode, 0)?;
+        self.write_inode(&mut inode, 0, data)?;
+        self.sync()
+    }
+
+    /// Escribir en una posición de un archivo existente, extendiéndolo si hace falta
+    ///
+    /// Los trozos que quedan entre el final anterior y `offset` son huecos y se leen como ceros.
+    pub fn write_at(&mut self, path: &str, offset: u64, data: &[u8]) -> Result<usize> {
+        let mut inode = self.lookup(path)?;
+        if inode.kind != ReactFSNodeKind::File {
+            bail!("{} is a directory", path);
+        }
+        self.write_inode(&mut inode, offset, data)?;
+        self.sync()?;
+        Ok(data.len())
+    }
+
+    /// Cambiar el tamaño de un archivo
+    pub fn truncate(&mut self, path: &str, size: u64) -> Result<()> {
+        let mut inode = self.lookup(path)?;
+        if inode.kind != ReactFSNodeKind::File {
+            bail!("{} is a directory", path);
+        }
+        self.truncate_inode(&mut inode, size)?;
+        self.sync()
+    }
+
+    /// Eliminar un archivo o un directorio vacío
+    pub fn remove(&mut self, path: &str) -> Result<()> {
+        let (parent_path, name) = split_parent(path)?;
+        let mut parent = self.lookup(parent_path)?;
+        let mut entries = self.read_directory(&parent)?;
+        let position = entries
+            .iter()
+            .position(|(entry_name, _)| entry_name == name)
+            .ok_or_else(|| anyhow!("{} not found", path))?;
+
+        let mut inode = self.load_inode(entries[position].1)?;
+        if inode.kind == ReactFSNodeKind::Directory && inode.size != 0 {
+            bail!("Directory {} is not empty", path);
+        }
+
+        entries.remove(position);
+        self.write_directory(&mut parent, &entries)?;
+        self.truncate_inode(&mut inode, 0)?;
+        self.set_allocated(inode.block, false);
+        self.sync()
+    }
+
+    /// Listar un directorio
+    pub fn list_dir(&mut self, path: &str) -> Result<Vec<ReactFSDirEntry>> {
+        let directory = self.lookup(path)?;
+        if directory.kind != ReactFSNodeKind::Directory {
+            bail!("{} is not a directory", path);
+        }
+        let mut listing = Vec::new();
+        for (name, block) in self.read_directory(&directory)? {
+            let metadata = self.load_inode(block)?.metadata();
+            listing.push(ReactFSDirEntry { name, metadata });
+        }
+        Ok(listing)
+    }
+
+    /// Obtener los metadatos de una ruta
+    pub fn metadata(&mut self, path: &str) -> Result<ReactFSMetadata> {
+        Ok(self.lookup(path)?.metadata())
+    }
+
+    /// Crear un archivo o directorio en su directorio padre
+    fn create_node(&mut self, path: &str, kind: ReactFSNodeKind) -> Result<()> {
+        let (parent_path, name) = split_parent(path)?;
+        validate_name(name)?;
+        let mut parent = self.lookup(parent_path)?;
+        if parent.kind != ReactFSNodeKind::Directory {
+            bail!("{} is not a directory", parent_path);
+        }
+
+        let mut entries = self.read_directory(&parent)?;
+        if entries.iter().any(|(entry_name, _)| entry_name == name) {
+            bail!("{} already exists", path);
+        }
+
+        let block = self.allocate_block()?;
+        let inode = self.new_inode(block, kind)?;
+        self.save_inode(&inode)?;
+        entries.push((name.to_string(), block));
+        self.write_directory(&mut parent, &entries)?;
+        self.sync()
+    }
+
+    /// Resolver una ruta absoluta
+    fn lookup(&mut self, path: &str) -> Result<Inode> {
+        let mut inode = self.load_inode(self.superblock.root_inode)?;
+        for component in path.split('/').filter(|component| !component.is_empty()) {
+            if inode.kind != ReactFSNodeKind::Directory {
+                bail!("{} not found", path);
+            }
+            let block = self
+                .read_directory(&inode)?
+                .into_iter()
+                .find(|(name, _)| name == component)
+                .map(|(_, block)| block)
+                .ok_or_else(|| anyhow!("{} not found", path))?;
+            inode = self.load_inode(block)?;
+        }
+        Ok(inode)
+    }
+
+    /// Leer las entradas (nombre, bloque del inodo) de un directorio
+    fn read_directory(&mut self, directory: &Inode) -> Result<Vec<(String, u64)>> {
+        let mut data = vec![0u8; directory.size as usize];
+        self.read_inode(directory, 0, &mut data)?;
+
+        let mut entries = Vec::new();
+        let mut offset = 0;
+        while offset < data.len() {
+            if offset + 10 > data.len() {
+                bail!("Corrupted ReactFS directory");
+            }
+            let block = read_u64(&data, offset);
+            let name_len = u16::from_le_bytes([data[offset + 8], data[offset + 9]]) as usize;
+            let name_end = offset + 10 + name_len;
+            if name_end > data.len() {
+                bail!("Corrupted ReactFS directory");
+            }
+            let name = String::from_utf8(data[offset + 10..name_end].to_vec()).map_err(|_| anyhow!("Corrupted ReactFS directory"))?;
+            entries.push((name, block));
+            offset = name_end;
+        }
+        Ok(entries)
+    }
+
+    /// Reescribir el contenido de un directorio
+    fn write_directory(&mut self, directory: &mut Inode, entries: &[(String, u64)]) -> Result<()> {
+        let mut data = Vec::new();
+        for (name, block) in entries {
+            data.extend_from_slice(&block.to_le_bytes());
+            data.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            data.extend_from_slice(name.as_bytes());
+        }
+        self.truncate_inode(directory, 0)?;
+        self.write_inode(directory, 0, &data)
+    }
+
+    /// Crear un inodo vacío con una clave de archivo nueva
+    fn new_inode(&self, block: u64, kind: ReactFSNodeKind) -> Result<Inode> {
+        let mut file_key = Zeroizing::new([0u8; KEY_SIZE]);
+        random_bytes(file_key.as_mut())?;
+        let aad = self.aad(AAD_FILE_KEY, block, 0);
+        let wrapped_key = wrap_key(&self.wrapping_cipher, &aad, &file_key)?;
+        Ok(Inode {
+            block,
+            kind,
+            size: 0,
+            modified: current_time(),
+            wrapped_key,
+            chunks: Vec::new(),
+            map_blocks: Vec::new(),
+        })
+    }
+
+    /// Leer y descifrar un inodo con su lista de punteros
+    fn load_inode(&mut self, block: u64) -> Result<Inode> {
+        if block == 0 || block >= self.superblock.block_count {
+            bail!("Invalid ReactFS inode {}", block);
+        }
+        let aad = self.aad(AAD_INODE, block, 0);
+        let raw = self.read_block(block)?;
+        let payload = open_block(&self.metadata_cipher, &aad, &raw)?;
+
+        let mut inode = Inode {
+            block,
+            kind: ReactFSNodeKind::from_byte(payload[0])?,
+            size: read_u64(&payload, 8),
+            modified: read_u64(&payload, 16),
+            wrapped_key: [0; WRAPPED_KEY_SIZE],
+            chunks: Vec::new(),
+            map_blocks: Vec::new(),
+        };
+        inode.wrapped_key.copy_from_slice(&payload[24..24 + WRAPPED_KEY_SIZE]);
+
+        let chunk_count = inode.size.div_ceil(REACTFS_CHUNK_SIZE as u64) as usize;
+        let inline = chunk_count.min(INODE_POINTERS);
+        inode.chunks.extend((0..inline).map(|i| read_u64(&payload, INODE_HEADER_SIZE + i * 8)));
+
+        let mut next_map = read_u64(&payload, 88);
+        while inode.chunks.len() < chunk_count {
+            if next_map == 0 || next_map >= self.superblock.block_count || inode.map_blocks.contains(&next_map) {
+                bail!("Corrupted ReactFS block map");
+            }
+            let aad = self.aad(AAD_MAP, next_map, block);
+            let raw = self.read_block(next_map)?;
+            let map = open_block(&self.metadata_cipher, &aad, &raw)?;
+            inode.map_blocks.push(next_map);
+            let count = (chunk_count - inode.chunks.len()).min(MAP_POINTERS);
+            inode.chunks.extend((0..count).map(|i| read_u64(&map, 8 + i * 8)));
+            next_map = read_u64(&map, 0);
+        }
+        Ok(inode)
+    }
+
+    /// Cifrar y escribir un inodo, ajustando su cadena de bloques de mapa
+    fn save_inode(&mut self, inode: &Inode) -> Result<()> {
+        let overflow = inode.chunks.len().saturating_sub(INODE_POINTERS);
+        let needed_maps = overflow.div_ceil(MAP_POINTERS);
+
+        let mut map_blocks = inode.map_blocks.clone();
+        while map_blocks.len() > needed_maps {
+            let block = map_blocks.pop().unwrap_or_default();
+            self.set_allocated(block, false);
+        }
+        while map_blocks.len() < needed_maps {
+            map_blocks.push(self.allocate_block()?);
+        }
+
+        for (index, &map_block) in map_blocks.iter().enumerate() {
+            let mut map = vec![0u8; REACTFS_CHUNK_SIZE];
+            let next = map_blocks.get(index + 1).copied().unwrap_or(0);
+            map[0..8].copy_from_slice(&next.to_le_bytes());
+            let start = INODE_POINTERS + index * MAP_POINTERS;
+            for (i, pointer) in inode.chunks.iter().skip(start).take(MAP_POINTERS).enumerate() {
+                map[8 + i * 8..16 + i * 8].copy_from_slice(&pointer.to_le_bytes());
+            }
+            let aad = self.aad(AAD_MAP, map_block, inode.block);
+            let block = seal_block(&self.metadata_cipher, &aad, &map)?;
+            self.write_block(map_block, &block)?;
+        }
+
+        let mut payload = vec![0u8; REACTFS_CHUNK_SIZE];
+        payload[0] = inode.kind.to_byte();
+        payload[8..16].copy_from_slice(&inode.size.to_le_bytes());
+        payload[16..24].copy_from_slice(&inode.modified.to_le_bytes());
+        payload[24..24 + WRAPPED_KEY_SIZE].copy_from_slice(&inode.wrapped_key);
+        payload[88..96].copy_from_slice(&map_blocks.first().copied().unwrap_or(0).to_le_bytes());
+        for (i, pointer) in inode.chunks.iter().take(INODE_POINTERS).enumerate() {
+            let offset = INODE_HEADER_SIZE + i * 8;
+            payload[offset..offset + 8].copy_from_slice(&pointer.to_le_bytes());
+        }
+
+        let aad = self.aad(AAD_INODE, inode.block, 0);
+        let block = seal_block(&self.metadata_cipher, &aad, &payload)?;
+        self.write_block(inode.block, &block)
+    }
+
+    /// Descifrar la clave de un archivo
+    fn file_cipher(&self, inode: &Inode) -> Result<Aes256Gcm> {
+        let aad = self.aad(AAD_FILE_KEY, inode.block, 0);
+        let file_key = unwrap_key(&self.wrapping_cipher, &aad, &inode.wrapped_key)?;
+        Aes256Gcm::new_from_slice(file_key.as_ref()).map_err(|_| anyhow!("Invalid ReactFS file key"))
+    }
+
+    /// Leer y descifrar un trozo de archivo (los huecos se leen como ceros)
+    fn read_chunk(&mut self, cipher: &Aes256Gcm, inode: &Inode, index: usize) -> Result<Vec<u8>> {
+        match inode.chunks.get(index).copied().unwrap_or(0) {
+            0 => Ok(vec![0u8; REACTFS_CHUNK_SIZE]),
+            block => {
+                let aad = self.aad(AAD_DATA, inode.block, index as u64);
+                open_block(cipher, &aad, &self.read_block(block)?)
+            }
+        }
+    }
+
+    /// Cifrar y escribir un trozo de archivo, reservando su bloque si es un hueco
+    fn write_chunk(&mut self, cipher: &Aes256Gcm, inode: &mut Inode, index: usize, payload: &[u8]) -> Result<()> {
+        if inode.chunks.len() <= index {
+            inode.chunks.resize(index + 1, 0);
+        }
+        if inode.chunks[index] == 0 {
+            inode.chunks[index] = self.allocate_block()?;
+        }
+        let aad = self.aad(AAD_DATA, inode.block, index as u64);
+        let block = seal_block(cipher, &aad, payload)?;
+        self.write_block(inode.chunks[index], &block)
+    }
+
+    /// Leer datos de un inodo a partir de `offset`
+    fn read_inode(&mut self, inode: &Inode, offset: u64, buffer: &mut [u8]) -> Result<usize> {
+        if offset >= inode.size {
+            return Ok(0);
+        }
+        let length = buffer.len().min((inode.size - offset) as usize);
+        let cipher = self.file_cipher(inode)?;
+
+        let mut done = 0;
+        while done < length {
+            let position = offset + done as u64;
+            let index = (position / REACTFS_CHUNK_SIZE as u64) as usize;
+            let within = (position % REACTFS_CHUNK_SIZE as u64) as usize;
+            let count = (REACTFS_CHUNK_SIZE - within).min(length - done);
+            let chunk = self.read_chunk(&cipher, inode, index)?;
+            buffer[done..done + count].copy_from_slice(&chunk[within..within + count]);
+            done += count;
+        }
+        Ok(length)
+    }
+
+    /// Escribir datos en un inodo a partir de `offset` y guardar el inodo
+    fn write_inode(&mut self, inode: &mut Inode, offset: u64, data: &[u8]) -> Result<()> {
+        let end = offset.checked_add(data.len() as u64).ok_or_else(|| anyhow!("File too large"))?;
+        let cipher = self.file_cipher(inode)?;
+
+        let mut done = 0;
+        while done < data.len() {
+            let position = offset + done as u64;
+            let index = (position / REACTFS_CHUNK_SIZE as u64) as usize;
+            let within = (position % REACTFS_CHUNK_SIZE as u64) as usize;
+            let count = (REACTFS_CHUNK_SIZE - within).min(data.len() - done);
+
+            // Un trozo completo se reescribe sin leerlo; uno parcial conserva el resto
+            let mut chunk = if count == REACTFS_CHUNK_SIZE {
+                vec![0u8; REACTFS_CHUNK_SIZE]
+            } else {
+                self.read_chunk(&cipher, inode, index)?
+            };
+            chunk[within..within + count].copy_from_slice(&data[done..done + count]);
+            self.write_chunk(&cipher, inode, index, &chunk)?;
+            done += count;
+        }
+
+        inode.size = inode.size.max(end);
+        inode.chunks.resize(inode.size.div_ceil(REACTFS_CHUNK_SIZE as u64) as usize, 0);
+        inode.modified = current_time();
+        self.save_inode(inode)
+    }
+
+    /// Cambiar el tamaño de un inodo liberando los trozos sobrantes
+    fn truncate_inode(&mut self, inode: &mut Inode, size: u64) -> Result<()> {
+        if size < inode.size {
+            let keep = size.div_ceil(REACTFS_CHUNK_SIZE as u64) as usize;
+            for &block in &inode.chunks[keep..] {
+                if block != 0 {
+                    self.set_allocated(block, false);
+                }
+            }
+            inode.chunks.truncate(keep);
+
+            // Borrar la cola del último trozo para que no reaparezca al extender
+            let within = (size % REACTFS_CHUNK_SIZE as u64) as usize;
+            if within != 0 && inode.chunks[keep - 1] != 0 {
+                let cipher = self.file_cipher(inode)?;
+                let mut chunk = self.read_chunk(&cipher, inode, keep - 1)?;
+                chunk[within..].fill(0);
+                self.write_chunk(&cipher, inode, keep - 1, &chunk)?;
+            }
+        }
+
+        inode.size = size;
+        inode.chunks.resize(size.div_ceil(REACTFS_CHUNK_SIZE as u64) as usize, 0);
+        inode.modified = current_time();
+        self.save_inode(inode)
+    }
+
+    /// Dato asociado de un bloque cifrado: volumen, dominio y posición
+    fn aad(&self, kind: u8, a: u64, b: u64) -> [u8; 33] {
+        let mut aad = [0u8; 33];
+        aad[0..16].copy_from_slice(&self.superblock.uuid);
+        aad[16] = kind;
+        aad[17..25].copy_from_slice(&a.to_le_bytes());
+        aad[25..33].copy_from_slice(&b.to_le_bytes());
+        aad
+    }
+
+    /// Verificar si un bloque está en uso
+    fn is_allocated(&self, block: u64) -> bool {
+        self.bitmap[(block / 8) as usize] & (1 << (block % 8)) != 0
+    }
+
+    /// Marcar un bloque como usado o libre
+    fn set_allocated(&mut self, block: u64, allocated: bool) {
+        let byte = (block / 8) as usize;
+        if allocated {
+            self.bitmap[byte] |= 1 << (block % 8);
+        } else {
+            self.bitmap[byte] &= !(1 << (block % 8));
+            self.next_free = self.next_free.min(block);
+        }
+        self.dirty_bitmap[byte / REACTFS_CHUNK_SIZE] = true;
+    }
+
+    /// Reservar un bloque libre
+    fn allocate_block(&mut self) -> Result<u64> {
+        let block = (self.next_free..self.superblock.block_count)
+            .find(|&block| !self.is_allocated(block))
+            .ok_or_else(|| anyhow!("ReactFS volume is full"))?;
+        self.set_allocated(block, true);
+        self.next_free = block + 1;
+        Ok(block)
+    }
+
+    /// Leer un bloque de la imagen
+    fn read_block(&mut self, block: u64) -> Result<Vec<u8>> {
+        let mut buffer = vec![0u8; REACTFS_BLOCK_SIZE];
+        self.file.seek(SeekFrom::Start(block * REACTFS_BLOCK_SIZE as u64))?;
+        self.file.read_exact(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Escribir un bloque de la imagen
+    fn write_block(&mut self, block: u64, data: &[u8]) -> Result<()> {
+        self.file.seek(SeekFrom::Start(block * REACTFS_BLOCK_SIZE as u64))?;
+        self.file.write_all(data)?;
+        Ok(())
+    }
+}
+
+/// Leer un u32 little-endian
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+/// Leer un u64 little-endian
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+/// Segundos desde la época Unix
+fn current_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs())
+}
+
+/// Rellenar un buffer con bytes aleatorios del sistema
+fn random_bytes(buffer: &mut [u8]) -> Result<()> {
+    getrandom::getrandom(buffer).map_err(|e| anyhow!("Random number generator failed: {}", e))
+}
+
+/// Separar una ruta en directorio padre y nombre
+fn split_parent(path: &str) -> Result<(&str, &str)> {
+    let trimmed = path.trim_end_matches('/');
+    match trimmed.rfind('/') {
+        Some(position) if !trimmed[position + 1..].is_empty() => Ok((&trimmed[..position], &trimmed[position + 1..])),
+        None if !trimmed.is_empty() => Ok(("", trimmed)),
+        _ => bail!("Invalid path {}", path),
+    }
+}
+
+/// Validar un nombre de archivo
+fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.len() > REACTFS_NAME_MAX || name == "." || name == ".." || name.contains('\0') {
+        bail!("Invalid file name {:?}", name);
+    }
+    Ok(())
+}
+
+/// Derivar con HKDF-SHA256 un cifrador de la clave maestra
+fn derive_cipher(master_key: &[u8; KEY_SIZE], uuid: &[u8; 16], info: &[u8]) -> Result<Aes256Gcm> {
+    let mut key = Zeroizing::new([0u8; KEY_SIZE]);
+    Hkdf::<Sha256>::new(Some(uuid), master_key)
+        .expand(info, key.as_mut())
+        .map_err(|_| anyhow!("Key derivation failed"))?;
+    Aes256Gcm::new_from_slice(key.as_ref()).map_err(|_| anyhow!("Key derivation failed"))
+}
+
+/// Derivar la clave que envuelve la clave maestra a partir de la contraseña
+fn passphrase_cipher(superblock: &Superblock, passphrase: &str) -> Result<Aes256Gcm> {
+    let mut key = Zeroizing::new([0u8; KEY_SIZE]);
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), &superblock.kdf_salt, superblock.kdf_iterations, key.as_mut());
+    Aes256Gcm::new_from_slice(key.as_ref()).map_err(|_| anyhow!("Key derivation failed"))
+}
+
+/// Envolver la clave maestra con la contraseña, autenticando la cabecera del superbloque
+fn wrap_master_key(superblock: &Superblock, passphrase: &str, master_key: &[u8; KEY_SIZE]) -> Result<[u8; WRAPPED_KEY_SIZE]> {
+    wrap_key(&passphrase_cipher(superblock, passphrase)?, &superblock.header(), master_key)
+}
+
+/// Desenvolver la clave maestra; falla si la contraseña es incorrecta
+fn unwrap_master_key(superblock: &Superblock, passphrase: &str) -> Result<Zeroizing<[u8; KEY_SIZE]>> {
+    unwrap_key(&passphrase_cipher(superblock, passphrase)?, &superblock.header(), &superblock.wrapped_master_key)
+        .map_err(|_| anyhow!("Wrong passphrase or corrupted ReactFS superblock"))
+}
+
+/// Envolver una clave: nonce aleatorio, clave cifrada y etiqueta
+fn wrap_key(cipher: &Aes256Gcm, aad: &[u8], key: &[u8; KEY_SIZE]) -> Result<[u8; WRAPPED_KEY_SIZE]> {
+    let mut wrapped = [0u8; WRAPPED_KEY_SIZE];
+    random_bytes(&mut wrapped[..NONCE_SIZE])?;
+    let sealed = cipher
+        .encrypt(Nonce::from_slice(&wrapped[..NONCE_SIZE]), Payload { msg: key, aad })
+        .map_err(|_| anyhow!("Key wrapping failed"))?;
+    wrapped[NONCE_SIZE..].copy_from_slice(&sealed);
+    Ok(wrapped)
+}
+
+/// Desenvolver una clave envuelta con `wrap_key`
+fn unwrap_key(cipher: &Aes256Gcm, aad: &[u8], wrapped: &[u8; WRAPPED_KEY_SIZE]) -> Result<Zeroizing<[u8; KEY_SIZE]>> {
+    let opened = Zeroizing::new(
+        cipher
+            .decrypt(Nonce::from_slice(&wrapped[..NONCE_SIZE]), Payload { msg: &wrapped[NONCE_SIZE..], aad })
+            .map_err(|_| anyhow!("Key unwrapping failed"))?,
+    );
+    let mut key = Zeroizing::new([0u8; KEY_SIZE]);
+    key.copy_from_slice(&opened);
+    Ok(key)
+}
+
+/// Sellar `REACTFS_CHUNK_SIZE` bytes en un bloque: nonce, datos cifrados y etiqueta
+fn seal_block(cipher: &Aes256Gcm, aad: &[u8], payload: &[u8]) -> Result<Vec<u8>> {
+    let mut block = vec![0u8; REACTFS_BLOCK_SIZE];
+    random_bytes(&mut block[..NONCE_SIZE])?;
+    let (nonce, rest) = block.split_at_mut(NONCE_SIZE);
+    let (body, tag) = rest.split_at_mut(REACTFS_CHUNK_SIZE);
+    body.copy_from_slice(payload);
+    let computed = cipher
+        .encrypt_in_place_detached(Nonce::from_slice(nonce), aad, body)
+        .map_err(|_| anyhow!("Encryption failed"))?;
+    tag.copy_from_slice(&computed);
+    Ok(block)
+}
+
+/// Abrir un bloque sellado con `seal_block`, verificando su autenticidad
+fn open_block(cipher: &Aes256Gcm, aad: &[u8], block: &[u8]) -> Result<Vec<u8>> {
+    let mut body = block[NONCE_SIZE..NONCE_SIZE + REACTFS_CHUNK_SIZE].to_vec();
+    cipher
+        .decrypt_in_place_detached(
+            Nonce::from_slice(&block[..NONCE_SIZE]),
+            aad,
+            &mut body,
+            Tag::from_slice(&block[NONCE_SIZE + REACTFS_CHUNK_SIZE..]),
+        )
+        .map_err(|_| anyhow!("ReactFS block failed authentication"))?;
+    Ok(body)
+}
+
+/// Estado detrás de un `ReactFSHandle`
+struct ReactFSInstance {
+    /// Volumen montado, si lo hay
+    volume: Option<ReactFSVolume>,
+}
+
+/// Volumen montado de un handle
+fn mounted_volume<'a>(handle: ReactFSHandle) -> Option<&'a mut ReactFSVolume> {
+    if handle.is_null() {
+        return None;
+    }
+    // El handle solo se obtiene de `create_reactfs` y es válido hasta `free_reactfs`
+    let instance = unsafe { &mut *(handle as *mut ReactFSInstance) };
+    instance.volume.as_mut()
+}
+
 /// Inicializar ReactFS
+#[allow(non_snake_case)]
 pub fn ReactFS_Initialize() {
     println!("🔒 ReactFS inicializado");
 }
 
 /// Crear instancia de ReactFS
 pub fn create_reactfs() -> ReactFSHandle {
-    // Implementación stub
-    std::ptr::null_mut()
+    Box::into_raw(Box::new(ReactFSInstance { volume: None })) as ReactFSHandle
 }
 
-/// Montar ReactFS
-pub fn mount_reactfs(_handle: ReactFSHandle, _path: &str) -> bool {
-    // Implementación stub
-    true
+/// Formatear una imagen de `size` bytes como ReactFS
+pub fn format_reactfs(image_path: &str, size: u64, passphrase: &str) -> bool {
+    ReactFSVolume::format(image_path, size, passphrase, &ReactFSFormatOptions::default()).is_ok()
+}
+
+/// Montar ReactFS desde una imagen, desbloqueándola con la contraseña
+pub fn mount_reactfs(handle: ReactFSHandle, image_path: &str, passphrase: &str) -> bool {
+    if handle.is_null() {
+        return false;
+    }
+    let instance = unsafe { &mut *(handle as *mut ReactFSInstance) };
+    if instance.volume.is_some() {
+        return false;
+    }
+    match ReactFSVolume::open(image_path, passphrase) {
+        Ok(volume) => {
+            instance.volume = Some(volume);
+            true
+        }
+        Err(_) => false,
+    }
 }
 
 /// Desmontar ReactFS
-pub fn unmount_reactfs(_handle: ReactFSHandle) -> bool {
-    // Implementación stub
-    true
+pub fn unmount_reactfs(handle: ReactFSHandle) -> bool {
+    if handle.is_null() {
+        return false;
+    }
+    let instance = unsafe { &mut *(handle as *mut ReactFSInstance) };
+    match instance.volume.take() {
+        Some(mut volume) => volume.sync().is_ok(),
+        None => false,
+    }
 }
 
 /// Crear archivo encriptado
-pub fn create_encrypted_file(_handle: ReactFSHandle, _path: &str) -> bool {
-    // Implementación stub
-    true
+pub fn create_encrypted_file(handle: ReactFSHandle, path: &str) -> bool {
+    mounted_volume(handle).is_some_and(|volume| volume.create_file(path).is_ok())
+}
+
+/// Leer archivo encriptado (vacío si no existe o no se puede descifrar)
+pub fn read_encrypted_file(handle: ReactFSHandle, path: &str) -> Vec<u8> {
+    mounted_volume(handle)
+        .and_then(|volume| volume.read_file(path).ok())
+        .unwrap_or_default()
 }
 
-/// Leer archivo encriptado
-pub fn read_encrypted_file(_handle: ReactFSHandle, _path: &str) -> Vec<u8> {
-    // Implementación stub
-    vec![]
+/// Escribir archivo encriptado, reemplazando su contenido
+pub fn write_encrypted_file(handle: ReactFSHandle, path: &str, data: &[u8]) -> bool {
+    mounted_volume(handle).is_some_and(|volume| volume.write_file(path, data).is_ok())
 }
 
-/// Escribir archivo encriptado
-pub fn write_encrypted_file(_handle: ReactFSHandle, _path: &str, _data: &[u8]) -> bool {
-    // Implementación stub
-    true
+/// Liberar ReactFS (desmonta el volumen si sigue montado)
+pub fn free_reactfs(handle: ReactFSHandle) -> bool {
+    if handle.is_null() {
+        return false;
+    }
+    let mut instance = unsafe { Box::from_raw(handle as *mut ReactFSInstance) };
+    instance.volume.take().is_none_or(|mut volume| volume.sync().is_ok())
 }
 
-/// Liberar ReactFS
-pub fn free_reactfs(_handle: ReactFSHandle) -> bool {
-    // Implementación stub
-    true
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Iteraciones bajas para que las pruebas sean rápidas
+    const TEST_OPTIONS: ReactFSFormatOptions = ReactFSFormatOptions { kdf_iterations: 1000 };
+
+    /// Ruta de imagen temporal única para una prueba
+    fn image_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("reactfs-{}-{}.img", std::process::id(), name))
+    }
+
+    fn pattern(len: usize, seed: u8) -> Vec<u8> {
+        (0..len).map(|i| (i as u8).wrapping_mul(31).wrapping_add(seed)).collect()
+    }
+
+    #[test]
+    fn round_trip_after_remount() {
+        let path = image_path("round-trip");
+        let large = pattern(3 * 1024 * 1024, 7);
+        {
+            let mut volume = ReactFSVolume::format(&path, 8 * 1024 * 1024, "correct horse", &TEST_OPTIONS).unwrap();
+            volume.create_dir("/docs").unwrap();
+            volume.write_file("/docs/notes.txt", b"secret notes").unwrap();
+            volume.write_file("/large.bin", &large).unwrap();
+            volume.create_file("/empty").unwrap();
+        }
+
+        let mut volume = ReactFSVolume::open(&path, "correct horse").unwrap();
+        assert_eq!(volume.read_file("/docs/notes.txt").unwrap(), b"secret notes");
+        assert_eq!(volume.read_file("/large.bin").unwrap(), large);
+        assert!(volume.read_file("/empty").unwrap().is_empty());
+
+        let mut names: Vec<String> = volume.list_dir("/").unwrap().into_iter().map(|entry| entry.name).collect();
+        names.sort();
+        assert_eq!(names, ["docs", "empty", "large.bin"]);
+        assert_eq!(volume.metadata("/docs").unwrap().kind, ReactFSNodeKind::Directory);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn contents_and_names_are_encrypted() {
+        let path = image_path("encrypted");
+        {
+            let mut volume = ReactFSVolume::format(&path, 1024 * 1024, "pw", &TEST_OPTIONS).unwrap();
+            volume.write_file("/very-visible-filename.txt", b"very visible file contents").unwrap();
+        }
+        let image = std::fs::read(&path).unwrap();
+        assert!(!image.windows(12).any(|window| window == b"very visible"));
+        assert!(!image.windows(16).any(|window| window == b"visible-filename"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn wrong_passphrase_and_tampering_are_rejected() {
+        let path = image_path("tamper");
+        let data_block;
+        {
+            let mut volume = ReactFSVolume::format(&path, 1024 * 1024, "right", &TEST_OPTIONS).unwrap();
+            volume.write_file("/file", b"payload").unwrap();
+            data_block = volume.lookup("/file").unwrap().chunks[0];
+        }
+        assert!(ReactFSVolume::open(&path, "wrong").is_err());
+
+        // Un bit cambiado en los datos hace fallar la autenticación
+        let mut image = std::fs::read(&path).unwrap();
+        image[data_block as usize * REACTFS_BLOCK_SIZE + 100] ^= 1;
+        std::fs::write(&path, &image).unwrap();
+        let mut volume = ReactFSVolume::open(&path, "right").unwrap();
+        assert!(volume.read_file("/file").is_err());
+
+        // Cambiar los parámetros del superbloque impide desbloquear el volumen
+        image[44] ^= 1;
+        std::fs::write(&path, &image).unwrap();
+        assert!(ReactFSVolume::open(&path, "right").is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn partial_writes_truncate_and_remove() {
+        let path = image_path("partial");
+        let mut volume = ReactFSVolume::format(&path, 2 * 1024 * 1024, "pw", &TEST_OPTIONS).unwrap();
+        let free = volume.free_blocks();
+
+        volume.create_file("/f").unwrap();
+        volume.write_at("/f", 10_000, b"tail").unwrap();
+        let data = volume.read_file("/f").unwrap();
+        assert_eq!(data.len(), 10_004);
+        assert!(data[..10_000].iter().all(|&byte| byte == 0));
+        assert_eq!(&data[10_000..], b"tail");
+
+        volume.write_at("/f", 2, b"head").unwrap();
+        volume.truncate("/f", 5).unwrap();
+        volume.truncate("/f", 8).unwrap();
+        assert_eq!(volume.read_file("/f").unwrap(), b"\0\0hea\0\0\0");
+
+        let mut buffer = [0u8; 4];
+        assert_eq!(volume.read_at("/f", 2, &mut buffer).unwrap(), 4);
+        assert_eq!(&buffer, b"hea\0");
+
+        volume.create_dir("/d").unwrap();
+        volume.write_file("/d/x", &pattern(20_000, 1)).unwrap();
+        assert!(volume.remove("/d").is_err());
+        volume.remove("/d/x").unwrap();
+        volume.remove("/d").unwrap();
+        volume.remove("/f").unwrap();
+        assert_eq!(volume.free_blocks(), free);
+        assert!(volume.list_dir("/").unwrap().is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn change_passphrase() {
+        let path = image_path("passphrase");
+        {
+            let mut volume = ReactFSVolume::format(&path, 1024 * 1024, "old", &TEST_OPTIONS).unwrap();
+            volume.write_file("/f", b"kept").unwrap();
+            volume.change_passphrase("new").unwrap();
+        }
+        assert!(ReactFSVolume::open(&path, "old").is_err());
+        assert_eq!(ReactFSVolume::open(&path, "new").unwrap().read_file("/f").unwrap(), b"kept");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn handle_api() {
+        let path = image_path("handle");
+        let image = path.to_str().unwrap();
+        ReactFSVolume::format(&path, 1024 * 1024, "pw", &TEST_OPTIONS).unwrap();
+
+        let handle = create_reactfs();
+        assert!(!create_encrypted_file(handle, "/a"));
+        assert!(!mount_reactfs(handle, image, "bad"));
+        assert!(mount_reactfs(handle, image, "pw"));
+        assert!(create_encrypted_file(handle, "/a"));
+        assert!(!create_encrypted_file(handle, "/a"));
+        assert!(write_encrypted_file(handle, "/a", b"via handle"));
+        assert_eq!(read_encrypted_file(handle, "/a"), b"via handle");
+        assert!(read_encrypted_file(handle, "/missing").is_empty());
+        assert!(unmount_reactfs(handle));
+        assert!(mount_reactfs(handle, image, "pw"));
+        assert_eq!(read_encrypted_file(handle, "/a"), b"via handle");
+        assert!(free_reactfs(handle));
+        std::fs::remove_file(&path).unwrap();
+    }
+}